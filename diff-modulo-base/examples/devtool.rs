@@ -1,31 +1,10 @@
 // SPDX-License-Identifier: MIT
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Parser, Subcommand};
 
 use diff_modulo_base::*;
 use utils::*;
 
-#[derive(ValueEnum, Debug, Clone, Copy)]
-enum DiffAlgorithm {
-    GraphSearch,
-    SweepLine,
-    SweepLineExact,
-}
-impl Default for DiffAlgorithm {
-    fn default() -> Self {
-        Self::SweepLine
-    }
-}
-impl From<DiffAlgorithm> for diff::DiffAlgorithm {
-    fn from(algorithm: DiffAlgorithm) -> Self {
-        match algorithm {
-            DiffAlgorithm::GraphSearch => Self::GraphSearch,
-            DiffAlgorithm::SweepLine => Self::SweepLine,
-            DiffAlgorithm::SweepLineExact => Self::SweepLineExact,
-        }
-    }
-}
-
 #[derive(Subcommand, Debug)]
 enum Command {
     Compose {
@@ -37,7 +16,23 @@ enum Command {
         new: std::path::PathBuf,
 
         #[clap(value_enum, short, long, default_value_t = Default::default())]
-        algorithm: DiffAlgorithm,
+        algorithm: diff::DiffAlgorithm,
+
+        /// Slide added and removed blocks to fit blank lines and indentation.
+        #[clap(long)]
+        indent_heuristic: bool,
+    },
+    /// Diff two files repeatedly and print the time per iteration, e.g. to
+    /// measure changes to the diff algorithms.
+    Bench {
+        old: std::path::PathBuf,
+        new: std::path::PathBuf,
+
+        #[clap(value_enum, short, long, default_value_t = Default::default())]
+        algorithm: diff::DiffAlgorithm,
+
+        #[clap(short, long, default_value_t = 20)]
+        iterations: u32,
     },
     GitDiffModuloBase {
         #[clap(flatten)]
@@ -71,6 +66,7 @@ fn do_main() -> Result<()> {
             old,
             new,
             algorithm,
+            indent_heuristic,
         } => {
             let old_body = buffer.insert(&utils::read_bytes(&old)?)?;
             let new_body = buffer.insert(&utils::read_bytes(&new)?)?;
@@ -80,6 +76,7 @@ fn do_main() -> Result<()> {
 
             let options = diff::DiffOptions {
                 strip_path_components: 0,
+                indent_heuristic,
                 ..Default::default()
             };
             let file = try_forward(
@@ -91,7 +88,7 @@ fn do_main() -> Result<()> {
                         old_body,
                         new_body,
                         &options,
-                        algorithm.into(),
+                        algorithm,
                     )
                 },
                 || "diffing",
@@ -101,6 +98,22 @@ fn do_main() -> Result<()> {
             diff.add_file(file);
             print!("{}", diff.display_lossy(&buffer));
         }
+        Command::Bench { old, new, algorithm, iterations } => {
+            let old_body = buffer.insert(&utils::read_bytes(&old)?)?;
+            let new_body = buffer.insert(&utils::read_bytes(&new)?)?;
+            let path = buffer.insert(b"bench")?;
+
+            let options = diff::DiffOptions {
+                strip_path_components: 0,
+                ..Default::default()
+            };
+            let start = std::time::Instant::now();
+            for _ in 0..iterations {
+                diff::diff_file(&buffer, path, path, old_body, new_body, &options, algorithm)?;
+            }
+            let elapsed = start.elapsed();
+            println!("{:?} per iteration", elapsed / iterations.max(1));
+        }
         Command::GitDiffModuloBase { gdmb, mock_data } => {
             let mut out = termcolor::StandardStream::stdout(termcolor::ColorChoice::Never);
 
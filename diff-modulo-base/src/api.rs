@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: MIT
+
+//! High-level API for tools that want to present diffs with diff-modulo-base
+//! semantics in their own way, e.g. IDE or LSP integrations.
+//!
+//! Results are plain owned data and no knowledge of [`diff::Buffer`] is
+//! required.
+
+use crate::diff::{self, render};
+use crate::utils::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub kind: LineKind,
+
+    /// Whether the line is a change that should be reviewed. Changes that
+    /// merely follow from a change of the base (shown with a `<` or `>`
+    /// prefix in text output) are not important.
+    pub important: bool,
+
+    /// 1-based line numbers in the old and new file, if the line exists there.
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+
+    /// Contents of the line without the line terminator.
+    pub text: String,
+
+    /// Whether the line is the last line of its file and has no terminating
+    /// newline.
+    pub missing_newline: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Whether this is a hunk of the base diff, i.e. of the change between
+    /// the old and the new base. Line numbers of such hunks refer to the base
+    /// versions of the file and not to the old and new file of the target.
+    pub baseline: bool,
+
+    pub old_begin: u32, // 1-based line number
+    pub old_count: u32,
+    pub new_begin: u32, // 1-based line number
+    pub new_count: u32,
+
+    pub lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHunks {
+    /// Names of the file, or None if it was added or removed.
+    pub old_name: Option<String>,
+    pub new_name: Option<String>,
+
+    pub hunks: Vec<Hunk>,
+}
+impl FileHunks {
+    /// Whether any line of the file is an important change.
+    pub fn is_important(&self) -> bool {
+        self.hunks.iter().flat_map(|hunk| &hunk.lines).any(|line| line.important)
+    }
+
+    /// Map a 1-based line number of the old file to the new file.
+    ///
+    /// Returns None if the line was removed.
+    pub fn old_to_new(&self, line: u32) -> Option<u32> {
+        self.map_line(line, false)
+    }
+
+    /// Map a 1-based line number of the new file to the old file.
+    ///
+    /// Returns None if the line was added.
+    pub fn new_to_old(&self, line: u32) -> Option<u32> {
+        self.map_line(line, true)
+    }
+
+    fn map_line(&self, line: u32, from_new: bool) -> Option<u32> {
+        let from = |old: u32, new: u32| if from_new { new } else { old };
+        let to = |old: u32, new: u32| if from_new { old } else { new };
+
+        // Lines outside of hunks are unchanged and only shifted by the hunks
+        // before them.
+        let mut delta: i64 = 0;
+        for hunk in self.hunks.iter().filter(|hunk| !hunk.baseline) {
+            let begin = from(hunk.old_begin, hunk.new_begin);
+            if line < begin {
+                break;
+            }
+            let end = begin + from(hunk.old_count, hunk.new_count);
+            if line < end {
+                return hunk.lines.iter().find_map(|hunk_line| {
+                    let (old, new) = (hunk_line.old_line, hunk_line.new_line);
+                    let (from_line, to_line) = if from_new { (new, old) } else { (old, new) };
+                    to_line.filter(|_| from_line == Some(line))
+                });
+            }
+            let to_end = to(hunk.old_begin, hunk.new_begin) + to(hunk.old_count, hunk.new_count);
+            delta = to_end as i64 - end as i64;
+        }
+        Some((line as i64 + delta) as u32)
+    }
+}
+
+fn name_to_string(name: &diff::FileName) -> Option<String> {
+    match name {
+        diff::FileName::Missing => None,
+        diff::FileName::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+    }
+}
+
+/// Collect [`render::Chunk`]s into [`FileHunks`].
+#[derive(Debug, Default)]
+pub struct HunkCollector {
+    pub files: Vec<FileHunks>,
+
+    /// Whether the last chunk was a file header. The file headers of the base
+    /// and the target diff of the same file are written back-to-back.
+    after_header: bool,
+
+    /// Next 1-based line numbers in the current hunk.
+    next_old: u32,
+    next_new: u32,
+}
+impl HunkCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+impl render::ChunkWriter for HunkCollector {
+    fn push_chunk(&mut self, chunk: render::Chunk) {
+        let baseline = chunk.context == render::Context::Baseline;
+        let after_header = std::mem::replace(&mut self.after_header, false);
+        match chunk.contents {
+            render::ChunkContents::FileHeader { old_name, new_name, .. } => {
+                self.after_header = true;
+                let old_name = name_to_string(&old_name);
+                let new_name = name_to_string(&new_name);
+                if after_header {
+                    // Prefer the names of the target diff.
+                    if !baseline {
+                        let file = self.files.last_mut().unwrap();
+                        file.old_name = old_name;
+                        file.new_name = new_name;
+                    }
+                    return;
+                }
+                self.files.push(FileHunks {
+                    old_name,
+                    new_name,
+                    hunks: Vec::new(),
+                });
+            }
+            render::ChunkContents::HunkHeader {
+                old_begin,
+                old_count,
+                new_begin,
+                new_count,
+            } => {
+                self.next_old = old_begin;
+                self.next_new = new_begin;
+                if self.files.is_empty() {
+                    self.files.push(FileHunks {
+                        old_name: None,
+                        new_name: None,
+                        hunks: Vec::new(),
+                    });
+                }
+                self.files.last_mut().unwrap().hunks.push(Hunk {
+                    baseline,
+                    old_begin,
+                    old_count,
+                    new_begin,
+                    new_count,
+                    lines: Vec::new(),
+                });
+            }
+            render::ChunkContents::Line { line, .. } => {
+                let Some(hunk) = self.files.last_mut().and_then(|file| file.hunks.last_mut())
+                else {
+                    return;
+                };
+                let kind = match line.status {
+                    diff::HunkLineStatus::Unchanged => LineKind::Unchanged,
+                    diff::HunkLineStatus::Old(_) => LineKind::Removed,
+                    diff::HunkLineStatus::New(_) => LineKind::Added,
+                };
+                let old_line = line.status.covers_old().then_some(self.next_old);
+                let new_line = line.status.covers_new().then_some(self.next_new);
+                self.next_old += old_line.is_some() as u32;
+                self.next_new += new_line.is_some() as u32;
+
+                let contents = line.contents.strip_suffix(b"\n");
+                let missing_newline = contents.is_none();
+                let contents = contents.unwrap_or(&line.contents);
+                let contents = contents.strip_suffix(b"\r").unwrap_or(contents);
+                hunk.lines.push(Line {
+                    kind,
+                    important: line.status.important(),
+                    old_line,
+                    new_line,
+                    text: String::from_utf8_lossy(contents).into_owned(),
+                    missing_newline,
+                });
+            }
+        }
+    }
+}
+
+/// Diff two versions of a file.
+///
+/// The names are used to detect added and removed files: pass None for the
+/// side that doesn't exist, whose text must then be empty.
+pub fn diff_texts(
+    old_name: Option<&str>,
+    old: &str,
+    new_name: Option<&str>,
+    new: &str,
+    options: &diff::DiffOptions,
+) -> Result<FileHunks> {
+    let mut buffer = diff::Buffer::new();
+    let path = |name: Option<&str>, prefix: &str| match name {
+        Some(name) => format!("{prefix}{name}"),
+        None => "/dev/null".into(),
+    };
+    let old_path = buffer.insert(path(old_name, "a/").as_bytes())?;
+    let new_path = buffer.insert(path(new_name, "b/").as_bytes())?;
+    let old_body = buffer.insert(old.as_bytes())?;
+    let new_body = buffer.insert(new.as_bytes())?;
+
+    let options = diff::DiffOptions {
+        strip_path_components: 1,
+        ..options.clone()
+    };
+    let file = diff::diff_file(
+        &buffer,
+        old_path,
+        new_path,
+        old_body,
+        new_body,
+        &options,
+        diff::DiffAlgorithm::default(),
+    )?;
+
+    let mut collector = HunkCollector::new();
+    for hunk in diff::hunkify(&file, Some(options.num_context_lines), &buffer) {
+        hunk.render(true, &mut collector);
+    }
+    Ok(FileHunks {
+        old_name: old_name.map(str::to_string),
+        new_name: new_name.map(str::to_string),
+        hunks: collector.files.pop().map(|file| file.hunks).unwrap_or_default(),
+    })
+}
+
+/// Compute the target diff modulo the base diffs, see [`diff::diff_modulo_base`].
+///
+/// All diffs are given as Git-style diff text. Files without any changes
+/// relative to the base are omitted.
+pub fn diff_modulo_base(
+    base_old: &str,
+    base_new: &str,
+    target: &str,
+    anchors: &[String],
+) -> Result<Vec<FileHunks>> {
+    let mut buffer = diff::Buffer::new();
+    let mut parse = |text: &str, what: &str| -> Result<diff::Diff> {
+        try_forward(
+            || {
+                let range = buffer.insert(text.as_bytes())?;
+                diff::Diff::parse(&buffer, range)
+            },
+            || format!("failed to parse {what} diff"),
+        )
+    };
+    let base_old = parse(base_old, "old base")?;
+    let base_new = parse(base_new, "new base")?;
+    let target = parse(target, "target")?;
+
+    let mut collector = HunkCollector::new();
+    diff::diff_modulo_base_with(
+        &buffer,
+        target,
+        &base_old,
+        &base_new,
+        &diff::ReduceOptions { anchors, ..Default::default() },
+        &mut collector,
+    )?;
+    Ok(collector.files)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diff_texts() -> Result<()> {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\nh2\ni\nj";
+        let options = diff::DiffOptions {
+            num_context_lines: 1,
+            ..Default::default()
+        };
+        let file = diff_texts(Some("file"), old, Some("file"), new, &options)?;
+        assert_eq!(file.hunks.len(), 2);
+        assert!(file.is_important());
+
+        let first = &file.hunks[0];
+        assert_eq!((first.old_begin, first.old_count), (1, 3));
+        let kinds: Vec<_> =
+            first.lines.iter().map(|line| (line.kind, line.text.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (LineKind::Unchanged, "a"),
+                (LineKind::Removed, "b"),
+                (LineKind::Added, "B"),
+                (LineKind::Unchanged, "c"),
+            ]
+        );
+        assert_eq!(first.lines[2].new_line, Some(2));
+        assert_eq!(first.lines[2].old_line, None);
+        assert!(file.hunks[1].lines.last().unwrap().missing_newline);
+
+        assert_eq!(file.old_to_new(1), Some(1));
+        assert_eq!(file.old_to_new(2), None);
+        assert_eq!(file.old_to_new(5), Some(5));
+        assert_eq!(file.old_to_new(9), Some(10));
+        assert_eq!(file.new_to_old(9), None);
+        assert_eq!(file.new_to_old(10), Some(9));
+
+        // The last line differs by its missing newline.
+        assert_eq!(file.old_to_new(10), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_modulo_base() -> Result<()> {
+        let files = diff_modulo_base(
+            include_str!("../tests/dmb_test/basic1/old.diff"),
+            include_str!("../tests/dmb_test/basic1/new.diff"),
+            include_str!("../tests/dmb_test/basic1/target.diff"),
+            &[],
+        )?;
+        let names: Vec<_> = files.iter().map(|file| file.new_name.as_deref()).collect();
+        assert_eq!(names, vec![Some("foo"), Some("bar")]);
+
+        let bar = &files[1];
+        assert!(bar.hunks.iter().all(|hunk| !hunk.baseline));
+        let removed: Vec<_> = bar.hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.kind == LineKind::Removed)
+            .map(|line| (line.old_line, line.text.as_str(), line.important))
+            .collect();
+        assert_eq!(removed, vec![(Some(74), "g", true)]);
+        assert_eq!(bar.old_to_new(74), None);
+        assert_eq!(bar.old_to_new(75), Some(74));
+        assert_eq!(bar.old_to_new(100), Some(99));
+        Ok(())
+    }
+}
@@ -20,12 +20,13 @@ pub struct Options {
 
 fn do_main() -> Result<()> {
     let args = Options::parse();
+    let palette = args.cli.palette;
     let mut cli = cli::Cli::new(args.cli);
     let out = cli.stream();
 
     let repo = git_core::Repository::new(args.path);
 
-    let mut writer = diff_color::Writer::new();
+    let mut writer = diff_color::Writer::with_palette(palette);
     tool::git_diff_modulo_base(
         &args.gdmb,
         &repo,
@@ -6,6 +6,8 @@ use std::process::{Command, Stdio};
 use clap::Args;
 use termcolor::{ColorChoice, StandardStream, WriteColor};
 
+use crate::diff_color::Palette;
+
 #[derive(Debug, Clone, Default, Args)]
 pub struct Options {
     /// Whether the output should be run through a pager
@@ -15,6 +17,10 @@ pub struct Options {
     /// Whether the output should be colored
     #[clap(long)]
     pub color: Option<bool>,
+
+    /// Color scheme of added and removed lines
+    #[clap(long, value_enum, default_value_t = Default::default())]
+    pub palette: Palette,
 }
 
 pub struct Cli {
@@ -15,15 +15,21 @@ mod buffer;
 mod file;
 mod file_match;
 mod hunks;
+mod indent_heuristic;
 mod reduce_changed;
 pub mod render;
 
-pub use buffer::{Buffer, BufferRef};
+pub use buffer::{
+    hash_bytes, peak_memory_usage, Buffer, BufferRef, LineHashMap, MemoryUsage, U64Hasher,
+};
 use file::parse_diff_path;
 pub use file::{File, FileBuilder, FileName};
 pub use file_match::{FileMatch, MatchStatus, MatchStatusMarker};
 pub use hunks::{hunkify, Hunk, HunkLine, HunkLineStatus};
-pub use reduce_changed::{reduce_changed_diff, reduce_changed_file, DiffAlgorithm};
+pub use reduce_changed::{
+    reduce_changed_diff, reduce_changed_diff_with, reduce_changed_file, reduce_changed_file_with,
+    DiffAlgorithm, ReduceOptions,
+};
 
 use render::ChunkWriterExt;
 
@@ -31,12 +37,17 @@ use render::ChunkWriterExt;
 pub struct DiffOptions {
     pub strip_path_components: usize,
     pub num_context_lines: usize,
+
+    /// Whether to slide groups of added or removed lines that are computed by
+    /// a [`DiffAlgorithm`] to fit blank lines and indentation, like Git does.
+    pub indent_heuristic: bool,
 }
 impl Default for DiffOptions {
     fn default() -> Self {
         Self {
             strip_path_components: 1,
             num_context_lines: 3,
+            indent_heuristic: false,
         }
     }
 }
@@ -476,6 +487,7 @@ pub fn compose(first: &Diff, second: &Diff, buffer: &Buffer) -> Result<Diff> {
                 first.options.num_context_lines,
                 second.options.num_context_lines,
             ),
+            indent_heuristic: first.options.indent_heuristic && second.options.indent_heuristic,
         },
     };
 
@@ -821,17 +833,31 @@ fn reduce_modulo_base(
     Ok(target)
 }
 
+/// Render the target diff modulo the base diffs.
 pub fn diff_modulo_base(
     buffer: &Buffer,
     target: Diff,
     base_old: &Diff,
     base_new: &Diff,
     writer: &mut dyn render::ChunkWriter,
+) -> Result<()> {
+    diff_modulo_base_with(buffer, target, base_old, base_new, &ReduceOptions::default(), writer)
+}
+
+/// Like [`diff_modulo_base`], but with the given options for reducing changed
+/// blocks, see [`reduce_changed_file_with`].
+pub fn diff_modulo_base_with(
+    buffer: &Buffer,
+    target: Diff,
+    base_old: &Diff,
+    base_new: &Diff,
+    options: &ReduceOptions,
+    writer: &mut dyn render::ChunkWriter,
 ) -> Result<()> {
     let base = compose(base_old, &target, buffer)?;
     let base = compose(&base, &reverse(base_new), buffer)?;
     let base = reduce_modulo_base(base, true, base_old, base_new, buffer)?;
-    let base = reduce_changed_diff(buffer, base, DiffAlgorithm::default());
+    let base = reduce_changed_diff_with(buffer, base, options);
 
     let target = reduce_modulo_base(target, false, base_old, base_new, buffer)?;
 
@@ -982,5 +1008,9 @@ pub fn diff_file(
         ],
     };
 
-    Ok(reduce_changed_file(buffer, file, algorithm).0)
+    let (mut file, _) = reduce_changed_file(buffer, file, algorithm);
+    if options.indent_heuristic {
+        indent_heuristic::apply(&mut file, buffer);
+    }
+    Ok(file)
 }
@@ -4,6 +4,12 @@
 ///! diff contents. All other data structures simply hold [`BufferRef`]s into
 ///! a common buffer.
 
+use std::{
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    sync::Mutex,
+};
+
 use vctools_utils::prelude::*;
 
 /// A reference to a span of bytes in a [`Buffer`].
@@ -49,15 +55,88 @@ impl BufferRef {
     }
 }
 
+/// Memory used by a [`Buffer`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes of contents that are stored.
+    pub bytes: usize,
+
+    /// Bytes that are allocated, including the table of interned contents.
+    pub allocated: usize,
+
+    /// Number of distinct interned contents.
+    pub interned: usize,
+
+    /// Bytes that weren't stored again because they were interned already.
+    pub deduplicated: usize,
+}
+
+/// Usage of the largest buffer that was dropped so far.
+static PEAK_USAGE: Mutex<MemoryUsage> = Mutex::new(MemoryUsage {
+    bytes: 0,
+    allocated: 0,
+    interned: 0,
+    deduplicated: 0,
+});
+
+/// Memory usage of the largest buffer that was dropped so far in this process.
+///
+/// Buffers only live while a diff is computed, so this is a good indicator of
+/// the memory needed for the largest diff.
+pub fn peak_memory_usage() -> MemoryUsage {
+    *PEAK_USAGE.lock().unwrap()
+}
+
+const HASH_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Keys of [`hash_bytes`]. They are fixed so that diffs don't depend on the run.
+static HASH_STATE: ahash::RandomState = ahash::RandomState::with_seeds(HASH_MULTIPLIER, 1, 2, 3);
+
+/// Non-cryptographic hash of bytes, e.g. of a line, using aHash.
+pub fn hash_bytes(data: &[u8]) -> u64 {
+    HASH_STATE.hash_one(data)
+}
+
+/// Hasher for keys that are a single u64 which is already a hash, or cheap to
+/// mix, like the hashes of [`hash_bytes`].
+#[derive(Debug, Default)]
+pub struct U64Hasher(u64);
+impl Hasher for U64Hasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = hash_bytes(bytes);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value.wrapping_mul(HASH_MULTIPLIER);
+    }
+}
+
+/// Hash map with keys that are hashes of lines.
+pub type LineHashMap<V> = HashMap<u64, V, BuildHasherDefault<U64Hasher>>;
+
 /// Owner of diff contents.
 #[derive(Debug)]
 pub struct Buffer {
     buf: Vec<u8>,
+
+    /// Hash-consed table of interned contents, by the hash of their bytes.
+    interned: LineHashMap<Vec<BufferRef>>,
+
+    /// Total size of contents that were found in `interned`.
+    deduplicated: usize,
 }
 
 impl Buffer {
     pub fn new() -> Self {
-        Buffer { buf: Vec::new() }
+        Buffer {
+            buf: Vec::new(),
+            interned: LineHashMap::default(),
+            deduplicated: 0,
+        }
     }
 
     pub fn insert(&mut self, data: &[u8]) -> Result<BufferRef> {
@@ -73,6 +152,42 @@ impl Buffer {
         })
     }
 
+    /// Insert data unless identical data was interned before, in which case a
+    /// reference to the existing copy is returned.
+    ///
+    /// This is meant for lines and texts that are likely to be repeated, such as
+    /// file names, commit messages, and the diffs of a commit before and after
+    /// a rebase.
+    pub fn intern(&mut self, data: &[u8]) -> Result<BufferRef> {
+        let hash = hash_bytes(data);
+        if let Some(candidates) = self.interned.get(&hash) {
+            if let Some(existing) = candidates.iter().find(|r| &self[**r] == data) {
+                self.deduplicated += data.len();
+                return Ok(*existing);
+            }
+        }
+
+        let range = self.insert(data)?;
+        self.interned.entry(hash).or_default().push(range);
+        Ok(range)
+    }
+
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let table = self.interned.capacity()
+            * (std::mem::size_of::<u64>() + std::mem::size_of::<Vec<BufferRef>>())
+            + self
+                .interned
+                .values()
+                .map(|refs| refs.capacity() * std::mem::size_of::<BufferRef>())
+                .sum::<usize>();
+        MemoryUsage {
+            bytes: self.buf.len(),
+            allocated: self.buf.capacity() + table,
+            interned: self.interned.values().map(Vec::len).sum(),
+            deduplicated: self.deduplicated,
+        }
+    }
+
     /// Iterate over the lines of the buffer as [`BufferRef`]s spanning the line
     /// contents but not the new line character.
     pub fn lines(&self, range: BufferRef) -> LineIterator<'_> {
@@ -89,6 +204,16 @@ impl Buffer {
     }
 }
 
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        let usage = self.memory_usage();
+        let mut peak = PEAK_USAGE.lock().unwrap();
+        if usage.allocated > peak.allocated {
+            *peak = usage;
+        }
+    }
+}
+
 pub struct LineIterator<'a> {
     buffer: &'a Buffer,
     range: BufferRef,
@@ -136,3 +261,46 @@ impl std::ops::Index<u32> for Buffer {
         &self.buf[index as usize]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_intern() -> Result<()> {
+        let mut buffer = Buffer::new();
+        let first = buffer.intern(b"line\n")?;
+        let other = buffer.intern(b"other line\n")?;
+        let second = buffer.intern(b"line\n")?;
+        assert_eq!((first.begin, first.end), (second.begin, second.end));
+        assert_ne!(first.begin, other.begin);
+
+        // Plain insertion always stores a new copy.
+        let third = buffer.insert(b"line\n")?;
+        assert_eq!(&buffer[first], &buffer[third]);
+        assert_ne!(first.begin, third.begin);
+
+        let usage = buffer.memory_usage();
+        assert_eq!(usage.bytes, 21);
+        assert_eq!(usage.interned, 2);
+        assert_eq!(usage.deduplicated, 5);
+        assert!(usage.allocated >= usage.bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_hash() -> Result<()> {
+        let mut buffer = Buffer::new();
+        let text = buffer.insert(b"fn main() {\nfn main() {\nfn main() {}\n\n\n")?;
+        let lines: Vec<_> = buffer.lines(text).collect();
+        let hashes: Vec<_> = lines.iter().map(|&line| hash_bytes(&buffer[line])).collect();
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        assert_eq!(hashes[3], hashes[4]);
+        assert_eq!(hashes[3], hash_bytes(b""));
+
+        // Trailing zero bytes must not collide with shorter data.
+        assert_ne!(hash_bytes(b"abc"), hash_bytes(b"abc\0"));
+        Ok(())
+    }
+}
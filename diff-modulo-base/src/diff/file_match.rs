@@ -149,3 +149,44 @@ impl FileMatch {
         }
     }
 }
+
+#[cfg(test)]
+impl FileMatch {
+    /// Match between two texts in which all lines are in one changed block, as
+    /// it is before changed blocks are reduced. Used by tests.
+    pub(crate) fn changed_for_test(
+        buffer: &mut Buffer,
+        old: &[u8],
+        new: &[u8],
+    ) -> vctools_utils::prelude::Result<Self> {
+        let old_body = buffer.insert(old)?;
+        let new_body = buffer.insert(new)?;
+        let name = buffer.insert(b"file")?;
+        let build = |body| -> vctools_utils::prelude::Result<File> {
+            let mut builder = super::FileBuilder::new();
+            builder.push_text(0, body, buffer)?;
+            Ok(builder.build(name, true, buffer))
+        };
+        let old_file = build(old_body)?;
+        let new_file = build(new_body)?;
+
+        Ok(FileMatch {
+            old_path: b"a/file".to_vec(),
+            new_path: b"b/file".to_vec(),
+            status_markers: vec![
+                MatchStatusMarker {
+                    old_line: 0,
+                    new_line: 0,
+                    status: MatchStatus::Changed { unimportant: false },
+                },
+                MatchStatusMarker {
+                    old_line: old_file.num_lines().unwrap(),
+                    new_line: new_file.num_lines().unwrap(),
+                    status: MatchStatus::Unchanged,
+                },
+            ],
+            old: Some(old_file),
+            new: Some(new_file),
+        })
+    }
+}
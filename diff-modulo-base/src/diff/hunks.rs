@@ -97,10 +97,11 @@ impl Hunk {
             });
         }
 
-        for line in &self.lines {
+        let changes = render::intra_line_changes(&self.lines);
+        for (line, changed) in self.lines.iter().zip(changes) {
             writer.push_chunk(render::Chunk {
                 context: render::Context::Unknown,
-                contents: render::ChunkContents::Line { line: line.clone() },
+                contents: render::ChunkContents::Line { line: line.clone(), changed },
             });
         }
     }
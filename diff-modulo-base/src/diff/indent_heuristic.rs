@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: MIT
+
+//! Post-processing that slides groups of added or removed lines to where they
+//! best fit the blank lines and indentation of the file, like the indent
+//! heuristic of Git (see `xdiff/xdiffi.c`).
+//!
+//! In repetitive code, a group of added lines often starts with the same line
+//! that follows it, or ends with the same line that precedes it. Such a group
+//! can be shifted without changing the meaning of the diff, but usually only
+//! one of the positions matches how a human would describe the change.
+
+use std::ops::RangeInclusive;
+
+use super::{Buffer, File, FileMatch, MatchStatusMarker};
+
+/// Indentation beyond this is treated as equal.
+const MAX_INDENT: i32 = 200;
+
+/// Blank lines beyond this are not counted.
+const MAX_BLANKS: i32 = 20;
+
+/// Groups are only considered at this many positions at most.
+const MAX_SLIDING: u32 = 100;
+
+// Weights of the scoring, as tuned by Git on a corpus of human-annotated diffs.
+const START_OF_FILE_PENALTY: i32 = 1;
+const END_OF_FILE_PENALTY: i32 = 21;
+const TOTAL_BLANK_WEIGHT: i32 = -30;
+const POST_BLANK_WEIGHT: i32 = 6;
+const RELATIVE_INDENT_PENALTY: i32 = -4;
+const RELATIVE_INDENT_WITH_BLANK_PENALTY: i32 = 10;
+const RELATIVE_OUTDENT_PENALTY: i32 = 24;
+const RELATIVE_OUTDENT_WITH_BLANK_PENALTY: i32 = 17;
+const RELATIVE_DEDENT_PENALTY: i32 = 23;
+const RELATIVE_DEDENT_WITH_BLANK_PENALTY: i32 = 17;
+const INDENT_WEIGHT: i32 = 60;
+
+/// Indentation of a line with tabs counting up to the next multiple of 8, or
+/// -1 if the line is blank.
+fn get_indent(line: &[u8]) -> i32 {
+    let mut indent = 0;
+    for &ch in line {
+        match ch {
+            b' ' => indent += 1,
+            b'\t' => indent += 8 - indent % 8,
+            b'\n' | b'\r' | b'\x0b' | b'\x0c' => {}
+            _ => return indent,
+        }
+        if indent >= MAX_INDENT {
+            return MAX_INDENT;
+        }
+    }
+    -1
+}
+
+/// Properties of the surroundings of a split between two lines.
+#[derive(Debug)]
+struct SplitMeasurement {
+    /// Whether the split is at the end of the (known part of the) file.
+    end_of_file: bool,
+
+    /// Indentation of the line after the split, or -1 if it is blank.
+    indent: i32,
+
+    /// Number of blank lines before the split, and the indentation of the
+    /// first non-blank line before them (-1 if there is none).
+    pre_blank: i32,
+    pre_indent: i32,
+
+    /// Number of blank lines after the line after the split, and the
+    /// indentation of the first non-blank line after them (-1 if there is none).
+    post_blank: i32,
+    post_indent: i32,
+}
+
+/// Measure the split before the given line.
+fn measure_split(file: &File, buffer: &Buffer, split: u32) -> SplitMeasurement {
+    let (end_of_file, indent) = match file.line(split, buffer) {
+        Some(line) => (false, get_indent(line)),
+        None => (true, -1),
+    };
+
+    let mut pre_blank = 0;
+    let mut pre_indent = -1;
+    for line in (0..split).rev().map_while(|line| file.line(line, buffer)) {
+        let indent = get_indent(line);
+        if indent != -1 {
+            pre_indent = indent;
+            break;
+        }
+        pre_blank += 1;
+        if pre_blank == MAX_BLANKS {
+            pre_indent = 0;
+            break;
+        }
+    }
+
+    let mut post_blank = 0;
+    let mut post_indent = -1;
+    for line in (split + 1..).map_while(|line| file.line(line, buffer)) {
+        let indent = get_indent(line);
+        if indent != -1 {
+            post_indent = indent;
+            break;
+        }
+        post_blank += 1;
+        if post_blank == MAX_BLANKS {
+            post_indent = 0;
+            break;
+        }
+    }
+
+    SplitMeasurement {
+        end_of_file,
+        indent,
+        pre_blank,
+        pre_indent,
+        post_blank,
+        post_indent,
+    }
+}
+
+/// Score of a position of a group; lower is better.
+#[derive(Debug, Clone, Copy, Default)]
+struct SplitScore {
+    effective_indent: i32,
+    penalty: i32,
+}
+impl SplitScore {
+    fn add_split(&mut self, m: &SplitMeasurement) {
+        if m.pre_indent == -1 && m.pre_blank == 0 {
+            self.penalty += START_OF_FILE_PENALTY;
+        }
+        if m.end_of_file {
+            self.penalty += END_OF_FILE_PENALTY;
+        }
+
+        let post_blank = if m.indent == -1 { 1 + m.post_blank } else { 0 };
+        let total_blank = m.pre_blank + post_blank;
+        self.penalty += TOTAL_BLANK_WEIGHT * total_blank;
+        self.penalty += POST_BLANK_WEIGHT * post_blank;
+
+        let indent = if m.indent != -1 { m.indent } else { m.post_indent };
+        let any_blanks = total_blank != 0;
+        self.effective_indent += indent;
+
+        if indent == -1 || m.pre_indent == -1 || indent == m.pre_indent {
+            // No adjustment.
+        } else if indent > m.pre_indent {
+            self.penalty += if any_blanks {
+                RELATIVE_INDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_INDENT_PENALTY
+            };
+        } else if m.post_indent != -1 && m.post_indent > indent {
+            self.penalty += if any_blanks {
+                RELATIVE_OUTDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_OUTDENT_PENALTY
+            };
+        } else {
+            self.penalty += if any_blanks {
+                RELATIVE_DEDENT_WITH_BLANK_PENALTY
+            } else {
+                RELATIVE_DEDENT_PENALTY
+            };
+        }
+    }
+
+    /// Compare two scores; negative if `self` is better.
+    fn cmp(&self, other: &SplitScore) -> i32 {
+        let cmp_indents = (self.effective_indent - other.effective_indent).signum();
+        INDENT_WEIGHT * cmp_indents + (self.penalty - other.penalty)
+    }
+}
+
+/// Slide groups of only added or only removed lines within the unchanged lines
+/// around them.
+///
+/// For each group that can be slid, `choose_end` is called with the lines of
+/// the side that the group is on, the range of possible (exclusive) end lines
+/// of the group, and the number of lines in the group. It returns the chosen
+/// end line.
+pub(crate) fn slide_groups(
+    file: &mut FileMatch,
+    buffer: &Buffer,
+    mut choose_end: impl FnMut(&File, RangeInclusive<u32>, u32) -> u32,
+) {
+    let (Some(old), Some(new)) = (&file.old, &file.new) else { return };
+    let markers = &mut file.status_markers;
+
+    for idx in 1..markers.len().saturating_sub(1) {
+        let (prev, sm, next) = (markers[idx - 1], markers[idx], markers[idx + 1]);
+        if !sm.is_changed() || prev.is_changed() || next.is_changed() {
+            continue;
+        }
+
+        let on_new = next.old_line == sm.old_line;
+        if !on_new && next.new_line != sm.new_line {
+            continue;
+        }
+        let side =
+            |marker: &MatchStatusMarker| if on_new { marker.new_line } else { marker.old_line };
+        let lines = if on_new { new } else { old };
+        let same = |a: u32, b: u32| {
+            lines.line(a, buffer).is_some_and(|line| Some(line) == lines.line(b, buffer))
+        };
+
+        let group_start = side(&sm);
+        let count = side(&next) - group_start;
+        if count == 0 {
+            continue;
+        }
+        let min_start = side(&prev);
+        let max_end = markers.get(idx + 2).map_or(u32::MAX, side);
+
+        let mut start = group_start;
+        while start > min_start && same(start - 1, start + count - 1) {
+            start -= 1;
+        }
+        let earliest_end = start + count;
+        while start + count < max_end && same(start, start + count) {
+            start += 1;
+        }
+        let latest_end = start + count;
+        if earliest_end == latest_end {
+            continue;
+        }
+
+        // Both sides shift by the same amount, since they are unchanged
+        // around the group.
+        let end = choose_end(lines, earliest_end..=latest_end, count);
+        let delta = end as i64 - (group_start + count) as i64;
+        for marker in &mut markers[idx..idx + 2] {
+            marker.old_line = (marker.old_line as i64 + delta) as u32;
+            marker.new_line = (marker.new_line as i64 + delta) as u32;
+        }
+    }
+
+    file.simplify();
+}
+
+/// Slide groups of only added or only removed lines to the best position
+/// according to the heuristic.
+pub(crate) fn apply(file: &mut FileMatch, buffer: &Buffer) {
+    slide_groups(file, buffer, |lines, ends, count| {
+        let mut best: Option<(u32, SplitScore)> = None;
+        let first_end = (*ends.start())
+            .max(ends.end().saturating_sub(count + 1))
+            .max(ends.end().saturating_sub(MAX_SLIDING));
+        for end in first_end..=*ends.end() {
+            let mut score = SplitScore::default();
+            score.add_split(&measure_split(lines, buffer, end));
+            score.add_split(&measure_split(lines, buffer, end - count));
+            if best.is_none_or(|(_, best_score)| score.cmp(&best_score) <= 0) {
+                best = Some((end, score));
+            }
+        }
+        best.unwrap().0
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diff::MatchStatus;
+    use vctools_utils::prelude::*;
+
+    #[test]
+    fn test_get_indent() {
+        assert_eq!(get_indent(b"foo\n"), 0);
+        assert_eq!(get_indent(b"    foo\n"), 4);
+        assert_eq!(get_indent(b"  \tfoo\n"), 8);
+        assert_eq!(get_indent(b"  \n"), -1);
+        assert_eq!(get_indent(b""), -1);
+    }
+
+    #[test]
+    fn test_slide_insertion() -> Result<()> {
+        let mut buffer = Buffer::new();
+        let mut file = FileMatch::changed_for_test(
+            &mut buffer,
+            b"fn a() {\n    one();\n}\n\nfn c() {\n    three();\n}\n",
+            b"fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n\nfn c() {\n    three();\n}\n",
+        )?;
+
+        // The inserted function is placed awkwardly, starting with the closing
+        // brace of the previous function.
+        let marker = |old_line, new_line, status| MatchStatusMarker { old_line, new_line, status };
+        file.status_markers = vec![
+            marker(0, 0, MatchStatus::Unchanged),
+            marker(2, 2, MatchStatus::Changed { unimportant: false }),
+            marker(2, 6, MatchStatus::Unchanged),
+        ];
+        apply(&mut file, &buffer);
+
+        let positions: Vec<_> = file
+            .status_markers
+            .iter()
+            .map(|sm| (sm.old_line, sm.new_line, sm.is_changed()))
+            .collect();
+        assert_eq!(positions, vec![(0, 0, false), (4, 4, true), (4, 8, false)]);
+        Ok(())
+    }
+}
@@ -49,8 +49,16 @@
 /// in the dynamic programming table.
 ///
 /// See [`DiffAlgorithm`] for more.
+///
+/// There are often several diffs with the same number of unchanged lines, and
+/// the algorithms may find different ones. For example, a block of lines that
+/// was added next to an identical copy can be reported as either copy. To make
+/// results stable across algorithms and versions, ties are broken in a
+/// separate pass over the result that is shared by all algorithms: added or
+/// removed lines are slid down as far as the surrounding unchanged lines
+/// allow, i.e. the earlier copy is kept unchanged. See [`break_ties`].
 use std::{
-    collections::{hash_map::Entry, BinaryHeap, HashMap},
+    collections::{BinaryHeap, HashMap},
     ops::Range,
 };
 
@@ -58,7 +66,7 @@ use itertools::Itertools;
 
 use crate::diff::*;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiffAlgorithm {
     /// Dijkstra search in the search graph, restricting outgoing edges to
     /// those forming a Pareto set.
@@ -73,6 +81,14 @@ pub enum DiffAlgorithm {
 
     /// Sweep-line over the quadrant arrangement created by matchable edges.
     SweepLineExact,
+
+    /// Patience diff: lines that occur exactly once on each side are matched
+    /// first, and the lines between them are diffed recursively. Ranges
+    /// without such lines fall back to `SweepLine`.
+    ///
+    /// This doesn't always keep the most lines unchanged, but tends to be
+    /// more readable for code that was moved around.
+    Patience,
 }
 impl Default for DiffAlgorithm {
     fn default() -> Self {
@@ -97,10 +113,129 @@ impl DiffAlgorithm {
             Self::SweepLineExact => {
                 diff_sweep_line_exact(buffer, old_begin, new_begin, old, new, unimportant)
             }
+            Self::Patience => diff_patience(buffer, old_begin, new_begin, old, new, unimportant),
         }
     }
 }
 
+/// How changed blocks are reduced, see [`reduce_changed_file_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReduceOptions<'a> {
+    pub algorithm: DiffAlgorithm,
+
+    /// Lines that start with one of the anchors and occur exactly once on each
+    /// side of a block are preferentially matched, like in `git diff --anchored`.
+    pub anchors: &'a [String],
+}
+impl ReduceOptions<'_> {
+    /// Like [`DiffAlgorithm::run`], but pairs of anchor lines (see
+    /// [`find_anchor_pairs`]) are matched first, and the algorithm only runs on
+    /// the lines between them.
+    fn run(
+        &self,
+        buffer: &Buffer,
+        old_begin: u32,
+        new_begin: u32,
+        old: &[BufferRef],
+        new: &[BufferRef],
+        unimportant: bool,
+    ) -> Vec<MatchStatusMarker> {
+        let pairs = find_anchor_pairs(buffer, self.anchors, old, new);
+        if pairs.is_empty() {
+            return self.algorithm.run(buffer, old_begin, new_begin, old, new, unimportant);
+        }
+
+        let mut status_markers = Vec::new();
+        let mut old_pos = 0;
+        let mut new_pos = 0;
+        for (old_idx, new_idx) in pairs.into_iter().chain([(old.len(), new.len())]) {
+            let old_line = old_begin + old_pos as u32;
+            let new_line = new_begin + new_pos as u32;
+            if old_pos == old_idx && new_pos == new_idx {
+                status_markers.push(MatchStatusMarker {
+                    old_line,
+                    new_line,
+                    status: MatchStatus::Unchanged,
+                });
+            } else {
+                status_markers.extend(self.algorithm.run(
+                    buffer,
+                    old_line,
+                    new_line,
+                    &old[old_pos..old_idx],
+                    &new[new_pos..new_idx],
+                    unimportant,
+                ));
+            }
+            old_pos = old_idx + 1;
+            new_pos = new_idx + 1;
+        }
+        status_markers
+    }
+}
+
+/// Find the pairs of anchor lines, like `git diff --anchored`: lines that start
+/// with one of the anchor texts and occur exactly once on each side.
+///
+/// Returns the largest set of pairs that can be matched together, as 0-based
+/// indices (old, new) in increasing order.
+fn find_anchor_pairs(
+    buffer: &Buffer,
+    anchors: &[String],
+    old: &[BufferRef],
+    new: &[BufferRef],
+) -> Vec<(usize, usize)> {
+    if anchors.is_empty() {
+        return Vec::new();
+    }
+
+    // Number of occurrences and index of the last occurrence on each side.
+    let mut occurrences: HashMap<&[u8], [(u32, usize); 2]> = HashMap::new();
+    for (side, lines) in [old, new].into_iter().enumerate() {
+        for (idx, &line) in lines.iter().enumerate() {
+            let contents = &buffer[line];
+            if anchors.iter().any(|anchor| contents.starts_with(anchor.as_bytes())) {
+                let entry = &mut occurrences.entry(contents).or_default()[side];
+                entry.0 += 1;
+                entry.1 = idx;
+            }
+        }
+    }
+    let mut pairs: Vec<(usize, usize)> = occurrences
+        .into_values()
+        .filter(|[(old_count, _), (new_count, _)]| *old_count == 1 && *new_count == 1)
+        .map(|[(_, old_idx), (_, new_idx)]| (old_idx, new_idx))
+        .collect();
+    pairs.sort();
+    longest_increasing_pairs(&pairs)
+}
+
+/// Given pairs of indices (old, new) that are sorted by old index, return the
+/// largest subset in which the new indices are increasing as well.
+fn longest_increasing_pairs(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // Longest increasing subsequence of new indices, by patience sorting.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut predecessors: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (idx, &(_, new_idx)) in pairs.iter().enumerate() {
+        let pos = tails.partition_point(|&tail| pairs[tail].1 < new_idx);
+        predecessors.push(pos.checked_sub(1).map(|pos| tails[pos]));
+        if pos == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[pos] = idx;
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut current = tails.last().copied();
+    while let Some(idx) = current {
+        result.push(pairs[idx]);
+        current = predecessors[idx];
+    }
+    result.reverse();
+    result
+}
+
 /// Collect status markers for a range of lines.
 ///
 /// Status is added in reverse order using `add_unchanged`.
@@ -527,13 +662,21 @@ impl DiffProblem {
             pos_short: SortedSmallVec,
             pos_long: SortedSmallVec,
             mapping: i32,
+
+            /// Contents of the line, from its first occurrence.
+            contents: BufferRef,
+
+            /// Index of another line with the same hash, or 0.
+            next_same_hash: usize,
         }
         impl TempLine {
-            fn new() -> Self {
+            fn new(contents: BufferRef, next_same_hash: usize) -> Self {
                 Self {
                     pos_short: SortedSmallVec::Empty,
                     pos_long: SortedSmallVec::Empty,
                     mapping: 0,
+                    contents,
+                    next_same_hash,
                 }
             }
         }
@@ -542,8 +685,20 @@ impl DiffProblem {
             panic!("should be prevented by checks in diff::Buffer")
         }
 
-        let mut hash: HashMap<&[u8], usize> = HashMap::new();
-        let mut temp_lines: Vec<_> = Vec::new();
+        // Lines are looked up by their hash first, and their contents are only
+        // compared if the hashes match.
+        let mut hash: LineHashMap<usize> = LineHashMap::default();
+        let mut temp_lines: Vec<TempLine> = Vec::new();
+        let find = |hash: &LineHashMap<usize>, temp_lines: &[TempLine], line: BufferRef, h| {
+            let mut idx = *hash.get(&h)?;
+            while buffer[temp_lines[idx].contents] != buffer[line] {
+                idx = temp_lines[idx].next_same_hash;
+                if idx == 0 {
+                    return None;
+                }
+            }
+            Some(idx)
+        };
 
         let short;
         let long;
@@ -558,25 +713,25 @@ impl DiffProblem {
 
         hash.reserve(short.len());
         temp_lines.reserve_exact(short.len() + 1);
-        temp_lines.push(TempLine::new());
+        temp_lines.push(TempLine::new(BufferRef::default(), 0));
 
         let mut backing_size = 0;
         let short: Vec<usize> = short
             .iter()
             .enumerate()
-            .map(|(idx, line)| {
-                let (line_idx, line) = match hash.entry(&buffer[*line]) {
-                    Entry::Occupied(entry) => (*entry.get(), &mut temp_lines[*entry.get()]),
-                    Entry::Vacant(entry) => {
-                        temp_lines.push(TempLine::new());
-                        (
-                            *entry.insert(temp_lines.len() - 1),
-                            temp_lines.last_mut().unwrap(),
-                        )
+            .map(|(idx, &line)| {
+                let h = hash_bytes(&buffer[line]);
+                let line_idx = match find(&hash, &temp_lines, line, h) {
+                    Some(line_idx) => line_idx,
+                    None => {
+                        let next_same_hash = hash.get(&h).copied().unwrap_or(0);
+                        temp_lines.push(TempLine::new(line, next_same_hash));
+                        hash.insert(h, temp_lines.len() - 1);
+                        temp_lines.len() - 1
                     }
                 };
 
-                line.pos_short.push(idx as u32 + 1);
+                temp_lines[line_idx].pos_short.push(idx as u32 + 1);
                 backing_size += 1;
 
                 line_idx
@@ -585,8 +740,8 @@ impl DiffProblem {
         let long: Vec<usize> = long
             .iter()
             .enumerate()
-            .map(|(idx, line)| {
-                if let Some(&line_idx) = hash.get(&buffer[*line]) {
+            .map(|(idx, &line)| {
+                if let Some(line_idx) = find(&hash, &temp_lines, line, hash_bytes(&buffer[line])) {
                     temp_lines[line_idx].pos_long.push(idx as u32 + 1);
                     backing_size += 1;
 
@@ -1232,6 +1387,110 @@ fn diff_sweep_line_exact(
     collect.finish()
 }
 
+fn diff_patience(
+    buffer: &Buffer,
+    old_begin: u32,
+    new_begin: u32,
+    old: &[BufferRef],
+    new: &[BufferRef],
+    unimportant: bool,
+) -> Vec<MatchStatusMarker> {
+    let mut pairs = Vec::new();
+    patience_pairs(buffer, old, new, 0, 0, &mut pairs);
+
+    let mut collect = ReverseStatusCollector::new(
+        old_begin..old_begin + old.len() as u32,
+        new_begin..new_begin + new.len() as u32,
+        unimportant,
+    );
+    for &(old_idx, new_idx) in pairs.iter().rev() {
+        collect.add_unchanged(old_idx as u32 + 1, new_idx as u32 + 1, 1);
+    }
+    collect.finish()
+}
+
+/// Append the pairs of 0-based indices (old, new) of unchanged lines to
+/// `pairs`, in increasing order. The indices are offset by `old_offset` and
+/// `new_offset`, respectively.
+fn patience_pairs(
+    buffer: &Buffer,
+    old: &[BufferRef],
+    new: &[BufferRef],
+    old_offset: usize,
+    new_offset: usize,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let same = |old_idx: usize, new_idx: usize| buffer[old[old_idx]] == buffer[new[new_idx]];
+
+    // Common lines at the start and end are always matched.
+    let head = (0..old.len().min(new.len())).take_while(|&idx| same(idx, idx)).count();
+    let tail = (0..old.len().min(new.len()) - head)
+        .take_while(|&idx| same(old.len() - 1 - idx, new.len() - 1 - idx))
+        .count();
+    pairs.extend((0..head).map(|idx| (old_offset + idx, new_offset + idx)));
+
+    let old_mid = &old[head..old.len() - tail];
+    let new_mid = &new[head..new.len() - tail];
+    if !old_mid.is_empty() && !new_mid.is_empty() {
+        // Number of occurrences and index of the last occurrence on each side.
+        let mut occurrences: HashMap<&[u8], [(u32, usize); 2]> = HashMap::new();
+        for (side, lines) in [old_mid, new_mid].into_iter().enumerate() {
+            for (idx, &line) in lines.iter().enumerate() {
+                let entry = &mut occurrences.entry(&buffer[line]).or_default()[side];
+                entry.0 += 1;
+                entry.1 = idx;
+            }
+        }
+        let mut unique: Vec<(usize, usize)> = occurrences
+            .into_values()
+            .filter(|[(old_count, _), (new_count, _)]| *old_count == 1 && *new_count == 1)
+            .map(|[(_, old_idx), (_, new_idx)]| (old_idx, new_idx))
+            .collect();
+        unique.sort();
+        let unique = longest_increasing_pairs(&unique);
+
+        let (old_offset, new_offset) = (old_offset + head, new_offset + head);
+        if unique.is_empty() {
+            let status_markers = diff_sweep_line(buffer, 0, 0, old_mid, new_mid, false);
+            for (sm, sm_next) in status_markers.iter().tuple_windows() {
+                if sm.status == MatchStatus::Unchanged {
+                    let old_line = old_offset + sm.old_line as usize;
+                    let new_line = new_offset + sm.new_line as usize;
+                    let count = (sm_next.old_line - sm.old_line) as usize;
+                    pairs.extend((0..count).map(|idx| (old_line + idx, new_line + idx)));
+                }
+            }
+        } else {
+            let mut old_pos = 0;
+            let mut new_pos = 0;
+            for (old_idx, new_idx) in unique {
+                patience_pairs(
+                    buffer,
+                    &old_mid[old_pos..old_idx],
+                    &new_mid[new_pos..new_idx],
+                    old_offset + old_pos,
+                    new_offset + new_pos,
+                    pairs,
+                );
+                pairs.push((old_offset + old_idx, new_offset + new_idx));
+                old_pos = old_idx + 1;
+                new_pos = new_idx + 1;
+            }
+            patience_pairs(
+                buffer,
+                &old_mid[old_pos..],
+                &new_mid[new_pos..],
+                old_offset + old_pos,
+                new_offset + new_pos,
+                pairs,
+            );
+        }
+    }
+
+    let (old_tail, new_tail) = (old_offset + old.len() - tail, new_offset + new.len() - tail);
+    pairs.extend((0..tail).map(|idx| (old_tail + idx, new_tail + idx)));
+}
+
 fn diff_sweep_line(
     buffer: &Buffer,
     old_begin: u32,
@@ -1504,8 +1763,17 @@ fn diff_sweep_line(
 /// change remaining.
 pub fn reduce_changed_file(
     buffer: &Buffer,
-    mut file: FileMatch,
+    file: FileMatch,
     algorithm: DiffAlgorithm,
+) -> (FileMatch, bool) {
+    reduce_changed_file_with(buffer, file, &ReduceOptions { algorithm, ..Default::default() })
+}
+
+/// Like [`reduce_changed_file`], but with all [`ReduceOptions`].
+pub fn reduce_changed_file_with(
+    buffer: &Buffer,
+    mut file: FileMatch,
+    options: &ReduceOptions,
 ) -> (FileMatch, bool) {
     let (Some(old_file), Some(new_file)) = (&file.old, &file.new) else {
         return (file, false);
@@ -1594,7 +1862,7 @@ pub fn reduce_changed_file(
 
         if !old.is_empty() || !new.is_empty() {
             have_change = true;
-            file.status_markers.extend(algorithm.run(
+            file.status_markers.extend(options.run(
                 buffer,
                 sm.old_line,
                 sm.new_line,
@@ -1611,16 +1879,61 @@ pub fn reduce_changed_file(
         }
     }
 
-    file.simplify();
+    break_ties(&mut file, buffer);
 
     (file, have_change)
 }
 
+/// Make the result independent of the algorithm among diffs with the same
+/// number of unchanged lines that only differ by sliding added or removed lines
+/// up or down: such lines are moved to the latest possible position.
+///
+/// This is the same rule as Git's diff uses before applying its heuristics.
+/// Changed blocks with more lines on one side are treated as a replacement
+/// followed by a group of only added or only removed lines, so that e.g.
+/// `-x +y +a` followed by an unchanged `a` becomes `-x +y`, the unchanged `a`,
+/// and `+a`.
+fn break_ties(file: &mut FileMatch, buffer: &Buffer) {
+    file.simplify();
+    let markers = std::mem::take(&mut file.status_markers);
+    for (sm, next) in markers.iter().tuple_windows() {
+        file.status_markers.push(*sm);
+
+        let old_count = next.old_line - sm.old_line;
+        let new_count = next.new_line - sm.new_line;
+        if !sm.is_changed() || next.is_changed() || old_count.min(new_count) == 0 {
+            continue;
+        }
+
+        // Split off the excess lines with an empty unchanged region, which
+        // the slide may grow and which is removed by simplification otherwise.
+        let common = old_count.min(new_count);
+        let split = MatchStatusMarker {
+            old_line: sm.old_line + common,
+            new_line: sm.new_line + common,
+            status: MatchStatus::Unchanged,
+        };
+        file.status_markers.push(split);
+        file.status_markers.push(MatchStatusMarker { status: sm.status, ..split });
+    }
+    file.status_markers.push(*markers.last().unwrap());
+
+    indent_heuristic::slide_groups(file, buffer, |_, ends, _| *ends.end());
+}
+
 /// Reduce changed blocks by applying a diff algorithm within each changed block.
-pub fn reduce_changed_diff(buffer: &Buffer, mut diff: Diff, algorithm: DiffAlgorithm) -> Diff {
+pub fn reduce_changed_diff(buffer: &Buffer, diff: Diff, algorithm: DiffAlgorithm) -> Diff {
+    reduce_changed_diff_with(buffer, diff, &ReduceOptions { algorithm, ..Default::default() })
+}
+
+/// Like [`reduce_changed_diff`], but with all [`ReduceOptions`].
+pub fn reduce_changed_diff_with(buffer: &Buffer, mut diff: Diff, options: &ReduceOptions) -> Diff {
     for mut file in std::mem::take(&mut diff.files) {
         let have_change;
-        (file, have_change) = reduce_changed_file(buffer, file, algorithm);
+        (file, have_change) = reduce_changed_file_with(buffer, file, options);
+        if diff.options.indent_heuristic {
+            indent_heuristic::apply(&mut file, buffer);
+        }
 
         if have_change {
             diff.files.push(file);
@@ -1629,3 +1942,114 @@ pub fn reduce_changed_diff(buffer: &Buffer, mut diff: Diff, algorithm: DiffAlgor
 
     diff
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Pairs of 0-based (old, new) line numbers that are unchanged.
+    fn unchanged_lines(file: &FileMatch, old_lines: u32) -> Vec<(u32, u32)> {
+        let mut result = Vec::new();
+        for (sm, sm_next) in file.status_markers.iter().tuple_windows() {
+            if sm.status == MatchStatus::Unchanged {
+                for i in 0..sm_next.old_line - sm.old_line {
+                    result.push((sm.old_line + i, sm.new_line + i));
+                }
+            }
+        }
+        let last = file.status_markers.last().unwrap();
+        for i in 0..old_lines.saturating_sub(last.old_line) {
+            result.push((last.old_line + i, last.new_line + i));
+        }
+        result
+    }
+
+    fn reduce(old: &[u8], new: &[u8], anchors: &[String]) -> Result<Vec<(u32, u32)>> {
+        reduce_with(DiffAlgorithm::default(), old, new, anchors)
+    }
+
+    fn reduce_with(
+        algorithm: DiffAlgorithm,
+        old: &[u8],
+        new: &[u8],
+        anchors: &[String],
+    ) -> Result<Vec<(u32, u32)>> {
+        let mut buffer = Buffer::new();
+        let file = FileMatch::changed_for_test(&mut buffer, old, new)?;
+        let old_lines = file.old.as_ref().unwrap().num_lines().unwrap();
+        let (file, _) =
+            reduce_changed_file_with(&buffer, file, &ReduceOptions { algorithm, anchors });
+        Ok(unchanged_lines(&file, old_lines))
+    }
+
+    #[test]
+    fn test_anchored() -> Result<()> {
+        let old = b"fn a() {\n    one\n}\nfn b() {\n    two\n}\n";
+        let new = b"fn b() {\n    two\n}\nfn a() {\n    one\n}\n";
+
+        // The common last line is matched before anchors are considered, but
+        // ties are then broken towards keeping the closing brace of the
+        // unchanged function.
+        let unchanged = reduce(old, new, &["fn b".into()])?;
+        assert_eq!(unchanged, vec![(3, 0), (4, 1), (5, 2)]);
+
+        let unchanged = reduce(old, new, &["fn a".into()])?;
+        assert_eq!(unchanged, vec![(0, 3), (1, 4), (2, 5)]);
+
+        // Anchor lines that cross each other can't all be kept.
+        let unchanged = reduce(old, new, &["fn".into()])?;
+        assert_eq!(unchanged.len(), 3);
+
+        // Lines that occur more than once on a side aren't anchors.
+        let old = b"x\nfn a\nfn a\ny\n";
+        let new = b"fn a\nz\n";
+        let unchanged = reduce(old, new, &["fn a".into()])?;
+        assert_eq!(unchanged, reduce(old, new, &[])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tie_breaking() -> Result<()> {
+        // Old text, new text, and the unchanged lines.
+        type Case<'a> = (&'a [u8], &'a [u8], &'a [(u32, u32)]);
+        let cases: &[Case] = &[
+            // Added copy of lines after a replaced line.
+            (b"x\na\nb\ny\n", b"z\na\nb\na\nb\ny\n", &[(1, 1), (2, 2), (3, 5)]),
+            // Added copy of lines before a replaced line.
+            (b"a\nb\nx\n", b"a\nb\na\nb\nz\n", &[(0, 0), (1, 1)]),
+            // Removed repeated lines.
+            (b"x\nb\nb\nb\ny\n", b"z\nb\ny\n", &[(1, 1), (4, 2)]),
+        ];
+        for (old, new, expected) in cases {
+            for algorithm in [
+                DiffAlgorithm::GraphSearch,
+                DiffAlgorithm::SweepLine,
+                DiffAlgorithm::SweepLineExact,
+                DiffAlgorithm::Patience,
+            ] {
+                let unchanged = reduce_with(algorithm, old, new, &[])?;
+                assert_eq!(&unchanged, expected, "{algorithm:?}");
+            }
+        }
+        Ok(())
+    }
+    #[test]
+    fn test_patience() -> Result<()> {
+        // Lines that are unique on both sides are kept, even if more of the
+        // repeated lines could be kept otherwise.
+        let old = b"u\nv\nc\nc\nc\n";
+        let new = b"c\nc\nc\nu\nv\n";
+        let unchanged = reduce_with(DiffAlgorithm::Patience, old, new, &[])?;
+        assert_eq!(unchanged, vec![(0, 3), (1, 4)]);
+        let unchanged = reduce_with(DiffAlgorithm::SweepLine, old, new, &[])?;
+        assert_eq!(unchanged, vec![(2, 0), (3, 1), (4, 2)]);
+
+        // The lines between unique lines are diffed recursively, with a
+        // fallback for those without any unique lines.
+        let old = b"fn a\n{\nx\n}\nfn b\n{\ny\ny\n}\n";
+        let new = b"fn a\n{\nz\n}\nfn b\n{\ny\n}\n";
+        let unchanged = reduce_with(DiffAlgorithm::Patience, old, new, &[])?;
+        assert_eq!(unchanged, vec![(0, 0), (1, 1), (3, 3), (4, 4), (5, 5), (6, 6), (8, 7)]);
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
 // SPDX-License-Identifier: MIT
 
+use std::ops::Range;
+
 use super::file::FileName;
-use super::hunks::HunkLine;
+use super::hunks::{HunkLine, HunkLineStatus};
+
+/// Largest number of pairs of tokens that the intra-line diff compares. Longer
+/// lines aren't highlighted.
+const MAX_TOKEN_PAIRS: usize = 1 << 18;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Context {
@@ -43,6 +49,11 @@ pub enum ChunkContents {
     },
     Line {
         line: HunkLine,
+
+        /// Byte ranges of the line's contents that differ from the line it is
+        /// paired with on the other side of the change, see
+        /// [`intra_line_changes`]. Empty if the line isn't paired.
+        changed: Vec<Range<usize>>,
     },
 }
 
@@ -83,7 +94,7 @@ impl Chunk {
                     .as_bytes(),
                 );
             }
-            ChunkContents::Line { line } => {
+            ChunkContents::Line { line, .. } => {
                 out.extend(prefix);
                 out.push(line.status.symbol_byte());
                 out.extend(&line.contents);
@@ -139,3 +150,214 @@ impl ChunkWriter for ChunkByteBufferWriter {
         chunk.render_text(&mut self.out);
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenClass {
+    Word,
+    Space,
+    Other,
+}
+
+impl TokenClass {
+    /// Bytes of non-ASCII characters count as word characters, so that tokens
+    /// never split a character.
+    fn of(byte: u8) -> Self {
+        if byte.is_ascii_alphanumeric() || byte == b'_' || !byte.is_ascii() {
+            TokenClass::Word
+        } else if byte.is_ascii_whitespace() {
+            TokenClass::Space
+        } else {
+            TokenClass::Other
+        }
+    }
+}
+
+/// Split a line into tokens: runs of word characters, runs of whitespace, and
+/// single other characters.
+fn tokenize(line: &[u8]) -> Vec<Range<usize>> {
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    for (idx, &byte) in line.iter().enumerate() {
+        let extend = tokens.last().is_some_and(|last| {
+            let prev = TokenClass::of(line[last.start]);
+            prev != TokenClass::Other && prev == TokenClass::of(byte)
+        });
+        if extend {
+            tokens.last_mut().unwrap().end = idx + 1;
+        } else {
+            tokens.push(idx..idx + 1);
+        }
+    }
+    tokens
+}
+
+/// Byte ranges within a line.
+type ByteRanges = Vec<Range<usize>>;
+
+/// Compute the byte ranges that differ between an old and a new line, from a
+/// longest common subsequence of their tokens.
+///
+/// Returns None if the lines have nothing but whitespace and punctuation in
+/// common, so that highlighting the differences wouldn't help, or if they are
+/// too long.
+pub fn changed_ranges(old: &[u8], new: &[u8]) -> Option<(ByteRanges, ByteRanges)> {
+    let old = old.strip_suffix(b"\n").unwrap_or(old);
+    let new = new.strip_suffix(b"\n").unwrap_or(new);
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    let same = |i: usize, j: usize| old[old_tokens[i].clone()] == new[new_tokens[j].clone()];
+    let is_word = |i: usize| TokenClass::of(old[old_tokens[i].start]) == TokenClass::Word;
+
+    let prefix = (0..old_tokens.len().min(new_tokens.len()))
+        .take_while(|&i| same(i, i))
+        .count();
+    let suffix = (0..old_tokens.len().min(new_tokens.len()) - prefix)
+        .take_while(|&i| same(old_tokens.len() - 1 - i, new_tokens.len() - 1 - i))
+        .count();
+    let (n, m) = (old_tokens.len() - prefix - suffix, new_tokens.len() - prefix - suffix);
+    if n.saturating_mul(m) > MAX_TOKEN_PAIRS {
+        return None;
+    }
+
+    // lcs[i * (m + 1) + j] is the length of the longest common subsequence of
+    // the middle tokens from i and j on.
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i * (m + 1) + j] = if same(prefix + i, prefix + j) {
+                lcs[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let mut old_changed: Vec<Range<usize>> = Vec::new();
+    let mut new_changed: Vec<Range<usize>> = Vec::new();
+    let push = |ranges: &mut Vec<Range<usize>>, token: &Range<usize>| match ranges.last_mut() {
+        Some(last) if last.end == token.start => last.end = token.end,
+        _ => ranges.push(token.clone()),
+    };
+    let mut common_word = (0..prefix).chain(prefix + n..old_tokens.len()).any(is_word);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(prefix + i, prefix + j) {
+            common_word |= is_word(prefix + i);
+            i += 1;
+            j += 1;
+        } else if j >= m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+            push(&mut old_changed, &old_tokens[prefix + i]);
+            i += 1;
+        } else {
+            push(&mut new_changed, &new_tokens[prefix + j]);
+            j += 1;
+        }
+    }
+
+    common_word.then_some((old_changed, new_changed))
+}
+
+/// Compute the changed ranges within the lines of a hunk, for highlighting
+/// only the parts of lines that actually changed.
+///
+/// A block of removed lines that is directly followed by a block of added
+/// lines of the same length is considered to be a change of each line into
+/// the corresponding one, and the lines are diffed pairwise. Returns the
+/// changed byte ranges of each line; other lines get no ranges.
+pub fn intra_line_changes(lines: &[HunkLine]) -> Vec<Vec<Range<usize>>> {
+    let mut changes = vec![Vec::new(); lines.len()];
+    let is_old = |idx: usize| matches!(lines[idx].status, HunkLineStatus::Old(_));
+    let is_new = |idx: usize| matches!(lines[idx].status, HunkLineStatus::New(_));
+
+    let mut idx = 0;
+    while idx < lines.len() {
+        let old_begin = idx;
+        while idx < lines.len() && is_old(idx) {
+            idx += 1;
+        }
+        let new_begin = idx;
+        while idx < lines.len() && is_new(idx) {
+            idx += 1;
+        }
+        if idx == old_begin {
+            idx += 1;
+            continue;
+        }
+        if idx - new_begin != new_begin - old_begin {
+            continue;
+        }
+
+        for (old, new) in (old_begin..new_begin).zip(new_begin..idx) {
+            if let Some((old_changed, new_changed)) =
+                changed_ranges(&lines[old].contents, &lines[new].contents)
+            {
+                changes[old] = old_changed;
+                changes[new] = new_changed;
+            }
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn changed<'a>(line: &'a str, ranges: &[Range<usize>]) -> Vec<&'a str> {
+        ranges.iter().map(|range| &line[range.clone()]).collect()
+    }
+
+    #[test]
+    fn changed_ranges() {
+        let old = "    let foo = bar(1, 2);\n";
+        let new = "    let foo = baz(1, 3);\n";
+        let (old_changed, new_changed) =
+            super::changed_ranges(old.as_bytes(), new.as_bytes()).unwrap();
+        assert_eq!(changed(old, &old_changed), vec!["bar", "2"]);
+        assert_eq!(changed(new, &new_changed), vec!["baz", "3"]);
+
+        // Adjacent changed tokens are merged.
+        let old = "return x;";
+        let new = "return -x + 1;";
+        let (old_changed, new_changed) =
+            super::changed_ranges(old.as_bytes(), new.as_bytes()).unwrap();
+        assert!(old_changed.is_empty());
+        assert_eq!(changed(new, &new_changed), vec!["-", " + 1"]);
+
+        // Lines that only share punctuation and whitespace aren't paired.
+        assert!(super::changed_ranges(b"foo(bar);\n", b"baz(qux);\n").is_none());
+
+        // Words are never split within a multi-byte character.
+        let old = "x = \"größer\"";
+        let new = "x = \"größte\"";
+        let (old_changed, _) = super::changed_ranges(old.as_bytes(), new.as_bytes()).unwrap();
+        assert_eq!(changed(old, &old_changed), vec!["größer"]);
+    }
+
+    #[test]
+    fn intra_line_changes() {
+        let line = |status, contents: &str| HunkLine {
+            status,
+            contents: contents.as_bytes().to_vec(),
+        };
+        let lines = vec![
+            line(HunkLineStatus::Unchanged, "fn main() {\n"),
+            line(HunkLineStatus::Old(false), "    one(1);\n"),
+            line(HunkLineStatus::Old(false), "    two(2);\n"),
+            line(HunkLineStatus::New(false), "    one(10);\n"),
+            line(HunkLineStatus::New(false), "    two(2, 3);\n"),
+            line(HunkLineStatus::Old(false), "    three();\n"),
+            line(HunkLineStatus::New(false), "    four();\n"),
+            line(HunkLineStatus::New(false), "    five();\n"),
+            line(HunkLineStatus::Unchanged, "}\n"),
+        ];
+        let changes = super::intra_line_changes(&lines);
+        assert!(changes[0].is_empty());
+        assert_eq!(changes[1], vec![8..9]);
+        assert_eq!(changes[3], vec![8..10]);
+        assert!(changes[2].is_empty());
+        assert_eq!(changes[4], vec![9..12]);
+
+        // Blocks of different lengths aren't paired.
+        assert!(changes[5..].iter().all(|changed| changed.is_empty()));
+    }
+}
@@ -1,12 +1,42 @@
 // SPDX-License-Identifier: MIT
 
-use lazy_static::lazy_static;
 use termcolor::{Color, ColorSpec};
 
 use crate::*;
 use diff::{*, render::{Context, Chunk, ChunkContents, ChunkWriter}};
 use git_core::{RangeDiffMatch, RangeDiffWriter};
 
+/// Color scheme of added and removed lines.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// Green for added and red for removed lines.
+    #[default]
+    RedGreen,
+
+    /// Blue for added and orange for removed lines, which can be told apart
+    /// with the common forms of color blindness.
+    BlueOrange,
+
+    /// Red and green, but added lines are also bold and removed lines
+    /// underlined, so that they can be told apart without colors.
+    Patterns,
+}
+impl Palette {
+    /// Colors of added and removed lines as indices into the 256-color
+    /// palette of terminals, or None for the basic green and red.
+    pub fn ansi256(self) -> Option<(u8, u8)> {
+        match self {
+            Palette::BlueOrange => Some((33, 208)),
+            Palette::RedGreen | Palette::Patterns => None,
+        }
+    }
+
+    /// Whether added lines are bold and removed lines underlined.
+    pub fn patterns(self) -> bool {
+        self == Palette::Patterns
+    }
+}
+
 #[derive(Default)]
 struct Colors {
     default: ColorSpec,
@@ -17,47 +47,62 @@ struct Colors {
     new_unimportant: ColorSpec,
     old_important: ColorSpec,
     old_unimportant: ColorSpec,
+
+    /// Changed parts of paired removed and added lines.
+    new_emphasis: ColorSpec,
+    old_emphasis: ColorSpec,
 }
 impl Colors {
-    fn new() -> Self {
+    fn new(palette: Palette) -> Self {
         let mut colors = Colors {
             ..Default::default()
         };
+        let (new, old) = match palette.ansi256() {
+            Some((new, old)) => (Color::Ansi256(new), Color::Ansi256(old)),
+            None => (Color::Green, Color::Red),
+        };
         colors.file_header.set_bold(true);
         colors.hunk_header.set_fg(Some(Color::Cyan));
         colors.baseline.set_dimmed(true);
-        colors.new_important.set_fg(Some(Color::Green));
-        colors.new_unimportant.set_fg(Some(Color::Green));
-        colors.old_important.set_fg(Some(Color::Red));
-        colors.old_unimportant.set_fg(Some(Color::Red));
+        colors.new_important.set_fg(Some(new));
+        colors.new_unimportant.set_fg(Some(new));
+        colors.old_important.set_fg(Some(old));
+        colors.old_unimportant.set_fg(Some(old));
+        colors.new_emphasis.set_bg(Some(new)).set_fg(Some(Color::Black));
+        colors.old_emphasis.set_bg(Some(old)).set_fg(Some(Color::Black));
+        if palette.patterns() {
+            colors.new_important.set_bold(true);
+            colors.new_unimportant.set_bold(true);
+            colors.new_emphasis.set_bold(true);
+            colors.old_important.set_underline(true);
+            colors.old_unimportant.set_underline(true);
+            colors.old_emphasis.set_underline(true);
+        }
         colors
     }
-}
-lazy_static! {
-    static ref COLORS: Colors = Colors::new();
-}
 
-fn get_line_color(context: Context, state: HunkLineStatus) -> &'static ColorSpec {
-    match state {
-        HunkLineStatus::New(unimportant) => {
-            if unimportant || context != Context::Change {
-                &COLORS.new_unimportant
-            } else {
-                &COLORS.new_important
+    fn line(&self, context: Context, state: HunkLineStatus) -> &ColorSpec {
+        match state {
+            HunkLineStatus::New(unimportant) => {
+                if unimportant || context != Context::Change {
+                    &self.new_unimportant
+                } else {
+                    &self.new_important
+                }
             }
-        }
-        HunkLineStatus::Old(unimportant) => {
-            if unimportant || context != Context::Change {
-                &COLORS.old_unimportant
-            } else {
-                &COLORS.old_important
+            HunkLineStatus::Old(unimportant) => {
+                if unimportant || context != Context::Change {
+                    &self.old_unimportant
+                } else {
+                    &self.old_important
+                }
             }
-        }
-        HunkLineStatus::Unchanged => {
-            if context == Context::Baseline {
-                &COLORS.baseline
-            } else {
-                &COLORS.default
+            HunkLineStatus::Unchanged => {
+                if context == Context::Baseline {
+                    &self.baseline
+                } else {
+                    &self.default
+                }
             }
         }
     }
@@ -69,16 +114,29 @@ enum Element {
     RangeDiffMatch(RangeDiffMatch),
 }
 
-#[derive(Default)]
 pub struct Writer {
+    colors: Colors,
     elements: Vec<Element>,
     rdm_column_widths: git_core::RangeDiffMatchColumnWidths,
 }
+impl Default for Writer {
+    fn default() -> Self {
+        Self::with_palette(Palette::default())
+    }
+}
 impl Writer {
     pub fn new() -> Self {
         Self::default()
     }
 
+    pub fn with_palette(palette: Palette) -> Self {
+        Self {
+            colors: Colors::new(palette),
+            elements: Vec::new(),
+            rdm_column_widths: Default::default(),
+        }
+    }
+
     pub fn write(mut self, out: &mut dyn termcolor::WriteColor) -> std::io::Result<()> {
         for element in std::mem::take(&mut self.elements) {
             match element {
@@ -105,12 +163,12 @@ impl Writer {
             ChunkContents::FileHeader {
                 old_path, new_path, ..
             } => {
-                out.set_color(&COLORS.file_header)?;
+                out.set_color(&self.colors.file_header)?;
                 out.write(prefix)?;
                 out.write(b"--- ")?;
                 out.write(old_path)?;
                 out.write(b"\n")?;
-                out.set_color(&COLORS.file_header)?;
+                out.set_color(&self.colors.file_header)?;
                 out.write(prefix)?;
                 out.write(b"+++ ")?;
                 out.write(new_path)?;
@@ -122,7 +180,7 @@ impl Writer {
                 new_begin,
                 new_count,
             } => {
-                out.set_color(&COLORS.hunk_header)?;
+                out.set_color(&self.colors.hunk_header)?;
                 out.write(prefix)?;
                 out.write(
                     format!(
@@ -133,14 +191,27 @@ impl Writer {
                 )?;
                 out.reset()?;
             }
-            ChunkContents::Line { line } => {
-                let color = get_line_color(chunk.context, line.status);
-                if color != &COLORS.default {
+            ChunkContents::Line { line, changed } => {
+                let color = self.colors.line(chunk.context, line.status);
+                if color != &self.colors.default {
                     out.set_color(color)?;
                 }
                 out.write(prefix)?;
                 out.write(&[line.status.symbol_byte()])?;
-                out.write(&line.contents)?;
+
+                let emphasis = match line.status {
+                    HunkLineStatus::New(_) => &self.colors.new_emphasis,
+                    _ => &self.colors.old_emphasis,
+                };
+                let mut pos = 0;
+                for range in changed {
+                    out.write_all(&line.contents[pos..range.start])?;
+                    out.set_color(emphasis)?;
+                    out.write_all(&line.contents[range.clone()])?;
+                    out.set_color(color)?;
+                    pos = range.end;
+                }
+                out.write_all(&line.contents[pos..])?;
                 if line.contents.last().is_none_or(|ch| *ch != b'\n') {
                     out.write(b"\n\\ No newline at end of file\n")?;
                 }
@@ -12,6 +12,8 @@ fn diff_ranges_full_impl(
     ep: &dyn ExecutionProvider,
     old: Option<Range<&Ref>>,
     new: Option<Range<&Ref>>,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()> {
     let mut buffer = diff::Buffer::new();
@@ -23,7 +25,7 @@ fn diff_ranges_full_impl(
         range: &Option<Range<&Ref>>,
     ) -> Result<diff::Diff> {
         if let Some(range) = range {
-            let diff_text = buffer.insert(&repo.diff(ep, range.clone(), None)?)?;
+            let diff_text = buffer.intern(&repo.diff(ep, range.clone(), None)?)?;
             Ok(diff::Diff::parse(&buffer, diff_text)?)
         } else {
             Ok(diff::Diff::new(diff::DiffOptions::default()))
@@ -53,7 +55,33 @@ fn diff_ranges_full_impl(
         _ => panic!("at least one range needs to be provided"),
     };
 
-    diff::diff_modulo_base(&buffer, target_diff, &base_old_diff, &base_new_diff, writer)?;
+    diff::diff_modulo_base_with(
+        &buffer,
+        target_diff,
+        &base_old_diff,
+        &base_new_diff,
+        &diff::ReduceOptions { algorithm, anchors },
+        writer,
+    )?;
+
+    Ok(())
+}
+
+/// Produce the plain diff between two commits or trees, restricted to the
+/// given paths.
+pub fn diff_paths(
+    repo: &git_core::Repository,
+    ep: &dyn ExecutionProvider,
+    old: &Ref,
+    new: &Ref,
+    paths: &[&[u8]],
+    writer: &mut dyn ChunkWriter,
+) -> Result<()> {
+    let mut buffer = diff::Buffer::new();
+    let diff_text = buffer.insert(&repo.diff(ep, old..new, Some(paths))?)?;
+    let diff = diff::Diff::parse(&buffer, diff_text)?;
+
+    diff.render(&buffer, &mut writer.with_context(render::Context::Change));
 
     Ok(())
 }
@@ -65,6 +93,8 @@ pub fn diff_optional_ranges_full<R>(
     ep: &dyn ExecutionProvider,
     old: Option<Range<R>>,
     new: Option<Range<R>>,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()>
 where
@@ -77,6 +107,8 @@ where
             .map(|range| range.start.borrow()..range.end.borrow()),
         new.as_ref()
             .map(|range| range.start.borrow()..range.end.borrow()),
+        algorithm,
+        anchors,
         writer,
     )
 }
@@ -87,6 +119,8 @@ pub fn diff_ranges_full<R>(
     ep: &dyn ExecutionProvider,
     old: Range<R>,
     new: Range<R>,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()>
 where
@@ -97,6 +131,8 @@ where
         ep,
         Some(old.start.borrow()..old.end.borrow()),
         Some(new.start.borrow()..new.end.borrow()),
+        algorithm,
+        anchors,
         writer,
     )
 }
@@ -106,6 +142,8 @@ fn diff_optional_commits_impl(
     ep: &dyn ExecutionProvider,
     old: Option<&Ref>,
     new: Option<&Ref>,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()> {
     fn get_meta(
@@ -123,8 +161,8 @@ fn diff_optional_commits_impl(
             };
 
             Ok((
-                buffer.insert(&repo.show_commit(ep, commit, &show_options)?)?,
-                buffer.insert(name)?,
+                buffer.intern(&repo.show_commit(ep, commit, &show_options)?)?,
+                buffer.intern(name)?,
             ))
         } else {
             Ok((diff::BufferRef::default(), buffer.insert(b"/dev/null")?))
@@ -145,7 +183,7 @@ fn diff_optional_commits_impl(
             strip_path_components: 1,
             ..Default::default()
         },
-        diff::DiffAlgorithm::default(),
+        algorithm,
     )?;
 
     struct DelayedMetaWriter<'a> {
@@ -176,6 +214,8 @@ fn diff_optional_commits_impl(
         ep,
         old.map(|commit| commit.first_parent()..commit.clone()),
         new.map(|commit| commit.first_parent()..commit.clone()),
+        algorithm,
+        anchors,
         &mut delayed_meta_writer,
     )?;
 
@@ -202,6 +242,8 @@ pub fn diff_optional_commits<R>(
     ep: &dyn ExecutionProvider,
     old: Option<R>,
     new: Option<R>,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()>
 where
@@ -212,6 +254,8 @@ where
         ep,
         old.as_ref().map(|old| old.borrow()),
         new.as_ref().map(|new| new.borrow()),
+        algorithm,
+        anchors,
         writer,
     )
 }
@@ -223,6 +267,8 @@ pub fn diff_commits(
     ep: &dyn ExecutionProvider,
     old: &Ref,
     new: &Ref,
+    algorithm: diff::DiffAlgorithm,
+    anchors: &[String],
     writer: &mut dyn ChunkWriter,
 ) -> Result<()> {
     let show_options = git_core::ShowOptions {
@@ -232,8 +278,8 @@ pub fn diff_commits(
     };
 
     let mut buffer = diff::Buffer::new();
-    let old_meta = buffer.insert(&repo.show_commit(ep, old, &show_options)?)?;
-    let new_meta = buffer.insert(&repo.show_commit(ep, new, &show_options)?)?;
+    let old_meta = buffer.intern(&repo.show_commit(ep, old, &show_options)?)?;
+    let new_meta = buffer.intern(&repo.show_commit(ep, new, &show_options)?)?;
     let old_meta_name = buffer.insert(b"a/commit-meta")?;
     let new_meta_name = buffer.insert(b"b/commit-meta")?;
 
@@ -247,7 +293,7 @@ pub fn diff_commits(
             strip_path_components: 1,
             ..Default::default()
         },
-        diff::DiffAlgorithm::default(),
+        algorithm,
     )?;
 
     meta_diff.render_full_body(
@@ -259,6 +305,8 @@ pub fn diff_commits(
         ep,
         &old.first_parent()..old,
         &new.first_parent()..new,
+        algorithm,
+        anchors,
         writer,
     )
 }
@@ -1,6 +1,12 @@
 // SPDX-License-Identifier: MIT
 
-use std::{ffi::OsString, fmt::Display, io::prelude::*};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt::Display,
+    io::prelude::*,
+    sync::{Arc, Mutex, OnceLock},
+};
 
 use crate::utils::{trim_ascii, try_forward, Result};
 
@@ -37,6 +43,65 @@ impl Display for Ref {
     }
 }
 
+/// Namespace in which a forge publishes the refs of its change requests
+/// (pull requests or merge requests).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeNamespace {
+    /// `refs/pull/<n>/...`
+    GitHub,
+
+    /// `refs/merge-requests/<n>/...`
+    GitLab,
+}
+impl ChangeNamespace {
+    pub const ALL: [ChangeNamespace; 2] = [ChangeNamespace::GitHub, ChangeNamespace::GitLab];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeRefKind {
+    /// The head commit of the change as pushed by its author.
+    Head,
+
+    /// The test merge of the change into its target branch that is maintained
+    /// by the forge. It is absent if the change does not merge cleanly.
+    Merge,
+}
+
+/// Ref that a forge publishes for a change request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeRef {
+    pub namespace: ChangeNamespace,
+    pub number: u64,
+    pub kind: ChangeRefKind,
+}
+impl ChangeRef {
+    pub fn new(namespace: ChangeNamespace, number: u64, kind: ChangeRefKind) -> Self {
+        Self {
+            namespace,
+            number,
+            kind,
+        }
+    }
+
+    /// Full name of the ref on the remote.
+    pub fn ref_name(&self) -> String {
+        let prefix = match self.namespace {
+            ChangeNamespace::GitHub => "refs/pull",
+            ChangeNamespace::GitLab => "refs/merge-requests",
+        };
+        let suffix = match self.kind {
+            ChangeRefKind::Head => "head",
+            ChangeRefKind::Merge => "merge",
+        };
+        format!("{}/{}/{}", prefix, self.number, suffix)
+    }
+}
+impl Display for ChangeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.ref_name())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ShowOptions {
     pub show_patch: bool,
@@ -61,6 +126,36 @@ pub enum Url {
     Url(reqwest::Url),
 }
 impl Url {
+    /// Parse a remote URL as understood by git.
+    ///
+    /// Besides proper URLs (`https://`, `ssh://`, ...), this understands the
+    /// scp-like syntax `[user@]host:path` which git uses for SSH remotes.
+    pub fn parse(url: &str) -> Result<Url> {
+        lazy_static! {
+            static ref SCP_RE: regex::Regex =
+                regex::Regex::new(r"^(?:([^@/:]+)@)?([^@/:]+):([^@:].*)$").unwrap();
+        }
+
+        let url = url.trim();
+
+        // Like git, only treat the URL as scp-like if there is no "://" and no
+        // slash before the first colon.
+        if !url.contains("://") {
+            if let Some(captures) = SCP_RE.captures(url) {
+                let host = captures.get(2).unwrap().as_str();
+                let path = captures.get(3).unwrap().as_str();
+
+                return Ok(Url::Ssh {
+                    user: captures.get(1).map(|x| x.as_str().into()),
+                    host: host.into(),
+                    path: path.into(),
+                });
+            }
+        }
+
+        Ok(Url::Url(reqwest::Url::parse(url)?))
+    }
+
     pub fn hostname(&self) -> Option<&str> {
         match self {
             Url::Ssh { host, .. } => Some(&host),
@@ -68,9 +163,36 @@ impl Url {
         }
     }
 
+    /// Host name after resolving aliases from the user's SSH configuration.
+    ///
+    /// For example, with a `Host gh` / `HostName github.com` section in
+    /// `~/.ssh/config`, the remote `gh:owner/repo` resolves to `github.com`.
+    /// Falls back to the plain host name if ssh cannot be queried.
+    pub fn resolved_hostname(&self) -> Option<String> {
+        let hostname = self.hostname()?;
+        let is_ssh = match self {
+            Url::Ssh { .. } => true,
+            Url::Url(url) => url.scheme() == "ssh" || url.scheme().starts_with("git+ssh"),
+        };
+        if !is_ssh {
+            return Some(hostname.to_string());
+        }
+
+        lazy_static! {
+            static ref RESOLVED: Mutex<HashMap<String, Arc<OnceLock<String>>>> = Default::default();
+        }
+
+        // Only hold the map lock to find the alias' slot. Running ssh happens
+        // outside of it, so that resolving one alias doesn't block the others.
+        let slot = RESOLVED.lock().unwrap().entry(hostname.to_string()).or_default().clone();
+        let resolved =
+            slot.get_or_init(|| resolve_ssh_hostname(hostname).unwrap_or(hostname.to_string()));
+        Some(resolved.clone())
+    }
+
     pub fn path(&self) -> &str {
         match self {
-            Url::Ssh { path, .. } => &path,
+            Url::Ssh { path, .. } => path.strip_prefix("/").unwrap_or(&path),
             Url::Url(url) => url.path().strip_prefix("/").unwrap_or_default(),
         }
     }
@@ -78,6 +200,7 @@ impl Url {
     // Returns (organization, repository) from a GitHub URL.
     pub fn github_path(&self) -> Option<(&str, &str)> {
         let path = self.path();
+        let path = path.strip_suffix("/").unwrap_or(path);
         let path = path.strip_suffix(".git").unwrap_or(path);
         let mut iter = path.split("/");
         let organization = iter.next()?;
@@ -89,6 +212,25 @@ impl Url {
         }
     }
 }
+
+/// Ask ssh for the effective host name of a (possibly aliased) host.
+fn resolve_ssh_hostname(host: &str) -> Option<String> {
+    let output = std::process::Command::new("ssh")
+        .args(["-G", host])
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("hostname "))
+        .map(|hostname| hostname.trim().to_string())
+}
+
 impl Display for Url {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -123,6 +265,10 @@ pub enum Cacheability {
     /// Depends on repository state, but can be cached if successful.
     Cacheable,
 
+    /// Depends on the state of a remote, which changes without any local
+    /// action. Can be cached briefly if successful.
+    Volatile,
+
     /// Pure function of hashes. Can be cached indefinitely if successful.
     Pure,
 }
@@ -317,6 +463,9 @@ impl Repository {
         self.exec(ep, subcommand, empty.into_iter(), cacheable)
     }
 
+    /// Get the URL of a remote.
+    ///
+    /// `url.<base>.insteadOf` rewrites are already applied by git.
     pub fn get_url(&self, ep: &dyn ExecutionProvider, remote: &str) -> Result<Url> {
         try_forward(
             || -> Result<Url> {
@@ -326,26 +475,7 @@ impl Repository {
                     [&"get-url", remote].iter(),
                     Cacheability::Cacheable,
                 )?;
-                let url = String::from_utf8(raw)?;
-                let url = url.trim();
-
-                lazy_static! {
-                    static ref GIT_RE: regex::Regex =
-                        regex::Regex::new(r"^(?:([^@/:]+)@)?([^@/:]+):([^@:]+)$").unwrap();
-                }
-
-                if let Some(captures) = GIT_RE.captures(&url) {
-                    let host = captures.get(2).unwrap().as_str();
-                    let path = captures.get(3).unwrap().as_str();
-
-                    return Ok(Url::Ssh {
-                        user: captures.get(1).map(|x| x.as_str().into()),
-                        host: host.into(),
-                        path: path.into(),
-                    });
-                }
-
-                Ok(Url::Url(reqwest::Url::parse(&url)?))
+                Url::parse(&String::from_utf8(raw)?)
             },
             || format!("failed to query URL for remote {}", remote),
         )
@@ -440,6 +570,82 @@ impl Repository {
         )
     }
 
+    /// Get the value of an attribute for the given paths, as set by the
+    /// `.gitattributes` files of the given commit. Returns (path, value)
+    /// pairs, where the value is "set", "unset", "unspecified", or the value
+    /// that was assigned. Needs Git 2.40.
+    pub fn check_attr(
+        &self,
+        ep: &dyn ExecutionProvider,
+        commit: &Ref,
+        attr: &str,
+        paths: &[&str],
+    ) -> Result<Vec<(String, String)>> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        try_forward(
+            || -> Result<Vec<(String, String)>> {
+                let args = ["-z".into(), format!("--source={commit}"), attr.into(), "--".into()]
+                    .into_iter()
+                    .chain(paths.iter().map(|path| path.to_string()));
+                let result = self.exec(ep, "check-attr", args, Cacheability::Cacheable)?;
+
+                // Each path is reported as "<path> NUL <attr> NUL <value> NUL".
+                let fields: Vec<&[u8]> = result.split(|&ch| ch == b'\0').collect();
+                Ok(fields
+                    .chunks_exact(3)
+                    .map(|chunk| {
+                        (
+                            String::from_utf8_lossy(chunk[0]).into_owned(),
+                            String::from_utf8_lossy(chunk[2]).into_owned(),
+                        )
+                    })
+                    .collect())
+            },
+            || format!("failed to check the {attr} attribute at {commit}"),
+        )
+    }
+
+    /// Get the contents of a file at the given commit.
+    pub fn file_contents(
+        &self,
+        ep: &dyn ExecutionProvider,
+        commit: &Ref,
+        path: &str,
+    ) -> Result<Vec<u8>> {
+        try_forward(
+            || -> Result<Vec<u8>> {
+                let cacheability = if commit.is_hash() {
+                    Cacheability::Pure
+                } else {
+                    Cacheability::Cacheable
+                };
+                self.exec(ep, "show", [format!("{commit}:{path}")].iter(), cacheability)
+            },
+            || format!("failed to read {path} at {commit}"),
+        )
+    }
+
+    /// Get the commit time of the last change of every line of a file at the
+    /// given commit, as Unix timestamps, with the first line at index 0.
+    pub fn blame(&self, ep: &dyn ExecutionProvider, commit: &Ref, path: &str) -> Result<Vec<i64>> {
+        try_forward(
+            || -> Result<Vec<i64>> {
+                let cacheability = if commit.is_hash() {
+                    Cacheability::Pure
+                } else {
+                    Cacheability::Cacheable
+                };
+                let args = ["--porcelain".into(), format!("{commit}"), "--".into(), path.into()];
+                let result = self.exec(ep, "blame", args.iter(), cacheability)?;
+                parse_blame(&result)
+            },
+            || format!("failed to blame {path} at {commit}"),
+        )
+    }
+
     pub fn merge_base(&self, ep: &dyn ExecutionProvider, a: &Ref, b: &Ref) -> Result<Ref> {
         try_forward(
             || -> Result<Ref> {
@@ -462,6 +668,40 @@ impl Repository {
         )
     }
 
+    /// Merge two commits without touching the index or working tree.
+    ///
+    /// Requires Git 2.38 or later.
+    pub fn merge_tree(&self, ep: &dyn ExecutionProvider, a: &Ref, b: &Ref) -> Result<MergeTree> {
+        try_forward(
+            || -> Result<MergeTree> {
+                let cacheability = if a.is_hash() && b.is_hash() {
+                    Cacheability::Pure
+                } else {
+                    Cacheability::Cacheable
+                };
+                let args = ["--write-tree", "--name-only", "--no-messages"]
+                    .into_iter()
+                    .map(String::from)
+                    .chain([format!("{a}"), format!("{b}")])
+                    .map(Into::into)
+                    .collect();
+
+                // Conflicts are reported with exit code 1.
+                match ep.exec(&self.path, "merge-tree", args, cacheability) {
+                    ExecutionResult::Ok(stdout, _) => MergeTree::parse(&stdout),
+                    ExecutionResult::Err(stdout, _, Some(1)) if !stdout.is_empty() => {
+                        MergeTree::parse(&stdout)
+                    }
+                    ExecutionResult::Err(_, stderr, _) => {
+                        Err(String::from_utf8_lossy(&stderr).trim().to_string())?
+                    }
+                    ExecutionResult::Pending => Err("git merge-tree execution is still pending")?,
+                }
+            },
+            || format!("failed to merge {a} and {b}"),
+        )
+    }
+
     pub fn rev_parse(&self, ep: &dyn ExecutionProvider, a: &Ref) -> Result<Ref> {
         try_forward(
             || -> Result<Ref> {
@@ -478,6 +718,87 @@ impl Repository {
         )
     }
 
+    /// Apply a patch file to the working tree.
+    pub fn apply(&self, ep: &dyn ExecutionProvider, patch: &std::path::Path) -> Result<()> {
+        try_forward(
+            || -> Result<()> {
+                self.exec_with_stderr(
+                    ep,
+                    "apply",
+                    [patch.as_os_str()].into_iter(),
+                    Cacheability::None,
+                )?;
+                Ok(())
+            },
+            || format!("failed to apply {}", patch.display()),
+        )
+    }
+
+    /// Verify the GPG or SSH signatures of commits, as `git verify-commit`
+    /// would. Returns one signature per commit, in the same order.
+    ///
+    /// The result depends on the local keyring and trust settings.
+    pub fn verify_commits(
+        &self,
+        ep: &dyn ExecutionProvider,
+        commits: &[Ref],
+    ) -> Result<Vec<Signature>> {
+        if commits.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        try_forward(
+            || -> Result<Vec<Signature>> {
+                let args = ["-s".into(), format!("--format={}", Signature::FORMAT)]
+                    .into_iter()
+                    .chain(commits.iter().map(|commit| format!("{commit}")));
+                // gpg may report details on stderr, which is redundant.
+                let (stdout, _) =
+                    self.exec_with_stderr(ep, "show", args, Cacheability::Cacheable)?;
+
+                let signatures = stdout
+                    .split(|&ch| ch == b'\n')
+                    .filter(|line| !line.is_empty())
+                    .map(Signature::parse)
+                    .collect::<Result<Vec<_>>>()?;
+                if signatures.len() != commits.len() {
+                    Err(format!(
+                        "expected {} signatures, got {}",
+                        commits.len(),
+                        signatures.len()
+                    ))?;
+                }
+                Ok(signatures)
+            },
+            || "failed to verify commit signatures",
+        )
+    }
+
+    /// Append a message to the note of a commit in the given notes ref, e.g.
+    /// "refs/notes/review". The note is created if there is none yet.
+    pub fn append_note(
+        &self,
+        ep: &dyn ExecutionProvider,
+        notes_ref: &str,
+        commit: &Ref,
+        message: &str,
+    ) -> Result<()> {
+        try_forward(
+            || -> Result<()> {
+                let args = [
+                    format!("--ref={notes_ref}"),
+                    "append".into(),
+                    "-m".into(),
+                    message.into(),
+                    format!("{commit}"),
+                ];
+                self.exec_with_stderr(ep, "notes", args.iter(), Cacheability::None)?;
+                Ok(())
+            },
+            || format!("failed to add a note to {commit}"),
+        )
+    }
+
     pub fn prefetch(&self, ep: &dyn ExecutionProvider, remote: &str) -> Result<()> {
         try_forward(
             || -> Result<()> {
@@ -538,6 +859,98 @@ impl Repository {
         )
     }
 
+    /// Look up the commit that a ref on the remote currently points to.
+    ///
+    /// Returns `None` if the remote does not have the ref.
+    pub fn ls_remote(
+        &self,
+        ep: &dyn ExecutionProvider,
+        remote: &str,
+        name: &str,
+    ) -> Result<Option<Ref>> {
+        try_forward(
+            || -> Result<Option<Ref>> {
+                let refs = self.ls_remote_refs(ep, remote, &[name])?;
+                Ok(refs.into_iter().next().map(|(_, commit)| commit))
+            },
+            || format!("failed to look up {} on remote {}", name, remote),
+        )
+    }
+
+    /// Look up several refs on the remote at once. Returns the (name, commit)
+    /// pairs of the refs that the remote has.
+    fn ls_remote_refs(
+        &self,
+        ep: &dyn ExecutionProvider,
+        remote: &str,
+        names: &[&str],
+    ) -> Result<Vec<(String, Ref)>> {
+        // The refs of a remote change all the time, so the result must not be
+        // cached for long.
+        let result = self.exec(
+            ep,
+            "ls-remote",
+            std::iter::once(remote).chain(names.iter().copied()),
+            Cacheability::Volatile,
+        )?;
+
+        let mut refs = Vec::new();
+        for line in result.split(|&ch| ch == b'\n') {
+            let mut fields = trim_ascii(line).split(|&ch| ch == b'\t');
+            let (Some(hash), Some(line_name)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let line_name = String::from_utf8(line_name.into())?;
+            if names.contains(&line_name.as_str()) {
+                refs.push((line_name, Ref::new(String::from_utf8(hash.into())?)));
+            }
+        }
+        Ok(refs)
+    }
+
+    /// Find out in which namespace the remote publishes the refs of the change
+    /// request with the given number, i.e. whether it is a GitHub or GitLab
+    /// style forge.
+    ///
+    /// Returns `None` if the remote does not publish the change.
+    pub fn change_namespace(
+        &self,
+        ep: &dyn ExecutionProvider,
+        remote: &str,
+        number: u64,
+    ) -> Result<Option<ChangeNamespace>> {
+        let heads = ChangeNamespace::ALL
+            .map(|namespace| ChangeRef::new(namespace, number, ChangeRefKind::Head).ref_name());
+        let names: Vec<&str> = heads.iter().map(String::as_str).collect();
+        try_forward(
+            || -> Result<Option<ChangeNamespace>> {
+                let refs = self.ls_remote_refs(ep, remote, &names)?;
+                Ok(ChangeNamespace::ALL.into_iter().zip(&heads).find_map(|(namespace, head)| {
+                    refs.iter().any(|(name, _)| name == head).then_some(namespace)
+                }))
+            },
+            || format!("failed to look up change {} on remote {}", number, remote),
+        )
+    }
+
+    /// Make the commit of a forge change ref available locally and return it.
+    ///
+    /// Returns `None` if the remote does not publish the ref. For
+    /// [`ChangeRefKind::Merge`], this typically means that the change has
+    /// conflicts with its target branch.
+    pub fn fetch_change_ref(
+        &self,
+        ep: &dyn ExecutionProvider,
+        remote: &str,
+        change: &ChangeRef,
+    ) -> Result<Option<Ref>> {
+        let Some(commit) = self.ls_remote(ep, remote, &change.ref_name())? else {
+            return Ok(None);
+        };
+        self.fetch_missing(ep, remote, std::slice::from_ref(&commit))?;
+        Ok(Some(commit))
+    }
+
     pub fn log<R>(&self, ep: &dyn ExecutionProvider, range: Range<R>) -> Result<Vec<LogEntry>>
     where
         R: std::borrow::Borrow<Ref>,
@@ -655,6 +1068,169 @@ impl Repository {
     }
 }
 
+/// Result of merging two commits with `git merge-tree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeTree {
+    /// The merged tree. Conflicted files contain conflict markers.
+    pub tree: Ref,
+
+    /// Paths of the files with conflicts.
+    pub conflicts: Vec<String>,
+}
+impl MergeTree {
+    fn parse(output: &[u8]) -> Result<Self> {
+        let mut lines = output.split(|&ch| ch == b'\n').map(trim_ascii);
+        let tree = match lines.next() {
+            Some(tree) if !tree.is_empty() => Ref::new(String::from_utf8_lossy(tree)),
+            _ => Err("merge-tree produced no tree")?,
+        };
+        let conflicts = lines
+            .take_while(|line| !line.is_empty())
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect();
+        Ok(Self { tree, conflicts })
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+/// Parse the output of `git blame --porcelain` into the commit time of every
+/// line.
+///
+/// Each line of the file is preceded by a header line with the commit hash.
+/// The details of a commit, including its time, only follow the first header
+/// line of that commit.
+fn parse_blame(output: &[u8]) -> Result<Vec<i64>> {
+    let mut times: HashMap<&[u8], i64> = HashMap::new();
+    let mut result = Vec::new();
+    let mut commit = None;
+    for line in output.split(|&ch| ch == b'\n').filter(|line| !line.is_empty()) {
+        if line[0] == b'\t' {
+            let commit = commit.take().ok_or("blame line without header")?;
+            let time = times.get(commit).ok_or_else(|| {
+                format!("no time for commit {}", String::from_utf8_lossy(commit))
+            })?;
+            result.push(*time);
+        } else if commit.is_none() {
+            commit = line.split(|&ch| ch == b' ').next();
+        } else if let Some(time) = line.strip_prefix(b"committer-time ") {
+            let time = std::str::from_utf8(time)?.parse()?;
+            times.insert(commit.unwrap(), time);
+        }
+    }
+    Ok(result)
+}
+
+/// Outcome of verifying the signature of a commit, following the `%G?`
+/// placeholder of `git log`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Good signature by a trusted key.
+    Good,
+
+    /// Good signature by a key of unknown validity.
+    UnknownValidity,
+
+    /// Good signature that has expired.
+    Expired,
+
+    /// Good signature made by an expired key.
+    ExpiredKey,
+
+    /// Good signature made by a revoked key.
+    RevokedKey,
+
+    /// The signature does not match the commit.
+    Bad,
+
+    /// The signature cannot be checked, e.g. because the key is missing.
+    Unverifiable,
+
+    /// The commit is not signed.
+    Unsigned,
+}
+impl SignatureStatus {
+    fn parse(code: u8) -> Result<Self> {
+        Ok(match code {
+            b'G' => SignatureStatus::Good,
+            b'U' => SignatureStatus::UnknownValidity,
+            b'X' => SignatureStatus::Expired,
+            b'Y' => SignatureStatus::ExpiredKey,
+            b'R' => SignatureStatus::RevokedKey,
+            b'B' => SignatureStatus::Bad,
+            b'E' => SignatureStatus::Unverifiable,
+            b'N' => SignatureStatus::Unsigned,
+            _ => Err(format!("unknown signature status '{}'", code.escape_ascii()))?,
+        })
+    }
+
+    /// The code of the `%G?` placeholder, e.g. 'G' for a good signature.
+    pub fn code(self) -> char {
+        match self {
+            SignatureStatus::Good => 'G',
+            SignatureStatus::UnknownValidity => 'U',
+            SignatureStatus::Expired => 'X',
+            SignatureStatus::ExpiredKey => 'Y',
+            SignatureStatus::RevokedKey => 'R',
+            SignatureStatus::Bad => 'B',
+            SignatureStatus::Unverifiable => 'E',
+            SignatureStatus::Unsigned => 'N',
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            SignatureStatus::Good => "good signature",
+            SignatureStatus::UnknownValidity => "good signature by a key of unknown validity",
+            SignatureStatus::Expired => "good signature that has expired",
+            SignatureStatus::ExpiredKey => "good signature by an expired key",
+            SignatureStatus::RevokedKey => "good signature by a revoked key",
+            SignatureStatus::Bad => "bad signature",
+            SignatureStatus::Unverifiable => "signature cannot be checked",
+            SignatureStatus::Unsigned => "not signed",
+        }
+    }
+}
+
+/// Signature of a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub commit: Ref,
+    pub status: SignatureStatus,
+
+    /// Name of the signer, e.g. "Jane Doe <jane@example.com>". Empty if
+    /// unknown.
+    pub signer: String,
+
+    /// Key that was used for signing and its primary key fingerprint. Empty
+    /// if unknown.
+    pub key: String,
+    pub fingerprint: String,
+}
+impl Signature {
+    const FORMAT: &str = "%H%x00%G?%x00%GS%x00%GK%x00%GF";
+
+    fn parse(line: &[u8]) -> Result<Self> {
+        let fields: Vec<_> = line.split(|&ch| ch == b'\0').collect();
+        let [commit, status, signer, key, fingerprint] = fields[..] else {
+            Err(format!("bad signature line\n{}", String::from_utf8_lossy(line)))?
+        };
+        let status = match status {
+            [code] => SignatureStatus::parse(*code)?,
+            _ => Err(format!("bad signature status\n{}", String::from_utf8_lossy(status)))?,
+        };
+        Ok(Self {
+            commit: Ref::new(String::from_utf8(commit.into())?),
+            status,
+            signer: String::from_utf8_lossy(signer).into(),
+            key: String::from_utf8_lossy(key).into(),
+            fingerprint: String::from_utf8_lossy(fingerprint).into(),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LogEntry {
     pub commit: Ref,
@@ -826,6 +1402,59 @@ impl RangeDiff {
 mod test {
     use crate::git_core::*;
 
+    #[test]
+    fn merge_tree_parse() -> Result<()> {
+        let clean = MergeTree::parse(b"4b825dc642cb6eb9a060e54bf8d69288fbee4904\n")?;
+        assert_eq!(clean.tree, Ref::new("4b825dc642cb6eb9a060e54bf8d69288fbee4904"));
+        assert!(!clean.has_conflicts());
+
+        let conflicted = MergeTree::parse(b"0123abcd\nsrc/lib.rs\nREADME.md\n")?;
+        assert_eq!(conflicted.tree, Ref::new("0123abcd"));
+        assert_eq!(conflicted.conflicts, ["src/lib.rs", "README.md"]);
+
+        assert!(MergeTree::parse(b"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn blame_parse() -> Result<()> {
+        let output = b"\
+            0123abcd 1 1 2\n\
+            author Jane Doe\n\
+            committer-time 1700000000\n\
+            filename src/lib.rs\n\
+            \tfirst\n\
+            0123abcd 2 2\n\
+            \tsecond\n\
+            89abcdef 1 3 1\n\
+            committer-time 1600000000\n\
+            filename src/lib.rs\n\
+            \tthird\n\
+        ";
+        assert_eq!(parse_blame(output)?, [1700000000, 1700000000, 1600000000]);
+
+        assert!(parse_blame(b"0123abcd 1 1 1\n\tfirst\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn signature_parse() -> Result<()> {
+        let good = Signature::parse(b"0123abcd\0G\0Jane Doe <jane@example.com>\0ABCD\0EF01")?;
+        assert_eq!(good.commit, Ref::new("0123abcd"));
+        assert_eq!(good.status, SignatureStatus::Good);
+        assert_eq!(good.signer, "Jane Doe <jane@example.com>");
+        assert_eq!(good.key, "ABCD");
+        assert_eq!(good.fingerprint, "EF01");
+
+        let unsigned = Signature::parse(b"0123abcd\0N\0\0\0")?;
+        assert_eq!(unsigned.status, SignatureStatus::Unsigned);
+        assert!(unsigned.signer.is_empty());
+
+        assert!(Signature::parse(b"0123abcd\0Q\0\0\0").is_err());
+        assert!(Signature::parse(b"0123abcd\0G").is_err());
+        Ok(())
+    }
+
     #[test]
     fn range_diff_basic() -> Result<()> {
         let range_diff_text = "\
@@ -878,6 +1507,49 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn url_parse() -> Result<()> {
+        let url = Url::parse("git@github.com:owner/repo.git\n")?;
+        assert!(matches!(url, Url::Ssh { .. }));
+        assert_eq!(url.hostname(), Some("github.com"));
+        assert_eq!(url.github_path(), Some(("owner", "repo")));
+
+        let url = Url::parse("https://github.com/owner/repo/")?;
+        assert!(matches!(url, Url::Url(_)));
+        assert_eq!(url.hostname(), Some("github.com"));
+        assert_eq!(url.github_path(), Some(("owner", "repo")));
+
+        let url = Url::parse("ssh://git@ghe.example:2222/owner/repo.git")?;
+        assert_eq!(url.hostname(), Some("ghe.example"));
+        assert_eq!(url.github_path(), Some(("owner", "repo")));
+
+        let url = Url::parse("gh:/owner/repo")?;
+        assert_eq!(url.hostname(), Some("gh"));
+        assert_eq!(url.github_path(), Some(("owner", "repo")));
+
+        assert!(Url::parse("https://github.com/owner/group/repo")?
+            .github_path()
+            .is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn change_ref_names() {
+        assert_eq!(
+            ChangeRef::new(ChangeNamespace::GitHub, 42, ChangeRefKind::Head).ref_name(),
+            "refs/pull/42/head"
+        );
+        assert_eq!(
+            ChangeRef::new(ChangeNamespace::GitHub, 42, ChangeRefKind::Merge).ref_name(),
+            "refs/pull/42/merge"
+        );
+        assert_eq!(
+            ChangeRef::new(ChangeNamespace::GitLab, 7, ChangeRefKind::Head).ref_name(),
+            "refs/merge-requests/7/head"
+        );
+    }
+
     #[test]
     fn range_diff_long() -> Result<()> {
         // With 10 or more commits, the number of spaces changes due to the
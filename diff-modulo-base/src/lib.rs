@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT
 
+pub mod api;
 pub mod cli;
 pub mod diff;
 pub mod diff_color;
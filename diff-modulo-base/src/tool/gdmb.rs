@@ -9,11 +9,20 @@ use diff::render::ChunkWriter;
 use git_core::{RangeDiffWriter, Ref};
 use utils::Result;
 
-#[derive(Parser, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Parser, Debug, Default, Clone, PartialEq, Eq)]
 pub struct GitDiffModuloBaseOptions {
     /// Combine the diff of all commits in a range, instead of showing per-commit diffs.
     #[clap(long)]
     pub combined: bool,
+
+    /// Prefer to keep lines that start with this text unchanged, if they occur
+    /// only once on each side (like `git diff --anchored`). May be repeated.
+    #[clap(long, value_name = "TEXT")]
+    pub anchored: Vec<String>,
+
+    /// The algorithm used to reduce blocks of changed lines.
+    #[clap(long, value_enum, default_value_t = Default::default())]
+    pub diff_algorithm: diff::DiffAlgorithm,
 }
 
 #[derive(Parser, Debug, Clone, PartialEq, Eq)]
@@ -96,7 +105,16 @@ pub fn git_diff_modulo_base(
     match (old, new) {
         (old @ RevSpec::Range(_, _), new @ RevSpec::Range(_, _)) => {
             if args.options.combined {
-                git::diff_ranges_full(&repo, ep, old.to_range(), new.to_range(), writer)?;
+                let options = &args.options;
+                git::diff_ranges_full(
+                    &repo,
+                    ep,
+                    old.to_range(),
+                    new.to_range(),
+                    options.diff_algorithm,
+                    &options.anchored,
+                    writer,
+                )?;
             } else {
                 let range_diff = repo.range_diff(ep, old.to_range(), new.to_range())?;
 
@@ -108,13 +126,23 @@ pub fn git_diff_modulo_base(
                     writer.push_range_diff_match(rd_match);
 
                     if changed {
-                        git::diff_optional_commits(&repo, ep, old, new, writer)?;
+                        git::diff_optional_commits(
+                            &repo,
+                            ep,
+                            old,
+                            new,
+                            args.options.diff_algorithm,
+                            &args.options.anchored,
+                            writer,
+                        )?;
                     }
                 }
             }
         }
         (RevSpec::Commit(old), RevSpec::Commit(new)) => {
-            git::diff_commits(&repo, ep, &old, &new, writer)?;
+            let options = &args.options;
+            let (algorithm, anchored) = (options.diff_algorithm, &options.anchored);
+            git::diff_commits(&repo, ep, &old, &new, algorithm, anchored, writer)?;
         }
         _ => return Err("old and new must either both refer to commits or both to ranges".into()),
     };
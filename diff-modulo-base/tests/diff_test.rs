@@ -23,7 +23,6 @@ fn diff_test() -> Result<()> {
         let new_path = path.join("new");
         let old_body = buffer.insert(&utils::read_bytes(&old_path)?)?;
         let new_body = buffer.insert(&utils::read_bytes(&new_path)?)?;
-        let expected = utils::read_bytes(path.join("expected.diff"))?;
 
         let old_path: std::path::PathBuf = old_path.components().skip(3).collect();
         let new_path: std::path::PathBuf = new_path.components().skip(3).collect();
@@ -31,27 +30,38 @@ fn diff_test() -> Result<()> {
         let old_path = buffer.insert(old_path.to_string_lossy().as_bytes())?;
         let new_path = buffer.insert(new_path.to_string_lossy().as_bytes())?;
 
-        let options = diff::DiffOptions {
-            strip_path_components: 0,
-            ..Default::default()
-        };
-
-        let file = diff::diff_file(
-            &buffer,
-            old_path,
-            new_path,
-            old_body,
-            new_body,
-            &options,
-            diff::DiffAlgorithm::default(),
-        )?;
-        let mut diff = diff::Diff::new(options);
-        diff.add_file(file);
-
-        let mut writer = diff::render::ChunkByteBufferWriter::new();
-        diff.render(&buffer, &mut writer);
-
-        assert_eq!(String::from_utf8(expected)?, String::from_utf8(writer.out)?);
+        for (indent_heuristic, expected_name) in
+            [(false, "expected.diff"), (true, "expected-indent-heuristic.diff")]
+        {
+            let expected_path = path.join(expected_name);
+            if indent_heuristic && !expected_path.exists() {
+                continue;
+            }
+            let expected = utils::read_bytes(expected_path)?;
+
+            let options = diff::DiffOptions {
+                strip_path_components: 0,
+                indent_heuristic,
+                ..Default::default()
+            };
+
+            let file = diff::diff_file(
+                &buffer,
+                old_path,
+                new_path,
+                old_body,
+                new_body,
+                &options,
+                diff::DiffAlgorithm::default(),
+            )?;
+            let mut diff = diff::Diff::new(options);
+            diff.add_file(file);
+
+            let mut writer = diff::render::ChunkByteBufferWriter::new();
+            diff.render(&buffer, &mut writer);
+
+            assert_eq!(String::from_utf8(expected)?, String::from_utf8(writer.out)?);
+        }
     }
 
     Ok(())
@@ -1,6 +1,7 @@
 #!/bin/sh
 #
-# Update `expected.diff` files.
+# Update `expected.diff` files, and `expected-indent-heuristic.diff` files
+# where they exist.
 
 path=$(dirname $0)
 echo "Updating expected.diff files in $path ..."
@@ -12,5 +13,11 @@ for entry in *; do
             $entry/old \
             $entry/new \
             > $entry/expected.diff
+        if [ -f "$entry/expected-indent-heuristic.diff" ]; then
+            cargo run --example devtool -- diff --indent-heuristic \
+                $entry/old \
+                $entry/new \
+                > $entry/expected-indent-heuristic.diff
+        fi
     fi
 done
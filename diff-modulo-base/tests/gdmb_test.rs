@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MIT
 
+#[cfg(unix)]
 use std::os::unix::ffi::OsStringExt;
 
 use clap::Parser;
@@ -33,7 +34,7 @@ fn dmb_test() -> Result<()> {
                         if slice.is_empty() {
                             None
                         } else {
-                            Some(std::ffi::OsString::from_vec(slice.to_vec()))
+                            Some(os_string(slice))
                         }
                     }),
             )
@@ -61,3 +62,14 @@ fn dmb_test() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(unix)]
+fn os_string(bytes: &[u8]) -> std::ffi::OsString {
+    std::ffi::OsString::from_vec(bytes.to_vec())
+}
+
+/// Elsewhere, arguments can't be arbitrary bytes.
+#[cfg(not(unix))]
+fn os_string(bytes: &[u8]) -> std::ffi::OsString {
+    std::ffi::OsString::from(String::from_utf8_lossy(bytes).into_owned())
+}
@@ -0,0 +1,64 @@
+# Messages of the forge TUIs.
+#
+# Translations go into i18n/<language>.ftl in the config directory and only
+# need to contain the messages that they translate.
+
+## Commands entered after `:`
+
+cmd-log = Toggle the debug log
+cmd-edits = Toggle the edit queue
+cmd-traffic = Toggle the list of recent API requests
+cmd-accounts = Toggle the list of accounts and the status of their tokens
+cmd-teams = Toggle the list of organizations, teams, and their review requests
+cmd-releases = Browse the tags and releases of the selected repository
+cmd-projects = Triage the milestones and project boards of the selected repository
+cmd-stats = Show who contributed to and reviewed the selected repository recently
+cmd-mine = Respond to reviews of your own pull requests
+cmd-todo = Toggle the list of follow-ups from review discussions
+cmd-columns = Choose the columns of the inbox or of lists of pull requests
+cmd-trace = Trace the next frame
+cmd-reqlog = Toggle logging of API requests to a file
+cmd-triage = Toggle triage mode
+cmd-unsnooze = Unsnooze all notifications
+cmd-unmute = Reset the priority of a repository
+cmd-priority = Set the priority of the selected repository
+cmd-quit = Quit
+cmd-custom = Custom command from settings.toml
+
+## Key bindings
+
+key-cancel = Cancel
+key-mark-done = Mark as done
+key-unsubscribe = Unsubscribe
+key-triage = Enter / leave triage mode
+key-snooze = Snooze for a day
+key-snooze-until = Snooze until...
+key-mute = Mute the repository
+key-focus-review = Focus the review
+key-open-browser = Open in the web browser
+key-open-notification = Open the notification
+key-open-pull = Open the pull request
+key-undo = Undo the most recent edit
+key-refresh = Refresh everything from the server
+key-quit = Quit
+
+## Help lines
+
+help-inbox = / to search, t to triage, ? for help, q to quit
+help-triage = TRIAGE: e done, s/S snooze, r review, m mute repository, M unsubscribe, t to leave
+snooze-until-label = Snooze until (e.g. 3d, next mon, 2024-12-24):
+
+## Errors
+
+error-no-selection = No notification selected
+error-no-repository = No repository selected
+error-unknown-command = Unknown command: { $command }
+error-usage-unmute = Usage: :unmute owner/name
+error-unknown-url = Unknown URL
+error-mark-done = Failed to mark as done: { $error }
+error-unsubscribe = Failed to unsubscribe: { $error }
+error-snooze = Failed to snooze: { $error }
+error-mute = Failed to mute: { $error }
+error-open = Failed to open { $title }: { $error }
+error-token = { $host }: { $problem } (see :accounts)
+error-undo = Failed to undo: { $error }
@@ -0,0 +1,999 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The dashboard of notifications and pull requests of all hosts.
+
+use std::{collections::HashSet, path::Path, sync::Arc, time::Duration};
+
+use diff_modulo_base::*;
+use log::{debug, error, info, trace, warn, LevelFilter};
+use ratatui::widgets::Block;
+use serde_json::{json, Value};
+use utils::Result;
+use vctools_utils::time;
+use vctuik::{
+    date_picker::{Clock, DatePicker},
+    event::{Event, KeyCode, KeyEventKind, MouseEventKind},
+    label::add_label,
+    section::with_section,
+    signals::{self, MergeWakeupWait},
+    state::Builder,
+    terminal::{HyperlinkBackend, Terminal},
+    theme,
+};
+
+use crate::{
+    browser, get_project_dirs,
+    github::{self, fixtures::FixtureMode, request_log::RequestLog, token::TokenCheck},
+    gitservice::GitService,
+    hooks,
+    ipc::{self, CommandServer, ForgeMethods, RpcError},
+    load_config,
+    logview::add_log_view,
+    tr,
+    metrics::MetricsExporter,
+    persistence::{self, Journal, Session},
+    settings::{Priority, Settings, TableView},
+    soak::{SoakInput, SoakMonitor},
+    todos::TodoState,
+    triage::{self, TriageState},
+    tui::{
+        actions, avatar,
+        navigation::{Breadcrumbs, Location, NavigationStack},
+        plugin::{PaneContext, PaneRegistry},
+        Accounts, ColumnsAction, ColumnsDialog, ColumnsDraft, CommandBar, ContributorStats,
+        EditQueue, ForgeStatus, Inbox, InboxResult, InboxState, MyPulls, Projects, Releases,
+        RepositoryInsights, Review, ReviewThreads, Teams, TeamsResult, Todos, TodosResult,
+        TrafficInspector,
+    },
+    ApiRepository, CompletePullRequest,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    /// Do not access the GitHub API.
+    #[clap(long)]
+    github_offline: bool,
+
+    #[clap(long)]
+    log_file: Option<String>,
+
+    /// Record all API responses into this directory.
+    #[clap(long, conflicts_with = "replay")]
+    record: Option<std::path::PathBuf>,
+
+    /// Serve API responses from a directory made with --record instead of
+    /// accessing the network. Edits are not committed.
+    #[clap(long)]
+    replay: Option<std::path::PathBuf>,
+
+    /// Record the session, i.e. what is shown and the keys that are pressed,
+    /// to this file in the asciinema v2 format.
+    #[clap(long)]
+    record_session: Option<std::path::PathBuf>,
+
+    /// Replay the key presses of a session made with --record-session.
+    #[clap(long)]
+    replay_session: Option<std::path::PathBuf>,
+
+    /// Periodically write internal metrics to this file, in the Prometheus
+    /// text format.
+    #[clap(long)]
+    metrics_file: Option<std::path::PathBuf>,
+
+    /// Run a soak test for this many minutes: press random keys in a
+    /// headless terminal and fail if the use of memory or other resources
+    /// keeps growing.
+    #[clap(long, requires = "replay")]
+    soak: Option<u64>,
+
+    /// Log API traffic to this file, with credentials redacted. Logging can
+    /// also be toggled at runtime with `:reqlog`.
+    #[clap(long)]
+    request_log: Option<std::path::PathBuf>,
+
+    /// Accept JSON-RPC requests on this Unix domain socket.
+    #[clap(long)]
+    listen: Option<std::path::PathBuf>,
+    /// Start in the screen reader mode (toggle with F2).
+    #[clap(long)]
+    screen_reader: bool,
+}
+
+/// Size of the headless terminal of soak tests.
+const SOAK_WIDTH: u16 = 120;
+const SOAK_HEIGHT: u16 = 40;
+
+/// Commands that can be entered after `:` or sent via the `run` method.
+/// Built-in commands and the message IDs of their help texts.
+const COMMANDS: &[(&str, &str)] = &[
+    ("log", "cmd-log"),
+    ("edits", "cmd-edits"),
+    ("traffic", "cmd-traffic"),
+    ("accounts", "cmd-accounts"),
+    ("teams", "cmd-teams"),
+    ("releases", "cmd-releases"),
+    ("projects", "cmd-projects"),
+    ("stats", "cmd-stats"),
+    ("mine", "cmd-mine"),
+    ("todo", "cmd-todo"),
+    ("columns [inbox|pulls]", "cmd-columns"),
+    ("trace", "cmd-trace"),
+    ("reqlog [host]", "cmd-reqlog"),
+    ("triage", "cmd-triage"),
+    ("unsnooze", "cmd-unsnooze"),
+    ("unmute owner/name", "cmd-unmute"),
+    ("priority <priority>", "cmd-priority"),
+    ("quit", "cmd-quit"),
+];
+
+/// Run the inbox until the user quits.
+pub fn run(args: Args) -> Result<()> {
+    let mut dmb_options = tool::GitDiffModuloBaseOptions {
+        combined: true,
+        ..Default::default()
+    };
+
+    if std::env::var("RUST_LOG").is_ok() {
+        env_logger::builder()
+            .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
+            .init();
+    } else {
+        tui_logger::init_logger(LevelFilter::Debug)?;
+        tui_logger::set_default_level(LevelFilter::Debug);
+        if let Some(log_file) = &args.log_file {
+            tui_logger::set_log_file(log_file)?;
+        }
+    }
+    debug!("Starting up");
+    trace!("test trace");
+    info!("test info");
+    warn!("test warn");
+    error!("test error");
+
+    let (refresh_signal, refresh_wait) = signals::make_merge_wakeup();
+
+    let mut connections = github::connections::Connections::new(
+        load_config("github.toml")?,
+        args.github_offline,
+        Some(get_project_dirs().cache_dir().into()),
+        Some(get_project_dirs().data_dir().into()),
+    );
+    if let Some(dir) = args.record.clone() {
+        connections.set_fixtures(FixtureMode::Record(dir));
+    } else if let Some(dir) = args.replay.clone() {
+        connections.set_fixtures(FixtureMode::Replay(dir));
+    }
+    if !args.github_offline && args.replay.is_none() {
+        avatar::enable(connections.hosts());
+    }
+
+    let request_log_path = args
+        .request_log
+        .clone()
+        .unwrap_or_else(|| get_project_dirs().cache_dir().join("requests.log"));
+    let mut request_log = None;
+    if args.request_log.is_some() {
+        toggle_request_log(&mut connections, &mut request_log, &request_log_path, "")?;
+    }
+
+    let mut git_service = GitService::new(
+        &load_config("repositories.toml")?,
+        connections.hosts(),
+        refresh_signal.clone(),
+    );
+
+    let server = match args.listen.clone() {
+        Some(path) => Some(CommandServer::listen(path, refresh_signal.clone())?),
+        None => None,
+    };
+    let mut forge_methods = ForgeMethods::default();
+
+    let mut metrics = args.metrics_file.clone().map(MetricsExporter::new);
+    let soak_duration = args.soak.map(|minutes| Duration::from_secs(minutes * 60));
+    let mut soak = soak_duration.map(SoakMonitor::new);
+    let mut running = true;
+    let mut show_debug_log = false;
+    let mut show_edit_queue = false;
+    let mut show_traffic = false;
+    let mut show_accounts = false;
+    let mut show_teams = false;
+    let mut show_todos = false;
+    let mut search: Option<regex::Regex> = None;
+    let mut error: Option<String> = None;
+    let mut command: Option<String> = None;
+    let mut trace_next_frame = false;
+    let mut triage_mode = false;
+    // Text of the prompt for the end of a snooze, while it is shown.
+    let mut snooze_until: Option<String> = None;
+    let mut columns_draft: Option<ColumnsDraft> = None;
+    let mut navigation = NavigationStack::new(Location::Inbox);
+
+    // The session is journaled for recovery after a crash, except in soak
+    // tests.
+    let mut journal = None;
+    if args.soak.is_none() {
+        let (new_journal, recovered) =
+            Journal::<Session>::open(get_project_dirs().data_dir(), "git-inbox");
+        if let Some((session, saved_at)) = recovered {
+            info!(
+                "Recovered the session of {}, which did not end cleanly",
+                time::format_iso(saved_at)
+            );
+            for location in session.locations.into_iter().skip(1) {
+                navigation.push(location);
+            }
+            persistence::restore_drafts(session.drafts);
+        }
+        journal = Some(new_journal);
+    }
+    let mut inbox_state = InboxState::default();
+    let mut panes = PaneRegistry::from_registered();
+    let mut triage_state =
+        TriageState::load(Some(get_project_dirs().data_dir().join("triage.json")));
+    let mut todo_state = TodoState::load(Some(get_project_dirs().data_dir().join("todos.json")));
+
+    // Hosts whose token has been checked and any problems reported.
+    let mut token_reported: HashSet<String> = HashSet::new();
+
+    // Batches of edits that are being committed, as (host, edit IDs, total)
+    let mut batches: Vec<(String, Vec<u64>, usize)> = Vec::new();
+
+    let mut frame = |builder: &mut Builder| -> Result<bool> {
+        builder.set_trace_frame(trace_next_frame);
+        trace_next_frame = false;
+
+        debug!("Start Frame");
+        connections.start_frame(Some(builder.start_frame() + Duration::from_millis(50)));
+        git_service.start_frame(Duration::from_millis(100));
+
+        if command.is_none() {
+            if match builder.peek_event() {
+                Some(Event::Key(ev)) if ev.kind == KeyEventKind::Press => true,
+                Some(Event::Mouse(ev)) if ev.kind != MouseEventKind::Moved => true,
+                _ => false,
+            } {
+                error = None;
+            }
+        }
+
+        // Report problems with tokens once their check completes.
+        for (host, client) in connections.all_clients() {
+            let Ok(client) = client else { continue };
+            if token_reported.contains(&host.host) {
+                continue;
+            }
+            let problem = match client.borrow().token_check() {
+                TokenCheck::Pending => continue,
+                TokenCheck::Unchecked => None,
+                TokenCheck::Failed(err) => Some(err),
+                TokenCheck::Valid(status) => {
+                    let warnings = status.warnings(&host.user, chrono::Utc::now());
+                    (!warnings.is_empty()).then(|| warnings.join("; "))
+                }
+            };
+            if let Some(problem) = problem {
+                error = Some(tr!("error-token", host = host.host, problem = problem));
+            }
+            token_reported.insert(host.host.clone());
+        }
+
+        // Clear the window
+        let frame_area = builder.frame().area();
+        let block = Block::new().style(builder.theme().pane_background);
+        builder.frame().render_widget(block, frame_area);
+
+        Breadcrumbs::new(&navigation).build(builder);
+
+        let location = navigation.current().clone();
+        // Pull request selected in "My Pull Requests"
+        let mut authored_pull = None;
+        let inbox = match &location {
+            Location::Inbox | Location::Repository(_) => {
+                let inbox = with_section(builder, "Inbox", |builder| {
+                    let mut inbox = Inbox::new().triage(&triage_state).state(&mut inbox_state);
+                    if let Location::Repository(repo) = &location {
+                        inbox = inbox.repository(repo);
+                    }
+                    inbox.build(builder, &mut connections)
+                })
+                .unwrap_or_default();
+
+                with_section(builder, "Notification", |builder| {
+                    let Some((host, thread)) = inbox.selection.clone() else {
+                        if let Some(repository) = &inbox.repository {
+                            RepositoryInsights::new(repository).build(builder, &mut connections);
+                            return;
+                        }
+                        add_label(builder, "(no notification selected)");
+                        builder.add_slack();
+                        return;
+                    };
+
+                    let url =
+                        thread.subject.url.as_ref().map(String::as_str).unwrap_or("<unknown>");
+                    let Some(id) = thread.pull_number() else {
+                        add_label(builder, format!("Notification: {}", url));
+                        add_label(builder, "(unsupported)");
+                        builder.add_slack();
+                        return;
+                    };
+
+                    let api_repo = ApiRepository::new(
+                        host,
+                        thread.repository.owner.login,
+                        thread.repository.name,
+                    );
+                    let pr = match CompletePullRequest::from_api(api_repo, id, &git_service) {
+                        Err(err) => {
+                            add_label(builder, format!("Notification: {}", url));
+                            add_label(builder, format!("{}", err));
+                            builder.add_slack();
+                            return;
+                        }
+                        Ok(pr) => pr,
+                    };
+                    Review::new(&git_service, &pr)
+                        .maybe_search(search.as_ref())
+                        .options(&mut dmb_options)
+                        .build(builder, &mut connections);
+                });
+
+                inbox
+            }
+            Location::PullRequest(repo, id) => {
+                with_section(builder, "Pull Request", |builder| {
+                    match CompletePullRequest::from_api(repo.clone(), *id, &git_service) {
+                        Ok(pr) => {
+                            Review::new(&git_service, &pr)
+                                .maybe_search(search.as_ref())
+                                .options(&mut dmb_options)
+                                .build(builder, &mut connections);
+                        }
+                        Err(err) => {
+                            add_label(builder, format!("{}", err));
+                            builder.add_slack();
+                        }
+                    }
+                });
+                InboxResult::default()
+            }
+            Location::Releases(repo) => {
+                with_section(builder, "Releases", |builder| {
+                    Releases::new(repo, &git_service).build(builder, &mut connections);
+                });
+                InboxResult::default()
+            }
+            Location::Projects(repo) => {
+                with_section(builder, "Milestones and Projects", |builder| {
+                    Projects::new(repo).build(builder, &mut connections);
+                });
+                InboxResult::default()
+            }
+            Location::Stats(repo) => {
+                with_section(builder, "Statistics", |builder| {
+                    ContributorStats::new(repo).build(builder, &mut connections);
+                });
+                InboxResult::default()
+            }
+            Location::Authored => {
+                let selection = with_section(builder, "My Pull Requests", |builder| {
+                    MyPulls::new().build(builder, &mut connections)
+                })
+                .flatten();
+                authored_pull = selection.clone();
+                with_section(builder, "Review Threads", |builder| {
+                    let Some((repo, id)) = selection else {
+                        add_label(builder, "(no pull request selected)");
+                        builder.add_slack();
+                        return;
+                    };
+                    match CompletePullRequest::from_api(repo, id, &git_service) {
+                        Ok(pr) => ReviewThreads::new(&pr).build(builder, &mut connections),
+                        Err(err) => {
+                            add_label(builder, format!("{}", err));
+                            builder.add_slack();
+                        }
+                    }
+                });
+                InboxResult::default()
+            }
+            Location::Thread { title, .. } => {
+                with_section(builder, "Notification", |builder| {
+                    add_label(builder, title.as_str());
+                    add_label(builder, "(only pull requests can be shown)");
+                    builder.add_slack();
+                });
+                InboxResult::default()
+            }
+        };
+
+        panes.build(
+            builder,
+            &mut PaneContext {
+                connections: &mut connections,
+                git_service: &git_service,
+                location: &location,
+                selection: inbox.selection.as_ref(),
+            },
+        );
+
+        let have_edit_conflicts = connections.all_clients().any(|(_, client)| {
+            client.is_ok_and(|client| client.borrow().has_edit_conflicts())
+        });
+        if show_edit_queue || have_edit_conflicts {
+            with_section(builder, "Queued Edits", |builder| {
+                let result = EditQueue::new().build(builder, &mut connections);
+                if result.error.is_some() {
+                    error = result.error;
+                }
+            });
+        }
+
+        let teams = if show_teams {
+            with_section(builder, "Teams", |builder| Teams::new().build(builder, &mut connections))
+                .unwrap_or_default()
+        } else {
+            TeamsResult::default()
+        };
+
+        let todos = if show_todos {
+            let shown_pull = match &location {
+                Location::PullRequest(repo, id) => Some((repo.clone(), *id)),
+                Location::Authored => authored_pull,
+                _ => inbox.selection.as_ref().and_then(|(host, thread)| {
+                    let repo = ApiRepository::new(
+                        host.clone(),
+                        thread.repository.owner.login.clone(),
+                        thread.repository.name.clone(),
+                    );
+                    Some((repo, thread.pull_number()?))
+                }),
+            };
+            with_section(builder, "TODO", |builder| {
+                Todos::new(&mut todo_state)
+                    .pull(shown_pull.as_ref().map(|(repo, id)| (repo, *id)))
+                    .build(builder, &mut connections)
+            })
+            .unwrap_or_default()
+        } else {
+            TodosResult::default()
+        };
+        if todos.error.is_some() {
+            error = todos.error;
+        }
+
+        if show_accounts {
+            with_section(builder, "Accounts", |builder| {
+                Accounts::new().build(builder, &mut connections);
+            });
+        }
+
+        if show_traffic {
+            with_section(builder, "HTTP Traffic", |builder| {
+                TrafficInspector::new().build(builder, &mut connections);
+            });
+        }
+
+        if show_debug_log {
+            with_section(builder, "Debug Log", |builder| {
+                add_log_view(builder);
+            });
+        }
+
+        batches.retain_mut(|(host, ids, _)| {
+            let Ok(client) = connections.client(host.as_str()) else { return false };
+            let queued = client.borrow().queued_edits();
+            ids.retain(|id| queued.iter().any(|queued| queued.id == *id));
+            !ids.is_empty()
+        });
+        let progress = (!batches.is_empty()).then(|| {
+            let total: usize = batches.iter().map(|(_, _, total)| total).sum();
+            let pending: usize = batches.iter().map(|(_, ids, _)| ids.len()).sum();
+            format!("Committing edits: {}/{} done", total - pending, total)
+        });
+
+        let mut status = ForgeStatus::new();
+        if let Location::PullRequest(repo, id) = &location {
+            status = status.host(&repo.host).repository(&repo.owner, &repo.name).pull(*id);
+        } else if let Some((host, thread)) = &inbox.selection {
+            status = status
+                .host(host)
+                .repository(&thread.repository.owner.login, &thread.repository.name);
+            if let Some(pull) = thread.pull_number() {
+                status = status.pull(pull);
+            }
+            if let Some(updated_at) = thread.updated_at() {
+                status = status.updated(updated_at.timestamp());
+            }
+        }
+        status.build(builder, &mut connections);
+
+        let mut snooze_picked = None;
+        if let Some(text) = snooze_until.as_mut() {
+            let clock = Clock {
+                now: chrono::Utc::now().timestamp(),
+                utc_offset: chrono::Local::now().offset().local_minus_utc(),
+            };
+            let state_id = builder.add_state_id("snooze");
+            builder
+                .nest()
+                .modal(state_id, true)
+                .theme_context(theme::Context::Modal)
+                .build(|builder| {
+                    if builder.on_key_binding(KeyCode::Esc, tr!("key-cancel")) {
+                        snooze_picked = Some(None);
+                    } else if let Some(until) = DatePicker::new("until")
+                        .label(tr!("snooze-until-label"))
+                        .clock(clock)
+                        .build(builder, text)
+                    {
+                        snooze_picked = Some(Some(until));
+                    }
+                });
+        }
+        if let Some(picked) = snooze_picked {
+            snooze_until = None;
+            if let Some(until) = picked {
+                for (host, notification) in inbox.targets() {
+                    if let Err(err) = triage_state.snooze_until(&host, &notification, until) {
+                        error = Some(tr!("error-snooze", error = err));
+                    }
+                }
+            }
+            builder.need_refresh();
+        }
+
+        if let Some(draft) = &mut columns_draft {
+            match ColumnsDialog::new().build(builder, draft) {
+                ColumnsAction::None => {}
+                ColumnsAction::Cancel => columns_draft = None,
+                ColumnsAction::Save => {
+                    if let Err(err) = Settings::set_columns(draft.view, &draft.columns()) {
+                        error = Some(err.to_string());
+                    }
+                    columns_draft = None;
+                }
+            }
+            builder.need_refresh();
+        }
+
+        let help = if triage_mode {
+            tr!("help-triage")
+        } else {
+            tr!("help-inbox")
+        };
+        let cmd = CommandBar::new(&mut command, &mut search, &mut error)
+            .help(&help)
+            .progress(progress.as_deref())
+            .build(builder);
+
+        // Commands from the command line and the command socket, with the
+        // request to reply to for the latter.
+        let mut commands: Vec<(String, Option<ipc::Request>)> = Vec::new();
+        commands.extend(cmd.map(|cmd| (cmd, None)));
+
+        let requests = server.iter().flat_map(|server| server.take_requests()).collect();
+        for request in forge_methods.handle(requests, &mut connections) {
+            match request.method.as_str() {
+                "run" => match request.param_str("command") {
+                    Ok(cmd) => {
+                        let cmd = cmd.trim_start_matches(':').to_string();
+                        commands.push((cmd, Some(request)));
+                    }
+                    Err(err) => request.reply(Err(err)),
+                },
+                "commands" => {
+                    let list: Vec<_> = COMMANDS
+                        .iter()
+                        .map(|(name, help)| (*name, tr!(help)))
+                        .chain(panes.commands().map(|(name, help)| (*name, help.to_string())))
+                        .chain(Settings::get().commands.keys().map(|name| {
+                            (name.as_str(), tr!("cmd-custom"))
+                        }))
+                        .map(|(name, help)| json!({"name": name, "help": help}))
+                        .collect();
+                    request.reply(Ok(list.into()));
+                }
+                "selection" => request.reply(Ok(selection_json(&navigation, &inbox))),
+                method => {
+                    let err = RpcError::method_not_found(method);
+                    request.reply(Err(err));
+                }
+            }
+        }
+
+        for (cmd, request) in commands {
+            let result: Result<()> = if cmd == "log" {
+                show_debug_log = !show_debug_log;
+                Ok(())
+            } else if cmd == "edits" {
+                show_edit_queue = !show_edit_queue;
+                Ok(())
+            } else if cmd == "traffic" {
+                show_traffic = !show_traffic;
+                Ok(())
+            } else if cmd == "accounts" {
+                show_accounts = !show_accounts;
+                Ok(())
+            } else if cmd == "teams" {
+                show_teams = !show_teams;
+                Ok(())
+            } else if cmd == "todo" {
+                show_todos = !show_todos;
+                Ok(())
+            } else if cmd == "releases" || cmd == "projects" || cmd == "stats" {
+                let repo = match &location {
+                    Location::Repository(repo) | Location::PullRequest(repo, _) => {
+                        Some(repo.clone())
+                    }
+                    _ => inbox.repository.clone().or_else(|| {
+                        inbox.selection.as_ref().map(|(host, thread)| {
+                            ApiRepository::new(
+                                host.clone(),
+                                thread.repository.owner.login.clone(),
+                                thread.repository.name.clone(),
+                            )
+                        })
+                    }),
+                };
+                match repo {
+                    Some(repo) => {
+                        navigation.push(match cmd.as_str() {
+                            "releases" => Location::Releases(repo),
+                            "projects" => Location::Projects(repo),
+                            _ => Location::Stats(repo),
+                        });
+                        Ok(())
+                    }
+                    None => Err(tr!("error-no-repository").into()),
+                }
+            } else if cmd == "mine" {
+                navigation.push(Location::Authored);
+                Ok(())
+            } else if let Some(view) =
+                cmd.strip_prefix("columns").filter(|rest| rest.is_empty() || rest.starts_with(' '))
+            {
+                let view = match view.trim() {
+                    "" if location == Location::Authored => Ok(TableView::Pulls),
+                    "" => Ok(TableView::Inbox),
+                    view => view.parse(),
+                };
+                view.map(|view| {
+                    let current = Settings::get().columns.get(view).to_vec();
+                    columns_draft = Some(ColumnsDraft::new(view, &current));
+                })
+            } else if cmd == "trace" {
+                trace_next_frame = true;
+                Ok(())
+            } else if let Some(host) =
+                cmd.strip_prefix("reqlog").filter(|rest| rest.is_empty() || rest.starts_with(' '))
+            {
+                let host = host.trim();
+                toggle_request_log(&mut connections, &mut request_log, &request_log_path, host)
+            } else if cmd == "triage" {
+                triage_mode = !triage_mode;
+                Ok(())
+            } else if cmd == "unsnooze" {
+                triage_state.unsnooze_all()
+            } else if let Some(repo) = cmd.strip_prefix("unmute ") {
+                repo.trim()
+                    .split_once('/')
+                    .ok_or_else(|| tr!("error-usage-unmute").into())
+                    .and_then(|(owner, name)| Settings::set_priority(owner, name, Priority::Normal))
+            } else if let Some(priority) = cmd.strip_prefix("priority ") {
+                priority.trim().parse().and_then(|priority| {
+                    let Some((_, notification)) = &inbox.selection else {
+                        Err(tr!("error-no-selection"))?
+                    };
+                    let repo = &notification.repository;
+                    Settings::set_priority(&repo.owner.login, &repo.name, priority)
+                })
+            } else if cmd == "q" || cmd == "quit" {
+                running = false;
+                Ok(())
+            } else if hooks::run_custom_command(
+                &cmd,
+                selection_json(&navigation, &inbox),
+                args.listen.as_deref(),
+            ) {
+                Ok(())
+            } else {
+                let mut ctx = PaneContext {
+                    connections: &mut connections,
+                    git_service: &git_service,
+                    location: &location,
+                    selection: inbox.selection.as_ref(),
+                };
+                panes
+                    .handle_command(&cmd, &mut ctx)
+                    .unwrap_or_else(|| Err(tr!("error-unknown-command", command = cmd).into()))
+            };
+            match request {
+                Some(request) => request.reply(result.map(|()| Value::Null).map_err(Into::into)),
+                None => {
+                    if let Err(err) = result {
+                        error = Some(err.to_string());
+                    }
+                }
+            }
+            builder.need_refresh();
+        }
+
+        // Global key bindings
+        {
+            let mark_done = builder.on_key_binding(KeyCode::Char('e'), tr!("key-mark-done"));
+            let unsubscribe = builder.on_key_binding(KeyCode::Char('M'), tr!("key-unsubscribe"));
+            if mark_done || unsubscribe {
+                let targets = inbox.targets();
+                if targets.is_empty() {
+                    error = Some(tr!("error-no-selection"));
+                }
+
+                // Submit the edits for each host as one batch.
+                let mut by_host: Vec<(String, Vec<_>)> = Vec::new();
+                for (host, notification) in targets {
+                    // Don't hide activity on the thread that we haven't seen yet.
+                    let precondition = github::edit::Precondition::UpdatedAt {
+                        url: github::Endpoint::NotificationThread {
+                            id: notification.id.clone(),
+                        }
+                        .key(),
+                        updated_at: notification.updated_at.clone(),
+                    };
+                    let thread_id = notification.id;
+                    let edit = if mark_done {
+                        github::edit::NotificationDone { thread_id }.into()
+                    } else {
+                        github::edit::NotificationUnsubscribe { thread_id }.into()
+                    };
+                    let idx = match by_host.iter().position(|(h, _)| *h == host) {
+                        Some(idx) => idx,
+                        None => {
+                            by_host.push((host, Vec::new()));
+                            by_host.len() - 1
+                        }
+                    };
+                    by_host[idx].1.push((edit, Some(precondition)));
+                }
+
+                let failed = if mark_done { "error-mark-done" } else { "error-unsubscribe" };
+                for (host, edits) in by_host {
+                    if edits.len() == 1 {
+                        let (edit, precondition) = edits.into_iter().next().unwrap();
+                        if let Err(err) = connections.edit(host, edit, precondition) {
+                            error = Some(tr!(failed, error = err));
+                        }
+                        continue;
+                    }
+                    let total = edits.len();
+                    match connections.edit_batch(host.clone(), edits) {
+                        Ok(ids) => batches.push((host, ids, total)),
+                        Err(err) => error = Some(tr!(failed, error = err)),
+                    }
+                }
+                builder.need_refresh();
+            }
+
+            if builder.on_key_binding(KeyCode::Char('t'), tr!("key-triage")) {
+                triage_mode = !triage_mode;
+                builder.need_refresh();
+            } else if triage_mode {
+                // The inbox table selects the next notification when the
+                // current one disappears, so triaging advances automatically.
+                if builder.on_key_binding(KeyCode::Char('s'), tr!("key-snooze")) {
+                    for (host, notification) in inbox.targets() {
+                        let result =
+                            triage_state.snooze(&host, &notification, triage::SNOOZE_DURATION);
+                        if let Err(err) = result {
+                            error = Some(tr!("error-snooze", error = err));
+                        }
+                    }
+                    builder.need_refresh();
+                } else if builder.on_key_binding(KeyCode::Char('S'), tr!("key-snooze-until")) {
+                    snooze_until = Some(String::new());
+                    builder.need_refresh();
+                } else if builder.on_key_binding(KeyCode::Char('m'), tr!("key-mute")) {
+                    if let Some((_, notification)) = &inbox.selection {
+                        let repo = &notification.repository;
+                        let result =
+                            Settings::set_priority(&repo.owner.login, &repo.name, Priority::Muted);
+                        if let Err(err) = result {
+                            error = Some(tr!("error-mute", error = err));
+                        }
+                    }
+                    builder.need_refresh();
+                } else if builder.on_key_binding(KeyCode::Char('r'), tr!("key-focus-review")) {
+                    builder.inject_custom(actions::FocusReview);
+                    builder.need_refresh();
+                }
+            }
+
+            if builder.on_key_binding(KeyCode::Char('o'), tr!("key-open-browser")) {
+                let targets = inbox.targets();
+                if targets.is_empty() {
+                    error = Some(tr!("error-no-selection"));
+                }
+                for (_, notification) in targets {
+                    let result = notification
+                        .html_url()
+                        .ok_or_else(|| tr!("error-unknown-url").into())
+                        .and_then(|url| browser::open_url(&url));
+                    if let Err(err) = result {
+                        let title = &notification.subject.title;
+                        error = Some(tr!("error-open", title = title, error = err));
+                    }
+                }
+            }
+        }
+
+        // Navigation
+        if inbox.has_focus && builder.on_key_binding(KeyCode::Enter, tr!("key-open-notification")) {
+            if let Some((host, thread)) = &inbox.selection {
+                if location == Location::Inbox {
+                    navigation.push(Location::Repository(ApiRepository::new(
+                        host.clone(),
+                        thread.repository.owner.login.clone(),
+                        thread.repository.name.clone(),
+                    )));
+                }
+                navigation.push(Location::for_notification(host, thread));
+                builder.need_refresh();
+            }
+        }
+        if teams.has_focus && builder.on_key_binding(KeyCode::Enter, tr!("key-open-pull")) {
+            if let Some((repo, number)) = teams.selection {
+                navigation.push(Location::PullRequest(repo, number));
+                builder.need_refresh();
+            }
+        }
+        if todos.has_focus && builder.on_key_binding(KeyCode::Enter, tr!("key-open-pull")) {
+            if let Some((repo, number)) = todos.selection {
+                navigation.push(Location::PullRequest(repo, number));
+                builder.need_refresh();
+            }
+        }
+        navigation.handle_back(builder);
+
+        if connections.can_undo() && builder.on_key_binding(KeyCode::Char('u'), tr!("key-undo")) {
+            if let Err(err) = connections.undo() {
+                error = Some(tr!("error-undo", error = err));
+            }
+            builder.need_refresh();
+        }
+
+        if builder.on_key_binding(KeyCode::F(5), tr!("key-refresh")) {
+            connections.refresh();
+            builder.need_refresh();
+        }
+
+        if builder.on_key_binding(KeyCode::Char('q'), tr!("key-quit")) {
+            running = false;
+        }
+
+        if let Some(metrics) = &mut metrics {
+            metrics.end_frame(builder.start_frame(), &mut connections);
+        }
+        if let Some(soak) = &mut soak {
+            soak.end_frame(builder, &mut connections, &git_service)?;
+        }
+        if let Some(journal) = &mut journal {
+            if let Err(err) = journal.update(&Session::capture(navigation.locations())) {
+                warn!("{err}");
+            }
+        }
+        connections.end_frame(Some(&refresh_signal));
+        git_service.end_frame();
+
+        Ok(running)
+    };
+
+    if let Some(duration) = soak_duration {
+        let mut terminal = Terminal::headless(SOAK_WIDTH, SOAK_HEIGHT)?;
+        configure_terminal(&mut terminal, &args, refresh_wait)?;
+        let mut input = SoakInput::new(duration, 1);
+        terminal.set_synthetic_input(move || input.next_event());
+        terminal.run(&mut frame)?;
+    } else {
+        let mut terminal = vctuik::init()?;
+        configure_terminal(&mut terminal, &args, refresh_wait)?;
+        terminal.run(&mut frame)?;
+    }
+    if let Some(journal) = journal {
+        journal.finish()?;
+    }
+    if let Some(soak) = &soak {
+        println!("{}", soak.summary());
+    }
+
+    let remaining = connections.shutdown(Duration::from_secs(5));
+    if remaining > 0 {
+        println!("{remaining} edits could not be committed; they will be retried next time");
+    }
+
+    Ok(())
+}
+
+/// Apply the command line options and settings to the terminal.
+/// The current location and notification, for scripts.
+fn selection_json(navigation: &NavigationStack, inbox: &InboxResult) -> Value {
+    let pull = match navigation.current() {
+        Location::PullRequest(_, number) => Some(*number),
+        _ => None,
+    };
+    let notification = inbox.selection.as_ref().map(|(host, thread)| {
+        json!({
+            "host": host,
+            "owner": thread.repository.owner.login,
+            "name": thread.repository.name,
+            "thread": thread.id,
+            "title": thread.subject.title,
+            "pull": thread.pull_number(),
+        })
+    });
+    json!({
+        "location": navigation.current().label(),
+        "pull": pull,
+        "notification": notification,
+    })
+}
+
+fn configure_terminal<B: HyperlinkBackend>(
+    terminal: &mut Terminal<B>,
+    args: &Args,
+    refresh_wait: MergeWakeupWait,
+) -> Result<()> {
+    terminal.set_macro_file(get_project_dirs().data_dir().join("macros.txt"));
+    terminal.set_screen_reader_transcript(get_project_dirs().data_dir().join("screen-reader.txt"));
+    terminal.set_screen_reader(args.screen_reader);
+    terminal.set_reduced_motion(Settings::get().ui.reduced_motion);
+    Settings::get().theme.apply(terminal.theme_mut());
+    terminal.set_coalesce_redraws(Settings::get().ui.coalesce_redraws);
+    if let Some(hyperlinks) = Settings::get().ui.hyperlinks {
+        terminal.set_hyperlinks(hyperlinks);
+    }
+    if let Some(path) = &args.replay_session {
+        terminal.replay_recording(path)?;
+    }
+    if let Some(path) = &args.record_session {
+        terminal.set_recording(path)?;
+    }
+    terminal.add_merge_wakeup(refresh_wait);
+    Ok(())
+}
+
+/// Toggle logging of the API traffic of the given host, or of all hosts if
+/// `host` is empty.
+///
+/// The log file is opened on first use and shared by all hosts.
+fn toggle_request_log(
+    connections: &mut github::connections::Connections,
+    log: &mut Option<Arc<RequestLog>>,
+    path: &Path,
+    host: &str,
+) -> Result<()> {
+    let hosts: Vec<String> = if host.is_empty() {
+        connections.hosts().iter().map(|host| host.host.clone()).collect()
+    } else {
+        vec![host.to_string()]
+    };
+    let enable = hosts.iter().all(|host| connections.request_log(host).is_none());
+
+    let new_log = if enable {
+        if log.is_none() {
+            *log = Some(Arc::new(RequestLog::open(path)?));
+        }
+        log.clone()
+    } else {
+        None
+    };
+    for host in &hosts {
+        connections.set_request_log(host, new_log.clone())?;
+    }
+
+    if enable {
+        info!("Logging API requests to {}", path.display());
+    } else {
+        info!("Stopped logging API requests");
+    }
+    Ok(())
+}
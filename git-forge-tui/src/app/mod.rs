@@ -0,0 +1,7 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The TUIs, which run as `git-inbox` and `git-review` or as subcommands of
+//! `vctools`.
+
+pub mod inbox;
+pub mod review;
@@ -0,0 +1,458 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The review of a single pull request, or of a comparison of local refs.
+
+use std::time::Duration;
+
+use diff_modulo_base::*;
+use log::{debug, error, info, trace, warn, LevelFilter};
+use serde_json::{json, Value};
+use vctools_utils::time;
+use vctuik::{
+    event::{Event, KeyCode, KeyEventKind, MouseEventKind},
+    prelude::*,
+    section::with_section,
+    signals,
+};
+
+use crate::{
+    get_project_dirs,
+    github::{self, fixtures::FixtureMode},
+    gitservice, hooks,
+    ipc::{self, CommandServer, ForgeMethods, RpcError},
+    load_config,
+    logview::add_log_view,
+    metrics::MetricsExporter,
+    persistence::{self, Journal, Session},
+    review_time::{self, ReviewTimer},
+    settings::Settings,
+    tui::{avatar, CommandBar, Compare, ForgeStatus, PerfHud, Review, TrafficInspector},
+    CompletePullRequest, GitRepository,
+};
+
+#[derive(clap::Args, Debug)]
+pub struct Args {
+    #[clap(required_unless_present = "compare")]
+    remote: Option<String>,
+    #[clap(required_unless_present = "compare")]
+    pull: Option<u64>,
+
+    /// Show the diff between two local refs, given as <REF1>..<REF2>,
+    /// instead of a pull request.
+    #[clap(long, conflicts_with_all = &["remote", "pull"])]
+    compare: Option<String>,
+
+    /// With --compare, show the diff modulo this base.
+    #[clap(long, requires = "compare")]
+    modulo: Option<String>,
+
+    #[clap(flatten)]
+    dmb_options: tool::GitDiffModuloBaseOptions,
+
+    /// Behave as if run from the given path.
+    #[clap(short = 'C', default_value = ".")]
+    path: std::path::PathBuf,
+
+    /// Do not access the GitHub API.
+    #[clap(long)]
+    github_offline: bool,
+
+    /// Record all API responses into this directory.
+    #[clap(long, conflicts_with = "replay")]
+    record: Option<std::path::PathBuf>,
+
+    /// Serve API responses from a directory made with --record instead of
+    /// accessing the network. Edits are not committed.
+    #[clap(long)]
+    replay: Option<std::path::PathBuf>,
+
+    /// Record the session, i.e. what is shown and the keys that are pressed,
+    /// to this file in the asciinema v2 format.
+    #[clap(long)]
+    record_session: Option<std::path::PathBuf>,
+
+    /// Replay the key presses of a session made with --record-session.
+    #[clap(long)]
+    replay_session: Option<std::path::PathBuf>,
+
+    /// Periodically write internal metrics to this file, in the Prometheus
+    /// text format.
+    #[clap(long)]
+    metrics_file: Option<std::path::PathBuf>,
+
+    /// Do not record the time spent on this review.
+    #[clap(long)]
+    no_time_tracking: bool,
+
+    /// Accept JSON-RPC requests on this Unix domain socket.
+    #[clap(long)]
+    listen: Option<std::path::PathBuf>,
+    /// Start in the screen reader mode (toggle with F2).
+    #[clap(long)]
+    screen_reader: bool,
+}
+
+/// Commands that can be entered after `:` or sent via the `run` method, with
+/// their help texts.
+const COMMANDS: &[(&str, &str)] = &[
+    ("log", "Toggle the debug log"),
+    ("traffic", "Toggle the list of recent API requests"),
+    ("perf", "Toggle the performance overlay"),
+    ("anchor <text>", "Toggle anchoring the diff at lines with the text"),
+    ("compare <ref1>..<ref2> [--modulo <base>]", "Show the diff between two refs"),
+    ("review", "Return to the review of the pull request"),
+    ("quit", "Quit"),
+];
+
+/// Two refs to compare, optionally modulo a base.
+#[derive(Debug, Clone)]
+struct Comparison {
+    old: String,
+    new: String,
+    modulo: Option<String>,
+}
+impl Comparison {
+    fn parse(range: &str, modulo: Option<String>) -> Result<Self> {
+        let Some((old, new)) = range.split_once("..").filter(|(old, new)| {
+            !old.is_empty() && !new.is_empty() && !new.starts_with('.')
+        }) else {
+            Err(format!("expected <ref1>..<ref2>, got: {range}"))?
+        };
+        Ok(Self {
+            old: old.into(),
+            new: new.into(),
+            modulo,
+        })
+    }
+
+    /// Parse the arguments of the `compare` command, e.g.
+    /// `a..b --modulo base`.
+    fn parse_command(args: &str) -> Result<Self> {
+        let mut words = args.split_whitespace();
+        let (Some(range), modulo) = (words.next(), words.next()) else {
+            Err("Usage: :compare <ref1>..<ref2> [--modulo <base>]")?
+        };
+        let modulo = match modulo {
+            Some("--modulo") => match words.next() {
+                Some(base) => Some(base.to_string()),
+                None => Err("--modulo needs a base")?,
+            },
+            Some(word) => Err(format!("unexpected argument: {word}"))?,
+            None => None,
+        };
+        if let Some(word) = words.next() {
+            Err(format!("unexpected argument: {word}"))?
+        }
+        Self::parse(range, modulo)
+    }
+}
+
+/// Run the review until the user quits.
+pub fn run(mut args: Args) -> Result<()> {
+
+    let mut connections = github::connections::Connections::new(
+        load_config("github.toml")?,
+        args.github_offline,
+        Some(get_project_dirs().cache_dir().into()),
+        Some(get_project_dirs().data_dir().into()),
+    );
+    if let Some(dir) = args.record.clone() {
+        connections.set_fixtures(FixtureMode::Record(dir));
+    } else if let Some(dir) = args.replay.clone() {
+        connections.set_fixtures(FixtureMode::Replay(dir));
+    }
+    if !args.github_offline && args.replay.is_none() {
+        avatar::enable(connections.hosts());
+    }
+
+    //    println!("{:?}", &config);
+    //    println!("{}", dirs.config_dir().display());
+
+    let (refresh_signal, refresh_wait) = signals::make_merge_wakeup();
+    let server = match args.listen.clone() {
+        Some(path) => Some(CommandServer::listen(path, refresh_signal.clone())?),
+        None => None,
+    };
+    let mut forge_methods = ForgeMethods::default();
+    let repository = git_core::Repository::new(args.path.clone());
+    let mut git_service = gitservice::GitService::new(
+        &gitservice::Config::default(),
+        connections.hosts(),
+        refresh_signal.clone(),
+    );
+    let mut compare = match &args.compare {
+        Some(range) => Some(Comparison::parse(range, args.modulo.take())?),
+        None => None,
+    };
+    let pr = match (args.remote.take(), args.pull) {
+        (Some(remote), Some(pull)) => Some(CompletePullRequest::from_git(
+            GitRepository::new(args.path.clone(), remote),
+            pull,
+            connections.hosts(),
+            &git_core::SimpleExecutionProvider,
+        )?),
+        _ => None,
+    };
+
+    tui_logger::init_logger(LevelFilter::Debug)?;
+    tui_logger::set_default_level(LevelFilter::Debug);
+    debug!("Starting up");
+    trace!("test trace");
+    info!("test info");
+    warn!("test warn");
+    error!("test error");
+
+    let mut timer = pr
+        .as_ref()
+        .filter(|_| !args.no_time_tracking)
+        .map(|pr| ReviewTimer::new(pr.api().clone(), pr.id()));
+
+    let mut terminal = vctuik::init()?;
+    terminal.set_macro_file(get_project_dirs().data_dir().join("macros.txt"));
+    terminal.set_screen_reader_transcript(get_project_dirs().data_dir().join("screen-reader.txt"));
+    terminal.set_screen_reader(args.screen_reader);
+    terminal.set_reduced_motion(Settings::get().ui.reduced_motion);
+    Settings::get().theme.apply(terminal.theme_mut());
+    terminal.set_coalesce_redraws(Settings::get().ui.coalesce_redraws);
+    if let Some(hyperlinks) = Settings::get().ui.hyperlinks {
+        terminal.set_hyperlinks(hyperlinks);
+    }
+    if let Some(path) = &args.replay_session {
+        terminal.replay_recording(path)?;
+    }
+    if let Some(path) = &args.record_session {
+        terminal.set_recording(path)?;
+    }
+
+    let mut metrics = args.metrics_file.take().map(MetricsExporter::new);
+    let mut running = true;
+    let mut show_debug_log = false;
+    let mut show_traffic = false;
+    let mut show_perf = false;
+    let mut search: Option<regex::Regex> = None;
+    let mut error: Option<String> = None;
+    let mut command: Option<String> = None;
+
+    terminal.add_merge_wakeup(refresh_wait);
+
+    // Drafts of comments are journaled for recovery after a crash.
+    let (mut journal, recovered) =
+        Journal::<Session>::open(get_project_dirs().data_dir(), "git-review");
+    if let Some((session, saved_at)) = recovered {
+        info!(
+            "Recovered the drafts of {}, which did not end cleanly",
+            time::format_iso(saved_at)
+        );
+        persistence::restore_drafts(session.drafts);
+    }
+
+    terminal.run(|builder| {
+        connections.start_frame(Some(builder.start_frame() + Duration::from_millis(150)));
+        git_service.start_frame(Duration::from_millis(150));
+
+        let is_input = match builder.peek_event() {
+            Some(Event::Key(ev)) if ev.kind == KeyEventKind::Press => true,
+            Some(Event::Mouse(ev)) if ev.kind != MouseEventKind::Moved => true,
+            _ => false,
+        };
+        if is_input {
+            if let Some(timer) = &mut timer {
+                timer.activity();
+            }
+            if command.is_none() {
+                error = None;
+            }
+        }
+
+        match (&compare, &pr) {
+            (Some(comparison), _) => {
+                with_section(builder, "Compare", |builder| {
+                    Compare::new(&git_service, &repository, &comparison.old, &comparison.new)
+                        .modulo(comparison.modulo.as_deref())
+                        .maybe_search(search.as_ref())
+                        .options(&mut args.dmb_options)
+                        .build(builder);
+                });
+            }
+            (None, Some(pr)) => {
+                with_section(builder, "Review", |builder| {
+                    Review::new(&git_service, pr)
+                        .maybe_search(search.as_ref())
+                        .options(&mut args.dmb_options)
+                        .build(builder, &mut connections);
+                });
+            }
+            (None, None) => unreachable!(),
+        }
+
+        if show_traffic {
+            with_section(builder, "HTTP Traffic", |builder| {
+                TrafficInspector::new().build(builder, &mut connections);
+            });
+        }
+
+        if show_debug_log {
+            with_section(builder, "Debug Log", |builder| {
+                add_log_view(builder);
+            });
+        }
+
+        if show_perf {
+            with_section(builder, "Performance", |builder| {
+                PerfHud::new().build(builder);
+            });
+        }
+
+        if let Some(pr) = &pr {
+            ForgeStatus::new()
+                .host(&pr.api().host)
+                .repository(&pr.api().owner, &pr.api().name)
+                .pull(pr.id())
+                .build(builder, &mut connections);
+        }
+
+        if let Some(metrics) = &mut metrics {
+            metrics.end_frame(builder.start_frame(), &mut connections);
+        }
+        if let Err(err) = journal.update(&Session::capture(&[])) {
+            warn!("{err}");
+        }
+
+        // Commands from the command line and the command socket, with the
+        // request to reply to for the latter.
+        let cmd = CommandBar::new(&mut command, &mut search, &mut error).build(builder);
+        let mut commands: Vec<(String, Option<ipc::Request>)> = Vec::new();
+        commands.extend(cmd.map(|cmd| (cmd, None)));
+
+        let requests = server.iter().flat_map(|server| server.take_requests()).collect();
+        for request in forge_methods.handle(requests, &mut connections) {
+            match request.method.as_str() {
+                "run" => match request.param_str("command") {
+                    Ok(cmd) => {
+                        let cmd = cmd.trim_start_matches(':').to_string();
+                        commands.push((cmd, Some(request)));
+                    }
+                    Err(err) => request.reply(Err(err)),
+                },
+                "commands" => {
+                    let list: Vec<_> = COMMANDS
+                        .iter()
+                        .copied()
+                        .chain(Settings::get().commands.keys().map(|name| {
+                            (name.as_str(), "Custom command from settings.toml")
+                        }))
+                        .map(|(name, help)| json!({"name": name, "help": help}))
+                        .collect();
+                    request.reply(Ok(list.into()));
+                }
+                "selection" => request.reply(Ok(selection_json(&pr, &compare))),
+                method => {
+                    let err = RpcError::method_not_found(method);
+                    request.reply(Err(err));
+                }
+            }
+        }
+        connections.end_frame(Some(&refresh_signal));
+        git_service.end_frame();
+
+        for (cmd, request) in commands {
+            let result: Result<()> = if cmd == "log" {
+                show_debug_log = !show_debug_log;
+                Ok(())
+            } else if cmd == "traffic" {
+                show_traffic = !show_traffic;
+                Ok(())
+            } else if let Some(text) = cmd.strip_prefix("anchor ") {
+                let anchored = &mut args.dmb_options.anchored;
+                match anchored.iter().position(|anchor| anchor == text) {
+                    Some(idx) => {
+                        anchored.remove(idx);
+                    }
+                    None => anchored.push(text.to_string()),
+                }
+                Ok(())
+            } else if cmd == "perf" {
+                show_perf = !show_perf;
+                Ok(())
+            } else if let Some(rest) = cmd.strip_prefix("compare ") {
+                Comparison::parse_command(rest).map(|comparison| compare = Some(comparison))
+            } else if cmd == "review" {
+                if pr.is_some() {
+                    compare = None;
+                    Ok(())
+                } else {
+                    Err("No pull request to review".into())
+                }
+            } else if cmd == "q" || cmd == "quit" {
+                running = false;
+                Ok(())
+            } else if hooks::run_custom_command(
+                &cmd,
+                selection_json(&pr, &compare),
+                args.listen.as_deref(),
+            ) {
+                Ok(())
+            } else {
+                Err(format!("Unknown command: {cmd}").into())
+            };
+            match request {
+                Some(request) => request.reply(result.map(|()| Value::Null).map_err(Into::into)),
+                None => {
+                    if let Err(err) = result {
+                        error = Some(err.to_string());
+                    }
+                }
+            }
+            builder.need_refresh();
+        }
+
+        if builder.on_key_binding(KeyCode::F(5), "Refresh everything from the server") {
+            connections.refresh();
+            builder.need_refresh();
+        }
+
+        if builder.on_key_binding(KeyCode::Char('q'), "Quit") {
+            running = false;
+        }
+
+        Ok(running)
+    })?;
+    std::mem::drop(terminal);
+    journal.finish()?;
+
+    let remaining = connections.shutdown(Duration::from_secs(5));
+    if remaining > 0 {
+        println!("{remaining} edits could not be committed; they will be retried next time");
+    }
+
+    if let Some(timer) = timer {
+        let session = timer.finish();
+        if session.seconds > 0 {
+            review_time::append_session(&review_time::default_log_file(), &session)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The pull request or comparison that is shown, for scripts.
+fn selection_json(pr: &Option<CompletePullRequest>, compare: &Option<Comparison>) -> Value {
+    let pull = pr.as_ref().map(|pr| {
+        json!({
+            "host": pr.api().host,
+            "owner": pr.api().owner,
+            "name": pr.api().name,
+            "number": pr.id(),
+            "repository": pr.git().repository.path,
+            "remote": pr.git().remote,
+        })
+    });
+    let compare = compare.as_ref().map(|comparison| {
+        json!({
+            "old": comparison.old,
+            "new": comparison.new,
+            "modulo": comparison.modulo,
+        })
+    });
+    json!({"pull": pull, "compare": compare})
+}
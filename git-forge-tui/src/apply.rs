@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Patches made of hunks that were picked from a reviewed diff.
+//!
+//! This allows prototyping a change that is requested in a review: the picked
+//! hunks are written as a fixup patch that can be shared, or applied to the
+//! working tree of the local clone to continue from there.
+
+use std::path::Path;
+
+use diff_modulo_base::{diff, git_core};
+use vctools_utils::prelude::*;
+
+/// A hunk of a diff, together with the paths of its file.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    /// Paths as found in the "---" and "+++" lines.
+    pub old_path: Vec<u8>,
+    pub new_path: Vec<u8>,
+
+    /// 1-based line numbers of the first line of the hunk on each side.
+    pub old_begin: u32,
+    pub new_begin: u32,
+
+    pub lines: Vec<diff::HunkLine>,
+}
+impl Hunk {
+    /// Whether both are the same hunk of the same file, e.g. in two builds of
+    /// the same diff.
+    pub fn is_same(&self, other: &Hunk) -> bool {
+        self.new_path == other.new_path
+            && self.old_begin == other.old_begin
+            && self.new_begin == other.new_begin
+    }
+
+    /// Short description for lists of hunks.
+    pub fn describe(&self) -> String {
+        let path = String::from_utf8_lossy(&self.new_path);
+        let path = path.strip_prefix("b/").unwrap_or(&path);
+        format!("{path} @@ -{} +{} @@", self.old_begin, self.new_begin)
+    }
+}
+
+/// Format hunks as a unified diff that `git apply` accepts.
+///
+/// Changes that were considered unimportant in the review become plain
+/// changes, and the line counts are recomputed from the lines.
+pub fn format_patch(hunks: &[Hunk]) -> Vec<u8> {
+    let mut hunks: Vec<&Hunk> = hunks.iter().collect();
+    hunks.sort_by(|a, b| (&a.new_path, a.old_begin).cmp(&(&b.new_path, b.old_begin)));
+
+    let mut out = Vec::new();
+    let mut current_path: Option<&[u8]> = None;
+    for hunk in hunks {
+        if current_path != Some(&hunk.new_path) {
+            out.extend(b"--- ");
+            out.extend(&hunk.old_path);
+            out.extend(b"\n+++ ");
+            out.extend(&hunk.new_path);
+            out.push(b'\n');
+            current_path = Some(&hunk.new_path);
+        }
+
+        let old_count = hunk.lines.iter().filter(|line| line.status.covers_old()).count();
+        let new_count = hunk.lines.iter().filter(|line| line.status.covers_new()).count();
+        out.extend(
+            format!("@@ -{},{old_count} +{},{new_count} @@\n", hunk.old_begin, hunk.new_begin)
+                .as_bytes(),
+        );
+        for line in &hunk.lines {
+            out.push(match line.status {
+                diff::HunkLineStatus::Unchanged => b' ',
+                diff::HunkLineStatus::Old(_) => b'-',
+                diff::HunkLineStatus::New(_) => b'+',
+            });
+            out.extend(&line.contents);
+            if line.contents.last().is_none_or(|ch| *ch != b'\n') {
+                out.extend(b"\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Write the hunks as a patch file.
+pub fn export(path: &Path, hunks: &[Hunk]) -> Result<()> {
+    try_forward(
+        || -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, format_patch(hunks))?;
+            Ok(())
+        },
+        || format!("Error writing {}", path.display()),
+    )
+}
+
+/// Apply the hunks to the working tree of a local clone.
+///
+/// The patch is written to `path` first, so that it can be inspected if
+/// applying fails.
+pub fn apply(
+    repository: &git_core::Repository,
+    ep: &dyn git_core::ExecutionProvider,
+    path: &Path,
+    hunks: &[Hunk],
+) -> Result<()> {
+    export(path, hunks)?;
+    repository.apply(ep, path)
+}
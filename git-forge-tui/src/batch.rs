@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Forge operations for scripts and cron jobs, see the `git-forge` tool.
+//!
+//! They use the same configuration, cache, and edit queue as the TUIs. Edits
+//! are only queued; call `Connections::shutdown` to commit them before exiting.
+
+use std::io::Write;
+
+use serde_json::{json, Value};
+use vctools_utils::prelude::*;
+
+use crate::github::{
+    api,
+    connections::Connections,
+    edit::{CommentCreate, Edit, NotificationDone},
+    ClientRef, Response,
+};
+
+/// Run a request on the given host, waiting for the response.
+pub fn fetch<T>(
+    connections: &mut Connections,
+    host: &str,
+    request: impl Fn(&mut ClientRef) -> Response<T>,
+) -> Result<T> {
+    loop {
+        // Without a deadline, the frame waits for responses.
+        connections.start_frame(None);
+        let response = connections
+            .client(host)
+            .map(|client| request(&mut client.borrow_mut().access()));
+        connections.end_frame(None);
+        match response? {
+            Response::Pending => continue,
+            response => return Ok(response.ok().map_err(|err| err.into_owned())?),
+        }
+    }
+}
+
+/// Queue edits on the given host.
+fn queue(connections: &mut Connections, host: &str, edits: Vec<Edit>) -> Result<()> {
+    connections.start_frame(None);
+    let edits = edits.into_iter().map(|edit| (edit, None)).collect();
+    let result = connections.edit_batch(host, edits);
+    connections.end_frame(None);
+    result.map(|_| ())
+}
+
+/// The configured host with the given name, or the only configured host if
+/// no name is given.
+pub fn host(connections: &Connections, name: Option<&str>) -> Result<String> {
+    let hosts = connections.hosts();
+    let host = match name {
+        Some(name) => hosts.iter().find(|host| host.matches_host(name)),
+        None if hosts.len() > 1 => Err("Several hosts are configured, select one with --host")?,
+        None => hosts.first(),
+    };
+    match host {
+        Some(host) => Ok(host.host.clone()),
+        None => Err(format!("Host not configured: {}", name.unwrap_or_default()))?,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub host: String,
+    pub thread: api::NotificationThread,
+}
+
+/// Unread notifications of all hosts, most recently updated first.
+pub fn notifications(connections: &mut Connections) -> Result<Vec<Notification>> {
+    let hosts: Vec<String> = connections.hosts().iter().map(|host| host.host.clone()).collect();
+    let mut notifications = Vec::new();
+    for host in hosts {
+        let threads = try_forward(
+            || fetch(connections, &host, |client| client.notifications()),
+            || format!("Error fetching notifications of {host}"),
+        )?;
+        notifications.extend(threads.iter().map(|thread| Notification {
+            host: host.clone(),
+            thread: thread.clone(),
+        }));
+    }
+    notifications.sort_by(|a, b| b.thread.updated_at.cmp(&a.thread.updated_at));
+    Ok(notifications)
+}
+
+/// Queue marking the notification threads with the given IDs as done.
+pub fn mark_done(connections: &mut Connections, host: &str, thread_ids: &[String]) -> Result<()> {
+    let edits = thread_ids
+        .iter()
+        .map(|thread_id| {
+            NotificationDone {
+                thread_id: thread_id.clone(),
+            }
+            .into()
+        })
+        .collect();
+    queue(connections, host, edits)
+}
+
+/// Pull requests that match a search query, see `ClientRef::search_pulls`.
+pub fn search_pulls(
+    connections: &mut Connections,
+    host: &str,
+    query: &str,
+) -> Result<Vec<api::IssueSummary>> {
+    Ok(fetch(connections, host, |client| client.search_pulls(query))?.items.clone())
+}
+
+/// Queue a comment on a pull request or issue.
+pub fn comment(
+    connections: &mut Connections,
+    host: &str,
+    owner: &str,
+    name: &str,
+    number: u64,
+    body: String,
+) -> Result<()> {
+    let edit = CommentCreate {
+        owner: owner.into(),
+        name: name.into(),
+        number,
+        body,
+    };
+    queue(connections, host, vec![edit.into()])
+}
+
+pub fn notification_json(notification: &Notification) -> Value {
+    let thread = &notification.thread;
+    json!({
+        "host": notification.host,
+        "id": thread.id,
+        "repository": format!("{}/{}", thread.repository.owner.login, thread.repository.name),
+        "type": format!("{:?}", thread.subject.subject_type),
+        "reason": thread.reason,
+        "title": thread.subject.title,
+        "updatedAt": thread.updated_at,
+        "url": thread.html_url(),
+    })
+}
+
+pub fn pull_json(pull: &api::IssueSummary) -> Value {
+    json!({
+        "repository": pull.repository().map(|(owner, name)| format!("{owner}/{name}")),
+        "number": pull.number,
+        "title": pull.title,
+        "state": pull.state,
+        "author": pull.user.as_ref().map(|user| &user.login),
+        "updatedAt": pull.updated_at,
+        "url": pull.html_url,
+    })
+}
+
+pub fn notification_row(notification: &Notification) -> Vec<String> {
+    let thread = &notification.thread;
+    vec![
+        thread.id.clone(),
+        format!("{}/{}", thread.repository.owner.login, thread.repository.name),
+        thread.reason.clone(),
+        thread.updated_at.clone(),
+        thread.subject.title.clone(),
+    ]
+}
+
+pub const NOTIFICATION_HEADER: &[&str] = &["ID", "REPOSITORY", "REASON", "UPDATED", "TITLE"];
+
+pub fn pull_row(pull: &api::IssueSummary) -> Vec<String> {
+    let repository = pull.repository().map(|(owner, name)| format!("{owner}/{name}"));
+    vec![
+        format!("{}#{}", repository.unwrap_or_default(), pull.number),
+        pull.user.as_ref().map(|user| user.login.clone()).unwrap_or_default(),
+        pull.updated_at.clone(),
+        pull.title.clone(),
+    ]
+}
+
+pub const PULL_HEADER: &[&str] = &["PULL REQUEST", "AUTHOR", "UPDATED", "TITLE"];
+
+/// Write rows as a table with aligned columns. The last column is not padded.
+pub fn write_table(out: &mut dyn Write, header: &[&str], rows: &[Vec<String>]) -> Result<()> {
+    let mut widths: Vec<usize> = header.iter().map(|title| title.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header: Vec<String> = header.iter().map(|title| title.to_string()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let mut line = String::new();
+        for (idx, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if idx + 1 == row.len() {
+                line += cell;
+            } else {
+                line += &format!("{cell:width$}  ");
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
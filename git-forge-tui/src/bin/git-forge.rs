@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{io::Read, time::Duration};
+
+use clap::{Parser, Subcommand};
+use diff_modulo_base::git_core;
+
+use git_forge_tui::{
+    batch, get_project_dirs, github, load_config, CompletePullRequest, GitRepository,
+};
+use vctools_utils::prelude::*;
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List unread notifications of all hosts.
+    Notifications {
+        /// Write JSON instead of a table.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Mark notifications as done, by the IDs that `notifications` lists.
+    Done {
+        #[clap(required = true)]
+        ids: Vec<String>,
+
+        /// The host of the notifications, if several are configured.
+        #[clap(long)]
+        host: Option<String>,
+    },
+
+    /// List pull requests that match a search query.
+    Pulls {
+        /// Search query, e.g. `repo:owner/name is:open review:required`.
+        #[clap(required = true)]
+        query: Vec<String>,
+
+        /// The host to search, if several are configured.
+        #[clap(long)]
+        host: Option<String>,
+
+        /// Write JSON instead of a table.
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Post a comment on a pull request.
+    Comment {
+        remote: String,
+        pull: u64,
+
+        /// Text of the comment. It is read from stdin if omitted.
+        #[clap(long)]
+        body: Option<String>,
+
+        /// Behave as if run from the given path.
+        #[clap(short = 'C', default_value = ".")]
+        path: std::path::PathBuf,
+    },
+}
+
+/// Forge operations for scripts and cron jobs.
+#[derive(Parser, Debug)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Do not access the GitHub API.
+    #[clap(long)]
+    github_offline: bool,
+}
+
+fn do_main() -> Result<()> {
+    let args = Cli::parse();
+    env_logger::init();
+
+    let mut connections = github::connections::Connections::new(
+        load_config("github.toml")?,
+        args.github_offline,
+        Some(get_project_dirs().cache_dir().into()),
+        Some(get_project_dirs().data_dir().into()),
+    );
+    let mut out = std::io::stdout().lock();
+
+    match args.command {
+        Command::Notifications { json } => {
+            let notifications = batch::notifications(&mut connections)?;
+            if json {
+                let json: Vec<_> = notifications.iter().map(batch::notification_json).collect();
+                serde_json::to_writer_pretty(&mut out, &json)?;
+                println!();
+            } else {
+                let rows: Vec<_> = notifications.iter().map(batch::notification_row).collect();
+                batch::write_table(&mut out, batch::NOTIFICATION_HEADER, &rows)?;
+            }
+        }
+        Command::Done { ids, host } => {
+            let host = batch::host(&connections, host.as_deref())?;
+            batch::mark_done(&mut connections, &host, &ids)?;
+        }
+        Command::Pulls { query, host, json } => {
+            let host = batch::host(&connections, host.as_deref())?;
+            let pulls = batch::search_pulls(&mut connections, &host, &query.join(" "))?;
+            if json {
+                let json: Vec<_> = pulls.iter().map(batch::pull_json).collect();
+                serde_json::to_writer_pretty(&mut out, &json)?;
+                println!();
+            } else {
+                let rows: Vec<_> = pulls.iter().map(batch::pull_row).collect();
+                batch::write_table(&mut out, batch::PULL_HEADER, &rows)?;
+            }
+        }
+        Command::Comment {
+            remote,
+            pull,
+            body,
+            path,
+        } => {
+            let body = match body {
+                Some(body) => body,
+                None => {
+                    let mut body = String::new();
+                    std::io::stdin().read_to_string(&mut body)?;
+                    body
+                }
+            };
+            if body.trim().is_empty() {
+                Err("The comment is empty")?;
+            }
+            let pr = CompletePullRequest::from_git(
+                GitRepository::new(path, remote),
+                pull,
+                connections.hosts(),
+                &git_core::SimpleExecutionProvider,
+            )?;
+            let api = pr.api();
+            batch::comment(&mut connections, &api.host, &api.owner, &api.name, pr.id(), body)?;
+        }
+    }
+
+    // Queued edits are committed in the background.
+    let remaining = connections.shutdown(Duration::from_secs(30));
+    if remaining > 0 {
+        Err(format!("{remaining} edits could not be committed; they will be retried later"))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = do_main() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use clap::Parser;
+
+use git_forge_tui::review_time;
+use vctools_utils::prelude::*;
+
+/// Summarize the time spent in git-review per week and repository.
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Number of weeks to include.
+    #[clap(long, default_value = "4")]
+    weeks: u32,
+
+    /// Read sessions from the given file instead of the default log.
+    #[clap(long)]
+    log: Option<std::path::PathBuf>,
+}
+
+fn do_main() -> Result<()> {
+    let args = Cli::parse();
+
+    let path = args.log.unwrap_or_else(review_time::default_log_file);
+    let sessions = review_time::read_sessions(&path)?;
+    review_time::report(&sessions, args.weeks, &mut std::io::stdout().lock())
+}
+
+fn main() {
+    if let Err(err) = do_main() {
+        println!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+
+use git_forge_tui::{app, get_project_dirs, github, load_config, quickfix, serve};
+use vctools_utils::prelude::*;
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show notifications and pull requests of all hosts, like `git-inbox`.
+    Inbox(app::inbox::Args),
+
+    /// Review a pull request, like `git-review`.
+    Review(app::review::Args),
+
+    /// Serve review data to editor plugins as JSON-RPC over stdin and stdout.
+    Serve {
+        /// Do not access the GitHub API.
+        #[clap(long)]
+        github_offline: bool,
+    },
+
+    /// Write the important hunks and unresolved review threads of a pull
+    /// request in the quickfix format, e.g. for `nvim -q`.
+    Quickfix {
+        remote: String,
+        pull: u64,
+
+        /// Behave as if run from the given path.
+        #[clap(short = 'C', default_value = ".")]
+        path: String,
+
+        /// Only include changes since this commit, e.g. of your last review.
+        #[clap(long)]
+        since: Option<String>,
+
+        /// Write to this file instead of stdout.
+        #[clap(short, long)]
+        output: Option<std::path::PathBuf>,
+
+        /// Do not access the GitHub API.
+        #[clap(long)]
+        github_offline: bool,
+    },
+}
+
+fn connections(github_offline: bool) -> Result<github::connections::Connections> {
+    Ok(github::connections::Connections::new(
+        load_config("github.toml")?,
+        github_offline,
+        Some(get_project_dirs().cache_dir().into()),
+        Some(get_project_dirs().data_dir().into()),
+    ))
+}
+
+#[derive(Parser, Debug)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+fn do_main() -> Result<()> {
+    let args = Cli::parse();
+
+    match args.command {
+        Command::Inbox(args) => app::inbox::run(args)?,
+        Command::Review(args) => app::review::run(args)?,
+        Command::Serve { github_offline } => {
+            // Logs go to stderr, which doesn't interfere with the protocol.
+            env_logger::init();
+
+            let mut server = serve::Server::new(connections(github_offline)?);
+            server.run(std::io::stdin().lock(), std::io::stdout().lock())?;
+
+            let remaining = server.shutdown(Duration::from_secs(5));
+            if remaining > 0 {
+                eprintln!("{remaining} comments could not be posted; they will be retried later");
+            }
+        }
+        Command::Quickfix {
+            remote,
+            pull,
+            path,
+            since,
+            output,
+            github_offline,
+        } => {
+            env_logger::init();
+
+            let mut server = serve::Server::new(connections(github_offline)?);
+            server.open_pull(&path, &remote, pull)?;
+            let files = server.diff_hunks(since.as_deref(), Vec::new())?;
+            let threads = server.review_threads()?;
+
+            let mut out: Box<dyn std::io::Write> = match output {
+                Some(output) => Box::new(std::fs::File::create(output)?),
+                None => Box::new(std::io::stdout().lock()),
+            };
+            quickfix::write(&mut out, &files, &threads)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = do_main() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
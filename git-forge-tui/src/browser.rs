@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::process::{Command, Stdio};
+
+use vctools_utils::prelude::*;
+
+/// Open a URL in the user's web browser without waiting for it.
+///
+/// Uses `$BROWSER` if set and the platform's default opener otherwise.
+pub fn open_url(url: &str) -> Result<()> {
+    let mut command = if let Ok(browser) = std::env::var("BROWSER") {
+        Command::new(browser)
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // The empty argument is the window title.
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    try_forward(
+        || -> Result<()> {
+            let mut child = command
+                .arg(url)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            // Reap the process when it exits.
+            std::thread::spawn(move || child.wait());
+            Ok(())
+        },
+        || format!("Failed to open {url}"),
+    )
+}
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Statistics of the pull requests that were merged into a repository over a
+//! period: who contributed them, who reviewed them, and how long they waited
+//! for their first review.
+
+use std::collections::HashMap;
+
+use crate::github::api;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+/// Upper bounds and labels of the buckets of the turnaround histogram.
+pub const TURNAROUND_BUCKETS: &[(i64, &str)] = &[
+    (HOUR, "within an hour"),
+    (DAY, "within a day"),
+    (7 * DAY, "within a week"),
+    (i64::MAX, "after a week or more"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// Number of merged pull requests.
+    pub total: usize,
+
+    /// Number of merged pull requests per author, most first.
+    pub authors: Vec<(String, usize)>,
+
+    /// Number of merged pull requests that each user reviewed, most first.
+    pub reviewers: Vec<(String, usize)>,
+
+    /// Seconds from opening each pull request to its first review by somebody
+    /// other than its author, sorted. Pull requests without reviews are not
+    /// included.
+    pub turnaround: Vec<i64>,
+
+    /// Seconds from opening each pull request that the user reviewed to the
+    /// user's first review, sorted.
+    pub my_turnaround: Vec<i64>,
+}
+impl Stats {
+    /// Compute the statistics, from the perspective of the user with the given
+    /// login.
+    pub fn new(pulls: &[api::MergedPull], me: &str) -> Self {
+        let login = |actor: &Option<api::Actor>| {
+            actor.as_ref().map_or("ghost".to_string(), |actor| actor.login.clone())
+        };
+        let timestamp = |s: &str| chrono::DateTime::parse_from_rfc3339(s).ok();
+
+        let mut authors: HashMap<String, usize> = HashMap::new();
+        let mut reviewers: HashMap<String, usize> = HashMap::new();
+        let mut stats = Stats {
+            total: pulls.len(),
+            ..Default::default()
+        };
+        for pull in pulls {
+            let author = login(&pull.author);
+            *authors.entry(author.clone()).or_default() += 1;
+
+            let created = timestamp(&pull.created_at);
+            let mut first_review = None;
+            let mut my_review = None;
+            let mut seen = Vec::new();
+            for review in &pull.reviews.nodes {
+                let reviewer = login(&review.author);
+                if reviewer == author {
+                    continue;
+                }
+                let submitted = review.submitted_at.as_deref().and_then(timestamp);
+                let wait = created.zip(submitted).map(|(c, s)| (s - c).num_seconds().max(0));
+                first_review = first_review.or(wait);
+                if reviewer == me {
+                    my_review = my_review.or(wait);
+                }
+                if !seen.contains(&reviewer) {
+                    *reviewers.entry(reviewer.clone()).or_default() += 1;
+                    seen.push(reviewer);
+                }
+            }
+            stats.turnaround.extend(first_review);
+            stats.my_turnaround.extend(my_review);
+        }
+
+        let sorted = |counts: HashMap<String, usize>| {
+            let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            counts
+        };
+        stats.authors = sorted(authors);
+        stats.reviewers = sorted(reviewers);
+        stats.turnaround.sort();
+        stats.my_turnaround.sort();
+        stats
+    }
+
+    /// Number of merged pull requests that the user reviewed.
+    pub fn my_reviews(&self) -> usize {
+        self.my_turnaround.len()
+    }
+}
+
+/// Median of sorted values.
+pub fn median(values: &[i64]) -> Option<i64> {
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[len / 2 - 1] + values[len / 2]) / 2),
+        len => Some(values[len / 2]),
+    }
+}
+
+/// Number of values in each of the `TURNAROUND_BUCKETS`.
+pub fn histogram(values: &[i64]) -> Vec<usize> {
+    let mut counts = vec![0; TURNAROUND_BUCKETS.len()];
+    for value in values {
+        let bucket = TURNAROUND_BUCKETS.iter().position(|(limit, _)| value < limit).unwrap();
+        counts[bucket] += 1;
+    }
+    counts
+}
+
+/// Format a turnaround time coarsely, e.g. "45m", "5h", or "3d 4h".
+pub fn format_turnaround(seconds: i64) -> String {
+    if seconds < HOUR {
+        format!("{}m", seconds / 60)
+    } else if seconds < DAY {
+        format!("{}h", seconds / HOUR)
+    } else {
+        format!("{}d {}h", seconds / DAY, seconds % DAY / HOUR)
+    }
+}
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Changes of dependencies in the lockfiles of a pull request.
+//!
+//! `Cargo.lock` and `package-lock.json` are supported. New versions of crates
+//! are looked up in a local clone of the RustSec advisory database
+//! (<https://github.com/rustsec/advisory-db>) if one is configured, so that no
+//! requests to other services are needed.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::Path,
+};
+
+use diff_modulo_base::git_core::{self, Ref};
+use vctools_utils::prelude::*;
+
+use crate::github::api;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+impl Ecosystem {
+    /// The ecosystem of a lockfile, or None if the path isn't a lockfile.
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.rsplit('/').next() {
+            Some("Cargo.lock") => Some(Ecosystem::Cargo),
+            Some("package-lock.json") => Some(Ecosystem::Npm),
+            _ => None,
+        }
+    }
+
+    /// Parse a lockfile into (name, version) pairs.
+    fn parse(self, contents: &[u8]) -> Result<BTreeSet<(String, String)>> {
+        let contents = std::str::from_utf8(contents)?;
+        let mut packages = BTreeSet::new();
+        match self {
+            Ecosystem::Cargo => {
+                let lockfile: toml::Table = toml::from_str(contents)?;
+                let entries = lockfile.get("package").and_then(|entries| entries.as_array());
+                for package in entries.into_iter().flatten() {
+                    let name = package.get("name").and_then(|name| name.as_str());
+                    let version = package.get("version").and_then(|version| version.as_str());
+                    if let (Some(name), Some(version)) = (name, version) {
+                        packages.insert((name.to_string(), version.to_string()));
+                    }
+                }
+            }
+            Ecosystem::Npm => {
+                let lockfile: serde_json::Value = serde_json::from_str(contents)?;
+                if let Some(entries) = lockfile["packages"].as_object() {
+                    // Lockfile version 2 and later, keyed by install path. The
+                    // empty path is the project itself.
+                    for (path, package) in entries {
+                        let Some((_, name)) = path.rsplit_once("node_modules/") else {
+                            continue;
+                        };
+                        let name = package["name"].as_str().unwrap_or(name);
+                        if let Some(version) = package["version"].as_str() {
+                            packages.insert((name.to_string(), version.to_string()));
+                        }
+                    }
+                } else {
+                    // Lockfile version 1, with nested dependencies.
+                    fn add(deps: &serde_json::Value, packages: &mut BTreeSet<(String, String)>) {
+                        for (name, package) in deps.as_object().into_iter().flatten() {
+                            if let Some(version) = package["version"].as_str() {
+                                packages.insert((name.clone(), version.to_string()));
+                            }
+                            add(&package["dependencies"], packages);
+                        }
+                    }
+                    add(&lockfile["dependencies"], &mut packages);
+                }
+            }
+        }
+        Ok(packages)
+    }
+}
+
+/// A security advisory that affects a version of a package.
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    /// For example, "RUSTSEC-2023-0001"
+    pub id: String,
+    pub title: String,
+}
+
+/// Change of a package in a lockfile.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub name: String,
+
+    /// Version before and after the change. The old version is None if the
+    /// package was added, the new version is None if it was removed.
+    pub old: Option<String>,
+    pub new: Option<String>,
+
+    /// Advisories that affect the new version.
+    pub advisories: Vec<Advisory>,
+}
+
+/// Compare the packages of two versions of a lockfile.
+///
+/// If a package has a single version before and after, that is reported as
+/// a change of its version. Otherwise, e.g. if a second version is added next
+/// to an existing one, versions are reported as added or removed individually.
+fn diff(old: &BTreeSet<(String, String)>, new: &BTreeSet<(String, String)>) -> Vec<Change> {
+    let mut by_name: BTreeMap<&str, (Vec<&str>, Vec<&str>)> = BTreeMap::new();
+    for (name, version) in old.difference(new) {
+        by_name.entry(name).or_default().0.push(version);
+    }
+    for (name, version) in new.difference(old) {
+        by_name.entry(name).or_default().1.push(version);
+    }
+
+    let mut changes = Vec::new();
+    for (name, (removed, added)) in by_name {
+        let change = |old: Option<&str>, new: Option<&str>| Change {
+            name: name.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+            advisories: Vec::new(),
+        };
+        if let ([old], [new]) = (&removed[..], &added[..]) {
+            changes.push(change(Some(old), Some(new)));
+            continue;
+        }
+        changes.extend(removed.iter().map(|old| change(Some(old), None)));
+        changes.extend(added.iter().map(|new| change(None, Some(new))));
+    }
+    changes
+}
+
+/// Dependency changes of one lockfile of a pull request.
+#[derive(Debug, Clone)]
+pub struct LockfileChanges {
+    pub path: String,
+    pub changes: Vec<Change>,
+}
+
+/// Compute the dependency changes of a changed file between two commits.
+/// Returns None if the file is not a lockfile.
+///
+/// Advisories are looked up if an advisory database is given.
+pub fn lockfile_changes(
+    repository: &git_core::Repository,
+    ep: &dyn git_core::ExecutionProvider,
+    range: Range<&Ref>,
+    file: &api::PullFile,
+    advisory_db: Option<&Path>,
+) -> Result<Option<LockfileChanges>> {
+    let Some(ecosystem) = Ecosystem::from_path(&file.filename) else {
+        return Ok(None);
+    };
+
+    let read = |commit: &Ref, path: &str| -> Result<BTreeSet<(String, String)>> {
+        try_forward(
+            || ecosystem.parse(&repository.file_contents(ep, commit, path)?),
+            || format!("failed to parse {path} at {commit}"),
+        )
+    };
+    let old_path = file.previous_filename.as_deref().unwrap_or(&file.filename);
+    let old = match file.status.as_str() {
+        "added" => BTreeSet::new(),
+        _ => read(range.start, old_path)?,
+    };
+    let new = match file.status.as_str() {
+        "removed" => BTreeSet::new(),
+        _ => read(range.end, &file.filename)?,
+    };
+
+    let mut changes = diff(&old, &new);
+    if let (Some(db), Ecosystem::Cargo) = (advisory_db, ecosystem) {
+        for change in &mut changes {
+            if let Some(version) = &change.new {
+                change.advisories = advisories(db, &change.name, version)?;
+            }
+        }
+    }
+
+    Ok(Some(LockfileChanges {
+        path: file.filename.clone(),
+        changes,
+    }))
+}
+
+/// Version as (major, minor, patch). Pre-release and build metadata are
+/// ignored, and missing components are 0.
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.trim().parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Whether a version matches a comma-separated list of comparators, as used
+/// by the advisory database, e.g. ">= 1.2.3, < 2" or "^0.4.1".
+fn matches_requirement(version: (u64, u64, u64), requirement: &str) -> bool {
+    requirement.split(',').all(|comparator| {
+        let comparator = comparator.trim();
+        let (op, bound) = ["<=", ">=", "<", ">", "=", "^", "~"]
+            .iter()
+            .find_map(|op| comparator.strip_prefix(op).map(|bound| (*op, bound)))
+            .unwrap_or(("^", comparator));
+        let Some(bound) = parse_version(bound) else {
+            return false;
+        };
+        let (major, minor, patch) = bound;
+        match op {
+            "<=" => version <= bound,
+            ">=" => version >= bound,
+            "<" => version < bound,
+            ">" => version > bound,
+            "=" => version == bound,
+            "~" => version >= bound && version < (major, minor + 1, 0),
+            _ => {
+                // Caret: compatible with the leftmost non-zero component.
+                let upper = if major > 0 {
+                    (major + 1, 0, 0)
+                } else if minor > 0 {
+                    (0, minor + 1, 0)
+                } else {
+                    (0, 0, patch + 1)
+                };
+                version >= bound && version < upper
+            }
+        }
+    })
+}
+
+/// Look up the advisories of the RustSec advisory database that affect a
+/// version of a crate.
+fn advisories(db: &Path, name: &str, version: &str) -> Result<Vec<Advisory>> {
+    let dir = db.join("crates").join(name);
+    let Some(version) = parse_version(version) else {
+        return Ok(Vec::new());
+    };
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut result = Vec::new();
+    for entry in std::fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&path)?;
+
+        // The metadata is TOML in a code block at the start of the file,
+        // followed by the description in Markdown.
+        let Some((metadata, description)) = contents
+            .strip_prefix("```toml")
+            .and_then(|rest| rest.split_once("\n```"))
+        else {
+            continue;
+        };
+        let metadata: toml::Table = try_forward(
+            || Ok(toml::from_str(metadata)?),
+            || format!("failed to parse {}", path.display()),
+        )?;
+
+        let advisory = metadata.get("advisory");
+        if advisory.and_then(|advisory| advisory.get("withdrawn")).is_some() {
+            continue;
+        }
+        let versions = metadata.get("versions");
+        let is_excluded = ["patched", "unaffected"].iter().any(|key| {
+            versions
+                .and_then(|versions| versions.get(key))
+                .and_then(|requirements| requirements.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|requirement| requirement.as_str())
+                .any(|requirement| matches_requirement(version, requirement))
+        });
+        if is_excluded {
+            continue;
+        }
+
+        let id = advisory
+            .and_then(|advisory| advisory.get("id"))
+            .and_then(|id| id.as_str())
+            .unwrap_or_default();
+        let title = description
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .unwrap_or_default();
+        result.push(Advisory {
+            id: id.to_string(),
+            title: title.trim().to_string(),
+        });
+    }
+    result.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(result)
+}
@@ -0,0 +1,55 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Detection of generated files.
+//!
+//! Generated files are collapsed in reviews and don't count towards the size
+//! of a pull request. A file is generated if `.gitattributes` marks it with
+//! `linguist-generated`, like on GitHub, or otherwise if it matches one of the
+//! `generated_paths` of the risk settings, which cover common lockfiles and
+//! build outputs by default.
+
+use std::collections::HashSet;
+
+use diff_modulo_base::git_core::{self, Ref};
+use log::debug;
+use vctools_utils::prelude::*;
+
+use crate::settings::RiskSettings;
+
+const ATTRIBUTE: &str = "linguist-generated";
+
+/// Whether a path matches one of the configured patterns for generated files.
+pub fn matches_patterns(settings: &RiskSettings, path: &str) -> bool {
+    settings.generated_paths.iter().any(|pattern| pattern.matches(path))
+}
+
+/// Detect which of the given paths are generated at the given commit.
+///
+/// Only the patterns are used if the attributes can't be checked, e.g.
+/// because Git is older than 2.40.
+pub fn detect(
+    repository: &git_core::Repository,
+    ep: &dyn git_core::ExecutionProvider,
+    commit: &Ref,
+    settings: &RiskSettings,
+    paths: &[&str],
+) -> Result<HashSet<String>> {
+    let attributes = match repository.check_attr(ep, commit, ATTRIBUTE, paths) {
+        Ok(attributes) => attributes,
+        Err(err) if ep.timed_out() => return Err(err),
+        Err(err) => {
+            debug!("{err}");
+            paths.iter().map(|path| (path.to_string(), "unspecified".into())).collect()
+        }
+    };
+
+    Ok(attributes
+        .into_iter()
+        .filter(|(path, value)| match value.as_str() {
+            "set" | "true" => true,
+            "unset" | "false" => false,
+            _ => matches_patterns(settings, path),
+        })
+        .map(|(path, _)| path)
+        .collect())
+}
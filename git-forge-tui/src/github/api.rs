@@ -1,8 +1,86 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use serde::Deserialize;
+//! Types of the responses of the API.
+//!
+//! Forges differ in what they report, e.g. older GitHub Enterprise Server
+//! releases lack fields and newer ones have states that we don't know, so
+//! the types are tolerant: missing fields take their default, unknown states
+//! fall back to a catch-all variant, and items of lists that can't be parsed
+//! are skipped. Whatever was tolerated is recorded as a warning of the
+//! response that is being parsed, see [`take_warnings`].
+
+use std::cell::RefCell;
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+thread_local! {
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record a problem with the response that is being parsed on this thread.
+pub(super) fn warning(warning: String) {
+    WARNINGS.with_borrow_mut(|warnings| warnings.push(warning));
+}
+
+/// Take the warnings that were recorded while parsing on this thread.
+pub fn take_warnings() -> Vec<String> {
+    WARNINGS.take()
+}
+
+/// Deserialize a list, skipping items that can't be parsed instead of
+/// failing. `null` is an empty list.
+pub fn tolerant_list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let values = Option::<Vec<serde_json::Value>>::deserialize(deserializer)?;
+    Ok(values.into_iter().flatten().filter_map(tolerant_item).collect())
+}
+
+/// Deserialize an item of a list, or record why it can't be.
+pub fn tolerant_item<T: DeserializeOwned>(value: serde_json::Value) -> Option<T> {
+    match T::deserialize(value) {
+        Ok(item) => Some(item),
+        Err(err) => {
+            let name = std::any::type_name::<T>().rsplit("::").next().unwrap_or_default();
+            warning(format!("skipped {name}: {err}"));
+            None
+        }
+    }
+}
+
+/// Deserialize `null` as the default value, e.g. for the user of a comment
+/// whose author's account was deleted.
+fn nullable<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Implement `Deserialize` for an enum that is represented by strings, with
+/// a variant for unknown strings.
+macro_rules! string_enum {
+    ($name:ident { $($value:literal => $variant:ident,)* _ => $other:ident $(,)? }) => {
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+                Ok(match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => {
+                        warning(format!("unknown {} {:?}", stringify!($name), value));
+                        $name::$other
+                    }
+                })
+            }
+        }
+    };
+}
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct Branch {
     #[serde(rename = "ref")]
     pub ref_: String,
@@ -11,19 +89,31 @@ pub struct Branch {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct Label {
     pub name: String,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Milestone {
+    pub title: String,
+    pub number: u64,
+    pub open_issues: u64,
+    pub due_on: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub enum PullState {
-    #[serde(rename = "open")]
     Open,
-    #[serde(rename = "closed")]
     Closed,
-    #[serde(other)]
     Other,
 }
+string_enum!(PullState {
+    "open" => Open,
+    "closed" => Closed,
+    _ => Other,
+});
 impl Default for PullState {
     fn default() -> Self {
         PullState::Other
@@ -31,17 +121,20 @@ impl Default for PullState {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct Pull {
     pub number: u64,
     pub state: PullState,
     pub draft: bool,
     pub merged: bool,
+    #[serde(deserialize_with = "nullable")]
     pub user: User,
     pub head: Branch,
     pub base: Branch,
     pub title: String,
     pub body: Option<String>,
     pub labels: Vec<Label>,
+    pub milestone: Option<Milestone>,
     pub created_at: String,
     pub updated_at: String,
     pub closed_at: Option<String>,
@@ -49,26 +142,49 @@ pub struct Pull {
     pub assignees: Vec<User>,
     pub requested_reviewers: Vec<User>,
     pub html_url: String,
+
+    /// Only reported for individual pull requests, not in lists.
+    pub additions: Option<u64>,
+    pub deletions: Option<u64>,
+    pub changed_files: Option<u64>,
+    pub commits: Option<u64>,
+}
+
+/// A file changed by a pull request.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct PullFile {
+    pub filename: String,
+    pub status: String,
+    pub additions: u64,
+    pub deletions: u64,
+    pub previous_filename: Option<String>,
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct User {
     pub login: String,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReviewState {
-    #[serde(rename = "APPROVED")]
     Approved,
-    #[serde(rename = "CHANGES_REQUESTED")]
     ChangesRequested,
-    #[serde(rename = "COMMENTED")]
     Commented,
-    #[serde(rename = "DISMISSED")]
     Dismissed,
-    #[serde(other)]
+    /// Started but not submitted yet. Only visible to its author.
+    Pending,
     Other,
 }
+string_enum!(ReviewState {
+    "APPROVED" => Approved,
+    "CHANGES_REQUESTED" => ChangesRequested,
+    "COMMENTED" => Commented,
+    "DISMISSED" => Dismissed,
+    "PENDING" => Pending,
+    _ => Other,
+});
 impl Default for ReviewState {
     fn default() -> Self {
         ReviewState::Other
@@ -81,16 +197,26 @@ impl ReviewState {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct Review {
+    pub id: u64,
+    #[serde(deserialize_with = "nullable")]
     pub user: User,
 
     // The Copilot pull request reviewer bot creates reviews without a commit ID.
     pub commit_id: Option<String>,
+
+    /// Empty for pending reviews.
     pub submitted_at: String,
+    #[serde(deserialize_with = "nullable")]
     pub body: String,
     pub state: ReviewState,
 }
 impl Review {
+    pub fn is_pending(&self) -> bool {
+        self.state == ReviewState::Pending
+    }
+
     pub fn submitted_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
         match chrono::DateTime::parse_from_rfc3339(&self.submitted_at) {
             Ok(dt) => Some(dt.with_timezone(&chrono::Utc)),
@@ -99,10 +225,30 @@ impl Review {
     }
 }
 
+/// A comment on a line of the diff, as part of a review.
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ReviewComment {
+    pub id: u64,
+    #[serde(deserialize_with = "nullable")]
+    pub user: User,
+    pub path: String,
+
+    /// Line in the new (or, for deleted lines, old) file; `None` for
+    /// comments on lines that are no longer part of the diff.
+    pub line: Option<u64>,
+    #[serde(deserialize_with = "nullable")]
+    pub body: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct Comment {
     pub id: u64,
+    #[serde(deserialize_with = "nullable")]
     pub user: User,
+    #[serde(deserialize_with = "nullable")]
     pub body: String,
     pub created_at: String,
     pub updated_at: String,
@@ -124,20 +270,26 @@ impl Comment {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct MinimalRepository {
     pub id: u64,
     pub node_id: String,
     pub name: String,
     pub owner: User,
+    pub html_url: String,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SubjectType {
     Issue,
     PullRequest,
-    #[serde(other)]
     Unknown,
 }
+string_enum!(SubjectType {
+    "Issue" => Issue,
+    "PullRequest" => PullRequest,
+    _ => Unknown,
+});
 impl Default for SubjectType {
     fn default() -> Self {
         SubjectType::Unknown
@@ -145,6 +297,7 @@ impl Default for SubjectType {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct NotificationSubject {
     pub title: String,
     pub url: Option<String>,
@@ -153,6 +306,7 @@ pub struct NotificationSubject {
 }
 
 #[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
 pub struct NotificationThread {
     pub id: String,
     pub last_read_at: Option<String>,
@@ -177,7 +331,515 @@ impl NotificationThread {
             .subject
             .url
             .as_ref()
-            .and_then(|url| url.split('/').last())
+            .and_then(|url| url.split('/').next_back())
             .and_then(|id_str| id_str.parse::<u64>().ok())
     }
+
+    /// URL of the subject in the web interface.
+    pub fn html_url(&self) -> Option<String> {
+        if self.repository.html_url.is_empty() {
+            return None;
+        }
+        let number = self
+            .subject
+            .url
+            .as_ref()
+            .and_then(|url| url.split('/').next_back())
+            .and_then(|id_str| id_str.parse::<u64>().ok());
+        let repo = &self.repository.html_url;
+        Some(match (&self.subject.subject_type, number) {
+            (SubjectType::PullRequest, Some(number)) => format!("{repo}/pull/{number}"),
+            (SubjectType::Issue, Some(number)) => format!("{repo}/issues/{number}"),
+            _ => repo.clone(),
+        })
+    }
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct GitActor {
+    pub name: String,
+    pub date: String,
+}
+
+/// Entry of the issue timeline.
+///
+/// Events have different fields depending on their type, so everything that
+/// isn't common to all events is optional.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct TimelineEvent {
+    /// Type of event, e.g. "committed", "reviewed", "commented", "labeled",
+    /// "head_ref_force_pushed".
+    pub event: String,
+    pub actor: Option<User>,
+    pub created_at: Option<String>,
+
+    // "reviewed" events
+    pub user: Option<User>,
+    pub submitted_at: Option<String>,
+    pub state: Option<String>,
+
+    // "committed" events
+    pub sha: Option<String>,
+    pub author: Option<GitActor>,
+    pub message: Option<String>,
+
+    // "labeled" / "unlabeled" events
+    pub label: Option<Label>,
+
+    pub commit_id: Option<String>,
+}
+impl TimelineEvent {
+    pub fn timestamp(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let timestamp = self
+            .created_at
+            .as_ref()
+            .or(self.submitted_at.as_ref())
+            .or(self.author.as_ref().map(|author| &author.date))?;
+        match chrono::DateTime::parse_from_rfc3339(timestamp) {
+            Ok(dt) => Some(dt.with_timezone(&chrono::Utc)),
+            Err(_) => None,
+        }
+    }
+
+    /// Login of the user who caused the event, or the name of the commit
+    /// author.
+    pub fn who(&self) -> Option<&str> {
+        self.actor
+            .as_ref()
+            .or(self.user.as_ref())
+            .map(|user| user.login.as_str())
+            .or(self.author.as_ref().map(|author| author.name.as_str()))
+    }
+}
+
+/// Public profile of a user (as opposed to the minimal `User`).
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct UserDetails {
+    pub login: String,
+    pub name: Option<String>,
+    pub company: Option<String>,
+    pub location: Option<String>,
+    pub bio: Option<String>,
+    pub created_at: String,
+    pub public_repos: u64,
+    pub followers: u64,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Organization {
+    pub login: String,
+}
+
+/// Details of a repository (as opposed to the `MinimalRepository` of
+/// notifications).
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Repository {
+    pub full_name: String,
+    pub description: Option<String>,
+    pub default_branch: String,
+    pub html_url: String,
+}
+
+/// Combined state of the commit statuses of a ref.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct CombinedStatus {
+    /// "success", "pending", or "failure"
+    pub state: String,
+    pub total_count: u64,
+    #[serde(deserialize_with = "tolerant_list")]
+    pub statuses: Vec<CommitStatus>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct CommitStatus {
+    pub context: String,
+
+    /// "success", "pending", "failure", or "error"
+    pub state: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct CheckRun {
+    pub name: String,
+
+    /// "queued", "in_progress", or "completed"
+    pub status: String,
+
+    /// For completed runs, e.g. "success", "failure", "neutral", "skipped".
+    pub conclusion: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct CheckRuns {
+    pub total_count: u64,
+    #[serde(deserialize_with = "tolerant_list")]
+    pub check_runs: Vec<CheckRun>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct RequiredStatusCheck {
+    pub context: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct RequiredStatusChecks {
+    pub contexts: Vec<String>,
+    pub checks: Vec<RequiredStatusCheck>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct RequiredPullRequestReviews {
+    pub required_approving_review_count: u32,
+    pub require_code_owner_reviews: bool,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Enabled {
+    pub enabled: bool,
+}
+
+/// Classic protection of a branch. Reading it requires admin access to the
+/// repository.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct BranchProtection {
+    pub required_status_checks: Option<RequiredStatusChecks>,
+    pub required_pull_request_reviews: Option<RequiredPullRequestReviews>,
+    pub required_conversation_resolution: Option<Enabled>,
+}
+
+/// A branch, whose summary of the classic protection can be read by everyone.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct BranchDetails {
+    pub name: String,
+    pub protected: bool,
+    pub protection: BranchProtectionSummary,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct BranchProtectionSummary {
+    pub enabled: bool,
+    pub required_status_checks: Option<RequiredStatusChecks>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct BranchRuleParameters {
+    pub required_approving_review_count: u32,
+    pub require_code_owner_review: bool,
+    pub required_review_thread_resolution: bool,
+    pub required_status_checks: Vec<RequiredStatusCheck>,
+}
+
+/// A rule of a ruleset that applies to a branch, e.g. of type "pull_request"
+/// or "required_status_checks".
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct BranchRule {
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    pub parameters: BranchRuleParameters,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub prerelease: bool,
+    pub published_at: Option<String>,
+    pub html_url: String,
+}
+impl Release {
+    pub fn published_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        let published_at = self.published_at.as_ref()?;
+        match chrono::DateTime::parse_from_rfc3339(published_at) {
+            Ok(dt) => Some(dt.with_timezone(&chrono::Utc)),
+            Err(_) => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct TagCommit {
+    pub sha: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Tag {
+    pub name: String,
+    pub commit: TagCommit,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Team {
+    pub name: String,
+    pub slug: String,
+    pub organization: Organization,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct IssueSummary {
+    pub number: u64,
+    pub title: String,
+    pub state: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub user: Option<User>,
+    pub labels: Vec<Label>,
+    pub milestone: Option<Milestone>,
+    pub repository_url: String,
+    pub html_url: String,
+}
+impl IssueSummary {
+    /// Owner and name of the repository, for search results.
+    pub fn repository(&self) -> Option<(&str, &str)> {
+        let mut parts = self.repository_url.rsplit('/');
+        let name = parts.next().filter(|name| !name.is_empty())?;
+        let owner = parts.next()?;
+        Some((owner, name))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, bound = "T: DeserializeOwned")]
+pub struct SearchResult<T> {
+    pub total_count: u64,
+    #[serde(deserialize_with = "tolerant_list")]
+    pub items: Vec<T>,
+}
+impl<T> Default for SearchResult<T> {
+    fn default() -> Self {
+        Self {
+            total_count: 0,
+            items: Vec::new(),
+        }
+    }
+}
+
+/// A connection of the GraphQL API, of which only the first page is used.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, bound = "T: DeserializeOwned")]
+pub struct Nodes<T> {
+    #[serde(deserialize_with = "tolerant_list")]
+    pub nodes: Vec<T>,
+}
+impl<T> Default for Nodes<T> {
+    fn default() -> Self {
+        Self { nodes: Vec::new() }
+    }
+}
+
+/// Author of a GraphQL object; `None` for deleted accounts ("ghost").
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Actor {
+    pub login: String,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThreadComment {
+    pub author: Option<Actor>,
+    pub body: String,
+    pub created_at: String,
+    pub diff_hunk: String,
+    pub url: String,
+}
+
+/// A conversation on a line of a pull request's diff.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReviewThread {
+    /// GraphQL node ID.
+    pub id: String,
+    pub is_resolved: bool,
+    pub is_outdated: bool,
+    pub path: String,
+    pub line: Option<u64>,
+    pub comments: Nodes<ThreadComment>,
+}
+
+/// Response of the query for the review threads of a pull request.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ReviewThreadsQuery {
+    pub repository: Option<ReviewThreadsRepository>,
+}
+impl ReviewThreadsQuery {
+    pub fn threads_mut(&mut self) -> Option<&mut Vec<ReviewThread>> {
+        let pull = self.repository.as_mut()?.pull_request.as_mut()?;
+        Some(&mut pull.review_threads.nodes)
+    }
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReviewThreadsRepository {
+    pub pull_request: Option<ReviewThreadsPull>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReviewThreadsPull {
+    pub review_threads: Nodes<ReviewThread>,
+}
+
+/// A project (board) of the current kind, called ProjectV2 in the API.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Project {
+    /// GraphQL node ID.
+    pub id: String,
+    pub number: u64,
+    pub title: String,
+    pub closed: bool,
+}
+
+/// Response of the query for the projects of a repository.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectsQuery {
+    pub repository: Option<ProjectsRepository>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectsRepository {
+    #[serde(rename = "projectsV2")]
+    pub projects: Nodes<Project>,
+}
+
+/// Response of the query for the items of a project.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectBoardQuery {
+    pub node: Option<ProjectBoard>,
+}
+
+/// The items of a project, in the columns of its "Status" field.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProjectBoard {
+    pub title: String,
+
+    /// None if the project has no "Status" field with options, in which case
+    /// its items are not in any column.
+    pub status_field: Option<ProjectStatusField>,
+    pub items: Nodes<ProjectItem>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectStatusField {
+    /// GraphQL node ID.
+    pub id: String,
+    pub options: Vec<ProjectFieldOption>,
+}
+
+/// An option of a single-select field, i.e. a column of the board.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectFieldOption {
+    pub id: String,
+    pub name: String,
+}
+
+/// A card on the board.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectItem {
+    /// GraphQL node ID.
+    pub id: String,
+    pub status: Option<ProjectItemStatus>,
+    pub content: Option<ProjectItemContent>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProjectItemStatus {
+    pub option_id: Option<String>,
+}
+
+/// The issue, pull request, or draft issue of a card.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectItemContent {
+    /// "Issue", "PullRequest", or "DraftIssue"
+    #[serde(rename = "__typename")]
+    pub typename: String,
+    pub number: Option<u64>,
+    pub title: String,
+    pub url: Option<String>,
+    pub repository: Option<RepositoryName>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RepositoryName {
+    pub name_with_owner: String,
+}
+
+/// Response of a search for the pull requests that were merged into a
+/// repository since some date.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct MergedPullsQuery {
+    pub search: MergedPullsPage,
+}
+
+/// A page of search results of the GraphQL API.
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MergedPullsPage {
+    pub page_info: PageInfo,
+    #[serde(deserialize_with = "tolerant_list")]
+    pub nodes: Vec<MergedPull>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MergedPull {
+    pub number: u64,
+    pub author: Option<Actor>,
+    pub created_at: String,
+    pub merged_at: Option<String>,
+
+    /// The first reviews, in the order in which they were submitted.
+    pub reviews: Nodes<PullReviewSummary>,
+}
+
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PullReviewSummary {
+    pub author: Option<Actor>,
+    pub submitted_at: Option<String>,
 }
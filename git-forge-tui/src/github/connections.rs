@@ -1,12 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{cell::RefCell, collections::HashMap, path::PathBuf, time::Instant};
+use std::{cell::RefCell, collections::HashMap, path::PathBuf, sync::Arc, time::Instant};
 
 use serde::Deserialize;
-use vctools_utils::prelude::*;
+use vctools_utils::{files::portable_file_name, prelude::*};
 use vctuik::signals::MergeWakeupSignal;
 
-use crate::github;
+use crate::github::{self, request_log::RequestLog};
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -18,6 +18,11 @@ struct LiveConfig {
     hosts: Vec<github::Host>,
     offline: bool,
     cache_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    fixtures: Option<github::fixtures::FixtureMode>,
+
+    /// Request logs by host name.
+    request_logs: HashMap<String, Arc<RequestLog>>,
 }
 
 #[derive(Debug)]
@@ -54,10 +59,23 @@ impl Clients {
                         config
                             .cache_dir
                             .as_ref()
-                            .map(|cache_dir| cache_dir.join(&host.host)),
+                            .map(|cache_dir| cache_dir.join(portable_file_name(&host.host))),
+                    )
+                    .maybe_state_dir(
+                        config
+                            .state_dir
+                            .as_ref()
+                            .map(|state_dir| state_dir.join(portable_file_name(&host.host))),
+                    )
+                    .maybe_fixtures(
+                        config
+                            .fixtures
+                            .as_ref()
+                            .map(|fixtures| fixtures.join(&portable_file_name(&host.host))),
                     )
                     .new()
                     .map(|mut client| {
+                        client.set_request_log(config.request_logs.get(&host.host).cloned());
                         client.start_frame(deadline);
                         RefCell::new(client)
                     })
@@ -89,24 +107,70 @@ pub struct Connections {
     config: LiveConfig,
     clients: Clients,
     frame: Option<Option<Instant>>,
+
+    /// Edits made through `edit` and `edit_batch` as (host, edit IDs), most
+    /// recent last.
+    undo_stack: Vec<(String, Vec<u64>)>,
 }
 impl Connections {
-    pub fn new(config: Config, offline: bool, cache_dir: Option<PathBuf>) -> Self {
+    pub fn new(
+        config: Config,
+        offline: bool,
+        cache_dir: Option<PathBuf>,
+        state_dir: Option<PathBuf>,
+    ) -> Self {
         Self {
             config: LiveConfig {
                 hosts: config.hosts,
                 offline,
                 cache_dir,
+                state_dir,
+                fixtures: None,
+                request_logs: HashMap::new(),
             },
             clients: Clients::new(),
             frame: None,
+            undo_stack: Vec::new(),
         }
     }
 
+    /// Record API responses to or replay them from a fixture directory. Must
+    /// be called before any client is used.
+    pub fn set_fixtures(&mut self, fixtures: github::fixtures::FixtureMode) {
+        assert!(self.clients.clients.is_empty());
+        self.config.fixtures = Some(fixtures);
+    }
+
     pub fn hosts(&self) -> &[github::Host] {
         &self.config.hosts
     }
 
+    /// Log the API traffic of the given host to `log`, or stop logging it.
+    pub fn set_request_log(&mut self, hostname: &str, log: Option<Arc<RequestLog>>) -> Result<()> {
+        let Some(host) = self.config.hosts.iter().find(|h| h.matches_host(hostname)) else {
+            Err(format!("Host not configured: {hostname}"))?
+        };
+        let host = host.host.clone();
+
+        for client in self.clients.clients.values_mut().filter_map(|client| client.as_mut().ok()) {
+            let client = client.get_mut();
+            if client.host().host == host {
+                client.set_request_log(log.clone());
+            }
+        }
+        match log {
+            Some(log) => self.config.request_logs.insert(host, log),
+            None => self.config.request_logs.remove(&host),
+        };
+        Ok(())
+    }
+
+    /// The log of the given host's API traffic, if it is being logged.
+    pub fn request_log(&self, hostname: &str) -> Option<&Arc<RequestLog>> {
+        let host = self.config.hosts.iter().find(|h| h.matches_host(hostname))?;
+        self.config.request_logs.get(&host.host)
+    }
+
     pub fn start_frame(&mut self, deadline: Option<Instant>) {
         assert!(self.frame.is_none());
         self.frame = Some(deadline);
@@ -136,6 +200,122 @@ impl Connections {
         self.clients.client(&self.config, deadline, host.into())
     }
 
+    /// Queue an edit on the given host and remember it for undo.
+    pub fn edit(
+        &mut self,
+        host: impl Into<String>,
+        edit: github::edit::Edit,
+        precondition: Option<github::edit::Precondition>,
+    ) -> Result<()> {
+        let host = host.into();
+        let id = self.client(host.clone())?.borrow_mut().edit_checked(edit, precondition)?;
+        self.undo_stack.push((host, vec![id]));
+        Ok(())
+    }
+
+    /// Queue an edit like `edit`, and call `on_committed` once it has been
+    /// committed, see `Client::edit_then`.
+    pub fn edit_then(
+        &mut self,
+        host: impl Into<String>,
+        edit: github::edit::Edit,
+        precondition: Option<github::edit::Precondition>,
+        on_committed: github::CommitCallback,
+    ) -> Result<()> {
+        let host = host.into();
+        let id = self
+            .client(host.clone())?
+            .borrow_mut()
+            .edit_then(edit, precondition, on_committed)?;
+        self.undo_stack.push((host, vec![id]));
+        Ok(())
+    }
+
+    /// Queue several edits on the same host in one submission. They are
+    /// undone together.
+    ///
+    /// Returns the IDs of the queued edits.
+    pub fn edit_batch(
+        &mut self,
+        host: impl Into<String>,
+        edits: Vec<(github::edit::Edit, Option<github::edit::Precondition>)>,
+    ) -> Result<Vec<u64>> {
+        let host = host.into();
+        let ids = self.client(host.clone())?.borrow_mut().edit_batch(edits)?;
+        self.undo_stack.push((host, ids.clone()));
+        Ok(ids)
+    }
+
+    /// Whether the most recent edit or batch of edits can still be undone.
+    ///
+    /// Edits that can no longer be undone, because they were committed and
+    /// have no inverse or the grace period has passed, are forgotten.
+    pub fn can_undo(&mut self) -> bool {
+        while let Some((host, ids)) = self.undo_stack.last() {
+            let host = host.clone();
+            let ids = ids.clone();
+            let Ok(client) = self.client(host) else {
+                self.undo_stack.pop();
+                continue;
+            };
+            if ids.iter().any(|id| client.borrow().can_undo(*id)) {
+                return true;
+            }
+            self.undo_stack.pop();
+        }
+        false
+    }
+
+    /// Undo the most recent edit or batch of edits.
+    ///
+    /// Returns a description of what was undone.
+    pub fn undo(&mut self) -> Result<String> {
+        let Some((host, ids)) = self.undo_stack.pop() else {
+            Err("Nothing to undo")?
+        };
+        let mut client = self.client(host)?.borrow_mut();
+        let mut descriptions = Vec::new();
+        for id in ids {
+            // Skip edits of the batch that were committed and can't be
+            // reverted.
+            if client.can_undo(id) {
+                descriptions.push(client.undo_edit(id)?);
+            }
+        }
+        if descriptions.is_empty() {
+            Err("Edit cannot be undone")?
+        }
+        if descriptions.len() == 1 {
+            Ok(descriptions.pop().unwrap())
+        } else {
+            Ok(format!("{} edits", descriptions.len()))
+        }
+    }
+
+    /// Request all responses of all clients again the next time that they
+    /// are used, see `Client::refresh`.
+    pub fn refresh(&mut self) {
+        for client in self.clients.clients.values().filter_map(|client| client.as_ref().ok()) {
+            client.borrow_mut().refresh();
+        }
+    }
+
+    /// Shut down all clients, committing queued edits for up to `timeout`.
+    ///
+    /// Returns the number of edits that were left uncommitted.
+    pub fn shutdown(&mut self, timeout: std::time::Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        self.clients
+            .clients
+            .values_mut()
+            .filter_map(|client| client.as_mut().ok())
+            .map(|client| {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                client.get_mut().shutdown(timeout)
+            })
+            .sum()
+    }
+
     pub fn all_clients(
         &mut self,
     ) -> impl Iterator<Item = (&github::Host, Result<&RefCell<github::Client>>)> {
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Content-addressed on-disk cache of API responses.
+//!
+//! Responses are stored zstd-compressed as `objects/<xx>/<hash>.zst`, where
+//! the hash is a BLAKE2b-128 checksum of the uncompressed contents. The file
+//! `index` maps URLs to hashes, one `<hash> <url>` entry per line. New entries
+//! are appended, so that later lines override earlier ones; the index is
+//! compacted when it has grown well beyond the number of live entries.
+//!
+//! Several processes can share the cache. Storing an entry holds a shared
+//! lock on the file `lock`, compacting holds an exclusive one and re-reads the
+//! index first, so that entries of other processes are neither dropped from
+//! the index nor have their objects removed.
+//!
+//! Corruption is detected and recovered from automatically: index lines that
+//! can't be parsed are skipped, and objects that can't be decompressed or
+//! whose contents don't match their hash are removed and treated as missing.
+//!
+//! Files of the previous layout, which stored each response as a file named
+//! after its URL, are removed when the cache is opened.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use blake2::Digest;
+use log::{info, warn};
+
+use vctools_utils::prelude::*;
+
+type Blake2b128 = blake2::Blake2b<blake2::digest::consts::U16>;
+
+/// The zstd compression level of objects. Responses are stored as they are
+/// received, so favor speed.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Files in the cache directory that belong to the current layout.
+const FILES: &[&str] = &["objects", "index", "index.tmp", "lock"];
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<String, String>,
+
+    /// Number of lines in the index file.
+    lines: usize,
+}
+impl Index {
+    fn read(dir: &Path) -> Result<Self> {
+        let mut index = Index::default();
+        match fs::read(dir.join("index")) {
+            Ok(bytes) => {
+                for line in String::from_utf8_lossy(&bytes).lines() {
+                    index.lines += 1;
+                    match line.split_once(' ') {
+                        Some((hash, url)) if is_hash(hash) && !url.is_empty() => {
+                            index.entries.insert(url.to_string(), hash.to_string());
+                        }
+                        _ => warn!("Skipping corrupt line in {}/index", dir.display()),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => Err(err)?,
+        }
+        Ok(index)
+    }
+}
+
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    index: Mutex<Index>,
+
+    /// Locked while the index and objects are changed.
+    lock: File,
+}
+impl DiskCache {
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(dir.join("objects"))?;
+        remove_old_layout(&dir);
+
+        let lock =
+            OpenOptions::new().create(true).truncate(false).write(true).open(dir.join("lock"))?;
+        let cache = Self {
+            index: Mutex::new(Index::read(&dir)?),
+            dir,
+            lock,
+        };
+        cache.compact_if_needed();
+        Ok(cache)
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.dir.join("objects").join(&hash[..2]).join(format!("{hash}.zst"))
+    }
+
+    /// Load the cached response for `url`, if there is a valid one.
+    pub fn load(&self, url: &str) -> Option<Vec<u8>> {
+        let hash = self.index.lock().unwrap().entries.get(url)?.clone();
+        let path = self.object_path(&hash);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Error reading cache object {}: {}", path.display(), err);
+                }
+                self.index.lock().unwrap().entries.remove(url);
+                return None;
+            }
+        };
+
+        let data = zstd::decode_all(data.as_slice()).ok();
+        if data.as_deref().is_none_or(|data| hash_of(data) != hash) {
+            warn!("Removing corrupt cache object {}", path.display());
+            let _ = fs::remove_file(&path);
+            self.index.lock().unwrap().entries.remove(url);
+            return None;
+        }
+
+        data
+    }
+
+    /// Store the response for `url`.
+    pub fn store(&self, url: &str, data: &[u8]) {
+        if url.contains('\n') {
+            return;
+        }
+
+        let hash = hash_of(data);
+        if self.index.lock().unwrap().entries.get(url) == Some(&hash) {
+            return;
+        }
+
+        let result = (|| -> Result<()> {
+            let compressed = zstd::encode_all(data, COMPRESSION_LEVEL)?;
+
+            let mut index = self.index.lock().unwrap();
+            // Keep other processes from compacting until the object is in
+            // the index.
+            self.lock.lock_shared()?;
+            let _unlock = Unlock(&self.lock);
+
+            let path = self.object_path(&hash);
+            if !path.exists() {
+                fs::create_dir_all(path.parent().unwrap())?;
+                // Write atomically so that a crash can't leave a partial
+                // object behind under its final name.
+                let tmp = path.with_extension("tmp");
+                fs::write(&tmp, compressed)?;
+                fs::rename(&tmp, &path)?;
+            }
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.dir.join("index"))?;
+            writeln!(file, "{} {}", hash, url)?;
+            index.entries.insert(url.to_string(), hash);
+            index.lines += 1;
+            Ok(())
+        })();
+        if let Err(err) = result {
+            warn!("Error writing cache entry for {}: {}", url, err);
+        }
+    }
+
+    /// Rewrite the index without overridden entries and remove objects that
+    /// are no longer referenced.
+    ///
+    /// This is skipped while another process uses the cache.
+    fn compact_if_needed(&self) {
+        let mut index = self.index.lock().unwrap();
+        if index.lines <= 2 * index.entries.len() + 100 {
+            return;
+        }
+
+        let result = (|| -> Result<()> {
+            if self.lock.try_lock().is_err() {
+                return Ok(());
+            }
+            let _unlock = Unlock(&self.lock);
+
+            // Other processes may have added entries since the index was read.
+            *index = Index::read(&self.dir)?;
+            if index.lines <= 2 * index.entries.len() + 100 {
+                return Ok(());
+            }
+
+            let mut text = String::new();
+            for (url, hash) in &index.entries {
+                text.push_str(&format!("{} {}\n", hash, url));
+            }
+            let tmp = self.dir.join("index.tmp");
+            fs::write(&tmp, text)?;
+            fs::rename(&tmp, self.dir.join("index"))?;
+            index.lines = index.entries.len();
+
+            let live: std::collections::HashSet<&String> = index.entries.values().collect();
+            for subdir in fs::read_dir(self.dir.join("objects"))? {
+                for object in fs::read_dir(subdir?.path())? {
+                    let object = object?;
+                    let name = object.file_name();
+                    let hash = name.to_str().and_then(|name| name.strip_suffix(".zst"));
+                    if !hash.is_some_and(|hash| live.contains(&hash.to_string())) {
+                        fs::remove_file(object.path())?;
+                    }
+                }
+            }
+            Ok(())
+        })();
+        if let Err(err) = result {
+            warn!("Error compacting cache in {}: {}", self.dir.display(), err);
+        }
+    }
+}
+
+/// Releases a lock on the lock file when dropped.
+struct Unlock<'a>(&'a File);
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+/// Remove the files of the previous layout, if any.
+fn remove_old_layout(dir: &Path) {
+    let result = (|| -> Result<()> {
+        let mut removed = 0;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let is_ours = entry.file_name().to_str().is_some_and(|name| FILES.contains(&name));
+            if !is_ours && entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!("Removed {removed} files of the old cache layout in {}", dir.display());
+        }
+        Ok(())
+    })();
+    if let Err(err) = result {
+        warn!("Error removing old cache files in {}: {}", dir.display(), err);
+    }
+}
+
+fn hash_of(data: &[u8]) -> String {
+    Blake2b128::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn is_hash(s: &str) -> bool {
+    s.len() == 32 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
@@ -3,60 +3,1082 @@
 use std::any::Any;
 
 use log::{debug, info};
-use reqwest::Url;
+use reqwest::{header, StatusCode, Url};
+use serde::{Deserialize, Serialize};
 use vctools_utils::prelude::*;
 
-use super::api;
+use super::{api, flavor::ApiFlavor, retry::RetryPolicy, Endpoint};
+use crate::task_list;
+
+/// Copies a parsed response, see [`ItemGetter::get`].
+pub type CloneItem = fn(&(dyn Any + Send + Sync)) -> Option<Box<dyn Any + Send + Sync>>;
 
 pub trait ItemGetter {
-    fn get(&mut self, url: &str) -> Option<&mut Box<dyn Any + Send + Sync>>;
+    /// The parsed response of the endpoint, for applying an edit. A response
+    /// that is still shared is copied with `clone` first.
+    fn get(&mut self, endpoint: &Endpoint, clone: CloneItem)
+        -> Option<&mut (dyn Any + Send + Sync)>;
 }
 
-#[derive(Debug, Clone)]
-pub enum Edit {
-    MarkNotificationDone(String),
-    Unsubscribe(String),
+/// The parsed response of the endpoint as a `T`, for applying an edit.
+fn get_item<'a, T: Any + Clone + Send + Sync>(
+    getter: &'a mut dyn ItemGetter,
+    endpoint: &Endpoint,
+) -> Option<&'a mut T> {
+    getter.get(endpoint, clone_item::<T>)?.downcast_mut::<T>()
 }
-impl Edit {
-    pub fn apply(&self, getter: &mut dyn ItemGetter) {
+
+fn clone_item<T: Any + Clone + Send + Sync>(
+    item: &(dyn Any + Send + Sync),
+) -> Option<Box<dyn Any + Send + Sync>> {
+    Some(Box::new(item.downcast_ref::<T>()?.clone()))
+}
+
+/// Error of a request that the server answered with an unsuccessful status.
+#[derive(Debug)]
+pub struct HttpError {
+    pub status: StatusCode,
+    message: String,
+}
+impl HttpError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+}
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: HTTP {}", self.message, self.status)
+    }
+}
+impl std::error::Error for HttpError {}
+
+/// What to do with an edit whose commit (or precondition check) failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitFailure {
+    /// The request didn't reach the server or the server had a transient
+    /// problem. The edit is kept and retried later.
+    Transient,
+
+    /// The server rejected the edit, e.g. because the item was deleted or
+    /// the user lacks permission. Retrying can't succeed, so the edit is
+    /// dropped.
+    Rejected,
+
+    /// The server answered, but the edit can't be applied as it is, e.g.
+    /// because the item was changed. The edit is kept as a conflict until
+    /// the user forces or cancels it.
+    Conflict,
+}
+impl CommitFailure {
+    pub fn classify(err: &Error, policy: &RetryPolicy) -> Self {
+        if let Some(err) = err.downcast_ref::<HttpError>() {
+            if policy.should_retry_status(err.status) || err.status.is_server_error() {
+                CommitFailure::Transient
+            } else if err.status.is_client_error() {
+                CommitFailure::Rejected
+            } else {
+                CommitFailure::Conflict
+            }
+        } else if err.downcast_ref::<reqwest::Error>().is_some() {
+            CommitFailure::Transient
+        } else {
+            CommitFailure::Conflict
+        }
+    }
+}
+
+/// Expected server-side state of an item that an edit was based on.
+///
+/// Edits are made against cached data that may be stale. Checking the
+/// precondition right before committing avoids blindly overwriting changes
+/// that were made by somebody else in the meantime. (The REST API doesn't
+/// support conditional writes, so there is a small window for races left.)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Precondition {
+    /// The item at the (API-relative) URL still has the given ETag.
+    ETag { url: String, etag: String },
+
+    /// The item at the (API-relative) URL still has the given `updated_at`.
+    UpdatedAt { url: String, updated_at: String },
+}
+impl Precondition {
+    /// Check the precondition against the server. Returns a description of
+    /// the conflict if the item has changed.
+    pub fn check(
+        &self,
+        client: &reqwest::blocking::Client,
+        flavor: &ApiFlavor,
+    ) -> Result<Option<String>> {
         match self {
-            Edit::MarkNotificationDone(id) | Edit::Unsubscribe(id) => {
-                if let Some(item) = getter.get("notifications") {
-                    if let Some(threads) = item.downcast_mut::<Vec<api::NotificationThread>>() {
-                        threads.retain(|thread| thread.id != *id);
-                    }
+            Precondition::ETag { url, etag } => {
+                let url = flavor.rest_url.join(url)?;
+                info!("GET {} (If-None-Match: {})", url, etag);
+
+                let response = client.get(url).header(header::IF_NONE_MATCH, etag).send()?;
+                debug!("Response: {:?}", &response);
+
+                match response.status() {
+                    StatusCode::NOT_MODIFIED => Ok(None),
+                    StatusCode::NOT_FOUND => Ok(Some("Item was deleted".into())),
+                    status if status.is_success() => Ok(Some("Item was changed".into())),
+                    status => Err(HttpError::new(status, "Failed to check for conflicts"))?,
+                }
+            }
+            Precondition::UpdatedAt { url, updated_at } => {
+                let url = flavor.rest_url.join(url)?;
+                info!("GET {}", url);
+
+                let response = client.get(url).send()?;
+                debug!("Response: {:?}", &response);
+
+                if response.status() == StatusCode::NOT_FOUND {
+                    return Ok(Some("Item was deleted".into()));
+                }
+                if !response.status().is_success() {
+                    Err(HttpError::new(response.status(), "Failed to check for conflicts"))?
+                }
+
+                #[derive(Deserialize)]
+                struct Item {
+                    updated_at: String,
+                }
+                let item: Item = serde_json::from_str(&response.text()?)?;
+                if item.updated_at == *updated_at {
+                    Ok(None)
+                } else {
+                    Ok(Some(format!("Updated at {} (expected {})", item.updated_at, updated_at)))
                 }
-            },
+            }
         }
     }
+}
 
-    pub fn commit(&self, client: &reqwest::blocking::Client, url_api: &Url) -> Result<()> {
-        match self {
-            Edit::MarkNotificationDone(id) => {
-                mark_notification_done(client, url_api, id)
+/// A typed operation on the forge.
+///
+/// Operations are applied optimistically to cached data as soon as they are
+/// queued, and committed to the server later by the helper thread. The queue
+/// is persisted, so operations must be serializable; they are wrapped in an
+/// [`Edit`] for that.
+pub trait Operation: std::fmt::Display {
+    /// Endpoints of cached items that are changed by `apply_optimistic`, or
+    /// that change on the server when the operation is committed.
+    fn affected_endpoints(&self) -> Vec<Endpoint>;
+
+    /// Update cached data as if the operation had already been committed.
+    fn apply_optimistic(&self, _getter: &mut dyn ItemGetter) {}
+
+    /// Perform the operation on the server.
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()>;
+
+    /// Returns an edit that reverts this operation after it has been
+    /// committed, if there is such an edit.
+    ///
+    /// Edits without an inverse are held back for a grace period before they
+    /// are committed, so that they can still be undone by cancelling them.
+    fn invert(&self) -> Option<Edit> {
+        None
+    }
+}
+
+/// Mark a notification thread as done.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct NotificationDone {
+    pub thread_id: String,
+}
+impl std::fmt::Display for NotificationDone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Mark notification {} as done", self.thread_id)
+    }
+}
+impl Operation for NotificationDone {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        vec![Endpoint::Notifications]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        remove_notification(getter, &self.thread_id);
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        mark_notification_done(client, flavor, &self.thread_id)
+    }
+
+    // The API has no way to mark a thread as unread or not done.
+}
+
+/// Unsubscribe from a notification thread and mark it as done.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(transparent)]
+pub struct NotificationUnsubscribe {
+    pub thread_id: String,
+}
+impl std::fmt::Display for NotificationUnsubscribe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unsubscribe from notification {}", self.thread_id)
+    }
+}
+impl Operation for NotificationUnsubscribe {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        vec![Endpoint::Notifications]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        remove_notification(getter, &self.thread_id);
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        unsubscribe(client, &flavor.rest_url, &self.thread_id)?;
+        mark_notification_done(client, flavor, &self.thread_id)
+    }
+}
+
+/// Add a comment to the conversation of an issue or pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommentCreate {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+    pub body: String,
+}
+impl std::fmt::Display for CommentCreate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Comment on {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for CommentCreate {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::IssueComments { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    // The comment shows up once it has been committed and the affected URLs
+    // are reloaded.
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, body } = self;
+        add_comment(client, &flavor.rest_url, owner, name, *number, body)
+    }
+
+    // Deleting the comment would need its ID, which we only learn when
+    // committing.
+}
+
+/// Add a comment to the user's pending review of a pull request, e.g. one
+/// that was started on the website, instead of posting it on its own.
+///
+/// The comment is appended to the body of the review, which is published
+/// together with the review's line comments when it is submitted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PendingReviewAppend {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+    pub review_id: u64,
+    pub body: String,
+}
+impl PendingReviewAppend {
+    fn review_url(&self) -> String {
+        let Self { owner, name, number, review_id, .. } = self;
+        format!("repos/{owner}/{name}/pulls/{number}/reviews/{review_id}")
+    }
+
+    fn reviews(&self) -> Endpoint {
+        let Self { owner, name, number, .. } = self;
+        Endpoint::Reviews { owner: owner.clone(), name: name.clone(), number: *number }
+    }
+}
+impl std::fmt::Display for PendingReviewAppend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Add to pending review of {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for PendingReviewAppend {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        vec![self.reviews()]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        let Some(reviews) = get_item::<Vec<api::Review>>(getter, &self.reviews()) else { return };
+        if let Some(review) = reviews.iter_mut().find(|review| review.id == self.review_id) {
+            review.body = append_paragraph(&review.body, &self.body);
+        }
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let url = flavor.rest_url.join(&self.review_url()).unwrap();
+
+        // The body may have been edited on the website in the meantime.
+        info!("GET {}", url);
+        let response = client.get(url.clone()).send()?;
+        debug!("Response: {:?}", &response);
+        if !response.status().is_success() {
+            Err(HttpError::new(response.status(), "Failed to get the pending review"))?
+        }
+        let review: api::Review = serde_json::from_str(&response.text()?)?;
+        if !review.is_pending() {
+            Err("The review was already submitted")?
+        }
+
+        info!("PUT {}", url);
+        let body = append_paragraph(&review.body, &self.body);
+        let response = client.put(url).json(&serde_json::json!({ "body": body })).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let Self { owner, name, number, .. } = self;
+            let message = format!("Failed to add to the pending review of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+}
+
+/// Verdict of a submitted review.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReviewEvent {
+    Approve,
+    RequestChanges,
+    Comment,
+}
+
+/// Submit a review of a pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewSubmit {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+    pub event: ReviewEvent,
+    pub body: String,
+
+    /// Commit that was reviewed. Defaults to the head of the pull request at
+    /// the time the review is committed.
+    #[serde(default)]
+    pub commit_id: Option<String>,
+}
+impl std::fmt::Display for ReviewSubmit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = match self.event {
+            ReviewEvent::Approve => "Approve",
+            ReviewEvent::RequestChanges => "Request changes on",
+            ReviewEvent::Comment => "Review",
+        };
+        write!(f, "{verb} {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for ReviewSubmit {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::Reviews { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, .. } = self;
+        let url = flavor
+            .rest_url
+            .join(&format!("repos/{owner}/{name}/pulls/{number}/reviews"))
+            .unwrap();
+        info!("POST {}", url);
+
+        let mut request = serde_json::json!({ "event": self.event, "body": self.body });
+        if let Some(commit_id) = &self.commit_id {
+            request["commit_id"] = commit_id.clone().into();
+        }
+        let response = client.post(url).json(&request).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let message = format!("Failed to submit review of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+
+    // Dismissing the review would need its ID, and doesn't work for plain
+    // comments.
+}
+
+/// Merge a pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PullMerge {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// "merge", "squash", or "rebase". Defaults to the repository's default.
+    #[serde(default)]
+    pub method: Option<String>,
+
+    /// Head commit that must still be current for the merge to succeed, so
+    /// that commits that were pushed in the meantime aren't merged unseen.
+    #[serde(default)]
+    pub sha: Option<String>,
+}
+impl std::fmt::Display for PullMerge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Merge {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for PullMerge {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::Pull { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, .. } = self;
+        let url = flavor
+            .rest_url
+            .join(&format!("repos/{owner}/{name}/pulls/{number}/merge"))
+            .unwrap();
+        info!("PUT {}", url);
+
+        let mut request = serde_json::json!({});
+        if let Some(method) = &self.method {
+            request["merge_method"] = method.clone().into();
+        }
+        if let Some(sha) = &self.sha {
+            request["sha"] = sha.clone().into();
+        }
+        let response = client.put(url).json(&request).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let message = format!("Failed to merge {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+}
+
+/// Resolve or unresolve a review thread of a pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreadResolve {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// GraphQL node ID of the thread.
+    pub thread_id: String,
+
+    /// Whether the thread is resolved (or else unresolved).
+    pub resolved: bool,
+}
+impl std::fmt::Display for ThreadResolve {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.resolved { "Resolve" } else { "Unresolve" };
+        write!(f, "{verb} thread on {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for ThreadResolve {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::ReviewThreads { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        let Self { owner, name, number, .. } = self;
+        let threads = Endpoint::ReviewThreads {
+            owner: owner.clone(),
+            name: name.clone(),
+            number: *number,
+        };
+        let Some(threads) = get_item::<Vec<api::ReviewThread>>(getter, &threads) else { return };
+        if let Some(thread) = threads.iter_mut().find(|thread| thread.id == self.thread_id) {
+            thread.is_resolved = self.resolved;
+        }
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let mutation = if self.resolved {
+            "resolveReviewThread"
+        } else {
+            "unresolveReviewThread"
+        };
+        let query = format!(
+            "mutation($id: ID!) {{ {mutation}(input: {{threadId: $id}}) {{ thread {{ id }} }} }}"
+        );
+        graphql(client, flavor, &query, serde_json::json!({ "id": self.thread_id }))?;
+        Ok(())
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        Some(Edit::ThreadResolve(Self {
+            resolved: !self.resolved,
+            ..self.clone()
+        }))
+    }
+}
+
+/// Reply to a review thread of a pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThreadReply {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// GraphQL node ID of the thread.
+    pub thread_id: String,
+    pub body: String,
+}
+impl std::fmt::Display for ThreadReply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Reply to thread on {}/{}#{}", self.owner, self.name, self.number)
+    }
+}
+impl Operation for ThreadReply {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        vec![Endpoint::ReviewThreads { owner: owner.clone(), name: name.clone(), number: *number }]
+    }
+
+    // The reply shows up once it has been committed and the threads are
+    // reloaded.
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let query = "mutation($id: ID!, $body: String!) {
+            addPullRequestReviewThreadReply(input: {pullRequestReviewThreadId: $id, body: $body}) {
+                comment { id }
+            }
+        }";
+        let variables = serde_json::json!({ "id": self.thread_id, "body": self.body });
+        graphql(client, flavor, query, variables)?;
+        Ok(())
+    }
+}
+
+/// Check or uncheck an item of the task list in the description of a pull
+/// request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TaskCheck {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// Index of the task in the description, see [`task_list::parse`].
+    pub task: usize,
+
+    /// Text of the task, to detect when the description was changed.
+    pub text: String,
+
+    /// Whether the task is checked (or else unchecked).
+    pub checked: bool,
+}
+impl TaskCheck {
+    fn pull(&self) -> Endpoint {
+        let Self { owner, name, number, .. } = self;
+        Endpoint::Pull { owner: owner.clone(), name: name.clone(), number: *number }
+    }
+}
+impl std::fmt::Display for TaskCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verb = if self.checked { "Check" } else { "Uncheck" };
+        write!(f, "{verb} \"{}\" on {}/{}#{}", self.text, self.owner, self.name, self.number)
+    }
+}
+impl Operation for TaskCheck {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        vec![self.pull()]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        let Some(pull) = get_item::<api::Pull>(getter, &self.pull()) else { return };
+        let body = pull.body.as_deref().unwrap_or_default();
+        if let Some(body) = task_list::set_checked(body, self.task, &self.text, self.checked) {
+            pull.body = Some(body);
+        }
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let url = flavor.rest_url.join(&self.pull().key()).unwrap();
+        // The description may have been edited in the meantime.
+        info!("GET {}", url);
+        let response = client.get(url.clone()).send()?;
+        debug!("Response: {:?}", &response);
+        if !response.status().is_success() {
+            Err(HttpError::new(response.status(), "Failed to get the pull request"))?
+        }
+        let pull: api::Pull = serde_json::from_str(&response.text()?)?;
+        let body = pull.body.as_deref().unwrap_or_default();
+        let Some(body) = task_list::set_checked(body, self.task, &self.text, self.checked) else {
+            Err("The task list in the description was changed")?
+        };
+        info!("PATCH {}", url);
+        let response = client.patch(url).json(&serde_json::json!({ "body": body })).send()?;
+        debug!("Response: {:?}", &response);
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let Self { owner, name, number, .. } = self;
+            let message = format!("Failed to update the description of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        Some(Edit::TaskCheck(Self {
+            checked: !self.checked,
+            ..self.clone()
+        }))
+    }
+}
+
+/// Request reviews of a pull request, e.g. again after addressing comments.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewersRequest {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// Logins of the reviewers.
+    pub reviewers: Vec<String>,
+}
+impl std::fmt::Display for ReviewersRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Request reviews of {}/{}#{} from {}",
+            self.owner,
+            self.name,
+            self.number,
+            self.reviewers.join(", ")
+        )
+    }
+}
+impl Operation for ReviewersRequest {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::Pull { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, .. } = self;
+        let url = flavor
+            .rest_url
+            .join(&format!("repos/{owner}/{name}/pulls/{number}/requested_reviewers"))
+            .unwrap();
+        info!("POST {}", url);
+
+        let request = serde_json::json!({ "reviewers": self.reviewers });
+        let response = client.post(url).json(&request).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let message = format!("Failed to request reviews of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        let Self { owner, name, number, reviewers } = self.clone();
+        Some(Edit::ReviewersUnrequest(ReviewersUnrequest { owner, name, number, reviewers }))
+    }
+}
+
+/// Withdraw requests for reviews of a pull request.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewersUnrequest {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// Logins of the reviewers.
+    pub reviewers: Vec<String>,
+}
+impl std::fmt::Display for ReviewersUnrequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Withdraw review requests of {}/{}#{} from {}",
+            self.owner,
+            self.name,
+            self.number,
+            self.reviewers.join(", ")
+        )
+    }
+}
+impl Operation for ReviewersUnrequest {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let (owner, name, number) = (owner.clone(), name.clone(), *number);
+        vec![
+            Endpoint::Pull { owner: owner.clone(), name: name.clone(), number },
+            Endpoint::Timeline { owner, name, number, page: 1 },
+        ]
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, .. } = self;
+        let url = flavor
+            .rest_url
+            .join(&format!("repos/{owner}/{name}/pulls/{number}/requested_reviewers"))
+            .unwrap();
+        info!("DELETE {}", url);
+
+        let request = serde_json::json!({ "reviewers": self.reviewers });
+        let response = client.delete(url).json(&request).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let message = format!("Failed to withdraw review requests of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        let Self { owner, name, number, reviewers } = self.clone();
+        Some(Edit::ReviewersRequest(ReviewersRequest { owner, name, number, reviewers }))
+    }
+}
+
+/// Move an issue or pull request to another milestone, or remove it from its
+/// milestone.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MilestoneSet {
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// Number and title of the new milestone, None to remove the milestone.
+    pub milestone: Option<(u64, String)>,
+
+    /// Number and title of the milestone before the edit.
+    pub previous: Option<(u64, String)>,
+}
+impl std::fmt::Display for MilestoneSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { owner, name, number, .. } = self;
+        match &self.milestone {
+            Some((_, title)) => write!(f, "Move {owner}/{name}#{number} to milestone {title}"),
+            None => write!(f, "Remove {owner}/{name}#{number} from its milestone"),
+        }
+    }
+}
+impl Operation for MilestoneSet {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        let Self { owner, name, number, .. } = self;
+        let issues = |milestone: &Option<(u64, String)>| Endpoint::MilestoneIssues {
+            owner: owner.clone(),
+            name: name.clone(),
+            milestone: milestone.as_ref().map(|(number, _)| *number),
+        };
+        vec![
+            issues(&self.previous),
+            issues(&self.milestone),
+            Endpoint::Milestones { owner: owner.clone(), name: name.clone() },
+            Endpoint::Issue { owner: owner.clone(), name: name.clone(), number: *number },
+        ]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        let endpoints = self.affected_endpoints();
+        let Some(from) = get_issues(getter, &endpoints[0]) else { return };
+        let Some(idx) = from.iter().position(|issue| issue.number == self.number) else {
+            return;
+        };
+        let mut issue = from.remove(idx);
+        issue.milestone = self.milestone.as_ref().map(|(number, title)| api::Milestone {
+            number: *number,
+            title: title.clone(),
+            ..Default::default()
+        });
+        if let Some(to) = get_issues(getter, &endpoints[1]) {
+            to.insert(0, issue);
+        }
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let Self { owner, name, number, .. } = self;
+        let url = flavor.rest_url.join(&format!("repos/{owner}/{name}/issues/{number}")).unwrap();
+        info!("PATCH {}", url);
+
+        let milestone = self.milestone.as_ref().map(|(number, _)| number);
+        let request = serde_json::json!({ "milestone": milestone });
+        let response = client.patch(url).json(&request).send()?;
+        debug!("Response: {:?}", &response);
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            let message = format!("Failed to set the milestone of {owner}/{name}#{number}");
+            Err(HttpError::new(response.status(), message))?
+        }
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        Some(Edit::MilestoneSet(Self {
+            milestone: self.previous.clone(),
+            previous: self.milestone.clone(),
+            ..self.clone()
+        }))
+    }
+}
+
+/// Move an item of a project to another column, i.e. set its "Status" field
+/// to another option, or clear the field if `to` is None.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectItemMove {
+    /// GraphQL node IDs of the project, the item, and the "Status" field.
+    pub project_id: String,
+    pub item_id: String,
+    pub field_id: String,
+
+    /// Title of the item, for display.
+    pub title: String,
+
+    /// Option ID and name of the new column.
+    pub to: Option<(String, String)>,
+
+    /// Option ID and name of the column before the edit.
+    pub from: Option<(String, String)>,
+}
+impl ProjectItemMove {
+    fn board(&self) -> Endpoint {
+        Endpoint::ProjectBoard { project_id: self.project_id.clone() }
+    }
+}
+impl std::fmt::Display for ProjectItemMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.to {
+            Some((_, column)) => write!(f, "Move \"{}\" to {}", self.title, column),
+            None => write!(f, "Clear the status of \"{}\"", self.title),
+        }
+    }
+}
+impl Operation for ProjectItemMove {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        vec![self.board()]
+    }
+
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        let Some(board) = get_item::<api::ProjectBoard>(getter, &self.board()) else { return };
+        if let Some(item) = board.items.nodes.iter_mut().find(|item| item.id == self.item_id) {
+            item.status = Some(api::ProjectItemStatus {
+                option_id: self.to.as_ref().map(|(id, _)| id.clone()),
+            });
+        }
+    }
+
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        let ids = serde_json::json!({
+            "projectId": self.project_id,
+            "itemId": self.item_id,
+            "fieldId": self.field_id,
+        });
+        match &self.to {
+            Some((option_id, _)) => {
+                let query = "mutation($input: UpdateProjectV2ItemFieldValueInput!) {
+                    updateProjectV2ItemFieldValue(input: $input) { projectV2Item { id } }
+                }";
+                let mut input = ids;
+                input["value"] = serde_json::json!({ "singleSelectOptionId": option_id });
+                graphql(client, flavor, query, serde_json::json!({ "input": input }))?;
             }
-            Edit::Unsubscribe(id) => {
-                unsubscribe(client, url_api, id)?;
-                mark_notification_done(client, url_api, id)
+            None => {
+                let query = "mutation($input: ClearProjectV2ItemFieldValueInput!) {
+                    clearProjectV2ItemFieldValue(input: $input) { projectV2Item { id } }
+                }";
+                graphql(client, flavor, query, serde_json::json!({ "input": ids }))?;
             }
         }
+        Ok(())
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        Some(Edit::ProjectItemMove(Self {
+            to: self.from.clone(),
+            from: self.to.clone(),
+            ..self.clone()
+        }))
+    }
+}
+
+/// An operation as it is queued and persisted.
+///
+/// The aliases keep edit queues that were saved by older versions readable.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum Edit {
+    #[serde(alias = "MarkNotificationDone")]
+    NotificationDone(NotificationDone),
+    #[serde(alias = "Unsubscribe")]
+    NotificationUnsubscribe(NotificationUnsubscribe),
+    #[serde(alias = "AddComment")]
+    CommentCreate(CommentCreate),
+    MilestoneSet(MilestoneSet),
+    PendingReviewAppend(PendingReviewAppend),
+    ProjectItemMove(ProjectItemMove),
+    PullMerge(PullMerge),
+    ReviewSubmit(ReviewSubmit),
+    ReviewersRequest(ReviewersRequest),
+    ReviewersUnrequest(ReviewersUnrequest),
+    TaskCheck(TaskCheck),
+    ThreadResolve(ThreadResolve),
+    ThreadReply(ThreadReply),
+}
+impl Edit {
+    pub fn operation(&self) -> &dyn Operation {
+        match self {
+            Edit::NotificationDone(op) => op,
+            Edit::NotificationUnsubscribe(op) => op,
+            Edit::CommentCreate(op) => op,
+            Edit::MilestoneSet(op) => op,
+            Edit::PendingReviewAppend(op) => op,
+            Edit::ProjectItemMove(op) => op,
+            Edit::PullMerge(op) => op,
+            Edit::ReviewSubmit(op) => op,
+            Edit::ReviewersRequest(op) => op,
+            Edit::ReviewersUnrequest(op) => op,
+            Edit::TaskCheck(op) => op,
+            Edit::ThreadResolve(op) => op,
+            Edit::ThreadReply(op) => op,
+        }
     }
 }
+impl std::fmt::Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.operation().fmt(f)
+    }
+}
+impl Operation for Edit {
+    fn affected_endpoints(&self) -> Vec<Endpoint> {
+        self.operation().affected_endpoints()
+    }
 
-fn mark_notification_done(client: &reqwest::blocking::Client, url_api: &Url, id: &str) -> Result<()> {
-    let url = url_api.join(&format!("notifications/threads/{id}")).unwrap();
-    info!("DELETE {}", url);
+    fn apply_optimistic(&self, getter: &mut dyn ItemGetter) {
+        self.operation().apply_optimistic(getter)
+    }
 
-    let response = client.delete(url).send()?;
+    fn commit(&self, client: &reqwest::blocking::Client, flavor: &ApiFlavor) -> Result<()> {
+        self.operation().commit(client, flavor)
+    }
+
+    fn invert(&self) -> Option<Edit> {
+        self.operation().invert()
+    }
+}
+
+macro_rules! impl_from_operation {
+    ($($op:ident),*) => {
+        $(
+            impl From<$op> for Edit {
+                fn from(op: $op) -> Self {
+                    Edit::$op(op)
+                }
+            }
+        )*
+    };
+}
+impl_from_operation!(
+    NotificationDone,
+    NotificationUnsubscribe,
+    CommentCreate,
+    MilestoneSet,
+    PendingReviewAppend,
+    ProjectItemMove,
+    PullMerge,
+    ReviewSubmit,
+    ReviewersRequest,
+    ReviewersUnrequest,
+    TaskCheck,
+    ThreadResolve,
+    ThreadReply
+);
+
+fn remove_notification(getter: &mut dyn ItemGetter, id: &str) {
+    let endpoint = Endpoint::Notifications;
+    if let Some(threads) = get_item::<Vec<api::NotificationThread>>(getter, &endpoint) {
+        threads.retain(|thread| thread.id != id);
+    }
+}
+
+fn get_issues<'a>(
+    getter: &'a mut dyn ItemGetter,
+    endpoint: &Endpoint,
+) -> Option<&'a mut Vec<api::IssueSummary>> {
+    get_item::<Vec<api::IssueSummary>>(getter, endpoint)
+}
+
+fn append_paragraph(text: &str, paragraph: &str) -> String {
+    if text.trim().is_empty() {
+        paragraph.to_string()
+    } else {
+        format!("{}\n\n{}", text.trim_end(), paragraph)
+    }
+}
+
+/// Send a GraphQL request and return the `data` of the response.
+fn graphql(
+    client: &reqwest::blocking::Client,
+    flavor: &ApiFlavor,
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let Some(url) = &flavor.graphql_url else {
+        Err("The GraphQL API is not available")?
+    };
+    info!("POST {}", url);
+
+    let request = serde_json::json!({ "query": query, "variables": variables });
+    let response = client.post(url.clone()).json(&request).send()?;
+    debug!("Response: {:?}", &response);
+
+    if !response.status().is_success() {
+        Err(HttpError::new(response.status(), "GraphQL request failed"))?
+    }
+    let mut value: serde_json::Value = serde_json::from_str(&response.text()?)?;
+    if let Some(errors) = value.get("errors") {
+        Err(format!("GraphQL request failed: {errors}"))?
+    }
+    Ok(value["data"].take())
+}
+
+fn mark_notification_done(client: &reqwest::blocking::Client, flavor: &ApiFlavor, id: &str) -> Result<()> {
+    let url = flavor.rest_url.join(&format!("notifications/threads/{id}")).unwrap();
+
+    // Without support for "done", fall back to marking the thread as read,
+    // which at least removes it from the list of unread notifications.
+    let response = if flavor.notification_done {
+        info!("DELETE {}", url);
+        client.delete(url).send()?
+    } else {
+        info!("PATCH {}", url);
+        client.patch(url).send()?
+    };
     debug!("Response: {:?}", &response);
 
     if response.status().is_success() {
         Ok(())
     } else {
-        Err(format!(
-            "Failed to mark notification thread {id} as done: HTTP {}",
-            response.status()
-        ))?
+        let message = format!("Failed to mark notification thread {id} as done");
+        Err(HttpError::new(response.status(), message))?
     }
 }
 
@@ -70,9 +1092,29 @@ fn unsubscribe(client: &reqwest::blocking::Client, url_api: &Url, id: &str) -> R
     if response.status().is_success() {
         Ok(())
     } else {
-        Err(format!(
-            "Failed to unsubscribe from notification thread {id}: HTTP {}",
-            response.status()
-        ))?
+        let message = format!("Failed to unsubscribe from notification thread {id}");
+        Err(HttpError::new(response.status(), message))?
+    }
+}
+
+fn add_comment(
+    client: &reqwest::blocking::Client,
+    url_api: &Url,
+    owner: &str,
+    name: &str,
+    number: u64,
+    body: &str,
+) -> Result<()> {
+    let url = url_api.join(&format!("repos/{owner}/{name}/issues/{number}/comments")).unwrap();
+    info!("POST {}", url);
+
+    let response = client.post(url).json(&serde_json::json!({ "body": body })).send()?;
+    debug!("Response: {:?}", &response);
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let message = format!("Failed to comment on {owner}/{name}#{number}");
+        Err(HttpError::new(response.status(), message))?
     }
 }
@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! The API endpoints that responses are fetched from.
+//!
+//! An [`Endpoint`] knows everything about a request that doesn't depend on
+//! the type of the response: the path of the REST request or the GraphQL
+//! query, how the results are paginated, and how they are cached. Its key
+//! identifies the response in the in-memory and on-disk caches and in
+//! recorded fixtures, so it must stay stable across versions.
+
+use std::time::Duration;
+
+/// How long lists that change without any action of the user are shown
+/// before they are refreshed in the background.
+const VOLATILE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How long the results of CI are shown before they are refreshed. Checks
+/// finish at any time, and waiting for them is common.
+const CI_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// How long the discussion and the contents of pull requests and issues are
+/// shown before they are refreshed.
+const ACTIVITY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// How long things that rarely change, like users, organizations, and the
+/// settings of repositories, are shown before they are refreshed.
+const SETTINGS_TTL: Duration = Duration::from_secs(60 * 60);
+
+const REVIEW_THREADS_QUERY: &str = "query($owner: String!, $name: String!, $number: Int!) {
+    repository(owner: $owner, name: $name) {
+        pullRequest(number: $number) {
+            reviewThreads(first: 100) {
+                nodes {
+                    id isResolved isOutdated path line
+                    comments(first: 50) {
+                        nodes { author { login } body createdAt diffHunk url }
+                    }
+                }
+            }
+        }
+    }
+}";
+
+const PROJECTS_QUERY: &str = "query($owner: String!, $name: String!) {
+    repository(owner: $owner, name: $name) {
+        projectsV2(first: 50, orderBy: {field: NUMBER, direction: ASC}) {
+            nodes { id number title closed }
+        }
+    }
+}";
+
+const PROJECT_BOARD_QUERY: &str = "query($id: ID!) {
+    node(id: $id) {
+        ... on ProjectV2 {
+            title
+            statusField: field(name: \"Status\") {
+                ... on ProjectV2SingleSelectField { id options { id name } }
+            }
+            items(first: 100) {
+                nodes {
+                    id
+                    status: fieldValueByName(name: \"Status\") {
+                        ... on ProjectV2ItemFieldSingleSelectValue { optionId }
+                    }
+                    content {
+                        __typename
+                        ... on Issue {
+                            number title url repository { nameWithOwner }
+                        }
+                        ... on PullRequest {
+                            number title url repository { nameWithOwner }
+                        }
+                        ... on DraftIssue { title }
+                    }
+                }
+            }
+        }
+    }
+}";
+
+const MERGED_PULLS_QUERY: &str = "query($query: String!, $cursor: String) {
+    search(query: $query, type: ISSUE, first: 100, after: $cursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+            ... on PullRequest {
+                number author { login } createdAt mergedAt
+                reviews(first: 20) { nodes { author { login } submittedAt } }
+            }
+        }
+    }
+}";
+
+/// How the results of an endpoint are paginated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pagination {
+    /// The response is a single item.
+    None,
+
+    /// Only the first page of a list is requested, with the given number of
+    /// results, or with as many as the server chooses if None.
+    FirstPage(Option<usize>),
+
+    /// All pages of a list are requested one by one, with the given number of
+    /// results per page. The endpoint has the number of the page, starting at
+    /// 1.
+    Pages(usize),
+
+    /// The response is one page of results, and the endpoint of the next page
+    /// has the cursor that the response ends with.
+    Cursor,
+}
+
+/// An API endpoint, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    Pull { owner: String, name: String, number: u64 },
+    PullFiles { owner: String, name: String, number: u64 },
+    Reviews { owner: String, name: String, number: u64 },
+    ReviewComments { owner: String, name: String, number: u64, review: u64 },
+    Issue { owner: String, name: String, number: u64 },
+    IssueComments { owner: String, name: String, number: u64 },
+    Timeline { owner: String, name: String, number: u64, page: u32 },
+    User { login: String },
+    UserOrgs { login: String },
+    Repository { owner: String, name: String },
+    CombinedStatus { owner: String, name: String, git_ref: String },
+    CheckRuns { owner: String, name: String, git_ref: String },
+    Branch { owner: String, name: String, branch: String },
+    BranchProtection { owner: String, name: String, branch: String },
+    BranchRules { owner: String, name: String, branch: String },
+    Releases { owner: String, name: String, count: usize },
+    Tags { owner: String, name: String },
+    Milestones { owner: String, name: String },
+
+    /// Open issues and pull requests of a milestone, or of those without a
+    /// milestone if `milestone` is None.
+    MilestoneIssues { owner: String, name: String, milestone: Option<u64> },
+
+    MyOrgs,
+    MyTeams,
+    Notifications,
+    NotificationThread { id: String },
+
+    /// Search for issues and pull requests. The query is in the syntax of
+    /// the search box on the website, e.g. `is:open author:someone`.
+    Search { query: String, sort: Option<&'static str>, per_page: usize },
+
+    ReviewThreads { owner: String, name: String, number: u64 },
+    Projects { owner: String, name: String },
+    ProjectBoard { project_id: String },
+
+    /// A page of the pull requests that were merged since a date, e.g.
+    /// "2024-03-01". The first page has no cursor.
+    MergedPulls { owner: String, name: String, since: String, cursor: Option<String> },
+}
+impl Endpoint {
+    /// The key of the endpoint in the caches.
+    ///
+    /// For REST endpoints, this is the URL relative to the API root. GraphQL
+    /// endpoints all share one URL, so they get a key of their own.
+    pub fn key(&self) -> String {
+        use Endpoint::*;
+        let (mut key, mut query) = match self {
+            Pull { owner, name, number } => {
+                (format!("repos/{owner}/{name}/pulls/{number}"), vec![])
+            }
+            PullFiles { owner, name, number } => {
+                (format!("repos/{owner}/{name}/pulls/{number}/files"), vec![])
+            }
+            Reviews { owner, name, number } => {
+                (format!("repos/{owner}/{name}/pulls/{number}/reviews"), vec![])
+            }
+            ReviewComments { owner, name, number, review } => (
+                format!("repos/{owner}/{name}/pulls/{number}/reviews/{review}/comments"),
+                vec![],
+            ),
+            Issue { owner, name, number } => {
+                (format!("repos/{owner}/{name}/issues/{number}"), vec![])
+            }
+            IssueComments { owner, name, number } => {
+                (format!("repos/{owner}/{name}/issues/{number}/comments"), vec![])
+            }
+            Timeline { owner, name, number, page } => {
+                let params = if *page > 1 { vec![format!("page={page}")] } else { vec![] };
+                (format!("repos/{owner}/{name}/issues/{number}/timeline"), params)
+            }
+            User { login } => (format!("users/{login}"), vec![]),
+            UserOrgs { login } => (format!("users/{login}/orgs"), vec![]),
+            Repository { owner, name } => (format!("repos/{owner}/{name}"), vec![]),
+            CombinedStatus { owner, name, git_ref } => {
+                (format!("repos/{owner}/{name}/commits/{git_ref}/status"), vec![])
+            }
+            CheckRuns { owner, name, git_ref } => {
+                (format!("repos/{owner}/{name}/commits/{git_ref}/check-runs"), vec![])
+            }
+            Branch { owner, name, branch } => {
+                (format!("repos/{owner}/{name}/branches/{branch}"), vec![])
+            }
+            BranchProtection { owner, name, branch } => {
+                (format!("repos/{owner}/{name}/branches/{branch}/protection"), vec![])
+            }
+            BranchRules { owner, name, branch } => {
+                (format!("repos/{owner}/{name}/rules/branches/{branch}"), vec![])
+            }
+            Releases { owner, name, .. } => (format!("repos/{owner}/{name}/releases"), vec![]),
+            Tags { owner, name } => (format!("repos/{owner}/{name}/tags"), vec![]),
+            Milestones { owner, name } => {
+                (format!("repos/{owner}/{name}/milestones"), vec!["state=open".into()])
+            }
+            MilestoneIssues { owner, name, milestone } => {
+                let milestone = milestone.map_or("none".into(), |number| number.to_string());
+                let query = vec![format!("milestone={milestone}"), "state=open".into()];
+                (format!("repos/{owner}/{name}/issues"), query)
+            }
+            MyOrgs => ("user/orgs".into(), vec![]),
+            MyTeams => ("user/teams".into(), vec![]),
+            Notifications => ("notifications".into(), vec![]),
+            NotificationThread { id } => (format!("notifications/threads/{id}"), vec![]),
+            Search { query, sort, .. } => {
+                let mut params = vec![format!("q={}", encode_search(query))];
+                params.extend(sort.map(|sort| format!("sort={sort}")));
+                ("search/issues".into(), params)
+            }
+            ReviewThreads { owner, name, number } => {
+                return format!("graphql/review-threads/{owner}/{name}/{number}");
+            }
+            Projects { owner, name } => return format!("graphql/projects/{owner}/{name}"),
+            ProjectBoard { project_id } => return format!("graphql/project-board/{project_id}"),
+            MergedPulls { owner, name, since, cursor } => {
+                let cursor = cursor.as_deref().unwrap_or("first");
+                return format!("graphql/merged-pulls/{owner}/{name}/{since}/{cursor}");
+            }
+        };
+        if let Pagination::FirstPage(Some(per_page)) | Pagination::Pages(per_page) =
+            self.pagination()
+        {
+            query.push(format!("per_page={per_page}"));
+        }
+        if !query.is_empty() {
+            key += "?";
+            key += &query.join("&");
+        }
+        key
+    }
+
+    /// The body of the request to the GraphQL API, or None for REST endpoints.
+    pub fn graphql(&self) -> Option<String> {
+        use Endpoint::*;
+        let (query, variables) = match self {
+            ReviewThreads { owner, name, number } => (
+                REVIEW_THREADS_QUERY,
+                serde_json::json!({ "owner": owner, "name": name, "number": number }),
+            ),
+            Projects { owner, name } => {
+                (PROJECTS_QUERY, serde_json::json!({ "owner": owner, "name": name }))
+            }
+            ProjectBoard { project_id } => {
+                (PROJECT_BOARD_QUERY, serde_json::json!({ "id": project_id }))
+            }
+            MergedPulls { owner, name, since, cursor } => {
+                let search = format!("repo:{owner}/{name} is:pr is:merged merged:>={since}");
+                let variables = serde_json::json!({ "query": search, "cursor": cursor });
+                (MERGED_PULLS_QUERY, variables)
+            }
+            _ => return None,
+        };
+        Some(serde_json::json!({ "query": query, "variables": variables }).to_string())
+    }
+
+    pub fn pagination(&self) -> Pagination {
+        use Endpoint::*;
+        match self {
+            Pull { .. }
+            | Issue { .. }
+            | User { .. }
+            | Repository { .. }
+            | CombinedStatus { .. }
+            | Branch { .. }
+            | BranchProtection { .. }
+            | NotificationThread { .. } => Pagination::None,
+            PullFiles { .. }
+            | CheckRuns { .. }
+            | Tags { .. }
+            | Milestones { .. }
+            | MilestoneIssues { .. }
+            | MyOrgs
+            | MyTeams
+            | ReviewThreads { .. }
+            | ProjectBoard { .. } => Pagination::FirstPage(Some(100)),
+            Projects { .. } => Pagination::FirstPage(Some(50)),
+            Timeline { .. } => Pagination::Pages(100),
+            Releases { count, .. } => Pagination::FirstPage(Some(*count)),
+            Search { per_page, .. } => Pagination::FirstPage(Some(*per_page)),
+            Reviews { .. }
+            | ReviewComments { .. }
+            | IssueComments { .. }
+            | UserOrgs { .. }
+            | BranchRules { .. }
+            | Notifications => Pagination::FirstPage(None),
+            MergedPulls { .. } => Pagination::Cursor,
+        }
+    }
+
+    /// How long a response is used before it is refreshed in the background,
+    /// or None if it is only refreshed when an edit affects it or on request
+    /// of the user.
+    ///
+    /// Stale responses are still shown until the new ones arrive.
+    pub fn ttl(&self) -> Option<Duration> {
+        use Endpoint::*;
+        match self {
+            Notifications | Search { .. } => Some(VOLATILE_TTL),
+            CombinedStatus { .. } | CheckRuns { .. } => Some(CI_TTL),
+            Pull { .. }
+            | PullFiles { .. }
+            | Reviews { .. }
+            | ReviewComments { .. }
+            | Issue { .. }
+            | IssueComments { .. }
+            | Timeline { .. }
+            | MilestoneIssues { .. }
+            | ReviewThreads { .. }
+            | ProjectBoard { .. } => Some(ACTIVITY_TTL),
+            User { .. }
+            | UserOrgs { .. }
+            | Repository { .. }
+            | Branch { .. }
+            | BranchProtection { .. }
+            | BranchRules { .. }
+            | Releases { .. }
+            | Tags { .. }
+            | Milestones { .. }
+            | MyOrgs
+            | MyTeams
+            | Projects { .. } => Some(SETTINGS_TTL),
+            // Threads are only fetched to check the preconditions of edits,
+            // and pages of merged pull requests are fetched once per day.
+            NotificationThread { .. } | MergedPulls { .. } => None,
+        }
+    }
+
+    /// Whether responses are kept in the on-disk cache, so that they can be
+    /// shown right away in the next session.
+    ///
+    /// Pages of merged pull requests are keyed by date and would pile up.
+    pub fn disk_cache(&self) -> bool {
+        !matches!(self, Endpoint::MergedPulls { .. })
+    }
+}
+
+/// Encode a search query for the `q` parameter. Qualifiers like `repo:o/n`
+/// are kept readable.
+fn encode_search(query: &str) -> String {
+    let mut encoded = String::new();
+    for byte in query.bytes() {
+        match byte {
+            b' ' => encoded.push('+'),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b':' | b'/' => {
+                encoded.push(byte as char)
+            }
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
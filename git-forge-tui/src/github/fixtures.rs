@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Recorded API responses for demos, tests, and debugging.
+//!
+//! When recording, every response that the client receives is also written to
+//! a fixture directory, one file per URL. File names are derived from the URL
+//! with `portable_file_name`, so that recordings can be shared between
+//! platforms. When replaying, the client serves
+//! requests only from that directory and never touches the network. Edits are
+//! accepted but not committed, so replaying is read-only.
+//!
+//! Requests that returned 404 are recorded as an empty file with the suffix
+//! `.404`.
+
+use std::{any::Any, path::PathBuf};
+
+use vctools_utils::files::portable_file_name;
+
+use super::{DynParser, Response};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Record responses into the directory, in addition to normal operation.
+    Record(PathBuf),
+
+    /// Serve responses from the directory.
+    Replay(PathBuf),
+}
+impl FixtureMode {
+    /// The same mode in a subdirectory, e.g. per host.
+    pub fn join(&self, name: &str) -> Self {
+        match self {
+            FixtureMode::Record(dir) => FixtureMode::Record(dir.join(name)),
+            FixtureMode::Replay(dir) => FixtureMode::Replay(dir.join(name)),
+        }
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        match self {
+            FixtureMode::Record(dir) | FixtureMode::Replay(dir) => dir,
+        }
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, FixtureMode::Replay(_))
+    }
+
+    pub(super) fn file_for_url(&self, url: &str) -> PathBuf {
+        self.dir().join(portable_file_name(url))
+    }
+
+    fn not_found_file_for_url(&self, url: &str) -> PathBuf {
+        self.dir().join(format!("{}.404", portable_file_name(url)))
+    }
+
+    /// File name used by older recordings, which only works on Unix-like
+    /// systems.
+    fn legacy_file_for_url(&self, url: &str) -> PathBuf {
+        self.dir().join(url.replace('/', "%"))
+    }
+
+    pub(super) fn record_not_found(&self, url: &str) -> std::io::Result<()> {
+        std::fs::write(self.not_found_file_for_url(url), b"")
+    }
+
+    /// Serve a request from the recorded responses.
+    pub(super) fn replay(
+        &self,
+        url: &str,
+        parser: Box<dyn DynParser>,
+    ) -> Response<Box<dyn Any + Send + Sync>> {
+        if self.not_found_file_for_url(url).exists() {
+            return Response::NotFound;
+        }
+        let mut file = self.file_for_url(url);
+        if !file.exists() && cfg!(unix) {
+            let legacy = self.legacy_file_for_url(url);
+            if legacy.exists() {
+                file = legacy;
+            }
+        }
+        match std::fs::read_to_string(file) {
+            Ok(text) => {
+                let parsed = parser.parse(&text);
+                super::log_parse_warnings(url);
+                match parsed {
+                    Ok(parsed) => Response::Ok(parsed),
+                    Err(err) => Response::Err(format!("Error parsing recorded response: {}", err)),
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Response::Err(format!("No recorded response for {url}"))
+            }
+            Err(err) => Response::Err(format!("Error reading recorded response: {}", err)),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Differences between github.com and GitHub Enterprise Server instances.
+//!
+//! GHE serves its API under a path prefix (typically `/api/v3`), may lag
+//! behind github.com in supported REST API versions, and older releases lack
+//! some endpoints altogether. The flavor is detected from the API URL and can
+//! be overridden per host in github.toml.
+
+use reqwest::Url;
+use serde::Deserialize;
+
+use vctools_utils::prelude::*;
+
+use super::Host;
+
+/// The REST API version that we are written against.
+pub const DEFAULT_REST_VERSION: &str = "2022-11-28";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ApiKind {
+    /// Detect from the API URL.
+    #[serde(rename = "auto")]
+    #[default]
+    Auto,
+    #[serde(rename = "github")]
+    GitHub,
+    #[serde(rename = "enterprise")]
+    Enterprise,
+}
+
+/// Effective API capabilities of a host.
+#[derive(Debug, Clone)]
+pub struct ApiFlavor {
+    pub kind: ApiKind,
+
+    /// Base URL of the REST API. Always ends in a slash so that relative
+    /// request paths can be joined onto it.
+    pub rest_url: Url,
+
+    /// Value of the `X-GitHub-Api-Version` header, if one should be sent.
+    pub rest_version: Option<String>,
+
+    /// URL of the GraphQL endpoint, if GraphQL is available.
+    pub graphql_url: Option<Url>,
+
+    /// Whether notification threads can be marked as done. If not, they are
+    /// only marked as read. Defaults to true for GHE as well, unless the host
+    /// configuration says otherwise.
+    pub notification_done: bool,
+}
+impl ApiFlavor {
+    pub fn new(host: &Host) -> Result<Self> {
+        let mut rest_url = Url::parse(&host.api)?;
+        if !rest_url.path().ends_with('/') {
+            rest_url.set_path(&format!("{}/", rest_url.path()));
+        }
+
+        let kind = match host.api_kind {
+            ApiKind::Auto => {
+                if rest_url.host_str() == Some("api.github.com") {
+                    ApiKind::GitHub
+                } else {
+                    ApiKind::Enterprise
+                }
+            }
+            kind => kind,
+        };
+
+        let rest_version = match &host.api_version {
+            Some(version) if version.is_empty() => None,
+            Some(version) => Some(version.clone()),
+            None => Some(DEFAULT_REST_VERSION.to_string()),
+        };
+
+        let graphql_url = match kind {
+            _ if host.graphql == Some(false) => None,
+            ApiKind::Enterprise => {
+                // https://ghe.example/api/v3/ -> https://ghe.example/api/graphql
+                let prefix = rest_url.path().trim_end_matches('/');
+                let prefix = prefix.strip_suffix("/v3").unwrap_or(prefix);
+                let mut url = rest_url.clone();
+                url.set_path(&format!("{prefix}/graphql"));
+                Some(url)
+            }
+            _ => Some(rest_url.join("graphql")?),
+        };
+
+        Ok(Self {
+            kind,
+            rest_url,
+            rest_version,
+            graphql_url,
+            notification_done: host.notification_done.unwrap_or(true),
+        })
+    }
+}
@@ -3,25 +3,46 @@
 use std::{
     any::Any,
     borrow::Cow,
-    collections::{hash_map, HashMap},
+    collections::{hash_map, HashMap, HashSet, VecDeque},
+    io::Read,
     ops::DerefMut,
-    path::{Path, PathBuf},
-    sync::{Arc, Condvar, Mutex},
-    time::Instant,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, TryLockError,
+    },
+    time::{Duration, Instant},
 };
 
 use itertools::Itertools;
+use diff_modulo_base::git_core;
 use log::{debug, error, info, warn};
 use reqwest::{header, StatusCode, Url};
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use vctools_utils::{files, prelude::*};
 use vctuik::signals::MergeWakeupSignal;
 
 pub mod api;
 pub mod connections;
+pub mod disk_cache;
 pub mod edit;
-
-use edit::Edit;
+pub mod endpoint;
+pub mod fixtures;
+pub mod flavor;
+pub mod prefetch;
+pub mod request_log;
+pub mod retry;
+pub mod token;
+
+use disk_cache::DiskCache;
+use edit::{CommitFailure, Edit, Operation, Precondition};
+pub use endpoint::Endpoint;
+use endpoint::Pagination;
+use fixtures::FixtureMode;
+use flavor::{ApiFlavor, ApiKind};
+use request_log::RequestLog;
+use retry::RetryPolicy;
+use token::{TokenCheck, TokenStatus};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Host {
@@ -31,18 +52,179 @@ pub struct Host {
     pub token: String,
     #[serde(default)]
     pub alias: Vec<String>,
+
+    /// Whether this is github.com or a GitHub Enterprise Server instance.
+    /// Detected from the API URL by default.
+    #[serde(default)]
+    pub api_kind: ApiKind,
+
+    /// REST API version to request. An empty string suppresses the version
+    /// header, for servers that predate API versioning.
+    #[serde(default)]
+    pub api_version: Option<String>,
+
+    /// Set to false if the GraphQL API is unavailable.
+    #[serde(default)]
+    pub graphql: Option<bool>,
+
+    /// Whether notification threads can be marked as done (as opposed to
+    /// only read). Set to false for older GitHub Enterprise Server releases
+    /// that lack this.
+    #[serde(default)]
+    pub notification_done: Option<bool>,
+
+    /// Proxy URL for all requests to this host. By default, the usual proxy
+    /// environment variables are honored; "none" disables proxies entirely.
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// Comma-separated list of hosts / domains / IP ranges to exclude from
+    /// `proxy`, in the syntax of the `NO_PROXY` environment variable.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+
+    /// Additional root certificates (PEM files) to trust.
+    #[serde(default)]
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// Disable TLS certificate verification. Dangerous; only intended as a
+    /// last resort for debugging.
+    #[serde(default)]
+    pub tls_insecure: bool,
+
+    #[serde(flatten)]
+    pub retry: RetryPolicy,
 }
 impl Host {
     pub fn matches_host(&self, host: &str) -> bool {
-        self.host == host || self.alias.iter().any(|a| a == host)
+        self.host.eq_ignore_ascii_case(host)
+            || self.alias.iter().any(|a| a.eq_ignore_ascii_case(host))
+    }
+
+    /// Find the host that a remote URL refers to.
+    ///
+    /// The host name of the URL is matched as-is first, and then after
+    /// resolving aliases from the user's SSH configuration.
+    pub fn find_for_url<'a>(hosts: &'a [Host], url: &git_core::Url) -> Option<&'a Host> {
+        let hostname = url.hostname()?;
+        hosts.iter().find(|host| host.matches_host(hostname)).or_else(|| {
+            let resolved = url.resolved_hostname()?;
+            hosts.iter().find(|host| host.matches_host(&resolved))
+        })
+    }
+
+    /// Prepare an HTTP client with the connection settings (proxy, TLS) of
+    /// this host.
+    pub(crate) fn http_client_builder(&self) -> Result<reqwest::blocking::ClientBuilder> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .connect_timeout(self.retry.connect_timeout())
+            .timeout(self.retry.request_timeout());
+
+        match self.proxy.as_deref() {
+            None => {}
+            Some("none") => builder = builder.no_proxy(),
+            Some(proxy) => {
+                let proxy = try_forward(
+                    || Ok(reqwest::Proxy::all(proxy)?),
+                    || format!("invalid proxy for {}: {}", self.host, proxy),
+                )?;
+                let no_proxy = self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string);
+                builder = builder.proxy(proxy.no_proxy(no_proxy));
+            }
+        }
+
+        for path in &self.ca_certificates {
+            let certificate = try_forward(
+                || Ok(reqwest::Certificate::from_pem(&files::read_bytes(path)?)?),
+                || format!("failed to load CA certificate {}", path.display()),
+            )?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        if self.tls_insecure {
+            warn!("TLS certificate verification is disabled for {}", self.host);
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+}
+
+/// Rate limit status as reported by the most recent response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+
+    /// Unix timestamp at which the limit resets.
+    pub reset: i64,
+}
+impl RateLimit {
+    fn from_headers(headers: &header::HeaderMap) -> Option<Self> {
+        let get = |name: &str| headers.get(name)?.to_str().ok()?.parse().ok();
+        Some(Self {
+            limit: get("x-ratelimit-limit")?,
+            remaining: get("x-ratelimit-remaining")?,
+            reset: get("x-ratelimit-reset")? as i64,
+        })
     }
 }
 
+/// Counters since the client was created.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientStats {
+    /// Requests handled by the helper thread, and how many of them failed.
+    pub requests: u64,
+    pub errors: u64,
+
+    /// Lookups that needed a new response, counted by whether a cached one
+    /// (from memory or disk) could be shown until it arrives. Lookups of
+    /// fresh responses, e.g. when a frame is built again, are not counted.
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+
+    /// Responses that are currently held in the in-memory cache.
+    pub cache_entries: usize,
+
+    /// How often the lock of the in-memory cache was already held by another
+    /// thread, and how long was spent waiting for it in total.
+    pub cache_lock_contended: u64,
+    pub cache_lock_wait: Duration,
+}
+
+/// Number of requests that are kept for the traffic inspector.
+const TRAFFIC_HISTORY: usize = 200;
+
+/// A request made by the helper thread, for debugging.
+#[derive(Debug, Clone)]
+pub struct TrafficEntry {
+    pub url: String,
+    pub started: chrono::DateTime<chrono::Local>,
+    pub duration: Duration,
+
+    /// HTTP status, if a response was received.
+    pub status: Option<u16>,
+
+    /// Whether a response from the on-disk cache was available while the
+    /// request was in flight.
+    pub cached: bool,
+
+    pub bytes: usize,
+
+    /// Response headers.
+    pub headers: Vec<(String, String)>,
+
+    /// The raw response body, or a description of the error.
+    pub body: Arc<str>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
     host: Host,
     offline: bool,
     cache_dir: Option<PathBuf>,
+    state_dir: Option<PathBuf>,
+    fixtures: Option<FixtureMode>,
 }
 impl ClientConfig {
     pub fn offline(self, offline: bool) -> Self {
@@ -60,18 +242,45 @@ impl ClientConfig {
         Self { cache_dir, ..self }
     }
 
-    pub fn new(self) -> Result<Client> {
-        let url_api = Url::parse(&self.host.api)?;
+    /// Directory for persistent state such as the queue of edits that have
+    /// not been committed yet.
+    pub fn maybe_state_dir(self, state_dir: Option<PathBuf>) -> Self {
+        Self { state_dir, ..self }
+    }
+
+    /// Record responses to or replay them from a fixture directory.
+    pub fn maybe_fixtures(self, fixtures: Option<FixtureMode>) -> Self {
+        Self { fixtures, ..self }
+    }
+
+    pub fn new(mut self) -> Result<Client> {
+        let flavor = ApiFlavor::new(&self.host)?;
+
+        if let Some(fixtures) = &self.fixtures {
+            if fixtures.is_replay() {
+                // Keep the replay independent of the real cache and edit queue.
+                self.offline = false;
+                self.cache_dir = None;
+                self.state_dir = None;
+            } else {
+                std::fs::create_dir_all(fixtures.dir())?;
+            }
+        }
 
-        if let Some(cache_dir) = &self.cache_dir {
-            std::fs::create_dir_all(cache_dir)?;
+        let disk = self.cache_dir.clone().map(DiskCache::open).transpose()?;
+        if let Some(state_dir) = &self.state_dir {
+            std::fs::create_dir_all(state_dir)?;
         }
 
         let mut client = Client {
             config: self,
-            url_api,
-            cache: Arc::new(Cache::default()),
+            flavor,
+            cache: Arc::new(Cache {
+                disk,
+                ..Default::default()
+            }),
             helper: None,
+            helper_thread: None,
             frame: None,
         };
 
@@ -82,10 +291,60 @@ impl ClientConfig {
         Ok(client)
     }
 
-    fn cache_for_url(&self, url: &str) -> Option<PathBuf> {
-        self.cache_dir
-            .as_ref()
-            .map(|dir| dir.join(url.replace('/', "%")))
+    fn edit_queue_file(&self) -> Option<PathBuf> {
+        self.state_dir.as_ref().map(|dir| dir.join("edits.json"))
+    }
+}
+
+/// How long a destructive action can be undone.
+pub const UNDO_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Longest delay before an edit is retried after a transient failure.
+const MAX_EDIT_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// Most pages that are requested of a list that is fetched page by page.
+const MAX_PAGES: u32 = 10;
+
+/// Called on a background thread once an edit has been committed.
+pub type CommitCallback = Box<dyn FnOnce() + Send>;
+
+/// An edit that is queued for committing to the server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueuedEdit {
+    /// Identifies the edit for cancellation; unique within a client.
+    pub id: u64,
+    pub edit: Edit,
+
+    /// Server-side state that the edit is based on, if any.
+    #[serde(default)]
+    pub precondition: Option<Precondition>,
+
+    /// Set if the precondition failed. Conflicting edits are not committed
+    /// until the user resolves the conflict.
+    #[serde(default)]
+    pub conflict: Option<String>,
+
+    /// Whether the edit is currently being committed. Such an edit can no
+    /// longer be cancelled.
+    ///
+    /// If the queue is saved in the meantime, e.g. at shutdown, the edit may
+    /// or may not reach the server. It is not committed again automatically
+    /// after a restart.
+    #[serde(default, rename = "maybe_committed")]
+    pub committing: bool,
+
+    /// The edit is held back until this time to allow undoing it, or to
+    /// retry it after a transient failure.
+    #[serde(skip)]
+    pub not_before: Option<Instant>,
+
+    /// Number of transient failures to commit the edit so far.
+    #[serde(skip)]
+    pub failures: u32,
+}
+impl QueuedEdit {
+    fn is_ready(&self, now: Instant) -> bool {
+        self.conflict.is_none() && self.not_before.is_none_or(|t| t <= now)
     }
 }
 
@@ -99,9 +358,10 @@ enum WaitPolicy {
 #[derive(Debug)]
 pub struct Client {
     config: ClientConfig,
-    url_api: Url,
+    flavor: ApiFlavor,
     cache: Arc<Cache>,
     helper: Option<Arc<HelperCtrl>>,
+    helper_thread: Option<std::thread::JoinHandle<()>>,
     frame: Option<WaitPolicy>,
 }
 impl Client {
@@ -110,6 +370,8 @@ impl Client {
             host,
             offline: false,
             cache_dir: None,
+            state_dir: None,
+            fixtures: None,
         }
     }
 
@@ -117,7 +379,31 @@ impl Client {
         &self.config.host
     }
 
+    pub fn flavor(&self) -> &ApiFlavor {
+        &self.flavor
+    }
+
     fn start_thread(&mut self) -> Result<()> {
+        // Restore edits that were not committed during a previous run.
+        let edit_queue_file = self.config.edit_queue_file();
+        let mut edit_requests: Vec<QueuedEdit> = match &edit_queue_file {
+            Some(path) if path.exists() => try_forward(
+                || Ok(serde_json::from_slice(&files::read_bytes(path)?)?),
+                || format!("failed to restore edit queue from {}", path.display()),
+            )?,
+            _ => Vec::new(),
+        };
+        if !edit_requests.is_empty() {
+            info!("Restored {} queued edits", edit_requests.len());
+        }
+        for queued in &mut edit_requests {
+            if queued.committing {
+                queued.committing = false;
+                queued.conflict =
+                    Some("May have been committed before the previous exit".into());
+            }
+        }
+
         let helper = Arc::new(HelperCtrl {
             response_notify: Condvar::new(),
             helper_wakeup: Condvar::new(),
@@ -128,7 +414,16 @@ impl Client {
                 frame_timed_out: false,
                 frame_requests: Vec::new(),
                 backlog_requests: Vec::new(),
-                edit_requests: Vec::new(),
+                in_flight: HashSet::new(),
+                next_edit_id: edit_requests.iter().map(|queued| queued.id + 1).max().unwrap_or(0),
+                edit_requests,
+                edit_queue_file,
+                committed_edits: Vec::new(),
+                commit_callbacks: HashMap::new(),
+                rate_limit: None,
+                traffic: VecDeque::new(),
+                request_log: None,
+                token: TokenCheck::Pending,
                 response_callback: None,
             }),
         });
@@ -136,15 +431,65 @@ impl Client {
 
         let cache = self.cache.clone();
         let config = self.config.clone();
-        let url_api = self.url_api.clone();
+        let flavor = self.flavor.clone();
 
-        std::thread::spawn(move || {
-            run_helper(cache, helper, config, url_api);
-        });
+        self.helper_thread = Some(std::thread::spawn(move || {
+            run_helper(cache, helper, config, flavor);
+        }));
 
         Ok(())
     }
 
+    /// Stop the helper thread.
+    ///
+    /// Queued edits are committed right away, skipping the undo grace period,
+    /// for up to `timeout`. Edits that could not be committed in time remain
+    /// in the persisted queue and are retried on the next start.
+    ///
+    /// Returns the number of edits that were left uncommitted.
+    pub fn shutdown(&mut self, timeout: Duration) -> usize {
+        let Some(helper) = self.helper.take() else {
+            return 0;
+        };
+        let deadline = Instant::now() + timeout;
+
+        let mut state = helper.state.lock().unwrap();
+        for queued in &mut state.edit_requests {
+            queued.not_before = None;
+        }
+        helper.helper_wakeup.notify_all();
+
+        loop {
+            let now = Instant::now();
+            if now >= deadline || !state.edit_requests.iter().any(|queued| queued.is_ready(now)) {
+                break;
+            }
+            state = helper.response_notify.wait_timeout(state, deadline - now).unwrap().0;
+        }
+
+        state.running = false;
+        state.save_edit_queue();
+        let remaining = state.edit_requests.len();
+        helper.helper_wakeup.notify_all();
+        std::mem::drop(state);
+
+        // The helper may be stuck in a request. Don't wait for it beyond the
+        // deadline; the thread ends by itself once the request completes.
+        if let Some(thread) = self.helper_thread.take() {
+            while !thread.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            if thread.is_finished() {
+                let _ = thread.join();
+            }
+        }
+
+        if remaining > 0 {
+            info!("{} edits remain queued for the next start", remaining);
+        }
+        remaining
+    }
+
     pub fn start_frame(&mut self, deadline: Option<Instant>) {
         assert!(self.frame.is_none());
 
@@ -164,11 +509,69 @@ impl Client {
         }
     }
 
+    /// Rate limit status of the most recent response, if any.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.helper.as_ref()?.state.lock().unwrap().rate_limit
+    }
+
+    /// Counters of requests and of the in-memory cache.
+    pub fn stats(&self) -> ClientStats {
+        let cache = &self.cache;
+        ClientStats {
+            requests: cache.requests.load(Ordering::Relaxed),
+            errors: cache.errors.load(Ordering::Relaxed),
+            cache_hits: cache.hits.load(Ordering::Relaxed),
+            cache_misses: cache.misses.load(Ordering::Relaxed),
+            cache_entries: cache.lock().len(),
+            cache_lock_contended: cache.contended.load(Ordering::Relaxed),
+            cache_lock_wait: Duration::from_nanos(cache.wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// The most recent requests, oldest first.
+    pub fn traffic(&self) -> Vec<TrafficEntry> {
+        self.helper.as_ref().map_or_else(Vec::new, |helper| {
+            helper.state.lock().unwrap().traffic.iter().cloned().collect()
+        })
+    }
+
+    /// Log API traffic to the given log, or stop logging it.
+    pub fn set_request_log(&mut self, log: Option<Arc<RequestLog>>) {
+        if let Some(helper) = &self.helper {
+            helper.state.lock().unwrap().request_log = log;
+        }
+    }
+
+    pub fn request_log(&self) -> Option<Arc<RequestLog>> {
+        self.helper.as_ref()?.state.lock().unwrap().request_log.clone()
+    }
+
+    /// Result of the validation of the token, which happens in the
+    /// background when the client starts.
+    pub fn token_check(&self) -> TokenCheck {
+        self.helper
+            .as_ref()
+            .map_or(TokenCheck::Unchecked, |helper| helper.state.lock().unwrap().token.clone())
+    }
+
+    /// Number of requests and edits that have not been handled yet.
+    pub fn pending_tasks(&self) -> usize {
+        let Some(helper) = &self.helper else {
+            return 0;
+        };
+        let state = helper.state.lock().unwrap();
+        state.frame_requests.len()
+            + state.backlog_requests.len()
+            + state.in_flight.len()
+            + state.edit_requests.len()
+    }
+
     pub fn access(&mut self) -> ClientRef<'_> {
         let wait_policy = self.frame.unwrap();
         ClientRef {
             client: self,
             wait_policy,
+            partial: false,
         }
     }
 
@@ -177,10 +580,45 @@ impl Client {
         ClientRef {
             client: self,
             wait_policy: WaitPolicy::Prefetch,
+            partial: false,
         }
     }
 
-    pub fn edit(&mut self, edit: Edit) -> Result<()> {
+    /// Queue an edit. Returns an ID that can be used to cancel or undo it.
+    pub fn edit(&mut self, edit: Edit) -> Result<u64> {
+        self.edit_checked(edit, None)
+    }
+
+    /// Queue an edit that is only committed if the precondition still holds
+    /// at that time.
+    pub fn edit_checked(&mut self, edit: Edit, precondition: Option<Precondition>) -> Result<u64> {
+        Ok(self.edit_batch(vec![(edit, precondition)])?[0])
+    }
+
+    /// Like `edit_checked`, and call `on_committed` once the edit has been
+    /// committed. It is not called if the edit is cancelled or rejected, or if
+    /// it remains queued when the client shuts down.
+    pub fn edit_then(
+        &mut self,
+        edit: Edit,
+        precondition: Option<Precondition>,
+        on_committed: CommitCallback,
+    ) -> Result<u64> {
+        Ok(self.queue_edits(vec![(edit, precondition)], Some(on_committed))?[0])
+    }
+
+    /// Queue several edits at once, e.g. for a batch action on multiple
+    /// notifications. The queue is only saved once and the edits share a
+    /// single grace period.
+    pub fn edit_batch(&mut self, edits: Vec<(Edit, Option<Precondition>)>) -> Result<Vec<u64>> {
+        self.queue_edits(edits, None)
+    }
+
+    fn queue_edits(
+        &mut self,
+        edits: Vec<(Edit, Option<Precondition>)>,
+        mut on_committed: Option<CommitCallback>,
+    ) -> Result<Vec<u64>> {
         assert!(self.frame.is_some());
 
         let Some(helper) = &self.helper else {
@@ -189,26 +627,167 @@ impl Client {
         let mut state = helper.state.lock().unwrap();
 
         {
-            struct ItemGetter<'a> {
-                cache: &'a mut HashMap<String, CacheEntry>,
+            let mut cache = self.cache.lock();
+            for (edit, _) in &edits {
+                edit.apply_optimistic(&mut CacheItemGetter { cache: cache.deref_mut() });
             }
-            impl<'a> edit::ItemGetter for ItemGetter<'a> {
-                fn get(&mut self, url: &str) -> Option<&mut Box<dyn Any + Send + Sync>> {
-                    self.cache.get_mut(url).and_then(|entry| entry.parsed.as_mut())
-                }
+        }
+
+        let now = Instant::now();
+        let mut ids = Vec::new();
+        for (edit, precondition) in edits {
+            let not_before = edit.invert().is_none().then(|| now + UNDO_GRACE_PERIOD);
+            let id = state.next_edit_id;
+            state.next_edit_id += 1;
+            state.edit_requests.push(QueuedEdit {
+                id,
+                edit,
+                precondition,
+                conflict: None,
+                committing: false,
+                not_before,
+                failures: 0,
+            });
+            if let Some(callback) = on_committed.take() {
+                state.commit_callbacks.insert(id, callback);
             }
+            ids.push(id);
+        }
+        state.save_edit_queue();
+
+        helper.helper_wakeup.notify_all();
+
+        Ok(ids)
+    }
 
-            let mut cache = self.cache.cache.lock().unwrap();
-            edit.apply(&mut ItemGetter { cache: cache.deref_mut() });
+    /// Undo an edit: cancel it if it is still queued, or queue its inverse if
+    /// it was committed no longer than the grace period ago.
+    ///
+    /// Returns a description of what was undone.
+    pub fn undo_edit(&mut self, id: u64) -> Result<String> {
+        let inverse = {
+            let Some(helper) = &self.helper else {
+                return Err("Cannot undo edits while offline")?;
+            };
+            let mut state = helper.state.lock().unwrap();
+            if let Some(queued) = state.edit_requests.iter().find(|queued| queued.id == id) {
+                let description = queued.edit.to_string();
+                std::mem::drop(state);
+                self.cancel_edit(id)?;
+                return Ok(description);
+            }
+
+            let now = Instant::now();
+            state
+                .committed_edits
+                .retain(|(_, _, committed)| now - *committed <= UNDO_GRACE_PERIOD);
+            let Some((_, edit, _)) = state.committed_edits.iter().find(|(other, ..)| *other == id)
+            else {
+                Err("Too late to undo")?
+            };
+            edit.invert().ok_or("Edit cannot be undone")?
+        };
+
+        let description = inverse.to_string();
+        self.edit(inverse)?;
+        Ok(description)
+    }
+
+    /// Whether `undo_edit` can still undo an edit: either it is still queued
+    /// and can be cancelled, or it was committed recently and has an inverse.
+    pub fn can_undo(&self, id: u64) -> bool {
+        let Some(helper) = &self.helper else {
+            return false;
+        };
+        let state = helper.state.lock().unwrap();
+        if let Some(queued) = state.edit_requests.iter().find(|queued| queued.id == id) {
+            return !queued.committing;
         }
+        let now = Instant::now();
+        state.committed_edits.iter().any(|(other, edit, committed)| {
+            *other == id && now - *committed <= UNDO_GRACE_PERIOD && edit.invert().is_some()
+        })
+    }
 
-        state.edit_requests.push(edit);
+    /// Edits that have not been committed to the server yet, in the order in
+    /// which they will be committed.
+    pub fn queued_edits(&self) -> Vec<QueuedEdit> {
+        let Some(helper) = &self.helper else {
+            return Vec::new();
+        };
+        helper.state.lock().unwrap().edit_requests.clone()
+    }
+
+    /// Cancel a queued edit before it is committed.
+    ///
+    /// The optimistic update of cached data is reverted by re-fetching the
+    /// affected items.
+    pub fn cancel_edit(&mut self, id: u64) -> Result<()> {
+        let Some(helper) = &self.helper else {
+            return Err("Cannot cancel edits while offline")?;
+        };
+        let mut state = helper.state.lock().unwrap();
+
+        let Some(idx) = state.edit_requests.iter().position(|queued| queued.id == id) else {
+            Err("Edit is no longer queued")?
+        };
+        if state.edit_requests[idx].committing {
+            Err("Edit is already being committed")?
+        }
+        let queued = state.edit_requests.remove(idx);
+        state.commit_callbacks.remove(&id);
+        state.save_edit_queue();
+        std::mem::drop(state);
+
+        info!("Cancelled edit {:?}", queued.edit);
+        self.invalidate_edit(&queued.edit);
+
+        Ok(())
+    }
+
+    /// Whether any queued edit has a conflict that needs to be resolved.
+    pub fn has_edit_conflicts(&self) -> bool {
+        let Some(helper) = &self.helper else {
+            return false;
+        };
+        let state = helper.state.lock().unwrap();
+        state.edit_requests.iter().any(|queued| queued.conflict.is_some())
+    }
+
+    /// Resolve a conflict by committing the edit regardless.
+    pub fn force_edit(&mut self, id: u64) -> Result<()> {
+        let Some(helper) = &self.helper else {
+            return Err("Cannot perform edits while offline")?;
+        };
+        let mut state = helper.state.lock().unwrap();
+
+        let Some(queued) = state.edit_requests.iter_mut().find(|queued| queued.id == id) else {
+            Err("Edit is no longer queued")?
+        };
+        queued.precondition = None;
+        queued.conflict = None;
+        state.save_edit_queue();
 
         helper.helper_wakeup.notify_all();
 
         Ok(())
     }
 
+    /// Request all responses again the next time that they are used, e.g.
+    /// because the user knows that something has changed on the server.
+    ///
+    /// Unlike invalidated responses, the old responses are still shown until
+    /// the new ones arrive.
+    pub fn refresh(&mut self) {
+        for entry in self.cache.lock().values_mut() {
+            entry.stale = true;
+        }
+    }
+
+    fn invalidate_edit(&mut self, edit: &Edit) {
+        self.cache.invalidate_edit(edit);
+    }
+
     pub fn end_frame(&mut self, notify: Option<&MergeWakeupSignal>) {
         assert!(self.frame.is_some());
 
@@ -218,38 +797,258 @@ impl Client {
             let mut state = helper.state.lock().unwrap();
             if state.frame_response_past_timeout == state.frame_number {
                 notify.signal();
-            } else if state.frame_timed_out {
+            } else if state.frame_timed_out || matches!(state.token, TokenCheck::Pending) {
+                // Also refresh when the token check finishes.
                 state.response_callback = Some(notify.clone());
             }
         }
     }
 }
 
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.shutdown(Duration::ZERO);
+    }
+}
+
+struct CacheItemGetter<'a> {
+    cache: &'a mut HashMap<String, CacheEntry>,
+}
+impl<'a> edit::ItemGetter for CacheItemGetter<'a> {
+    fn get(
+        &mut self,
+        endpoint: &Endpoint,
+        clone: edit::CloneItem,
+    ) -> Option<&mut (dyn Any + Send + Sync)> {
+        let entry = self.cache.get_mut(&endpoint.key())?;
+        let parsed = make_mut(entry.parsed.as_mut()?, clone)?;
+        entry.generation = next_generation();
+        Some(parsed)
+    }
+}
+
+struct SingleItemGetter<'a> {
+    url: &'a str,
+    parsed: Option<&'a mut Arc<dyn Any + Send + Sync>>,
+}
+impl<'a> edit::ItemGetter for SingleItemGetter<'a> {
+    fn get(
+        &mut self,
+        endpoint: &Endpoint,
+        clone: edit::CloneItem,
+    ) -> Option<&mut (dyn Any + Send + Sync)> {
+        if self.url == endpoint.key() {
+            make_mut(self.parsed.take()?, clone)
+        } else {
+            None
+        }
+    }
+}
+
+/// Copy-on-write access to a parsed response: views may still hold on to it.
+fn make_mut(
+    parsed: &mut Arc<dyn Any + Send + Sync>,
+    clone: edit::CloneItem,
+) -> Option<&mut (dyn Any + Send + Sync)> {
+    if Arc::get_mut(parsed).is_none() {
+        *parsed = Arc::from(clone(&**parsed)?);
+    }
+    Arc::get_mut(parsed)
+}
+
 trait DynParser: std::fmt::Debug + Send + Sync {
     fn parse(&self, s: &str) -> Result<Box<dyn Any + Send + Sync>>;
+
+    /// Parse from a response that is still being received. Parsers of lists
+    /// can hand off the items received so far to `partial` in the meantime.
+    /// The result always holds all items.
+    fn parse_reader(
+        &self,
+        reader: &mut dyn Read,
+        partial: &mut dyn FnMut(NewItems),
+    ) -> Result<Box<dyn Any + Send + Sync>>;
+}
+
+/// How often partial results of lists are handed off while they are being
+/// received.
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The items of a list that were received since the last handoff.
+struct NewItems {
+    /// The number of items that were handed off before.
+    offset: usize,
+    items: Box<dyn Any + Send + Sync>,
+
+    /// Append `items` to the list of the items that were handed off before.
+    append: fn(&mut Arc<dyn Any + Send + Sync>, Box<dyn Any + Send + Sync>),
+}
+
+fn append_items<T: Clone + Send + Sync + 'static>(
+    list: &mut Arc<dyn Any + Send + Sync>,
+    items: Box<dyn Any + Send + Sync>,
+) {
+    let items = items.downcast::<Vec<T>>().unwrap();
+    if let Some(list) = Arc::get_mut(list) {
+        list.downcast_mut::<Vec<T>>().unwrap().extend(*items);
+    } else {
+        // A caller still holds on to the items that were handed off before.
+        let mut copy = list.downcast_ref::<Vec<T>>().unwrap().clone();
+        copy.extend(*items);
+        *list = Arc::new(copy);
+    }
+}
+
+/// Parser for GraphQL responses. The part of the data that is of interest is
+/// extracted right away, so that it can be shared from the cache.
+struct GraphQlParser<T, U> {
+    extract: fn(T) -> U,
+}
+impl<T, U> std::fmt::Debug for GraphQlParser<T, U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GraphQlParser<{}>", std::any::type_name::<T>())
+    }
+}
+impl<T, U> DynParser for GraphQlParser<T, U>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+    U: Send + Sync + 'static,
+{
+    fn parse(&self, s: &str) -> Result<Box<dyn Any + Send + Sync>> {
+        #[derive(Deserialize)]
+        struct GraphQlError {
+            message: String,
+        }
+        #[derive(Deserialize)]
+        struct GraphQlResponse<T> {
+            data: Option<T>,
+            #[serde(default)]
+            errors: Vec<GraphQlError>,
+        }
+
+        let response: GraphQlResponse<T> = serde_json::from_str(s)?;
+        let Some(data) = response.data else {
+            match response.errors.first() {
+                Some(error) => Err(format!("GraphQL error: {}", error.message))?,
+                None => Err("GraphQL response without data")?,
+            }
+        };
+        // Data that comes with errors is partial, e.g. because some of the
+        // objects that were asked for aren't accessible. Show what there is.
+        for error in &response.errors {
+            api::warning(format!("GraphQL error: {}", error.message));
+        }
+        Ok(Box::new((self.extract)(data)))
+    }
+
+    fn parse_reader(
+        &self,
+        reader: &mut dyn Read,
+        _partial: &mut dyn FnMut(NewItems),
+    ) -> Result<Box<dyn Any + Send + Sync>> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        self.parse(&text)
+    }
+}
+
+/// Parser for JSON arrays that hands off the items received so far. Each
+/// handoff only holds the items that are new since the previous one.
+struct ListParser<T>(std::marker::PhantomData<T>);
+impl<T> std::fmt::Debug for ListParser<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ListParser<{}>", std::any::type_name::<T>())
+    }
+}
+impl<T: DeserializeOwned + Clone + Send + Sync + 'static> DynParser for ListParser<T> {
+    fn parse(&self, s: &str) -> Result<Box<dyn Any + Send + Sync>> {
+        let values: Vec<serde_json::Value> = serde_json::from_str(s)?;
+        let data: Vec<T> = values.into_iter().filter_map(api::tolerant_item).collect();
+        Ok(Box::new(data))
+    }
+
+    fn parse_reader(
+        &self,
+        reader: &mut dyn Read,
+        partial: &mut dyn FnMut(NewItems),
+    ) -> Result<Box<dyn Any + Send + Sync>> {
+        struct Visitor<'a, T> {
+            partial: &'a mut dyn FnMut(NewItems),
+            _marker: std::marker::PhantomData<T>,
+        }
+        impl<'de, T: DeserializeOwned + Clone + Send + Sync + 'static> serde::de::Visitor<'de>
+            for Visitor<'_, T>
+        {
+            type Value = Vec<T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a list")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> std::result::Result<Vec<T>, A::Error> {
+                let mut items = Vec::new();
+                let mut handed_off = 0;
+                let mut last_handoff = Instant::now();
+                while let Some(value) = seq.next_element::<serde_json::Value>()? {
+                    items.extend(api::tolerant_item(value));
+                    if last_handoff.elapsed() >= PARTIAL_INTERVAL && items.len() > handed_off {
+                        (self.partial)(NewItems {
+                            offset: handed_off,
+                            items: Box::new(items[handed_off..].to_vec()),
+                            append: append_items::<T>,
+                        });
+                        handed_off = items.len();
+                        last_handoff = Instant::now();
+                    }
+                }
+                Ok(items)
+            }
+        }
+
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        let visitor = Visitor::<T> {
+            partial,
+            _marker: std::marker::PhantomData,
+        };
+        let data = serde::Deserializer::deserialize_seq(&mut de, visitor)?;
+        de.end()?;
+        Ok(Box::new(data))
+    }
+}
+
+/// Log the warnings of parsing the response for `url`, i.e. what didn't match
+/// the expected schema but was tolerated, so that it shows up in the log view.
+fn log_parse_warnings(url: &str) {
+    let warnings = api::take_warnings();
+    for (warning, count) in warnings.into_iter().counts().into_iter().sorted() {
+        if count > 1 {
+            warn!("{url}: {warning} ({count} times)");
+        } else {
+            warn!("{url}: {warning}");
+        }
+    }
 }
 
 fn load_from_cache(
-    cache_file: &Path,
+    disk: &DiskCache,
+    url: &str,
     parser: &dyn DynParser,
 ) -> Response<Box<dyn Any + Send + Sync>> {
-    if !cache_file.exists() {
+    let Some(bytes) = disk.load(url) else {
         return Response::Pending;
-    }
+    };
 
     let result = || -> Result<_> {
-        let bytes = files::read_bytes(cache_file)?;
         let string = str::from_utf8(&bytes)?;
         Ok(Response::Ok(parser.parse(string)?))
     }();
+    log_parse_warnings(url);
 
     match result {
         Ok(response) => response,
-        Err(err) => Response::Err(format!(
-            "Error reading cache file {}: {}",
-            cache_file.display(),
-            err
-        )),
+        Err(err) => Response::Err(format!("Error reading cached response for {}: {}", url, err)),
     }
 }
 
@@ -257,182 +1056,744 @@ fn load_from_cache(
 pub struct ClientRef<'frame> {
     client: &'frame mut Client,
     wait_policy: WaitPolicy,
+
+    /// Whether the beginning of lists that are still being received is
+    /// returned instead of `Response::Pending`.
+    partial: bool,
 }
 impl<'frame> ClientRef<'frame> {
-    fn get_impl(&self, url: &str, parser: Box<dyn DynParser>) -> Response<()> {
+    /// A reference that only prefetches, for optional details that shouldn't
+    /// hold up the frame.
+    pub fn prefetching(&mut self) -> ClientRef<'_> {
+        ClientRef {
+            client: self.client,
+            wait_policy: WaitPolicy::Prefetch,
+            partial: self.partial,
+        }
+    }
+
+    /// A reference that also returns the beginning of lists that are still
+    /// being received, for views that can show them while they load. See
+    /// `Shared::is_partial`.
+    pub fn partial(&mut self) -> ClientRef<'_> {
+        ClientRef {
+            client: self.client,
+            wait_policy: self.wait_policy,
+            partial: true,
+        }
+    }
+
+    /// If `max_age` is given, a response that was fetched longer ago is
+    /// requested again in the background, and the old response is returned
+    /// in the meantime. The same happens for responses that were marked as
+    /// stale by `Client::refresh`.
+    ///
+    /// `url` is the key of `endpoint`.
+    fn get_impl(
+        &self,
+        endpoint: &Endpoint,
+        url: &str,
+        parser: Box<dyn DynParser>,
+        max_age: Option<Duration>,
+    ) -> Response<()> {
         let frame_number = self.client.helper.as_ref().map(|helper| {
             helper.state.lock().unwrap().frame_number
         });
 
-        let (request_now, request_pending, response) = {
-            let mut cache = self.client.cache.cache.lock().unwrap();
-            match cache.entry(url.into()) {
-                hash_map::Entry::Occupied(mut entry) => {
-                    let entry = entry.get_mut();
-                    if let Some(frame_number) = frame_number {
-                        entry.request_frame = frame_number;
-                    }
-                    (false, entry.fetched.is_none(), entry.response.clone())
-                }
-                hash_map::Entry::Vacant(entry) => {
-                    let mut response = Response::Pending;
-                    if let Some(cache_file) = self.client.config.cache_for_url(url) {
-                        response = load_from_cache(cache_file.as_ref(), parser.as_ref())
-                    }
+        let hit = |entry: &mut CacheEntry| {
+            if let Some(frame_number) = frame_number {
+                entry.request_frame = frame_number;
+            }
+            let stale = entry.fetched.is_some_and(|fetched| {
+                entry.stale || max_age.is_some_and(|max_age| fetched.elapsed() > max_age)
+            });
+            if stale {
+                // Don't request it again while the request is in flight.
+                entry.fetched = Some(Instant::now());
+                entry.stale = false;
+            }
+            let response = if entry.partial && !self.partial {
+                Response::Pending
+            } else {
+                entry.response.clone()
+            };
+            (stale, entry.fetched.is_none(), response, stale)
+        };
+
+        let found = self.client.cache.lock().get_mut(url).map(hit);
+        let (request_now, request_pending, response, cached) = match found {
+            Some(found) => found,
+            None => {
+                // Parse the copy on disk without holding the lock.
+                let mut response = Response::Pending;
+                let disk = self.client.cache.disk.as_ref().filter(|_| endpoint.disk_cache());
+                if let Some(disk) = disk {
+                    response = load_from_cache(disk, url, parser.as_ref())
+                }
+
+                let (parsed, response) = response.split();
+                let cached = parsed.is_some();
+
+                match self.client.cache.lock().entry(url.into()) {
+                    // The helper thread stored a response in the meantime.
+                    hash_map::Entry::Occupied(mut entry) => hit(entry.get_mut()),
+                    hash_map::Entry::Vacant(entry) => {
+                        entry.insert(CacheEntry {
+                            fetched: None,
+                            response: response.clone(),
+                            parsed: parsed.map(Arc::from),
+                            generation: next_generation(),
+                            partial: false,
+                            stale: false,
+                            request_frame: frame_number.unwrap_or(0),
+                        });
+                        (true, false, response, cached)
+                    }
+                }
+            }
+        };
+
+        if request_now {
+            let counter = match response {
+                Response::Pending => &self.client.cache.misses,
+                _ => &self.client.cache.hits,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let Some(helper) = &self.client.helper else {
+            return response.pending_to_offline();
+        };
+        let mut state = helper.state.lock().unwrap();
+        if !state.running {
+            return response.pending_to_offline();
+        }
+
+        if request_now && !state.edit_requests.is_empty() {
+            // The entry may have been loaded from the on-disk cache, which
+            // doesn't reflect queued edits yet.
+            // The entry is gone if it was invalidated in the meantime; it is
+            // requested again anyway.
+            let mut cache = self.client.cache.lock();
+            if let Some(entry) = cache.get_mut(url) {
+                entry.generation = next_generation();
+                let mut parsed = entry.parsed.as_mut();
+                for queued in &state.edit_requests {
+                    queued.edit.apply_optimistic(&mut SingleItemGetter {
+                        url,
+                        parsed: parsed.as_deref_mut(),
+                    });
+                }
+            }
+        }
+
+        let is_prefetch = matches!(self.wait_policy, WaitPolicy::Prefetch);
+        if request_now {
+            let request = Request {
+                url: url.to_string(),
+                parser,
+                graphql: endpoint.graphql(),
+                disk_cache: endpoint.disk_cache(),
+                cached,
+            };
+            state.add_request(request, is_prefetch);
+            helper.helper_wakeup.notify_all();
+        }
+
+        if request_now && !request_pending && !response.is_pending() {
+            // An old response is refreshed. Keep showing it, but rebuild when
+            // the new one arrives.
+            state.frame_timed_out = true;
+            return response;
+        }
+
+        if !request_now && !request_pending {
+            return response;
+        }
+
+        if is_prefetch {
+            if response.is_pending() {
+                state.frame_timed_out = true;
+            }
+            return response;
+        }
+
+        loop {
+            if !state.running {
+                return response.pending_to_offline();
+            }
+
+            match self.wait_policy {
+                WaitPolicy::Wait => {
+                    state = helper.response_notify.wait(state).unwrap();
+                }
+                WaitPolicy::Deadline(deadline) => {
+                    let timed_out;
+                    (state, timed_out) = helper
+                        .response_notify
+                        .wait_timeout(state, deadline - Instant::now())
+                        .unwrap();
+                    if timed_out.timed_out() {
+                        state.frame_timed_out = true;
+                        return response;
+                    }
+                }
+                WaitPolicy::Prefetch => unreachable!(),
+            }
+
+            if let Some(entry) = self.client.cache.lock().get(url) {
+                if entry.fetched.is_some() {
+                    return entry.response.clone();
+                }
+            }
+        }
+    }
+
+    fn get<T: DeserializeOwned + Send + Sync + 'static>(
+        &self,
+        endpoint: Endpoint,
+    ) -> Response<Shared<T>> {
+        self.get_max_age(endpoint, None)
+    }
+
+    /// Like `get`, but re-fetch the response in the background when it is
+    /// older than `max_age`, regardless of the endpoint's TTL.
+    fn get_polled<T: DeserializeOwned + Send + Sync + 'static>(
+        &self,
+        endpoint: Endpoint,
+        max_age: Duration,
+    ) -> Response<Shared<T>> {
+        self.get_max_age(endpoint, Some(max_age))
+    }
+
+    fn get_max_age<T: DeserializeOwned + Send + Sync + 'static>(
+        &self,
+        endpoint: Endpoint,
+        max_age: Option<Duration>,
+    ) -> Response<Shared<T>> {
+        struct Parser<T>(std::marker::PhantomData<T>);
+        impl<T> std::fmt::Debug for Parser<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "Parser<{}>", std::any::type_name::<T>())
+            }
+        }
+        impl<T: DeserializeOwned + Send + Sync + 'static> DynParser for Parser<T> {
+            fn parse(&self, s: &str) -> Result<Box<dyn Any + Send + Sync>> {
+                let data: T = serde_json::from_str(s)?;
+                Ok(Box::new(data))
+            }
+
+            fn parse_reader(
+                &self,
+                reader: &mut dyn Read,
+                _partial: &mut dyn FnMut(NewItems),
+            ) -> Result<Box<dyn Any + Send + Sync>> {
+                let data: T = serde_json::from_reader(reader)?;
+                Ok(Box::new(data))
+            }
+        }
+        self.get_with_parser(endpoint, Box::new(Parser::<T>(std::marker::PhantomData)), max_age)
+    }
+
+    /// Like `get`, but items of the list can be shown while the response is
+    /// still being received.
+    fn get_list<T: DeserializeOwned + Clone + Send + Sync + 'static>(
+        &self,
+        endpoint: Endpoint,
+    ) -> Response<Shared<Vec<T>>> {
+        self.get_with_parser(endpoint, Box::new(ListParser::<T>(std::marker::PhantomData)), None)
+    }
+
+    /// Like `get_list`, but for endpoints with `Pagination::Pages`. Pages are
+    /// requested one after the other until one isn't full.
+    fn get_pages<T: DeserializeOwned + Clone + Send + Sync + 'static>(
+        &self,
+        endpoint: impl Fn(u32) -> Endpoint,
+    ) -> Response<Vec<T>> {
+        let mut items = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let endpoint = endpoint(page);
+            let Pagination::Pages(per_page) = endpoint.pagination() else {
+                panic!("{endpoint:?} is not requested page by page");
+            };
+            let list = match self.get_list::<T>(endpoint) {
+                Response::Ok(list) => list,
+                Response::Pending => return Response::Pending,
+                Response::Offline => return Response::Offline,
+                Response::NotFound => return Response::NotFound,
+                Response::Err(err) => return Response::Err(err),
+            };
+            if list.is_partial() {
+                return Response::Pending;
+            }
+            items.extend(list.iter().cloned());
+            if list.len() < per_page {
+                break;
+            }
+        }
+        Response::Ok(items)
+    }
+
+    /// Run the GraphQL query of the endpoint. Only the part of the data that
+    /// `extract` returns is kept.
+    fn get_graphql<T, U>(&self, endpoint: Endpoint, extract: fn(T) -> U) -> Response<Shared<U>>
+    where
+        T: DeserializeOwned + Send + Sync + 'static,
+        U: Send + Sync + 'static,
+    {
+        self.get_with_parser(endpoint, Box::new(GraphQlParser { extract }), None)
+    }
+
+    fn get_with_parser<T: Send + Sync + 'static>(
+        &self,
+        endpoint: Endpoint,
+        parser: Box<dyn DynParser>,
+        max_age: Option<Duration>,
+    ) -> Response<Shared<T>> {
+        let url = endpoint.key();
+        let max_age = max_age.or(endpoint.ttl());
+
+        // NOTE: The type-erased get_impl can't return the parsed result
+        //       because it is only accessible while the cache is locked.
+        //       We re-lock and re-check, which is not ideal but works because
+        //       entries are only removed by this thread.
+        self.get_impl(&endpoint, &url, parser, max_age).map(|_| {
+            let cache = self.client.cache.lock();
+            let entry = cache.get(&url).unwrap();
+            Shared {
+                value: entry.parsed.clone().unwrap().downcast::<T>().unwrap(),
+                generation: entry.generation,
+                partial: entry.partial,
+            }
+        })
+    }
+
+    pub fn pull<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+    ) -> Response<Shared<api::Pull>> {
+        self.get(Endpoint::Pull {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+        })
+    }
+
+    /// Like [`Self::pull`], but re-fetch the pull request in the background
+    /// when the cached copy is older than `interval`.
+    ///
+    /// Used to notice when a pull request is updated while it is being viewed.
+    pub fn pull_polled<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+        interval: Duration,
+    ) -> Response<Shared<api::Pull>> {
+        let endpoint = Endpoint::Pull {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+        };
+        self.get_polled(endpoint, interval)
+    }
+
+    /// Returns the files changed by a pull request (only the first 100).
+    pub fn pull_files<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+    ) -> Response<Shared<Vec<api::PullFile>>> {
+        self.get_list(Endpoint::PullFiles {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+        })
+    }
+
+    pub fn reviews<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+    ) -> Response<Shared<Vec<api::Review>>> {
+        self.get_list(Endpoint::Reviews {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+        })
+    }
+
+    /// Returns the review threads of a pull request, with their comments.
+    ///
+    /// Only the GraphQL API knows which threads are resolved.
+    pub fn review_threads<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+    ) -> Response<Shared<Vec<api::ReviewThread>>> {
+        let endpoint = Endpoint::ReviewThreads {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+        };
+        self.get_graphql(endpoint, |mut response: api::ReviewThreadsQuery| {
+            response.threads_mut().map(std::mem::take).unwrap_or_default()
+        })
+    }
+
+    /// Returns the line comments of a review.
+    pub fn review_comments<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        pull: u64,
+        review: u64,
+    ) -> Response<Shared<Vec<api::ReviewComment>>> {
+        self.get_list(Endpoint::ReviewComments {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number: pull,
+            review,
+        })
+    }
+
+    /// Returns the comments on an issue (including non-review comments on a PR).
+    pub fn issue_comments<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        number: u64,
+    ) -> Response<Shared<Vec<api::Comment>>> {
+        self.get_list(Endpoint::IssueComments {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            number,
+        })
+    }
+
+    /// Returns the timeline of an issue or pull request.
+    ///
+    /// The timeline is fetched page by page, so it is pending until all pages
+    /// have been received.
+    pub fn timeline<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        number: u64,
+    ) -> Response<Vec<api::TimelineEvent>> {
+        let owner = organization.into().into_owned();
+        let name = gh_repo.into().into_owned();
+        self.get_pages(|page| Endpoint::Timeline {
+            owner: owner.clone(),
+            name: name.clone(),
+            number,
+            page,
+        })
+    }
+
+    /// Returns the public profile of a user.
+    pub fn user<'a>(
+        &self,
+        login: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::UserDetails>> {
+        self.get(Endpoint::User { login: login.into().into_owned() })
+    }
+
+    /// Returns the organizations in which the user's membership is public.
+    pub fn user_orgs<'a>(
+        &self,
+        login: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<Vec<api::Organization>>> {
+        self.get_list(Endpoint::UserOrgs { login: login.into().into_owned() })
+    }
+
+    pub fn repository<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::Repository>> {
+        self.get(Endpoint::Repository {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+        })
+    }
+
+    /// Returns the number of open issues or pull requests in a repository.
+    ///
+    /// `kind` is "issue" or "pr".
+    pub fn open_count<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        kind: &str,
+    ) -> Response<u64> {
+        let (organization, gh_repo) = (organization.into(), gh_repo.into());
+        let endpoint = Endpoint::Search {
+            query: format!("repo:{organization}/{gh_repo} is:{kind} is:open"),
+            sort: None,
+            per_page: 1,
+        };
+        self.get::<api::SearchResult<api::IssueSummary>>(endpoint)
+            .map(|result| result.total_count)
+    }
+
+    /// Returns the combined state of the commit statuses of a ref.
+    pub fn combined_status<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        git_ref: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::CombinedStatus>> {
+        self.get(Endpoint::CombinedStatus {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            git_ref: git_ref.into().into_owned(),
+        })
+    }
+
+    /// Returns the check runs of a ref (only the first 100).
+    pub fn check_runs<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        git_ref: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::CheckRuns>> {
+        self.get(Endpoint::CheckRuns {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            git_ref: git_ref.into().into_owned(),
+        })
+    }
+
+    /// Returns a branch with a summary of its classic protection.
+    pub fn branch<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        branch: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::BranchDetails>> {
+        self.get(Endpoint::Branch {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            branch: branch.into().into_owned(),
+        })
+    }
+
+    /// Returns the classic protection of a branch. Fails unless the user has
+    /// admin access to the repository.
+    pub fn branch_protection<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        branch: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::BranchProtection>> {
+        self.get(Endpoint::BranchProtection {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            branch: branch.into().into_owned(),
+        })
+    }
+
+    /// Returns the rules of rulesets that apply to a branch.
+    pub fn branch_rules<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        branch: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<Vec<api::BranchRule>>> {
+        self.get_list(Endpoint::BranchRules {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            branch: branch.into().into_owned(),
+        })
+    }
+
+    /// Returns the most recent releases of a repository.
+    pub fn releases<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+        count: usize,
+    ) -> Response<Shared<Vec<api::Release>>> {
+        self.get_list(Endpoint::Releases {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+            count,
+        })
+    }
 
-                    let (parsed, response) = response.split();
+    /// Returns the tags of a repository, most recent first.
+    pub fn tags<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<Vec<api::Tag>>> {
+        self.get_list(Endpoint::Tags {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+        })
+    }
 
-                    entry.insert(CacheEntry {
-                        fetched: None,
-                        response: response.clone(),
-                        parsed,
-                        request_frame: frame_number.unwrap_or(0),
-                    });
+    /// Returns the open milestones of a repository.
+    pub fn milestones<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<Vec<api::Milestone>>> {
+        self.get_list(Endpoint::Milestones {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
+        })
+    }
 
-                    (true, false, response)
-                }
-            }
-        };
+    /// Returns the open issues and pull requests of a milestone, or of those
+    /// without a milestone if `milestone` is None.
+    pub fn milestone_issues(
+        &self,
+        organization: &str,
+        gh_repo: &str,
+        milestone: Option<u64>,
+    ) -> Response<Shared<Vec<api::IssueSummary>>> {
+        self.get_list(Endpoint::MilestoneIssues {
+            owner: organization.into(),
+            name: gh_repo.into(),
+            milestone,
+        })
+    }
 
-        let Some(helper) = &self.client.helper else {
-            return response.pending_to_offline();
+    /// Returns the projects of a repository (only the first 50).
+    pub fn projects<'a>(
+        &self,
+        organization: impl Into<Cow<'a, str>>,
+        gh_repo: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<Vec<api::Project>>> {
+        let endpoint = Endpoint::Projects {
+            owner: organization.into().into_owned(),
+            name: gh_repo.into().into_owned(),
         };
-        let mut state = helper.state.lock().unwrap();
-        if !state.running {
-            return response.pending_to_offline();
-        }
-
-        let is_prefetch = matches!(self.wait_policy, WaitPolicy::Prefetch);
-        if request_now {
-            state.add_request(url.to_string(), parser, is_prefetch);
-            helper.helper_wakeup.notify_all();
-        }
+        self.get_graphql(endpoint, |response: api::ProjectsQuery| {
+            response.repository.map(|repo| repo.projects.nodes).unwrap_or_default()
+        })
+    }
 
-        if !request_now && !request_pending {
-            return response;
-        }
+    /// Returns the items of a project (only the first 100) with the columns
+    /// of its "Status" field.
+    pub fn project_board(&self, project_id: &str) -> Response<Shared<api::ProjectBoard>> {
+        let endpoint = Endpoint::ProjectBoard { project_id: project_id.into() };
+        self.get_graphql(endpoint, |response: api::ProjectBoardQuery| {
+            response.node.unwrap_or_default()
+        })
+    }
 
-        if is_prefetch {
-            if response.is_pending() {
-                state.frame_timed_out = true;
-            }
-            return response;
-        }
+    /// Returns a page of the pull requests that were merged into a repository
+    /// since the given date, e.g. "2024-03-01", and with their first reviews.
+    ///
+    /// Pass the `end_cursor` of a page to get the next one. The search API
+    /// returns at most 1000 results.
+    pub fn merged_pulls(
+        &self,
+        organization: &str,
+        gh_repo: &str,
+        since: &str,
+        cursor: Option<&str>,
+    ) -> Response<Shared<api::MergedPullsPage>> {
+        let endpoint = Endpoint::MergedPulls {
+            owner: organization.into(),
+            name: gh_repo.into(),
+            since: since.into(),
+            cursor: cursor.map(Into::into),
+        };
+        self.get_graphql(endpoint, |response: api::MergedPullsQuery| response.search)
+    }
 
-        loop {
-            if !state.running {
-                return response.pending_to_offline();
-            }
+    /// Returns the organizations that the authenticated user belongs to.
+    pub fn my_orgs(&self) -> Response<Shared<Vec<api::Organization>>> {
+        self.get_list(Endpoint::MyOrgs)
+    }
 
-            match self.wait_policy {
-                WaitPolicy::Wait => {
-                    state = helper.response_notify.wait(state).unwrap();
-                }
-                WaitPolicy::Deadline(deadline) => {
-                    let timed_out;
-                    (state, timed_out) = helper
-                        .response_notify
-                        .wait_timeout(state, deadline - Instant::now())
-                        .unwrap();
-                    if timed_out.timed_out() {
-                        state.frame_timed_out = true;
-                        return response;
-                    }
-                }
-                WaitPolicy::Prefetch => unreachable!(),
-            }
+    /// Returns the teams that the authenticated user belongs to, in all
+    /// organizations.
+    pub fn my_teams(&self) -> Response<Shared<Vec<api::Team>>> {
+        self.get_list(Endpoint::MyTeams)
+    }
 
-            if let Some(entry) = self.client.cache.cache.lock().unwrap().get(url) {
-                if entry.fetched.is_some() {
-                    return entry.response.clone();
-                }
-            }
-        }
+    /// Returns open pull requests that request a review from the user
+    /// directly (as opposed to through a team).
+    pub fn user_review_requests<'a>(
+        &self,
+        login: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::SearchResult<api::IssueSummary>>> {
+        self.get(Endpoint::Search {
+            query: format!("is:open is:pr user-review-requested:{}", login.into()),
+            sort: Some("updated"),
+            per_page: 50,
+        })
     }
 
-    fn get<'a, T: DeserializeOwned + Clone + Send + Sync + 'static>(
+    /// Returns open pull requests opened by the user.
+    pub fn user_pulls<'a>(
         &self,
-        url: impl Into<Cow<'a, str>>,
-    ) -> Response<T> {
-        struct Parser<T>(std::marker::PhantomData<T>);
-        impl<T> std::fmt::Debug for Parser<T> {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "Parser<{}>", std::any::type_name::<T>())
-            }
-        }
-        impl<T: DeserializeOwned + Send + Sync + 'static> DynParser for Parser<T> {
-            fn parse(&self, s: &str) -> Result<Box<dyn Any + Send + Sync>> {
-                let data: T = serde_json::from_str(s)?;
-                Ok(Box::new(data))
-            }
-        }
-        let url: String = url.into().into();
-
-        // NOTE: The type-erased get_impl can't return a reference to the parsed result
-        //       because its lifetime ends when the cache lock is dropped.
-        //       We re-lock and re-check, which is not ideal but works because we
-        //       never remove cache entries.
-        //
-        //       It should be possible to fix that once MutexGuard::map becomes stable.
-        self.get_impl(&url, Box::new(Parser::<T>(std::marker::PhantomData)))
-            .map(|_| {
-                self.client
-                    .cache
-                    .cache
-                    .lock()
-                    .unwrap()
-                    .get(&url)
-                    .unwrap()
-                    .parsed
-                    .as_ref()
-                    .unwrap()
-                    .downcast_ref::<T>()
-                    .unwrap()
-                    .clone()
-            })
+        login: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::SearchResult<api::IssueSummary>>> {
+        self.get(Endpoint::Search {
+            query: format!("is:open is:pr author:{}", login.into()),
+            sort: Some("updated"),
+            per_page: 50,
+        })
     }
 
-    pub fn pull<'a>(
+    /// Returns open pull requests that request a review from a team.
+    pub fn team_review_requests<'a>(
         &self,
         organization: impl Into<Cow<'a, str>>,
-        gh_repo: impl Into<Cow<'a, str>>,
-        pull: u64,
-    ) -> Response<api::Pull> {
-        self.get(format!(
-            "repos/{}/{}/pulls/{}",
-            organization.into(),
-            gh_repo.into(),
-            pull
-        ))
+        team_slug: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::SearchResult<api::IssueSummary>>> {
+        self.get(Endpoint::Search {
+            query: format!(
+                "is:open is:pr team-review-requested:{}/{}",
+                organization.into(),
+                team_slug.into(),
+            ),
+            sort: Some("updated"),
+            per_page: 50,
+        })
     }
 
-    pub fn reviews<'a>(
+    /// Returns the most recent pull requests by an author in a repository.
+    pub fn pulls_by_author<'a>(
         &self,
         organization: impl Into<Cow<'a, str>>,
         gh_repo: impl Into<Cow<'a, str>>,
-        pull: u64,
-    ) -> Response<Vec<api::Review>> {
-        self.get(format!(
-            "repos/{}/{}/pulls/{}/reviews",
-            organization.into(),
-            gh_repo.into(),
-            pull
-        ))
+        login: impl Into<Cow<'a, str>>,
+    ) -> Response<Shared<api::SearchResult<api::IssueSummary>>> {
+        self.get(Endpoint::Search {
+            query: format!(
+                "repo:{}/{} type:pr author:{}",
+                organization.into(),
+                gh_repo.into(),
+                login.into(),
+            ),
+            sort: Some("created"),
+            per_page: 5,
+        })
     }
 
-    /// Returns the comments on an issue (including non-review comments on a PR).
-    pub fn issue_comments<'a>(
+    /// Returns pull requests that match a search query, e.g.
+    /// `repo:owner/name is:open label:bug`.
+    pub fn search_pulls(
         &self,
-        organization: impl Into<Cow<'a, str>>,
-        gh_repo: impl Into<Cow<'a, str>>,
-        number: u64,
-    ) -> Response<Vec<api::Comment>> {
-        self.get(format!(
-            "repos/{}/{}/issues/{}/comments",
-            organization.into(),
-            gh_repo.into(),
-            number,
-        ))
+        query: &str,
+    ) -> Response<Shared<api::SearchResult<api::IssueSummary>>> {
+        self.get(Endpoint::Search {
+            query: format!("is:pr {query}"),
+            sort: Some("updated"),
+            per_page: 50,
+        })
     }
 
     /// Returns unread notifications (like github.com/notifications).
@@ -440,8 +1801,8 @@ impl<'frame> ClientRef<'frame> {
     /// The API seems to be unable to report the "done" state of notification
     /// threads, so we only ever show unread notifications, and markting them
     /// "done" marks them both read and done.
-    pub fn notifications<'a>(&self) -> Response<Vec<api::NotificationThread>> {
-        self.get("notifications")
+    pub fn notifications<'a>(&self) -> Response<Shared<Vec<api::NotificationThread>>> {
+        self.get_list(Endpoint::Notifications)
     }
 }
 
@@ -508,11 +1869,101 @@ impl<T> Response<T> {
     }
 }
 
+/// Source of the generations of cache entries. Generations are unique across
+/// all entries of all clients.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+fn next_generation() -> u64 {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A parsed response that is shared with the cache instead of copied.
+///
+/// Views can hold on to it across frames cheaply. Requesting the same data
+/// again in a later frame returns the same value, unless the cache entry was
+/// refreshed or edited in the meantime, in which case the generation differs.
+/// Views that derive expensive state from a response can compare generations
+/// to tell whether that state is stale.
+#[derive(Debug)]
+pub struct Shared<T> {
+    value: Arc<T>,
+    generation: u64,
+    partial: bool,
+}
+impl<T> Shared<T> {
+    /// The generation of the cache entry that the value was taken from.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the value is only the beginning of a list that is still being
+    /// received. This is only the case for references made with
+    /// `ClientRef::partial`.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// Whether `other` is the same version of the same response.
+    pub fn same_generation(&self, other: &Self) -> bool {
+        self.generation == other.generation
+    }
+
+    /// Take the value, copying it only if it is still shared.
+    pub fn into_owned(self) -> T
+    where
+        T: Clone,
+    {
+        Arc::unwrap_or_clone(self.value)
+    }
+}
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            generation: self.generation,
+            partial: self.partial,
+        }
+    }
+}
+impl<T> std::ops::Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<'a, T> IntoIterator for &'a Shared<T>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.value.as_ref().into_iter()
+    }
+}
+
 #[derive(Debug)]
 struct CacheEntry {
     response: Response<()>,
     fetched: Option<Instant>,
-    parsed: Option<Box<dyn Any + Send + Sync>>,
+
+    /// The parsed response. It is shared with callers that asked for it,
+    /// so that large responses aren't copied while the cache is locked.
+    parsed: Option<Arc<dyn Any + Send + Sync>>,
+
+    /// Changes whenever `parsed` is replaced or edited.
+    generation: u64,
+
+    /// Whether `parsed` holds the beginning of a response that is still being
+    /// received.
+    partial: bool,
+
+    /// Whether the response is requested again the next time that it is
+    /// used, regardless of its age.
+    stale: bool,
+
     request_frame: u64,
 }
 impl Default for CacheEntry {
@@ -521,6 +1972,9 @@ impl Default for CacheEntry {
             response: Response::Pending,
             fetched: None,
             parsed: None,
+            generation: next_generation(),
+            partial: false,
+            stale: false,
             request_frame: 0,
         }
     }
@@ -529,6 +1983,43 @@ impl Default for CacheEntry {
 #[derive(Debug, Default)]
 struct Cache {
     cache: Mutex<HashMap<String, CacheEntry>>,
+    disk: Option<DiskCache>,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    contended: AtomicU64,
+    wait_nanos: AtomicU64,
+}
+impl Cache {
+    /// Lock the in-memory cache, counting the times that it is contended.
+    ///
+    /// The lock is taken by the UI thread and the helper thread, so it must
+    /// only be held briefly: no parsing or deep cloning while it is held.
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CacheEntry>> {
+        match self.cache.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                let start = Instant::now();
+                let guard = self.cache.lock().unwrap();
+                self.contended.fetch_add(1, Ordering::Relaxed);
+                self.wait_nanos.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                guard
+            }
+            Err(TryLockError::Poisoned(err)) => panic!("{err}"),
+        }
+    }
+
+    /// Revert the optimistic update of an edit that is no longer queued.
+    fn invalidate_edit(&self, edit: &Edit) {
+        // Drop the optimistically updated entries. Re-loading them from the
+        // on-disk cache (which stores unmodified server responses) and
+        // re-requesting them applies the remaining queued edits on top.
+        let mut cache = self.lock();
+        for endpoint in edit.affected_endpoints() {
+            cache.remove(&endpoint.key());
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -542,6 +2033,13 @@ struct HelperCtrl {
 struct Request {
     url: String,
     parser: Box<dyn DynParser>,
+
+    /// Body of a GraphQL query; `url` is only the cache key then.
+    graphql: Option<String>,
+
+    /// Whether the response is stored in the on-disk cache.
+    disk_cache: bool,
+    cached: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -576,7 +2074,34 @@ struct HelperState {
     /// Requests from the current frame.
     frame_requests: Vec<Request>,
     backlog_requests: Vec<Request>,
-    edit_requests: Vec<Edit>,
+
+    /// URLs that are currently being requested. Interest in them is served by
+    /// the response that is already on its way.
+    in_flight: HashSet<String>,
+
+    /// Queued edits, including the one that is currently being committed.
+    edit_requests: Vec<QueuedEdit>,
+    next_edit_id: u64,
+
+    /// Where the edit queue is persisted, if anywhere.
+    edit_queue_file: Option<PathBuf>,
+
+    /// Recently committed edits that have an inverse, for undo.
+    committed_edits: Vec<(u64, Edit, Instant)>,
+
+    /// Called once the edit with the given ID has been committed.
+    commit_callbacks: HashMap<u64, CommitCallback>,
+
+    rate_limit: Option<RateLimit>,
+
+    /// Recent requests for the traffic inspector, oldest first.
+    traffic: VecDeque<TrafficEntry>,
+
+    /// Where to log API traffic, if anywhere.
+    request_log: Option<Arc<RequestLog>>,
+
+    token: TokenCheck,
+
     response_callback: Option<MergeWakeupSignal>,
 }
 impl std::fmt::Debug for HelperState {
@@ -588,7 +2113,12 @@ impl std::fmt::Debug for HelperState {
             .field("frame_timed_out", &self.frame_timed_out)
             .field("frame_requests", &self.frame_requests.len())
             .field("backlog_requests", &self.backlog_requests.len())
+            .field("in_flight", &self.in_flight)
             .field("edit_requests", &self.edit_requests.len())
+            .field("commit_callbacks", &self.commit_callbacks.len())
+            .field("edit_queue_file", &self.edit_queue_file)
+            .field("rate_limit", &self.rate_limit)
+            .field("request_log", &self.request_log.as_ref().map(|log| log.path()))
             .field(
                 "response_callback",
                 if self.response_callback.is_some() {
@@ -601,61 +2131,249 @@ impl std::fmt::Debug for HelperState {
     }
 }
 impl HelperState {
-    fn add_request(&mut self, url: String, parser: Box<dyn DynParser>, prefetch: bool) {
-        if let Some((idx, _)) = self.backlog_requests.iter().find_position(|r| r.url == url) {
+    fn save_edit_queue(&self) {
+        let Some(path) = &self.edit_queue_file else {
+            return;
+        };
+
+        let result = if self.edit_requests.is_empty() {
+            match std::fs::remove_file(path) {
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        } else {
+            let json = serde_json::to_vec(&self.edit_requests).unwrap();
+            std::fs::write(path, json)
+        };
+        if let Err(err) = result {
+            warn!("Error saving edit queue {}: {}", path.display(), err);
+        }
+    }
+
+    fn add_request(&mut self, request: Request, prefetch: bool) {
+        let url = &request.url;
+        if self.in_flight.contains(url) {
+            return;
+        }
+
+        if let Some((idx, _)) = self.backlog_requests.iter().find_position(|r| &r.url == url) {
             if prefetch {
                 return;
             }
 
             self.backlog_requests.remove(idx);
-        } else if self.frame_requests.iter().any(|r| r.url == url) {
+        } else if self.frame_requests.iter().any(|r| &r.url == url) {
             return;
         }
 
         if prefetch {
-            self.backlog_requests.push(Request { url, parser });
+            self.backlog_requests.push(request);
         } else {
-            self.frame_requests.push(Request { url, parser });
+            self.frame_requests.push(request);
         }
     }
 }
 
-fn do_request(
+/// Send a GET request, retrying transient failures according to the policy.
+fn send_with_retry(
     client: &reqwest::blocking::Client,
-    url_api: &Url,
-    url: &str,
-    cache_file: Option<PathBuf>,
-    parser: Box<dyn DynParser>,
-) -> Result<Response<Box<dyn Any + Send + Sync>>> {
-    let url = url_api.join(url).unwrap();
-    info!("Requesting {}", url);
+    url: &Url,
+    graphql: Option<&str>,
+    policy: &RetryPolicy,
+) -> Result<reqwest::blocking::Response> {
+    let mut retry = 0;
+    loop {
+        let request = match graphql {
+            Some(body) => client
+                .post(url.clone())
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.to_string()),
+            None => client.get(url.clone()),
+        };
+        let delay = match request.send() {
+            Ok(response) => {
+                if retry >= policy.retries || !policy.should_retry_status(response.status()) {
+                    return Ok(response);
+                }
 
-    let response = client.get(url).send()?;
-    debug!("Response: {:?}", &response);
+                // Rate limiting responses tell us how long to wait.
+                let retry_after = response
+                    .headers()
+                    .get(header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+                    .map(std::time::Duration::from_secs);
+                warn!("{}: HTTP {}, retrying", url, response.status());
+                retry_after.unwrap_or_else(|| policy.backoff(retry))
+            }
+            Err(err) => {
+                if retry >= policy.retries || !policy.should_retry_error(&err) {
+                    return Err(err.into());
+                }
+                warn!("{}: {}, retrying", url, err);
+                policy.backoff(retry)
+            }
+        };
+
+        std::thread::sleep(delay.min(policy.request_timeout()));
+        retry += 1;
+    }
+}
+
+/// Keeps a copy of everything that is read, for the on-disk cache.
+struct TeeReader<R> {
+    inner: R,
+    copy: Vec<u8>,
+}
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.copy.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// The endpoint that a request is sent to. GraphQL queries are POSTed to the
+/// GraphQL endpoint, everything else is a GET relative to the REST API.
+fn request_url(flavor: &ApiFlavor, url: &str, graphql: bool) -> Result<Url> {
+    if graphql {
+        Ok(flavor.graphql_url.clone().ok_or("The GraphQL API is not available")?)
+    } else {
+        Ok(flavor.rest_url.join(url)?)
+    }
+}
+
+/// A parsed response and the rate limit reported with it.
+type RequestResult = Result<(Response<Box<dyn Any + Send + Sync>>, Option<RateLimit>)>;
+
+/// Send a request and parse the response while it is being received.
+///
+/// Parsers may hand off partial results to `partial` before the response is
+/// complete.
+fn do_request(
+    client: &reqwest::blocking::Client,
+    flavor: &ApiFlavor,
+    request: Request,
+    config: &ClientConfig,
+    disk: Option<&DiskCache>,
+    traffic: &mut TrafficEntry,
+    partial: &mut dyn FnMut(NewItems),
+) -> RequestResult {
+    let Request { url, parser, graphql, .. } = request;
+    let url = url.as_str();
+    let fixtures = config.fixtures.as_ref();
+    let record_file = fixtures.map(|fixtures| fixtures.file_for_url(url));
+    let record_not_found = || {
+        if let Some(fixtures) = fixtures {
+            if let Err(err) = fixtures.record_not_found(url) {
+                warn!("Error recording response for {}: {}", url, err);
+            }
+        }
+    };
 
-    let converted = if response.status().is_success() {
-        let text = response.text()?;
+    let cache_key = url;
+    let url = request_url(flavor, url, graphql.is_some())?;
+    info!("Requesting {} ({})", url, cache_key);
 
-        if let Some(cache_file) = cache_file {
-            if let Err(err) = std::fs::write(&cache_file, text.as_bytes()) {
-                warn!("Error writing cache file {}: {}", cache_file.display(), err);
+    let response = send_with_retry(client, &url, graphql.as_deref(), &config.host.retry)?;
+    debug!("Response: {:?}", &response);
+    let rate_limit = RateLimit::from_headers(response.headers());
+    let status = response.status();
+    traffic.status = Some(status.as_u16());
+    traffic.headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned())
+        })
+        .collect();
+
+    let mut reader = std::io::BufReader::new(TeeReader {
+        inner: response,
+        copy: Vec::new(),
+    });
+    let parsed = if status.is_success() {
+        let parsed = parser.parse_reader(&mut reader, partial);
+        log_parse_warnings(cache_key);
+        Some(parsed)
+    } else {
+        None
+    };
+    reader.read_to_end(&mut Vec::new())?;
+    let text = reader.into_inner().copy;
+    traffic.bytes = text.len();
+    traffic.body = String::from_utf8_lossy(&text).into();
+
+    let converted = if let Some(parsed) = parsed {
+        if let Some(disk) = disk {
+            disk.store(cache_key, &text);
+        }
+        if let Some(record_file) = record_file {
+            if let Err(err) = std::fs::write(&record_file, &text) {
+                warn!("Error recording response in {}: {}", record_file.display(), err);
             }
         }
 
-        match parser.parse(&text) {
+        match parsed {
             Ok(parsed) => Response::Ok(parsed),
             Err(err) => Response::Err(format!("Error parsing response: {}", err)),
         }
-    } else if response.status() == StatusCode::NOT_FOUND {
+    } else if status == StatusCode::NOT_FOUND {
+        record_not_found();
         Response::NotFound
     } else {
-        Response::Err(format!("HTTP error: {}", response.status()))
+        Response::Err(format!("HTTP error: {}", status))
+    };
+
+    Ok((converted, rate_limit))
+}
+
+/// Make the beginning of a list that is still being received available to
+/// views that ask for it, unless older data is already shown.
+fn handoff_partial(cache: &Cache, ctrl: &HelperCtrl, url: &str, new: NewItems) {
+    let mut state = ctrl.state.lock().unwrap();
+
+    // Queued edits may add or move items, which can't be done on a part of
+    // the list. Such lists are only shown once they are complete.
+    let edited = state.edit_requests.iter().any(|queued| {
+        queued.edit.affected_endpoints().iter().any(|endpoint| endpoint.key() == url)
+    });
+    if edited {
+        return;
+    }
+
+    let is_current_frame = {
+        let mut cache = cache.lock();
+        let entry = cache.entry(url.to_string()).or_default();
+        if entry.fetched.is_some() || (entry.parsed.is_some() && !entry.partial) {
+            return;
+        }
+
+        match &mut entry.parsed {
+            Some(list) => (new.append)(list, new.items),
+            // The entry was dropped in the meantime; don't show a list
+            // without its beginning.
+            None if new.offset > 0 => return,
+            None => entry.parsed = Some(Arc::from(new.items)),
+        }
+        entry.generation = next_generation();
+        entry.partial = true;
+        entry.response = Response::Ok(());
+
+        entry.request_frame == state.frame_number
     };
 
-    Ok(converted)
+    if is_current_frame && state.frame_timed_out {
+        state.frame_response_past_timeout = state.frame_number;
+
+        if let Some(callback) = state.response_callback.take() {
+            callback.signal();
+        }
+    }
 }
 
-fn run_helper(cache: Arc<Cache>, ctrl: Arc<HelperCtrl>, config: ClientConfig, url_api: Url) {
+fn run_helper(cache: Arc<Cache>, ctrl: Arc<HelperCtrl>, config: ClientConfig, flavor: ApiFlavor) {
+    let url_api = &flavor.rest_url;
+    let replay = config.fixtures.as_ref().filter(|fixtures| fixtures.is_replay());
     let result = || -> Result<()> {
         let mut default_headers = header::HeaderMap::new();
         default_headers.insert(
@@ -663,27 +2381,146 @@ fn run_helper(cache: Arc<Cache>, ctrl: Arc<HelperCtrl>, config: ClientConfig, ur
             format!("Bearer {}", config.host.token).parse()?,
         );
         default_headers.insert(header::ACCEPT, "application/vnd.github+json".parse()?);
-        default_headers.insert("X-GitHub-Api-Version", "2022-11-28".parse()?);
+        if let Some(version) = &flavor.rest_version {
+            default_headers.insert("X-GitHub-Api-Version", version.parse()?);
+        }
 
-        let client = reqwest::blocking::Client::builder()
+        let client = config
+            .host
+            .http_client_builder()?
             .user_agent("git-review")
-            .default_headers(default_headers)
+            .default_headers(default_headers.clone())
             .build()?;
 
+        // Check the token on the side, so that the first requests aren't
+        // delayed.
+        if replay.is_none() {
+            let client = client.clone();
+            let url_api = url_api.clone();
+            let host = config.host.clone();
+            let ctrl = ctrl.clone();
+            std::thread::spawn(move || {
+                let token = match TokenStatus::check(&client, &url_api) {
+                    Ok(status) => {
+                        for warning in status.warnings(&host.user, chrono::Utc::now()) {
+                            warn!("{}: {}", host.host, warning);
+                        }
+                        TokenCheck::Valid(status)
+                    }
+                    Err(err) => {
+                        warn!("{}: failed to check the token: {}", host.host, err);
+                        TokenCheck::Failed(err.to_string())
+                    }
+                };
+
+                let mut state = ctrl.state.lock().unwrap();
+                state.token = token;
+                if let Some(callback) = state.response_callback.take() {
+                    callback.signal();
+                }
+            });
+        }
+
         let mut state = ctrl.state.lock().unwrap();
+        if replay.is_some() {
+            state.token = TokenCheck::Unchecked;
+        }
         while state.running {
-            // Commit edits first.
-            if !state.edit_requests.is_empty() {
-                let edit = state.edit_requests.drain(0..1).next().unwrap();
+            // Commit edits first. The edit stays in the queue while it is
+            // being committed, so that it survives a restart in the meantime.
+            let now = Instant::now();
+            if let Some(queued) = state.edit_requests.iter_mut().find(|queued| queued.is_ready(now))
+            {
+                queued.committing = true;
+                let queued = queued.clone();
+                // Mark it in the saved queue, in case we exit before the
+                // result is known.
+                state.save_edit_queue();
                 std::mem::drop(state);
 
-                info!("Committing edit {:?}", edit);
-
-                if let Err(err) = edit.commit(&client, &url_api) {
-                    error!("Error committing edit {:?}: {}", edit, err);
-                }
+                let conflict = match &queued.precondition {
+                    _ if replay.is_some() => Ok(None),
+                    Some(precondition) => precondition.check(&client, &flavor),
+                    None => Ok(None),
+                };
+                let result = match conflict {
+                    Ok(None) if replay.is_some() => {
+                        info!("Replaying, not committing edit {:?}", queued.edit);
+                        Ok(None)
+                    }
+                    Ok(None) => {
+                        info!("Committing edit {:?}", queued.edit);
+                        queued.edit.commit(&client, &flavor).map(|_| None)
+                    }
+                    Ok(Some(conflict)) => {
+                        warn!("Conflict for edit {:?}: {}", queued.edit, conflict);
+                        Ok(Some(conflict))
+                    }
+                    Err(err) => Err(err),
+                };
 
                 state = ctrl.state.lock().unwrap();
+                match result {
+                    Ok(Some(conflict)) => {
+                        // Keep the edit around until the user resolves the conflict.
+                        if let Some(queued) =
+                            state.edit_requests.iter_mut().find(|other| other.id == queued.id)
+                        {
+                            queued.committing = false;
+                            queued.conflict = Some(conflict);
+                        }
+                        if let Some(callback) = state.response_callback.take() {
+                            callback.signal();
+                        }
+                    }
+                    Ok(None) => {
+                        if queued.edit.invert().is_some() {
+                            let committed = (queued.id, queued.edit.clone(), Instant::now());
+                            state.committed_edits.push(committed);
+                        }
+                        state.edit_requests.retain(|other| other.id != queued.id);
+                        if let Some(callback) = state.commit_callbacks.remove(&queued.id) {
+                            std::thread::spawn(callback);
+                        }
+                    }
+                    Err(err) => match CommitFailure::classify(&err, &config.host.retry) {
+                        CommitFailure::Transient => {
+                            if let Some(queued) =
+                                state.edit_requests.iter_mut().find(|other| other.id == queued.id)
+                            {
+                                let delay = config.host.retry.backoff(queued.failures);
+                                warn!("Failed to commit edit {:?}, retrying: {}", queued.edit, err);
+                                queued.committing = false;
+                                queued.failures += 1;
+                                queued.not_before =
+                                    Some(Instant::now() + delay.min(MAX_EDIT_RETRY_DELAY));
+                            }
+                        }
+                        CommitFailure::Rejected => {
+                            error!("Error committing edit {:?}: {}", queued.edit, err);
+                            state.edit_requests.retain(|other| other.id != queued.id);
+                            state.commit_callbacks.remove(&queued.id);
+                            cache.invalidate_edit(&queued.edit);
+                            if let Some(callback) = state.response_callback.take() {
+                                callback.signal();
+                            }
+                        }
+                        CommitFailure::Conflict => {
+                            warn!("Conflict for edit {:?}: {}", queued.edit, err);
+                            if let Some(queued) =
+                                state.edit_requests.iter_mut().find(|other| other.id == queued.id)
+                            {
+                                queued.committing = false;
+                                queued.conflict = Some(err.to_string());
+                            }
+                            if let Some(callback) = state.response_callback.take() {
+                                callback.signal();
+                            }
+                        }
+                    },
+                }
+                state.save_edit_queue();
+                ctrl.response_notify.notify_all();
                 continue;
             }
 
@@ -694,61 +2531,115 @@ fn run_helper(cache: Arc<Cache>, ctrl: Arc<HelperCtrl>, config: ClientConfig, ur
                 state.backlog_requests.pop()
             };
             let Some(request) = request else {
-                state = ctrl.helper_wakeup.wait(state).unwrap();
+                // Wake up in time for edits that are held back for undo.
+                let next_edit = state
+                    .edit_requests
+                    .iter()
+                    .filter(|queued| queued.conflict.is_none())
+                    .filter_map(|queued| queued.not_before)
+                    .min();
+                state = match next_edit {
+                    Some(next_edit) => {
+                        let timeout = next_edit.saturating_duration_since(Instant::now());
+                        ctrl.helper_wakeup.wait_timeout(state, timeout).unwrap().0
+                    }
+                    None => ctrl.helper_wakeup.wait(state).unwrap(),
+                };
                 continue;
             };
+            state.in_flight.insert(request.url.clone());
+            let request_log = state.request_log.clone();
             std::mem::drop(state);
 
-            let response = match do_request(
-                &client,
-                &url_api,
-                &request.url,
-                config.cache_for_url(&request.url),
-                request.parser,
-            ) {
-                Ok(response) => response,
-                Err(err) => {
-                    error!("Error processing request: {}", err);
-                    Response::Err(err.to_string())
+            let url = request.url.clone();
+            let sent = request_log.as_ref().map(|_| {
+                let target = request_url(&flavor, &url, request.graphql.is_some())
+                    .map_or_else(|_| url.clone(), String::from);
+                (target, request.graphql.clone())
+            });
+            let start = Instant::now();
+            let mut traffic = TrafficEntry {
+                url: url.clone(),
+                started: chrono::Local::now(),
+                duration: Duration::ZERO,
+                status: None,
+                cached: request.cached,
+                bytes: 0,
+                headers: Vec::new(),
+                body: "".into(),
+            };
+            let (response, rate_limit) = if let Some(replay) = replay {
+                traffic.body = "(replayed from fixtures)".into();
+                (replay.replay(&url, request.parser), None)
+            } else {
+                let mut partial = |parsed| handoff_partial(&cache, &ctrl, &url, parsed);
+                let disk = cache.disk.as_ref().filter(|_| request.disk_cache);
+                let result = do_request(
+                    &client,
+                    &flavor,
+                    request,
+                    &config,
+                    disk,
+                    &mut traffic,
+                    &mut partial,
+                );
+                match result {
+                    Ok(response) => response,
+                    Err(err) => {
+                        error!("Error processing request: {}", err);
+                        traffic.body = err.to_string().into();
+                        (Response::Err(err.to_string()), None)
+                    }
                 }
             };
+            traffic.duration = start.elapsed();
+            if let (Some(request_log), Some((target, body))) = (&request_log, &sent) {
+                let method = if body.is_some() { "POST" } else { "GET" };
+                request_log.record(
+                    &config.host.host,
+                    method,
+                    target,
+                    body.as_deref(),
+                    &default_headers,
+                    &traffic,
+                );
+            }
+            cache.requests.fetch_add(1, Ordering::Relaxed);
+            if matches!(response, Response::Err(_)) {
+                cache.errors.fetch_add(1, Ordering::Relaxed);
+            }
 
             // Re-acquire the control lock *before* updating the cache.
             //
             // This ensures that response notifications aren't lost.
             state = ctrl.state.lock().unwrap();
-            let is_current_frame = {
-                let mut cache = cache.cache.lock().unwrap();
-                let entry = cache.entry(request.url.clone()).or_default();
-
-                let (mut parsed, response) = response.split();
-
-                struct ItemGetter<'a> {
-                    url: &'a str,
-                    parsed: Option<&'a mut Box<dyn Any + Send + Sync>>,
-                }
-                impl<'a> edit::ItemGetter for ItemGetter<'a> {
-                    fn get(&mut self, url: &str) -> Option<&mut Box<dyn Any + Send + Sync>> {
-                        if self.url == url {
-                            self.parsed.take()
-                        } else {
-                            None
-                        }
-                    }
-                }
-
-                for edit in &state.edit_requests {
-                    edit.apply(&mut ItemGetter {
-                        url: &request.url,
-                        parsed: parsed.as_mut(),
-                    });
-                }
+            if rate_limit.is_some() {
+                state.rate_limit = rate_limit;
+            }
+            if state.traffic.len() >= TRAFFIC_HISTORY {
+                state.traffic.pop_front();
+            }
+            state.traffic.push_back(traffic);
+            state.in_flight.remove(&url);
+            let (parsed, response) = response.split();
+            let mut parsed = parsed.map(Arc::from);
+            for queued in &state.edit_requests {
+                queued.edit.apply_optimistic(&mut SingleItemGetter {
+                    url: &url,
+                    parsed: parsed.as_mut(),
+                });
+            }
 
+            let is_current_frame = {
+                let mut cache = cache.lock();
+                let entry = cache.entry(url.clone()).or_default();
                 if parsed.is_some() || matches!(response, Response::NotFound) {
                     entry.parsed = parsed;
+                    entry.generation = next_generation();
                 }
 
                 entry.fetched = Some(Instant::now());
+                entry.partial = false;
                 entry.response = response;
 
                 entry.request_frame == state.frame_number
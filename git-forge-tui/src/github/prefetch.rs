@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Declarative prefetching.
+//!
+//! Views describe what the user is likely to look at next as [`Intent`]s
+//! instead of issuing prefetch requests themselves. This module translates
+//! intents into requests, so that the heuristics of what to load are kept in
+//! one place.
+//!
+//! Prefetch requests go to the back of the queue and never block a frame.
+
+use serde::Deserialize;
+
+use super::{Client, ClientRef};
+
+/// Number of requests that a single intent issues at most.
+const REQUESTS_PER_INTENT: u64 = 5;
+
+/// How much may be prefetched without the user asking for it, e.g. when
+/// the inbox is first loaded.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Budget {
+    /// Maximum number of pull requests.
+    pub pull_requests: usize,
+
+    /// Number of requests of the rate limit that are kept for interactive
+    /// use.
+    pub reserve: u64,
+}
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            pull_requests: 5,
+            reserve: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Intent {
+    /// A pull request is selected in a list and is likely to be opened.
+    PullRequest {
+        owner: String,
+        name: String,
+        number: u64,
+    },
+}
+impl Intent {
+    pub fn pull_request(owner: impl Into<String>, name: impl Into<String>, number: u64) -> Self {
+        Intent::PullRequest {
+            owner: owner.into(),
+            name: name.into(),
+            number,
+        }
+    }
+
+    fn issue(&self, client: &ClientRef) {
+        match self {
+            Intent::PullRequest {
+                owner,
+                name,
+                number,
+            } => {
+                // Roughly in the order in which the review view needs them.
+                let _ = client.pull(owner, name, *number);
+                let _ = client.reviews(owner, name, *number);
+                let _ = client.issue_comments(owner, name, *number);
+                let _ = client.timeline(owner, name, *number);
+                let _ = client.pull_files(owner, name, *number);
+            }
+        }
+    }
+}
+
+impl Client {
+    /// Declare data that is likely to be needed soon.
+    ///
+    /// Must be called between `start_frame` and `end_frame`.
+    pub fn prefetch_intents(&mut self, intents: impl IntoIterator<Item = Intent>) {
+        let client = self.prefetch();
+        for intent in intents {
+            intent.issue(&client);
+        }
+    }
+
+    /// Declare data that is likely to be needed, most likely first, but only
+    /// as much as the budget and the remaining rate limit allow.
+    ///
+    /// Must be called between `start_frame` and `end_frame`.
+    pub fn prefetch_within_budget(
+        &mut self,
+        intents: impl IntoIterator<Item = Intent>,
+        budget: &Budget,
+    ) {
+        let mut count = budget.pull_requests;
+        if let Some(rate_limit) = self.rate_limit() {
+            let available = rate_limit.remaining.saturating_sub(budget.reserve);
+            count = count.min((available / REQUESTS_PER_INTENT) as usize);
+        }
+
+        self.prefetch_intents(intents.into_iter().take(count));
+    }
+}
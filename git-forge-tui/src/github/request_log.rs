@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Log of API traffic for debugging misbehaving servers.
+//!
+//! Credentials in headers are redacted and bodies are truncated, so that logs
+//! can be shared in bug reports. Request URLs, GraphQL queries and the
+//! beginning of responses are logged as they are.
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::warn;
+use reqwest::header::HeaderMap;
+use vctools_utils::prelude::*;
+
+use super::TrafficEntry;
+
+/// Request and response bodies are cut off after this many bytes.
+const MAX_BODY: usize = 4096;
+
+/// Headers whose values are never logged.
+const REDACTED_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+#[derive(Debug)]
+pub struct RequestLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+impl RequestLog {
+    /// Open the log, appending to the file if it exists.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = try_forward(
+            || {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Ok(File::options().create(true).append(true).open(&path)?)
+            },
+            || format!("Error opening {}", path.display()),
+        )?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Log a request and its response.
+    pub(super) fn record(
+        &self,
+        host: &str,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+        headers: &HeaderMap,
+        traffic: &TrafficEntry,
+    ) {
+        let mut text = format!(
+            "=== {} {host} {method} {url}\n",
+            traffic.started.format("%Y-%m-%d %H:%M:%S%.3f"),
+        );
+        for (name, value) in headers {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            text.push_str(&format!("> {name}: {}\n", redact(name.as_str(), &value)));
+        }
+        if let Some(body) = body {
+            push_truncated(&mut text, body);
+            text.push('\n');
+        }
+
+        let status = traffic.status.map_or("-".into(), |status| status.to_string());
+        text.push_str(&format!(
+            "< {status} ({} ms, {} bytes{})\n",
+            traffic.duration.as_millis(),
+            traffic.bytes,
+            if traffic.cached { ", cached" } else { "" },
+        ));
+        for (name, value) in &traffic.headers {
+            text.push_str(&format!("< {name}: {}\n", redact(name, value)));
+        }
+
+        push_truncated(&mut text, &traffic.body);
+        text.push_str("\n\n");
+
+        if let Err(err) = self.file.lock().unwrap().write_all(text.as_bytes()) {
+            warn!("Error writing to {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+fn push_truncated(text: &mut String, body: &str) {
+    if body.len() > MAX_BODY {
+        let mut end = MAX_BODY;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.push_str(&body[..end]);
+        text.push_str(&format!("\n[... truncated, {} bytes in total]", body.len()));
+    } else {
+        text.push_str(body);
+    }
+}
+
+fn redact<'a>(name: &str, value: &'a str) -> &'a str {
+    let name = name.to_ascii_lowercase();
+    if REDACTED_HEADERS.contains(&name.as_str()) || name.contains("token") {
+        "<redacted>"
+    } else {
+        value
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// Timeouts and retry behavior for requests to a host.
+///
+/// Configured as part of the host entry in github.toml.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Timeout for establishing a connection, in seconds.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub connect_timeout: f64,
+
+    /// Timeout for a complete request, in seconds.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub request_timeout: f64,
+
+    /// How often a failed request is retried before giving up.
+    pub retries: u32,
+
+    /// HTTP status codes that are considered transient and lead to a retry.
+    pub retry_on_status: Vec<u16>,
+
+    /// Base delay before the first retry, in seconds. The delay doubles with
+    /// every retry, and a random jitter is applied.
+    #[serde(deserialize_with = "deserialize_seconds")]
+    pub retry_backoff: f64,
+}
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            connect_timeout: 10.0,
+            request_timeout: 30.0,
+            retries: 3,
+            retry_on_status: vec![429, 500, 502, 503, 504],
+            retry_backoff: 0.5,
+        }
+    }
+}
+impl RetryPolicy {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.connect_timeout)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.request_timeout)
+    }
+
+    pub fn should_retry_status(&self, status: StatusCode) -> bool {
+        self.retry_on_status.contains(&status.as_u16())
+    }
+
+    /// Whether a transport-level error is worth retrying.
+    pub fn should_retry_error(&self, err: &reqwest::Error) -> bool {
+        err.is_timeout() || err.is_connect() || err.is_request()
+    }
+
+    /// Delay before the given retry (starting at 0 for the first retry).
+    ///
+    /// Uses "full jitter": a uniformly random delay up to the exponentially
+    /// growing limit, so that many clients behind the same flaky connection
+    /// don't retry in lockstep.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let limit = self.retry_backoff * 2.0f64.powi(retry.min(16) as i32);
+        Duration::try_from_secs_f64(limit * random_unit()).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Deserialize a number of seconds, rejecting values that aren't a valid
+/// duration, so that they are reported when the configuration is loaded.
+fn deserialize_seconds<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let seconds = f64::deserialize(deserializer)?;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| serde::de::Error::custom(format!("invalid number of seconds: {seconds}")))?;
+    Ok(seconds)
+}
+
+/// Cheap random number in [0, 1) without pulling in an RNG dependency.
+fn random_unit() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Validation of access tokens.
+//!
+//! When a client starts, its token is checked against `/user`. Classic tokens
+//! report their scopes in the `X-OAuth-Scopes` header; fine-grained tokens
+//! don't, so only their expiry can be checked.
+
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, header::HeaderMap, StatusCode, Url};
+use serde::Deserialize;
+use vctools_utils::prelude::*;
+
+/// Scopes of classic tokens that are needed for all features to work, with
+/// the scopes that include them.
+pub const REQUIRED_SCOPES: &[(&str, &[&str])] =
+    &[("repo", &["repo"]), ("notifications", &["notifications", "repo"])];
+
+/// Warn about tokens that expire within this many days.
+const EXPIRY_WARNING_DAYS: i64 = 7;
+
+#[derive(Debug, Clone)]
+pub struct TokenStatus {
+    /// The user that the token belongs to.
+    pub login: String,
+
+    /// Scopes of the token, or `None` if the server doesn't report them,
+    /// as for fine-grained tokens.
+    pub scopes: Option<Vec<String>>,
+
+    pub expires: Option<DateTime<Utc>>,
+}
+impl TokenStatus {
+    /// Query the token's status from the server.
+    pub(super) fn check(client: &Client, url_api: &Url) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+
+        let response = client.get(url_api.join("user")?).send()?;
+        match response.status() {
+            StatusCode::UNAUTHORIZED => Err("the token was rejected (expired or revoked?)")?,
+            status if !status.is_success() => Err(format!("HTTP error: {status}"))?,
+            _ => {}
+        }
+
+        let headers = response.headers().clone();
+        let user: User = response.json()?;
+        Ok(Self::from_headers(user.login, &headers))
+    }
+
+    fn from_headers(login: String, headers: &HeaderMap) -> Self {
+        let get = |name: &str| headers.get(name)?.to_str().ok();
+        let scopes = get("x-oauth-scopes").map(|scopes| {
+            scopes
+                .split(',')
+                .map(str::trim)
+                .filter(|scope| !scope.is_empty())
+                .map(String::from)
+                .collect()
+        });
+
+        // For example "2024-03-01 12:00:00 UTC" or "2024-03-01 12:00:00 +0100".
+        let expires = get("github-authentication-token-expiration").and_then(|value| {
+            let value = value.replace(" UTC", " +0000");
+            DateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S %z")
+                .ok()
+                .map(|dt| dt.with_timezone(&Utc))
+        });
+
+        Self {
+            login,
+            scopes,
+            expires,
+        }
+    }
+
+    /// Required scopes that the token doesn't have.
+    pub fn missing_scopes(&self) -> Vec<&'static str> {
+        let Some(scopes) = &self.scopes else {
+            return Vec::new();
+        };
+        REQUIRED_SCOPES
+            .iter()
+            .filter(|(_, granted_by)| {
+                !scopes.iter().any(|scope| granted_by.contains(&scope.as_str()))
+            })
+            .map(|(required, _)| *required)
+            .collect()
+    }
+
+    /// Problems with the token that the user should know about.
+    pub fn warnings(&self, user: &str, now: DateTime<Utc>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.login.eq_ignore_ascii_case(user) {
+            warnings.push(format!("token belongs to {}, not {}", self.login, user));
+        }
+        let missing = self.missing_scopes();
+        if !missing.is_empty() {
+            warnings.push(format!("token lacks the scopes: {}", missing.join(", ")));
+        }
+        if let Some(expires) = self.expires {
+            let remaining = expires - now;
+            if remaining <= chrono::Duration::zero() {
+                warnings.push("token has expired".into());
+            } else if remaining < chrono::Duration::days(EXPIRY_WARNING_DAYS) {
+                let when = vctools_utils::time::format_relative(expires.timestamp(), now.timestamp());
+                warnings.push(format!("token expires {when}"));
+            }
+        }
+        warnings
+    }
+}
+
+/// Progress of the validation of a client's token.
+#[derive(Debug, Clone, Default)]
+pub enum TokenCheck {
+    /// The token isn't checked, e.g. because the client is offline or
+    /// replays recorded responses.
+    #[default]
+    Unchecked,
+    Pending,
+    Valid(TokenStatus),
+    Failed(String),
+}
@@ -58,9 +58,16 @@ struct Remote {
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 struct CacheKey([u8; 16]);
 
+/// How long the results of commands that depend on the state of a remote are
+/// cached.
+const VOLATILE_TTL: time::Duration = time::Duration::from_secs(60);
+
 #[derive(Debug, Default)]
 struct Cache {
     index: HashMap<CacheKey, git_core::ExecutionResult>,
+
+    /// When the results of volatile commands expire.
+    expiry: HashMap<CacheKey, time::Instant>,
 }
 
 #[derive(Debug)]
@@ -74,7 +81,7 @@ struct Job {
 #[derive(Debug)]
 struct ServiceInner {
     repositories: Vec<git_core::Repository>,
-    api_hosts: HashMap<String, String>,
+    api_hosts: Vec<crate::github::Host>,
     remotes: OnceLock<Vec<Remote>>,
     job: Mutex<Option<Job>>,
     job_available: Condvar,
@@ -101,10 +108,7 @@ impl GitService {
             .iter()
             .map(|repo| git_core::Repository::new(repo.path.clone()))
             .collect();
-        let api_hosts = api_hosts.iter().map(|host| {
-            std::iter::once((host.host.clone(), host.host.clone()))
-                .chain(host.alias.iter().map(|alias| (alias.clone(), host.host.clone())))
-        }).flatten().collect();
+        let api_hosts = api_hosts.to_vec();
         let inner = Arc::new(ServiceInner {
             repositories,
             api_hosts,
@@ -138,6 +142,11 @@ impl GitService {
         self.frame = None;
     }
 
+    /// Number of results of git commands that are currently cached.
+    pub fn cache_entries(&self) -> usize {
+        self.inner.cache.lock().unwrap().index.len()
+    }
+
     pub fn find_git(&self, api: &ApiRepository) -> Option<&GitRepository> {
         let remotes = self.inner.remotes.wait();
 
@@ -186,7 +195,13 @@ impl git_core::ExecutionProvider for GitService {
 
         // First, check if we have a cached result.
         let submitted = {
-            let cache = self.inner.cache.lock().unwrap();
+            let mut cache = self.inner.cache.lock().unwrap();
+            if cache.expiry.get(&cache_key).is_some_and(|expiry| *expiry <= time::Instant::now())
+                && !matches!(cache.index.get(&cache_key), Some(git_core::ExecutionResult::Pending))
+            {
+                cache.index.remove(&cache_key);
+                cache.expiry.remove(&cache_key);
+            }
             if let Some(entry) = cache.index.get(&cache_key) {
                 if !matches!(entry, git_core::ExecutionResult::Pending) {
                     return entry.clone();
@@ -222,6 +237,9 @@ impl git_core::ExecutionProvider for GitService {
             cache
                 .index
                 .insert(cache_key, git_core::ExecutionResult::Pending);
+            if cacheable == Cacheability::Volatile {
+                cache.expiry.insert(cache_key, time::Instant::now() + VOLATILE_TTL);
+            }
 
             self.inner.job_available.notify_all();
         }
@@ -264,18 +282,15 @@ impl ServiceInner {
                 Ok(remotes) => remotes,
             };
             for (remote, url) in current_remotes {
-                let Some(hostname) = url.hostname() else {
-                    continue;
-                };
                 let Some((owner, name)) = url.github_path() else {
                     continue;
                 };
 
-                let Some(canonical_host) = self.api_hosts.get(hostname) else {
+                let Some(host) = crate::github::Host::find_for_url(&self.api_hosts, &url) else {
                     continue;
                 };
                 let api =
-                    ApiRepository::new(canonical_host.to_string(), owner.to_string(), name.to_string());
+                    ApiRepository::new(host.host.clone(), owner.to_string(), name.to_string());
                 let git = GitRepository::new(repo.path.clone(), remote);
 
                 debug!("Found remote {:?} for API {:?}", git, api);
@@ -0,0 +1,210 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! External commands that are run when something happens in the TUI.
+//!
+//! Hooks are configured in settings.toml:
+//!
+//! ```toml
+//! [hooks]
+//! on_pr_opened = "my-time-tracker start"
+//! on_review_submitted = "notify-send 'Comment posted'"
+//! ```
+//!
+//! The command is run by `sh -c` (`cmd /C` on Windows) in the background and
+//! receives a JSON object
+//! describing the event on stdin, e.g.
+//!
+//! ```json
+//! {"hook": "on_pr_opened", "host": "github.com", "owner": "o", "name": "n",
+//!  "number": 123, "repository": "/path/to/clone", "remote": "origin"}
+//! ```
+//!
+//! Custom commands are run the same way when they are entered after `:`:
+//!
+//! ```toml
+//! [commands]
+//! ship = "~/bin/approve-and-merge-when-green"
+//! ```
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{settings::Settings, CompletePullRequest};
+
+fn shell_command(command: &str) -> Command {
+    let mut result;
+    if cfg!(windows) {
+        result = Command::new("cmd");
+        result.arg("/C");
+    } else {
+        result = Command::new("sh");
+        result.arg("-c");
+    }
+    result.arg(command);
+    result
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct HookSettings {
+    /// Run after a comment or review on a pull request has been posted to the
+    /// forge.
+    pub on_review_submitted: Option<String>,
+
+    /// Run when a pull request is shown.
+    pub on_pr_opened: Option<String>,
+
+    /// Run when a head of a pull request is first shown for review, i.e. when
+    /// the pull request is opened and whenever new commits are pushed. The
+    /// commits have been fetched into the local clone at that point, but
+    /// nothing is checked out.
+    pub on_checkout: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    ReviewSubmitted,
+    PullRequestOpened,
+    Checkout,
+}
+impl Hook {
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::ReviewSubmitted => "on_review_submitted",
+            Hook::PullRequestOpened => "on_pr_opened",
+            Hook::Checkout => "on_checkout",
+        }
+    }
+
+    fn command(self, settings: &HookSettings) -> Option<&str> {
+        match self {
+            Hook::ReviewSubmitted => settings.on_review_submitted.as_deref(),
+            Hook::PullRequestOpened => settings.on_pr_opened.as_deref(),
+            Hook::Checkout => settings.on_checkout.as_deref(),
+        }
+    }
+}
+
+/// Context for hooks about a pull request. Additional fields can be inserted
+/// into the returned object.
+pub fn pull_request_context(pr: &CompletePullRequest) -> Value {
+    json!({
+        "host": pr.api.host,
+        "owner": pr.api.owner,
+        "name": pr.api.name,
+        "number": pr.id,
+        "repository": pr.git.repository.path,
+        "remote": pr.git.remote,
+    })
+}
+
+/// Run the configured command for `hook`, if any, in the background.
+pub fn run(hook: Hook, mut context: Value) {
+    let settings = Settings::get();
+    let Some(command) = hook.command(&settings.hooks) else {
+        return;
+    };
+    let command = command.to_string();
+
+    if let Value::Object(map) = &mut context {
+        map.insert("hook".into(), hook.name().into());
+    }
+    debug!("Running {} hook: {}", hook.name(), command);
+    spawn(format!("{} hook", hook.name()), command, context, Vec::new());
+}
+
+/// Run a custom command line that was entered after `:`, in the background.
+/// The first word is the name in the `[commands]` section; the rest is passed
+/// as `args`. Returns false if there is no such command.
+///
+/// If the TUI listens for remote control on `socket`, its path is passed in
+/// `VCTOOLS_SOCKET`, so that the command can query and change the TUI's state
+/// in turn.
+pub fn run_custom_command(line: &str, mut context: Value, socket: Option<&Path>) -> bool {
+    let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+    let settings = Settings::get();
+    let Some(command) = settings.commands.get(name) else {
+        return false;
+    };
+    let command = command.to_string();
+
+    if let Value::Object(map) = &mut context {
+        map.insert("command".into(), name.into());
+        map.insert("args".into(), args.into());
+    }
+    let env = socket
+        .map(|socket| ("VCTOOLS_SOCKET", socket.display().to_string()))
+        .into_iter()
+        .collect();
+    debug!("Running command {}: {}", name, command);
+    spawn(format!("Command {name}"), command, context, env);
+    true
+}
+
+/// Run a command in the background with `context` on stdin, logging if it
+/// fails.
+fn spawn(what: String, command: String, context: Value, env: Vec<(&'static str, String)>) {
+    std::thread::spawn(move || {
+        let result = (|| -> std::io::Result<std::process::ExitStatus> {
+            let mut child = shell_command(&command)
+                .envs(env)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                // The command may not read its input at all.
+                let _ = writeln!(stdin, "{}", context);
+            }
+            child.wait()
+        })();
+        match result {
+            Ok(status) if status.success() => {}
+            Ok(status) => warn!("{} `{}` failed: {}", what, command, status),
+            Err(err) => warn!("{} `{}` failed: {}", what, command, err),
+        }
+    });
+}
+
+/// Run commands one after the other in `dir`, in the background, with the
+/// given additional environment variables. Stops at the first command that
+/// fails.
+///
+/// `done` is called with the error output of the failed command, if any.
+pub fn run_commands(
+    commands: Vec<String>,
+    dir: PathBuf,
+    env: Vec<(&'static str, String)>,
+    done: impl FnOnce(std::result::Result<(), String>) + Send + 'static,
+) {
+    std::thread::spawn(move || {
+        for command in commands {
+            debug!("Running `{}` in {}", command, dir.display());
+            let output = shell_command(&command)
+                .current_dir(&dir)
+                .envs(env.iter().map(|(name, value)| (name, value)))
+                .stdin(Stdio::null())
+                .output();
+            let error = match output {
+                Ok(output) if output.status.success() => continue,
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let last = stderr.lines().rev().find(|line| !line.trim().is_empty());
+                    format!("`{command}` failed ({}): {}", output.status, last.unwrap_or(""))
+                }
+                Err(err) => format!("`{command}` failed: {err}"),
+            };
+            warn!("{}", error);
+            done(Err(error));
+            return;
+        }
+        done(Ok(()));
+    });
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Translatable user-visible strings.
+//!
+//! The English messages are built in. Translations are loaded from
+//! `i18n/<language>.ftl` in the config directory, where the language is taken
+//! from the environment (`LC_ALL`, `LC_MESSAGES` or `LANG`). Messages that are
+//! missing from a translation fall back to English.
+//!
+//! Use the `tr!` macro to look up messages:
+//!
+//! ```ignore
+//! error = Some(tr!("inbox-error-undo", error = err));
+//! ```
+
+use std::fmt::Display;
+
+use lazy_static::lazy_static;
+use log::warn;
+
+use vctools_utils::{files, i18n::Catalog, prelude::*};
+
+use crate::get_project_dirs;
+
+const ENGLISH: &str = include_str!("../i18n/en.ftl");
+
+lazy_static! {
+    static ref CATALOG: Catalog = load();
+}
+
+fn load() -> Catalog {
+    let mut catalog = Catalog::parse(ENGLISH).expect("built-in messages must parse");
+
+    let Some(language) = vctools_utils::i18n::language_from_env() else {
+        return catalog;
+    };
+    if language == "en" {
+        return catalog;
+    }
+
+    let path = get_project_dirs()
+        .config_dir()
+        .join("i18n")
+        .join(format!("{language}.ftl"));
+    if !path.exists() {
+        return catalog;
+    }
+    let translation = try_forward(
+        || Catalog::parse(&String::from_utf8(files::read_bytes(&path)?)?),
+        || format!("Error loading {}", path.display()),
+    );
+    match translation {
+        Ok(translation) => catalog.merge(translation),
+        Err(err) => warn!("{err}"),
+    }
+    catalog
+}
+
+/// Look up and format a message. Unknown IDs are returned as they are, so
+/// that they are easy to spot.
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    CATALOG.format(id, args).unwrap_or_else(|| id.to_string())
+}
+
+/// Look up a message by ID, with optional `name = value` arguments.
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::message($id, &[])
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+],
+        )
+    };
+}
@@ -0,0 +1,388 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Remote control of the TUIs over a Unix domain socket.
+//!
+//! The protocol is JSON-RPC 2.0 with one request or response per line. The
+//! requests are executed by the UI thread between frames, so that scripts see
+//! the same behavior as interactive use. Each tool defines its own methods in
+//! addition to those of [`ForgeMethods`]; a typical request looks like:
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "run", "params": {"command": "edits"}}
+//! ```
+//!
+//! Unix domain sockets are not available on Windows, where listening fails.
+
+use std::{path::PathBuf, sync::mpsc};
+#[cfg(unix)]
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+};
+
+#[cfg(unix)]
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use vctools_utils::prelude::*;
+use vctuik::signals::MergeWakeupSignal;
+
+use crate::{
+    github::{
+        api,
+        connections::Connections,
+        edit::{PullMerge, ReviewEvent, ReviewSubmit},
+    },
+    merge_readiness::{self, Status},
+    ApiRepository,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct RawRequest {
+    #[serde(default)]
+    pub(crate) id: Value,
+    pub(crate) method: String,
+    #[serde(default)]
+    pub(crate) params: Value,
+}
+
+/// Parse a line with a request. If it can't be parsed, returns the error
+/// response to send instead.
+pub(crate) fn parse_request(line: &str) -> std::result::Result<RawRequest, Value> {
+    serde_json::from_str::<RawRequest>(line)
+        .map_err(|err| error_response(Value::Null, PARSE_ERROR, &err.to_string()))
+}
+
+/// The response to a request with the given ID.
+pub(crate) fn response(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(value) => json!({"jsonrpc": "2.0", "id": id, "result": value}),
+        Err(err) => error_response(id, err.code, &err.message),
+    }
+}
+
+/// Get a string parameter by name.
+pub fn param_str<'a>(params: &'a Value, name: &str) -> std::result::Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing parameter: {name}")))
+}
+
+/// Error returned to the client.
+#[derive(Debug)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+impl RpcError {
+    pub fn method_not_found(method: &str) -> Self {
+        Self {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method: {method}"),
+        }
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
+impl From<Error> for RpcError {
+    fn from(err: Error) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: err.to_string(),
+        }
+    }
+}
+impl From<std::borrow::Cow<'static, str>> for RpcError {
+    fn from(err: std::borrow::Cow<'static, str>) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: err.into_owned(),
+        }
+    }
+}
+
+/// A request that is waiting to be handled by the UI thread.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub params: Value,
+    reply: mpsc::Sender<std::result::Result<Value, RpcError>>,
+}
+impl Request {
+    /// Get a string parameter by name.
+    pub fn param_str(&self, name: &str) -> std::result::Result<&str, RpcError> {
+        param_str(&self.params, name)
+    }
+
+    pub fn reply(self, result: std::result::Result<Value, RpcError>) {
+        // The client may have disconnected in the meantime.
+        let _ = self.reply.send(result);
+    }
+}
+
+/// Listens for requests on a Unix domain socket. The socket is removed when
+/// the server is dropped.
+#[derive(Debug)]
+pub struct CommandServer {
+    path: PathBuf,
+    requests: mpsc::Receiver<Request>,
+}
+impl CommandServer {
+    /// Start listening. The wakeup signal is triggered whenever a request
+    /// arrives.
+    #[cfg(not(unix))]
+    pub fn listen(path: PathBuf, _wakeup: MergeWakeupSignal) -> Result<Self> {
+        Err(format!(
+            "Failed to listen on {}: not supported on this platform",
+            path.display()
+        ))?
+    }
+
+    /// Start listening. The wakeup signal is triggered whenever a request
+    /// arrives.
+    #[cfg(unix)]
+    pub fn listen(path: PathBuf, wakeup: MergeWakeupSignal) -> Result<Self> {
+        let listener = try_forward(
+            || Ok(UnixListener::bind(&path)?),
+            || format!("Failed to listen on {}", path.display()),
+        )?;
+        info!("Listening for commands on {}", path.display());
+
+        let (send, requests) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let send = send.clone();
+                        let wakeup = wakeup.clone();
+                        std::thread::spawn(move || serve_connection(stream, send, wakeup));
+                    }
+                    Err(err) => warn!("Error accepting command connection: {}", err),
+                }
+            }
+        });
+
+        Ok(Self { path, requests })
+    }
+
+    /// Take all requests that have arrived so far.
+    pub fn take_requests(&self) -> Vec<Request> {
+        self.requests.try_iter().collect()
+    }
+}
+impl Drop for CommandServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+fn serve_connection(stream: UnixStream, send: mpsc::Sender<Request>, wakeup: MergeWakeupSignal) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            warn!("Error setting up command connection: {}", err);
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match parse_request(&line) {
+            Ok(raw) => {
+                let (reply, result) = mpsc::channel();
+                let request = Request {
+                    method: raw.method,
+                    params: raw.params,
+                    reply,
+                };
+                if send.send(request).is_err() {
+                    // The UI has shut down.
+                    break;
+                }
+                wakeup.signal();
+
+                match result.recv() {
+                    Ok(result) => response(raw.id, result),
+                    Err(_) => break,
+                }
+            }
+            Err(response) => response,
+        };
+
+        if writeln!(writer, "{}", response).is_err() {
+            break;
+        }
+    }
+}
+
+/// Methods for querying and changing pull requests that all TUIs offer. They
+/// take the `host`, `owner`, `name`, and `number` of the pull request:
+///
+/// - `pull`: its state, head commit, the overall state of CI, and what stands
+///   in the way of merging it.
+/// - `approve`, optionally with `body` and `sha`: queues an approving review
+///   of the given head commit.
+/// - `merge`, optionally with `method` ("merge", "squash", or "rebase") and
+///   `sha`: queues merging it. If `sha` is given, the merge fails if the head
+///   has changed in the meantime.
+///
+/// Together, they allow scripts to e.g. approve and merge a pull request once
+/// CI is green.
+#[derive(Debug, Default)]
+pub struct ForgeMethods {
+    /// Requests that are waiting for data from the forge.
+    pending: Vec<Request>,
+}
+impl ForgeMethods {
+    /// Handle the requests for forge methods. Requests whose data is still
+    /// being loaded are kept and handled in a later frame. Returns the
+    /// requests for other methods.
+    ///
+    /// Only allowed between `Connections::start_frame` and `end_frame`.
+    pub fn handle(
+        &mut self,
+        requests: Vec<Request>,
+        connections: &mut Connections,
+    ) -> Vec<Request> {
+        let mut other = Vec::new();
+        for request in std::mem::take(&mut self.pending).into_iter().chain(requests) {
+            let result = match request.method.as_str() {
+                "pull" => pull_status(&request, connections),
+                "approve" => approve(&request, connections).map(Some),
+                "merge" => merge(&request, connections).map(Some),
+                _ => {
+                    other.push(request);
+                    continue;
+                }
+            };
+            match result {
+                Ok(Some(value)) => request.reply(Ok(value)),
+                Ok(None) => self.pending.push(request),
+                Err(err) => request.reply(Err(err)),
+            }
+        }
+        other
+    }
+}
+
+/// The pull request that a forge method refers to.
+fn pull_params(request: &Request) -> std::result::Result<(ApiRepository, u64), RpcError> {
+    let repo = ApiRepository::new(
+        request.param_str("host")?.into(),
+        request.param_str("owner")?.into(),
+        request.param_str("name")?.into(),
+    );
+    let number = request
+        .params
+        .get("number")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| RpcError::invalid_params("Missing parameter: number"))?;
+    Ok((repo, number))
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Met => "met",
+        Status::Pending => "pending",
+        Status::Unmet => "unmet",
+        Status::Unknown => "unknown",
+    }
+}
+
+/// The `pull` method, or None while its data is loading.
+fn pull_status(
+    request: &Request,
+    connections: &mut Connections,
+) -> std::result::Result<Option<Value>, RpcError> {
+    let (repo, number) = pull_params(request)?;
+    let mut client = connections.client(&repo.host)?.borrow_mut();
+    let client = client.access();
+    let pull = client.pull(&repo.owner, &repo.name, number);
+    let reviews = client.reviews(&repo.owner, &repo.name, number);
+
+    let (Some(pull), Some(reviews)) = (pull.ok_or_pending()?, reviews.ok_or_pending()?) else {
+        return Ok(None);
+    };
+    let status = client.combined_status(&repo.owner, &repo.name, &pull.head.sha);
+    let checks = client.check_runs(&repo.owner, &repo.name, &pull.head.sha);
+    let items = merge_readiness::load(&client, &repo, &pull, Some(&reviews))?;
+    let (Some(status), Some(checks), Some(items)) =
+        (status.ok_or_pending()?, checks.ok_or_pending()?, items)
+    else {
+        return Ok(None);
+    };
+
+    let state = match pull.state {
+        _ if pull.merged => "merged",
+        api::PullState::Open => "open",
+        api::PullState::Closed => "closed",
+        api::PullState::Other => "unknown",
+    };
+    let blockers: Vec<&str> =
+        merge_readiness::blockers(&items).map(|item| item.text.as_str()).collect();
+    Ok(Some(json!({
+        "title": pull.title,
+        "state": state,
+        "draft": pull.draft,
+        "sha": pull.head.sha,
+        "ci": status_name(merge_readiness::ci_status(&status, &checks)),
+        "blockers": blockers,
+    })))
+}
+
+fn approve(
+    request: &Request,
+    connections: &mut Connections,
+) -> std::result::Result<Value, RpcError> {
+    let (repo, number) = pull_params(request)?;
+    let edit = ReviewSubmit {
+        owner: repo.owner,
+        name: repo.name,
+        number,
+        event: ReviewEvent::Approve,
+        body: request.params.get("body").and_then(Value::as_str).unwrap_or_default().into(),
+        commit_id: request.params.get("sha").and_then(Value::as_str).map(Into::into),
+    };
+    connections.edit(repo.host, edit.into(), None)?;
+    Ok(Value::Null)
+}
+
+fn merge(
+    request: &Request,
+    connections: &mut Connections,
+) -> std::result::Result<Value, RpcError> {
+    let (repo, number) = pull_params(request)?;
+    let method = request.params.get("method").and_then(Value::as_str);
+    if let Some(method) = method.filter(|m| !["merge", "squash", "rebase"].contains(m)) {
+        Err(RpcError::invalid_params(format!("Unknown merge method: {method}")))?
+    }
+    let edit = PullMerge {
+        owner: repo.owner,
+        name: repo.name,
+        number,
+        method: method.map(Into::into),
+        sha: request.params.get("sha").and_then(Value::as_str).map(Into::into),
+    };
+    connections.edit(repo.host, edit.into(), None)?;
+    Ok(Value::Null)
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
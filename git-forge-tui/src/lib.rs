@@ -1,14 +1,38 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod app;
+pub mod apply;
+pub mod batch;
+pub mod browser;
 mod config;
+pub mod contributor_stats;
+pub mod dependencies;
+pub mod generated;
 pub mod github;
 pub mod gitservice;
+pub mod hooks;
+pub mod i18n;
+pub mod ipc;
 pub mod logview;
+pub mod merge_readiness;
+pub mod metrics;
+pub mod persistence;
+pub mod quickfix;
+pub mod review_notes;
+pub mod review_time;
+pub mod risk;
+pub mod serve;
+pub mod settings;
+pub mod soak;
+pub mod task_list;
+pub mod todos;
+pub mod triage;
 pub mod tui;
 
 pub use config::{get_project_dirs, load_config};
 
 use diff_modulo_base::git_core;
+use serde::{Deserialize, Serialize};
 use vctools_utils::prelude::*;
 
 /// Reference to a forge repository through a local clone and a remote.
@@ -27,7 +51,7 @@ impl GitRepository {
 }
 
 /// Reference to a forge repository through its API.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ApiRepository {
     pub host: String,
     pub owner: String,
@@ -97,7 +121,7 @@ impl CompletePullRequest {
             Err(format!("cannot parse {url} as a GitHub repository"))?
         };
 
-        let Some(host) = hosts.iter().find(|host| host.matches_host(hostname)) else {
+        let Some(host) = github::Host::find_for_url(hosts, &url) else {
             Err(format!(
                 "Host not configured; add it to your github.toml: {hostname}"
             ))?
@@ -124,4 +148,16 @@ impl CompletePullRequest {
             id,
         })
     }
+
+    pub fn git(&self) -> &GitRepository {
+        &self.git
+    }
+
+    pub fn api(&self) -> &ApiRepository {
+        &self.api
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
 }
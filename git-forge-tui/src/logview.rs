@@ -15,27 +15,30 @@ pub fn add_log_view(builder: &mut Builder) {
     let has_focus = builder.check_focus(state_id);
 
     if has_focus {
-        let event = if builder.on_key_press(KeyCode::Char(' ')) {
+        let event = if builder
+            .on_key_binding(KeyCode::Char(' '), "Toggle hiding of the selected target")
+        {
             Some(TuiWidgetEvent::SpaceKey)
-        } else if builder.on_key_press(KeyCode::Down) {
+        } else if builder.on_key_binding(KeyCode::Down, "Select target") {
             Some(TuiWidgetEvent::DownKey)
-        } else if builder.on_key_press(KeyCode::Up) {
+        } else if builder.on_key_binding(KeyCode::Up, "Select target") {
             Some(TuiWidgetEvent::UpKey)
-        } else if builder.on_key_press(KeyCode::Left) {
+        } else if builder.on_key_binding(KeyCode::Left, "Change the level of the selected target") {
             Some(TuiWidgetEvent::LeftKey)
-        } else if builder.on_key_press(KeyCode::Right) {
+        } else if builder.on_key_binding(KeyCode::Right, "Change the level of the selected target")
+        {
             Some(TuiWidgetEvent::RightKey)
-        } else if builder.on_key_press(KeyCode::Char('+')) {
+        } else if builder.on_key_binding(KeyCode::Char('+'), "Change the captured level") {
             Some(TuiWidgetEvent::PlusKey)
-        } else if builder.on_key_press(KeyCode::Char('-')) {
+        } else if builder.on_key_binding(KeyCode::Char('-'), "Change the captured level") {
             Some(TuiWidgetEvent::MinusKey)
-        } else if builder.on_key_press(KeyCode::Char('h')) {
+        } else if builder.on_key_binding(KeyCode::Char('h'), "Toggle the target selector") {
             Some(TuiWidgetEvent::HideKey)
-        } else if builder.on_key_press(KeyCode::Char('f')) {
+        } else if builder.on_key_binding(KeyCode::Char('f'), "Focus on the selected target") {
             Some(TuiWidgetEvent::FocusKey)
-        } else if builder.on_key_press(KeyCode::PageDown) {
+        } else if builder.on_key_binding(KeyCode::PageDown, "Scroll the log") {
             Some(TuiWidgetEvent::NextPageKey)
-        } else if builder.on_key_press(KeyCode::PageUp) {
+        } else if builder.on_key_binding(KeyCode::PageUp, "Scroll the log") {
             Some(TuiWidgetEvent::PrevPageKey)
         } else {
             None
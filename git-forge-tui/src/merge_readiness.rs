@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Whether a pull request satisfies the rules of its target branch for
+//! merging: required approvals, required checks, and resolved conversations.
+//!
+//! The rules come from the classic protection of the branch and from
+//! rulesets. Only admins of a repository can read all of the classic
+//! protection; everybody else only sees its required checks, so that the
+//! other requirements may be unknown.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    github::{api, ClientRef, Response, Shared},
+    ApiRepository,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeRules {
+    pub required_approvals: u32,
+    pub code_owner_review: bool,
+    pub conversation_resolution: bool,
+    pub required_checks: Vec<String>,
+
+    /// Whether the review and conversation requirements of the classic
+    /// protection could not be read.
+    pub incomplete: bool,
+}
+impl MergeRules {
+    /// Combine the rules of the classic protection and of rulesets. Pass None
+    /// for `protection` if it could not be read.
+    pub fn new(
+        branch: &api::BranchDetails,
+        protection: Option<&api::BranchProtection>,
+        rules: &[api::BranchRule],
+    ) -> Self {
+        let mut result = MergeRules {
+            incomplete: branch.protected && protection.is_none(),
+            ..Default::default()
+        };
+        let mut add_checks = |checks: &api::RequiredStatusChecks| {
+            let contexts = checks.checks.iter().map(|check| &check.context);
+            result.required_checks.extend(checks.contexts.iter().chain(contexts).cloned());
+        };
+        if let Some(checks) = &branch.protection.required_status_checks {
+            add_checks(checks);
+        }
+        if let Some(protection) = protection {
+            if let Some(checks) = &protection.required_status_checks {
+                add_checks(checks);
+            }
+            if let Some(reviews) = &protection.required_pull_request_reviews {
+                result.required_approvals = reviews.required_approving_review_count;
+                result.code_owner_review = reviews.require_code_owner_reviews;
+            }
+            result.conversation_resolution =
+                protection.required_conversation_resolution.as_ref().is_some_and(|r| r.enabled);
+        }
+
+        for rule in rules {
+            let parameters = &rule.parameters;
+            match rule.rule_type.as_str() {
+                "pull_request" => {
+                    result.required_approvals =
+                        result.required_approvals.max(parameters.required_approving_review_count);
+                    result.code_owner_review |= parameters.require_code_owner_review;
+                    result.conversation_resolution |= parameters.required_review_thread_resolution;
+                }
+                "required_status_checks" => result
+                    .required_checks
+                    .extend(parameters.required_status_checks.iter().map(|c| c.context.clone())),
+                _ => {}
+            }
+        }
+
+        result.required_checks.sort();
+        result.required_checks.dedup();
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Met,
+    Pending,
+    Unmet,
+    Unknown,
+}
+impl Status {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Status::Met => "✓",
+            Status::Pending => "…",
+            Status::Unmet => "✗",
+            Status::Unknown => "?",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub status: Status,
+    pub text: String,
+}
+impl Item {
+    fn new(status: Status, text: impl Into<String>) -> Self {
+        Self {
+            status,
+            text: text.into(),
+        }
+    }
+}
+
+fn run_status(run: &api::CheckRun) -> Status {
+    match (run.status.as_str(), run.conclusion.as_deref()) {
+        ("completed", Some("success" | "neutral" | "skipped")) => Status::Met,
+        ("completed", _) => Status::Unmet,
+        _ => Status::Pending,
+    }
+}
+
+fn commit_status(status: &api::CommitStatus) -> Status {
+    match status.state.as_str() {
+        "success" => Status::Met,
+        "pending" => Status::Pending,
+        _ => Status::Unmet,
+    }
+}
+
+/// State of a check run or commit status with the given name.
+fn check_status(name: &str, status: &api::CombinedStatus, checks: &api::CheckRuns) -> Status {
+    if let Some(run) = checks.check_runs.iter().find(|run| run.name == name) {
+        return run_status(run);
+    }
+    match status.statuses.iter().find(|status| status.context == name) {
+        Some(status) => commit_status(status),
+        // Expected, but not reported yet.
+        None => Status::Pending,
+    }
+}
+
+/// Overall state of all check runs and commit statuses, whether they are
+/// required or not. Met if there are none.
+pub fn ci_status(status: &api::CombinedStatus, checks: &api::CheckRuns) -> Status {
+    let runs = checks.check_runs.iter().map(run_status);
+    let all: Vec<Status> = runs.chain(status.statuses.iter().map(commit_status)).collect();
+    if all.contains(&Status::Unmet) {
+        Status::Unmet
+    } else if all.contains(&Status::Pending) {
+        Status::Pending
+    } else {
+        Status::Met
+    }
+}
+
+/// The checklist of what the pull request needs for merging. `threads` is
+/// None if the review threads are not loaded (yet).
+pub fn checklist(
+    rules: &MergeRules,
+    pull: &api::Pull,
+    reviews: &[api::Review],
+    status: &api::CombinedStatus,
+    checks: &api::CheckRuns,
+    threads: Option<&[api::ReviewThread]>,
+) -> Vec<Item> {
+    let mut items = Vec::new();
+    if pull.draft {
+        items.push(Item::new(Status::Unmet, "Marked as draft"));
+    }
+
+    // The most recent approval or request for changes of each reviewer counts.
+    let mut verdicts: HashMap<&str, api::ReviewState> = HashMap::new();
+    for review in reviews {
+        match review.state {
+            api::ReviewState::Approved
+            | api::ReviewState::ChangesRequested
+            | api::ReviewState::Dismissed => {
+                verdicts.insert(&review.user.login, review.state);
+            }
+            _ => {}
+        }
+    }
+    let approvals = verdicts.values().filter(|state| **state == api::ReviewState::Approved).count();
+    let mut changes_requested: Vec<&str> = verdicts
+        .iter()
+        .filter(|(_, state)| **state == api::ReviewState::ChangesRequested)
+        .map(|(login, _)| *login)
+        .collect();
+    changes_requested.sort();
+
+    let required = rules.required_approvals as usize;
+    items.push(if required > 0 {
+        let status = if approvals >= required { Status::Met } else { Status::Unmet };
+        Item::new(status, format!("Approvals: {approvals} of {required} required"))
+    } else if rules.incomplete {
+        Item::new(Status::Unknown, format!("Approvals: {approvals} (requirement unknown)"))
+    } else {
+        Item::new(Status::Met, format!("Approvals: {approvals} (none required)"))
+    });
+    if rules.code_owner_review {
+        items.push(Item::new(Status::Unknown, "Review by code owners required"));
+    }
+    if !changes_requested.is_empty() {
+        let reviewers: Vec<String> = changes_requested.iter().map(|l| format!("@{l}")).collect();
+        items.push(Item::new(
+            Status::Unmet,
+            format!("Changes requested by {}", reviewers.join(", ")),
+        ));
+    }
+
+    for name in &rules.required_checks {
+        let status = check_status(name, status, checks);
+        items.push(Item::new(status, format!("Required check: {name}")));
+    }
+
+    if rules.conversation_resolution {
+        items.push(match threads {
+            Some(threads) => {
+                let unresolved = threads.iter().filter(|thread| !thread.is_resolved).count();
+                if unresolved == 0 {
+                    Item::new(Status::Met, "All conversations resolved")
+                } else {
+                    Item::new(Status::Unmet, format!("{unresolved} unresolved conversation(s)"))
+                }
+            }
+            None => Item::new(Status::Unknown, "Conversations: not loaded"),
+        });
+    } else if rules.incomplete {
+        items.push(Item::new(Status::Unknown, "Conversation resolution: requirement unknown"));
+    }
+    items
+}
+
+/// Items of the checklist that stand in the way of merging, e.g. to warn
+/// before a merge is attempted.
+pub fn blockers(items: &[Item]) -> impl Iterator<Item = &Item> {
+    items.iter().filter(|item| matches!(item.status, Status::Pending | Status::Unmet))
+}
+
+/// The checklist of what the pull request needs for merging, or None while
+/// its data is loading.
+pub fn load(
+    client: &ClientRef,
+    repo: &ApiRepository,
+    pull: &api::Pull,
+    reviews: Option<&[api::Review]>,
+) -> std::result::Result<Option<Vec<Item>>, Cow<'static, str>> {
+    let (owner, name, base) = (&repo.owner, &repo.name, &pull.base.ref_);
+    let branch = client.branch(owner, name, base);
+    let protection = client.branch_protection(owner, name, base);
+    let rules = client.branch_rules(owner, name, base);
+    let status = client.combined_status(owner, name, &pull.head.sha);
+    let checks = client.check_runs(owner, name, &pull.head.sha);
+    let threads = client.review_threads(owner, name, pull.number);
+
+    if protection.is_pending() || threads.is_pending() {
+        return Ok(None);
+    }
+    // Only admins can read the classic protection, and older servers don't
+    // have rulesets.
+    let protection = protection.ok().ok();
+    let rules = match rules {
+        Response::NotFound => Some(Vec::new()),
+        rules => rules.ok_or_pending()?.map(Shared::into_owned),
+    };
+    let (Some(branch), Some(rules), Some(status), Some(checks), Some(reviews)) = (
+        branch.ok_or_pending()?,
+        rules,
+        status.ok_or_pending()?,
+        checks.ok_or_pending()?,
+        reviews,
+    ) else {
+        return Ok(None);
+    };
+
+    let rules = MergeRules::new(&branch, protection.as_deref(), &rules);
+    let threads = threads.ok().ok();
+    Ok(Some(checklist(
+        &rules,
+        pull,
+        reviews,
+        &status,
+        &checks,
+        threads.as_deref().map(Vec::as_slice),
+    )))
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Export of internal counters in the Prometheus text format.
+//!
+//! The metrics are written periodically to a file, which can be picked up by
+//! the textfile collector of the node exporter, or simply inspected by hand.
+
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::github::connections::Connections;
+
+/// How often the file is rewritten.
+const WRITE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Number of recent frames from which the frame time percentiles are computed.
+const FRAME_HISTORY: usize = 1000;
+
+#[derive(Debug)]
+pub struct MetricsExporter {
+    path: PathBuf,
+    last_write: Option<Instant>,
+    frames: u64,
+    frame_times: VecDeque<Duration>,
+}
+impl MetricsExporter {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_write: None,
+            frames: 0,
+            frame_times: VecDeque::new(),
+        }
+    }
+
+    /// Record a frame that was started at `start` and write the metrics file
+    /// if it is due.
+    ///
+    /// Must be called between `Connections::start_frame` and `end_frame`.
+    pub fn end_frame(&mut self, start: Instant, connections: &mut Connections) {
+        self.frames += 1;
+        if self.frame_times.len() >= FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(start.elapsed());
+
+        if self.last_write.is_some_and(|last| last.elapsed() < WRITE_INTERVAL) {
+            return;
+        }
+        self.last_write = Some(Instant::now());
+
+        let text = self.format(connections);
+        // Write atomically so that readers never see a partial file.
+        let tmp = self.path.with_extension("tmp");
+        let result = std::fs::write(&tmp, text).and_then(|_| std::fs::rename(&tmp, &self.path));
+        if let Err(err) = result {
+            warn!("Error writing metrics to {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn format(&self, connections: &mut Connections) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, values: &[(String, f64)]| {
+            writeln!(out, "# HELP git_forge_tui_{name} {help}").unwrap();
+            writeln!(out, "# TYPE git_forge_tui_{name} {kind}").unwrap();
+            for (labels, value) in values {
+                writeln!(out, "git_forge_tui_{name}{labels} {value}").unwrap();
+            }
+        };
+
+        let mut requests = Vec::new();
+        let mut errors = Vec::new();
+        let mut hits = Vec::new();
+        let mut misses = Vec::new();
+        let mut contended = Vec::new();
+        let mut lock_wait = Vec::new();
+        let mut remaining = Vec::new();
+        for (host, client) in connections.all_clients() {
+            let Ok(client) = client else { continue };
+            let client = client.borrow();
+            let labels = format!("{{host=\"{}\"}}", host.host);
+            let stats = client.stats();
+            requests.push((labels.clone(), stats.requests as f64));
+            errors.push((labels.clone(), stats.errors as f64));
+            hits.push((labels.clone(), stats.cache_hits as f64));
+            misses.push((labels.clone(), stats.cache_misses as f64));
+            contended.push((labels.clone(), stats.cache_lock_contended as f64));
+            lock_wait.push((labels.clone(), stats.cache_lock_wait.as_secs_f64()));
+            if let Some(rate_limit) = client.rate_limit() {
+                remaining.push((labels, rate_limit.remaining as f64));
+            }
+        }
+        metric("requests_total", "counter", "API requests sent", &requests);
+        metric("request_errors_total", "counter", "API requests that failed", &errors);
+        metric("cache_hits_total", "counter", "Requests with a cached response", &hits);
+        metric("cache_misses_total", "counter", "Requests without a cached response", &misses);
+        metric(
+            "cache_lock_contended_total",
+            "counter",
+            "Cache locks that had to wait for another thread",
+            &contended,
+        );
+        metric(
+            "cache_lock_wait_seconds_total",
+            "counter",
+            "Time spent waiting for the cache lock",
+            &lock_wait,
+        );
+        metric("rate_limit_remaining", "gauge", "Remaining API rate limit", &remaining);
+
+        metric("frames_total", "counter", "Frames drawn", &[(String::new(), self.frames as f64)]);
+
+        let mut times: Vec<Duration> = self.frame_times.iter().copied().collect();
+        times.sort();
+        let quantiles: Vec<_> = [0.5, 0.9, 0.99]
+            .into_iter()
+            .filter(|_| !times.is_empty())
+            .map(|q| {
+                let idx = ((times.len() - 1) as f64 * q).round() as usize;
+                (format!("{{quantile=\"{q}\"}}"), times[idx].as_secs_f64())
+            })
+            .collect();
+        metric("frame_seconds", "summary", "Time spent building frames", &quantiles);
+
+        out
+    }
+}
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Crash recovery for the TUIs.
+//!
+//! State that would hurt to lose, like the locations that are open and the
+//! drafts of comments, is written periodically to a journal in the data
+//! directory. A clean exit removes the journal, so finding one on start means
+//! that the previous session crashed, and its state is offered for recovery.
+//!
+//! The journal is double-buffered: snapshots alternate between two files,
+//! each replaced atomically, and carry a sequence number. If the newer file
+//! is damaged anyway, the older snapshot is recovered.
+
+use std::{
+    collections::BTreeMap,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use vctools_utils::{files, prelude::*, time};
+
+use crate::tui::navigation::Location;
+
+/// How often the journal is written, if the state has changed.
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref DRAFTS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+}
+
+/// Remember the text of a draft, e.g. of a comment on a pull request, so that
+/// it becomes part of the journal. An empty text forgets the draft.
+pub fn set_draft(key: &str, text: &str) {
+    let mut drafts = DRAFTS.lock().unwrap();
+    if text.is_empty() {
+        drafts.remove(key);
+    } else if drafts.get(key).is_none_or(|old| old != text) {
+        drafts.insert(key.to_string(), text.to_string());
+    }
+}
+
+/// Take a draft that was recovered from the journal, if any.
+pub fn take_draft(key: &str) -> Option<String> {
+    DRAFTS.lock().unwrap().remove(key)
+}
+
+/// All drafts, by key.
+pub fn drafts() -> BTreeMap<String, String> {
+    DRAFTS.lock().unwrap().clone()
+}
+
+/// Add drafts that were recovered from the journal.
+pub fn restore_drafts(drafts: BTreeMap<String, String>) {
+    DRAFTS.lock().unwrap().extend(drafts);
+}
+
+/// Write a file by writing a temporary file next to it first and renaming
+/// it, so that readers and later sessions never see a partial file, even
+/// after a crash.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    try_forward(
+        || -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut tmp = path.as_os_str().to_owned();
+            tmp.push(".tmp");
+            std::fs::write(&tmp, contents)?;
+            std::fs::rename(&tmp, path)?;
+            Ok(())
+        },
+        || format!("Error writing {}", path.display()),
+    )
+}
+
+/// State of a session of the TUIs that is recovered after a crash.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// Locations of the navigation stack, from the root.
+    #[serde(default)]
+    pub locations: Vec<Location>,
+
+    /// Drafts of comments, by key.
+    #[serde(default)]
+    pub drafts: BTreeMap<String, String>,
+}
+impl Session {
+    /// The current session, with the given locations and all drafts.
+    pub fn capture(locations: &[Location]) -> Self {
+        Self {
+            locations: locations.to_vec(),
+            drafts: drafts(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Snapshot<T> {
+    sequence: u64,
+
+    /// Unix timestamp of when the snapshot was taken.
+    saved_at: i64,
+
+    state: T,
+}
+
+/// A journal of snapshots of the state of type T.
+#[derive(Debug)]
+pub struct Journal<T> {
+    /// The two files between which snapshots alternate.
+    paths: [PathBuf; 2],
+    sequence: u64,
+    last_write: Option<Instant>,
+
+    /// Serialized state of the last snapshot, to skip unchanged snapshots.
+    last_state: Vec<u8>,
+
+    _marker: PhantomData<fn(&T)>,
+}
+impl<T: Serialize + DeserializeOwned> Journal<T> {
+    /// Open the journal with the given name in the given directory.
+    ///
+    /// Returns the state of the last snapshot and when it was taken, if the
+    /// previous session didn't remove its journal, i.e. it crashed. The
+    /// snapshot is kept until a new one is written.
+    pub fn open(dir: &Path, name: &str) -> (Self, Option<(T, i64)>) {
+        let paths = [0, 1].map(|idx| dir.join(format!("{name}.journal.{idx}")));
+        let newest = paths
+            .iter()
+            .filter(|path| path.exists())
+            .filter_map(|path| {
+                let result = try_forward(
+                    || -> Result<Snapshot<T>> {
+                        Ok(serde_json::from_slice(&files::read_bytes(path)?)?)
+                    },
+                    || format!("Error loading {}", path.display()),
+                );
+                result.map_err(|err| warn!("{}", err)).ok()
+            })
+            .max_by_key(|snapshot| snapshot.sequence);
+
+        let journal = Self {
+            paths,
+            sequence: newest.as_ref().map_or(0, |snapshot| snapshot.sequence + 1),
+            last_write: None,
+            last_state: Vec::new(),
+            _marker: PhantomData,
+        };
+        (journal, newest.map(|snapshot| (snapshot.state, snapshot.saved_at)))
+    }
+
+    /// Write a snapshot if the last one is old enough and the state has
+    /// changed since.
+    pub fn update(&mut self, state: &T) -> Result<()> {
+        if self.last_write.is_some_and(|last| last.elapsed() < JOURNAL_INTERVAL) {
+            return Ok(());
+        }
+        self.last_write = Some(Instant::now());
+
+        let serialized = serde_json::to_vec(state)?;
+        if serialized == self.last_state {
+            return Ok(());
+        }
+
+        let snapshot = Snapshot {
+            sequence: self.sequence,
+            saved_at: time::unix_now(),
+            state,
+        };
+        let path = &self.paths[(self.sequence % 2) as usize];
+        write_atomic(path, &serde_json::to_vec(&snapshot)?)?;
+        self.sequence += 1;
+        self.last_state = serialized;
+        Ok(())
+    }
+
+    /// Remove the journal at the end of a session that ended cleanly.
+    pub fn finish(self) -> Result<()> {
+        for path in &self.paths {
+            if path.exists() {
+                try_forward(
+                    || -> Result<()> { Ok(std::fs::remove_file(path)?) },
+                    || format!("Error removing {}", path.display()),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Changed locations of a review in the quickfix format of Vim and Neovim.
+//!
+//! Each entry is written as `file:line:col: message`, which the default
+//! `errorformat` understands, so that e.g. `:cfile` or `nvim -q` can be used
+//! to jump through the hunks and unresolved review threads.
+
+use std::io::Write;
+
+use diff_modulo_base::api::{FileHunks, Hunk, LineKind};
+use vctools_utils::prelude::*;
+
+use crate::github::api;
+
+/// Maximum number of characters of a line or comment that is shown.
+const MAX_TEXT: usize = 80;
+
+#[derive(Debug)]
+struct Entry {
+    path: String,
+    line: u32,
+    message: String,
+}
+
+fn summarize(text: &str) -> String {
+    let text = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+    match text.char_indices().nth(MAX_TEXT) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// The entry of a hunk, at its first important change. Returns None if the
+/// hunk has no important changes.
+fn hunk_entry(path: &str, hunk: &Hunk, use_old: bool) -> Option<Entry> {
+    let mut next_line = if use_old { hunk.old_begin } else { hunk.new_begin };
+    let mut first = None;
+    let (mut added, mut removed) = (0, 0);
+    for line in &hunk.lines {
+        let line_number = if use_old { line.old_line } else { line.new_line };
+        if line.important {
+            match line.kind {
+                LineKind::Added => added += 1,
+                LineKind::Removed => removed += 1,
+                LineKind::Unchanged => {}
+            }
+            // Removed lines are located at the line that follows them.
+            first.get_or_insert((line_number.unwrap_or(next_line), &line.text));
+        }
+        if let Some(line_number) = line_number {
+            next_line = line_number + 1;
+        }
+    }
+
+    let (line, text) = first?;
+    Some(Entry {
+        path: path.to_string(),
+        line: line.max(1),
+        message: format!("+{added} -{removed}: {}", summarize(text)),
+    })
+}
+
+/// Write the entries for the important hunks of `files` and the unresolved
+/// review threads, sorted by location.
+pub fn write(
+    out: &mut dyn Write,
+    files: &[FileHunks],
+    threads: &[api::ReviewThread],
+) -> Result<()> {
+    let mut entries = Vec::new();
+    for file in files {
+        // Removed files only have an old name and line numbers.
+        let (path, use_old) = match (&file.new_name, &file.old_name) {
+            (Some(path), _) => (path, false),
+            (None, Some(path)) => (path, true),
+            (None, None) => continue,
+        };
+        entries.extend(
+            file.hunks
+                .iter()
+                .filter(|hunk| !hunk.baseline)
+                .filter_map(|hunk| hunk_entry(path, hunk, use_old)),
+        );
+    }
+
+    for thread in threads.iter().filter(|thread| !thread.is_resolved) {
+        let Some(first) = thread.comments.nodes.first() else {
+            continue;
+        };
+        let author = first.author.as_ref().map_or("ghost", |author| &author.login);
+        let mut message = format!("[@{author}] {}", summarize(&first.body));
+        let replies = thread.comments.nodes.len() - 1;
+        if replies > 0 {
+            message += &format!(" ({replies} repl{})", if replies == 1 { "y" } else { "ies" });
+        }
+        if thread.is_outdated {
+            message += " (outdated)";
+        }
+        entries.push(Entry {
+            path: thread.path.clone(),
+            line: thread.line.unwrap_or(1) as u32,
+            message,
+        });
+    }
+
+    entries.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    for entry in entries {
+        writeln!(out, "{}:{}:1: {}", entry.path, entry.line, entry.message)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Record of submitted reviews in the local clone.
+//!
+//! Each review is appended to the note of the reviewed commit in
+//! `refs/notes/review`, so that the review history can be searched with plain
+//! Git (e.g. `git log --notes=review`) and survives independently of the
+//! forge. Recording is enabled with `record_notes` in the `[review]` settings.
+
+use diff_modulo_base::git_core::{self, Ref};
+use vctools_utils::{prelude::*, time};
+
+use crate::{github::edit::ReviewEvent, settings::Settings, CompletePullRequest};
+
+pub const NOTES_REF: &str = "refs/notes/review";
+
+fn verdict(event: ReviewEvent) -> &'static str {
+    match event {
+        ReviewEvent::Approve => "approved",
+        ReviewEvent::RequestChanges => "changes requested",
+        ReviewEvent::Comment => "commented",
+    }
+}
+
+/// Text of the note for a review, in the style of commit trailers.
+pub fn format_note(pr: &CompletePullRequest, head: &str, event: ReviewEvent, at: i64) -> String {
+    let api = &pr.api;
+    format!(
+        "Review: {}\nPull-Request: {}/{}/{}#{}\nDate: {}\nHead: {head}",
+        verdict(event),
+        api.host,
+        api.owner,
+        api.name,
+        pr.id,
+        time::format_iso(at),
+    )
+}
+
+/// Record a review of the given head commit, if enabled in the settings.
+pub fn record(
+    pr: &CompletePullRequest,
+    ep: &dyn git_core::ExecutionProvider,
+    head: &str,
+    event: ReviewEvent,
+) -> Result<()> {
+    if !Settings::get().review.record_notes {
+        return Ok(());
+    }
+    let note = format_note(pr, head, event, time::unix_now());
+    pr.git.repository.append_note(ep, NOTES_REF, &Ref::new(head), &note)
+}
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tracking of the time spent reviewing pull requests.
+//!
+//! Review sessions are appended to a log file in the data directory, one JSON
+//! object per line, so that concurrent instances of git-review don't
+//! overwrite each other's records.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Datelike, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use vctools_utils::prelude::*;
+
+use crate::{get_project_dirs, ApiRepository};
+
+/// Time without any input after which we assume that the user has walked away.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(180);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewSession {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    pub pull: u64,
+
+    /// Start of the session, as a Unix timestamp.
+    pub started: i64,
+
+    /// Active time spent in the session.
+    pub seconds: u64,
+}
+impl ReviewSession {
+    pub fn started(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.started, 0).single().unwrap_or_default()
+    }
+}
+
+pub fn default_log_file() -> PathBuf {
+    get_project_dirs().data_dir().join("review-time.jsonl")
+}
+
+pub fn append_session(path: &Path, session: &ReviewSession) -> Result<()> {
+    try_forward(
+        || -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{}", serde_json::to_string(session)?)?;
+            Ok(())
+        },
+        || format!("Error writing {}", path.display()),
+    )
+}
+
+/// Read all sessions from the log. Lines that can't be parsed are skipped.
+pub fn read_sessions(path: &Path) -> Result<Vec<ReviewSession>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => Err(format!("Error reading {}: {}", path.display(), err))?,
+    };
+    let mut sessions = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        if let Ok(session) = serde_json::from_str(&line?) {
+            sessions.push(session);
+        }
+    }
+    Ok(sessions)
+}
+
+/// Measures the time during which the user is actively looking at a review.
+///
+/// Time is only counted while input keeps arriving: gaps longer than
+/// [`IDLE_TIMEOUT`] are not counted beyond the timeout.
+#[derive(Debug)]
+pub struct ReviewTimer {
+    repository: ApiRepository,
+    pull: u64,
+    started: DateTime<Utc>,
+    last_activity: Instant,
+    active: Duration,
+}
+impl ReviewTimer {
+    pub fn new(repository: ApiRepository, pull: u64) -> Self {
+        Self {
+            repository,
+            pull,
+            started: Utc::now(),
+            last_activity: Instant::now(),
+            active: Duration::ZERO,
+        }
+    }
+
+    /// Record user input.
+    pub fn activity(&mut self) {
+        let now = Instant::now();
+        self.active += now.duration_since(self.last_activity).min(IDLE_TIMEOUT);
+        self.last_activity = now;
+    }
+
+    pub fn active(&self) -> Duration {
+        self.active + self.last_activity.elapsed().min(IDLE_TIMEOUT)
+    }
+
+    pub fn finish(self) -> ReviewSession {
+        ReviewSession {
+            seconds: self.active().as_secs(),
+            host: self.repository.host,
+            owner: self.repository.owner,
+            name: self.repository.name,
+            pull: self.pull,
+            started: self.started.timestamp(),
+        }
+    }
+}
+
+/// Format a duration as e.g. "1h 05m".
+pub fn format_duration(seconds: u64) -> String {
+    let minutes = (seconds + 30) / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Summarize review time per week and repository.
+///
+/// Only sessions that started within the last `weeks` weeks are included.
+pub fn report(sessions: &[ReviewSession], weeks: u32, out: &mut dyn Write) -> Result<()> {
+    let since = Utc::now() - chrono::Duration::weeks(weeks.into());
+
+    // (ISO year, ISO week) -> "owner/name" -> (seconds, pull requests)
+    type Summary = BTreeMap<(i32, u32), BTreeMap<String, (u64, Vec<u64>)>>;
+    let mut summary = Summary::new();
+    for session in sessions {
+        let started = session.started();
+        if started < since {
+            continue;
+        }
+        let week = started.with_timezone(&Local).iso_week();
+        let entry = summary
+            .entry((week.year(), week.week()))
+            .or_default()
+            .entry(format!("{}/{}", session.owner, session.name))
+            .or_default();
+        entry.0 += session.seconds;
+        if !entry.1.contains(&session.pull) {
+            entry.1.push(session.pull);
+        }
+    }
+
+    if summary.is_empty() {
+        writeln!(out, "No review time recorded in the last {weeks} weeks.")?;
+        return Ok(());
+    }
+
+    for ((year, week), repos) in summary {
+        let total: u64 = repos.values().map(|(seconds, _)| seconds).sum();
+        writeln!(out, "{year}-W{week:02}: {}", format_duration(total))?;
+        for (repo, (seconds, pulls)) in repos {
+            writeln!(
+                out,
+                "    {:>8}  {} ({} pull request{})",
+                format_duration(seconds),
+                repo,
+                pulls.len(),
+                if pulls.len() == 1 { "" } else { "s" },
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Heuristics for the size and risk of a pull request.
+//!
+//! These are meant as a quick hint for prioritizing reviews, not as a verdict.
+
+use crate::{generated, github::api, settings::RiskSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Size {
+    Small,
+    Medium,
+    Large,
+    Huge,
+}
+impl Size {
+    pub fn badge(self) -> &'static str {
+        match self {
+            Size::Small => "S",
+            Size::Medium => "M",
+            Size::Large => "L",
+            Size::Huge => "XL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RiskAssessment {
+    /// Changed lines, excluding generated files.
+    pub lines: u64,
+
+    /// Changed lines in generated files.
+    pub generated_lines: u64,
+
+    /// Changed lines in test files.
+    pub test_lines: u64,
+
+    /// Files matching one of the risky paths.
+    pub risky_files: Vec<String>,
+
+    /// Whether the list of files was truncated, so that the numbers are only
+    /// a lower bound.
+    pub incomplete: bool,
+}
+impl RiskAssessment {
+    /// Assess a pull request based on its list of changed files.
+    ///
+    /// `pull` is used to detect whether the file list is incomplete.
+    pub fn new(settings: &RiskSettings, pull: Option<&api::Pull>, files: &[api::PullFile]) -> Self {
+        let is_generated = |path: &str| generated::matches_patterns(settings, path);
+        Self::with_generated(settings, pull, files, is_generated)
+    }
+
+    /// Like `new`, but with a different test for generated files, e.g. one
+    /// that also takes `.gitattributes` into account.
+    pub fn with_generated(
+        settings: &RiskSettings,
+        pull: Option<&api::Pull>,
+        files: &[api::PullFile],
+        is_generated: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut result = RiskAssessment::default();
+
+        for file in files {
+            let lines = file.additions + file.deletions;
+            if is_generated(&file.filename) {
+                result.generated_lines += lines;
+                continue;
+            }
+
+            result.lines += lines;
+            if settings.test_paths.iter().any(|p| p.matches(&file.filename)) {
+                result.test_lines += lines;
+            }
+
+            let is_risky = |name: &str| settings.risky_paths.iter().any(|p| p.matches(name));
+            if is_risky(&file.filename) || file.previous_filename.as_deref().is_some_and(is_risky)
+            {
+                result.risky_files.push(file.filename.clone());
+            }
+        }
+
+        if let Some(changed_files) = pull.and_then(|pull| pull.changed_files) {
+            result.incomplete = changed_files > files.len() as u64;
+        }
+        if result.incomplete {
+            // Fall back to the totals for the size, which may however
+            // include generated files.
+            if let (Some(additions), Some(deletions)) =
+                pull.map(|pull| (pull.additions, pull.deletions)).unwrap_or_default()
+            {
+                result.lines = result
+                    .lines
+                    .max((additions + deletions).saturating_sub(result.generated_lines));
+            }
+        }
+
+        result
+    }
+
+    pub fn code_lines(&self) -> u64 {
+        self.lines - self.test_lines
+    }
+
+    pub fn size(&self, settings: &RiskSettings) -> Size {
+        if self.lines >= settings.huge_lines {
+            Size::Huge
+        } else if self.lines >= settings.large_lines {
+            Size::Large
+        } else if self.lines >= settings.medium_lines {
+            Size::Medium
+        } else {
+            Size::Small
+        }
+    }
+
+    /// Changed test lines per changed code line.
+    pub fn test_ratio(&self) -> Option<f64> {
+        match self.code_lines() {
+            0 => None,
+            code => Some(self.test_lines as f64 / code as f64),
+        }
+    }
+
+    pub fn untested(&self, settings: &RiskSettings) -> bool {
+        self.test_lines == 0 && self.code_lines() >= settings.untested_lines
+    }
+
+    /// Short badges summarizing the assessment, e.g. "L" or "XL risky untested".
+    pub fn badges(&self, settings: &RiskSettings) -> Vec<&'static str> {
+        let mut badges = vec![self.size(settings).badge()];
+        if !self.risky_files.is_empty() {
+            badges.push("risky");
+        }
+        if self.untested(settings) {
+            badges.push("untested");
+        }
+        badges
+    }
+
+    /// Rough assessment from the totals of a pull request when the list of
+    /// files isn't available (yet). Generated files can't be excluded.
+    pub fn from_totals(pull: &api::Pull) -> Option<Self> {
+        Some(RiskAssessment {
+            lines: pull.additions? + pull.deletions?,
+            incomplete: true,
+            ..Default::default()
+        })
+    }
+}
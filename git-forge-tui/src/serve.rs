@@ -0,0 +1,291 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Review data for editor plugins, served over stdin and stdout.
+//!
+//! The protocol is the same JSON-RPC 2.0 with one request or response per line
+//! as for remote control (see [`crate::ipc`]), so that plugins can reuse the
+//! forge client and diff-modulo-base instead of reimplementing them. Methods:
+//!
+//! - `open` with `remote`, `pull`, and optionally `path` (of the local clone):
+//!   selects the pull request that the other methods refer to.
+//! - `hunks`, optionally with `since` (a commit, e.g. of the last review) and
+//!   `anchored` (see `git diff-modulo-base --anchored`): the diff of the pull
+//!   request modulo its target branch.
+//! - `threads`: the review threads with their comments.
+//! - `comment` with `body`, and optionally `thread` to reply to a review
+//!   thread: queues a comment. It is posted in the background, and queued
+//!   comments are retried by the next run of any tool if posting fails.
+
+use std::io::{BufRead, Write};
+
+use diff_modulo_base::{
+    api::{FileHunks, HunkCollector, LineKind},
+    diff::render::{Chunk, ChunkWriter},
+    git_core::{self, RangeDiffMatch, RangeDiffWriter, Ref},
+    tool::{self, GitDiffModuloBaseArgs, GitDiffModuloBaseOptions},
+};
+use serde_json::{json, Value};
+use vctools_utils::prelude::*;
+
+use crate::{
+    batch,
+    github::{
+        api,
+        connections::Connections,
+        edit::{CommentCreate, Edit, ThreadReply},
+        ClientRef, Response, Shared,
+    },
+    ipc::{self, param_str, RpcError},
+    CompletePullRequest, GitRepository,
+};
+
+/// Collects the hunks of a combined diff, which has no range-diff matches.
+struct Collector(HunkCollector);
+impl ChunkWriter for Collector {
+    fn push_chunk(&mut self, chunk: Chunk) {
+        self.0.push_chunk(chunk);
+    }
+}
+impl RangeDiffWriter for Collector {
+    fn push_range_diff_match(&mut self, _rdm: RangeDiffMatch) {}
+}
+
+fn file_json(file: &FileHunks) -> Value {
+    let hunks: Vec<Value> = file
+        .hunks
+        .iter()
+        .map(|hunk| {
+            let lines: Vec<Value> = hunk
+                .lines
+                .iter()
+                .map(|line| {
+                    let kind = match line.kind {
+                        LineKind::Unchanged => "unchanged",
+                        LineKind::Removed => "removed",
+                        LineKind::Added => "added",
+                    };
+                    json!({
+                        "kind": kind,
+                        "important": line.important,
+                        "oldLine": line.old_line,
+                        "newLine": line.new_line,
+                        "text": line.text,
+                    })
+                })
+                .collect();
+            json!({
+                "baseline": hunk.baseline,
+                "oldBegin": hunk.old_begin,
+                "oldCount": hunk.old_count,
+                "newBegin": hunk.new_begin,
+                "newCount": hunk.new_count,
+                "lines": lines,
+            })
+        })
+        .collect();
+    json!({"oldName": file.old_name, "newName": file.new_name, "hunks": hunks})
+}
+
+fn thread_json(thread: &api::ReviewThread) -> Value {
+    let comments: Vec<Value> = thread
+        .comments
+        .nodes
+        .iter()
+        .map(|comment| {
+            json!({
+                "author": comment.author.as_ref().map(|author| &author.login),
+                "body": comment.body,
+                "createdAt": comment.created_at,
+                "url": comment.url,
+            })
+        })
+        .collect();
+    json!({
+        "id": thread.id,
+        "resolved": thread.is_resolved,
+        "outdated": thread.is_outdated,
+        "path": thread.path,
+        "line": thread.line,
+        "comments": comments,
+    })
+}
+
+pub struct Server {
+    connections: Connections,
+    pr: Option<CompletePullRequest>,
+}
+impl Server {
+    pub fn new(connections: Connections) -> Self {
+        Self { connections, pr: None }
+    }
+
+    /// Handle requests until the input is closed.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match ipc::parse_request(&line) {
+                Ok(request) => {
+                    let result = self.handle(&request.method, &request.params);
+                    ipc::response(request.id, result)
+                }
+                Err(response) => response,
+            };
+            writeln!(output, "{response}")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Wait for queued comments to be posted, for at most the given time.
+    ///
+    /// Returns the number of comments that could not be posted.
+    pub fn shutdown(mut self, timeout: std::time::Duration) -> usize {
+        self.connections.shutdown(timeout)
+    }
+
+    fn handle(&mut self, method: &str, params: &Value) -> std::result::Result<Value, RpcError> {
+        match method {
+            "open" => self.open(params),
+            "hunks" => self.hunks(params),
+            "threads" => self.threads(),
+            "comment" => self.comment(params),
+            _ => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    fn pr(&self) -> std::result::Result<CompletePullRequest, RpcError> {
+        self.pr.clone().ok_or_else(|| RpcError::invalid_params("No pull request is open"))
+    }
+
+    /// Run a request to the forge of the open pull request, waiting for the
+    /// response.
+    fn fetch<T>(
+        &mut self,
+        pr: &CompletePullRequest,
+        request: impl Fn(&mut ClientRef) -> Response<T>,
+    ) -> Result<T> {
+        batch::fetch(&mut self.connections, &pr.api.host, request)
+    }
+
+    /// Select the pull request that the other methods refer to.
+    pub fn open_pull(&mut self, path: &str, remote: &str, pull: u64) -> Result<Shared<api::Pull>> {
+        let pr = CompletePullRequest::from_git(
+            GitRepository::new(path.into(), remote.into()),
+            pull,
+            self.connections.hosts(),
+            &git_core::SimpleExecutionProvider,
+        )?;
+        let info = self.fetch(&pr, |client| client.pull(&pr.api.owner, &pr.api.name, pr.id))?;
+        self.pr = Some(pr);
+        Ok(info)
+    }
+
+    fn open(&mut self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let remote = param_str(params, "remote")?;
+        let pull = params
+            .get("pull")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| RpcError::invalid_params("Missing parameter: pull"))?;
+        let path = params.get("path").and_then(Value::as_str).unwrap_or(".");
+        let info = self.open_pull(path, remote, pull)?;
+
+        Ok(json!({
+            "title": info.title,
+            "author": info.user.login,
+            "url": info.html_url,
+            "draft": info.draft,
+            "merged": info.merged,
+            "base": {"ref": info.base.ref_, "sha": info.base.sha},
+            "head": {"ref": info.head.ref_, "sha": info.head.sha},
+        }))
+    }
+
+    /// The diff of the open pull request modulo its target branch, relative
+    /// to `since` or to the merge base.
+    pub fn diff_hunks(
+        &mut self,
+        since: Option<&str>,
+        anchored: Vec<String>,
+    ) -> Result<Vec<FileHunks>> {
+        let Some(pr) = self.pr.clone() else {
+            Err("No pull request is open")?
+        };
+        let pull = self.fetch(&pr, |client| client.pull(&pr.api.owner, &pr.api.name, pr.id))?;
+
+        let ep = &git_core::SimpleExecutionProvider;
+        let repository = &pr.git.repository;
+        let mut refs = vec![Ref::new(&pull.head.sha), Ref::new(&pull.base.sha)];
+        refs.extend(since.map(Ref::new));
+        repository.fetch_missing(ep, &pr.git.remote, &refs)?;
+
+        let old = match since {
+            Some(since) => since.to_string(),
+            None => {
+                let (base, head) = (Ref::new(&pull.base.sha), Ref::new(&pull.head.sha));
+                repository.merge_base(ep, &base, &head)?.name
+            }
+        };
+        let args = GitDiffModuloBaseArgs {
+            base: Some(pull.base.sha.clone()),
+            old: Some(old),
+            new: Some(pull.head.sha.clone()),
+            options: GitDiffModuloBaseOptions {
+                combined: true,
+                anchored,
+                ..Default::default()
+            },
+        };
+        let mut collector = Collector(HunkCollector::new());
+        tool::git_diff_modulo_base(&args, repository, ep, &mut collector)?;
+        Ok(collector.0.files)
+    }
+
+    fn hunks(&mut self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let anchored = match params.get("anchored") {
+            None => Vec::new(),
+            Some(anchored) => serde_json::from_value(anchored.clone())
+                .map_err(|_| RpcError::invalid_params("anchored must be a list of strings"))?,
+        };
+        let since = params.get("since").and_then(Value::as_str);
+        let files = self.diff_hunks(since, anchored)?;
+        Ok(files.iter().map(file_json).collect())
+    }
+
+    /// The review threads of the open pull request.
+    pub fn review_threads(&mut self) -> Result<Shared<Vec<api::ReviewThread>>> {
+        let Some(pr) = self.pr.clone() else {
+            Err("No pull request is open")?
+        };
+        self.fetch(&pr, |client| client.review_threads(&pr.api.owner, &pr.api.name, pr.id))
+    }
+
+    fn threads(&mut self) -> std::result::Result<Value, RpcError> {
+        Ok(self.review_threads()?.iter().map(thread_json).collect())
+    }
+
+    fn comment(&mut self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let pr = self.pr()?;
+        let body = param_str(params, "body")?.to_string();
+        let (owner, name, number) = (pr.api.owner.clone(), pr.api.name.clone(), pr.id);
+        let edit: Edit = match params.get("thread").and_then(Value::as_str) {
+            Some(thread_id) => ThreadReply {
+                owner,
+                name,
+                number,
+                thread_id: thread_id.to_string(),
+                body,
+            }
+            .into(),
+            None => CommentCreate { owner, name, number, body }.into(),
+        };
+
+        self.connections.start_frame(None);
+        let result = self.connections.edit(&pr.api.host, edit, None);
+        self.connections.end_frame(None);
+        result?;
+        Ok(json!({"queued": true}))
+    }
+}
@@ -0,0 +1,577 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! General settings from settings.toml.
+//!
+//! Settings can be overridden per repository:
+//!
+//! ```toml
+//! [risk]
+//! large_lines = 500
+//!
+//! [repository."owner/name".risk]
+//! large_lines = 2000
+//! ```
+//!
+//! Repository sections replace whole groups of settings (such as `risk`); they
+//! are not merged field by field. Snippets are the exception: repository
+//! snippets are added to the global ones.
+//!
+//! Some settings (such as repository priorities) can also be changed from the
+//! TUI. Those changes are written back to settings.toml, preserving the rest of
+//! the file.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use diff_modulo_base::diff_color::Palette;
+use lazy_static::lazy_static;
+use log::warn;
+use ratatui::style::{Color, Modifier};
+use regex::Regex;
+use serde::Deserialize;
+
+use vctools_utils::{files, prelude::*};
+use vctuik::theme::{TextStyle, Theme};
+
+use crate::{get_project_dirs, github::prefetch::Budget, hooks::HookSettings, load_config};
+
+/// Glob-like pattern for file paths.
+///
+/// `*` matches within a path component, `**` matches across components, and a
+/// pattern ending in `/` matches everything below a directory. Patterns
+/// without a `/` match the file name in any directory.
+#[derive(Debug, Clone)]
+pub struct PathPattern {
+    pattern: String,
+    regex: Regex,
+}
+impl PathPattern {
+    pub fn new(pattern: &str) -> Self {
+        let mut re = String::from("^");
+        if !pattern.contains('/') {
+            re.push_str("(?:.*/)?");
+        }
+        let mut rest = pattern;
+        while !rest.is_empty() {
+            if let Some(tail) = rest.strip_prefix("**") {
+                re.push_str(".*");
+                rest = tail;
+            } else if let Some(tail) = rest.strip_prefix('*') {
+                re.push_str("[^/]*");
+                rest = tail;
+            } else {
+                let ch = rest.chars().next().unwrap();
+                re.push_str(&regex::escape(&ch.to_string()));
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+        if !pattern.ends_with('/') {
+            re.push('$');
+        }
+
+        Self {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&re).unwrap(),
+        }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+impl<'de> Deserialize<'de> for PathPattern {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        Ok(PathPattern::new(&String::deserialize(deserializer)?))
+    }
+}
+
+fn patterns(patterns: &[&str]) -> Vec<PathPattern> {
+    patterns.iter().map(|p| PathPattern::new(p)).collect()
+}
+
+/// Thresholds and path lists for the pull request risk heuristics.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RiskSettings {
+    /// Number of changed lines (excluding generated files) from which on a
+    /// pull request counts as medium / large / extra large.
+    pub medium_lines: u64,
+    pub large_lines: u64,
+    pub huge_lines: u64,
+
+    /// Files that deserve extra scrutiny.
+    pub risky_paths: Vec<PathPattern>,
+
+    /// Generated files, which don't count towards the size.
+    pub generated_paths: Vec<PathPattern>,
+
+    /// Test files, for the test-to-code ratio.
+    pub test_paths: Vec<PathPattern>,
+
+    /// Minimum number of changed code lines for a missing test change to be
+    /// flagged.
+    pub untested_lines: u64,
+}
+impl Default for RiskSettings {
+    fn default() -> Self {
+        Self {
+            medium_lines: 100,
+            large_lines: 500,
+            huge_lines: 2000,
+            risky_paths: Vec::new(),
+            generated_paths: patterns(&[
+                "Cargo.lock",
+                "package-lock.json",
+                "yarn.lock",
+                "pnpm-lock.yaml",
+                "go.sum",
+                "poetry.lock",
+                "*.min.js",
+                "*.pb.go",
+                "*_pb2.py",
+                "*.snap",
+            ]),
+            test_paths: patterns(&[
+                "test/",
+                "tests/",
+                "**/test/",
+                "**/tests/",
+                "*_test.*",
+                "test_*",
+                "*.test.*",
+                "*.spec.*",
+            ]),
+            untested_lines: 50,
+        }
+    }
+}
+
+/// Frequently used review comment text.
+///
+/// The text can contain placeholders, see [`Snippet::expand`].
+#[derive(Deserialize, Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+
+    /// Typing the abbreviation in the comment composer and pressing Tab
+    /// replaces it by the snippet.
+    #[serde(default)]
+    pub abbreviation: Option<String>,
+
+    pub text: String,
+}
+impl Snippet {
+    /// Replace `{placeholder}`s in the snippet text. Unknown placeholders are
+    /// kept as they are.
+    pub fn expand(&self, values: &[(&str, &str)]) -> String {
+        let mut result = String::new();
+        let mut rest = self.text.as_str();
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = rest.find('}').and_then(|end| {
+                let key = &rest[1..end];
+                values
+                    .iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| (*value, end))
+            });
+            if let Some((value, end)) = value {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            } else {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+/// How notifications from a repository are treated in the inbox.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Notifications are hidden and not refreshed in the background.
+    Muted,
+    /// Notifications are sorted last, and pull requests are only loaded on
+    /// demand.
+    Low,
+    #[default]
+    Normal,
+    /// Notifications are sorted first.
+    High,
+}
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Muted => "muted",
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+        }
+    }
+}
+impl std::str::FromStr for Priority {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "muted" => Priority::Muted,
+            "low" => Priority::Low,
+            "normal" => Priority::Normal,
+            "high" => Priority::High,
+            _ => Err(format!("Unknown priority: {s} (expected muted, low, normal, or high)"))?,
+        })
+    }
+}
+
+/// Optional column of the pull request and notification tables.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnKind {
+    /// Size and risk badges.
+    Risk,
+    /// Time since the pull request was opened.
+    Age,
+    /// Time since the last update.
+    Updated,
+    Author,
+    /// Lines added and removed.
+    Size,
+    Ci,
+    Labels,
+    Milestone,
+}
+impl ColumnKind {
+    pub const ALL: [ColumnKind; 8] = [
+        ColumnKind::Risk,
+        ColumnKind::Age,
+        ColumnKind::Updated,
+        ColumnKind::Author,
+        ColumnKind::Size,
+        ColumnKind::Ci,
+        ColumnKind::Labels,
+        ColumnKind::Milestone,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColumnKind::Risk => "risk",
+            ColumnKind::Age => "age",
+            ColumnKind::Updated => "updated",
+            ColumnKind::Author => "author",
+            ColumnKind::Size => "size",
+            ColumnKind::Ci => "ci",
+            ColumnKind::Labels => "labels",
+            ColumnKind::Milestone => "milestone",
+        }
+    }
+}
+
+/// A kind of table whose columns can be configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableView {
+    /// The notifications of the inbox.
+    Inbox,
+    /// Lists of pull requests, e.g. review requests and the user's own pull
+    /// requests.
+    Pulls,
+}
+impl TableView {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TableView::Inbox => "inbox",
+            TableView::Pulls => "pulls",
+        }
+    }
+}
+impl std::str::FromStr for TableView {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "inbox" => TableView::Inbox,
+            "pulls" => TableView::Pulls,
+            _ => Err(format!("Unknown table: {s} (expected inbox or pulls)"))?,
+        })
+    }
+}
+
+/// Optional columns of the tables, in the order in which they are shown.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ColumnSettings {
+    pub inbox: Vec<ColumnKind>,
+    pub pulls: Vec<ColumnKind>,
+}
+impl Default for ColumnSettings {
+    fn default() -> Self {
+        Self {
+            inbox: vec![ColumnKind::Risk, ColumnKind::Updated],
+            pulls: vec![ColumnKind::Age],
+        }
+    }
+}
+impl ColumnSettings {
+    pub fn get(&self, view: TableView) -> &[ColumnKind] {
+        match view {
+            TableView::Inbox => &self.inbox,
+            TableView::Pulls => &self.pulls,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RepositorySettings {
+    pub priority: Priority,
+
+    pub risk: Option<RiskSettings>,
+
+    #[serde(rename = "snippet")]
+    pub snippets: Vec<Snippet>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct UiSettings {
+    /// Use only ASCII characters for markers and lines.
+    pub reduced_motion: bool,
+
+    /// Redraw at most about 30 times per second, e.g. over slow SSH links.
+    pub coalesce_redraws: bool,
+
+    /// Whether to show clickable hyperlinks. Detected from the terminal by
+    /// default.
+    pub hyperlinks: Option<bool>,
+
+    /// Whether to show identicons next to authors. Shown by default, unless
+    /// `reduced_motion` is set.
+    pub identicons: Option<bool>,
+
+    /// Whether to fetch avatars and show them in place of identicons. On by
+    /// default.
+    pub avatars: Option<bool>,
+}
+
+fn deserialize_palette<'de, D>(deserializer: D) -> std::result::Result<Palette, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    clap::ValueEnum::from_str(&name, true).map_err(serde::de::Error::custom)
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// Color scheme of added and removed lines in diffs, as for the
+    /// `--palette` option of `git diff-modulo-base`.
+    #[serde(deserialize_with = "deserialize_palette")]
+    pub diff: Palette,
+}
+impl ThemeSettings {
+    pub fn apply(&self, theme: &mut Theme) {
+        let palette = self.diff;
+        theme.map_diff_styles(|kind, mut style| {
+            // Without colors, e.g. with NO_COLOR, only patterns are added.
+            if let Some((added, removed)) = palette.ansi256().filter(|_| style.fg.is_some()) {
+                let color = if kind == TextStyle::Added { added } else { removed };
+                style = style.fg(Color::Indexed(color));
+            }
+            if palette.patterns() {
+                style = style.add_modifier(if kind == TextStyle::Added {
+                    Modifier::BOLD
+                } else {
+                    Modifier::UNDERLINED
+                });
+            }
+            style
+        });
+    }
+}
+
+/// Settings for responding to reviews of one's own pull requests.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AuthorSettings {
+    /// Commands that rebase the branch of a pull request and push it. They
+    /// are run one after the other in the local clone.
+    pub rebase_and_push: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ReviewSettings {
+    /// Record submitted reviews as Git notes in the local clone.
+    pub record_notes: bool,
+
+    /// Local clone of the RustSec advisory database, for looking up
+    /// advisories of crate versions that a pull request adds to Cargo.lock.
+    pub advisory_db: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub risk: RiskSettings,
+
+    #[serde(rename = "snippet")]
+    pub snippets: Vec<Snippet>,
+
+    pub hooks: HookSettings,
+
+    /// Custom commands by name, see `hooks::run_custom_command`.
+    pub commands: BTreeMap<String, String>,
+
+    pub ui: UiSettings,
+
+    pub theme: ThemeSettings,
+
+    pub prefetch: Budget,
+
+    pub author: AuthorSettings,
+
+    pub review: ReviewSettings,
+
+    pub columns: ColumnSettings,
+
+    /// Overrides by "owner/name".
+    #[serde(rename = "repository")]
+    pub repositories: HashMap<String, RepositorySettings>,
+}
+impl Settings {
+    /// Settings loaded from settings.toml, or the defaults if the file
+    /// doesn't exist or can't be loaded.
+    pub fn get() -> Arc<Settings> {
+        SETTINGS.read().unwrap().clone()
+    }
+
+    fn path() -> PathBuf {
+        get_project_dirs().config_dir().join("settings.toml")
+    }
+
+    fn load() -> Settings {
+        if !Self::path().exists() {
+            return Settings::default();
+        }
+        load_config("settings.toml").unwrap_or_else(|err| {
+            warn!("{}", err);
+            Settings::default()
+        })
+    }
+
+    /// Edit settings.toml, keeping the user's formatting and comments, and
+    /// reload the settings.
+    fn edit(f: impl FnOnce(&mut toml_edit::DocumentMut) -> Result<()>) -> Result<()> {
+        let path = Self::path();
+        try_forward(
+            || -> Result<()> {
+                let text = if path.exists() {
+                    String::from_utf8(files::read_bytes(&path)?)?
+                } else {
+                    String::new()
+                };
+                let mut doc: toml_edit::DocumentMut = text.parse()?;
+                f(&mut doc)?;
+
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, doc.to_string())?;
+                Ok(())
+            },
+            || format!("Error saving {}", path.display()),
+        )?;
+
+        *SETTINGS.write().unwrap() = Arc::new(Self::load());
+        Ok(())
+    }
+
+    /// Change a repository's priority and save it in settings.toml.
+    pub fn set_priority(owner: &str, name: &str, priority: Priority) -> Result<()> {
+        Self::edit(|doc| {
+            let repositories = doc
+                .entry("repository")
+                .or_insert_with(|| {
+                    let mut table = toml_edit::Table::new();
+                    table.set_implicit(true);
+                    toml_edit::Item::Table(table)
+                })
+                .as_table_mut()
+                .ok_or("`repository` is not a table")?;
+            let repository = repositories
+                .entry(&format!("{owner}/{name}"))
+                .or_insert_with(|| toml_edit::Item::Table(toml_edit::Table::new()))
+                .as_table_like_mut()
+                .ok_or("repository entry is not a table")?;
+            if priority == Priority::Normal {
+                repository.remove("priority");
+            } else {
+                repository.insert("priority", toml_edit::value(priority.as_str()));
+            }
+            Ok(())
+        })
+    }
+
+    /// Change the columns of a table and save them in settings.toml.
+    pub fn set_columns(view: TableView, columns: &[ColumnKind]) -> Result<()> {
+        Self::edit(|doc| {
+            let table = doc
+                .entry("columns")
+                .or_insert_with(|| toml_edit::Item::Table(toml_edit::Table::new()))
+                .as_table_like_mut()
+                .ok_or("`columns` is not a table")?;
+            let columns: toml_edit::Array = columns.iter().map(|column| column.as_str()).collect();
+            table.insert(view.as_str(), toml_edit::value(columns));
+            Ok(())
+        })
+    }
+
+    fn repository(&self, owner: &str, name: &str) -> Option<&RepositorySettings> {
+        self.repositories.get(&format!("{owner}/{name}"))
+    }
+
+    pub fn priority(&self, owner: &str, name: &str) -> Priority {
+        self.repository(owner, name)
+            .map(|settings| settings.priority)
+            .unwrap_or_default()
+    }
+
+    pub fn risk(&self, owner: &str, name: &str) -> &RiskSettings {
+        self.repository(owner, name)
+            .and_then(|settings| settings.risk.as_ref())
+            .unwrap_or(&self.risk)
+    }
+
+    /// Snippets available in a repository. Repository snippets take
+    /// precedence over global snippets of the same name.
+    pub fn snippets(&self, owner: &str, name: &str) -> Vec<&Snippet> {
+        let mut snippets: Vec<&Snippet> = self
+            .repository(owner, name)
+            .map(|settings| settings.snippets.iter().collect())
+            .unwrap_or_default();
+        for snippet in &self.snippets {
+            if !snippets.iter().any(|s| s.name == snippet.name) {
+                snippets.push(snippet);
+            }
+        }
+        snippets
+    }
+}
+
+lazy_static! {
+    static ref SETTINGS: RwLock<Arc<Settings>> = RwLock::new(Arc::new(Settings::load()));
+}
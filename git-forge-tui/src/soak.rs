@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Soak testing: long runs with synthetic input that check that the memory
+//! and the resources used by the UI stay bounded.
+//!
+//! `SoakInput` generates random key presses that navigate through the UI
+//! without leaving it, and `SoakMonitor` periodically samples the sizes of
+//! the per-frame state, of the caches, and the numbers of threads and file
+//! descriptors. The largest samples of a warm-up period are the baseline;
+//! the run fails if a sample grows well beyond it later.
+//!
+//! Soak tests of the UI are meant to run against recorded API responses
+//! (`--replay`), so that edits are not committed and the forge is not
+//! hammered. `tests/soak_test.rs` soaks the API client against a mock forge.
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use vctools_utils::prelude::*;
+use vctuik::{
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    state::Builder,
+};
+
+use crate::{github::connections::Connections, gitservice::GitService};
+
+/// Time between synthetic key presses.
+const KEY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Time between samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Longest warm-up period. Shorter runs use the first quarter.
+const WARM_UP: Duration = Duration::from_secs(5 * 60);
+
+/// Growth beyond the baseline that is always tolerated, for small values.
+const SLACK: usize = 64;
+
+/// Keys that are pressed. They navigate and toggle views, but don't quit,
+/// open a browser or an editor, or write files.
+const KEYS: &[KeyCode] = &[
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Tab,
+    KeyCode::BackTab,
+    KeyCode::Enter,
+    KeyCode::Enter,
+    KeyCode::Esc,
+    KeyCode::Backspace,
+    KeyCode::Char('?'),
+    KeyCode::Char('['),
+    KeyCode::Char(']'),
+    KeyCode::Char('a'),
+    KeyCode::Char('B'),
+    KeyCode::Char('C'),
+    KeyCode::Char('d'),
+    KeyCode::Char('L'),
+    KeyCode::Char('O'),
+    KeyCode::Char('X'),
+    KeyCode::F(5),
+];
+
+/// Random key presses at a steady pace until a deadline.
+///
+/// The keys are chosen by a PRNG with a fixed seed, so that runs are
+/// reproducible as far as the timing of responses allows.
+#[derive(Debug)]
+pub struct SoakInput {
+    state: u64,
+    deadline: Instant,
+    next_key: Instant,
+}
+impl SoakInput {
+    pub fn new(duration: Duration, seed: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            // xorshift gets stuck at 0.
+            state: seed.max(1),
+            deadline: now + duration,
+            next_key: now,
+        }
+    }
+
+    fn random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Wait for the next key press and return it, or None once the deadline
+    /// has passed.
+    pub fn next_event(&mut self) -> Option<Event> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return None;
+        }
+        if self.next_key > now {
+            std::thread::sleep(self.next_key - now);
+        }
+        self.next_key = Instant::now() + KEY_INTERVAL;
+
+        let code = KEYS[(self.random() % KEYS.len() as u64) as usize];
+        Some(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+    }
+}
+
+/// Number of threads of the process, if the platform tells.
+fn count_threads() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("Threads:"))?;
+    line["Threads:".len()..].trim().parse().ok()
+}
+
+/// Number of open file descriptors of the process, if the platform tells.
+fn count_fds() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+/// Resident memory of the process in KiB, if the platform tells.
+fn resident_kib() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line["VmRSS:".len()..].trim().strip_suffix("kB")?.trim().parse().ok()
+}
+
+/// Checks periodically that the resources used by the UI stay bounded.
+#[derive(Debug)]
+pub struct SoakMonitor {
+    start: Instant,
+    warm_up: Duration,
+    sample_interval: Duration,
+    last_sample: Option<Instant>,
+    samples: usize,
+
+    /// Largest values during the warm-up period.
+    baseline: BTreeMap<&'static str, usize>,
+
+    /// Largest values overall.
+    peak: BTreeMap<&'static str, usize>,
+}
+impl SoakMonitor {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            warm_up: WARM_UP.min(duration / 4),
+            sample_interval: SAMPLE_INTERVAL,
+            last_sample: None,
+            samples: 0,
+            baseline: BTreeMap::new(),
+            peak: BTreeMap::new(),
+        }
+    }
+
+    /// Sample more often than usual, for short runs.
+    pub fn sample_interval(self, sample_interval: Duration) -> Self {
+        Self { sample_interval, ..self }
+    }
+
+    /// Take a sample if it is due, and fail if anything has grown beyond its
+    /// limit.
+    ///
+    /// Must be called between `Connections::start_frame` and `end_frame`.
+    pub fn end_frame(
+        &mut self,
+        builder: &Builder,
+        connections: &mut Connections,
+        git_service: &GitService,
+    ) -> Result<()> {
+        if !self.sample_due() {
+            return Ok(());
+        }
+
+        let stats = builder.stats();
+        let clients: Vec<_> =
+            connections.all_clients().filter_map(|(_, client)| client.ok()).collect();
+        let api_cache = clients.iter().map(|client| client.borrow().stats().cache_entries).sum();
+        let pending = clients.iter().map(|client| client.borrow().pending_tasks()).sum();
+        self.record(vec![
+            ("state ids", stats.ids),
+            ("states", stats.states),
+            ("persistent cursors", stats.persistent_cursors),
+            ("API cache entries", api_cache),
+            ("pending API tasks", pending),
+            ("git cache entries", git_service.cache_entries()),
+        ])
+    }
+
+    /// Whether the next sample is due. If so, it must be passed to `record`.
+    pub fn sample_due(&mut self) -> bool {
+        if self.last_sample.is_some_and(|last| last.elapsed() < self.sample_interval) {
+            return false;
+        }
+        self.last_sample = Some(Instant::now());
+        self.samples += 1;
+        true
+    }
+
+    /// Add the resources used by the process to a sample, and fail if
+    /// anything has grown beyond its limit.
+    pub fn record(&mut self, mut sample: Vec<(&'static str, usize)>) -> Result<()> {
+        sample.extend(count_threads().map(|threads| ("threads", threads)));
+        sample.extend(count_fds().map(|fds| ("file descriptors", fds)));
+        sample.extend(resident_kib().map(|kib| ("resident memory (KiB)", kib)));
+        info!("Soak sample {}: {:?}", self.samples, sample);
+
+        let warming_up = self.start.elapsed() < self.warm_up;
+        for (name, value) in sample {
+            let peak = self.peak.entry(name).or_default();
+            *peak = (*peak).max(value);
+            if warming_up {
+                let baseline = self.baseline.entry(name).or_default();
+                *baseline = (*baseline).max(value);
+                continue;
+            }
+
+            let baseline = self.baseline.get(name).copied().unwrap_or_default();
+            if value > (2 * baseline).max(baseline + SLACK) {
+                Err(format!(
+                    "Soak test: {name} grew from {baseline} to {value} after {}s",
+                    self.start.elapsed().as_secs()
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Baseline and peak of each value, for a report at the end of the run.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Soak test passed after {}s, {} samples",
+            self.start.elapsed().as_secs(),
+            self.samples
+        );
+        for (name, peak) in &self.peak {
+            let baseline = self.baseline.get(name).copied().unwrap_or_default();
+            summary += &format!("\n  {name}: {baseline} after warm-up, {peak} at most");
+        }
+        summary
+    }
+}
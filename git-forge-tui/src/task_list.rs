@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Task lists in Markdown, e.g. the checklist of a pull request template:
+//!
+//! ```text
+//! - [ ] Tests were added
+//! - [x] The documentation was updated
+//! ```
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task<'a> {
+    /// Line of the task in the text, counting from 0.
+    pub line: usize,
+
+    /// Byte offset of the check mark (between the brackets) in the line.
+    mark: usize,
+
+    pub checked: bool,
+
+    /// Whitespace before the list marker, for nested lists.
+    pub indent: &'a str,
+
+    pub text: &'a str,
+}
+
+/// Lines of the text without their line terminators.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive('\n').map(|line| line.trim_end_matches(['\n', '\r']))
+}
+
+/// The tasks of the text, outside of fenced code blocks.
+pub fn parse(text: &str) -> Vec<Task<'_>> {
+    lazy_static::lazy_static! {
+        static ref RE: Regex =
+            Regex::new(r"^([ \t]*)(?:[-*+]|[0-9]+[.)])[ \t]+\[([ xX])\][ \t]+(.*)$").unwrap();
+    }
+    let mut tasks = Vec::new();
+    let mut in_code = false;
+    for (idx, line) in lines(text).enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some(captures) = RE.captures(line) {
+            let mark = captures.get(2).unwrap();
+            tasks.push(Task {
+                line: idx,
+                mark: mark.start(),
+                checked: mark.as_str() != " ",
+                indent: captures.get(1).unwrap().as_str(),
+                text: captures.get(3).unwrap().as_str(),
+            });
+        }
+    }
+    tasks
+}
+
+/// Check or uncheck the `index`th task of the text.
+///
+/// Returns None if there is no such task with the given text, e.g. because the
+/// text was edited in the meantime.
+pub fn set_checked(text: &str, index: usize, task_text: &str, checked: bool) -> Option<String> {
+    let task = parse(text).into_iter().nth(index).filter(|task| task.text == task_text)?;
+    let mut result = String::with_capacity(text.len());
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        if idx == task.line {
+            result.push_str(&line[..task.mark]);
+            result.push(if checked { 'x' } else { ' ' });
+            result.push_str(&line[task.mark + 1..]);
+        } else {
+            result.push_str(line);
+        }
+    }
+    Some(result)
+}
@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Actionable items from review discussions, e.g. promises to fix something
+//! in a follow-up.
+//!
+//! Items are extracted from the review threads that the user took part in
+//! and kept in the data directory together with their done state, so that
+//! they survive the threads being resolved or the pull request being merged.
+
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use vctools_utils::{files, prelude::*};
+
+use crate::{github::api, persistence, ApiRepository};
+
+/// Phrases that mark a line as actionable, in lower case.
+const MARKERS: &[&str] = &[
+    "todo",
+    "fixme",
+    "follow-up",
+    "follow up",
+    "followup",
+    "will fix",
+    "will do",
+    "will change",
+    "will address",
+    "i'll fix",
+    "i'll do",
+    "i'll change",
+    "i'll address",
+];
+
+/// Return the actionable lines of a comment: lines with one of the markers
+/// and unchecked task list items. Quotes and code blocks are skipped since
+/// they usually repeat what somebody else wrote.
+pub fn extract(body: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut in_code = false;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || trimmed.starts_with('>') {
+            continue;
+        }
+
+        let task = ["- [ ] ", "* [ ] "].iter().find_map(|prefix| trimmed.strip_prefix(prefix));
+        if let Some(task) = task {
+            items.push(task.trim().to_string());
+            continue;
+        }
+        let lower = trimmed.to_lowercase();
+        let has_marker = MARKERS.iter().any(|marker| {
+            lower.match_indices(marker).any(|(pos, _)| {
+                // Only match whole words, so that e.g. "todos" and "collateral"
+                // don't count.
+                let before = lower[..pos].chars().next_back();
+                let after = lower[pos + marker.len()..].chars().next();
+                let is_word = |ch: Option<char>| ch.is_some_and(char::is_alphanumeric);
+                !is_word(before) && !is_word(after)
+            })
+        });
+        if has_marker {
+            items.push(trimmed.trim_start_matches(['-', '*', ' ']).to_string());
+        }
+    }
+    items
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Todo {
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    pub number: u64,
+
+    /// URL of the comment that the item was found in.
+    pub url: String,
+
+    /// Login of the comment's author.
+    pub author: String,
+    pub text: String,
+    pub created_at: String,
+    pub done: bool,
+}
+impl Todo {
+    pub fn repository(&self) -> ApiRepository {
+        ApiRepository::new(self.host.clone(), self.owner.clone(), self.name.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TodoState {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+
+    /// Items in the order in which they were found.
+    items: Vec<Todo>,
+}
+impl TodoState {
+    /// Load the state from the given file. A missing or unreadable file
+    /// results in an empty state.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut state: TodoState = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| {
+                let result = try_forward(
+                    || -> Result<TodoState> {
+                        Ok(serde_json::from_slice(&files::read_bytes(path)?)?)
+                    },
+                    || format!("Error loading {}", path.display()),
+                );
+                result.map_err(|err| warn!("{}", err)).ok()
+            })
+            .unwrap_or_default();
+        state.path = path;
+        state
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        persistence::write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    pub fn items(&self) -> &[Todo] {
+        &self.items
+    }
+
+    /// Add the actionable items of the review threads of a pull request that
+    /// `user` took part in. Items that are already known keep their done
+    /// state; items whose comment was edited away are kept.
+    pub fn collect(
+        &mut self,
+        repo: &ApiRepository,
+        number: u64,
+        threads: &[api::ReviewThread],
+        user: &str,
+    ) -> Result<()> {
+        let mut changed = false;
+        for thread in threads {
+            let comments = &thread.comments.nodes;
+            let author = |comment: &api::ThreadComment| {
+                comment.author.as_ref().map_or(String::new(), |author| author.login.clone())
+            };
+            if !comments.iter().any(|comment| author(comment) == user) {
+                continue;
+            }
+            for comment in comments {
+                for text in extract(&comment.body) {
+                    let known = self
+                        .items
+                        .iter()
+                        .any(|item| item.url == comment.url && item.text == text);
+                    if known {
+                        continue;
+                    }
+                    self.items.push(Todo {
+                        host: repo.host.clone(),
+                        owner: repo.owner.clone(),
+                        name: repo.name.clone(),
+                        number,
+                        url: comment.url.clone(),
+                        author: author(comment),
+                        text,
+                        created_at: comment.created_at.clone(),
+                        done: false,
+                    });
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn toggle_done(&mut self, idx: usize) -> Result<()> {
+        if let Some(item) = self.items.get_mut(idx) {
+            item.done = !item.done;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Forget all items that are done.
+    pub fn remove_done(&mut self) -> Result<()> {
+        self.items.retain(|item| !item.done);
+        self.save()
+    }
+}
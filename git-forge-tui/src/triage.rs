@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Local triage state for the inbox, i.e. snoozed notifications.
+//!
+//! The forge has no notion of snoozing, so this is kept in the data
+//! directory.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use vctools_utils::{files, prelude::*};
+
+use crate::{github::api::NotificationThread, persistence};
+
+/// How long `s` snoozes a notification for.
+pub const SNOOZE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Snooze {
+    /// Unix timestamp at which the notification reappears.
+    until: i64,
+
+    /// Last update of the thread when it was snoozed. New activity ends the
+    /// snooze early.
+    updated_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TriageState {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+
+    /// Snoozed threads by "host/thread ID"
+    snoozed: HashMap<String, Snooze>,
+}
+impl TriageState {
+    /// Load the state from the given file. A missing or unreadable file
+    /// results in an empty state.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut state: TriageState = path
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| {
+                let result = try_forward(
+                    || -> Result<TriageState> {
+                        Ok(serde_json::from_slice(&files::read_bytes(path)?)?)
+                    },
+                    || format!("Error loading {}", path.display()),
+                );
+                result.map_err(|err| warn!("{}", err)).ok()
+            })
+            .unwrap_or_default();
+        state.path = path;
+        state.expire();
+        state
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        persistence::write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    fn expire(&mut self) {
+        let now = Utc::now().timestamp();
+        self.snoozed.retain(|_, snooze| snooze.until > now);
+    }
+
+    /// Whether the notification should be hidden from the inbox.
+    pub fn is_hidden(&self, host: &str, thread: &NotificationThread) -> bool {
+        self.snoozed
+            .get(&format!("{}/{}", host, thread.id))
+            .is_some_and(|snooze| {
+                snooze.until > Utc::now().timestamp() && snooze.updated_at == thread.updated_at
+            })
+    }
+
+    pub fn snooze(
+        &mut self,
+        host: &str,
+        thread: &NotificationThread,
+        duration: Duration,
+    ) -> Result<()> {
+        let until = Utc::now().timestamp() + duration.as_secs() as i64;
+        self.snooze_until(host, thread, until)
+    }
+
+    /// Snooze until the given Unix timestamp.
+    pub fn snooze_until(
+        &mut self,
+        host: &str,
+        thread: &NotificationThread,
+        until: i64,
+    ) -> Result<()> {
+        self.expire();
+        self.snoozed.insert(
+            format!("{}/{}", host, thread.id),
+            Snooze {
+                until,
+                updated_at: thread.updated_at.clone(),
+            },
+        );
+        self.save()
+    }
+
+    pub fn unsnooze_all(&mut self) -> Result<()> {
+        self.snoozed.clear();
+        self.save()
+    }
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctools_utils::time;
+use vctuik::{
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, simple_table},
+};
+
+use crate::github::{self, token::TokenCheck};
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<String>,
+}
+
+/// Lists the configured hosts with the user, and the scopes and expiry of
+/// their tokens, and highlights problems with the tokens.
+#[derive(Debug, Default)]
+pub struct Accounts {}
+impl Accounts {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let state_id = builder.add_state_id("accounts");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let error_style = table_builder.add_style(text.error);
+        let inactive_style = table_builder.add_style(text.inactive);
+
+        let now = chrono::Utc::now();
+        for (host, client) in connections.all_clients() {
+            let item = table_builder
+                .add(0, host.host.clone())
+                .raw(0, host.host.clone())
+                .raw(1, host.user.clone());
+            let check = match client {
+                Ok(client) => client.borrow().token_check(),
+                Err(err) => TokenCheck::Failed(err.to_string()),
+            };
+            match check {
+                TokenCheck::Unchecked => {
+                    item.styled(4, "not checked", inactive_style);
+                }
+                TokenCheck::Pending => {
+                    item.styled(4, "checking...", inactive_style);
+                }
+                TokenCheck::Failed(err) => {
+                    item.styled(4, err, error_style);
+                }
+                TokenCheck::Valid(status) => {
+                    let scopes = match &status.scopes {
+                        Some(scopes) => scopes.join(", "),
+                        None => "(fine-grained)".into(),
+                    };
+                    let expires = match status.expires {
+                        Some(expires) => {
+                            time::format_relative(expires.timestamp(), now.timestamp())
+                        }
+                        None => "never".into(),
+                    };
+                    let warnings = status.warnings(&host.user, now);
+                    let item = item.raw(2, scopes).raw(3, expires);
+                    if warnings.is_empty() {
+                        item.raw(4, "OK");
+                    } else {
+                        item.styled(4, warnings.join("; "), error_style);
+                    }
+                }
+            }
+        }
+
+        let columns = vec![
+            table::Column::new(0, "Host", Constraint1D::new(4, 30)),
+            table::Column::new(1, "User", Constraint1D::new(4, 20)),
+            table::Column::new(2, "Scopes", Constraint1D::new(6, 40)),
+            table::Column::new(3, "Expires", Constraint1D::new(7, 12)),
+            table::Column::new(4, "Status", Constraint1D::unconstrained()),
+        ];
+        builder.nest().id(state_id).build(|builder| {
+            table::Table::new(&table_builder.finish())
+                .id("hosts")
+                .columns(columns)
+                .build(builder);
+        });
+    }
+}
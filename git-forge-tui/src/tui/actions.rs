@@ -4,3 +4,7 @@ use regex::Regex;
 
 #[derive(Debug)]
 pub struct Search(pub Regex);
+
+/// Move the focus to the review.
+#[derive(Debug)]
+pub struct FocusReview;
@@ -0,0 +1,474 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Views for responding to reviews of one's own pull requests.
+
+use std::{
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use ratatui::text::Line;
+use vctools_utils::time;
+use vctuik::{
+    event::KeyCode,
+    label::add_text_label,
+    layout::Constraint1D,
+    pager::{Pager, PagerState, RichPagerSourceBuilder},
+    prelude::*,
+    state::Builder,
+    table::{self, simple_table},
+    theme::TextStyle,
+};
+
+use crate::{
+    browser,
+    github::{
+        self, api,
+        edit::{ReviewersRequest, ThreadReply, ThreadResolve},
+    },
+    hooks,
+    settings::{Settings, TableView},
+    ApiRepository, CompletePullRequest,
+};
+
+use super::{columns, identicon, markdown, CommentComposer, CommentDraft, ComposerAction};
+
+/// Lists the open pull requests of the user on every host.
+#[derive(Debug, Default)]
+pub struct MyPulls {}
+impl MyPulls {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Returns the selected pull request, if any.
+    pub fn build(
+        self,
+        builder: &mut Builder,
+        connections: &mut github::connections::Connections,
+    ) -> Option<(ApiRepository, u64)> {
+        let state_id = builder.add_state_id("my-pulls");
+        let state: &mut simple_table::SourceState<(String, String)> = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.build();
+        let host_style = table_builder.add_style(text.header1);
+        let inactive_style = table_builder.add_style(text.inactive);
+        let error_style = table_builder.add_style(text.error);
+
+        let now = time::unix_now();
+        let kinds = Settings::get().columns.get(TableView::Pulls).to_vec();
+        let mut pulls = Vec::new();
+        for (host, client) in connections.all_clients() {
+            let host_id = table_builder
+                .add(0, (host.host.clone(), String::new()))
+                .styled(0, &host.host, host_style)
+                .group()
+                .id();
+            let mut client = match client {
+                Ok(client) => client.borrow_mut(),
+                Err(err) => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "error".into()))
+                        .styled(0, err.to_string(), error_style);
+                    continue;
+                }
+            };
+            let mut access = client.access();
+            let result = match access.user_pulls(&host.user).ok_or_pending() {
+                Ok(Some(result)) => result,
+                Ok(None) => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "loading".into()))
+                        .styled(0, "Loading...", inactive_style);
+                    continue;
+                }
+                Err(err) => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "error".into()))
+                        .styled(0, err, error_style);
+                    continue;
+                }
+            };
+            for issue in &result.items {
+                let Some((owner, name)) = issue.repository() else { continue };
+                let reference = format!("{owner}/{name}#{}", issue.number);
+                let item = table_builder
+                    .add(host_id, (host.host.clone(), reference.clone()))
+                    .raw(0, issue.title.clone())
+                    .raw(1, reference);
+                let item = match issue.html_url.as_str() {
+                    "" => item,
+                    url => item.link(1, url.to_string()),
+                };
+                let item_id = columns::add_issue_cells(
+                    item,
+                    &kinds,
+                    issue,
+                    (&host.host, owner, name),
+                    &mut access,
+                    now,
+                )
+                .id();
+                let repo = ApiRepository::new(host.host.clone(), owner.into(), name.into());
+                pulls.push((item_id, (repo, issue.number)));
+            }
+        }
+
+        let mut table_columns = vec![
+            table::Column::new(0, "", Constraint1D::unconstrained()),
+            table::Column::new(1, "Pull Request", Constraint1D::new(8, 40)),
+        ];
+        table_columns.extend(columns::table_columns(&kinds));
+        let result = builder.nest().id(state_id).build(|builder| {
+            table::Table::new(&table_builder.finish())
+                .id("pulls")
+                .columns(table_columns)
+                .filter_column(0)
+                .build(builder)
+        });
+
+        let selection = result.selection?;
+        pulls.into_iter().find(|(id, _)| *id == selection).map(|(_, pull)| pull)
+    }
+}
+
+/// Outcome of the rebase and push commands, filled in by the thread that
+/// runs them.
+type JobResult = Arc<Mutex<Option<std::result::Result<(), String>>>>;
+
+#[derive(Debug, Default)]
+struct ThreadsState {
+    table_state: simple_table::SourceState<String>,
+    pager_state: PagerState,
+
+    /// Also list resolved threads.
+    show_resolved: bool,
+
+    /// Reply being written, with the ID of the thread.
+    reply: Option<(String, CommentDraft)>,
+    reply_error: Option<String>,
+
+    /// Pull request whose branch is being rebased and pushed.
+    job: Option<(ApiRepository, u64, JobResult)>,
+
+    /// Outcome of the last action, and whether it is an error.
+    message: Option<(String, bool)>,
+}
+
+/// Write a review thread on a host: the diff hunk it refers to and the
+/// conversation.
+fn write_thread(
+    pager: &mut RichPagerSourceBuilder,
+    host: &str,
+    thread: &api::ReviewThread,
+) -> Result<()> {
+    pager.set_theme_style(TextStyle::Header1);
+    write!(pager, "{}", thread.path)?;
+    if let Some(line) = thread.line {
+        write!(pager, ":{line}")?;
+    }
+    pager.set_theme_style(TextStyle::Inactive);
+    match (thread.is_resolved, thread.is_outdated) {
+        (true, _) => writeln!(pager, " (resolved)")?,
+        (false, true) => writeln!(pager, " (outdated)")?,
+        (false, false) => writeln!(pager)?,
+    }
+
+    if let Some(first) = thread.comments.nodes.first() {
+        for line in first.diff_hunk.lines() {
+            let style = match line.chars().next() {
+                Some('+') => TextStyle::Added,
+                Some('-') => TextStyle::Removed,
+                Some('@') => TextStyle::Header2,
+                _ => TextStyle::Normal,
+            };
+            pager.set_theme_style(style);
+            writeln!(pager, "{line}")?;
+        }
+        writeln!(pager)?;
+    }
+
+    for comment in &thread.comments.nodes {
+        let author = comment.author.as_ref().map_or("ghost", |author| author.login.as_str());
+        identicon::write(pager, host, author)?;
+        pager.set_theme_style(TextStyle::Highlight);
+        write!(pager, "@{author}")?;
+        pager.set_theme_style(TextStyle::Header2);
+        writeln!(pager, " at {}:", comment.created_at)?;
+        pager.set_indent(4);
+        markdown::write_markdown(pager, &comment.body)?;
+        pager.set_indent(0);
+        writeln!(pager)?;
+    }
+    Ok(())
+}
+
+/// Users whose review to request again: everybody who reviewed the pull
+/// request, except its author and bots.
+fn reviewers_to_request(reviews: &[api::Review], author: &str) -> Vec<String> {
+    let mut reviewers: Vec<String> = Vec::new();
+    for review in reviews {
+        let login = &review.user.login;
+        if review.is_pending() || login == author || login.ends_with("[bot]") {
+            continue;
+        }
+        if !reviewers.contains(login) {
+            reviewers.push(login.clone());
+        }
+    }
+    reviewers
+}
+
+/// The review threads of one of the user's pull requests, for responding to
+/// them: replying, resolving, and pushing an updated branch.
+pub struct ReviewThreads<'build> {
+    pr: &'build CompletePullRequest,
+}
+impl<'build> ReviewThreads<'build> {
+    pub fn new(pr: &'build CompletePullRequest) -> Self {
+        Self { pr }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let pr = self.pr;
+        let (owner, name, number) = (&pr.api.owner, &pr.api.name, pr.id);
+        let state_id = builder.add_state_id("review-threads");
+        let state: &mut ThreadsState = builder.get_state(state_id);
+
+        let (threads, pull, reviews, user) = match connections.client(&pr.api.host) {
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let user = client.host().user.clone();
+                let access = client.access();
+                (
+                    access.review_threads(owner, name, number).ok_or_pending(),
+                    access.pull(owner, name, number).ok().ok(),
+                    access.reviews(owner, name, number).ok().ok(),
+                    user,
+                )
+            }
+            Err(err) => (Err(err.to_string().into()), None, None, String::new()),
+        };
+
+        // Check on the rebase and push commands.
+        if let Some((repo, job_number, result)) = &state.job {
+            let outcome = result.lock().unwrap().take();
+            match outcome {
+                None => builder.refresh_after(Duration::from_millis(250)),
+                Some(Err(err)) => state.message = Some((err, true)),
+                Some(Ok(())) => {
+                    let reviewers = reviews
+                        .as_deref()
+                        .filter(|_| *repo == pr.api && *job_number == number)
+                        .map(|reviews| reviewers_to_request(reviews, &user))
+                        .unwrap_or_default();
+                    state.message = Some(if reviewers.is_empty() {
+                        ("Pushed".into(), false)
+                    } else {
+                        let edit = ReviewersRequest {
+                            owner: repo.owner.clone(),
+                            name: repo.name.clone(),
+                            number: *job_number,
+                            reviewers: reviewers.clone(),
+                        };
+                        match connections.edit(&repo.host, edit.into(), None) {
+                            Ok(()) => (
+                                format!("Pushed; requesting reviews from {}", reviewers.join(", ")),
+                                false,
+                            ),
+                            Err(err) => (format!("Pushed, but {err}"), true),
+                        }
+                    });
+                }
+            }
+            if state.message.is_some() {
+                state.job = None;
+            }
+        }
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let inactive_style = table_builder.add_style(text.inactive);
+        let error_style = table_builder.add_style(text.error);
+        let (header_style, normal_style) = (text.header2, text.normal);
+        let (error_text, inactive_text) = (text.error, text.inactive);
+
+        let threads: &[api::ReviewThread] = match &threads {
+            Ok(Some(threads)) => threads,
+            Ok(None) => {
+                table_builder.add(0, "loading".into()).styled(0, "Loading...", inactive_style);
+                &[]
+            }
+            Err(err) => {
+                table_builder.add(0, "error".into()).styled(0, err.clone(), error_style);
+                &[]
+            }
+        };
+        let unresolved = threads.iter().filter(|thread| !thread.is_resolved).count();
+
+        let mut item_ids = Vec::new();
+        for (idx, thread) in threads.iter().enumerate() {
+            if thread.is_resolved && !state.show_resolved {
+                continue;
+            }
+            let location = match thread.line {
+                Some(line) => format!("{}:{}", thread.path, line),
+                None => thread.path.clone(),
+            };
+            let last = thread.comments.nodes.last();
+            let who = last
+                .and_then(|comment| comment.author.as_ref())
+                .map_or(String::new(), |author| identicon::mention(&pr.api.host, &author.login));
+            let summary = thread
+                .comments
+                .nodes
+                .first()
+                .and_then(|comment| comment.body.lines().next())
+                .unwrap_or_default()
+                .to_string();
+            let item = table_builder.add(0, thread.id.clone());
+            let item = if thread.is_resolved || thread.is_outdated {
+                item.styled(0, location, inactive_style)
+                    .styled(1, who, inactive_style)
+                    .styled(2, summary, inactive_style)
+            } else {
+                item.raw(0, location).raw(1, who).raw(2, summary)
+            };
+            item_ids.push((item.id(), idx));
+        }
+
+        let columns = vec![
+            table::Column::new(0, "Location", Constraint1D::new(8, 40)),
+            table::Column::new(1, "Last", Constraint1D::new(4, 20)),
+            table::Column::new(2, "Comment", Constraint1D::unconstrained()),
+        ];
+
+        builder.nest().id(state_id).build(|builder| {
+            let mut lines = vec![Line::styled(
+                format!("{unresolved} unresolved of {} threads", threads.len()),
+                header_style,
+            )];
+            if let Some((message, is_error)) = &state.message {
+                lines.push(Line::styled(
+                    message.clone(),
+                    if *is_error { error_text } else { normal_style },
+                ));
+            } else if state.job.is_some() {
+                lines.push(Line::styled("Rebasing and pushing...", inactive_text));
+            }
+            add_text_label(builder, lines.into());
+
+            let table_result = table::Table::new(&table_builder.finish())
+                .id("threads")
+                .columns(columns)
+                .build(builder);
+            let selected = table_result
+                .selection
+                .and_then(|id| item_ids.iter().find(|(item_id, _)| *item_id == id))
+                .map(|(_, idx)| &threads[*idx]);
+
+            let mut pager = RichPagerSourceBuilder::new();
+            if let Some(thread) = selected {
+                if let Err(err) = write_thread(&mut pager, &pr.api.host, thread) {
+                    pager.set_theme_style(TextStyle::Error);
+                    writeln!(&mut pager, "{err}").unwrap();
+                }
+            }
+            let pager_source = pager.build();
+            Pager::new(&pager_source).build_with_state(builder, "thread", &mut state.pager_state);
+
+            if state.reply.is_none() && builder.check_group_focus(state_id) {
+                if builder.on_key_binding(KeyCode::Char('a'), "Show / hide resolved threads") {
+                    state.show_resolved = !state.show_resolved;
+                    builder.need_refresh();
+                } else if builder.on_key_binding(KeyCode::Char('P'), "Rebase, push, re-request") {
+                    let commands = Settings::get().author.rebase_and_push.clone();
+                    if commands.is_empty() {
+                        state.message = Some((
+                            "Set [author] rebase_and_push in settings.toml".into(),
+                            true,
+                        ));
+                    } else if let Some(pull) = &pull {
+                        let result = JobResult::default();
+                        let done = result.clone();
+                        let env = vec![
+                            ("PR_REMOTE", pr.git.remote.clone()),
+                            ("PR_BASE", pull.base.ref_.clone()),
+                            ("PR_HEAD", pull.head.ref_.clone()),
+                            ("PR_NUMBER", number.to_string()),
+                        ];
+                        hooks::run_commands(
+                            commands,
+                            pr.git.repository.path.clone(),
+                            env,
+                            move |outcome| *done.lock().unwrap() = Some(outcome),
+                        );
+                        state.job = Some((pr.api.clone(), number, result));
+                        state.message = None;
+                    }
+                    builder.need_refresh();
+                }
+
+                if let Some(thread) = selected {
+                    if builder.on_key_binding(KeyCode::Char('r'), "Reply to the thread") {
+                        state.reply = Some((thread.id.clone(), CommentDraft::default()));
+                        state.reply_error = None;
+                        builder.need_refresh();
+                    } else if builder.on_key_binding(KeyCode::Char('x'), "Resolve / unresolve") {
+                        let edit = ThreadResolve {
+                            owner: owner.clone(),
+                            name: name.clone(),
+                            number,
+                            thread_id: thread.id.clone(),
+                            resolved: !thread.is_resolved,
+                        };
+                        state.message = connections
+                            .edit(&pr.api.host, edit.into(), None)
+                            .err()
+                            .map(|err| (err.to_string(), true));
+                        builder.need_refresh();
+                    } else if builder.on_key_binding(KeyCode::Char('o'), "Open in the browser") {
+                        let url = thread.comments.nodes.first().map(|comment| &comment.url);
+                        if let Some(Err(err)) = url.map(|url| browser::open_url(url)) {
+                            state.message = Some((err.to_string(), true));
+                        }
+                    }
+                }
+            }
+
+            if let Some((thread_id, draft)) = &mut state.reply {
+                let title = format!("Reply on {owner}/{name}#{number}");
+                let action = CommentComposer::new(&title)
+                    .error(state.reply_error.as_deref())
+                    .build(builder, draft);
+                if action != ComposerAction::None {
+                    builder.need_refresh();
+                }
+                match action {
+                    ComposerAction::None => {}
+                    ComposerAction::Cancel => state.reply = None,
+                    ComposerAction::Submit(body) if body.trim().is_empty() => {
+                        state.reply_error = Some("Reply is empty".into());
+                    }
+                    ComposerAction::Submit(body) => {
+                        let edit = ThreadReply {
+                            owner: owner.clone(),
+                            name: name.clone(),
+                            number,
+                            thread_id: thread_id.clone(),
+                            body,
+                        };
+                        match connections.edit(&pr.api.host, edit.into(), None) {
+                            Ok(()) => state.reply = None,
+                            Err(err) => {
+                                state.reply_error = Some(format!("Failed to reply: {err}"));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Avatars: the pictures of users, shown in place of their identicons.
+//!
+//! The avatar of a user is looked up on the host that the user was seen on:
+//! its REST API reports the `avatar_url` of the login. Avatars are fetched in
+//! the background with the connection settings (proxy, TLS) of that host and
+//! cached on disk, so that each one is downloaded only once. They are drawn
+//! like identicons, as two braille characters in one color: the dots are the
+//! parts of the picture that stand out from its border, in their average color.
+//!
+//! Users whose avatar can't be fetched or decoded keep their identicon, and so
+//! does everybody until [`enable`] is called, e.g. when offline.
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    io::Cursor,
+    sync::{mpsc, Mutex},
+};
+
+use image::{ImageReader, Limits, RgbaImage};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use ratatui::style::Color;
+use reqwest::{header, Url};
+use serde::Deserialize;
+use vctools_utils::prelude::*;
+
+use super::identicon;
+use crate::{
+    config::get_project_dirs,
+    github::{self, disk_cache::DiskCache, flavor::ApiFlavor},
+};
+
+/// Size of the avatars that are requested, in pixels.
+const FETCH_SIZE: u32 = 32;
+
+/// Largest width and height of a picture that is decoded, in pixels.
+const MAX_SIZE: u32 = 2048;
+
+#[derive(Debug, Default)]
+struct Avatars {
+    /// Hosts that avatars are fetched from.
+    hosts: Vec<github::Host>,
+
+    /// Pictures by host and login. `None` while the avatar is fetched, or if
+    /// it couldn't be.
+    pictures: HashMap<String, HashMap<String, Option<(String, Color)>>>,
+
+    /// Hosts and logins to fetch, for the background thread.
+    queue: Option<mpsc::Sender<(github::Host, String)>>,
+}
+
+lazy_static! {
+    static ref AVATARS: Mutex<Avatars> = Mutex::new(Avatars::default());
+}
+
+/// Fetch avatars from the given hosts from now on.
+pub fn enable(hosts: &[github::Host]) {
+    AVATARS.lock().unwrap().hosts = hosts.to_vec();
+}
+
+/// The avatar of a login on a host, drawn like an identicon, once it has been
+/// fetched. Starts fetching it otherwise.
+pub fn picture(host: &str, login: &str) -> Option<(String, Color)> {
+    let mut avatars = AVATARS.lock().unwrap();
+    if let Some(picture) = avatars.pictures.get(host).and_then(|pictures| pictures.get(login)) {
+        return picture.clone();
+    }
+    let host = avatars.hosts.iter().find(|config| config.matches_host(host))?.clone();
+
+    let pictures = avatars.pictures.entry(host.host.clone()).or_default();
+    pictures.insert(login.to_string(), None);
+    let queue = avatars.queue.get_or_insert_with(|| {
+        let (send, recv) = mpsc::channel();
+        std::thread::spawn(move || fetch_all(recv));
+        send
+    });
+    let _ = queue.send((host, login.to_string()));
+    None
+}
+
+fn fetch_all(queue: mpsc::Receiver<(github::Host, String)>) {
+    let disk = match DiskCache::open(get_project_dirs().cache_dir().join("avatars")) {
+        Ok(disk) => Some(disk),
+        Err(err) => {
+            warn!("Error opening the avatar cache: {}", err);
+            None
+        }
+    };
+    let mut clients: HashMap<String, Option<HostClient>> = HashMap::new();
+
+    for (host, login) in queue {
+        let key = format!("{}/{}", host.host, login);
+        let data = disk.as_ref().and_then(|disk| disk.load(&key)).or_else(|| {
+            let client = clients.entry(host.host.clone()).or_insert_with(|| {
+                HostClient::new(&host)
+                    .map_err(|err| warn!("Can't fetch avatars from {}: {}", host.host, err))
+                    .ok()
+            });
+            match client.as_ref()?.fetch(&login) {
+                Ok(data) => {
+                    if let Some(disk) = &disk {
+                        disk.store(&key, &data);
+                    }
+                    Some(data)
+                }
+                Err(err) => {
+                    debug!("Error fetching the avatar of {}: {}", key, err);
+                    None
+                }
+            }
+        });
+
+        let picture = data.as_deref().and_then(decode);
+        if data.is_some() && picture.is_none() {
+            debug!("Can't decode the avatar of {}", key);
+        }
+        let mut avatars = AVATARS.lock().unwrap();
+        avatars.pictures.entry(host.host).or_default().insert(login, picture);
+    }
+}
+
+/// Fetches avatars from one host.
+struct HostClient {
+    client: reqwest::blocking::Client,
+    rest_url: Url,
+    token: String,
+}
+impl HostClient {
+    fn new(host: &github::Host) -> Result<Self> {
+        Ok(Self {
+            client: host.http_client_builder()?.user_agent("git-review").build()?,
+            rest_url: ApiFlavor::new(host)?.rest_url,
+            token: host.token.clone(),
+        })
+    }
+
+    fn fetch(&self, login: &str) -> Result<Vec<u8>> {
+        #[derive(Deserialize)]
+        struct User {
+            avatar_url: String,
+        }
+
+        let user: User = self
+            .client
+            .get(self.rest_url.join(&format!("users/{login}"))?)
+            .bearer_auth(&self.token)
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        // Avatars may be served from another host, so the token is not sent.
+        let mut url = Url::parse(&user.avatar_url)?;
+        url.query_pairs_mut().append_pair("s", &FETCH_SIZE.to_string());
+        let response = self.client.get(url).send()?.error_for_status()?;
+        Ok(response.bytes()?.to_vec())
+    }
+}
+
+/// Draw the picture in a PNG or JPEG file like an identicon.
+pub fn decode(data: &[u8]) -> Option<(String, Color)> {
+    let mut reader = ImageReader::new(Cursor::new(data)).with_guessed_format().ok()?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SIZE);
+    limits.max_image_height = Some(MAX_SIZE);
+    reader.limits(limits);
+    let image = reader.decode().ok()?.into_rgba8();
+    if image.width() == 0 || image.height() == 0 {
+        return None;
+    }
+    draw(&image)
+}
+
+fn draw(image: &RgbaImage) -> Option<(String, Color)> {
+    // Average the picture down to 4x4 cells, weighted by opacity.
+    let span = |index: u32, size: u32| {
+        let begin = index * size / 4;
+        begin..((index + 1) * size / 4).max(begin + 1)
+    };
+    let mut cells = [[None; 4]; 4];
+    for (row, cells_row) in (0..).zip(cells.iter_mut()) {
+        for (col, cell) in (0..).zip(cells_row.iter_mut()) {
+            let mut sum = [0.0; 3];
+            let mut weight = 0.0;
+            for y in span(row, image.height()) {
+                for x in span(col, image.width()) {
+                    let [red, green, blue, alpha] = image.get_pixel(x, y).0;
+                    let alpha = f32::from(alpha);
+                    for (sum, value) in sum.iter_mut().zip([red, green, blue]) {
+                        *sum += f32::from(value) * alpha;
+                    }
+                    weight += alpha;
+                }
+            }
+            if weight > 0.0 {
+                *cell = Some(sum.map(|sum| sum / weight));
+            }
+        }
+    }
+
+    // With a transparent background, the dots are the opaque cells. Otherwise,
+    // split the cells into bright and dark ones, and the dots are the side
+    // that covers less of the border.
+    let luma = |[red, green, blue]: [f32; 3]| 0.299 * red + 0.587 * green + 0.114 * blue;
+    let opaque: Vec<_> = cells.iter().flatten().flatten().copied().collect();
+    if opaque.is_empty() {
+        return None;
+    }
+    let mut split = None;
+    if opaque.len() == 16 {
+        let threshold = opaque.iter().copied().map(luma).sum::<f32>() / 16.0;
+        let count = |border_only: bool, bright: bool| {
+            let is_border = |row: usize, col: usize| row.is_multiple_of(3) || col.is_multiple_of(3);
+            (0..16)
+                .filter(|&i| !border_only || is_border(i / 4, i % 4))
+                .filter(|&i| cells[i / 4][i % 4].is_some_and(|c| (luma(c) > threshold) == bright))
+                .count()
+        };
+        let bright = match count(true, true).cmp(&count(true, false)) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => count(false, true) <= count(false, false),
+        };
+        if count(false, bright) > 0 {
+            split = Some((threshold, bright));
+        }
+    }
+    let is_dot =
+        |cell| split.is_none_or(|(threshold, bright)| (luma(cell) > threshold) == bright);
+
+    let mut grid = [[false; 4]; 4];
+    let mut sum = [0.0; 3];
+    let mut count = 0.0;
+    for (grid_row, cells_row) in grid.iter_mut().zip(&cells) {
+        for (dot, cell) in grid_row.iter_mut().zip(cells_row) {
+            if let Some(cell) = cell.filter(|&cell| is_dot(cell)) {
+                *dot = true;
+                for (sum, value) in sum.iter_mut().zip(cell) {
+                    *sum += value;
+                }
+                count += 1.0;
+            }
+        }
+    }
+
+    // The closest color of the 6x6x6 cube of the 256-color palette, without
+    // the darkest and brightest levels, like identicons.
+    let [red, green, blue] = sum.map(|sum| ((sum / count / 255.0 * 5.0).round() as u8).clamp(1, 4));
+    Some((identicon::braille(grid), Color::Indexed(16 + 36 * red + 6 * green + blue)))
+}
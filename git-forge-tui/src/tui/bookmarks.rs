@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::BTreeMap;
+
+use ratatui::text::{Line, Span};
+use vctuik::{
+    pager::{Cursor, PagerSource, PersistentCursor},
+    section::with_section,
+    state::Builder,
+};
+
+#[derive(Debug)]
+struct Bookmark {
+    cursor: PersistentCursor,
+
+    /// Line in the current pager source.
+    line: usize,
+
+    /// Whether the anchor of the bookmark could not be found in the current
+    /// pager source, so that `line` is only an approximation.
+    lost: bool,
+}
+
+/// Named positions in a pager source.
+///
+/// Bookmarks are stored as persistent cursors, so they follow the contents
+/// when the pager source is rebuilt, e.g. after the pull request was updated.
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    marks: BTreeMap<char, Bookmark>,
+}
+impl Bookmarks {
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Whether the given key can be used as the name of a bookmark.
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_alphabetic()
+    }
+
+    pub fn set(&mut self, name: char, source: &dyn PagerSource, line: usize) {
+        let cursor = PersistentCursor::persist(source, Cursor::new(line, 0));
+        self.marks.insert(name, Bookmark { cursor, line, lost: false });
+    }
+
+    pub fn get(&self, name: char) -> Option<usize> {
+        self.marks.get(&name).map(|bookmark| bookmark.line)
+    }
+
+    /// Find all bookmarks in the current pager source.
+    ///
+    /// Must be called whenever the pager source may have changed, before
+    /// `get`.
+    pub fn update(&mut self, source: &dyn PagerSource) {
+        for bookmark in self.marks.values_mut() {
+            let (cursor, success) = bookmark.cursor.retrieve(source);
+            bookmark.line = cursor.line;
+            bookmark.lost = !success;
+            if success {
+                // Re-anchor, so that the bookmark keeps following the
+                // contents through later changes.
+                bookmark.cursor = PersistentCursor::persist(source, cursor);
+            }
+        }
+    }
+
+    /// Build a pane that lists the bookmarks with the text of their lines.
+    pub fn build_pane(&self, builder: &mut Builder, source: &dyn PagerSource) {
+        with_section(builder, "Bookmarks", |builder| {
+            let text = builder.theme().text(builder.theme_context()).clone();
+            if self.marks.is_empty() {
+                let area = builder.take_lines_fixed(1);
+                let hint = "No bookmarks; press m and a letter to add one";
+                builder.frame().render_widget(Span::styled(hint, text.inactive), area);
+                return;
+            }
+            for (name, bookmark) in &self.marks {
+                let area = builder.take_lines_fixed(1);
+                let style = if bookmark.lost { text.inactive } else { text.normal };
+                let line = source.get_raw_line(bookmark.line, 0, area.width as usize);
+                let line = Line::from(vec![
+                    Span::styled(format!("'{name} "), text.highlight),
+                    Span::styled(format!("{:>6} ", bookmark.line + 1), text.header2),
+                    Span::styled(line.trim_end().to_string(), style),
+                ]);
+                builder.frame().render_widget(line, area);
+            }
+        });
+    }
+}
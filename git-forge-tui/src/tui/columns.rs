@@ -0,0 +1,382 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Optional columns of the pull request and notification tables, and the
+//! dialog for choosing them.
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use vctools_utils::time;
+use vctuik::{
+    event::{KeyCode, KeyModifiers, WithModifiers},
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, simple_table},
+    theme,
+};
+
+use crate::{
+    github::{self, api},
+    risk::RiskAssessment,
+    settings::{ColumnKind, Settings, TableView},
+    tui::{identicon, links},
+};
+
+/// Source ID of the first optional column. The fixed columns of the tables
+/// (e.g. the title) use lower IDs.
+const FIRST_ID: usize = 2;
+
+/// Source ID of an optional column. It only depends on the kind, so that the
+/// layout engine keeps a column's width when others are added or removed.
+pub fn source_id(kind: ColumnKind) -> usize {
+    FIRST_ID + ColumnKind::ALL.iter().position(|k| *k == kind).unwrap()
+}
+
+pub fn title(kind: ColumnKind) -> &'static str {
+    match kind {
+        ColumnKind::Risk => "Risk",
+        ColumnKind::Age => "Age",
+        ColumnKind::Updated => "Last Update",
+        ColumnKind::Author => "Author",
+        ColumnKind::Size => "Size",
+        ColumnKind::Ci => "CI",
+        ColumnKind::Labels => "Labels",
+        ColumnKind::Milestone => "Milestone",
+    }
+}
+
+fn constraint(kind: ColumnKind) -> Constraint1D {
+    match kind {
+        ColumnKind::Risk => Constraint1D::new(4, 16),
+        ColumnKind::Age | ColumnKind::Updated => Constraint1D::new(5, 20),
+        ColumnKind::Author => Constraint1D::new(4, 20),
+        ColumnKind::Size => Constraint1D::new(5, 14),
+        ColumnKind::Ci => Constraint1D::new(4, 8),
+        ColumnKind::Labels => Constraint1D::new(4, 30),
+        ColumnKind::Milestone => Constraint1D::new(4, 20),
+    }
+}
+
+/// Table columns for the given optional columns.
+pub fn table_columns(kinds: &[ColumnKind]) -> Vec<table::Column<'static>> {
+    kinds
+        .iter()
+        .map(|&kind| table::Column::new(source_id(kind), title(kind), constraint(kind)))
+        .collect()
+}
+
+/// State of the CI of a commit.
+#[derive(Debug, Clone)]
+pub struct CiState {
+    /// Compact state for the column, e.g. "pass" or "fail".
+    pub state: &'static str,
+
+    /// Summary of the checks, one per line.
+    pub summary: String,
+}
+
+/// State of the CI of a commit, loaded with prefetch priority.
+pub fn ci_state(
+    client: &github::ClientRef,
+    owner: &str,
+    name: &str,
+    sha: &str,
+) -> Option<CiState> {
+    let status = client.combined_status(owner, name, sha).ok().ok()?;
+    let checks = client.check_runs(owner, name, sha).ok().ok()?;
+    if status.total_count == 0 && checks.total_count == 0 {
+        return Some(CiState {
+            state: "-",
+            summary: "No checks".into(),
+        });
+    }
+
+    let mut summary = Vec::new();
+    if status.total_count > 0 {
+        summary.push(format!("Commit statuses: {} ({})", status.state, status.total_count));
+    }
+    let mut running = status.total_count > 0 && status.state == "pending";
+    let mut failed = status.state == "failure" || status.state == "error";
+    for run in &checks.check_runs {
+        match (run.status.as_str(), run.conclusion.as_deref()) {
+            ("completed", Some("failure" | "timed_out" | "cancelled" | "action_required")) => {
+                failed = true
+            }
+            ("completed", _) => {}
+            _ => running = true,
+        }
+        let result = run.conclusion.as_deref().unwrap_or(&run.status);
+        summary.push(format!("{}: {}", run.name, result));
+    }
+    Some(CiState {
+        state: if failed {
+            "fail"
+        } else if running {
+            "running"
+        } else {
+            "pass"
+        },
+        summary: summary.join("\n"),
+    })
+}
+
+/// What is known about a pull request, for filling in the optional columns.
+#[derive(Debug, Default)]
+pub struct Row<'a> {
+    /// Host of the pull request, for the avatar of its author.
+    pub host: &'a str,
+    pub author: Option<&'a str>,
+    pub labels: &'a [api::Label],
+    pub milestone: Option<&'a api::Milestone>,
+    pub created_at: Option<&'a str>,
+    pub updated_at: Option<&'a str>,
+
+    /// The pull request itself, if it was loaded.
+    pub pull: Option<&'a api::Pull>,
+
+    /// URL of the repository in the web interface, for hyperlinks.
+    pub repo_url: Option<&'a str>,
+
+    pub risk: Option<String>,
+    pub ci: Option<CiState>,
+}
+impl<'a> Row<'a> {
+    pub fn from_issue(issue: &'a api::IssueSummary) -> Self {
+        Self {
+            author: issue.user.as_ref().map(|user| user.login.as_str()),
+            labels: &issue.labels,
+            milestone: issue.milestone.as_ref(),
+            created_at: Some(&issue.created_at),
+            updated_at: Some(&issue.updated_at),
+            repo_url: links::repository(&issue.html_url),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_pull(pull: &'a api::Pull) -> Self {
+        Self {
+            author: Some(&pull.user.login),
+            labels: &pull.labels,
+            milestone: pull.milestone.as_ref(),
+            created_at: Some(&pull.created_at),
+            updated_at: Some(&pull.updated_at),
+            pull: Some(pull),
+            repo_url: links::repository(&pull.html_url),
+            ..Default::default()
+        }
+    }
+
+    fn cell(&self, kind: ColumnKind, now: i64) -> String {
+        let relative = |timestamp: Option<&str>| {
+            let timestamp = timestamp.unwrap_or_default();
+            match chrono::DateTime::parse_from_rfc3339(timestamp) {
+                Ok(dt) => time::format_relative(dt.timestamp(), now),
+                Err(_) => timestamp.to_string(),
+            }
+        };
+        match kind {
+            ColumnKind::Risk => self.risk.clone().unwrap_or_default(),
+            ColumnKind::Age => relative(self.created_at),
+            ColumnKind::Updated => relative(self.updated_at),
+            ColumnKind::Author => {
+                self.author.map(|author| identicon::mention(self.host, author)).unwrap_or_default()
+            }
+            ColumnKind::Size => self
+                .pull
+                .and_then(|pull| Some(format!("+{} -{}", pull.additions?, pull.deletions?)))
+                .unwrap_or_default(),
+            ColumnKind::Ci => self.ci.as_ref().map(|ci| ci.state).unwrap_or_default().to_string(),
+            ColumnKind::Labels => {
+                self.labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>().join(", ")
+            }
+            ColumnKind::Milestone => {
+                self.milestone.map(|milestone| milestone.title.clone()).unwrap_or_default()
+            }
+        }
+    }
+
+    /// Details of a cell that are shown when the mouse pointer is on it.
+    fn tooltip(&self, kind: ColumnKind) -> Option<String> {
+        let timestamp = |timestamp: Option<&str>| {
+            let dt = chrono::DateTime::parse_from_rfc3339(timestamp?).ok()?;
+            Some(time::format_iso(dt.timestamp()))
+        };
+        match kind {
+            ColumnKind::Age => timestamp(self.created_at),
+            ColumnKind::Updated => timestamp(self.updated_at),
+            ColumnKind::Ci => self.ci.as_ref().map(|ci| ci.summary.clone()),
+            _ => None,
+        }
+    }
+
+    /// Fill in the given columns of a table item.
+    pub fn add_cells<'builder, 'source>(
+        &self,
+        mut item: simple_table::ItemBuilder<'builder, 'source>,
+        kinds: &[ColumnKind],
+        now: i64,
+    ) -> simple_table::ItemBuilder<'builder, 'source> {
+        for &kind in kinds {
+            item = item.raw(source_id(kind), self.cell(kind, now));
+            if let Some(tooltip) = self.tooltip(kind) {
+                item = item.tooltip(source_id(kind), tooltip);
+            }
+            if kind == ColumnKind::Author {
+                let url = self.repo_url.zip(self.author);
+                if let Some(url) = url.and_then(|(repo, user)| links::user(repo, user)) {
+                    item = item.link(source_id(kind), url);
+                }
+            }
+        }
+        item
+    }
+}
+
+/// Fill in the optional columns for a pull request from a search result.
+/// Details that searches don't return are prefetched if they are shown.
+pub fn add_issue_cells<'builder, 'source>(
+    item: simple_table::ItemBuilder<'builder, 'source>,
+    kinds: &[ColumnKind],
+    issue: &api::IssueSummary,
+    (host, owner, name): (&str, &str, &str),
+    client: &mut github::ClientRef,
+    now: i64,
+) -> simple_table::ItemBuilder<'builder, 'source> {
+    let client = client.prefetching();
+    let need_pull = kinds
+        .iter()
+        .any(|kind| matches!(kind, ColumnKind::Risk | ColumnKind::Size | ColumnKind::Ci));
+    let pull = need_pull.then(|| client.pull(owner, name, issue.number).ok().ok()).flatten();
+
+    let mut row = Row::from_issue(issue);
+    row.host = host;
+    if let Some(pull) = &pull {
+        let settings = Settings::get();
+        let risk_settings = settings.risk(owner, name);
+        row.pull = Some(pull);
+        row.risk = RiskAssessment::from_totals(pull)
+            .map(|risk| risk.badges(risk_settings).join(" "));
+        if kinds.contains(&ColumnKind::Ci) {
+            row.ci = ci_state(&client, owner, name, &pull.head.sha);
+        }
+    }
+    row.add_cells(item, kinds, now)
+}
+
+/// Columns of a table while they are being chosen.
+#[derive(Debug)]
+pub struct ColumnsDraft {
+    pub view: TableView,
+
+    /// All columns in order, and whether they are shown.
+    entries: Vec<(ColumnKind, bool)>,
+    selected: usize,
+}
+impl ColumnsDraft {
+    pub fn new(view: TableView, current: &[ColumnKind]) -> Self {
+        let mut entries: Vec<_> = current.iter().map(|&kind| (kind, true)).collect();
+        for kind in ColumnKind::ALL {
+            if !current.contains(&kind) {
+                entries.push((kind, false));
+            }
+        }
+        Self {
+            view,
+            entries,
+            selected: 0,
+        }
+    }
+
+    pub fn columns(&self) -> Vec<ColumnKind> {
+        self.entries.iter().filter(|(_, shown)| *shown).map(|(kind, _)| *kind).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnsAction {
+    None,
+    Save,
+    Cancel,
+}
+
+/// Modal popup for choosing and ordering the optional columns of a table.
+#[derive(Debug, Default)]
+pub struct ColumnsDialog {}
+impl ColumnsDialog {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(self, builder: &mut Builder, draft: &mut ColumnsDraft) -> ColumnsAction {
+        let state_id = builder.add_state_id("columns_dialog");
+
+        let viewport = builder.viewport();
+        let height = std::cmp::min(draft.entries.len() as u16 + 2, viewport.height);
+        let width = std::cmp::min(40, viewport.width);
+        let area = Rect {
+            x: viewport.x + (viewport.width - width) / 2,
+            y: viewport.y + (viewport.height - height) / 2,
+            width,
+            height,
+        };
+
+        builder
+            .nest()
+            .modal(state_id, true)
+            .theme_context(theme::Context::Modal)
+            .build(|builder| {
+                if builder.on_key_binding(KeyCode::Esc, "Cancel") {
+                    return ColumnsAction::Cancel;
+                }
+                if builder.on_key_binding(KeyCode::Enter, "Save the columns") {
+                    return ColumnsAction::Save;
+                }
+
+                let last = draft.entries.len() - 1;
+                let selected = draft.selected;
+                let move_up = KeyCode::Up.with_modifiers(KeyModifiers::SHIFT);
+                let move_down = KeyCode::Down.with_modifiers(KeyModifiers::SHIFT);
+                if builder.on_key_binding(move_up, "Move up") {
+                    if selected > 0 {
+                        draft.entries.swap(selected, selected - 1);
+                        draft.selected -= 1;
+                    }
+                } else if builder.on_key_binding(move_down, "Move down") {
+                    if selected < last {
+                        draft.entries.swap(selected, selected + 1);
+                        draft.selected += 1;
+                    }
+                } else if builder.on_key_binding(KeyCode::Up, "Select a column") {
+                    draft.selected = selected.saturating_sub(1);
+                } else if builder.on_key_binding(KeyCode::Down, "Select a column") {
+                    draft.selected = std::cmp::min(selected + 1, last);
+                } else if builder.on_key_binding(KeyCode::Char(' '), "Show / hide the column") {
+                    draft.entries[selected].1 = !draft.entries[selected].1;
+                }
+
+                let text = builder.theme().modal_text.clone();
+                let block = Block::new()
+                    .borders(Borders::ALL)
+                    .title(format!(" Columns of the {} ", draft.view.as_str()))
+                    .title_bottom(" Space: show  Shift+↑↓: move  Enter: save ")
+                    .style(builder.theme().modal_background.patch(text.normal));
+                let inner = block.inner(area);
+                builder.frame().render_widget(Clear, area);
+                builder.frame().render_widget(block, area);
+
+                let lines: Vec<Line> = draft
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (kind, shown))| {
+                        let style = if idx == draft.selected { text.selected } else { text.normal };
+                        let mark = if *shown { "[x]" } else { "[ ]" };
+                        Line::styled(format!("{mark} {}", title(*kind)), style)
+                    })
+                    .collect();
+                builder.frame().render_widget(Paragraph::new(lines), inner);
+                ColumnsAction::None
+            })
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::prelude::*;
+use regex::Regex;
+use vctuik::{
+    command::{CommandAction, CommandLine},
+    event::KeyCode,
+    state::Builder,
+};
+
+use super::actions;
+
+/// Command line at the bottom of the screen, shared by the tools.
+///
+/// Handles `/` to search and returns commands that are entered after `:`.
+/// Progress and the most recent error are shown above the command line.
+pub struct CommandBar<'build> {
+    command: &'build mut Option<String>,
+    search: &'build mut Option<Regex>,
+    error: &'build mut Option<String>,
+    help: &'build str,
+    progress: Option<&'build str>,
+}
+impl<'build> CommandBar<'build> {
+    pub fn new(
+        command: &'build mut Option<String>,
+        search: &'build mut Option<Regex>,
+        error: &'build mut Option<String>,
+    ) -> Self {
+        Self {
+            command,
+            search,
+            error,
+            help: "/ to search, ? for help, q to quit",
+            progress: None,
+        }
+    }
+
+    pub fn help(self, help: &'build str) -> Self {
+        Self { help, ..self }
+    }
+
+    pub fn progress(self, progress: Option<&'build str>) -> Self {
+        Self { progress, ..self }
+    }
+
+    /// Returns the command (without the `:`) if one was entered.
+    pub fn build(self, builder: &mut Builder) -> Option<String> {
+        let was_search = self.command.as_ref().is_some_and(|cmd| cmd.starts_with('/'));
+
+        let error = &*self.error;
+        let progress = self.progress;
+        let action = CommandLine::new("command", &mut *self.command)
+            .help(self.help)
+            .build(builder, |builder, _| {
+                let text = builder.theme().text(builder.theme_context());
+                for (line, style) in [(progress, text.inactive), (error.as_deref(), text.error)] {
+                    if let Some(line) = line {
+                        let area = builder.take_lines_fixed(1);
+                        builder.frame().render_widget(Span::styled(line, style), area);
+                    }
+                }
+            });
+
+        let mut result = None;
+        match action {
+            CommandAction::None => {}
+            CommandAction::Command(cmd) => {
+                *self.error = None;
+                if was_search {
+                    if let Some(pattern) = self.search.as_ref() {
+                        builder.inject_custom(actions::Search(pattern.clone()));
+                    }
+                } else if let Some(cmd) = cmd.strip_prefix(':') {
+                    result = Some(cmd.to_string());
+                }
+                builder.need_refresh();
+            }
+            CommandAction::Changed(cmd) => {
+                assert!(!cmd.is_empty());
+
+                *self.error = None;
+                if let Some(pattern) = cmd.strip_prefix('/') {
+                    *self.search = None;
+                    if !pattern.is_empty() {
+                        match Regex::new(pattern) {
+                            Ok(regex) => {
+                                *self.search = Some(regex);
+                            }
+                            Err(e) => {
+                                *self.error = Some(format!("{}", e));
+                            }
+                        }
+                    }
+                } else if cmd.starts_with(':') {
+                    // nothing to do
+                } else {
+                    *self.error = Some(format!(
+                        "Unknown command prefix: {}",
+                        cmd.chars().next().unwrap()
+                    ));
+                }
+                builder.need_refresh();
+            }
+            CommandAction::Cancelled => {
+                if was_search {
+                    *self.search = None;
+                }
+                *self.error = None;
+            }
+        }
+
+        if builder.on_key_binding(KeyCode::Char('/'), "Search") {
+            *self.command = Some("/".into());
+            *self.search = None;
+            builder.need_refresh();
+        } else if builder.on_key_binding(KeyCode::Char(':'), "Enter a command") {
+            *self.command = Some(":".into());
+            builder.need_refresh();
+        }
+
+        result
+    }
+}
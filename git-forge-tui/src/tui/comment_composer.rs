@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use vctuik::{
+    event::{KeyCode, KeyModifiers, WithModifiers},
+    input::{Input, InputAction},
+    state::Builder,
+    theme,
+};
+
+use crate::settings::Snippet;
+
+/// Text of a comment that is being written.
+#[derive(Debug, Default)]
+pub struct CommentDraft {
+    /// Completed lines
+    lines: Vec<String>,
+
+    /// Line that is currently being edited
+    current: String,
+
+    /// Selected snippet, if the snippet picker is open
+    picker: Option<usize>,
+}
+impl CommentDraft {
+    /// A draft with the given text, e.g. one that was recovered after a crash.
+    pub fn from_text(text: &str) -> Self {
+        let mut draft = Self::default();
+        draft.insert(text);
+        draft
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = self.lines.join("\n");
+        if !self.lines.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&self.current);
+        text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.current.is_empty()
+    }
+
+    fn insert(&mut self, text: &str) {
+        let mut parts = text.split('\n');
+        self.current.push_str(parts.next().unwrap());
+        for part in parts {
+            self.lines.push(std::mem::replace(&mut self.current, part.to_string()));
+        }
+    }
+
+    /// Expand an abbreviation at the end of the current line. Returns true if
+    /// an abbreviation was found.
+    fn expand_abbreviation(&mut self, snippets: &[&Snippet], values: &[(&str, &str)]) -> bool {
+        let word_start = self
+            .current
+            .rfind(char::is_whitespace)
+            .map(|idx| idx + self.current[idx..].chars().next().unwrap().len_utf8())
+            .unwrap_or(0);
+        let word = &self.current[word_start..];
+        if word.is_empty() {
+            return false;
+        }
+        let Some(snippet) = snippets
+            .iter()
+            .find(|snippet| snippet.abbreviation.as_deref() == Some(word))
+        else {
+            return false;
+        };
+        self.current.truncate(word_start);
+        self.insert(&snippet.expand(values));
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComposerAction {
+    None,
+    Submit(String),
+    Cancel,
+}
+
+/// Modal popup for writing a comment.
+///
+/// Enter starts a new line, Ctrl+S submits, and Esc cancels. Snippets can be
+/// inserted by typing their abbreviation followed by Tab, or picked from a
+/// list with Ctrl+T.
+#[derive(Debug)]
+pub struct CommentComposer<'build> {
+    title: &'build str,
+    error: Option<&'build str>,
+    snippets: Vec<&'build Snippet>,
+    values: &'build [(&'build str, &'build str)],
+}
+impl<'build> CommentComposer<'build> {
+    pub fn new(title: &'build str) -> Self {
+        Self {
+            title,
+            error: None,
+            snippets: Vec::new(),
+            values: &[],
+        }
+    }
+
+    /// Error to show, e.g. from a failed attempt to submit.
+    pub fn error(self, error: Option<&'build str>) -> Self {
+        Self { error, ..self }
+    }
+
+    /// Snippets to offer, and the values for their placeholders.
+    pub fn snippets(
+        self,
+        snippets: Vec<&'build Snippet>,
+        values: &'build [(&'build str, &'build str)],
+    ) -> Self {
+        Self {
+            snippets,
+            values,
+            ..self
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder, draft: &mut CommentDraft) -> ComposerAction {
+        let state_id = builder.add_state_id("comment_composer");
+
+        // Take the lower half of the available space.
+        let viewport = builder.viewport();
+        let height = std::cmp::max(viewport.height / 2, std::cmp::min(5, viewport.height));
+        let area = Rect {
+            y: viewport.y + viewport.height - height,
+            height,
+            ..viewport
+        };
+
+        builder
+            .nest()
+            .modal(state_id, true)
+            .theme_context(theme::Context::Modal)
+            .build(|builder| {
+                if builder.on_key_binding(KeyCode::Esc, "Cancel") {
+                    if draft.picker.is_some() {
+                        draft.picker = None;
+                        builder.need_refresh();
+                        return ComposerAction::None;
+                    }
+                    return ComposerAction::Cancel;
+                }
+                if builder.on_key_binding(
+                    KeyCode::Char('s').with_modifiers(KeyModifiers::CONTROL),
+                    "Submit the comment",
+                ) {
+                    return ComposerAction::Submit(draft.text());
+                }
+                if builder.on_key_binding(
+                    KeyCode::Char('t').with_modifiers(KeyModifiers::CONTROL),
+                    "Pick a snippet",
+                ) {
+                    draft.picker = match draft.picker {
+                        None if !self.snippets.is_empty() => Some(0),
+                        _ => None,
+                    };
+                    builder.need_refresh();
+                }
+
+                let text = builder.theme().modal_text.clone();
+                let block = Block::new()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", self.title))
+                    .title_bottom(" Ctrl+S: submit  Ctrl+T: snippets  Esc: cancel ")
+                    .style(builder.theme().modal_background.patch(text.normal));
+                let mut inner = block.inner(area);
+                builder.frame().render_widget(Clear, area);
+                builder.frame().render_widget(block, area);
+                if let Some(error) = self.error.filter(|_| inner.height > 1) {
+                    builder
+                        .frame()
+                        .render_widget(Span::styled(error, text.error), inner);
+                    inner.y += 1;
+                    inner.height -= 1;
+                }
+                if inner.height == 0 {
+                    return ComposerAction::None;
+                }
+
+                if let Some(selected) = draft.picker {
+                    if builder.on_key_binding(KeyCode::Up, "Select a snippet") {
+                        draft.picker = Some(selected.saturating_sub(1));
+                    } else if builder.on_key_binding(KeyCode::Down, "Select a snippet") {
+                        draft.picker = Some(std::cmp::min(selected + 1, self.snippets.len() - 1));
+                    } else if builder.on_key_binding(KeyCode::Enter, "Insert the snippet") {
+                        draft.insert(&self.snippets[selected].expand(self.values));
+                        draft.picker = None;
+                        builder.need_refresh();
+                    }
+
+                    if let Some(selected) = draft.picker {
+                        let first = (selected + 1).saturating_sub(inner.height as usize);
+                        let lines: Vec<Line> = self
+                            .snippets
+                            .iter()
+                            .enumerate()
+                            .skip(first)
+                            .map(|(idx, snippet)| {
+                                let style =
+                                    if idx == selected { text.selected } else { text.normal };
+                                let abbreviation = snippet
+                                    .abbreviation
+                                    .as_ref()
+                                    .map(|abbr| format!(" ({abbr})"))
+                                    .unwrap_or_default();
+                                Line::styled(format!("{}{}", snippet.name, abbreviation), style)
+                            })
+                            .collect();
+                        builder.frame().render_widget(Paragraph::new(lines), inner);
+                        return ComposerAction::None;
+                    }
+                }
+
+                if builder.on_key_binding(KeyCode::Tab, "Expand a snippet abbreviation")
+                    && draft.expand_abbreviation(&self.snippets, self.values)
+                {
+                    builder.need_refresh();
+                }
+
+                // Show as many of the completed lines as fit above the input line.
+                let history = inner.height as usize - 1;
+                let first = draft.lines.len().saturating_sub(history);
+                let lines: Vec<Line> = draft.lines[first..]
+                    .iter()
+                    .map(|line| Line::styled(line.as_str(), text.normal))
+                    .collect();
+                let history_area = Rect {
+                    height: lines.len() as u16,
+                    ..inner
+                };
+                let input_area = Rect {
+                    y: inner.y + history_area.height,
+                    height: 1,
+                    ..inner
+                };
+                builder.frame().render_widget(Paragraph::new(lines), history_area);
+
+                match Input::new("input").area(input_area).build(builder, &mut draft.current) {
+                    Some(InputAction::Enter) => {
+                        draft.lines.push(std::mem::take(&mut draft.current));
+                        builder.need_refresh();
+                    }
+                    Some(InputAction::TextChanged) => {}
+                    None => {
+                        // Backspace at the start of a line joins it with the
+                        // previous line.
+                        if draft.current.is_empty()
+                            && !draft.lines.is_empty()
+                            && builder
+                                .on_key_binding(KeyCode::Backspace, "Join with the previous line")
+                        {
+                            draft.current = draft.lines.pop().unwrap();
+                            builder.need_refresh();
+                        }
+                    }
+                }
+
+                ComposerAction::None
+            })
+    }
+}
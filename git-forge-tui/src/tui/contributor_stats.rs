@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::text::Line;
+use vctools_utils::time;
+use vctuik::{
+    chart,
+    event::KeyCode,
+    label::add_text_label,
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, simple_table},
+};
+
+use crate::{
+    contributor_stats::{self, Stats, TURNAROUND_BUCKETS},
+    github::{self, api, Response},
+    ApiRepository,
+};
+
+/// Periods that can be chosen, in days.
+const PERIODS: &[i64] = &[7, 30, 90, 365];
+
+/// Pages of search results that are fetched at most. The search API doesn't
+/// return more than 1000 results anyway.
+const MAX_PAGES: usize = 10;
+
+/// Width of the longest bar, in cells.
+const BAR_WIDTH: usize = 30;
+
+/// The pull requests that were merged since the given date, fetched page by
+/// page. Returns None while pages are still loading, and whether the results
+/// are incomplete because there were too many.
+fn merged_pulls(
+    client: &github::ClientRef,
+    repo: &ApiRepository,
+    since: &str,
+) -> Result<Option<(Vec<api::MergedPull>, bool)>, String> {
+    let mut pulls = Vec::new();
+    let mut cursor = None;
+    for _ in 0..MAX_PAGES {
+        let page = match client.merged_pulls(&repo.owner, &repo.name, since, cursor.as_deref()) {
+            Response::Ok(page) => page,
+            Response::Pending => return Ok(None),
+            response => return Err(response.ok().err().unwrap_or_default().into_owned()),
+        };
+        pulls.extend(page.nodes.iter().cloned());
+        match page.page_info.end_cursor.as_ref().filter(|_| page.page_info.has_next_page) {
+            Some(next) => cursor = Some(next.clone()),
+            None => return Ok(Some((pulls, false))),
+        }
+    }
+    Ok(Some((pulls, true)))
+}
+
+#[derive(Debug)]
+struct State {
+    table_state: simple_table::SourceState<String>,
+
+    /// Index into `PERIODS`.
+    period: usize,
+}
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            table_state: Default::default(),
+            // 30 days
+            period: 1,
+        }
+    }
+}
+
+/// Statistics of the pull requests that were merged into a repository over a
+/// period: merged pull requests per contributor and reviews per reviewer, as
+/// bar charts, and how long pull requests waited for their first review, in
+/// general and for the user's reviews.
+///
+/// The pull requests are searched with the GraphQL API, page by page. Pages
+/// are cached per day, so the statistics are only computed from fresh data
+/// once per day and period.
+pub struct ContributorStats<'build> {
+    repository: &'build ApiRepository,
+}
+impl<'build> ContributorStats<'build> {
+    pub fn new(repository: &'build ApiRepository) -> Self {
+        Self { repository }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let repo = self.repository;
+        let state_id = builder.add_state_id("stats");
+        let state: &mut State = builder.get_state(state_id);
+        let days = PERIODS[state.period];
+        let since = time::format_iso(time::unix_now() - days * 24 * 60 * 60)[..10].to_string();
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let group_style = table_builder.add_style(text.header2);
+        let me_style = table_builder.add_style(text.highlight);
+        let error_style = table_builder.add_style(text.error);
+        let inactive_style = table_builder.add_style(text.inactive);
+        let summary_style = text.header1;
+
+        let mut summary = format!("Pull requests merged in the last {days} days, since {since}");
+        match connections.client(&repo.host) {
+            Err(err) => {
+                table_builder.add(0, "error".into()).styled(0, err.to_string(), error_style);
+            }
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let me = client.host().user.clone();
+                let client = client.access();
+                match merged_pulls(&client, repo, &since) {
+                    Ok(Some((pulls, incomplete))) => {
+                        let stats = Stats::new(&pulls, &me);
+                        let more = if incomplete { "+" } else { "" };
+                        summary += &format!(": {}{more}", stats.total);
+                        add_stats(&mut table_builder, &stats, &me, group_style, me_style);
+                    }
+                    Ok(None) => {
+                        table_builder
+                            .add(0, "loading".into())
+                            .styled(0, "Loading pull requests...", inactive_style);
+                    }
+                    Err(err) => {
+                        table_builder.add(0, "error".into()).styled(0, err, error_style);
+                    }
+                }
+            }
+        }
+
+        let columns = vec![
+            table::Column::new(0, "", Constraint1D::new(16, 40)),
+            table::Column::new(1, "", Constraint1D::new(6, 10)),
+            table::Column::new(2, "", Constraint1D::unconstrained()),
+        ];
+
+        builder.nest().id(state_id).build(|builder| {
+            add_text_label(builder, Line::styled(summary, summary_style).into());
+            let table_result = table::Table::new(&table_builder.finish())
+                .id("stats")
+                .columns(columns)
+                .build(builder);
+            if table_result.has_focus
+                && builder.on_key_binding(KeyCode::Char('p'), "Change the period")
+            {
+                state.period = (state.period + 1) % PERIODS.len();
+                builder.need_refresh();
+            }
+        });
+    }
+}
+
+/// Add the statistics as groups of rows to the table.
+fn add_stats(
+    table_builder: &mut simple_table::SourceBuilder<'_, '_, String>,
+    stats: &Stats,
+    me: &str,
+    group_style: simple_table::StyleId,
+    me_style: simple_table::StyleId,
+) {
+    let mut add_counts = |key: &str, title: &str, counts: &[(String, usize)]| {
+        let group = table_builder.add(0, key.into());
+        let group = group.styled(0, title.to_string(), group_style).group().id();
+        let max = counts.first().map_or(0, |(_, count)| *count);
+        for (login, count) in counts {
+            let item = table_builder.add(group, login.clone());
+            let item = if login == me {
+                item.styled(0, format!("@{login}"), me_style)
+            } else {
+                item.raw(0, format!("@{login}"))
+            };
+            let bar = chart::bar(*count as f64, max as f64, BAR_WIDTH);
+            item.raw(1, count.to_string()).raw(2, bar);
+        }
+    };
+    add_counts("authors", "Merged pull requests by author", &stats.authors);
+    add_counts("reviewers", "Reviewed pull requests by reviewer", &stats.reviewers);
+
+    let mut add_turnaround = |key: &str, title: &str, values: &[i64]| {
+        let group = table_builder.add(0, key.into());
+        let group = group.styled(0, title.to_string(), group_style).group().id();
+        let counts = contributor_stats::histogram(values);
+        let max = counts.iter().copied().max().unwrap_or(0);
+        for ((_, label), count) in TURNAROUND_BUCKETS.iter().zip(counts) {
+            table_builder
+                .add(group, label.to_string())
+                .raw(0, label.to_string())
+                .raw(1, count.to_string())
+                .raw(2, chart::bar(count as f64, max as f64, BAR_WIDTH));
+        }
+        if let Some(median) = contributor_stats::median(values) {
+            table_builder
+                .add(group, "median".into())
+                .raw(0, "median")
+                .raw(1, contributor_stats::format_turnaround(median));
+        }
+    };
+    let unreviewed = stats.total - stats.turnaround.len();
+    let title = format!("First review ({unreviewed} pull requests without any review)");
+    add_turnaround("turnaround", &title, &stats.turnaround);
+
+    let title = format!(
+        "My first review ({} of {} pull requests)",
+        stats.my_reviews(),
+        stats.total
+    );
+    add_turnaround("mine", &title, &stats.my_turnaround);
+}
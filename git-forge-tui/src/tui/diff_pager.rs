@@ -1,15 +1,27 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{fmt::Write, ops::Range};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    ops::Range,
+};
 
 use diff_modulo_base::{diff, git_core};
-use ratatui::text::{Line, Span};
+use itertools::Itertools;
+use log::debug;
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+use vctools_utils::time;
 use vctuik::{
     pager::{self, PagerSource},
     prelude::*,
     theme,
 };
 
+use crate::{apply, generated, settings::RiskSettings, tui::links};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DiffDisplayMode {
     All,
@@ -47,10 +59,31 @@ impl DiffDisplayMode {
     }
 }
 
+/// Structural points of a diff that can be jumped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Landmark {
+    File,
+    Hunk,
+}
+
+/// Number of lines after which the bodies of further files are deferred until
+/// they are scrolled into view. This keeps building the source of huge diffs
+/// fast.
+const EAGER_LINES: usize = 5000;
+
+/// Ages in seconds after which unchanged lines are dimmed, and shown as
+/// inactive, respectively, when they are colored by age.
+const RECENT_AGE: i64 = 30 * 24 * 60 * 60;
+const OLD_AGE: i64 = 365 * 24 * 60 * 60;
+
 #[derive(Debug)]
 enum Element {
     Chunk(diff::render::Chunk),
     Commit(git_core::RangeDiffMatch),
+
+    /// Hunks of a file body that haven't been loaded yet. Shown as a single
+    /// placeholder line.
+    Deferred(Vec<diff::render::Chunk>),
 }
 impl Element {
     fn num_lines(&self, mode: DiffDisplayMode) -> usize {
@@ -58,7 +91,7 @@ impl Element {
             Element::Chunk(chunk) => match &chunk.contents {
                 diff::render::ChunkContents::FileHeader { .. } => 2,
                 diff::render::ChunkContents::HunkHeader { .. } => 1,
-                diff::render::ChunkContents::Line { line } =>
+                diff::render::ChunkContents::Line { line, .. } =>
                     if mode.is_covered(line.status) {
                         if line.contents.last().is_none_or(|ch| *ch != b'\n') {
                             2
@@ -69,7 +102,7 @@ impl Element {
                         0
                     },
             },
-            Element::Commit(_) => 1,
+            Element::Commit(_) | Element::Deferred(_) => 1,
         }
     }
 }
@@ -96,6 +129,30 @@ pub struct DiffPagerSource {
 
     /// Column widths for range diff matches
     rdm_column_widths: git_core::RangeDiffMatchColumnWidths,
+
+    /// Signatures of the commits of the range diff, by the (possibly
+    /// abbreviated) hash as it is shown.
+    signatures: HashMap<String, git_core::Signature>,
+
+    /// Indices into `files` of generated files
+    generated: HashSet<usize>,
+
+    /// Whether chunks of the body of the current file are deferred
+    defer_body: bool,
+
+    /// (repository URL, new commit) for hyperlinks to the web interface
+    web_urls: Option<(String, String)>,
+
+    /// Commit times of the lines of the changed files at the new commit, by
+    /// path. Empty unless unchanged lines are colored by age.
+    blame: HashMap<String, Vec<i64>>,
+
+    /// Commit time of the last change of every element that is an unchanged
+    /// line, computed from `blame`.
+    ages: Vec<Option<i64>>,
+
+    /// Time at which the blame was loaded, relative to which ages are shown.
+    now: i64,
 }
 impl std::fmt::Debug for DiffPagerSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -113,16 +170,316 @@ impl DiffPagerSource {
             .map_or(0, |&l| l + self.elements.last().unwrap().num_lines(self.mode))
     }
 
+    /// Link the commits and the new files of the diff to the web interface of
+    /// the given repository, where `new_commit` is the new side of the diff.
+    pub fn set_web_urls(&mut self, repo_url: &str, new_commit: &str) {
+        self.web_urls = Some((repo_url.to_string(), new_commit.to_string()));
+    }
+
     pub fn toggle_mode(&mut self) {
         self.mode = self.mode.toggled();
+        self.reindex();
+    }
+
+    /// Recompute the line numbers and the indices of headers from `elements`.
+    fn reindex(&mut self) {
+        self.global_lines.clear();
+        self.commits.clear();
+        self.files.clear();
+        self.hunks.clear();
 
         let mut line = 0;
-        for (global_line, element) in self.global_lines.iter_mut().zip(self.elements.iter()) {
-            *global_line = line;
+        for (idx, element) in self.elements.iter().enumerate() {
+            self.global_lines.push(line);
             line += element.num_lines(self.mode);
+
+            match element {
+                Element::Commit(_) => self.commits.push(idx),
+                Element::Chunk(chunk) => match chunk.contents {
+                    diff::render::ChunkContents::FileHeader { .. } => self.files.push(idx),
+                    diff::render::ChunkContents::HunkHeader { .. } => self.hunks.push(idx),
+                    diff::render::ChunkContents::Line { .. } => {}
+                },
+                Element::Deferred(_) => {}
+            }
+        }
+
+        self.ages = if self.blame.is_empty() { Vec::new() } else { self.compute_ages() };
+    }
+
+    /// Load the blame of the changed files at the given commit, so that
+    /// unchanged lines are colored by age. The output of Git is cached per
+    /// file by the execution provider.
+    ///
+    /// Files that can't be blamed, e.g. because they were deleted, are skipped.
+    pub fn load_blame(
+        &mut self,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+        commit: &git_core::Ref,
+    ) -> Result<()> {
+        let mut paths: Vec<String> = self
+            .files
+            .iter()
+            .filter(|&&idx| {
+                matches!(&self.elements[idx], Element::Chunk(chunk)
+                    if chunk.context == diff::render::Context::Change)
+            })
+            .filter_map(|&idx| self.file_name(idx))
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        for path in paths {
+            match repository.blame(ep, commit, &path) {
+                Ok(times) => {
+                    self.blame.insert(path, times);
+                }
+                Err(err) if ep.timed_out() => return Err(err),
+                Err(err) => debug!("{err}"),
+            }
+        }
+        self.now = time::unix_now();
+        self.reindex();
+        Ok(())
+    }
+
+    /// Look up the commit time of every unchanged line of the change in
+    /// `blame`, following the line numbers of the new side from the hunk
+    /// headers.
+    fn compute_ages(&self) -> Vec<Option<i64>> {
+        let mut times = None;
+        let mut new_line = 0;
+        self.elements
+            .iter()
+            .enumerate()
+            .map(|(idx, element)| {
+                let Element::Chunk(chunk) = element else { return None };
+                if chunk.context != diff::render::Context::Change {
+                    return None;
+                }
+                match &chunk.contents {
+                    diff::render::ChunkContents::FileHeader { .. } => {
+                        times = self.file_name(idx).and_then(|name| self.blame.get(&name));
+                        None
+                    }
+                    diff::render::ChunkContents::HunkHeader { new_begin, .. } => {
+                        new_line = *new_begin as usize;
+                        None
+                    }
+                    diff::render::ChunkContents::Line { line, .. } => {
+                        if !line.status.covers_new() {
+                            return None;
+                        }
+                        let line_no = new_line;
+                        new_line += 1;
+                        if !matches!(line.status, diff::HunkLineStatus::Unchanged) {
+                            return None;
+                        }
+                        times?.get(line_no.checked_sub(1)?).copied()
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Style of an unchanged line: the longer ago it was last changed, the
+    /// dimmer, if lines are colored by age.
+    fn age_style(&self, theme: &theme::Text, idx: usize) -> Style {
+        match self.ages.get(idx).copied().flatten() {
+            Some(time) if self.now - time > OLD_AGE => theme.inactive,
+            Some(time) if self.now - time > RECENT_AGE => theme.normal.add_modifier(Modifier::DIM),
+            _ => theme.normal,
         }
     }
 
+    /// Load the deferred file bodies that overlap the given range of lines.
+    /// Returns true if anything was loaded, which changes line numbers.
+    pub fn load_deferred(&mut self, lines: Range<usize>) -> bool {
+        if lines.is_empty() || lines.start >= self.num_global_lines() {
+            return false;
+        }
+        let first = self.global_lines.partition_point(|&l| l <= lines.start) - 1;
+        let last = self.global_lines.partition_point(|&l| l < lines.end);
+        let load: HashSet<usize> = (first..last)
+            .filter(|&idx| matches!(self.elements[idx], Element::Deferred(_)))
+            .collect();
+        if load.is_empty() {
+            return false;
+        }
+
+        let elements = std::mem::take(&mut self.elements);
+        for (idx, element) in elements.into_iter().enumerate() {
+            match element {
+                Element::Deferred(chunks) if load.contains(&idx) => {
+                    self.elements.extend(chunks.into_iter().map(Element::Chunk));
+                }
+                element => self.elements.push(element),
+            }
+        }
+        self.reindex();
+        true
+    }
+
+    /// Number of commit and file headers at or before the given element, and
+    /// the index of the last of them (or 0 if there is none).
+    ///
+    /// Loading deferred file bodies doesn't change sections, which makes them
+    /// suitable for persistent line numbers.
+    fn section_of(&self, idx: usize) -> (usize, usize) {
+        let commits = self.commits.partition_point(|&i| i <= idx);
+        let files = self.files.partition_point(|&i| i <= idx);
+        let last_commit = commits.checked_sub(1).map(|i| self.commits[i]);
+        let last_file = files.checked_sub(1).map(|i| self.files[i]);
+        let start = last_commit.max(last_file).unwrap_or(0);
+        (commits + files, start)
+    }
+
+    /// Find the first line of the next (or previous) file or hunk header,
+    /// strictly after (or before) the given line.
+    pub fn find_landmark(&self, line: usize, landmark: Landmark, forward: bool) -> Option<usize> {
+        let indices = match landmark {
+            Landmark::File => &self.files,
+            Landmark::Hunk => &self.hunks,
+        };
+        if forward {
+            let i = indices.partition_point(|&idx| self.global_lines[idx] <= line);
+            indices.get(i).map(|&idx| self.global_lines[idx])
+        } else {
+            let i = indices.partition_point(|&idx| self.global_lines[idx] < line);
+            i.checked_sub(1).map(|i| self.global_lines[indices[i]])
+        }
+    }
+
+    /// Path of the file whose header is at the given element index, preferring
+    /// the new name.
+    fn file_name(&self, idx: usize) -> Option<String> {
+        let Element::Chunk(chunk) = &self.elements[idx] else { return None };
+        let diff::render::ChunkContents::FileHeader { old_name, new_name, .. } = &chunk.contents
+        else {
+            return None;
+        };
+        match (new_name, old_name) {
+            (diff::FileName::Name(name), _) | (_, diff::FileName::Name(name)) => {
+                Some(String::from_utf8_lossy(name).into_owned())
+            }
+            _ => None,
+        }
+    }
+
+    /// Detect which of the files in the diff are generated as of the given
+    /// commit.
+    pub fn detect_generated(
+        &mut self,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+        commit: &git_core::Ref,
+        settings: &RiskSettings,
+    ) -> Result<()> {
+        let names: Vec<_> = self.files.iter().map(|&idx| self.file_name(idx)).collect();
+        let mut paths: Vec<&str> = names.iter().flatten().map(String::as_str).collect();
+        paths.sort();
+        paths.dedup();
+
+        let detected = generated::detect(repository, ep, commit, settings, &paths)?;
+        self.generated = names
+            .iter()
+            .positions(|name| name.as_ref().is_some_and(|name| detected.contains(name)))
+            .collect();
+        Ok(())
+    }
+
+    /// First lines of the headers of generated files, with their paths.
+    pub fn generated_files(&self) -> Vec<(usize, String)> {
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|(ordinal, _)| self.generated.contains(ordinal))
+            .filter_map(|(_, &idx)| Some((self.global_lines[idx], self.file_name(idx)?)))
+            .collect()
+    }
+
+    /// Verify the signatures of all commits of the range diff.
+    pub fn verify_signatures(
+        &mut self,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+    ) -> Result<()> {
+        let mut commits: Vec<git_core::Ref> = self
+            .commits
+            .iter()
+            .filter_map(|&idx| match &self.elements[idx] {
+                Element::Commit(rdm) => Some(rdm),
+                _ => None,
+            })
+            .flat_map(|rdm| [&rdm.old, &rdm.new])
+            .filter_map(|side| side.as_ref().map(|(_, commit)| commit.clone()))
+            .collect();
+        commits.sort_by(|a, b| a.name.cmp(&b.name));
+        commits.dedup();
+
+        let signatures = repository.verify_commits(ep, &commits)?;
+        self.signatures =
+            commits.into_iter().map(|commit| commit.name).zip(signatures).collect();
+        Ok(())
+    }
+
+    /// The signature of the commit shown at the given line, if any. The new
+    /// commit of a range diff match takes precedence over the old one.
+    pub fn signature_at(&self, line: usize) -> Option<&git_core::Signature> {
+        if line >= self.num_global_lines() {
+            return None;
+        }
+        let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
+        let Element::Commit(rdm) = &self.elements[idx] else { return None };
+        let (_, commit) = rdm.new.as_ref().or(rdm.old.as_ref())?;
+        self.signatures.get(&commit.name)
+    }
+
+    /// The hunk that contains the given line, if any.
+    pub fn hunk_at(&self, line: usize) -> Option<apply::Hunk> {
+        if line >= self.num_global_lines() {
+            return None;
+        }
+        let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
+
+        // Walk back to the hunk header and then to the file header.
+        let mut header = None;
+        let mut file_idx = idx;
+        let (old_path, new_path) = loop {
+            let Element::Chunk(chunk) = &self.elements[file_idx] else { return None };
+            match &chunk.contents {
+                diff::render::ChunkContents::FileHeader { old_path, new_path, .. } => {
+                    break (old_path, new_path);
+                }
+                diff::render::ChunkContents::HunkHeader { old_begin, new_begin, .. } => {
+                    header.get_or_insert((file_idx, *old_begin, *new_begin));
+                }
+                diff::render::ChunkContents::Line { .. } => {}
+            }
+            file_idx = file_idx.checked_sub(1)?;
+        };
+        let (header_idx, old_begin, new_begin) = header?;
+
+        let lines = self.elements[header_idx + 1..]
+            .iter()
+            .map_while(|element| match element {
+                Element::Chunk(diff::render::Chunk {
+                    contents: diff::render::ChunkContents::Line { line, .. },
+                    ..
+                }) => Some(line.clone()),
+                _ => None,
+            })
+            .collect();
+        Some(apply::Hunk {
+            old_path: old_path.clone(),
+            new_path: new_path.clone(),
+            old_begin,
+            new_begin,
+            lines,
+        })
+    }
+
     /// Find the nearest folding header at or below the given depth.
     ///
     /// If forward is true, find the smallest index strictly greater than the given index.
@@ -167,6 +524,18 @@ impl DiffPagerSource {
 }
 impl diff::render::ChunkWriter for DiffPagerSource {
     fn push_chunk(&mut self, chunk: diff::render::Chunk) {
+        if matches!(chunk.contents, diff::render::ChunkContents::FileHeader { .. }) {
+            self.defer_body = self.num_global_lines() >= EAGER_LINES;
+        } else if self.defer_body {
+            if let Some(Element::Deferred(chunks)) = self.elements.last_mut() {
+                chunks.push(chunk);
+            } else {
+                self.global_lines.push(self.num_global_lines());
+                self.elements.push(Element::Deferred(vec![chunk]));
+            }
+            return;
+        }
+
         self.global_lines.push(self.num_global_lines());
 
         if matches!(chunk.contents, diff::render::ChunkContents::FileHeader { .. }) {
@@ -181,6 +550,7 @@ impl diff::render::ChunkWriter for DiffPagerSource {
 impl git_core::RangeDiffWriter for DiffPagerSource {
     fn push_range_diff_match(&mut self, rdm: git_core::RangeDiffMatch) {
         self.rdm_column_widths = self.rdm_column_widths.max(rdm.column_widths());
+        self.defer_body = false;
 
         self.global_lines.push(self.num_global_lines());
         self.commits.push(self.elements.len());
@@ -196,6 +566,12 @@ impl PagerSource for DiffPagerSource {
         let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
         let line = line - self.global_lines[idx];
 
+        // Trust indicator of commits, as a separately styled first column.
+        let mut marker = None;
+
+        // Byte ranges of the text with the changed parts of paired lines.
+        let mut emphasis = Vec::new();
+
         let (text, style) = match &self.elements[idx] {
             Element::Chunk(chunk) =>
                 match &chunk.contents {
@@ -214,8 +590,8 @@ impl PagerSource for DiffPagerSource {
                     _ => {
                         let style = match &chunk.contents {
                             diff::render::ChunkContents::FileHeader { .. } => theme.header1,
-                            diff::render::ChunkContents::Line { line } => match line.status {
-                                diff::HunkLineStatus::Unchanged => theme.normal,
+                            diff::render::ChunkContents::Line { line, .. } => match line.status {
+                                diff::HunkLineStatus::Unchanged => self.age_style(theme, idx),
                                 diff::HunkLineStatus::Old(_) => theme.removed,
                                 diff::HunkLineStatus::New(_) => theme.added,
                             },
@@ -224,10 +600,49 @@ impl PagerSource for DiffPagerSource {
 
                         let mut text = Vec::new();
                         chunk.render_text(&mut text);
-                        (String::from_utf8_lossy(&text).into(), style)
+                        if let diff::render::ChunkContents::Line { line, changed } = &chunk.contents {
+                            // Offsets only carry over if the conversion is lossless.
+                            if str::from_utf8(&line.contents).is_ok() {
+                                let start = chunk.context.prefix_bytes().len() + 1;
+                                emphasis = changed
+                                    .iter()
+                                    .map(|range| range.start + start..range.end + start)
+                                    .collect();
+                            }
+                        }
+                        let mut text: String = String::from_utf8_lossy(&text).into();
+                        let ordinal = self.files.binary_search(&idx).ok();
+                        if ordinal.is_some_and(|ordinal| self.generated.contains(&ordinal)) {
+                            // Badge at the end of the first line of the header.
+                            let end = text.find('\n').unwrap_or(text.len());
+                            text.insert_str(end, " [generated]");
+                        }
+                        (text, style)
                     }
                 }
-            Element::Commit(rdm) => (rdm.format(self.rdm_column_widths), theme.header0),
+            Element::Commit(rdm) => {
+                let signature = rdm
+                    .new
+                    .as_ref()
+                    .or(rdm.old.as_ref())
+                    .and_then(|(_, commit)| self.signatures.get(&commit.name));
+                marker = Some(match signature.map(|signature| signature.status) {
+                    None | Some(git_core::SignatureStatus::Unsigned) => (' ', theme.normal),
+                    Some(git_core::SignatureStatus::Good) => ('G', theme.added),
+                    Some(git_core::SignatureStatus::Bad) => ('B', theme.error),
+                    Some(status) => (status.code(), theme.highlight),
+                });
+                (format!("  {}", rdm.format(self.rdm_column_widths)), theme.header0)
+            }
+            Element::Deferred(chunks) => {
+                let num_hunks = chunks
+                    .iter()
+                    .filter(|chunk| {
+                        matches!(chunk.contents, diff::render::ChunkContents::HunkHeader { .. })
+                    })
+                    .count();
+                (format!("... {num_hunks} hunk(s) not loaded yet"), theme.inactive)
+            }
         };
 
         let offset = text
@@ -247,7 +662,27 @@ impl PagerSource for DiffPagerSource {
         };
 
         let text = text[offset..].get_first_line(max_cols);
-        Line::from(Span::styled(text.to_owned(), style))
+        match marker {
+            Some((marker, marker_style)) if col_no == 0 && !text.is_empty() => Line::from(vec![
+                Span::styled(marker.to_string(), marker_style),
+                Span::styled(text[1..].to_owned(), style),
+            ]),
+            _ if !emphasis.is_empty() => {
+                let emphasis_style = style.add_modifier(Modifier::REVERSED);
+                let mut spans = Vec::new();
+                let mut pos = 0;
+                for range in &emphasis {
+                    let start = range.start.saturating_sub(offset).clamp(pos, text.len());
+                    let end = range.end.saturating_sub(offset).clamp(start, text.len());
+                    spans.push(Span::styled(text[pos..start].to_owned(), style));
+                    spans.push(Span::styled(text[start..end].to_owned(), emphasis_style));
+                    pos = end;
+                }
+                spans.push(Span::styled(text[pos..].to_owned(), style));
+                Line::from(spans)
+            }
+            _ => Line::from(Span::styled(text.to_owned(), style)),
+        }
     }
 
     fn get_folding_range(&self, line: usize, parent: bool) -> Option<(Range<usize>, usize)> {
@@ -272,17 +707,96 @@ impl PagerSource for DiffPagerSource {
         Some((self.global_lines[header_idx]..end_line, depth))
     }
 
+    fn minimap_mark(&self, line: usize) -> Option<pager::MinimapMark> {
+        let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
+        let Element::Chunk(chunk) = &self.elements[idx] else { return None };
+        let diff::render::ChunkContents::Line { line, .. } = &chunk.contents else { return None };
+        // Same distinction as in the colored output of diff-modulo-base.
+        let important = chunk.context == diff::render::Context::Change;
+        match line.status {
+            diff::HunkLineStatus::Unchanged => None,
+            diff::HunkLineStatus::Old(unimportant) | diff::HunkLineStatus::New(unimportant)
+                if unimportant || !important =>
+            {
+                Some(pager::MinimapMark::Unimportant)
+            }
+            diff::HunkLineStatus::Old(_) => Some(pager::MinimapMark::Removed),
+            diff::HunkLineStatus::New(_) => Some(pager::MinimapMark::Added),
+        }
+    }
+
+    fn get_links(&self, line: usize) -> Vec<pager::Link> {
+        let Some((repo_url, new_commit)) = &self.web_urls else {
+            return Vec::new();
+        };
+        let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
+        let line = line - self.global_lines[idx];
+
+        match &self.elements[idx] {
+            Element::Chunk(chunk) => {
+                // The path in the "+++" line. Headers of the base diff refer
+                // to other commits.
+                let diff::render::ChunkContents::FileHeader {
+                    new_name: diff::FileName::Name(name),
+                    new_path,
+                    ..
+                } = &chunk.contents
+                else {
+                    return Vec::new();
+                };
+                if line != 1 || chunk.context == diff::render::Context::Baseline {
+                    return Vec::new();
+                }
+                let name = String::from_utf8_lossy(name);
+                let end = chunk.context.prefix_bytes().len()
+                    + "+++ ".len()
+                    + String::from_utf8_lossy(new_path).chars().count();
+                vec![pager::Link {
+                    cols: end - name.chars().count()..end,
+                    url: links::file(repo_url, new_commit, &name),
+                }]
+            }
+            Element::Commit(rdm) => {
+                // The hashes precede the commit title, so byte offsets are
+                // columns.
+                let text = format!("  {}", rdm.format(self.rdm_column_widths));
+                let mut from = 0;
+                let mut result = Vec::new();
+                for (_, commit) in rdm.old.iter().chain(&rdm.new) {
+                    let hash = commit.to_string();
+                    let Some(begin) = text[from..].find(&hash).map(|begin| from + begin) else {
+                        continue;
+                    };
+                    from = begin + hash.len();
+                    result.push(pager::Link {
+                        cols: begin..from,
+                        url: links::commit(repo_url, &hash),
+                    });
+                }
+                result
+            }
+            Element::Deferred(_) => Vec::new(),
+        }
+    }
+
     fn persist_line_number(&self, line: usize) -> (Vec<pager::Anchor>, usize) {
         let idx = self.global_lines.partition_point(|&l| l <= line) - 1;
-        (vec![pager::Anchor::USize(idx)], line - self.global_lines[idx])
+        let (section, start) = self.section_of(idx);
+        let anchor = vec![pager::Anchor::USize(section), pager::Anchor::USize(idx - start)];
+        (anchor, line - self.global_lines[idx])
     }
 
     fn retrieve_line_number(&self, anchor: &[pager::Anchor], line_offset: usize) -> (usize, bool) {
-        if anchor.len() != 1 {
+        let [pager::Anchor::USize(section), pager::Anchor::USize(offset)] = anchor else {
             return (0, false);
-        }
-        let pager::Anchor::USize(idx) = anchor[0] else { return (0, false); };
-        let Some(line) = self.global_lines.get(idx) else { return (self.num_global_lines(), false); };
+        };
+        let start = match section.checked_sub(1) {
+            Some(n) => self.commits.iter().merge(&self.files).nth(n).copied(),
+            None => Some(0),
+        };
+        let Some(line) = start.and_then(|start| self.global_lines.get(start + offset)) else {
+            return (self.num_global_lines(), false);
+        };
         (*line + line_offset, true)
     }
 }
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use vctuik::{
+    event::KeyCode,
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, simple_table},
+};
+
+use crate::github;
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<(String, u64)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EditQueueResult {
+    /// Whether focus is on this widget
+    pub has_focus: bool,
+
+    /// Total number of queued edits across all hosts
+    pub num_queued: usize,
+
+    /// Error that occurred while cancelling an edit
+    pub error: Option<String>,
+}
+
+/// Shows edits that haven't been committed to the server yet, and allows
+/// cancelling them.
+#[derive(Debug, Default)]
+pub struct EditQueue {}
+impl EditQueue {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(
+        self,
+        builder: &mut Builder,
+        connections: &mut github::connections::Connections,
+    ) -> EditQueueResult {
+        let state_id = builder.add_state_id("edit_queue");
+        let state: &mut State = builder.get_state(state_id);
+
+        let mut table_builder = state.table_state.build();
+        let host_style =
+            table_builder.add_style(builder.theme().text(builder.theme_context()).header1);
+        let committing_style =
+            table_builder.add_style(builder.theme().text(builder.theme_context()).inactive);
+        let conflict_style =
+            table_builder.add_style(builder.theme().text(builder.theme_context()).error);
+
+        let mut edits: HashMap<u64, (String, u64)> = HashMap::new();
+        let mut num_queued = 0;
+
+        for (host, client) in connections.all_clients() {
+            let Ok(client) = client else { continue };
+            let queued = client.borrow().queued_edits();
+            if queued.is_empty() {
+                continue;
+            }
+            num_queued += queued.len();
+
+            let top_level = table_builder
+                .add(0, (host.host.clone(), u64::MAX))
+                .styled(0, host.host.clone(), host_style)
+                .id();
+
+            for queued in queued {
+                let item = table_builder.add(top_level, (host.host.clone(), queued.id));
+                let item = if let Some(conflict) = queued.conflict {
+                    item.styled(0, queued.edit.to_string(), conflict_style)
+                        .styled(1, format!("conflict: {conflict}"), conflict_style)
+                } else if queued.committing {
+                    item.styled(0, queued.edit.to_string(), committing_style)
+                        .styled(1, "committing...", committing_style)
+                } else if queued.failures > 0 {
+                    item.raw(0, queued.edit.to_string())
+                        .raw(1, format!("retrying after {} failure(s)", queued.failures))
+                } else if queued.not_before.is_some_and(|t| t > std::time::Instant::now()) {
+                    item.raw(0, queued.edit.to_string()).raw(1, "u to undo")
+                } else {
+                    item.raw(0, queued.edit.to_string())
+                };
+                edits.insert(item.id(), (host.host.clone(), queued.id));
+            }
+        }
+
+        if num_queued == 0 {
+            table_builder.add(0, (String::new(), 0)).raw(0, "No queued edits");
+        }
+
+        let columns = vec![
+            table::Column::new(0, "", Constraint1D::unconstrained()),
+            table::Column::new(1, "", Constraint1D::new(5, 40)),
+        ];
+        let table_result = builder.nest().id(state_id).build(|builder| {
+            table::Table::new(&table_builder.finish())
+                .id("tree")
+                .columns(columns)
+                .build(builder)
+        });
+
+        let mut error = None;
+        if table_result.has_focus {
+            let cancel = builder.on_key_binding(KeyCode::Char('x'), "Cancel the selected edit")
+                || builder.on_key_binding(KeyCode::Delete, "Cancel the selected edit");
+            let force = builder.on_key_binding(
+                KeyCode::Char('f'),
+                "Commit the selected edit despite conflicts",
+            );
+            if cancel || force {
+                if let Some((host, id)) =
+                    table_result.selection.and_then(|item| edits.remove(&item))
+                {
+                    let (result, action) = match connections.client(host) {
+                        Ok(client) if cancel => (client.borrow_mut().cancel_edit(id), "cancel"),
+                        Ok(client) => (client.borrow_mut().force_edit(id), "force"),
+                        Err(err) => (Err(err), "access"),
+                    };
+                    if let Err(err) = result {
+                        error = Some(format!("Failed to {} edit: {}", action, err));
+                    }
+                    builder.need_refresh();
+                }
+            }
+        }
+
+        EditQueueResult {
+            has_focus: table_result.has_focus,
+            num_queued,
+            error,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Identicons: tiny pictures derived from a login, so that authors can be
+//! told apart at a glance in lists and threads.
+//!
+//! Like the default avatars on GitHub, an identicon is a horizontally
+//! mirrored pattern in a color, both taken from a hash of the login. It is
+//! drawn as two braille characters, i.e. a grid of 4x4 dots.
+//!
+//! Once the avatar of a user has been fetched, it is drawn the same way in
+//! place of the identicon, see [`super::avatar`].
+
+use std::fmt::Write;
+
+use blake2::Digest;
+use ratatui::style::{Color, Style};
+use vctuik::{pager::RichPagerSourceBuilder, prelude::*};
+
+use super::avatar;
+use crate::settings::Settings;
+
+type Blake2b128 = blake2::Blake2b<blake2::digest::consts::U16>;
+
+/// Bits of the dots of a braille character, by row and column.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Whether identicons are shown. Braille isn't ASCII, so they are hidden
+/// with `reduced_motion` unless enabled explicitly.
+pub fn enabled() -> bool {
+    let ui = &Settings::get().ui;
+    ui.identicons.unwrap_or(!ui.reduced_motion)
+}
+
+/// The identicon of a login and its color.
+pub fn identicon(login: &str) -> (String, Color) {
+    let hash = Blake2b128::digest(login.as_bytes());
+
+    // The left half of the grid, row by row. Never leave it empty.
+    let pattern = hash[0].max(1);
+    let mut grid = [[false; 4]; 4];
+    for (row, dots) in grid.iter_mut().enumerate() {
+        for col in 0..2 {
+            if pattern & (1 << (2 * row + col)) != 0 {
+                dots[col] = true;
+                dots[3 - col] = true;
+            }
+        }
+    }
+
+    // A color of the 6x6x6 cube of the 256-color palette, without the
+    // darkest and brightest levels so that it is visible on any background.
+    let [red, green, blue] = [hash[1], hash[2], hash[3]].map(|byte| byte % 4 + 1);
+    (braille(grid), Color::Indexed(16 + 36 * red + 6 * green + blue))
+}
+
+/// Two braille characters for a grid of 4x4 dots, given row by row.
+pub fn braille(grid: [[bool; 4]; 4]) -> String {
+    let mut cells = [0x2800; 2];
+    for (row, dots) in grid.iter().enumerate() {
+        for (col, &dot) in dots.iter().enumerate() {
+            if dot {
+                cells[col / 2] |= BRAILLE_DOTS[row][col % 2];
+            }
+        }
+    }
+    cells.iter().map(|&cell| char::from_u32(cell).unwrap()).collect()
+}
+
+/// The picture of a login on a host and its color: its avatar once it has
+/// been fetched, and its identicon until then.
+pub fn picture(host: &str, login: &str) -> (String, Color) {
+    let avatars = Settings::get().ui.avatars.unwrap_or(true);
+    avatars.then(|| avatar::picture(host, login)).flatten().unwrap_or_else(|| identicon(login))
+}
+
+/// `@login`, preceded by the picture if identicons are enabled.
+pub fn mention(host: &str, login: &str) -> String {
+    if enabled() {
+        format!("{} @{login}", picture(host, login).0)
+    } else {
+        format!("@{login}")
+    }
+}
+
+/// Write the picture of a login in its color, followed by a space, if
+/// identicons are enabled. The style must be set again afterwards.
+pub fn write(pager: &mut RichPagerSourceBuilder, host: &str, login: &str) -> Result<()> {
+    if enabled() {
+        let (text, color) = picture(host, login);
+        let style = pager.register_style(Style::new().fg(color));
+        pager.set_style(style);
+        write!(pager, "{text} ")?;
+    }
+    Ok(())
+}
@@ -1,32 +1,103 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use vctools_utils::time;
 
 use vctuik::{
-    layout::Constraint1D, prelude::*, state::Builder, table::{self, simple_table}
+    event::KeyCode, layout::Constraint1D, prelude::*, state::Builder, table::{self, simple_table}
+};
+
+use crate::{
+    github,
+    risk::RiskAssessment,
+    settings::{ColumnKind, Priority, Settings, TableView},
+    triage::TriageState,
+    ApiRepository,
 };
 
-use crate::github;
+use super::columns::{self, Row};
 
+/// State of the inbox that can be kept by the caller, so that it survives
+/// frames in which the inbox isn't shown.
 #[derive(Debug, Default)]
-struct State {
+pub struct InboxState {
     table_state: simple_table::SourceState<String>,
+    table: table::TableState,
+
+    /// Marked notification threads as (host, thread ID)
+    marked: HashSet<(String, String)>,
+
+    /// Notification threads per host as (thread ID, last update) that were
+    /// there in the last frame, to announce new notifications.
+    known: HashMap<String, HashMap<String, String>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct InboxResult {
     /// Whether focus is on this widget
     pub has_focus: bool,
 
     /// Host and notification thread of the current selection
     pub selection: Option<(String, github::api::NotificationThread)>,
+
+    /// The repository of the current selection, if a repository row is
+    /// selected
+    pub repository: Option<ApiRepository>,
+
+    /// Host and notification thread of all marked notifications
+    pub marked: Vec<(String, github::api::NotificationThread)>,
+}
+impl InboxResult {
+    /// The marked notifications, or the selected one if none are marked.
+    pub fn targets(&self) -> Vec<(String, github::api::NotificationThread)> {
+        if self.marked.is_empty() {
+            self.selection.iter().cloned().collect()
+        } else {
+            self.marked.clone()
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct Inbox {}
-impl Inbox {
+#[derive(Debug, Default)]
+pub struct Inbox<'build> {
+    triage: Option<&'build TriageState>,
+    repository: Option<&'build ApiRepository>,
+    state: Option<&'build mut InboxState>,
+}
+impl<'build> Inbox<'build> {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            triage: None,
+            repository: None,
+            state: None,
+        }
+    }
+
+    /// Hide snoozed notifications and muted repositories.
+    pub fn triage(self, triage: &'build TriageState) -> Self {
+        Self {
+            triage: Some(triage),
+            ..self
+        }
+    }
+
+    /// Only show notifications of the given repository.
+    pub fn repository(self, repository: &'build ApiRepository) -> Self {
+        Self {
+            repository: Some(repository),
+            ..self
+        }
+    }
+
+    pub fn state(self, state: &'build mut InboxState) -> Self {
+        Self {
+            state: Some(state),
+            ..self
+        }
     }
 
     pub fn build(
@@ -35,7 +106,10 @@ impl Inbox {
         connections: &mut github::connections::Connections,
     ) -> InboxResult {
         let state_id = builder.add_state_id("inbox");
-        let state: &mut State = builder.get_state(state_id);
+        let state: &mut InboxState = match self.state {
+            Some(state) => state,
+            None => builder.get_state(state_id),
+        };
 
         let mut table_builder = state.table_state.build();
 
@@ -43,18 +117,38 @@ impl Inbox {
             table_builder.add_style(builder.theme().text(builder.theme_context()).header1);
         let repo_style =
             table_builder.add_style(builder.theme().text(builder.theme_context()).header2);
+        let marked_style =
+            table_builder.add_style(builder.theme().text(builder.theme_context()).highlight);
+        let inactive_style =
+            table_builder.add_style(builder.theme().text(builder.theme_context()).inactive);
+        let marked_symbol = builder.theme().symbols.marked;
         let mut threads: HashMap<u64, (&_, github::api::NotificationThread)> = HashMap::new();
+        let mut repositories: HashMap<u64, ApiRepository> = HashMap::new();
+        let settings = Settings::get();
+        let kinds = settings.columns.get(TableView::Inbox);
+        let priority = |n: &github::api::NotificationThread| {
+            settings.priority(&n.repository.owner.login, &n.repository.name)
+        };
+
+        // Last updates are shown relative to now, so rebuild when the first
+        // of them goes stale.
+        let now = time::unix_now();
+        let mut stale_after = Duration::MAX;
+
+        // Whether the notifications of some host are still being received.
+        let mut partial = false;
 
         for (host, client) in connections.all_clients() {
             let top_level = table_builder
                 .add(0, host.host.clone())
                 .styled(0, &host.host, host_style)
+                .group()
                 .id();
 
             let result =
                 client.and_then(|client| {
                     let mut client = client.borrow_mut();
-                    let notifications = client.access().notifications().ok()?;
+                    let notifications = client.access().partial().notifications().ok()?;
                     Ok((client, notifications))
                 });
 
@@ -65,26 +159,104 @@ impl Inbox {
                 continue;
             };
 
+            // Announce notifications that are new or have new activity in
+            // the screen reader mode, but not the ones that are there when the
+            // inbox is first loaded, i.e. once the list is complete.
+            let first_load = !state.known.contains_key(&host.host);
+            let is_partial = notifications.is_partial();
+            partial |= is_partial;
+            if !is_partial {
+                let known = state.known.entry(host.host.clone()).or_default();
+                let previous = std::mem::take(known);
+                for n in &notifications {
+                    if !first_load
+                        && previous.get(&n.id) != Some(&n.updated_at)
+                        && !self.triage.is_some_and(|triage| triage.is_hidden(&host.host, n))
+                        && priority(n) != Priority::Muted
+                    {
+                        builder.announce(format!(
+                            "New notification in {}/{}: {}",
+                            n.repository.owner.login, n.repository.name, n.subject.title
+                        ));
+                    }
+                    known.insert(n.id.clone(), n.updated_at.clone());
+                }
+            }
+
             let prefetch = client.prefetch();
-            let mut notifications =
+            let notifications =
                 notifications
-                    .into_iter()
+                    .iter()
+                    .filter(|n| !self.triage.is_some_and(|triage| triage.is_hidden(&host.host, n)))
+                    .filter(|n| priority(n) != Priority::Muted)
+                    .filter(|n| {
+                        self.repository.is_none_or(|repo| {
+                            repo.host == host.host
+                                && repo.owner == n.repository.owner.login
+                                && repo.name == n.repository.name
+                        })
+                    })
                     .map(|n| {
                         let org = &n.repository.owner.login;
                         let gh_repo = &n.repository.name;
+                        // Pull requests in low-priority repositories are only
+                        // loaded when the notification is selected.
                         let pull =
-                            n.pull_number().and_then(|id| {
-                                prefetch
-                                    .pull(org, gh_repo, id)
-                                    .ok()
-                                    .ok()
-                            });
+                            n.pull_number()
+                                .filter(|_| priority(n) > Priority::Low)
+                                .and_then(|id| prefetch.pull(org, gh_repo, id).ok().ok());
                         (n, pull)
                     })
                     .collect::<Vec<_>>();
 
+            let badges =
+                notifications
+                    .iter()
+                    .map(|(n, pull)| {
+                        let pull = pull.as_ref()?;
+                        let org = &n.repository.owner.login;
+                        let gh_repo = &n.repository.name;
+                        let settings = settings.risk(org, gh_repo);
+                        let risk = match prefetch.pull_files(org, gh_repo, pull.number) {
+                            github::Response::Ok(files) => {
+                                RiskAssessment::new(settings, Some(pull), &files)
+                            }
+                            _ => RiskAssessment::from_totals(pull)?,
+                        };
+                        Some(risk.badges(settings).join(" "))
+                    })
+                    .collect::<Vec<_>>();
+
+            let ci = notifications
+                .iter()
+                .map(|(n, pull)| {
+                    let pull = pull.as_ref().filter(|_| kinds.contains(&ColumnKind::Ci))?;
+                    let (org, gh_repo) = (&n.repository.owner.login, &n.repository.name);
+                    columns::ci_state(&prefetch, org, gh_repo, &pull.head.sha)
+                })
+                .collect::<Vec<_>>();
+
+            // Warm the cache for the pull requests that are likely to be
+            // opened first, so that their reviews show up instantly.
+            if first_load && !is_partial {
+                let mut pulls: Vec<_> = notifications
+                    .iter()
+                    .filter_map(|(n, _)| Some((priority(n), n, n.pull_number()?)))
+                    .filter(|(priority, ..)| *priority > Priority::Low)
+                    .collect();
+                pulls.sort_by_key(|(priority, ..)| std::cmp::Reverse(*priority));
+                let intents = pulls.into_iter().map(|(_, n, number)| {
+                    github::prefetch::Intent::pull_request(
+                        &n.repository.owner.login,
+                        &n.repository.name,
+                        number,
+                    )
+                });
+                client.prefetch_within_budget(intents, &settings.prefetch);
+            }
+
             // We create table entries for repositories that have notifications
-            // in alphabetical order.
+            // in order of priority, then alphabetically.
             //
             // Map API repo IDs to repo table item IDs.
             let repo_ids = {
@@ -98,7 +270,10 @@ impl Inbox {
                         });
                 }
                 repos.sort_by(|a, b| {
-                    let ord = a.owner.login.cmp(&b.owner.login);
+                    let ord = settings
+                        .priority(&b.owner.login, &b.name)
+                        .cmp(&settings.priority(&a.owner.login, &a.name))
+                        .then_with(|| a.owner.login.cmp(&b.owner.login));
                     if ord == std::cmp::Ordering::Equal {
                         a.name.cmp(&b.name)
                     } else {
@@ -117,7 +292,16 @@ impl Inbox {
                                 ),
                                 repo_style,
                             )
+                            .group()
                             .id();
+                    repositories.insert(
+                        id,
+                        ApiRepository::new(
+                            host.host.clone(),
+                            repo.owner.login.clone(),
+                            repo.name.clone(),
+                        ),
+                    );
                     *repo_ids.get_mut(&repo.id).unwrap() = id;
                 }
                 repo_ids
@@ -179,12 +363,29 @@ impl Inbox {
                         });
 
                     // Create the table item for this notification.
-                    let item =
-                        table_builder
-                        .add(parent_id, notification.id.clone())
-                        .raw(0, notification.subject.title.clone())
-                        .raw(1, notification.updated_at.clone());
-                    let item_id = item.id();
+                    let is_marked =
+                        state.marked.contains(&(host.host.clone(), notification.id.clone()));
+                    let item = table_builder.add(parent_id, notification.id.clone());
+                    let item = if is_marked {
+                        let title = format!("{marked_symbol} {}", notification.subject.title);
+                        item.styled(0, title, marked_style)
+                    } else {
+                        item.raw(0, notification.subject.title.clone())
+                    };
+                    let item = match notification.html_url() {
+                        Some(url) => item.link(0, url),
+                        None => item,
+                    };
+                    if let Some(updated_at) = notification.updated_at() {
+                        let timestamp = updated_at.timestamp();
+                        stale_after = stale_after.min(time::stale_after(timestamp, now));
+                    }
+                    let mut row = pull.as_deref().map(Row::from_pull).unwrap_or_default();
+                    row.host = &host.host;
+                    row.updated_at = Some(&notification.updated_at);
+                    row.risk = badges[notification_idx].clone();
+                    row.ci = ci[notification_idx].clone();
+                    let item_id = row.add_cells(item, kinds, now).id();
 
                     if let Some(pull) = pull {
                         let repo_id = pull.head.repo.id;
@@ -202,30 +403,93 @@ impl Inbox {
             }
 
             for (notification_idx, item_id) in item_ids {
-                threads.insert(item_id, (host, std::mem::take(&mut notifications[notification_idx].0)));
+                threads.insert(item_id, (host, notifications[notification_idx].0.clone()));
+            }
+
+            if is_partial {
+                table_builder
+                    .add(top_level, "loading".into())
+                    .styled(0, "Loading more notifications...", inactive_style);
             }
         }
 
-        let columns = vec![
-            table::Column::new(0, "", Constraint1D::unconstrained()),
-            table::Column::new(1, "Last Update", Constraint1D::new(5, 20)),
-        ];
+        builder.refresh_after(stale_after);
+
+        let mut columns = vec![table::Column::new(0, "", Constraint1D::unconstrained())];
+        columns.extend(columns::table_columns(kinds));
         let table_result = builder
             .nest()
             .id(state_id)
             .build(|builder| {
                 table::Table::new(&table_builder.finish())
                     .id("tree")
+                    .state(&mut state.table)
                     .columns(columns)
+                    .filter_column(0)
                     .build(builder)
             });
 
+        // Forget marks of notifications that have gone away, e.g. because
+        // they were marked as done.
+        if !partial {
+            state.marked.retain(|(host, id)| {
+                threads.values().any(|(h, thread)| h.host == *host && thread.id == *id)
+            });
+        }
+
+        let repository = table_result.selection.and_then(|id| repositories.remove(&id));
+        let selection = table_result
+            .selection
+            .and_then(|id| threads.remove(&id))
+            .map(|(host, thread)| (host.host.clone(), thread));
+
+        if table_result.has_focus {
+            if builder.on_key_binding(KeyCode::Char(' '), "Mark / unmark for a batch action") {
+                if let Some((host, thread)) = &selection {
+                    let key = (host.clone(), thread.id.clone());
+                    if !state.marked.remove(&key) {
+                        state.marked.insert(key);
+                    }
+                    builder.need_refresh();
+                }
+            } else if !state.marked.is_empty()
+                && builder.on_key_binding(KeyCode::Esc, "Clear all marks")
+            {
+                state.marked.clear();
+                builder.need_refresh();
+            }
+        }
+
+        let mut marked: Vec<_> = threads
+            .into_values()
+            .filter(|(host, thread)| state.marked.contains(&(host.host.clone(), thread.id.clone())))
+            .map(|(host, thread)| (host.host.clone(), thread))
+            .collect();
+        if let Some((host, thread)) = &selection {
+            if state.marked.contains(&(host.clone(), thread.id.clone())) {
+                marked.push((host.clone(), thread.clone()));
+            }
+        }
+        marked.sort_by(|(a_host, a), (b_host, b)| {
+            a_host.cmp(b_host).then_with(|| b.updated_at.cmp(&a.updated_at))
+        });
+
+        // The selected pull request is likely to be opened next.
+        if let Some((host, thread)) = &selection {
+            if let (Some(number), Ok(client)) = (thread.pull_number(), connections.client(host)) {
+                client.borrow_mut().prefetch_intents([github::prefetch::Intent::pull_request(
+                    &thread.repository.owner.login,
+                    &thread.repository.name,
+                    number,
+                )]);
+            }
+        }
+
         InboxResult {
             has_focus: table_result.has_focus,
-            selection: table_result
-                .selection
-                .and_then(|id| threads.remove(&id))
-                .map(|(host, thread)| (host.host.clone(), thread)),
+            selection,
+            repository,
+            marked,
         }
     }
 }
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! URLs of the web interface of a forge, for the hyperlinks of pull requests,
+//! users, commits, and files.
+//!
+//! Everything is derived from the URL of a pull request or repository as the
+//! API reports it, so that it also works for GitHub Enterprise Server.
+
+/// URL of the repository of the pull request with the given URL.
+pub fn repository(pull_url: &str) -> Option<&str> {
+    pull_url.rsplitn(3, '/').nth(2).filter(|url| !url.is_empty())
+}
+
+/// URL of the profile of a user, given the URL of a repository on the same
+/// host.
+pub fn user(repo_url: &str, login: &str) -> Option<String> {
+    let host_url = repo_url.rsplitn(3, '/').nth(2).filter(|url| !url.is_empty())?;
+    Some(format!("{host_url}/{login}"))
+}
+
+pub fn commit(repo_url: &str, sha: &str) -> String {
+    format!("{repo_url}/commit/{sha}")
+}
+
+pub fn file(repo_url: &str, sha: &str, path: &str) -> String {
+    format!("{repo_url}/blob/{sha}/{path}")
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Rendering of Markdown text (descriptions, release notes) into a pager.
+//!
+//! Only the block structure that matters for reading in a terminal is
+//! interpreted: headings, lists, quotes, code blocks and rules. Inline code,
+//! strong emphasis and links are shown without their markup. HTML comments,
+//! which often hold templates, are dropped.
+
+use std::fmt::Write;
+
+use vctuik::{pager::RichPagerSourceBuilder, prelude::*, theme::TextStyle};
+
+/// Write inline text, highlighting code spans and strong emphasis and
+/// replacing links by their text.
+fn write_inline(pager: &mut RichPagerSourceBuilder, text: &str, style: TextStyle) -> Result<()> {
+    let mut rest = text;
+    pager.set_theme_style(style);
+    while !rest.is_empty() {
+        let Some(pos) = rest.find(['`', '*', '_', '[']) else {
+            pager.write_str(rest)?;
+            break;
+        };
+        pager.write_str(&rest[..pos])?;
+        rest = &rest[pos..];
+
+        let delimiter = if rest.starts_with('`') {
+            "`"
+        } else if rest.starts_with("**") {
+            "**"
+        } else if rest.starts_with("__") {
+            "__"
+        } else {
+            ""
+        };
+        if !delimiter.is_empty() {
+            let inner = &rest[delimiter.len()..];
+            if let Some(end) = inner.find(delimiter).filter(|&end| end > 0) {
+                pager.set_theme_style(TextStyle::Highlight);
+                pager.write_str(&inner[..end])?;
+                pager.set_theme_style(style);
+                rest = &inner[end + delimiter.len()..];
+                continue;
+            }
+        }
+
+        if rest.starts_with('[') {
+            let link = rest[1..]
+                .split_once("](")
+                .and_then(|(label, tail)| Some((label, tail.find(')')?)));
+            if let Some((label, end)) = link.filter(|(label, _)| !label.contains(']')) {
+                pager.write_str(label)?;
+                rest = &rest[1 + label.len() + 2 + end + 1..];
+                continue;
+            }
+        }
+
+        pager.write_str(&rest[..1])?;
+        rest = &rest[1..];
+    }
+    Ok(())
+}
+
+/// Write Markdown text, followed by a newline.
+pub fn write_markdown(pager: &mut RichPagerSourceBuilder, text: &str) -> Result<()> {
+    let mut in_code = false;
+    let mut in_comment = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if in_comment {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            pager.set_theme_style(TextStyle::Inactive);
+            writeln!(pager, "    {line}")?;
+            continue;
+        }
+        if trimmed.starts_with("<!--") {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        let heading = trimmed.chars().take_while(|&ch| ch == '#').count();
+        if (1..=6).contains(&heading) && trimmed[heading..].starts_with(' ') {
+            let style = match heading {
+                1 => TextStyle::Header0,
+                2 => TextStyle::Header1,
+                _ => TextStyle::Header2,
+            };
+            write_inline(pager, trimmed[heading..].trim(), style)?;
+        } else if ["---", "***", "___"].contains(&trimmed.trim_end()) {
+            pager.set_theme_style(TextStyle::Inactive);
+            pager.write_str(&"─".repeat(40))?;
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            pager.set_theme_style(TextStyle::Inactive);
+            pager.write_str("│ ")?;
+            write_inline(pager, quote.trim_start(), TextStyle::Inactive)?;
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|p| trimmed.strip_prefix(p)) {
+            pager.set_theme_style(TextStyle::Normal);
+            write!(pager, "{:indent$}• ", "")?;
+            write_inline(pager, item, TextStyle::Normal)?;
+        } else {
+            pager.set_theme_style(TextStyle::Normal);
+            write!(pager, "{:indent$}", "")?;
+            write_inline(pager, trimmed, TextStyle::Normal)?;
+        }
+        writeln!(pager)?;
+    }
+
+    pager.set_theme_style(TextStyle::Normal);
+    Ok(())
+}
@@ -1,9 +1,48 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+mod accounts;
 pub mod actions;
+mod author;
+pub mod avatar;
+mod bookmarks;
+mod columns;
+mod command_bar;
+mod comment_composer;
+mod contributor_stats;
 mod diff_pager;
+mod edit_queue;
+mod identicon;
 mod inbox;
+mod links;
+mod markdown;
+pub mod navigation;
+mod perf;
+pub mod plugin;
+mod projects;
+mod releases;
+mod repo_insights;
 mod review;
+mod status;
+mod teams;
+mod todos;
+mod traffic;
+mod user_card;
 
-pub use inbox::{Inbox, InboxResult};
-pub use review::Review;
+pub use accounts::Accounts;
+pub use author::{MyPulls, ReviewThreads};
+pub use columns::{ColumnsAction, ColumnsDialog, ColumnsDraft};
+pub use command_bar::CommandBar;
+pub use comment_composer::{CommentComposer, CommentDraft, ComposerAction};
+pub use contributor_stats::ContributorStats;
+pub use edit_queue::{EditQueue, EditQueueResult};
+pub use inbox::{Inbox, InboxResult, InboxState};
+pub use perf::PerfHud;
+pub use projects::Projects;
+pub use releases::Releases;
+pub use repo_insights::RepositoryInsights;
+pub use review::{Compare, Review};
+pub use status::ForgeStatus;
+pub use teams::{Teams, TeamsResult};
+pub use todos::{Todos, TodosResult};
+pub use traffic::TrafficInspector;
+pub use user_card::UserCard;
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::prelude::*;
+use serde::{Deserialize, Serialize};
+use vctuik::{event::KeyCode, state::Builder};
+
+use crate::{github::api::NotificationThread, ApiRepository};
+
+/// A place that the user can navigate to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Location {
+    Inbox,
+    Repository(ApiRepository),
+    PullRequest(ApiRepository, u64),
+
+    /// Tags and releases of a repository.
+    Releases(ApiRepository),
+
+    /// Milestones and project boards of a repository.
+    Projects(ApiRepository),
+
+    /// Statistics of the contributions to a repository.
+    Stats(ApiRepository),
+
+    /// The user's own open pull requests and their review threads.
+    Authored,
+
+    /// A notification thread whose subject is not a pull request.
+    Thread {
+        host: String,
+        id: String,
+        title: String,
+    },
+}
+impl Location {
+    /// The location of a notification's subject.
+    pub fn for_notification(host: &str, thread: &NotificationThread) -> Self {
+        let repo = ApiRepository::new(
+            host.to_string(),
+            thread.repository.owner.login.clone(),
+            thread.repository.name.clone(),
+        );
+        match thread.pull_number() {
+            Some(number) => Location::PullRequest(repo, number),
+            None => Location::Thread {
+                host: host.to_string(),
+                id: thread.id.clone(),
+                title: thread.subject.title.clone(),
+            },
+        }
+    }
+
+    /// Short description for the breadcrumbs.
+    pub fn label(&self) -> String {
+        match self {
+            Location::Inbox => "Inbox".into(),
+            Location::Repository(repo) => format!("{}/{}", repo.owner, repo.name),
+            Location::PullRequest(_, number) => format!("#{number}"),
+            Location::Releases(_) => "Releases".into(),
+            Location::Projects(_) => "Projects".into(),
+            Location::Stats(_) => "Statistics".into(),
+            Location::Authored => "My pull requests".into(),
+            Location::Thread { title, .. } => title.clone(),
+        }
+    }
+}
+
+/// Stack of locations, from the root (e.g. the inbox) to the location that is
+/// currently shown.
+#[derive(Debug)]
+pub struct NavigationStack {
+    locations: Vec<Location>,
+}
+impl NavigationStack {
+    pub fn new(root: Location) -> Self {
+        Self {
+            locations: vec![root],
+        }
+    }
+
+    pub fn current(&self) -> &Location {
+        self.locations.last().unwrap()
+    }
+
+    pub fn locations(&self) -> &[Location] {
+        &self.locations
+    }
+
+    /// Navigate to a new location. Pushing the current location again has
+    /// no effect.
+    pub fn push(&mut self, location: Location) {
+        if *self.current() != location {
+            self.locations.push(location);
+        }
+    }
+
+    /// Return to the previous location. The root is never popped.
+    ///
+    /// Returns true if there was a location to go back to.
+    pub fn back(&mut self) -> bool {
+        if self.locations.len() > 1 {
+            self.locations.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Go back on Backspace or Esc, unless the key was already handled by a
+    /// widget.
+    ///
+    /// Returns true if the location changed.
+    pub fn handle_back(&mut self, builder: &mut Builder) -> bool {
+        if self.locations.len() > 1
+            && (builder.on_key_binding(KeyCode::Backspace, "Go back")
+                || builder.on_key_binding(KeyCode::Esc, "Go back"))
+        {
+            self.back();
+            builder.need_refresh();
+            return true;
+        }
+        false
+    }
+}
+
+/// Single line showing the path through the navigation stack.
+#[derive(Debug)]
+pub struct Breadcrumbs<'build> {
+    stack: &'build NavigationStack,
+}
+impl<'build> Breadcrumbs<'build> {
+    pub fn new(stack: &'build NavigationStack) -> Self {
+        Self { stack }
+    }
+
+    pub fn build(self, builder: &mut Builder) {
+        let area = builder.take_lines_fixed(1);
+        let text = builder.theme().text(builder.theme_context());
+
+        let mut spans = Vec::new();
+        let last = self.stack.locations().len() - 1;
+        for (idx, location) in self.stack.locations().iter().enumerate() {
+            if idx > 0 {
+                spans.push(Span::styled(" › ", text.inactive));
+            }
+            let style = if idx == last {
+                text.highlight
+            } else {
+                text.normal
+            };
+            spans.push(Span::styled(location.label(), style));
+        }
+        if last > 0 {
+            spans.push(Span::styled("  (Backspace: back)", text.inactive));
+        }
+
+        builder.frame().render_widget(Line::from(spans), area);
+    }
+}
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{collections::VecDeque, time::Duration};
+
+use diff_modulo_base::diff;
+use ratatui::text::{Line, Text};
+use vctuik::{label::add_text_label, state::Builder};
+
+/// Number of recent frames over which the maximum frame time is shown.
+const FRAME_HISTORY: usize = 100;
+
+#[derive(Debug, Default)]
+struct State {
+    frame_times: VecDeque<Duration>,
+}
+
+fn kib(bytes: usize) -> String {
+    format!("{} KiB", bytes.div_ceil(1024))
+}
+
+/// Shows how long frames take and how much memory the largest diff needed,
+/// to find out why a review of a large pull request is slow.
+///
+/// This should be built late in the frame, so that the frame time covers most
+/// of the work.
+#[derive(Debug, Default)]
+pub struct PerfHud {}
+impl PerfHud {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(self, builder: &mut Builder) {
+        let elapsed = builder.start_frame().elapsed();
+        let state_id = builder.add_state_id("perf");
+        let state: &mut State = builder.get_state(state_id);
+        if state.frame_times.len() >= FRAME_HISTORY {
+            state.frame_times.pop_front();
+        }
+        state.frame_times.push_back(elapsed);
+        let max = state.frame_times.iter().max().copied().unwrap_or_default();
+        let num_frames = state.frame_times.len();
+
+        let usage = diff::peak_memory_usage();
+        let text = builder.theme().text(builder.theme_context());
+        let lines = vec![
+            Line::styled(
+                format!(
+                    "Frame time: {} ms (max. {} ms over the last {num_frames} frames)",
+                    elapsed.as_millis(),
+                    max.as_millis(),
+                ),
+                text.normal,
+            ),
+            Line::styled(
+                format!(
+                    "Largest diff buffer: {} allocated for {} of contents, \
+                     {} deduplicated by interning {} lines or texts",
+                    kib(usage.allocated),
+                    kib(usage.bytes),
+                    kib(usage.deduplicated),
+                    usage.interned,
+                ),
+                text.normal,
+            ),
+        ];
+        add_text_label(builder, Text::from(lines));
+    }
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Custom panes that are compiled into the tools.
+//!
+//! A downstream build implements `PanePlugin` for its pane (e.g. the status of
+//! deployments) and calls `register_pane` before the UI starts, typically at
+//! the start of `main`. git-inbox shows all registered panes, each in its own
+//! section, so they take part in the layout, focus handling, and theming like
+//! the built-in panes. Commands that no built-in command matches are offered
+//! to the panes.
+
+use std::sync::Mutex;
+
+use vctools_utils::prelude::*;
+use vctuik::{section::with_section, state::Builder};
+
+use crate::{
+    github::{api::NotificationThread, connections::Connections},
+    gitservice::GitService,
+    tui::navigation::Location,
+};
+
+/// Application state that is available to panes.
+pub struct PaneContext<'ctx> {
+    pub connections: &'ctx mut Connections,
+    pub git_service: &'ctx GitService,
+    pub location: &'ctx Location,
+
+    /// Host and notification thread that are selected in the inbox
+    pub selection: Option<&'ctx (String, NotificationThread)>,
+}
+
+pub trait PanePlugin {
+    /// Title of the pane's section.
+    fn title(&self) -> &str;
+
+    /// Whether the pane should be shown at all in the current context.
+    fn is_visible(&self, _ctx: &PaneContext) -> bool {
+        true
+    }
+
+    /// Build the contents of the pane. This is called once per frame while the
+    /// pane is visible.
+    fn build(&mut self, builder: &mut Builder, ctx: &mut PaneContext);
+
+    /// Commands that the pane handles, as (usage, description) pairs.
+    fn commands(&self) -> &[(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Handle a command entered after `:`. Returns `None` if the command is not
+    /// handled by this pane.
+    fn handle_command(&mut self, _command: &str, _ctx: &mut PaneContext) -> Option<Result<()>> {
+        None
+    }
+}
+
+pub type PaneFactory = fn() -> Box<dyn PanePlugin>;
+
+lazy_static::lazy_static! {
+    static ref REGISTERED_PANES: Mutex<Vec<PaneFactory>> = Mutex::new(Vec::new());
+}
+
+/// Register a pane for all `PaneRegistry`s that are created afterwards.
+pub fn register_pane(factory: PaneFactory) {
+    REGISTERED_PANES.lock().unwrap().push(factory);
+}
+
+/// The panes used by an application.
+#[derive(Default)]
+pub struct PaneRegistry {
+    panes: Vec<Box<dyn PanePlugin>>,
+}
+impl PaneRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an instance of every pane registered via `register_pane`.
+    pub fn from_registered() -> Self {
+        let panes = REGISTERED_PANES
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|factory| factory())
+            .collect();
+        Self { panes }
+    }
+
+    pub fn add(&mut self, pane: Box<dyn PanePlugin>) {
+        self.panes.push(pane);
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = &(&'static str, &'static str)> {
+        self.panes.iter().flat_map(|pane| pane.commands())
+    }
+
+    /// Build a section for each visible pane.
+    pub fn build(&mut self, builder: &mut Builder, ctx: &mut PaneContext) {
+        for pane in &mut self.panes {
+            if pane.is_visible(ctx) {
+                let title = pane.title().to_string();
+                with_section(builder, title, |builder| pane.build(builder, ctx));
+            }
+        }
+    }
+
+    /// Offer a command to the panes in order of registration.
+    pub fn handle_command(&mut self, command: &str, ctx: &mut PaneContext) -> Option<Result<()>> {
+        self.panes
+            .iter_mut()
+            .find_map(|pane| pane.handle_command(command, ctx))
+    }
+}
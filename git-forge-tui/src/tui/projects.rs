@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
+use vctuik::{
+    event::KeyCode,
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, async_source::AsyncTableSource, simple_table},
+};
+
+use crate::{
+    github::{
+        self, api,
+        edit::{Edit, MilestoneSet, ProjectItemMove},
+    },
+    ApiRepository,
+};
+
+/// Items of the tree whose children are loaded once they are expanded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Request {
+    /// The issues of a milestone, or of those without a milestone.
+    Milestone(Option<u64>),
+
+    /// The items of a project, by its node ID.
+    Project(String),
+}
+
+/// Items of the tree that need extra handling.
+#[derive(Debug, Default)]
+struct Items {
+    requests: HashMap<u64, Request>,
+    loading: Vec<u64>,
+    failed: Vec<(u64, String)>,
+
+    /// Edits that move a card to the previous and the next column.
+    moves: HashMap<u64, [Option<Edit>; 2]>,
+}
+impl Items {
+    /// Remember the edits that move a card from the column with the given
+    /// index to its neighbors.
+    fn add_moves<T>(
+        &mut self,
+        item_id: u64,
+        columns: &[T],
+        idx: usize,
+        edit: impl Fn(&T, &T) -> Edit,
+    ) {
+        let prev = idx.checked_sub(1).map(|prev| edit(&columns[idx], &columns[prev]));
+        let next = columns.get(idx + 1).map(|next| edit(&columns[idx], next));
+        self.moves.insert(item_id, [prev, next]);
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<String>,
+
+    /// Milestones and projects whose items have been asked for by expanding
+    /// them.
+    requested: HashSet<Request>,
+}
+
+/// Lists the milestones and the projects of a repository, with their issues
+/// and pull requests grouped by milestone or by the columns of the project
+/// board, respectively.
+///
+/// The selected card can be moved to the previous or next milestone or
+/// column with `<` and `>`. The items of a milestone or project are only
+/// loaded once it is expanded.
+pub struct Projects<'build> {
+    repository: &'build ApiRepository,
+}
+impl<'build> Projects<'build> {
+    pub fn new(repository: &'build ApiRepository) -> Self {
+        Self { repository }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let repo = self.repository;
+        let state_id = builder.add_state_id("projects");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let board_style = table_builder.add_style(text.header1);
+        let column_style = table_builder.add_style(text.header2);
+        let error_style = table_builder.add_style(text.error);
+        let inactive_style = table_builder.add_style(text.inactive);
+
+        let mut items = Items::default();
+        match connections.client(&repo.host) {
+            Err(err) => {
+                table_builder.add(0, "error".into()).styled(0, err.to_string(), error_style);
+            }
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let client = client.access();
+
+                let milestones_id = table_builder
+                    .add(0, "milestones".into())
+                    .styled(0, "Milestones", board_style)
+                    .group()
+                    .id();
+                match client.milestones(&repo.owner, &repo.name).ok_or_pending() {
+                    Ok(Some(milestones)) => add_milestones(
+                        &mut table_builder,
+                        &mut items,
+                        &client,
+                        repo,
+                        &state.requested,
+                        &milestones,
+                        milestones_id,
+                        column_style,
+                    ),
+                    Ok(None) => items.loading.push(milestones_id),
+                    Err(err) => items.failed.push((milestones_id, err.into_owned())),
+                }
+
+                let projects = client.projects(&repo.owner, &repo.name).ok_or_pending();
+                let projects: &[api::Project] = match &projects {
+                    Ok(Some(projects)) => projects,
+                    Ok(None) => {
+                        table_builder
+                            .add(0, "loading".into())
+                            .styled(0, "Loading projects...", inactive_style);
+                        &[]
+                    }
+                    Err(err) => {
+                        table_builder.add(0, "error".into()).styled(0, err.clone(), error_style);
+                        &[]
+                    }
+                };
+                for project in projects.iter().filter(|project| !project.closed) {
+                    let request = Request::Project(project.id.clone());
+                    let response = if state.requested.contains(&request) {
+                        client.project_board(&project.id).ok_or_pending()
+                    } else {
+                        Ok(None)
+                    };
+                    let project_id = table_builder
+                        .add(0, format!("project/{}", project.id))
+                        .styled(0, project.title.clone(), board_style)
+                        .raw(2, format!("project #{}", project.number))
+                        .group()
+                        .id();
+                    items.requests.insert(project_id, request);
+
+                    let board = match response {
+                        Ok(Some(board)) => board,
+                        Ok(None) => {
+                            items.loading.push(project_id);
+                            continue;
+                        }
+                        Err(err) => {
+                            items.failed.push((project_id, err.into_owned()));
+                            continue;
+                        }
+                    };
+                    let style = column_style;
+                    add_board(&mut table_builder, &mut items, project, &board, project_id, style);
+                }
+            }
+        }
+
+        let columns = vec![
+            table::Column::new(0, "", Constraint1D::unconstrained()),
+            table::Column::new(1, "Item", Constraint1D::new(8, 40)),
+            table::Column::new(2, "", Constraint1D::new(8, 16)),
+        ];
+
+        let mut expanded = Vec::new();
+        let table_result = builder.nest().id(state_id).build(|builder| {
+            let mut source = AsyncTableSource::new(table_builder.finish())
+                .on_request(|item_id| expanded.push(item_id));
+            for item_id in items.loading {
+                source.loading(item_id);
+            }
+            for (item_id, message) in items.failed {
+                source.failed(item_id, message);
+            }
+            table::Table::new(&source)
+                .id("tree")
+                .columns(columns)
+                .filter_column(0)
+                .build(builder)
+        });
+
+        // Start loading the items of milestones and projects that were
+        // expanded.
+        for item_id in expanded {
+            if let Some(request) = items.requests.get(&item_id) {
+                if state.requested.insert(request.clone()) {
+                    builder.need_refresh();
+                }
+            }
+        }
+
+        if table_result.has_focus {
+            let moves = table_result.selection.and_then(|item_id| items.moves.remove(&item_id));
+            let [prev, next] = moves.unwrap_or_default();
+            let edit = if builder.on_key_binding(KeyCode::Char('<'), "Move to the previous column")
+            {
+                prev
+            } else if builder.on_key_binding(KeyCode::Char('>'), "Move to the next column") {
+                next
+            } else {
+                None
+            };
+            if let Some(edit) = edit {
+                if let Err(err) = connections.edit(&repo.host, edit, None) {
+                    warn!("{err}");
+                }
+                builder.need_refresh();
+            }
+        }
+    }
+}
+
+/// Add the milestones of the repository as columns, with the open issues and
+/// pull requests of those that were requested as their cards.
+#[allow(clippy::too_many_arguments)]
+fn add_milestones(
+    table_builder: &mut simple_table::SourceBuilder<'_, '_, String>,
+    items: &mut Items,
+    client: &github::ClientRef,
+    repo: &ApiRepository,
+    requested: &HashSet<Request>,
+    milestones: &[api::Milestone],
+    parent: u64,
+    column_style: simple_table::StyleId,
+) {
+    // Issues without a milestone come first, like items without a status on
+    // project boards.
+    let columns: Vec<Option<&api::Milestone>> =
+        std::iter::once(None).chain(milestones.iter().map(Some)).collect();
+
+    for (idx, column) in columns.iter().enumerate() {
+        let number = column.map(|milestone| milestone.number);
+        let (label, due) = match column {
+            Some(milestone) => (
+                format!("{} ({})", milestone.title, milestone.open_issues),
+                milestone.due_on.as_deref().and_then(|due| due.get(..10)),
+            ),
+            None => ("No milestone".into(), None),
+        };
+        let column_id = table_builder
+            .add(parent, format!("milestone/{}", number.unwrap_or_default()))
+            .styled(0, label, column_style)
+            .raw(2, due.map(|due| format!("due {due}")).unwrap_or_default())
+            .id();
+        let request = Request::Milestone(number);
+        let response = if requested.contains(&request) {
+            client.milestone_issues(&repo.owner, &repo.name, number).ok_or_pending()
+        } else {
+            Ok(None)
+        };
+        items.requests.insert(column_id, request);
+
+        let issues = match response {
+            Ok(Some(issues)) => issues,
+            Ok(None) => {
+                items.loading.push(column_id);
+                continue;
+            }
+            Err(err) => {
+                items.failed.push((column_id, err.into_owned()));
+                continue;
+            }
+        };
+        for issue in &issues {
+            let reference = format!("{}/{}#{}", repo.owner, repo.name, issue.number);
+            let item = table_builder
+                .add(column_id, reference.clone())
+                .raw(0, issue.title.clone())
+                .raw(1, reference);
+            let item = match issue.html_url.as_str() {
+                "" => item,
+                url => item.link(1, url.to_string()),
+            };
+            let item_id = item.id();
+            items.add_moves(item_id, &columns, idx, |from, to| {
+                let milestone =
+                    |m: &Option<&api::Milestone>| m.map(|m| (m.number, m.title.clone()));
+                MilestoneSet {
+                    owner: repo.owner.clone(),
+                    name: repo.name.clone(),
+                    number: issue.number,
+                    milestone: milestone(to),
+                    previous: milestone(from),
+                }
+                .into()
+            });
+        }
+    }
+}
+
+/// Add the columns of a project board with their cards.
+fn add_board(
+    table_builder: &mut simple_table::SourceBuilder<'_, '_, String>,
+    items: &mut Items,
+    project: &api::Project,
+    board: &api::ProjectBoard,
+    parent: u64,
+    column_style: simple_table::StyleId,
+) {
+    // Items without a status are shown in a column of their own, like on the
+    // website. Without a "Status" field, that's the only column.
+    let field = board.status_field.as_ref().filter(|field| !field.id.is_empty());
+    let columns: Vec<Option<(String, String)>> = std::iter::once(None)
+        .chain(field.iter().flat_map(|field| &field.options).map(Some))
+        .map(|option| option.map(|option| (option.id.clone(), option.name.clone())))
+        .collect();
+
+    for (idx, column) in columns.iter().enumerate() {
+        let option_id = column.as_ref().map(|(id, _)| id.as_str());
+        let cards: Vec<&api::ProjectItem> = board
+            .items
+            .nodes
+            .iter()
+            .filter(|item| {
+                let status = item.status.as_ref().and_then(|status| status.option_id.as_deref());
+                // Items in columns that no longer exist have no status.
+                let status = status.filter(|id| columns.iter().flatten().any(|(c, _)| c == id));
+                status == option_id
+            })
+            .collect();
+        let name = column.as_ref().map_or("No status", |(_, name)| name.as_str());
+        let column_id = table_builder
+            .add(parent, format!("column/{}", option_id.unwrap_or_default()))
+            .styled(0, format!("{name} ({})", cards.len()), column_style)
+            .group()
+            .id();
+
+        for card in cards {
+            let content = card.content.clone().unwrap_or_default();
+            let reference = match (&content.repository, content.number) {
+                (Some(repo), Some(number)) => format!("{}#{}", repo.name_with_owner, number),
+                _ => "draft".into(),
+            };
+            let item = table_builder
+                .add(column_id, card.id.clone())
+                .raw(0, content.title.clone())
+                .raw(1, reference);
+            let item = match content.url {
+                Some(url) => item.link(1, url),
+                None => item,
+            };
+            let item_id = item.id();
+            if let Some(field) = field {
+                items.add_moves(item_id, &columns, idx, |from, to| {
+                    ProjectItemMove {
+                        project_id: project.id.clone(),
+                        item_id: card.id.clone(),
+                        field_id: field.id.clone(),
+                        title: content.title.clone(),
+                        to: to.clone(),
+                        from: from.clone(),
+                    }
+                    .into()
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Write;
+
+use diff_modulo_base::{
+    git_core::{self, Ref},
+    tool::{self, GitDiffModuloBaseArgs, GitDiffModuloBaseOptions},
+};
+use vctools_utils::time;
+use vctuik::{
+    event::KeyCode,
+    layout::Constraint1D,
+    pager::{Pager, PagerState, RichPagerSource, RichPagerSourceBuilder},
+    prelude::*,
+    state::Builder,
+    table::{self, simple_table},
+    theme::TextStyle,
+};
+
+use crate::{
+    github::{self, api},
+    gitservice::GitService,
+    ApiRepository,
+};
+
+use super::{diff_pager::DiffPagerSource, markdown};
+
+/// A tag, with its release if there is one.
+#[derive(Debug, Clone)]
+struct Entry {
+    tag: api::Tag,
+    release: Option<api::Release>,
+}
+
+/// Two tags that are compared, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparison {
+    old: String,
+    new: String,
+    range_diff: bool,
+}
+
+/// Release notes and diff of the compared tags.
+#[derive(Debug, Default)]
+struct ComparisonView {
+    /// The comparison that the pagers were built for.
+    comparison: Option<Comparison>,
+    head_pager: RichPagerSource<'static>,
+    diff_pager: DiffPagerSource,
+    pager_state: PagerState,
+
+    /// Whether to rebuild the pagers because data was still missing.
+    need_rebuild: bool,
+}
+impl ComparisonView {
+    fn update(
+        &mut self,
+        comparison: Option<Comparison>,
+        entries: &[Entry],
+        repo: &ApiRepository,
+        git_service: &GitService,
+    ) {
+        if comparison == self.comparison && !self.need_rebuild {
+            return;
+        }
+        if comparison != self.comparison {
+            self.pager_state = PagerState::default();
+        }
+        self.comparison = comparison;
+        self.diff_pager = DiffPagerSource::new();
+        self.need_rebuild = false;
+
+        let mut pager = RichPagerSourceBuilder::new();
+        if let Some(comparison) = self.comparison.clone() {
+            let find = |name: &str| entries.iter().find(|entry| entry.tag.name == name);
+            if let (Some(old), Some(new)) = (find(&comparison.old), find(&comparison.new)) {
+                if let Err(err) = self.build(&mut pager, old, new, &comparison, repo, git_service) {
+                    if git_core::ExecutionProvider::timed_out(git_service) {
+                        pager.set_theme_style(TextStyle::Header2);
+                        writeln!(&mut pager, "Generating diff... {err}").unwrap();
+                        self.need_rebuild = true;
+                    } else {
+                        pager.set_theme_style(TextStyle::Error);
+                        writeln!(&mut pager, "Error comparing releases: {err}").unwrap();
+                    }
+                }
+            }
+        } else {
+            pager.set_theme_style(TextStyle::Inactive);
+            writeln!(&mut pager, "Mark two tags with Space to compare them.").unwrap();
+        }
+        self.head_pager = pager.build();
+    }
+
+    fn build(
+        &mut self,
+        pager: &mut RichPagerSourceBuilder,
+        old: &Entry,
+        new: &Entry,
+        comparison: &Comparison,
+        repo: &ApiRepository,
+        git_service: &GitService,
+    ) -> Result<()> {
+        pager.set_theme_style(TextStyle::Header0);
+        writeln!(pager, "Changes from {} to {}", old.tag.name, new.tag.name)?;
+        writeln!(pager)?;
+
+        if let Some(release) = &new.release {
+            let body = release.body.as_deref().unwrap_or_default();
+            pager.set_theme_style(TextStyle::Header1);
+            if body.trim().is_empty() {
+                writeln!(pager, "No release notes for {}.", new.tag.name)?;
+            } else {
+                pager.begin_folding_range();
+                writeln!(pager, "Release notes of {}:", new.tag.name)?;
+                markdown::write_markdown(pager, body)?;
+                pager.end_folding_range();
+            }
+            writeln!(pager)?;
+        }
+
+        let Some(git) = git_service.find_git(repo) else {
+            Err(format!(
+                "Local clone not found; add it to your repositories.toml: {}/{}",
+                repo.owner, repo.name
+            ))?
+        };
+        let (old_sha, new_sha) = (&old.tag.commit.sha, &new.tag.commit.sha);
+        git.repository.fetch_missing(
+            git_service,
+            &git.remote,
+            &[Ref::new(old_sha), Ref::new(new_sha)],
+        )?;
+
+        // Without a common base, a range diff shows all commits of the newer
+        // tag as new. With the merge base as the base, it shows what happened
+        // on both sides, e.g. for tags on different release branches.
+        let dmb_args = if comparison.range_diff {
+            let base =
+                git.repository.merge_base(git_service, &Ref::new(old_sha), &Ref::new(new_sha))?;
+            pager.set_theme_style(TextStyle::Header0);
+            writeln!(pager, "Range diff from the merge base {}:", base.name)?;
+            GitDiffModuloBaseArgs {
+                base: Some(base.name),
+                old: Some(old_sha.clone()),
+                new: Some(new_sha.clone()),
+                options: GitDiffModuloBaseOptions {
+                    combined: false,
+                    ..Default::default()
+                },
+            }
+        } else {
+            pager.set_theme_style(TextStyle::Header0);
+            writeln!(pager, "Diff:")?;
+            GitDiffModuloBaseArgs {
+                base: None,
+                old: Some(old_sha.clone()),
+                new: Some(new_sha.clone()),
+                options: GitDiffModuloBaseOptions::default(),
+            }
+        };
+
+        tool::git_diff_modulo_base(&dmb_args, &git.repository, git_service, &mut self.diff_pager)
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<String>,
+
+    /// Names of the marked tags, at most two.
+    marked: Vec<String>,
+
+    range_diff: bool,
+    view: ComparisonView,
+}
+
+/// Lists the tags of a repository with their releases.
+///
+/// The two marked tags, or else the selected tag and the one before it, are
+/// compared: the release notes of the newer one are shown together with the
+/// diff or range diff between them.
+pub struct Releases<'build> {
+    repository: &'build ApiRepository,
+    git_service: &'build GitService,
+}
+impl<'build> Releases<'build> {
+    pub fn new(repository: &'build ApiRepository, git_service: &'build GitService) -> Self {
+        Self {
+            repository,
+            git_service,
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let repo = self.repository;
+        let state_id = builder.add_state_id("releases");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let marked_symbol = builder.theme().symbols.marked;
+        let mut table_builder = state.table_state.build();
+        let marked_style = table_builder.add_style(text.highlight);
+        let inactive_style = table_builder.add_style(text.inactive);
+        let error_style = table_builder.add_style(text.error);
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut releases_pending = false;
+        match connections.client(&repo.host) {
+            Err(err) => {
+                table_builder.add(0, "error".into()).styled(0, err.to_string(), error_style);
+            }
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let client = client.access();
+                let tags = client.tags(&repo.owner, &repo.name).ok_or_pending();
+                let releases = client.releases(&repo.owner, &repo.name, 100).ok_or_pending();
+                match (tags, releases) {
+                    (Ok(Some(tags)), Ok(releases)) => {
+                        releases_pending = releases.is_none();
+                        let releases = releases.as_deref().map_or(&[][..], Vec::as_slice);
+                        entries = tags
+                            .iter()
+                            .map(|tag| {
+                                let release =
+                                    releases.iter().find(|r| r.tag_name == tag.name).cloned();
+                                Entry {
+                                    tag: tag.clone(),
+                                    release,
+                                }
+                            })
+                            .collect();
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        table_builder.add(0, "error".into()).styled(0, err, error_style);
+                    }
+                    (Ok(None), _) => {
+                        table_builder
+                            .add(0, "loading".into())
+                            .styled(0, "Loading tags...", inactive_style);
+                    }
+                }
+            }
+        }
+
+        state.marked.retain(|name| entries.iter().any(|entry| entry.tag.name == *name));
+
+        let now = time::unix_now();
+        let mut item_ids = Vec::new();
+        for entry in &entries {
+            let name = &entry.tag.name;
+            let item = table_builder.add(0, name.clone());
+            let item = if state.marked.contains(name) {
+                item.styled(0, format!("{marked_symbol} {name}"), marked_style)
+            } else {
+                item.raw(0, name.clone())
+            };
+            let item = match &entry.release {
+                Some(release) => {
+                    let published = release
+                        .published_at()
+                        .map(|dt| time::format_relative(dt.timestamp(), now))
+                        .unwrap_or_default();
+                    let title = release.name.clone().filter(|name| !name.is_empty());
+                    let item = item.raw(1, title.unwrap_or_else(|| release.tag_name.clone()));
+                    if release.prerelease {
+                        item.styled(2, format!("{published} (pre-release)"), inactive_style)
+                    } else {
+                        item.raw(2, published)
+                    }
+                }
+                None => item.styled(1, "(no release)", inactive_style),
+            };
+            item_ids.push(item.id());
+        }
+
+        let columns = vec![
+            table::Column::new(0, "Tag", Constraint1D::new(6, 30)),
+            table::Column::new(1, "Release", Constraint1D::unconstrained()),
+            table::Column::new(2, "Published", Constraint1D::new(8, 24)),
+        ];
+
+        builder.nest().id(state_id).build(|builder| {
+            let table_result = table::Table::new(&table_builder.finish())
+                .id("tags")
+                .columns(columns)
+                .build(builder);
+            let selected = table_result
+                .selection
+                .and_then(|id| item_ids.iter().position(|item_id| *item_id == id));
+
+            if table_result.has_focus
+                && builder.on_key_binding(KeyCode::Char(' '), "Mark / unmark for comparison")
+            {
+                if let Some(idx) = selected {
+                    let name = &entries[idx].tag.name;
+                    if let Some(pos) = state.marked.iter().position(|m| m == name) {
+                        state.marked.remove(pos);
+                    } else {
+                        if state.marked.len() == 2 {
+                            state.marked.remove(0);
+                        }
+                        state.marked.push(name.clone());
+                    }
+                    builder.need_refresh();
+                }
+            }
+            if builder.check_group_focus(state_id)
+                && builder.on_key_binding(KeyCode::Char('R'), "Diff / range diff")
+            {
+                state.range_diff = !state.range_diff;
+            }
+
+            // Tags are listed from newest to oldest.
+            let pair = if let [a, b] = state.marked.as_slice() {
+                let position = |name: &String| entries.iter().position(|e| e.tag.name == *name);
+                let (old, new) = if position(a) > position(b) { (a, b) } else { (b, a) };
+                Some((old.clone(), new.clone()))
+            } else {
+                selected
+                    .filter(|&idx| idx + 1 < entries.len())
+                    .map(|idx| (entries[idx + 1].tag.name.clone(), entries[idx].tag.name.clone()))
+            };
+            let comparison = pair.map(|(old, new)| Comparison {
+                old,
+                new,
+                range_diff: state.range_diff,
+            });
+            let view = &mut state.view;
+            view.need_rebuild |= releases_pending;
+            view.update(comparison, &entries, repo, self.git_service);
+
+            let mut pager = RichPagerSourceBuilder::new();
+            pager.add_child_ref(&view.head_pager);
+            pager.add_child_ref(&view.diff_pager);
+            let pager_source = pager.build();
+            Pager::new(&pager_source).build_with_state(builder, "pager", &mut view.pager_state);
+        });
+    }
+}
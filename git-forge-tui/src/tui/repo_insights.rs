@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::prelude::*;
+use vctools_utils::time;
+use vctuik::{label::add_text_label, state::Builder};
+
+use crate::{
+    github::{self, api, Response},
+    ApiRepository,
+};
+
+/// Number of releases that are listed.
+const RELEASES: usize = 5;
+
+/// Summarize the CI state of a ref from its commit statuses and check runs.
+///
+/// Returns the text and whether it indicates a failure.
+fn ci_summary(status: &api::CombinedStatus, checks: &api::CheckRuns) -> (String, bool) {
+    let mut parts = Vec::new();
+    let mut failed = false;
+
+    if status.total_count > 0 {
+        failed |= status.state == "failure" || status.state == "error";
+        parts.push(format!("statuses {}", status.state));
+    }
+
+    if checks.total_count > 0 {
+        let mut passed = 0;
+        let mut failures = 0;
+        let mut running = 0;
+        for run in &checks.check_runs {
+            match (run.status.as_str(), run.conclusion.as_deref()) {
+                ("completed", Some("failure" | "timed_out" | "cancelled" | "action_required")) => {
+                    failures += 1
+                }
+                ("completed", _) => passed += 1,
+                _ => running += 1,
+            }
+        }
+        failed |= failures > 0;
+        let mut counts = vec![format!("{passed} passed")];
+        if failures > 0 {
+            counts.push(format!("{failures} failed"));
+        }
+        if running > 0 {
+            counts.push(format!("{running} running"));
+        }
+        parts.push(format!("checks {}", counts.join(", ")));
+    }
+
+    if parts.is_empty() {
+        ("no CI results".into(), false)
+    } else {
+        (parts.join("; "), failed)
+    }
+}
+
+/// Overview of a repository: open pull requests and issues, the CI state of
+/// the default branch, and recent releases.
+///
+/// Everything is fetched lazily from a handful of endpoints and cached by the
+/// client.
+#[derive(Debug)]
+pub struct RepositoryInsights<'build> {
+    repository: &'build ApiRepository,
+}
+impl<'build> RepositoryInsights<'build> {
+    pub fn new(repository: &'build ApiRepository) -> Self {
+        Self { repository }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let text = builder.theme().text(builder.theme_context());
+        let repo = self.repository;
+        let mut lines: Vec<Line> = Vec::new();
+        let label = |label: &'static str| Span::styled(format!("{label:<14}"), text.header2);
+        let pending = |what: &str| Span::styled(format!("Loading {what}..."), text.inactive);
+
+        match connections.client(&repo.host) {
+            Err(err) => lines.push(Line::styled(err.to_string(), text.error)),
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let client = client.access();
+
+                let details = client.repository(&repo.owner, &repo.name);
+                match &details {
+                    Response::Ok(details) => {
+                        lines.push(Line::styled(details.full_name.clone(), text.header1));
+                        if let Some(description) =
+                            details.description.as_ref().filter(|d| !d.is_empty())
+                        {
+                            lines.push(Line::styled(description.clone(), text.normal));
+                        }
+                    }
+                    Response::Pending => lines.push(pending("repository").into()),
+                    response => lines.push(Line::styled(
+                        response.clone().ok().err().unwrap_or_default().to_string(),
+                        text.error,
+                    )),
+                }
+
+                for (name, kind) in [("Open PRs:", "pr"), ("Open issues:", "issue")] {
+                    let value = match client.open_count(&repo.owner, &repo.name, kind) {
+                        Response::Ok(count) => Span::styled(count.to_string(), text.normal),
+                        Response::Pending => pending("count"),
+                        response => {
+                            Span::styled(response.ok().err().unwrap_or_default(), text.inactive)
+                        }
+                    };
+                    lines.push(Line::from(vec![label(name), value]));
+                }
+
+                if let Response::Ok(details) = &details {
+                    let branch = &details.default_branch;
+                    let status = client.combined_status(&repo.owner, &repo.name, branch);
+                    let checks = client.check_runs(&repo.owner, &repo.name, branch);
+                    let value = match (status, checks) {
+                        (Response::Ok(status), Response::Ok(checks)) => {
+                            let (summary, failed) = ci_summary(&status, &checks);
+                            Span::styled(summary, if failed { text.error } else { text.normal })
+                        }
+                        (Response::Pending, _) | (_, Response::Pending) => pending("CI results"),
+                        (status, checks) => {
+                            let err = status.ok().err().or(checks.ok().err());
+                            Span::styled(err.unwrap_or_default(), text.inactive)
+                        }
+                    };
+                    lines.push(Line::from(vec![
+                        label("CI:"),
+                        Span::styled(format!("{branch}: "), text.normal),
+                        value,
+                    ]));
+                }
+
+                lines.push(Line::default());
+                lines.push(Line::styled("Recent releases", text.header2));
+                match client.releases(&repo.owner, &repo.name, RELEASES) {
+                    Response::Ok(releases) if releases.is_empty() => {
+                        lines.push(Line::styled("  (none)", text.inactive))
+                    }
+                    Response::Ok(releases) => {
+                        let now = time::unix_now();
+                        for release in releases.iter() {
+                            let published = release
+                                .published_at()
+                                .map(|dt| time::format_relative(dt.timestamp(), now))
+                                .unwrap_or_else(|| "unpublished".into());
+                            let mut spans = vec![
+                                Span::styled(format!("  {:<20} ", release.tag_name), text.normal),
+                                Span::styled(format!("{published:<12} "), text.inactive),
+                            ];
+                            let name = release.name.as_deref().filter(|name| !name.is_empty());
+                            if let Some(name) = name {
+                                spans.push(Span::styled(name.to_string(), text.normal));
+                            }
+                            if release.draft || release.prerelease {
+                                let kind = if release.draft { "draft" } else { "pre-release" };
+                                spans.push(Span::styled(format!(" ({kind})"), text.inactive));
+                            }
+                            lines.push(Line::from(spans));
+                        }
+                    }
+                    Response::Pending => lines.push(Line::from(pending("releases"))),
+                    response => lines.push(Line::styled(
+                        format!("  {}", response.ok().err().unwrap_or_default()),
+                        text.inactive,
+                    )),
+                }
+            }
+        }
+
+        add_text_label(builder, Text::from(lines));
+        builder.add_slack();
+    }
+}
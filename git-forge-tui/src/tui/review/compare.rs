@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use diff_modulo_base::git_core::{self, Ref};
+use diff_modulo_base::tool::{self, GitDiffModuloBaseArgs, GitDiffModuloBaseOptions};
+use log::warn;
+use regex::Regex;
+use vctuik::{
+    event::KeyCode,
+    pager::{Pager, PagerSource, PagerState, RichPagerSource, RichPagerSourceBuilder},
+    prelude::*,
+    state::Builder,
+    theme::TextStyle,
+};
+
+use super::{diff_view::DiffView, header};
+use crate::settings::Settings;
+use crate::tui::{actions, diff_pager::DiffPagerSource};
+
+#[derive(Debug, Default)]
+struct CompareState {
+    options: GitDiffModuloBaseOptions,
+    head_pager: RichPagerSource<'static>,
+    diff_pager: DiffPagerSource,
+    diff_view: DiffView,
+    pager_state: PagerState,
+    dmb_args: Option<GitDiffModuloBaseArgs>,
+
+    /// Whether to rebuild on the next frame because Git timed out.
+    need_rebuild: bool,
+}
+impl CompareState {
+    fn update(
+        &mut self,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+        args: GitDiffModuloBaseArgs,
+    ) {
+        if self.dmb_args.as_ref() == Some(&args) && !self.need_rebuild {
+            return;
+        }
+        if self.dmb_args.as_ref().is_none_or(|old| old.old != args.old || old.new != args.new) {
+            self.pager_state = PagerState::default();
+            self.diff_view.clear();
+        }
+        self.diff_pager = DiffPagerSource::new();
+        self.need_rebuild = false;
+
+        let mut pager = RichPagerSourceBuilder::new();
+        if let Err(err) = self.build(&mut pager, repository, ep, &args) {
+            if ep.timed_out() {
+                pager.set_theme_style(TextStyle::Header2);
+                writeln!(&mut pager, "Generating diff... {err}").unwrap();
+                self.need_rebuild = true;
+            } else {
+                pager.set_theme_style(TextStyle::Error);
+                writeln!(&mut pager, "Error comparing refs: {err}").unwrap();
+            }
+        }
+        self.head_pager = pager.build();
+        self.dmb_args = Some(args);
+    }
+
+    fn build(
+        &mut self,
+        pager: &mut RichPagerSourceBuilder,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+        args: &GitDiffModuloBaseArgs,
+    ) -> Result<()> {
+        let old = args.old.as_deref().unwrap();
+        let new = args.new.as_deref().unwrap();
+        let old_commit = repository.rev_parse(ep, &Ref::new(old))?;
+        let new_commit = repository.rev_parse(ep, &Ref::new(new))?;
+        let base = match &args.base {
+            Some(base) => Some((base.as_str(), repository.rev_parse(ep, &Ref::new(base))?)),
+            None => None,
+        };
+        header::write_comparison(
+            pager,
+            (old, &old_commit.name),
+            (new, &new_commit.name),
+            base.as_ref().map(|(name, commit)| (*name, commit.name.as_str())),
+        )?;
+
+        tool::git_diff_modulo_base(args, repository, ep, &mut self.diff_pager)?;
+        if let Err(err) = self.diff_pager.verify_signatures(repository, ep) {
+            if ep.timed_out() {
+                return Err(err);
+            }
+            warn!("{err}");
+        }
+        let settings = Settings::get();
+        self.diff_pager.detect_generated(repository, ep, &new_commit, &settings.risk)
+    }
+}
+
+/// Diff between two refs of a local repository, optionally modulo a base,
+/// without any pull request or forge.
+pub struct Compare<'build> {
+    repository: &'build git_core::Repository,
+    ep: &'build dyn git_core::ExecutionProvider,
+    old: &'build str,
+    new: &'build str,
+    modulo: Option<&'build str>,
+    options: Option<&'build mut GitDiffModuloBaseOptions>,
+    search: Option<&'build Regex>,
+}
+impl<'build> Compare<'build> {
+    pub fn new(
+        ep: &'build dyn git_core::ExecutionProvider,
+        repository: &'build git_core::Repository,
+        old: &'build str,
+        new: &'build str,
+    ) -> Self {
+        Self {
+            repository,
+            ep,
+            old,
+            new,
+            modulo: None,
+            options: None,
+            search: None,
+        }
+    }
+
+    /// Reduce the diff modulo the given base, i.e. compare the changes of
+    /// `old` and `new` relative to their merge bases with `base`.
+    pub fn modulo(self, base: Option<&'build str>) -> Self {
+        Self {
+            modulo: base,
+            ..self
+        }
+    }
+
+    pub fn maybe_search(self, search: Option<&'build Regex>) -> Self {
+        Self { search, ..self }
+    }
+
+    pub fn options(self, options: &'build mut GitDiffModuloBaseOptions) -> Self {
+        Self {
+            options: Some(options),
+            ..self
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder) {
+        let state_id = builder.add_state_id("compare");
+        let state: &mut CompareState = builder.get_state(state_id);
+
+        if let Some(options) = &self.options {
+            state.options = (*options).clone();
+        }
+
+        builder.nest().id(state_id).build(|builder| {
+            if builder.on_custom::<actions::FocusReview>().is_some() {
+                builder.grab_focus(state_id);
+            }
+
+            let has_focus = builder.check_group_focus(state_id);
+            if has_focus {
+                if builder.on_key_binding(KeyCode::Char('C'), "Combined diff / range diff") {
+                    state.options.combined = !state.options.combined;
+                    if let Some(options) = self.options {
+                        options.combined = state.options.combined;
+                    }
+                }
+                state.diff_view.handle_layout_keys(builder, &mut state.diff_pager);
+            }
+
+            let args = GitDiffModuloBaseArgs {
+                base: self.modulo.map(String::from),
+                old: Some(self.old.into()),
+                new: Some(self.new.into()),
+                options: state.options.clone(),
+            };
+            state.update(self.repository, self.ep, args);
+
+            let mut pager = RichPagerSourceBuilder::new();
+            pager.add_child_ref(&state.head_pager);
+            pager.add_child_ref(&state.diff_pager);
+            let pager_source = pager.build();
+
+            state.diff_view.update(&pager_source);
+
+            let mut pager =
+                Pager::new(&pager_source).minimap(state.diff_view.show_minimap());
+            if let Some(regex) = self.search {
+                pager = pager.search(Cow::Borrowed(regex));
+            }
+            let mut pager_result = pager.build_with_state(builder, "pager", &mut state.pager_state);
+
+            let offset = state.head_pager.num_lines();
+            state.diff_view.collapse_generated(
+                builder,
+                &state.diff_pager,
+                &mut pager_result,
+                offset,
+            );
+
+            if has_focus {
+                if let Some(search) = builder.on_custom::<actions::Search>() {
+                    pager_result.search(&search.0, true);
+                    builder.need_refresh();
+                }
+
+                state.diff_view.handle_keys(
+                    builder,
+                    &state.diff_pager,
+                    &pager_source,
+                    &mut pager_result,
+                    offset,
+                );
+                state.diff_view.handle_fixup_keys(builder, self.repository, self.ep);
+            }
+            let visible = pager_result.visible_lines();
+            std::mem::drop(pager_result);
+
+            state.diff_view.build_panes(builder, &pager_source);
+            std::mem::drop(pager_source);
+
+            // Files of huge diffs are loaded once they are scrolled into view.
+            let visible = visible.start.saturating_sub(offset)..visible.end.saturating_sub(offset);
+            if state.diff_pager.load_deferred(visible) {
+                builder.need_refresh();
+            }
+        });
+    }
+}
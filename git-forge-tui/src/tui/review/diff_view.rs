@@ -0,0 +1,298 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::{HashMap, HashSet};
+
+use diff_modulo_base::git_core;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use vctuik::{
+    event::{Event, KeyCode, KeyEventKind},
+    pager::{PagerResult, PagerSource},
+    section::with_section,
+    state::Builder,
+};
+
+use crate::{
+    apply, get_project_dirs,
+    tui::{
+        bookmarks::Bookmarks,
+        diff_pager::{DiffPagerSource, Landmark},
+    },
+};
+
+/// First key of a two-key command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PendingKey {
+    /// `]` or `[`: jump forward or backward to a hunk or file.
+    Jump(bool),
+
+    /// `m`: set a bookmark.
+    SetBookmark,
+
+    /// `'`: jump to a bookmark.
+    GoToBookmark,
+}
+
+/// Navigation between hunks and files of a diff, bookmarks, hunks that are
+/// picked for a fixup patch, and details of commit signatures.
+///
+/// The diff is shown at the end of a pager that is composed of several parts,
+/// so the methods take both the diff and the composed pager source, and the
+/// line at which the diff starts in it.
+#[derive(Debug, Default)]
+pub struct DiffView {
+    /// First key of a two-key command, while waiting for the second key.
+    pending_key: Option<PendingKey>,
+
+    bookmarks: Bookmarks,
+    show_bookmarks: bool,
+
+    /// Show the overview of changes next to the pager.
+    show_minimap: bool,
+
+    /// Hunks that are picked for the fixup patch.
+    fixup: Vec<apply::Hunk>,
+
+    /// Outcome of the last export or application of the fixup patch.
+    fixup_status: Option<Result<String, String>>,
+
+    /// Signature whose details are shown in a popup, if any.
+    signature: Option<git_core::Signature>,
+
+    /// Generated files that were collapsed by default, by path and the number
+    /// of earlier headers of the same path (e.g. in other commits), so that
+    /// they stay expanded when the user expands them.
+    collapsed_generated: HashSet<(String, usize)>,
+}
+impl DiffView {
+    /// Forget bookmarks and picked hunks, e.g. because a different diff is
+    /// shown.
+    pub fn clear(&mut self) {
+        self.bookmarks.clear();
+        self.fixup.clear();
+        self.fixup_status = None;
+        self.signature = None;
+        self.collapsed_generated.clear();
+    }
+
+    pub fn show_minimap(&self) -> bool {
+        self.show_minimap
+    }
+
+    /// Handle keys that change how the diff is laid out.
+    ///
+    /// Must be called before the pager source is composed.
+    pub fn handle_layout_keys(&mut self, builder: &mut Builder, diff: &mut DiffPagerSource) {
+        if builder.on_key_binding(KeyCode::Char('d'), "Cycle diff styles") {
+            diff.toggle_mode();
+        } else if builder.on_key_binding(KeyCode::Char('O'), "Show / hide the change overview") {
+            self.show_minimap = !self.show_minimap;
+        }
+    }
+
+    /// Must be called whenever the composed pager source may have changed.
+    pub fn update(&mut self, source: &dyn PagerSource) {
+        self.bookmarks.update(source);
+    }
+
+    /// Collapse generated files when they first appear.
+    pub fn collapse_generated(
+        &mut self,
+        builder: &mut Builder,
+        diff: &DiffPagerSource,
+        pager: &mut PagerResult,
+        offset: usize,
+    ) {
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        for (line, path) in diff.generated_files() {
+            let occurrence = occurrences.entry(path.clone()).or_default();
+            if self.collapsed_generated.insert((path, *occurrence)) {
+                pager.collapse(offset + line);
+                builder.need_refresh();
+            }
+            *occurrence += 1;
+        }
+    }
+
+    /// Handle navigation keys while the pager has focus.
+    pub fn handle_keys(
+        &mut self,
+        builder: &mut Builder,
+        diff: &DiffPagerSource,
+        source: &dyn PagerSource,
+        pager: &mut PagerResult,
+        offset: usize,
+    ) {
+        builder.describe_key(KeyCode::Char(']'), "Next hunk (]h) / file (]f)");
+        builder.describe_key(KeyCode::Char('['), "Previous hunk ([h) / file ([f)");
+        builder.describe_key(KeyCode::Char('m'), "Set a bookmark (m and a letter)");
+        builder.describe_key(KeyCode::Char('\''), "Jump to a bookmark ('x)");
+
+        let Some(pending) = self.pending_key else {
+            if builder.on_key_press(KeyCode::Char(']')) {
+                self.pending_key = Some(PendingKey::Jump(true));
+            } else if builder.on_key_press(KeyCode::Char('[')) {
+                self.pending_key = Some(PendingKey::Jump(false));
+            } else if builder.on_key_press(KeyCode::Char('m')) {
+                self.pending_key = Some(PendingKey::SetBookmark);
+            } else if builder.on_key_press(KeyCode::Char('\'')) {
+                self.pending_key = Some(PendingKey::GoToBookmark);
+            } else if builder.on_key_binding(KeyCode::Char('B'), "Show / hide bookmarks") {
+                self.show_bookmarks = !self.show_bookmarks;
+            } else if builder.on_key_binding(KeyCode::Char('H'), "Pick / unpick for the fixup") {
+                let line = pager.selected_line().checked_sub(offset);
+                if let Some(hunk) = line.and_then(|line| diff.hunk_at(line)) {
+                    match self.fixup.iter().position(|picked| picked.is_same(&hunk)) {
+                        Some(idx) => {
+                            self.fixup.remove(idx);
+                        }
+                        None => self.fixup.push(hunk),
+                    }
+                    self.fixup_status = None;
+                }
+            } else if builder.on_key_binding(KeyCode::Char('V'), "Show the commit's signature") {
+                let line = pager.selected_line().checked_sub(offset);
+                let signature = line.and_then(|line| diff.signature_at(line)).cloned();
+                self.signature =
+                    signature.filter(|signature| self.signature.as_ref() != Some(signature));
+            } else if self.signature.is_some()
+                && builder.on_key_binding(KeyCode::Esc, "Close the signature")
+            {
+                self.signature = None;
+            }
+            return;
+        };
+
+        let key = match builder.peek_event() {
+            Some(Event::Key(ev)) if ev.kind == KeyEventKind::Press => ev.code,
+            _ => return,
+        };
+        self.pending_key = None;
+        match (pending, key) {
+            (PendingKey::Jump(forward), KeyCode::Char(ch @ ('h' | 'f'))) => {
+                builder.on_key_press(key);
+                let landmark = if ch == 'h' { Landmark::Hunk } else { Landmark::File };
+                let line = pager.selected_line().saturating_sub(offset);
+                if let Some(line) = diff.find_landmark(line, landmark, forward) {
+                    pager.move_to(offset + line);
+                }
+            }
+            (PendingKey::SetBookmark, KeyCode::Char(name)) if Bookmarks::is_valid_name(name) => {
+                builder.on_key_press(key);
+                self.bookmarks.set(name, source, pager.selected_line());
+            }
+            (PendingKey::GoToBookmark, KeyCode::Char(name)) => {
+                builder.on_key_press(key);
+                if let Some(line) = self.bookmarks.get(name) {
+                    pager.move_to(line);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle the keys that write the picked hunks as a fixup patch or apply
+    /// them to the working tree of the given local clone.
+    pub fn handle_fixup_keys(
+        &mut self,
+        builder: &mut Builder,
+        repository: &git_core::Repository,
+        ep: &dyn git_core::ExecutionProvider,
+    ) {
+        if self.fixup.is_empty() {
+            return;
+        }
+        let path = get_project_dirs().data_dir().join("fixup.patch");
+        if builder.on_key_binding(KeyCode::Char('W'), "Write the fixup patch") {
+            self.fixup_status = Some(
+                apply::export(&path, &self.fixup)
+                    .map(|()| format!("Wrote {}", path.display()))
+                    .map_err(|err| err.to_string()),
+            );
+        } else if builder.on_key_binding(KeyCode::Char('A'), "Apply the fixup to the worktree") {
+            self.fixup_status = Some(
+                apply::apply(repository, ep, &path, &self.fixup)
+                    .map(|()| format!("Applied to {}", repository.path.display()))
+                    .map_err(|err| err.to_string()),
+            );
+        }
+    }
+
+    /// Build the panes that accompany the diff and the signature popup, if
+    /// they are shown.
+    pub fn build_panes(&self, builder: &mut Builder, source: &dyn PagerSource) {
+        if self.show_bookmarks {
+            self.bookmarks.build_pane(builder, source);
+        }
+        if !self.fixup.is_empty() || self.fixup_status.is_some() {
+            self.build_fixup_pane(builder);
+        }
+        if let Some(signature) = &self.signature {
+            build_signature_popup(builder, signature);
+        }
+    }
+
+    fn build_fixup_pane(&self, builder: &mut Builder) {
+        with_section(builder, "Fixup", |builder| {
+            let text = builder.theme().text(builder.theme_context()).clone();
+            for hunk in &self.fixup {
+                let area = builder.take_lines_fixed(1);
+                builder.frame().render_widget(Span::styled(hunk.describe(), text.normal), area);
+            }
+            let (status, style) = match &self.fixup_status {
+                Some(Ok(message)) => (message.as_str(), text.highlight),
+                Some(Err(message)) => (message.as_str(), text.error),
+                None => ("W: write the patch, A: apply it to the worktree", text.inactive),
+            };
+            for line in status.lines() {
+                let area = builder.take_lines_fixed(1);
+                builder.frame().render_widget(Span::styled(line, style), area);
+            }
+        });
+    }
+}
+
+/// Popup with the details of a commit signature.
+fn build_signature_popup(builder: &mut Builder, signature: &git_core::Signature) {
+    let text = builder.theme().modal_text.clone();
+    let status_style = match signature.status {
+        git_core::SignatureStatus::Good => text.added,
+        git_core::SignatureStatus::Bad => text.error,
+        git_core::SignatureStatus::Unsigned => text.inactive,
+        _ => text.highlight,
+    };
+    let mut lines = vec![Line::styled(signature.status.describe(), status_style)];
+    for (label, value) in [
+        ("Signer:      ", &signature.signer),
+        ("Key:         ", &signature.key),
+        ("Fingerprint: ", &signature.fingerprint),
+    ] {
+        if !value.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled(label, text.header2),
+                Span::styled(value.as_str(), text.normal),
+            ]));
+        }
+    }
+
+    // Show the popup in the bottom right corner of the available space.
+    let viewport = builder.viewport();
+    let width = std::cmp::min(72, viewport.width);
+    let height = std::cmp::min(lines.len() as u16 + 2, viewport.height);
+    let area = Rect {
+        x: viewport.x + viewport.width - width,
+        y: viewport.y + viewport.height - height,
+        width,
+        height,
+    };
+
+    let commit = &signature.commit.name;
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .title(format!(" Signature of {} ", commit.get(..12).unwrap_or(commit)))
+        .style(builder.theme().modal_background.patch(text.normal));
+    builder.frame().render_widget(Clear, area);
+    builder.frame().render_widget(Paragraph::new(lines).block(block), area);
+}
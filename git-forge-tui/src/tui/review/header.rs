@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Write;
+
+use diff_modulo_base::git_core::MergeTree;
+use vctuik::{pager::RichPagerSourceBuilder, prelude::*, theme::TextStyle};
+
+use crate::{
+    dependencies::LockfileChanges,
+    merge_readiness::{self, Status},
+    risk::RiskAssessment,
+    settings::RiskSettings,
+    task_list,
+    tui::links,
+};
+
+/// Write a label, followed by a value in the normal style.
+fn coln<'pager, 'text>(
+    pager: &'pager mut RichPagerSourceBuilder<'text>,
+    label: &str,
+) -> &'pager mut RichPagerSourceBuilder<'text> {
+    pager.set_theme_style(TextStyle::Header2);
+    pager.write_str(label).unwrap();
+    pager.set_theme_style(TextStyle::Normal);
+    pager
+}
+
+/// Write a label, followed by a highlighted value.
+fn colh<'pager, 'text>(
+    pager: &'pager mut RichPagerSourceBuilder<'text>,
+    label: &str,
+) -> &'pager mut RichPagerSourceBuilder<'text> {
+    pager.set_theme_style(TextStyle::Header2);
+    pager.write_str(label).unwrap();
+    pager.set_theme_style(TextStyle::Highlight);
+    pager
+}
+
+/// Summary of a change at the top of a review.
+#[derive(Debug)]
+pub struct Header<'a> {
+    /// For example, "Pull Request owner/name#123"
+    pub name: String,
+    pub url: &'a str,
+    pub title: &'a str,
+    pub author: &'a str,
+
+    /// For example, "🟢 Open"
+    pub state: &'a str,
+    pub risk: Option<(RiskAssessment, &'a RiskSettings)>,
+}
+impl<'a> Header<'a> {
+    pub fn write(&self, pager: &mut RichPagerSourceBuilder) -> Result<()> {
+        pager.set_theme_style(TextStyle::Header0);
+        pager.write_link(&self.name, self.url)?;
+        writeln!(pager, " ({})", self.url)?;
+        writeln!(colh(pager, "Title:   "), "{}", self.title)?;
+        let author = format!("@{}", self.author);
+        match links::repository(self.url).and_then(|repo| links::user(repo, self.author)) {
+            Some(url) => colh(pager, "Author:  ").write_link(&author, url)?,
+            None => colh(pager, "Author:  ").write_str(&author)?,
+        }
+        writeln!(pager)?;
+        writeln!(coln(pager, "State:   "), "{}", self.state)?;
+
+        if let Some((risk, settings)) = &self.risk {
+            write!(coln(pager, "Risk:    "), "[{}]", risk.badges(settings).join("] ["))?;
+            write!(
+                pager,
+                " {}{} lines changed",
+                risk.lines,
+                if risk.incomplete { "+" } else { "" },
+            )?;
+            if risk.generated_lines != 0 {
+                write!(pager, ", {} in generated files", risk.generated_lines)?;
+            }
+            if let Some(ratio) = risk.test_ratio() {
+                write!(pager, ", test/code ratio {:.2}", ratio)?;
+            }
+            writeln!(pager)?;
+            if !risk.risky_files.is_empty() {
+                pager.set_theme_style(TextStyle::Highlight);
+                writeln!(pager, "         Risky: {}", risk.risky_files.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write a (name, commit) pair, linking the commit if the URL of the
+/// repository is known.
+fn write_branch(
+    pager: &mut RichPagerSourceBuilder,
+    (name, sha): (&str, &str),
+    repo_url: Option<&str>,
+) -> Result<()> {
+    write!(pager, "{} (", name)?;
+    match repo_url {
+        Some(repo_url) => pager.write_link(sha, links::commit(repo_url, sha))?,
+        None => pager.write_str(sha)?,
+    }
+    writeln!(pager, ")")?;
+    Ok(())
+}
+
+/// Write the branches of a change as (name, commit) pairs, followed by its
+/// description, if any.
+///
+/// Returns the lines of the pager on which the items of the task list in the
+/// description are shown, in the order of the task list.
+pub fn write_branches_and_description(
+    pager: &mut RichPagerSourceBuilder,
+    head: (&str, &str),
+    target: (&str, &str),
+    repo_url: Option<&str>,
+    description: Option<&str>,
+) -> Result<Vec<usize>> {
+    write_branch(coln(pager, "Current head:       "), head, repo_url)?;
+    write_branch(coln(pager, "Target branch:      "), target, repo_url)?;
+    writeln!(pager)?;
+
+    let mut task_lines = Vec::new();
+    pager.set_theme_style(TextStyle::Header0);
+    if let Some(body) = description.filter(|b| !b.trim().is_empty()) {
+        pager.begin_folding_range();
+        writeln!(pager, "Description:")?;
+        pager.set_theme_style(TextStyle::Normal);
+        pager.set_indent(4);
+        let mut tasks = task_list::parse(body).into_iter().peekable();
+        for (idx, line) in body.lines().enumerate() {
+            let Some(task) = tasks.next_if(|task| task.line == idx) else {
+                writeln!(pager, "{}", line)?;
+                continue;
+            };
+            task_lines.push(pager.num_lines());
+            if task.checked {
+                pager.set_theme_style(TextStyle::Inactive);
+            }
+            let mark = if task.checked { 'x' } else { ' ' };
+            writeln!(pager, "{}[{}] {}", task.indent, mark, task.text)?;
+            pager.set_theme_style(TextStyle::Normal);
+        }
+        pager.set_indent(0);
+        pager.end_folding_range();
+    } else {
+        writeln!(pager, "No description provided.")?;
+    }
+    writeln!(pager)?;
+    Ok(task_lines)
+}
+
+/// Write whether the change merges cleanly into the target branch, given as
+/// (name, commit), and list the conflicting files if not.
+pub fn write_merge_preview(
+    pager: &mut RichPagerSourceBuilder,
+    target: (&str, &str),
+    merge: &MergeTree,
+) -> Result<()> {
+    if !merge.has_conflicts() {
+        writeln!(coln(pager, "Merge:   "), "merges cleanly into {} ({})", target.0, target.1)?;
+        return Ok(());
+    }
+
+    pager.set_theme_style(TextStyle::Header2);
+    pager.write_str("Merge:   ")?;
+    pager.set_theme_style(TextStyle::Error);
+    writeln!(
+        pager,
+        "conflicts with {} ({}) in {} file(s):",
+        target.0,
+        target.1,
+        merge.conflicts.len()
+    )?;
+    for path in &merge.conflicts {
+        writeln!(pager, "         {}", path)?;
+    }
+    Ok(())
+}
+
+/// Write the checklist of what the change needs for merging into its target
+/// branch.
+pub fn write_merge_readiness(
+    pager: &mut RichPagerSourceBuilder,
+    items: &[merge_readiness::Item],
+) -> Result<()> {
+    let blockers = merge_readiness::blockers(items).count();
+    pager.set_theme_style(TextStyle::Header2);
+    pager.write_str("Ready:   ")?;
+    if blockers > 0 {
+        pager.set_theme_style(TextStyle::Error);
+        writeln!(pager, "{blockers} requirement(s) not met")?;
+    } else if items.iter().any(|item| item.status == Status::Unknown) {
+        writeln!(coln(pager, ""), "no known blockers")?;
+    } else {
+        writeln!(coln(pager, ""), "all requirements met")?;
+    }
+    for item in items {
+        pager.set_theme_style(match item.status {
+            Status::Met => TextStyle::Normal,
+            Status::Unmet => TextStyle::Error,
+            Status::Pending | Status::Unknown => TextStyle::Inactive,
+        });
+        writeln!(pager, "         {} {}", item.status.symbol(), item.text)?;
+    }
+    Ok(())
+}
+
+/// Write the dependency changes of the lockfiles that a change touches, with
+/// the advisories that affect new versions.
+pub fn write_dependency_changes(
+    pager: &mut RichPagerSourceBuilder,
+    lockfiles: &[LockfileChanges],
+) -> Result<()> {
+    for lockfile in lockfiles {
+        let changes = &lockfile.changes;
+        let added = changes.iter().filter(|change| change.old.is_none()).count();
+        let removed = changes.iter().filter(|change| change.new.is_none()).count();
+        pager.set_theme_style(TextStyle::Header1);
+        pager.begin_folding_range();
+        writeln!(
+            pager,
+            "Dependencies ({}): {added} added, {removed} removed, {} changed",
+            lockfile.path,
+            changes.len() - added - removed,
+        )?;
+
+        for change in &lockfile.changes {
+            match (&change.old, &change.new) {
+                (Some(old), Some(new)) => {
+                    pager.set_theme_style(TextStyle::Normal);
+                    writeln!(pager, "    ~ {} {old} -> {new}", change.name)?;
+                }
+                (None, Some(new)) => {
+                    pager.set_theme_style(TextStyle::Added);
+                    writeln!(pager, "    + {} {new}", change.name)?;
+                }
+                (Some(old), None) => {
+                    pager.set_theme_style(TextStyle::Removed);
+                    writeln!(pager, "    - {} {old}", change.name)?;
+                }
+                (None, None) => {}
+            }
+            pager.set_theme_style(TextStyle::Error);
+            for advisory in &change.advisories {
+                writeln!(pager, "        {}: {}", advisory.id, advisory.title)?;
+            }
+        }
+        pager.end_folding_range();
+    }
+    Ok(())
+}
+
+/// Write the refs of a comparison as (name, commit) pairs, and the base that
+/// the comparison is made modulo, if any.
+pub fn write_comparison(
+    pager: &mut RichPagerSourceBuilder,
+    old: (&str, &str),
+    new: (&str, &str),
+    modulo: Option<(&str, &str)>,
+) -> Result<()> {
+    pager.set_theme_style(TextStyle::Header0);
+    writeln!(pager, "Compare {}..{}", old.0, new.0)?;
+    writeln!(colh(pager, "Old:     "), "{} ({})", old.0, old.1)?;
+    writeln!(colh(pager, "New:     "), "{} ({})", new.0, new.1)?;
+    if let Some(modulo) = modulo {
+        writeln!(coln(pager, "Modulo:  "), "{} ({})", modulo.0, modulo.1)?;
+    }
+    writeln!(pager)?;
+    Ok(())
+}
@@ -0,0 +1,1003 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Review of a pull request.
+//!
+//! The review is shown in a single pager, which is composed of parts that
+//! don't depend on the forge:
+//!
+//! * `header`: summary of the change, its branches and description
+//! * `threads`: reviews and comments, and the activity timeline
+//! * `diff_view`: navigation in the diff (`DiffPagerSource`) and bookmarks
+//!
+//! `Compare` composes the same parts for the diff between two local refs,
+//! without a forge.
+//!
+//! `Review` fetches the pull request from GitHub and feeds the parts. Other
+//! backends (e.g. for changes sent by email or hosted on Gerrit) are meant to
+//! convert their data into the types of the parts and compose them the same
+//! way: write the header and threads into a `RichPagerSourceBuilder`, add the
+//! diff after it, and let a `DiffView` handle keys and panes for the diff.
+
+mod compare;
+mod diff_view;
+mod header;
+mod threads;
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use diff_modulo_base::git;
+use diff_modulo_base::git_core::{self, ChangeRef, ChangeRefKind, MergeTree, Ref};
+use diff_modulo_base::tool::{self, GitDiffModuloBaseArgs, GitDiffModuloBaseOptions};
+use log::warn;
+use ratatui::text::Line;
+use regex::Regex;
+use vctuik::pager::RichPagerSourceBuilder;
+use vctuik::theme::TextStyle;
+use vctuik::{
+    event::KeyCode,
+    label::add_text_label,
+    pager::{Pager, PagerSource, PagerState, RichPagerSource},
+    prelude::*,
+    state::Builder,
+};
+
+use crate::dependencies::{self, Ecosystem};
+use crate::generated;
+use crate::github::{
+    api,
+    edit::{CommentCreate, Edit, PendingReviewAppend, ReviewEvent, ReviewSubmit, TaskCheck},
+    CommitCallback, Response,
+};
+use crate::hooks::{self, Hook};
+use crate::merge_readiness;
+use crate::persistence;
+use crate::review_notes;
+use crate::risk::RiskAssessment;
+use crate::settings::Settings;
+use crate::task_list;
+use crate::{github::connections::Connections, CompletePullRequest};
+
+pub use compare::Compare;
+use diff_view::DiffView;
+use header::Header;
+
+use super::{
+    actions, diff_pager::DiffPagerSource, links, CommentComposer, CommentDraft, ComposerAction,
+    UserCard,
+};
+
+/// How often the pull request is re-fetched to notice new pushes.
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reviews and comments of the pull request, merged in chronological order.
+type MainComments = Arc<Vec<threads::CommentOrReview>>;
+
+#[derive(Debug, Default)]
+struct ReviewState {
+    options: GitDiffModuloBaseOptions,
+    head_pager: RichPagerSource<'static>,
+    diff_pager: DiffPagerSource,
+    diff_view: DiffView,
+    pager_state: PagerState,
+    pr: Option<CompletePullRequest>,
+    dmb_args: Option<GitDiffModuloBaseArgs>,
+
+    /// Show what will actually land, i.e. the forge's test merge of the pull
+    /// request into its target branch, instead of the incremental diff.
+    show_landing: bool,
+
+    /// Show the conflicts of merging the pull request into the tip of its
+    /// target branch instead of the incremental diff.
+    show_conflicts: bool,
+
+    /// Color the unchanged lines of the diff by how long ago they were last
+    /// changed, according to the blame at the head commit.
+    show_age: bool,
+
+    /// Login of the user whose card is shown, if any.
+    user_card: Option<String>,
+
+    /// Comment being written, if the composer is open.
+    comment: Option<CommentDraft>,
+
+    /// Verdict of the review that the composer submits, or None for a plain
+    /// comment.
+    review_event: Option<ReviewEvent>,
+
+    /// Error from submitting the comment.
+    comment_error: Option<String>,
+
+    /// Description of the pull request as shown, and the lines of the pager on
+    /// which the items of its task list are shown.
+    description: Option<String>,
+    task_lines: Vec<usize>,
+
+    /// ID of the user's pending review, to which new comments are added.
+    pending_review: Option<u64>,
+
+    /// Reviews and comments in chronological order, and the generations of
+    /// the responses that they were merged from.
+    main_comments: Option<((u64, u64), MainComments)>,
+
+    /// Head commit for which the new-head hook was last run.
+    hooked_head: Option<String>,
+
+    /// Head commit and number of commits of the pull request as shown.
+    shown_head: Option<(String, Option<u64>)>,
+
+    /// Changed files of the pull request that are generated, as detected at
+    /// the given head commit, including with `.gitattributes`.
+    generated: Option<(String, HashSet<String>)>,
+
+    /// Whether to rebuild on the next frame (but let the next frame be triggered
+    /// by asynchronous API / Git fetch completion).
+    need_rebuild: bool,
+
+    /// Whether to rebuild right away, because the build found something that
+    /// changes what it wrote before, e.g. the size in the header.
+    rebuild_now: bool,
+}
+impl ReviewState {
+    fn update(
+        &mut self,
+        connections: &mut Connections,
+        ep: &dyn git_core::ExecutionProvider,
+        pr: GCow<'_, CompletePullRequest>,
+    ) {
+        let mut keep_pager_state = false;
+
+        if let Some(old_pr) = &mut self.pr {
+            if *old_pr != *pr {
+                *old_pr = pr.into_owned();
+                self.hooked_head = None;
+                self.shown_head = None;
+                self.generated = None;
+                self.diff_view.clear();
+                hooks::run(Hook::PullRequestOpened, hooks::pull_request_context(old_pr));
+            } else {
+                let options_changed = self.dmb_args.as_ref().is_some_and(|args| args.options != self.options);
+                if !self.need_rebuild && !options_changed {
+                    // Just re-use the cached pager data.
+                    return;
+                }
+
+                // If the PR and options stays the same and we're just retrying
+                // because an API or Git fetch timed out last time around, then
+                // we can keep the same pager state.
+                if !options_changed {
+                    keep_pager_state = true;
+                }
+            }
+        } else {
+            hooks::run(Hook::PullRequestOpened, hooks::pull_request_context(&pr));
+            self.pr = Some(pr.into_owned());
+        }
+
+        self.diff_pager = DiffPagerSource::new();
+        if !keep_pager_state {
+            self.pager_state = PagerState::default();
+        }
+        self.need_rebuild = false;
+
+        let mut pager = RichPagerSourceBuilder::new();
+
+        if let Err(err) = self.build(&mut pager, connections, ep) {
+            if ep.timed_out() {
+                pager.set_theme_style(TextStyle::Header2);
+                writeln!(&mut pager, "Generating diff... {err}").unwrap();
+                self.need_rebuild = true;
+            } else {
+                pager.set_theme_style(TextStyle::Error);
+                writeln!(&mut pager, "Error loading review: {err}").unwrap();
+                self.need_rebuild = false;
+            }
+        }
+
+        self.head_pager = pager.build();
+    }
+
+    fn build(
+        &mut self,
+        pager: &mut RichPagerSourceBuilder,
+        connections: &mut Connections,
+        ep: &dyn git_core::ExecutionProvider,
+    ) -> Result<()> {
+        // Fire off all requests.
+        let pr = self.pr.as_ref().unwrap();
+        let mut client = connections.client(&pr.api.host)?.borrow_mut();
+        let user = client.host().user.clone();
+        let client_ref = client.access();
+        let pull = client_ref.pull(&pr.api.owner, &pr.api.name, pr.id);
+        let reviews = client_ref.reviews(&pr.api.owner, &pr.api.name, pr.id);
+        let comments = client_ref.issue_comments(&pr.api.owner, &pr.api.name, pr.id);
+        let timeline = client_ref.timeline(&pr.api.owner, &pr.api.name, pr.id);
+        let files = client_ref.pull_files(&pr.api.owner, &pr.api.name, pr.id);
+
+        let Some(pull) = pull.ok_or_pending()? else {
+            pager.set_theme_style(TextStyle::Header0);
+            writeln!(pager, "Loading pull request...")?;
+            self.need_rebuild = true;
+            return Ok(());
+        };
+        self.shown_head = Some((pull.head.sha.clone(), pull.commits));
+
+        let state = match pull.state {
+            api::PullState::Open => {
+                if pull.draft {
+                    "⚪ Draft"
+                } else {
+                    "🟢 Open"
+                }
+            }
+            api::PullState::Closed => {
+                if pull.merged {
+                    "🟣 Merged"
+                } else {
+                    "🔴 Closed"
+                }
+            }
+            api::PullState::Other => "❓ Unknown",
+        };
+        let settings = Settings::get();
+        let settings = settings.risk(&pr.api.owner, &pr.api.name);
+        let generated = self.generated.as_ref().filter(|(sha, _)| *sha == pull.head.sha);
+        let risk = match (&files, generated) {
+            (Response::Ok(files), Some((_, generated))) => {
+                let is_generated = |path: &str| generated.contains(path);
+                Some(RiskAssessment::with_generated(settings, Some(&pull), files, is_generated))
+            }
+            (Response::Ok(files), None) => Some(RiskAssessment::new(settings, Some(&pull), files)),
+            _ => RiskAssessment::from_totals(&pull),
+        };
+        Header {
+            name: format!("Pull Request {}/{}#{}", pr.api.owner, pr.api.name, pr.id),
+            url: &pull.html_url,
+            title: &pull.title,
+            author: &pull.user.login,
+            state,
+            risk: risk.map(|risk| (risk, settings)),
+        }
+        .write(pager)?;
+
+        let reviews = reviews.ok_or_pending()?;
+        let comments = comments.ok_or_pending()?;
+
+        let readiness = matches!(pull.state, api::PullState::Open)
+            .then(|| {
+                let reviews = reviews.as_deref().map(Vec::as_slice);
+                merge_readiness::load(&client_ref, &pr.api, &pull, reviews)
+            });
+
+        // A review that the user started, e.g. on the website, but didn't
+        // submit yet. Only its author can see it.
+        let pending_review = reviews
+            .iter()
+            .flatten()
+            .find(|review| review.is_pending() && review.user.login == user);
+        self.pending_review = pending_review.map(|review| review.id);
+        let pending_review = pending_review.map(|review| {
+            let comments =
+                client_ref.review_comments(&pr.api.owner, &pr.api.name, pr.id, review.id);
+            (review.body.clone(), comments)
+        });
+
+        // Merging is only redone when the reviews or comments have changed.
+        let main_comments = reviews.as_ref().zip(comments.as_ref()).map(|(reviews, comments)| {
+            let generations = (reviews.generation(), comments.generation());
+            match &self.main_comments {
+                Some((merged_from, merged)) if *merged_from == generations => merged.clone(),
+                _ => {
+                    let merged =
+                        Arc::new(threads::normalize_comments_and_reviews(reviews, comments));
+                    self.main_comments = Some((generations, merged.clone()));
+                    merged
+                }
+            }
+        });
+
+        threads::write_summary(
+            pager,
+            &pr.api.host,
+            main_comments.as_deref().map(Vec::as_slice),
+        )?;
+        if main_comments.is_none() {
+            self.need_rebuild = true;
+        }
+
+        self.task_lines = header::write_branches_and_description(
+            pager,
+            (&pull.head.ref_, &pull.head.sha),
+            (&pull.base.ref_, &pull.base.sha),
+            links::repository(&pull.html_url),
+            pull.body.as_deref(),
+        )?;
+        self.description = pull.body.clone();
+
+        if let Some(comments) = &main_comments {
+            threads::write_thread(pager, &pr.api.host, comments)?;
+        }
+
+        if let Some((body, comments)) = pending_review {
+            let comments = comments.ok_or_pending();
+            let comments = match &comments {
+                Ok(comments) => comments.as_deref().map(Vec::as_slice),
+                Err(err) => {
+                    pager.set_theme_style(TextStyle::Error);
+                    writeln!(pager, "Pending review comments unavailable: {err}")?;
+                    Some(&[][..])
+                }
+            };
+            if comments.is_none() {
+                self.need_rebuild = true;
+            }
+            threads::write_pending_review(pager, &body, comments)?;
+        }
+
+        let most_recent_review = main_comments
+            .as_deref()
+            .into_iter()
+            .flatten()
+            .rev()
+            .filter(|review| review.commit_id.is_some())
+            .find(|review| review.user == user);
+
+        match timeline.ok_or_pending() {
+            Ok(Some(timeline)) => {
+                let last_review = most_recent_review.map(|review| review.submitted_at);
+                threads::write_timeline(pager, &threads::timeline_entries(&timeline), last_review)?;
+            }
+            Err(err) => {
+                pager.set_theme_style(TextStyle::Error);
+                writeln!(pager, "Timeline unavailable: {err}")?;
+                writeln!(pager)?;
+            }
+            Ok(None) => {
+                pager.set_theme_style(TextStyle::Header2);
+                writeln!(pager, "Loading timeline...")?;
+                writeln!(pager)?;
+                self.need_rebuild = true;
+            }
+        }
+
+        let refs: Vec<_> = [&pull.head.sha, &pull.base.sha]
+            .into_iter()
+            .chain(most_recent_review.iter().map(|review| review.commit_id.as_ref().unwrap()))
+            .map(Ref::new)
+            .collect();
+        pr.git.repository.fetch_missing(ep, &pr.git.remote, &refs)?;
+        if self.hooked_head.as_ref() != Some(&pull.head.sha) {
+            self.hooked_head = Some(pull.head.sha.clone());
+            let mut context = hooks::pull_request_context(pr);
+            context["head"] = pull.head.sha.clone().into();
+            context["base"] = pull.base.sha.clone().into();
+            hooks::run(Hook::Checkout, context);
+        }
+
+        // Preview the merge into the current tip of the target branch, which
+        // may have moved on since the pull request was last updated.
+        let head = Ref::new(&pull.head.sha);
+        let target_ref = format!("refs/heads/{}", pull.base.ref_);
+        let target = match pr.git.repository.ls_remote(ep, &pr.git.remote, &target_ref)? {
+            Some(tip) => {
+                pr.git.repository.fetch_missing(ep, &pr.git.remote, std::slice::from_ref(&tip))?;
+                tip
+            }
+            None => Ref::new(&pull.base.sha),
+        };
+        let merge = match pr.git.repository.merge_tree(ep, &target, &head) {
+            Ok(merge) => {
+                header::write_merge_preview(pager, (&pull.base.ref_, &target.name), &merge)?;
+                Some(merge)
+            }
+            Err(err) if ep.timed_out() => return Err(err),
+            Err(err) => {
+                pager.set_theme_style(TextStyle::Error);
+                writeln!(pager, "Merge preview unavailable: {err}")?;
+                None
+            }
+        };
+        match readiness {
+            Some(Ok(Some(items))) => header::write_merge_readiness(pager, &items)?,
+            Some(Ok(None)) => {
+                pager.set_theme_style(TextStyle::Inactive);
+                writeln!(pager, "Ready:   loading...")?;
+                self.need_rebuild = true;
+            }
+            Some(Err(err)) => {
+                pager.set_theme_style(TextStyle::Error);
+                writeln!(pager, "Merge readiness unavailable: {err}")?;
+            }
+            None => {}
+        }
+        writeln!(pager)?;
+
+        if self.show_landing {
+            return self.build_landing(pager, ep, &pull.base.ref_, merge.as_ref());
+        }
+
+        if let Response::Ok(files) = &files {
+            if self.generated.as_ref().is_none_or(|(sha, _)| *sha != pull.head.sha) {
+                let paths: Vec<&str> = files.iter().map(|file| file.filename.as_str()).collect();
+                let detected = generated::detect(&pr.git.repository, ep, &head, settings, &paths)?;
+                // The size in the header was computed with the patterns only.
+                let differs = |path: &&str| {
+                    detected.contains(*path) != generated::matches_patterns(settings, path)
+                };
+                if paths.iter().any(differs) {
+                    self.need_rebuild = true;
+                    self.rebuild_now = true;
+                }
+                self.generated = Some((pull.head.sha.clone(), detected));
+            }
+
+            if files.iter().any(|file| Ecosystem::from_path(&file.filename).is_some()) {
+                let base =
+                    pr.git.repository.merge_base(ep, &Ref::new(&pull.base.sha), &head)?;
+                let review_settings = Settings::get();
+                let advisory_db = review_settings.review.advisory_db.as_deref();
+                let lockfiles: Result<Vec<_>> = files
+                    .iter()
+                    .filter_map(|file| {
+                        dependencies::lockfile_changes(
+                            &pr.git.repository,
+                            ep,
+                            &base..&head,
+                            file,
+                            advisory_db,
+                        )
+                        .transpose()
+                    })
+                    .collect();
+                match lockfiles {
+                    Ok(lockfiles) => header::write_dependency_changes(pager, &lockfiles)?,
+                    Err(err) if ep.timed_out() => return Err(err),
+                    Err(err) => {
+                        pager.set_theme_style(TextStyle::Error);
+                        writeln!(pager, "Dependency changes unavailable: {err}")?;
+                    }
+                }
+                writeln!(pager)?;
+            }
+        }
+
+        if self.show_conflicts {
+            pager.set_theme_style(TextStyle::Header0);
+            let Some(merge) = merge.filter(|merge| merge.has_conflicts()) else {
+                writeln!(pager, "No merge conflicts with {}.", pull.base.ref_)?;
+                return Ok(());
+            };
+            writeln!(pager, "Conflicts when merging into {} ({}):", pull.base.ref_, target)?;
+            let paths: Vec<&[u8]> = merge.conflicts.iter().map(|path| path.as_bytes()).collect();
+            return git::diff_paths(
+                &pr.git.repository,
+                ep,
+                &head,
+                &merge.tree,
+                &paths,
+                &mut self.diff_pager,
+            );
+        }
+
+        pager.set_theme_style(TextStyle::Header0);
+        if let Some(most_recent_review) = &most_recent_review {
+            writeln!(
+                pager,
+                "Diff against most recent review at commit {}:",
+                most_recent_review.commit_id.as_ref().unwrap()
+            )?;
+        } else {
+            writeln!(pager, "Diff against the target branch:")?;
+        }
+
+        let old = if let Some(review) = most_recent_review {
+            review.commit_id.clone().unwrap()
+        } else {
+            pr.git
+                .repository
+                .merge_base(ep, &Ref::new(&pull.base.sha), &Ref::new(&pull.head.sha))?
+                .name
+        };
+
+        if let Some(repo_url) = links::repository(&pull.html_url) {
+            self.diff_pager.set_web_urls(repo_url, &pull.head.sha);
+        }
+
+        let dmb_args = tool::GitDiffModuloBaseArgs {
+            base: Some(pull.base.sha.clone()),
+            old: Some(old),
+            new: Some(pull.head.sha.clone()),
+            options: self.options.clone(),
+        };
+
+        tool::git_diff_modulo_base(&dmb_args, &pr.git.repository, ep, &mut self.diff_pager)?;
+        if let Err(err) = self.diff_pager.verify_signatures(&pr.git.repository, ep) {
+            if ep.timed_out() {
+                return Err(err);
+            }
+            warn!("{err}");
+        }
+        self.diff_pager.detect_generated(&pr.git.repository, ep, &head, settings)?;
+        if self.show_age {
+            self.diff_pager.load_blame(&pr.git.repository, ep, &head)?;
+        }
+
+        self.dmb_args = Some(dmb_args);
+        Ok(())
+    }
+
+    /// Queue checking or unchecking the `index`th item of the task list in the
+    /// description.
+    fn toggle_task(&mut self, connections: &mut Connections, index: usize) {
+        let (Some(pr), Some(description)) = (&self.pr, &self.description) else { return };
+        let Some(task) = task_list::parse(description).into_iter().nth(index) else { return };
+        let edit = TaskCheck {
+            owner: pr.api.owner.clone(),
+            name: pr.api.name.clone(),
+            number: pr.id,
+            task: index,
+            text: task.text.into(),
+            checked: !task.checked,
+        };
+        match connections.edit(&pr.api.host, edit.into(), None) {
+            Ok(()) => self.need_rebuild = true,
+            Err(err) => warn!("{err}"),
+        }
+    }
+
+    /// Check in the background whether the pull request was pushed to since
+    /// it was shown, and describe the update if so.
+    fn poll_update(&self, connections: &mut Connections) -> Option<String> {
+        let pr = self.pr.as_ref()?;
+        let (shown_sha, shown_commits) = self.shown_head.as_ref()?;
+        let mut client = connections.client(&pr.api.host).ok()?.borrow_mut();
+        let pull = client
+            .access()
+            .pull_polled(&pr.api.owner, &pr.api.name, pr.id, POLL_INTERVAL)
+            .ok()
+            .ok()?;
+        if pull.head.sha == *shown_sha {
+            return None;
+        }
+
+        let update = match (*shown_commits, pull.commits) {
+            (Some(old), Some(new)) if new > old => {
+                let count = new - old;
+                format!("{count} new commit{}", if count == 1 { "" } else { "s" })
+            }
+            _ => format!("head is now {}", pull.head.sha.get(..12).unwrap_or(&pull.head.sha)),
+        };
+        Some(format!("PR updated: {update} — press R to reload"))
+    }
+
+    fn build_landing(
+        &mut self,
+        pager: &mut RichPagerSourceBuilder,
+        ep: &dyn git_core::ExecutionProvider,
+        target_branch: &str,
+        preview: Option<&MergeTree>,
+    ) -> Result<()> {
+        let pr = self.pr.as_ref().unwrap();
+        let Some(namespace) = pr.git.repository.change_namespace(ep, &pr.git.remote, pr.id)? else {
+            pager.set_theme_style(TextStyle::Error);
+            writeln!(pager, "Remote {} doesn't publish #{}.", pr.git.remote, pr.id)?;
+            return Ok(());
+        };
+        let change = ChangeRef::new(namespace, pr.id, ChangeRefKind::Merge);
+
+        let Some(merge) = pr.git.repository.fetch_change_ref(ep, &pr.git.remote, &change)? else {
+            pager.set_theme_style(TextStyle::Error);
+            match preview {
+                // The conflicting files are listed in the header.
+                Some(preview) if preview.has_conflicts() => writeln!(
+                    pager,
+                    "No test merge available ({change}): the pull request conflicts with \
+                     {target_branch}. Press X to show the conflicts."
+                )?,
+                _ => writeln!(
+                    pager,
+                    "No test merge available ({change}) yet, although the pull request \
+                     merges cleanly into {target_branch}."
+                )?,
+            }
+            return Ok(());
+        };
+
+        // The forge may have merged into a more recent state of the target
+        // branch than what the pull request metadata says.
+        let target = pr.git.repository.rev_parse(ep, &merge.first_parent())?;
+
+        pager.set_theme_style(TextStyle::Header0);
+        writeln!(
+            pager,
+            "What will land: test merge {} into {} ({}):",
+            merge, target_branch, target
+        )?;
+
+        let dmb_args = tool::GitDiffModuloBaseArgs {
+            base: Some(target.name.clone()),
+            old: Some(target.name),
+            new: Some(merge.name),
+            options: self.options.clone(),
+        };
+
+        tool::git_diff_modulo_base(&dmb_args, &pr.git.repository, ep, &mut self.diff_pager)?;
+
+        self.dmb_args = Some(dmb_args);
+        Ok(())
+    }
+}
+
+/// Find the first `@login` on a line.
+fn find_user_mention(line: &str) -> Option<String> {
+    lazy_static::lazy_static! {
+        static ref RE: Regex = Regex::new(r"@([A-Za-z0-9][A-Za-z0-9-]*(?:\[bot\])?)").unwrap();
+    }
+    RE.captures(line).map(|captures| captures[1].to_string())
+}
+
+pub struct Review<'build> {
+    pr: GCow<'build, CompletePullRequest>,
+    ep: &'build dyn git_core::ExecutionProvider,
+    options: Option<&'build mut GitDiffModuloBaseOptions>,
+    search: Option<&'build Regex>,
+}
+impl<'build> Review<'build> {
+    pub fn new(
+        ep: &'build dyn git_core::ExecutionProvider,
+        pr: impl Into<GCow<'build, CompletePullRequest>>,
+    ) -> Self {
+        Self {
+            pr: pr.into(),
+            ep,
+            options: Default::default(),
+            search: None,
+        }
+    }
+
+    pub fn search(self, search: &'build Regex) -> Self {
+        Self {
+            search: Some(search),
+            ..self
+        }
+    }
+
+    pub fn maybe_search(self, search: Option<&'build Regex>) -> Self {
+        Self { search, ..self }
+    }
+
+    pub fn options(self, options: &'build mut GitDiffModuloBaseOptions) -> Self {
+        Self {
+            options: Some(options),
+            ..self
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut Connections) {
+        let state_id = builder.add_state_id("review");
+        let state: &mut ReviewState = builder.get_state(state_id);
+
+        if let Some(options) = &self.options {
+            if **options != state.options {
+                state.options = (*options).clone();
+                state.need_rebuild = true;
+            }
+        }
+
+        builder
+            .nest()
+            .id(state_id)
+            .build(|builder| {
+                if builder.on_custom::<actions::FocusReview>().is_some() {
+                    builder.grab_focus(state_id);
+                }
+
+                builder.refresh_after(POLL_INTERVAL);
+                let mut update = state.poll_update(connections);
+
+                let has_focus = builder.check_group_focus(state_id);
+                if has_focus {
+                    if builder.on_key_binding(KeyCode::Char('C'), "Combined diff / range diff") {
+                        state.options.combined = !state.options.combined;
+                        if let Some(options) = self.options {
+                            options.combined = state.options.combined;
+                        }
+                        state.need_rebuild = true;
+                    } else if update.is_some()
+                        && builder.on_key_binding(KeyCode::Char('R'), "Reload the updated PR")
+                    {
+                        // Fetches the new head; the pager state is kept, so
+                        // the view stays where it was.
+                        update = None;
+                        state.need_rebuild = true;
+                    } else if builder
+                        .on_key_binding(KeyCode::Char('L'), "Incremental diff / what will land")
+                    {
+                        state.show_landing = !state.show_landing;
+                        state.show_conflicts = false;
+                        state.need_rebuild = true;
+                    } else if builder
+                        .on_key_binding(KeyCode::Char('X'), "Incremental diff / merge conflicts")
+                    {
+                        state.show_conflicts = !state.show_conflicts;
+                        state.show_landing = false;
+                        state.need_rebuild = true;
+                    } else if builder
+                        .on_key_binding(KeyCode::Char('a'), "Color unchanged lines by age")
+                    {
+                        state.show_age = !state.show_age;
+                        state.need_rebuild = true;
+                    }
+                    state.diff_view.handle_layout_keys(builder, &mut state.diff_pager);
+                }
+
+                state.update(connections, self.ep, self.pr);
+                if std::mem::take(&mut state.rebuild_now) {
+                    builder.need_refresh();
+                }
+
+                if let Some(update) = update {
+                    let text = builder.theme().text(builder.theme_context());
+                    add_text_label(builder, Line::styled(update, text.highlight).into());
+                }
+
+                let mut pager = RichPagerSourceBuilder::new();
+                pager.add_child_ref(&state.head_pager);
+                pager.add_child_ref(&state.diff_pager);
+                let pager_source = pager.build();
+
+                state.diff_view.update(&pager_source);
+
+                let mut pager =
+                    Pager::new(&pager_source).minimap(state.diff_view.show_minimap());
+                if let Some(regex) = self.search {
+                    pager = pager.search(Cow::Borrowed(regex));
+                }
+                let mut pager_result = pager.build_with_state(builder, "pager", &mut state.pager_state);
+
+                // The diff follows the header.
+                let offset = state.head_pager.num_lines();
+                state.diff_view.collapse_generated(
+                    builder,
+                    &state.diff_pager,
+                    &mut pager_result,
+                    offset,
+                );
+
+                let mut toggle_task = None;
+                if has_focus {
+                    if let Some(search) = builder.on_custom::<actions::Search>() {
+                        pager_result.search(&search.0, true);
+                        builder.need_refresh();
+                    }
+
+                    state.diff_view.handle_keys(
+                        builder,
+                        &state.diff_pager,
+                        &pager_source,
+                        &mut pager_result,
+                        offset,
+                    );
+                    if let Some(pr) = &state.pr {
+                        state.diff_view.handle_fixup_keys(builder, &pr.git.repository, self.ep);
+                    }
+
+                    if builder.on_key_binding(KeyCode::Char('K'), "Show the mentioned user") {
+                        let line =
+                            pager_source.get_raw_line(pager_result.selected_line(), 0, usize::MAX);
+                        let login = find_user_mention(&line);
+                        state.user_card =
+                            login.filter(|login| state.user_card.as_ref() != Some(login));
+                    } else if state.user_card.is_some()
+                        && builder.on_key_binding(KeyCode::Esc, "Close the user card")
+                    {
+                        state.user_card = None;
+                    } else if builder.on_key_binding(KeyCode::Char('c'), "Write a comment") {
+                        state.comment.get_or_insert_with(CommentDraft::default);
+                        state.review_event = None;
+                        builder.need_refresh();
+                    } else if state.pending_review.is_none()
+                        && builder.on_key_binding(KeyCode::Char('v'), "Approve")
+                    {
+                        state.comment.get_or_insert_with(CommentDraft::default);
+                        state.review_event = Some(ReviewEvent::Approve);
+                        builder.need_refresh();
+                    } else if state.pending_review.is_none()
+                        && builder.on_key_binding(KeyCode::Char('r'), "Request changes")
+                    {
+                        state.comment.get_or_insert_with(CommentDraft::default);
+                        state.review_event = Some(ReviewEvent::RequestChanges);
+                        builder.need_refresh();
+                    } else if builder.on_key_binding(KeyCode::Char('x'), "Check / uncheck the task")
+                    {
+                        let line = pager_result.selected_line();
+                        toggle_task = state.task_lines.iter().position(|l| *l == line);
+                    }
+                }
+                let visible = pager_result.visible_lines();
+                std::mem::drop(pager_result);
+
+                state.diff_view.build_panes(builder, &pager_source);
+                std::mem::drop(pager_source);
+
+                if let Some(task) = toggle_task {
+                    state.toggle_task(connections, task);
+                    builder.need_refresh();
+                }
+
+                // Files of huge diffs are loaded once they are scrolled into view.
+                let visible =
+                    visible.start.saturating_sub(offset)..visible.end.saturating_sub(offset);
+                if state.diff_pager.load_deferred(visible) {
+                    builder.need_refresh();
+                }
+
+                if let (Some(login), Some(pr)) = (&state.user_card, &state.pr) {
+                    UserCard::new(&pr.api.host, login)
+                        .repository(&pr.api.owner, &pr.api.name)
+                        .build(builder, connections);
+                }
+
+                if let (Some(draft), Some(pr)) = (&mut state.comment, &state.pr) {
+                    let action = build_comment_composer(
+                        builder,
+                        connections,
+                        pr,
+                        draft,
+                        state.review_event,
+                        state.pending_review.is_some(),
+                        state.comment_error.as_deref(),
+                    );
+                    if action != ComposerAction::None {
+                        builder.need_refresh();
+                    }
+                    match action {
+                        ComposerAction::None => {}
+                        ComposerAction::Cancel => {
+                            persistence::set_draft(&draft_key(pr), "");
+                            state.comment = None;
+                            state.review_event = None;
+                            state.comment_error = None;
+                        }
+                        // Only approvals can do without a comment.
+                        ComposerAction::Submit(body)
+                            if body.trim().is_empty()
+                                && state.review_event != Some(ReviewEvent::Approve) =>
+                        {
+                            state.comment_error = Some("Comment is empty".into());
+                        }
+                        ComposerAction::Submit(body) => {
+                            let mut context = hooks::pull_request_context(pr);
+                            context["body"] = body.clone().into();
+                            let (owner, name, number) =
+                                (pr.api.owner.clone(), pr.api.name.clone(), pr.id);
+                            let head = state.shown_head.as_ref().map(|(head, _)| head.clone());
+                            let edit: Edit = match (state.review_event, state.pending_review) {
+                                (Some(event), _) => {
+                                    let commit_id = head.clone();
+                                    ReviewSubmit { owner, name, number, event, body, commit_id }
+                                        .into()
+                                }
+                                // Add to the pending review instead of starting
+                                // a separate conversation next to it.
+                                (None, Some(review_id)) => {
+                                    PendingReviewAppend { owner, name, number, review_id, body }
+                                        .into()
+                                }
+                                (None, None) => CommentCreate { owner, name, number, body }.into(),
+                            };
+
+                            // Run the hook and record the review once it has
+                            // actually been posted.
+                            let on_committed: Option<CommitCallback> = match &edit {
+                                Edit::ReviewSubmit(review) => {
+                                    let (pr, event) = (pr.clone(), review.event);
+                                    Some(Box::new(move || {
+                                        if let Some(head) = head {
+                                            let ep = git_core::SimpleExecutionProvider;
+                                            let result =
+                                                review_notes::record(&pr, &ep, &head, event);
+                                            if let Err(err) = result {
+                                                warn!("{err}");
+                                            }
+                                        }
+                                        hooks::run(Hook::ReviewSubmitted, context);
+                                    }))
+                                }
+                                Edit::CommentCreate(_) => Some(Box::new(move || {
+                                    hooks::run(Hook::ReviewSubmitted, context)
+                                })),
+                                _ => None,
+                            };
+                            let result = match on_committed {
+                                Some(on_committed) => {
+                                    connections.edit_then(&pr.api.host, edit, None, on_committed)
+                                }
+                                None => connections.edit(&pr.api.host, edit, None),
+                            };
+                            match result {
+                                Ok(()) => {
+                                    persistence::set_draft(&draft_key(pr), "");
+                                    state.comment = None;
+                                    state.review_event = None;
+                                    state.comment_error = None;
+                                    state.need_rebuild = true;
+                                }
+                                Err(err) => {
+                                    state.comment_error =
+                                        Some(format!("Failed to submit comment: {err}"));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Drafts are kept in the journal for crash recovery. A draft
+                // that was recovered reopens the composer.
+                if let Some(pr) = &state.pr {
+                    let key = draft_key(pr);
+                    match &state.comment {
+                        Some(draft) => persistence::set_draft(&key, &draft.text()),
+                        None => {
+                            if let Some(text) = persistence::take_draft(&key) {
+                                state.comment = Some(CommentDraft::from_text(&text));
+                                builder.need_refresh();
+                            }
+                        }
+                    }
+                }
+            });
+    }
+}
+
+/// Key of the draft of a comment on the pull request.
+fn draft_key(pr: &CompletePullRequest) -> String {
+    format!("comment/{}/{}/{}#{}", pr.api.host, pr.api.owner, pr.api.name, pr.id)
+}
+
+fn build_comment_composer(
+    builder: &mut Builder,
+    connections: &mut Connections,
+    pr: &CompletePullRequest,
+    draft: &mut CommentDraft,
+    review_event: Option<ReviewEvent>,
+    pending_review: bool,
+    error: Option<&str>,
+) -> ComposerAction {
+    let author = connections
+        .client(&pr.api.host)
+        .ok()
+        .and_then(|client| {
+            client
+                .borrow_mut()
+                .access()
+                .pull(&pr.api.owner, &pr.api.name, pr.id)
+                .ok()
+                .ok()
+        })
+        .map(|pull| pull.user.login.clone())
+        .unwrap_or_default();
+    let repo = format!("{}/{}", pr.api.owner, pr.api.name);
+    let number = pr.id.to_string();
+    let values = [
+        ("author", author.as_str()),
+        ("repo", repo.as_str()),
+        ("number", number.as_str()),
+    ];
+
+    let title = match review_event {
+        Some(ReviewEvent::Approve) => format!("Approve {repo}#{number}"),
+        Some(ReviewEvent::RequestChanges) => format!("Request changes on {repo}#{number}"),
+        Some(ReviewEvent::Comment) => format!("Review {repo}#{number}"),
+        None if pending_review => format!("Add to your pending review of {repo}#{number}"),
+        None => format!("Comment on {repo}#{number}"),
+    };
+    let settings = Settings::get();
+    CommentComposer::new(&title)
+        .error(error)
+        .snippets(settings.snippets(&pr.api.owner, &pr.api.name), &values)
+        .build(builder, draft)
+}
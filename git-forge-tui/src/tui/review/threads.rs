@@ -0,0 +1,324 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::fmt::Write;
+
+use vctuik::{pager::RichPagerSourceBuilder, prelude::*, theme::TextStyle};
+
+use crate::{github::api, tui::identicon};
+
+/// A review or top-level comment on a change.
+#[derive(Debug)]
+pub struct CommentOrReview {
+    pub user: String,
+    pub submitted_at: chrono::DateTime<chrono::Utc>,
+    pub body: String,
+    pub commit_id: Option<String>,
+    pub review_state: Option<api::ReviewState>,
+}
+impl CommentOrReview {
+    fn is_significant_review(&self) -> bool {
+        if let Some(state) = &self.review_state {
+            state.is_significant()
+        } else {
+            false
+        }
+    }
+}
+
+/// Merge the reviews and comments of a GitHub pull request in chronological
+/// order.
+///
+/// Pending reviews are skipped, see [`write_pending_review`].
+pub fn normalize_comments_and_reviews(
+    reviews: &[api::Review],
+    comments: &[api::Comment],
+) -> Vec<CommentOrReview> {
+    let mut items: Vec<CommentOrReview> = Vec::new();
+
+    for review in reviews.iter().filter(|review| !review.is_pending()) {
+        let submitted_at = review.submitted_at().unwrap();
+        items.push(CommentOrReview {
+            user: review.user.login.clone(),
+            submitted_at,
+            body: review.body.clone(),
+            commit_id: review.commit_id.clone(),
+            review_state: Some(review.state),
+        });
+    }
+
+    for comment in comments {
+        let created_at = comment.created_at().unwrap();
+        items.push(CommentOrReview {
+            user: comment.user.login.clone(),
+            submitted_at: created_at,
+            body: comment.body.clone(),
+            commit_id: None,
+            review_state: None,
+        });
+    }
+
+    items.sort_by_key(|item| item.submitted_at);
+    items
+}
+
+/// Write the most recent review or comment by each user on a host.
+///
+/// `comments` is `None` while they are still loading.
+pub fn write_summary(
+    pager: &mut RichPagerSourceBuilder,
+    host: &str,
+    comments: Option<&[CommentOrReview]>,
+) -> Result<()> {
+    pager.set_theme_style(TextStyle::Header2);
+    let Some(comments) = comments else {
+        writeln!(pager, "Loading reviews and comments...")?;
+        return Ok(());
+    };
+    if comments.is_empty() {
+        writeln!(pager, "No reviews or comments yet")?;
+        return Ok(());
+    }
+
+    // Keep only the most recent review or comment by each user,
+    // except we also keep the most significant reviews
+    // (approved / changes requested).
+    let mut filtered: Vec<&CommentOrReview> = Vec::new();
+    let mut max_user_len = 0;
+    for c in comments.iter().rev() {
+        if !filtered.iter().any(|f| {
+            f.user == c.user &&
+            (f.is_significant_review() || !c.is_significant_review())
+        }) {
+            filtered.push(c);
+            max_user_len = max_user_len.max(c.user.len());
+        }
+    }
+
+    writeln!(pager, "Most recent reviews and comments by user:")?;
+    for c in filtered.into_iter().rev() {
+        let state = match c.review_state {
+            Some(api::ReviewState::Approved) => "✅",
+            Some(api::ReviewState::ChangesRequested) => "❌",
+            Some(api::ReviewState::Commented) |
+            Some(api::ReviewState::Dismissed) | None => "💬",
+            Some(api::ReviewState::Pending) | Some(api::ReviewState::Other) => "❓",
+        };
+
+        write!(pager, "  ")?;
+        identicon::write(pager, host, &c.user)?;
+        pager.set_theme_style(TextStyle::Highlight);
+        write!(pager, "@{:<max_user_len$}", c.user)?;
+        pager.set_theme_style(TextStyle::Normal);
+        writeln!(
+            pager,
+            " {} {}{}",
+            state,
+            c.submitted_at,
+            if let Some(commit_id) = c.commit_id.as_ref() {
+                format!(" (at {})", commit_id)
+            } else {
+                String::new()
+            }
+        )?;
+    }
+    Ok(())
+}
+
+/// Write all reviews and comments on a host as a foldable thread.
+pub fn write_thread(
+    pager: &mut RichPagerSourceBuilder,
+    host: &str,
+    comments: &[CommentOrReview],
+) -> Result<()> {
+    if comments.is_empty() {
+        return Ok(());
+    }
+
+    pager.set_theme_style(TextStyle::Header0);
+    pager.begin_folding_range();
+    writeln!(pager, "Comment Thread:")?;
+    writeln!(pager)?;
+
+    let mut have_body = false;
+    for c in comments {
+        if have_body {
+            writeln!(pager)?;
+            pager.end_folding_range(); // end previous comment
+        }
+
+        have_body = !c.body.trim().is_empty();
+        if have_body {
+            pager.begin_folding_range();
+        }
+
+        let state_str = match c.review_state {
+            Some(api::ReviewState::Approved) => "approved",
+            Some(api::ReviewState::ChangesRequested) => "requested changes",
+            Some(api::ReviewState::Dismissed) => "dismissed an earlier review",
+            Some(_) => "reviewed",
+            _ => "commented",
+        };
+        write!(pager, "    ")?;
+        identicon::write(pager, host, &c.user)?;
+        pager.set_theme_style(TextStyle::Highlight);
+        write!(pager, "@{}", c.user)?;
+        pager.set_theme_style(TextStyle::Header1);
+        writeln!(
+            pager,
+            " {} at {}{}{}",
+            state_str,
+            c.submitted_at,
+            if let Some(commit_id) = c.commit_id.as_ref() {
+                format!(" (at {})", commit_id)
+            } else {
+                String::new()
+            },
+            if have_body { ":" } else { "" }
+        )?;
+
+        if have_body {
+            pager.set_indent(8);
+            pager.clear_style();
+            writeln!(pager, "{}", c.body)?;
+            pager.set_indent(0);
+        }
+    }
+    if have_body {
+        pager.end_folding_range(); // end last comment (if it has a body)
+    }
+
+    pager.end_folding_range(); // end comment thread
+    writeln!(pager)?;
+    Ok(())
+}
+
+/// Write the user's own review that was started but not submitted yet, e.g.
+/// on the website.
+///
+/// `comments` is `None` while they are still loading.
+pub fn write_pending_review(
+    pager: &mut RichPagerSourceBuilder,
+    body: &str,
+    comments: Option<&[api::ReviewComment]>,
+) -> Result<()> {
+    pager.set_theme_style(TextStyle::Header0);
+    pager.begin_folding_range();
+    writeln!(pager, "Your pending review (not submitted yet):")?;
+
+    if !body.trim().is_empty() {
+        pager.set_indent(4);
+        pager.clear_style();
+        writeln!(pager, "{}", body.trim_end())?;
+        pager.set_indent(0);
+    }
+
+    match comments {
+        None => {
+            pager.set_theme_style(TextStyle::Header2);
+            writeln!(pager, "    Loading comments...")?;
+        }
+        Some(comments) => {
+            for comment in comments {
+                pager.set_theme_style(TextStyle::Highlight);
+                match comment.line {
+                    Some(line) => writeln!(pager, "    {}:{}:", comment.path, line)?,
+                    None => writeln!(pager, "    {} (outdated):", comment.path)?,
+                }
+                pager.set_indent(8);
+                pager.clear_style();
+                writeln!(pager, "{}", comment.body.trim_end())?;
+                pager.set_indent(0);
+            }
+        }
+    }
+
+    pager.set_theme_style(TextStyle::Inactive);
+    writeln!(pager, "    New comments are added to this review. Submit it on the website.")?;
+    pager.end_folding_range();
+    writeln!(pager)?;
+    Ok(())
+}
+
+/// An entry of the activity timeline of a change.
+#[derive(Debug)]
+pub struct TimelineEntry {
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub who: Option<String>,
+    pub description: String,
+}
+
+/// Convert the timeline of a GitHub issue or pull request.
+pub fn timeline_entries(timeline: &[api::TimelineEvent]) -> Vec<TimelineEntry> {
+    timeline
+        .iter()
+        .map(|event| {
+            let description = match event.event.as_str() {
+                "committed" => format!(
+                    "committed {} {}",
+                    event.sha.as_deref().map(|sha| &sha[..sha.len().min(12)]).unwrap_or("?"),
+                    event.message.as_deref().and_then(|msg| msg.lines().next()).unwrap_or(""),
+                ),
+                "reviewed" => match event.state.as_deref() {
+                    Some("approved") => "approved".into(),
+                    Some("changes_requested") => "requested changes".into(),
+                    Some("dismissed") => "reviewed (dismissed)".into(),
+                    _ => "reviewed".into(),
+                },
+                "commented" => "commented".into(),
+                "labeled" | "unlabeled" => format!(
+                    "{} {}",
+                    event.event,
+                    event.label.as_ref().map(|label| label.name.as_str()).unwrap_or("?"),
+                ),
+                "head_ref_force_pushed" => format!(
+                    "force-pushed{}",
+                    event.commit_id.as_deref().map(|id| format!(" to {id}")).unwrap_or_default(),
+                ),
+                other => other.replace('_', " "),
+            };
+            TimelineEntry {
+                timestamp: event.timestamp(),
+                who: event.who().map(str::to_string),
+                description,
+            }
+        })
+        .collect()
+}
+
+/// Write the activity timeline, marking the position of the user's most
+/// recent review.
+pub fn write_timeline(
+    pager: &mut RichPagerSourceBuilder,
+    timeline: &[TimelineEntry],
+    last_review: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<()> {
+    pager.set_theme_style(TextStyle::Header0);
+    pager.begin_folding_range();
+    writeln!(pager, "Activity Timeline:")?;
+
+    let mut marked_last_review = last_review.is_none();
+    for entry in timeline {
+        let timestamp = entry.timestamp;
+
+        if !marked_last_review && timestamp.is_some_and(|t| Some(t) > last_review) {
+            pager.set_theme_style(TextStyle::Header1);
+            writeln!(pager, "    --- your most recent review ---")?;
+            marked_last_review = true;
+        }
+
+        pager.set_theme_style(TextStyle::Normal);
+        write!(
+            pager,
+            "    {} ",
+            timestamp.map(|t| t.to_string()).unwrap_or_else(|| "?".into())
+        )?;
+        pager.set_theme_style(TextStyle::Highlight);
+        write!(pager, "@{}", entry.who.as_deref().unwrap_or("?"))?;
+        pager.set_theme_style(TextStyle::Normal);
+        writeln!(pager, " {}", entry.description)?;
+    }
+
+    pager.end_folding_range();
+    writeln!(pager)?;
+    Ok(())
+}
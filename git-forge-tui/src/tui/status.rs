@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctuik::{
+    state::Builder,
+    status_line::{Segment, StatusLine},
+    theme::TextStyle,
+};
+
+use crate::github::connections::Connections;
+
+/// Status line with the context that is shared by the forge TUIs: account,
+/// repository, pull request, head commit, rate limit, and background work.
+#[derive(Debug, Default)]
+pub struct ForgeStatus<'build> {
+    host: Option<&'build str>,
+    repository: Option<(&'build str, &'build str)>,
+    pull: Option<u64>,
+    updated: Option<i64>,
+}
+impl<'build> ForgeStatus<'build> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn host(self, host: &'build str) -> Self {
+        Self {
+            host: Some(host),
+            ..self
+        }
+    }
+
+    pub fn repository(self, owner: &'build str, name: &'build str) -> Self {
+        Self {
+            repository: Some((owner, name)),
+            ..self
+        }
+    }
+
+    pub fn pull(self, pull: u64) -> Self {
+        Self {
+            pull: Some(pull),
+            ..self
+        }
+    }
+
+    /// Show the full time of the last update of the selection, as a Unix
+    /// timestamp, to complement the relative times shown elsewhere.
+    pub fn updated(self, timestamp: i64) -> Self {
+        Self {
+            updated: Some(timestamp),
+            ..self
+        }
+    }
+
+    /// Must be called between `start_frame` and `end_frame` of the
+    /// connections.
+    pub fn build(self, builder: &mut Builder, connections: &mut Connections) {
+        let mut account = None;
+        let mut head = None;
+        let mut rate_limit = None;
+        if let Some(client) = self.host.and_then(|host| connections.client(host).ok()) {
+            let mut client = client.borrow_mut();
+            account = Some(format!("{}@{}", client.host().user, client.host().host));
+            rate_limit = client.rate_limit();
+            if let (Some((owner, name)), Some(pull)) = (self.repository, self.pull) {
+                head = client
+                    .prefetch()
+                    .pull(owner, name, pull)
+                    .ok_or_pending()
+                    .ok()
+                    .flatten()
+                    .map(|pull| pull.head.sha.clone());
+            }
+        }
+
+        // Without a specific host, show the most constrained rate limit.
+        let mut pending = 0;
+        for (_, client) in connections.all_clients() {
+            let Ok(client) = client else { continue };
+            let client = client.borrow();
+            pending += client.pending_tasks();
+            if self.host.is_none() {
+                rate_limit = match (rate_limit, client.rate_limit()) {
+                    (Some(a), Some(b)) => Some(if b.remaining < a.remaining { b } else { a }),
+                    (a, b) => a.or(b),
+                };
+            }
+        }
+
+        StatusLine::new()
+            .maybe_segment(account.map(|account| Segment::new(account).priority(1)))
+            .maybe_segment(self.repository.map(|(owner, name)| {
+                Segment::new(format!("{owner}/{name}")).priority(3)
+            }))
+            .maybe_segment(self.pull.map(|pull| {
+                Segment::new(format!("#{pull}")).style(TextStyle::Highlight).priority(4)
+            }))
+            .maybe_segment(head.map(|sha| {
+                Segment::new(sha[..sha.len().min(12)].to_string()).label("head")
+            }))
+            .maybe_segment(self.updated.map(|timestamp| {
+                Segment::new(vctools_utils::time::format_iso(timestamp)).label("updated")
+            }))
+            .maybe_segment((pending > 0).then(|| {
+                Segment::new(format!("{pending} pending")).right().priority(2)
+            }))
+            .maybe_segment(rate_limit.map(|rate_limit| {
+                let style = if rate_limit.remaining * 10 < rate_limit.limit {
+                    TextStyle::Error
+                } else {
+                    TextStyle::Normal
+                };
+                Segment::new(format!("{}/{}", rate_limit.remaining, rate_limit.limit))
+                    .label("API")
+                    .style(style)
+                    .right()
+                    .priority(2)
+            }))
+            .build(builder);
+    }
+}
@@ -0,0 +1,255 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use vctools_utils::time;
+use vctuik::{
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, async_source::AsyncTableSource, simple_table},
+};
+
+use crate::{
+    github::{self, api, Shared},
+    settings::{ColumnKind, Settings, TableView},
+    ApiRepository,
+};
+
+use super::columns;
+
+type SearchResponse =
+    std::result::Result<Option<Shared<api::SearchResult<api::IssueSummary>>>, Cow<'static, str>>;
+
+/// Label of a group of pull requests, with their number once it is known.
+fn with_count(label: &str, response: &SearchResponse) -> String {
+    match response {
+        Ok(Some(result)) => format!("{label} ({})", result.total_count),
+        _ => label.to_string(),
+    }
+}
+
+/// Items of the tree that need extra handling.
+#[derive(Debug, Default)]
+struct Items {
+    now: i64,
+    columns: Vec<ColumnKind>,
+    pulls: HashMap<u64, (ApiRepository, u64)>,
+    loading: Vec<u64>,
+    failed: Vec<(u64, String)>,
+}
+impl Items {
+    /// Add the pull requests of a search result as children of `parent`, or
+    /// mark it as loading or failed.
+    fn add_pulls(
+        &mut self,
+        table_builder: &mut simple_table::SourceBuilder<'_, '_, (String, String)>,
+        client: &mut github::ClientRef,
+        parent: u64,
+        host: &str,
+        response: SearchResponse,
+    ) {
+        let result = match response {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                self.loading.push(parent);
+                return;
+            }
+            Err(err) => {
+                self.failed.push((parent, err.into_owned()));
+                return;
+            }
+        };
+        for issue in &result.items {
+            let Some((owner, name)) = issue.repository() else { continue };
+            let reference = format!("{owner}/{name}#{}", issue.number);
+            let item = table_builder
+                .add(parent, (host.to_string(), reference.clone()))
+                .raw(0, issue.title.clone())
+                .raw(1, reference);
+            let item = match issue.html_url.as_str() {
+                "" => item,
+                url => item.link(1, url.to_string()),
+            };
+            let item_id = columns::add_issue_cells(
+                item,
+                &self.columns,
+                issue,
+                (host, owner, name),
+                client,
+                self.now,
+            )
+            .id();
+            let repo = ApiRepository::new(host.to_string(), owner.into(), name.into());
+            self.pulls.insert(item_id, (repo, issue.number));
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<(String, String)>,
+
+    /// Teams as (host, organization, team slug) whose review requests have
+    /// been asked for by expanding them.
+    requested: HashSet<(String, String, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TeamsResult {
+    /// Whether focus is on this widget
+    pub has_focus: bool,
+
+    /// The selected pull request, if any.
+    pub selection: Option<(ApiRepository, u64)>,
+}
+
+/// Lists the organizations and teams of the user on every host, with the
+/// pull requests whose review is requested from each team, next to the ones
+/// requested from the user personally.
+///
+/// The review requests of a team are only loaded once it is expanded.
+#[derive(Debug, Default)]
+pub struct Teams {}
+impl Teams {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(
+        self,
+        builder: &mut Builder,
+        connections: &mut github::connections::Connections,
+    ) -> TeamsResult {
+        let state_id = builder.add_state_id("teams");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let host_style = table_builder.add_style(text.header1);
+        let org_style = table_builder.add_style(text.header2);
+        let error_style = table_builder.add_style(text.error);
+        let inactive_style = table_builder.add_style(text.inactive);
+
+        let mut items = Items {
+            now: time::unix_now(),
+            columns: Settings::get().columns.get(TableView::Pulls).to_vec(),
+            ..Default::default()
+        };
+        let mut teams: HashMap<u64, (String, String, String)> = HashMap::new();
+
+        for (host, client) in connections.all_clients() {
+            let host_id = table_builder
+                .add(0, (host.host.clone(), String::new()))
+                .styled(0, &host.host, host_style)
+                .group()
+                .id();
+            let client = match client {
+                Ok(client) => client,
+                Err(err) => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "error".into()))
+                        .styled(0, err.to_string(), error_style);
+                    continue;
+                }
+            };
+            let mut client = client.borrow_mut();
+            let mut access = client.access();
+
+            let personal = access.user_review_requests(&host.user).ok_or_pending();
+            let personal_id = table_builder
+                .add(host_id, (host.host.clone(), "@me".into()))
+                .raw(0, with_count("Requested from me", &personal))
+                .id();
+            items.add_pulls(&mut table_builder, &mut access, personal_id, &host.host, personal);
+
+            // Organizations without teams are listed, too, so that it's clear
+            // that they were considered.
+            let my_teams = access.my_teams().ok_or_pending();
+            let my_orgs = access.my_orgs().ok_or_pending();
+            let (my_teams, mut orgs) = match (my_teams, my_orgs) {
+                (Ok(Some(teams)), Ok(Some(orgs))) => {
+                    (teams, orgs.iter().map(|org| org.login.clone()).collect::<Vec<_>>())
+                }
+                (Err(err), _) | (_, Err(err)) => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "error".into()))
+                        .styled(0, err, error_style);
+                    continue;
+                }
+                _ => {
+                    table_builder
+                        .add(host_id, (host.host.clone(), "loading".into()))
+                        .styled(0, "Loading teams...", inactive_style);
+                    continue;
+                }
+            };
+            for team in &my_teams {
+                if !orgs.contains(&team.organization.login) {
+                    orgs.push(team.organization.login.clone());
+                }
+            }
+
+            for org in &orgs {
+                let org_id = table_builder
+                    .add(host_id, (host.host.clone(), org.clone()))
+                    .styled(0, org.clone(), org_style)
+                    .group()
+                    .id();
+                for team in my_teams.iter().filter(|team| team.organization.login == *org) {
+                    let key = (host.host.clone(), org.clone(), team.slug.clone());
+                    let response = if state.requested.contains(&key) {
+                        access.team_review_requests(org, &team.slug).ok_or_pending()
+                    } else {
+                        Ok(None)
+                    };
+                    let team_id = table_builder
+                        .add(org_id, (host.host.clone(), format!("{org}/{}", team.slug)))
+                        .raw(0, with_count(&team.name, &response))
+                        .id();
+                    items.add_pulls(&mut table_builder, &mut access, team_id, &host.host, response);
+                    teams.insert(team_id, key);
+                }
+            }
+        }
+
+        let mut columns = vec![
+            table::Column::new(0, "", Constraint1D::unconstrained()),
+            table::Column::new(1, "Pull Request", Constraint1D::new(8, 40)),
+        ];
+        columns.extend(columns::table_columns(&items.columns));
+
+        let mut expanded = Vec::new();
+        let table_result = builder.nest().id(state_id).build(|builder| {
+            let mut source = AsyncTableSource::new(table_builder.finish())
+                .on_request(|item_id| expanded.push(item_id));
+            for item_id in items.loading {
+                source.loading(item_id);
+            }
+            for (item_id, message) in items.failed {
+                source.failed(item_id, message);
+            }
+            table::Table::new(&source)
+                .id("tree")
+                .columns(columns)
+                .filter_column(0)
+                .build(builder)
+        });
+
+        // Start loading the review requests of teams that were expanded.
+        for item_id in expanded {
+            if let Some(key) = teams.get(&item_id) {
+                if state.requested.insert(key.clone()) {
+                    builder.need_refresh();
+                }
+            }
+        }
+
+        TeamsResult {
+            has_focus: table_result.has_focus,
+            selection: table_result.selection.and_then(|item_id| items.pulls.remove(&item_id)),
+        }
+    }
+}
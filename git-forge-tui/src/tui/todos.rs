@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctools_utils::time;
+use vctuik::{
+    event::KeyCode,
+    layout::Constraint1D,
+    state::Builder,
+    table::{self, simple_table},
+};
+
+use crate::{browser, github, todos::TodoState, tui::identicon, ApiRepository};
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<usize>,
+
+    /// Also list items that are done.
+    show_done: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TodosResult {
+    /// Whether focus is on this widget
+    pub has_focus: bool,
+
+    /// The pull request of the selected item, if any.
+    pub selection: Option<(ApiRepository, u64)>,
+
+    pub error: Option<String>,
+}
+
+/// Lists the actionable items found in review discussions, with a local done
+/// state.
+///
+/// The review threads of the pull request that is currently shown are
+/// scanned for new items.
+pub struct Todos<'build> {
+    todos: &'build mut TodoState,
+    pull: Option<(&'build ApiRepository, u64)>,
+}
+impl<'build> Todos<'build> {
+    pub fn new(todos: &'build mut TodoState) -> Self {
+        Self { todos, pull: None }
+    }
+
+    /// Scan the review threads of this pull request.
+    pub fn pull(self, pull: Option<(&'build ApiRepository, u64)>) -> Self {
+        Self { pull, ..self }
+    }
+
+    pub fn build(
+        self,
+        builder: &mut Builder,
+        connections: &mut github::connections::Connections,
+    ) -> TodosResult {
+        let todos = self.todos;
+        let mut result = TodosResult::default();
+
+        if let Some((repo, number)) = self.pull {
+            if let Ok(client) = connections.client(&repo.host) {
+                let mut client = client.borrow_mut();
+                let user = client.host().user.clone();
+                let threads = client.access().review_threads(&repo.owner, &repo.name, number);
+                if let Ok(threads) = threads.ok() {
+                    if let Err(err) = todos.collect(repo, number, &threads, &user) {
+                        result.error = Some(err.to_string());
+                    }
+                }
+            }
+        }
+
+        let state_id = builder.add_state_id("todos");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let inactive_style = table_builder.add_style(text.inactive);
+
+        let now = time::unix_now();
+        let mut item_ids = Vec::new();
+        for (idx, todo) in todos.items().iter().enumerate() {
+            if todo.done && !state.show_done {
+                continue;
+            }
+            let reference = format!("{}/{}#{}", todo.owner, todo.name, todo.number);
+            let age = chrono::DateTime::parse_from_rfc3339(&todo.created_at)
+                .map(|created| time::format_relative(created.timestamp(), now))
+                .unwrap_or_default();
+            let item = table_builder.add(0, idx);
+            let item = if todo.done {
+                item.styled(0, format!("✓ {}", todo.text), inactive_style)
+                    .styled(1, identicon::mention(&todo.host, &todo.author), inactive_style)
+                    .styled(2, reference, inactive_style)
+                    .styled(3, age, inactive_style)
+            } else {
+                item.raw(0, todo.text.clone())
+                    .raw(1, identicon::mention(&todo.host, &todo.author))
+                    .raw(2, reference)
+                    .raw(3, age)
+            };
+            item_ids.push((item.id(), idx));
+        }
+        if item_ids.is_empty() {
+            table_builder.add(0, usize::MAX).styled(0, "(nothing to do)", inactive_style);
+        }
+
+        let columns = vec![
+            table::Column::new(0, "", Constraint1D::unconstrained()),
+            table::Column::new(1, "By", Constraint1D::new(4, 20)),
+            table::Column::new(2, "Pull Request", Constraint1D::new(8, 40)),
+            table::Column::new(3, "Age", Constraint1D::new(5, 20)),
+        ];
+
+        builder.nest().id(state_id).build(|builder| {
+            let table_result = table::Table::new(&table_builder.finish())
+                .id("todos")
+                .columns(columns)
+                .filter_column(0)
+                .build(builder);
+            let selected = table_result
+                .selection
+                .and_then(|id| item_ids.iter().find(|(item_id, _)| *item_id == id))
+                .map(|(_, idx)| *idx);
+            result.has_focus = table_result.has_focus;
+            result.selection = selected.map(|idx| {
+                let todo = &todos.items()[idx];
+                (todo.repository(), todo.number)
+            });
+
+            if !table_result.has_focus {
+                return;
+            }
+            if builder.on_key_binding(KeyCode::Char('a'), "Show / hide done items") {
+                state.show_done = !state.show_done;
+                builder.need_refresh();
+            } else if builder.on_key_binding(KeyCode::Char('D'), "Forget done items") {
+                if let Err(err) = todos.remove_done() {
+                    result.error = Some(err.to_string());
+                }
+                builder.need_refresh();
+            }
+            let Some(idx) = selected else { return };
+            if builder.on_key_binding(KeyCode::Char(' '), "Done / not done") {
+                if let Err(err) = todos.toggle_done(idx) {
+                    result.error = Some(err.to_string());
+                }
+                builder.need_refresh();
+            } else if builder.on_key_binding(KeyCode::Char('o'), "Open in the browser") {
+                if let Err(err) = browser::open_url(&todos.items()[idx].url) {
+                    result.error = Some(err.to_string());
+                }
+            }
+        });
+
+        result
+    }
+}
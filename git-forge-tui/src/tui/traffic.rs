@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+use vctuik::{
+    layout::Constraint1D,
+    pager::{Pager, StringPagerSource},
+    state::Builder,
+    table::{self, simple_table},
+};
+
+use crate::github::{self, TrafficEntry};
+
+#[derive(Debug, Default)]
+struct State {
+    table_state: simple_table::SourceState<(String, String)>,
+}
+
+/// Lists recent API requests of all clients, most recent first, and shows the
+/// raw response of the selected request.
+#[derive(Debug, Default)]
+pub struct TrafficInspector {}
+impl TrafficInspector {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let state_id = builder.add_state_id("traffic");
+        let state: &mut State = builder.get_state(state_id);
+
+        let text = builder.theme().text(builder.theme_context());
+        let mut table_builder = state.table_state.build();
+        let error_style = table_builder.add_style(text.error);
+        let inactive_style = table_builder.add_style(text.inactive);
+
+        let mut traffic: Vec<(String, TrafficEntry)> = Vec::new();
+        for (host, client) in connections.all_clients() {
+            let Ok(client) = client else { continue };
+            traffic.extend(
+                client
+                    .borrow()
+                    .traffic()
+                    .into_iter()
+                    .map(|entry| (host.host.clone(), entry)),
+            );
+        }
+        traffic.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.started));
+
+        let mut entries: HashMap<u64, usize> = HashMap::new();
+        for (idx, (host, entry)) in traffic.iter().enumerate() {
+            let key = (host.clone(), format!("{}@{}", entry.url, entry.started.to_rfc3339()));
+            let item = table_builder
+                .add(0, key)
+                .raw(0, entry.started.format("%H:%M:%S").to_string())
+                .raw(1, host.clone());
+            let item = match entry.status {
+                Some(status) if status < 400 => item.raw(2, status.to_string()),
+                Some(status) => item.styled(2, status.to_string(), error_style),
+                None => item.styled(2, "-", inactive_style),
+            };
+            let item = item
+                .raw(3, format!("{} ms", entry.duration.as_millis()))
+                .raw(4, if entry.cached { "hit" } else { "miss" })
+                .raw(5, entry.bytes.to_string())
+                .raw(6, entry.url.clone());
+            entries.insert(item.id(), idx);
+        }
+
+        if traffic.is_empty() {
+            table_builder
+                .add(0, (String::new(), String::new()))
+                .raw(6, "No requests yet");
+        }
+
+        let columns = vec![
+            table::Column::new(0, "Time", Constraint1D::new_fixed(8)),
+            table::Column::new(1, "Host", Constraint1D::new(4, 20)),
+            table::Column::new(2, "Status", Constraint1D::new_fixed(6)),
+            table::Column::new(3, "Duration", Constraint1D::new_fixed(9)),
+            table::Column::new(4, "Cache", Constraint1D::new_fixed(5)),
+            table::Column::new(5, "Bytes", Constraint1D::new_fixed(8)),
+            table::Column::new(6, "URL", Constraint1D::unconstrained()),
+        ];
+        builder.nest().id(state_id).build(|builder| {
+            let table_result = table::Table::new(&table_builder.finish())
+                .id("requests")
+                .columns(columns)
+                .build(builder);
+
+            let selected = table_result
+                .selection
+                .and_then(|item| entries.get(&item))
+                .map(|&idx| &traffic[idx].1);
+            if let Some(entry) = selected {
+                let source = StringPagerSource::new(&*entry.body);
+                Pager::new(&source).build(builder, "body");
+            }
+        });
+    }
+}
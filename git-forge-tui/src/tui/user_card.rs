@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use vctuik::state::Builder;
+
+use crate::{
+    github::{self, Response},
+    tui::identicon,
+};
+
+/// Popup with information about a user, to help calibrate the tone and
+/// scrutiny of a review.
+///
+/// The information is fetched lazily and cached by the client.
+#[derive(Debug)]
+pub struct UserCard<'build> {
+    host: &'build str,
+    login: &'build str,
+    repository: Option<(&'build str, &'build str)>,
+}
+impl<'build> UserCard<'build> {
+    pub fn new(host: &'build str, login: &'build str) -> Self {
+        Self {
+            host,
+            login,
+            repository: None,
+        }
+    }
+
+    /// Also show the user's history in the given repository.
+    pub fn repository(self, owner: &'build str, name: &'build str) -> Self {
+        Self {
+            repository: Some((owner, name)),
+            ..self
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder, connections: &mut github::connections::Connections) {
+        let text = builder.theme().modal_text.clone();
+        let mut lines: Vec<Line> = Vec::new();
+
+        match connections.client(self.host) {
+            Err(err) => lines.push(Line::styled(err.to_string(), text.error)),
+            Ok(client) => {
+                let mut client = client.borrow_mut();
+                let client = client.access();
+
+                match client.user(self.login) {
+                    Response::Ok(user) => {
+                        if let Some(name) = user.name.clone().filter(|name| !name.is_empty()) {
+                            lines.push(Line::styled(name, text.header1));
+                        }
+                        for (label, value) in [
+                            ("Company:  ", user.company.clone()),
+                            ("Location: ", user.location.clone()),
+                            ("Bio:      ", user.bio.clone()),
+                        ] {
+                            if let Some(value) = value.filter(|value| !value.is_empty()) {
+                                lines.push(Line::from(vec![
+                                    Span::styled(label, text.header2),
+                                    Span::styled(value, text.normal),
+                                ]));
+                            }
+                        }
+                        lines.push(Line::from(vec![
+                            Span::styled("Joined:   ", text.header2),
+                            Span::styled(user.created_at.clone(), text.normal),
+                        ]));
+                    }
+                    response => lines.push(Line::styled(
+                        response.ok().err().unwrap_or_default().to_string(),
+                        text.inactive,
+                    )),
+                }
+
+                if let Response::Ok(orgs) = client.user_orgs(self.login) {
+                    if !orgs.is_empty() {
+                        let orgs = orgs.iter().map(|org| org.login.clone()).collect::<Vec<_>>();
+                        lines.push(Line::from(vec![
+                            Span::styled("Orgs:     ", text.header2),
+                            Span::styled(orgs.join(", "), text.normal),
+                        ]));
+                    }
+                }
+
+                if let Some((owner, name)) = self.repository {
+                    match client.pulls_by_author(owner, name, self.login) {
+                        Response::Ok(pulls) => {
+                            let summary = if pulls.total_count <= 1 {
+                                Span::styled("First-time contributor", text.highlight)
+                            } else {
+                                let count = pulls.total_count;
+                                Span::styled(
+                                    format!("{count} pull requests in this repository"),
+                                    text.normal,
+                                )
+                            };
+                            lines.push(summary.into());
+                            for pull in &pulls.items {
+                                lines.push(Line::styled(
+                                    format!("  #{} ({}) {}", pull.number, pull.state, pull.title),
+                                    text.normal,
+                                ));
+                            }
+                        }
+                        Response::Pending => {
+                            lines.push(Line::styled("Loading contributions...", text.inactive))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Show the card in the bottom right corner of the available space.
+        let viewport = builder.viewport();
+        let width = std::cmp::min(64, viewport.width);
+        let height = std::cmp::min(lines.len() as u16 + 2, viewport.height);
+        let area = Rect {
+            x: viewport.x + viewport.width - width,
+            y: viewport.y + viewport.height - height,
+            width,
+            height,
+        };
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", identicon::mention(self.host, self.login)))
+            .style(builder.theme().modal_background.patch(text.normal));
+        builder.frame().render_widget(Clear, area);
+        builder
+            .frame()
+            .render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Decode avatars in the formats that forges serve: a dark square on a light
+//! or transparent background must come out as the dots in the middle.
+
+use std::io::Cursor;
+
+use git_forge_tui::tui::avatar::decode;
+use image::{DynamicImage, GrayImage, ImageFormat, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use ratatui::style::Color;
+
+const SIZE: u32 = 32;
+
+/// The dots of the middle 2x2 cells of the grid.
+const SQUARE: &str = "\u{2830}\u{2806}";
+
+const DARK: [u8; 3] = [40, 40, 120];
+const LIGHT: [u8; 3] = [220, 220, 220];
+
+fn is_square(x: u32, y: u32) -> bool {
+    (8..24).contains(&x) && (8..24).contains(&y)
+}
+
+fn encode(image: impl Into<DynamicImage>, format: ImageFormat) -> Vec<u8> {
+    let mut data = Cursor::new(Vec::new());
+    image.into().write_to(&mut data, format).unwrap();
+    data.into_inner()
+}
+
+#[test]
+fn png_rgb() {
+    let image = RgbImage::from_fn(SIZE, SIZE, |x, y| {
+        Rgb(if is_square(x, y) { DARK } else { LIGHT })
+    });
+    let data = encode(image, ImageFormat::Png);
+    assert_eq!(decode(&data), Some((SQUARE.to_string(), Color::Indexed(16 + 36 + 6 + 2))));
+}
+
+#[test]
+fn png_transparent() {
+    let image = RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        if is_square(x, y) {
+            let [red, green, blue] = LIGHT;
+            Rgba([red, green, blue, 255])
+        } else {
+            Rgba([0; 4])
+        }
+    });
+    let data = encode(image, ImageFormat::Png);
+    assert_eq!(decode(&data), Some((SQUARE.to_string(), Color::Indexed(16 + 4 * 36 + 4 * 6 + 4))));
+}
+
+#[test]
+fn jpeg_gray() {
+    let image = GrayImage::from_fn(SIZE, SIZE, |x, y| {
+        Luma([if is_square(x, y) { DARK[0] } else { LIGHT[0] }])
+    });
+    let data = encode(image, ImageFormat::Jpeg);
+    assert_eq!(decode(&data), Some((SQUARE.to_string(), Color::Indexed(16 + 36 + 6 + 1))));
+}
+
+#[test]
+fn invalid() {
+    let data = encode(RgbImage::from_pixel(SIZE, SIZE, Rgb(LIGHT)), ImageFormat::Png);
+    assert_eq!(decode(&data[..data.len() / 2]), None);
+    assert_eq!(decode(b"GIF89a"), None);
+}
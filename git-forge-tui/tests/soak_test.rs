@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Soak the API client against a mock forge: poll notifications and their pull
+//! requests, refresh them and request reviews in a busy loop, and check that
+//! the cache, the queues, and the resources of the process stay bounded.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use git_forge_tui::{
+    github::{
+        edit::{Edit, ReviewersRequest},
+        Client, Host,
+    },
+    soak::SoakMonitor,
+};
+use serde_json::json;
+use vctools_utils::prelude::*;
+
+/// Length of the run.
+const DURATION: Duration = Duration::from_secs(6);
+
+/// Number of notifications that the mock forge returns.
+const NOTIFICATIONS: u64 = 30;
+
+/// Minimal GitHub API server on localhost. Notifications and pull requests are
+/// listed, requests for reviews succeed, and everything else is not found.
+struct MockForge {
+    api: String,
+    requests: Arc<AtomicUsize>,
+    review_requests: Arc<AtomicUsize>,
+}
+impl MockForge {
+    fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let api = format!("http://{}/", listener.local_addr()?);
+        let requests = Arc::new(AtomicUsize::new(0));
+        let review_requests = Arc::new(AtomicUsize::new(0));
+
+        let counters = (requests.clone(), review_requests.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                counters.0.fetch_add(1, Ordering::Relaxed);
+                if let Ok(true) = serve(stream) {
+                    counters.1.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        Ok(Self { api, requests, review_requests })
+    }
+}
+
+/// Answer one request. Returns whether it requested reviews.
+fn serve(stream: std::net::TcpStream) -> Result<bool> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            }
+        }
+    }
+    reader.read_exact(&mut vec![0; content_length])?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+
+    let is_review_request = method == "POST" && path.ends_with("/requested_reviewers");
+    let pull = path.strip_prefix("/repos/owner/repo/pulls/").and_then(|n| n.parse::<u64>().ok());
+    let (status, body) = if method == "GET" && path == "/notifications" {
+        ("200 OK", notifications().to_string())
+    } else if let Some(number) = pull.filter(|_| method == "GET") {
+        let title = format!("Pull request {number}");
+        ("200 OK", json!({ "number": number, "title": title, "state": "open" }).to_string())
+    } else if is_review_request {
+        ("201 Created", "{}".into())
+    } else {
+        ("404 Not Found", r#"{"message": "Not Found"}"#.into())
+    };
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    Ok(is_review_request)
+}
+
+fn notifications() -> serde_json::Value {
+    (1..=NOTIFICATIONS)
+        .map(|number| {
+            json!({
+                "id": number.to_string(),
+                "reason": "review_requested",
+                "updated_at": "2026-01-01T00:00:00Z",
+                "repository": {
+                    "name": "repo",
+                    "full_name": "owner/repo",
+                    "owner": { "login": "owner" },
+                },
+                "subject": {
+                    "title": format!("Pull request {number}"),
+                    "url": format!("https://api.example/repos/owner/repo/pulls/{number}"),
+                    "type": "PullRequest",
+                },
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn soak_test() -> Result<()> {
+    let forge = MockForge::start()?;
+    let host: Host = serde_json::from_value(json!({
+        "host": "forge.example",
+        "api": forge.api,
+        "user": "soak",
+        "token": "token",
+        "proxy": "none",
+        "retries": 0,
+    }))?;
+    let mut client = Client::build(host).new()?;
+    let mut monitor = SoakMonitor::new(DURATION).sample_interval(Duration::from_millis(100));
+
+    let start = Instant::now();
+    let mut frame = 0u64;
+    let mut edits = 0;
+    let mut max_pending = 0;
+    while start.elapsed() < DURATION {
+        client.start_frame(Some(Instant::now() + Duration::from_millis(5)));
+
+        let notifications =
+            client.access().notifications().ok_or_pending().map_err(|err| err.to_string())?;
+        if let Some(notifications) = notifications {
+            let number = notifications[frame as usize % notifications.len()].pull_number().unwrap();
+            let pull = client.access().pull("owner", "repo", number);
+            pull.ok_or_pending().map_err(|err| err.to_string())?;
+            if frame.is_multiple_of(10) {
+                client.edit(Edit::ReviewersRequest(ReviewersRequest {
+                    owner: "owner".into(),
+                    name: "repo".into(),
+                    number,
+                    reviewers: vec!["reviewer".into()],
+                }))?;
+                edits += 1;
+            }
+        }
+        if frame.is_multiple_of(50) {
+            client.refresh();
+        }
+
+        max_pending = max_pending.max(client.pending_tasks());
+        if monitor.sample_due() {
+            monitor.record(vec![
+                ("API cache entries", client.stats().cache_entries),
+                ("pending API tasks", client.pending_tasks()),
+                ("queued edits", client.queued_edits().len()),
+            ])?;
+        }
+
+        client.end_frame(None);
+        std::thread::sleep(Duration::from_millis(2));
+        frame += 1;
+    }
+    println!("{}", monitor.summary());
+
+    assert_eq!(client.shutdown(Duration::from_secs(5)), 0);
+    assert!(max_pending < 20, "up to {max_pending} pending API tasks");
+    assert!(client.stats().cache_entries <= NOTIFICATIONS as usize + 1);
+
+    let committed = forge.review_requests.load(Ordering::Relaxed);
+    assert_eq!(committed, edits, "only {committed} of {edits} edits were committed");
+    assert!(forge.requests.load(Ordering::Relaxed) > committed);
+    Ok(())
+}
@@ -14,3 +14,21 @@ fn read_bytes_impl(path: &Path) -> Result<Vec<u8>> {
 pub fn read_bytes<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
     read_bytes_impl(path.as_ref())
 }
+
+/// Turn an arbitrary string, e.g. a URL, into a name that is a valid file name
+/// on all platforms.
+///
+/// ASCII letters, digits, `-`, and `.` are kept, all other bytes are written
+/// as `_` followed by two hex digits, so that the mapping is unique. For
+/// example, `repos/o/n?page=2` becomes `repos_2fo_2fn_3fpage_3d2`.
+pub fn portable_file_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for &byte in name.as_bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.' {
+            result.push(byte as char);
+        } else {
+            result.push_str(&format!("_{byte:02x}"));
+        }
+    }
+    result
+}
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MIT
+
+//! Message catalogs for user-visible strings.
+//!
+//! Catalogs use a subset of the Fluent syntax:
+//!
+//! ```text
+//! # Comments start with a hash.
+//! inbox-no-selection = No notification selected
+//! inbox-error-open = Failed to open { $title }: { $error }
+//! long-message =
+//!     Indented lines continue the message
+//!     on multiple lines.
+//! ```
+//!
+//! Placeables are either variables (`{ $name }`) or string literals
+//! (`{ "{" }`), which allow braces to be used in the text. Attributes, terms
+//! and selectors aren't supported.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Element {
+    Text(String),
+    Variable(String),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Catalog {
+    messages: HashMap<String, Vec<Element>>,
+}
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut catalog = Self::new();
+        let mut current: Option<(String, String)> = None;
+
+        for (line_idx, line) in source.lines().enumerate() {
+            let line_no = line_idx + 1;
+            let trimmed = line.trim();
+
+            if line.starts_with([' ', '\t']) && !trimmed.is_empty() {
+                let Some((_, value)) = &mut current else {
+                    Err(format!("line {line_no}: continuation without a message"))?
+                };
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+
+            if let Some((id, value)) = current.take() {
+                catalog.insert(id, &value)?;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let Some((id, value)) = line.split_once('=') else {
+                Err(format!("line {line_no}: expected `id = message`"))?
+            };
+            let id = id.trim();
+            if id.is_empty() || !id.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-') {
+                Err(format!("line {line_no}: invalid message ID `{id}`"))?
+            }
+            current = Some((id.to_string(), value.trim().to_string()));
+        }
+        if let Some((id, value)) = current.take() {
+            catalog.insert(id, &value)?;
+        }
+
+        Ok(catalog)
+    }
+
+    fn insert(&mut self, id: String, value: &str) -> Result<()> {
+        let pattern = try_forward(|| parse_pattern(value), || format!("message `{id}`"))?;
+        self.messages.insert(id, pattern);
+        Ok(())
+    }
+
+    /// Add the messages of `other`, replacing messages with the same ID.
+    pub fn merge(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+
+    /// Format the message with the given ID.
+    ///
+    /// Variables without a value in `args` are left in the text as they are.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+        let mut result = String::new();
+        for element in pattern {
+            match element {
+                Element::Text(text) => result.push_str(text),
+                Element::Variable(name) => {
+                    match args.iter().find(|(arg, _)| arg == name) {
+                        Some((_, value)) => result.push_str(&value.to_string()),
+                        None => {
+                            result.push_str("{ $");
+                            result.push_str(name);
+                            result.push_str(" }");
+                        }
+                    }
+                }
+            }
+        }
+        Some(result)
+    }
+}
+
+fn parse_pattern(value: &str) -> Result<Vec<Element>> {
+    let mut elements = Vec::new();
+    let mut text = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        text.push_str(&rest[..start]);
+        if rest[start..].starts_with('}') {
+            Err("unbalanced `}`")?
+        }
+        let Some(end) = rest[start..].find('}') else {
+            Err("unterminated placeable")?
+        };
+        let placeable = rest[start + 1..start + end].trim();
+        rest = &rest[start + end + 1..];
+
+        if let Some(name) = placeable.strip_prefix('$') {
+            if !text.is_empty() {
+                elements.push(Element::Text(std::mem::take(&mut text)));
+            }
+            elements.push(Element::Variable(name.to_string()));
+        } else if let Some(literal) =
+            placeable.strip_prefix('"').and_then(|p| p.strip_suffix('"'))
+        {
+            text.push_str(literal);
+        } else {
+            Err(format!("unsupported placeable `{{ {placeable} }}`"))?
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        elements.push(Element::Text(text));
+    }
+
+    Ok(elements)
+}
+
+/// The language for messages according to the environment, e.g. "de" for
+/// `LANG=de_DE.UTF-8`.
+///
+/// Returns `None` for the "C" and "POSIX" locales.
+pub fn language_from_env() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    if language.is_empty() || language == "C" || language == "POSIX" {
+        return None;
+    }
+    Some(language.to_ascii_lowercase())
+}
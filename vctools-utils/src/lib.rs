@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: MIT
 
 pub mod files;
+pub mod i18n;
 pub mod prelude;
+pub mod time;
 mod partition_point;
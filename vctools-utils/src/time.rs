@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MIT
+
+//! Formatting of points in time for display.
+//!
+//! Points in time are Unix timestamps in seconds. Relative descriptions like
+//! "3h ago" go stale as time passes, so `stale_after` tells how long a
+//! description remains valid, which can be used to schedule a refresh.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+/// Relative descriptions switch to the date after this long.
+const RELATIVE_LIMIT: i64 = 30 * DAY;
+
+/// The current time as a Unix timestamp.
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Convert days since the Unix epoch into (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Convert (year, month, day) into days since the Unix epoch.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Format a point in time in UTC as ISO 8601, e.g. "2024-03-01T12:00:00Z".
+pub fn format_iso(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+    let seconds = timestamp.rem_euclid(DAY);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / HOUR,
+        seconds % HOUR / MINUTE,
+        seconds % MINUTE
+    )
+}
+
+/// Describe a point in time relative to `now`, e.g. "3h ago" or "yesterday".
+///
+/// Points in time that are more than 30 days away are shown as their date.
+pub fn format_relative(timestamp: i64, now: i64) -> String {
+    let delta = now - timestamp;
+    let (ago, future) = (delta.abs(), delta < 0);
+    let relative = |count: i64, unit: &str| {
+        if future {
+            format!("in {count}{unit}")
+        } else {
+            format!("{count}{unit} ago")
+        }
+    };
+
+    if ago < MINUTE {
+        "just now".into()
+    } else if ago < HOUR {
+        relative(ago / MINUTE, "m")
+    } else if ago < DAY {
+        relative(ago / HOUR, "h")
+    } else if ago < 2 * DAY {
+        if future { "tomorrow" } else { "yesterday" }.into()
+    } else if ago < RELATIVE_LIMIT {
+        relative(ago / DAY, "d")
+    } else {
+        let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// How long the result of `format_relative` stays valid.
+pub fn stale_after(timestamp: i64, now: i64) -> Duration {
+    let delta = now - timestamp;
+    let ago = delta.abs();
+    let unit = if ago < HOUR {
+        MINUTE
+    } else if ago < DAY {
+        HOUR
+    } else if ago < RELATIVE_LIMIT {
+        DAY
+    } else {
+        // Only the date is shown, which doesn't change.
+        return Duration::MAX;
+    };
+
+    // Seconds until `ago` reaches the next multiple of the unit (in the past)
+    // or drops below the current one (in the future).
+    let remaining = if delta >= 0 {
+        unit - ago % unit
+    } else {
+        ago % unit + 1
+    };
+    Duration::from_secs(remaining as u64)
+}
@@ -1,30 +1,113 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-///! This crate contains internals of vctuik that make use of `unsafe`.
-///!
-///! The APIs exported by this crate are safe to use.
-///!
-///! No use of the `unsafe` keyword is allowed outside of this crate.
+//! This crate contains internals of vctuik that make use of `unsafe`.
+//!
+//! The APIs exported by this crate are safe to use.
+//!
+//! No use of the `unsafe` keyword is allowed outside of this crate.
+//!
+//! The crate is an implementation detail of vctuik and is published only
+//! because vctuik depends on it. Its API may change in any release.
+
 pub mod state;
 
 use std::ptr;
 
-#[cfg(test)]
-mod test {
-    #[test]
-    fn ui() {
-        let t = trybuild::TestCases::new();
-        t.compile_fail("tests/ui/lifetime_check.rs");
+/// Aborts the process if it is dropped, i.e. if it isn't forgotten before a
+/// panic unwinds past it.
+struct AbortOnUnwind;
+impl Drop for AbortOnUnwind {
+    fn drop(&mut self) {
+        std::process::abort();
     }
 }
 
+/// Replace the value behind x by the result of f, which takes the old value.
+///
+/// The process is aborted if f panics, since there is no value to leave
+/// behind x. Use `update_mut_or_else` to recover instead.
 pub fn update_mut<T>(x: &mut T, f: impl FnOnce(T) -> T) {
-    // Safety: Since we hold an exclusive reference to x, we can safely read and write its value.
-    //
-    // The one concern is that f may panic, leaving x in an inconsistent state.
-    let mut x_value = unsafe { ptr::read(x) };
-    x_value = f(x_value);
+    let guard = AbortOnUnwind;
+
+    // Safety: Since we hold an exclusive reference to x, we can safely read
+    // its value. It is moved out until the write below, so nobody must see x
+    // in between: if f panics, the guard aborts before the unwind reaches any
+    // code that could.
+    let x_value = unsafe { ptr::read(x) };
+    let x_value = f(x_value);
     unsafe {
         ptr::write(x, x_value);
+    }
+
+    std::mem::forget(guard);
+}
+
+/// Like `update_mut`, but if f panics, the value behind x is replaced by the
+/// result of recover before the panic continues.
+pub fn update_mut_or_else<T>(x: &mut T, recover: impl FnOnce() -> T, f: impl FnOnce(T) -> T) {
+    struct Recover<T, R: FnOnce() -> T> {
+        x: *mut T,
+        recover: Option<R>,
+    }
+    impl<T, R: FnOnce() -> T> Drop for Recover<T, R> {
+        fn drop(&mut self) {
+            if let Some(recover) = self.recover.take() {
+                // Safety: The guard only has a recover function left if f
+                // panicked, in which case x has been moved out of and not
+                // written again. Writing it without dropping the old value
+                // is therefore correct.
+                //
+                // If recover panics as well, the process aborts because of
+                // the double panic.
+                unsafe { ptr::write(self.x, recover()) }
+            }
+        }
+    }
+
+    let mut guard = Recover {
+        x,
+        recover: Some(recover),
     };
+
+    // Safety: See update_mut. The guard refills x if f panics.
+    let x_value = unsafe { ptr::read(guard.x) };
+    let x_value = f(x_value);
+    guard.recover = None;
+    unsafe {
+        ptr::write(guard.x, x_value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ui() {
+        let t = trybuild::TestCases::new();
+        t.compile_fail("tests/ui/*.rs");
+    }
+
+    #[test]
+    fn update() {
+        let mut x = vec![1, 2];
+        update_mut(&mut x, |mut x| {
+            x.push(3);
+            x
+        });
+        assert_eq!(x, [1, 2, 3]);
+
+        update_mut_or_else(&mut x, Vec::new, |x| x.into_iter().rev().collect());
+        assert_eq!(x, [3, 2, 1]);
+    }
+
+    #[test]
+    fn update_panic() {
+        let mut x = vec![1, 2];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            update_mut_or_else(&mut x, || vec![0], |_| panic!("oops"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(x, [0]);
+    }
 }
@@ -78,6 +78,15 @@ impl<'frame, K: Eq + Hash> Builder<'frame, K> {
         self.get_or_insert_with(new_key, old_key, Default::default)
     }
 
+    /// Number of entries that were added or preserved in this frame so far.
+    pub fn len(&self) -> usize {
+        self.store.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.current.is_empty()
+    }
+
     pub fn preserve(&mut self, new_key: K, old_key: K) -> bool {
         let entry = self.store.current.entry(new_key);
         match entry {
@@ -169,4 +178,55 @@ mod test {
         assert_eq!(*a, 0);
         assert_eq!(*b, 2);
     }
+
+    #[test]
+    fn entry() {
+        let mut store = Store::new();
+
+        {
+            let mut builder = Builder::new(&mut store);
+            match builder.entry::<i32>("a", None) {
+                Access::Existing(_) => panic!("unexpected existing entry"),
+                Access::New(insert) => *insert.insert(1) += 1,
+            }
+            assert!(!builder.preserve("c", "c"));
+            assert_eq!(builder.len(), 1);
+        }
+
+        {
+            let mut builder = Builder::new(&mut store);
+            match builder.entry::<i32>("b", Some("a")) {
+                Access::Existing(value) => assert_eq!(*value, 2),
+                Access::New(_) => panic!("entry was not kept"),
+            }
+        }
+
+        {
+            // The type of the state changed. The old state is dropped.
+            let mut builder = Builder::new(&mut store);
+            let value: &mut String = builder.get_or_insert_default("b", Some("b"));
+            assert!(value.is_empty());
+            value.push('x');
+        }
+
+        {
+            let mut builder = Builder::new(&mut store);
+            assert!(builder.preserve("c", "b"));
+        }
+
+        {
+            let mut builder = Builder::new(&mut store);
+            let value: &mut String = builder.get_or_insert_default("d", Some("c"));
+            assert_eq!(value, "x");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Key inserted again in the same frame")]
+    fn same_key() {
+        let mut store = Store::new();
+        let mut builder = Builder::new(&mut store);
+        let _: &mut i32 = builder.get_or_insert_default("a", None);
+        let _: &mut i32 = builder.get_or_insert_default("a", None);
+    }
 }
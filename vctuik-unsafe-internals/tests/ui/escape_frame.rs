@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctuik_unsafe_internals::state::*;
+
+// The state must not outlive the borrow of the store.
+fn leak(store: &mut Store<&'static str>) -> &'static mut i32 {
+    Builder::new(store).get_or_insert_default("a", None)
+}
+
+fn main() {
+    let mut store = Store::new();
+    *leak(&mut store) = 1;
+}
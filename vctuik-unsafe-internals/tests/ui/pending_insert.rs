@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctuik_unsafe_internals::state::*;
+
+fn main() {
+    let mut store = Store::new();
+    let mut builder = Builder::new(&mut store);
+
+    // The entry borrows the builder until it is inserted.
+    let access = builder.entry::<i32>("a", None);
+    let _: &mut i32 = builder.get_or_insert_default("b", None);
+    if let Access::New(insert) = access {
+        insert.insert(1);
+    }
+}
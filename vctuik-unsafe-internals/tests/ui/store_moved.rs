@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use vctuik_unsafe_internals::state::*;
+
+fn main() {
+    let mut store = Store::new();
+
+    let a: &mut i32 = Builder::new(&mut store).get_or_insert_default("a", None);
+
+    let mut moved = store;
+    Builder::new(&mut moved);
+
+    *a = 1;
+}
@@ -5,6 +5,7 @@ use ratatui::widgets::Block;
 use vctuik::{
     check_box::add_check_box,
     event::KeyCode,
+    form::Form,
     input::Input,
     label::add_label,
     layout::Constraint1D,
@@ -21,6 +22,8 @@ fn main() -> Result<()> {
     let mut foo = false;
     let mut bar = false;
     let mut name: String = "world".into();
+    let mut cheese = 0;
+    let mut greeting = None;
     let mut last_event = None;
 
     let mut table_source_state = simple_table::SourceState::new();
@@ -33,9 +36,19 @@ fn main() -> Result<()> {
 
         // Draw UI
         with_section(builder, "Settings", |builder| {
-            add_check_box(builder, "Foo", &mut foo);
-            add_check_box(builder, "Bar", &mut bar);
-            Input::new("name").label("Name:").build(builder, &mut name);
+            let result = Form::new("settings").build(builder, |form| {
+                form.check_box("Foo", &mut foo);
+                form.check_box("Bar", &mut bar);
+                form.input("Name:", &mut name);
+                if name.trim().is_empty() {
+                    form.error("Please enter a name");
+                }
+                let cheeses = ["Cheddar", "Provolone", "Swiss"];
+                form.radio_group("Favorite cheese:", &cheeses, &mut cheese);
+            });
+            if result.submitted {
+                greeting = Some(format!("Hello, {name}!"));
+            }
             builder.add_slack();
         });
 
@@ -43,7 +56,7 @@ fn main() -> Result<()> {
             add_label(builder, "Cheddar");
             add_label(builder, "Provolone");
             add_label(builder, "Swiss");
-            add_label(builder, format!("Hello, {name}!"));
+            add_label(builder, greeting.as_deref().unwrap_or("Press Enter in the settings"));
             builder.add_slack();
         });
 
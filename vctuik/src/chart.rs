@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Small charts drawn with text, for dashboards and table cells.
+//!
+//! * `sparkline`: one line of block characters, one per value
+//! * `bar`: a horizontal bar with a resolution of eighths of a cell
+//! * `BrailleCanvas`: a plot with 2x4 dots per cell
+//!
+//! The charts are plain strings, so they can be styled and placed like any
+//! other text, e.g. in a `Line` or a cell of a `simple_table`.
+
+/// Levels of the sparkline, from lowest to highest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Partial blocks of the horizontal bar, from 1/8 to 7/8 of a cell.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Draw one character per value, scaled from 0 (or the smallest value, if it
+/// is negative) to the largest value. Values that are not finite are drawn
+/// as spaces.
+pub fn sparkline(values: &[f64]) -> String {
+    let finite = || values.iter().copied().filter(|value| value.is_finite());
+    let min = finite().fold(0.0, f64::min);
+    let max = finite().fold(min, f64::max);
+    values
+        .iter()
+        .map(|value| {
+            if !value.is_finite() {
+                ' '
+            } else if max <= min {
+                SPARKS[0]
+            } else {
+                let level = ((value - min) / (max - min) * (SPARKS.len() - 1) as f64).round();
+                SPARKS[level as usize]
+            }
+        })
+        .collect()
+}
+
+/// Draw a bar of `value` relative to `max` that is `width` cells long for the
+/// maximum. Values outside of 0..=max are clamped.
+pub fn bar(value: f64, max: f64, width: usize) -> String {
+    let fraction = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+    let eighths = (fraction * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(EIGHTHS[partial]);
+    }
+    bar
+}
+
+/// A plot of dots, drawn with braille characters, which have 2 columns and 4
+/// rows of dots per cell.
+///
+/// The origin is at the bottom left, like in a plot.
+#[derive(Debug, Clone)]
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+
+    /// Dot bits of the cells, row by row from the top.
+    cells: Vec<u8>,
+}
+impl BrailleCanvas {
+    /// Create an empty canvas of the given size, in cells.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// Size of the canvas in dots.
+    pub fn dots(&self) -> (usize, usize) {
+        (self.width * 2, self.height * 4)
+    }
+
+    /// Set the dot at the given position. Dots outside of the canvas are
+    /// ignored.
+    pub fn set(&mut self, x: usize, y: usize) {
+        let (width, height) = self.dots();
+        if x >= width || y >= height {
+            return;
+        }
+        // Bits of the dots in a cell, indexed by [row from the top][column].
+        const BITS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+        let row = height - 1 - y;
+        self.cells[row / 4 * self.width + x / 2] |= BITS[row % 4][x % 2];
+    }
+
+    /// Plot the values from left to right, one column of dots per value,
+    /// scaled from the smallest to the largest value. Consecutive values are
+    /// connected by vertical runs of dots. Values that don't fit are dropped
+    /// from the front, so that the most recent values are shown.
+    pub fn plot(&mut self, values: &[f64]) {
+        let (width, height) = self.dots();
+        let values: Vec<f64> = values.iter().copied().filter(|value| value.is_finite()).collect();
+        let values = &values[values.len().saturating_sub(width)..];
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let scale = |value: f64| {
+            if max > min {
+                ((value - min) / (max - min) * (height - 1) as f64).round() as usize
+            } else {
+                0
+            }
+        };
+
+        let mut prev = None;
+        for (x, value) in values.iter().enumerate() {
+            let y = scale(*value);
+            let (low, high) = match prev {
+                Some(prev) if prev < y => (prev + 1, y),
+                Some(prev) if prev > y => (y, prev - 1),
+                _ => (y, y),
+            };
+            for y in low..=high {
+                self.set(x, y);
+            }
+            prev = Some(y);
+        }
+    }
+
+    /// The lines of the canvas, from the top.
+    pub fn lines(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.width.max(1))
+            .take(self.height)
+            .map(|row| {
+                row.iter().map(|bits| char::from_u32(0x2800 + *bits as u32).unwrap()).collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sparkline() {
+        assert_eq!(super::sparkline(&[0.0, 1.0, 7.0, f64::NAN, 3.5]), "▁▂█ ▅");
+        assert_eq!(super::sparkline(&[2.0, 2.0]), "██");
+        assert_eq!(super::sparkline(&[]), "");
+    }
+
+    #[test]
+    fn bar() {
+        assert_eq!(super::bar(10.0, 10.0, 4), "████");
+        assert_eq!(super::bar(5.0, 10.0, 3), "█▌");
+        assert_eq!(super::bar(1.0, 0.0, 4), "");
+        assert_eq!(super::bar(-1.0, 10.0, 4), "");
+    }
+
+    #[test]
+    fn braille() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.plot(&[0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(canvas.lines(), vec!["⡠⠊"]);
+
+        let mut canvas = BrailleCanvas::new(1, 2);
+        canvas.set(0, 0);
+        canvas.set(1, 7);
+        canvas.set(2, 0);
+        assert_eq!(canvas.lines(), vec!["⠈", "⡀"]);
+    }
+}
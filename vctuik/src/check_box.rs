@@ -82,23 +82,35 @@ impl<'a> CheckBoxStateRef<'a> for &'a Cell<bool> {
 }
 
 pub fn add_check_box<'s>(builder: &mut Builder, title: &str, state: impl CheckBoxStateRef<'s>) {
+    let area = builder.take_lines_fixed(1);
+    build_check_box(builder, area, title, state);
+}
+
+/// Build a check box in the given area instead of taking a line from the layout.
+///
+/// Returns true if the check box was toggled.
+pub(crate) fn build_check_box<'s>(
+    builder: &mut Builder,
+    area: Rect,
+    title: &str,
+    state: impl CheckBoxStateRef<'s>,
+) -> bool {
     let state_id = builder.add_state_id(title);
     let mut state = state.as_check_box_state();
     let has_focus = builder.check_focus(state_id);
 
     let text_width = title.graphemes(true).count() as u16;
 
-    let area = builder.take_lines_fixed(1);
     let click_area = Rect {
         width: 4 + text_width,
         ..area
     };
 
-    if builder
+    let toggled = builder
         .on_mouse_press(click_area, MouseButton::Left)
         .is_some()
-        || (has_focus && builder.on_key_press(KeyCode::Char(' ')))
-    {
+        || (has_focus && builder.on_key_binding(KeyCode::Char(' '), "Toggle"));
+    if toggled {
         state.toggle();
     }
 
@@ -119,4 +131,6 @@ pub fn add_check_box<'s>(builder: &mut Builder, title: &str, state: impl CheckBo
     builder.frame().render_widget(span, area);
 
     // TODO: Set focus via mouse
+
+    toggled
 }
@@ -49,7 +49,7 @@ impl<'bar, 'cmd> CommandLine<'bar, 'cmd> {
 
         let mut cancelled = false;
 
-        if self.command.is_some() && builder.on_key_press(KeyCode::Esc) {
+        if self.command.is_some() && builder.on_key_binding(KeyCode::Esc, "Cancel command") {
             *self.command = None;
             cancelled = true;
         }
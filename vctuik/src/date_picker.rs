@@ -0,0 +1,380 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Entry of points in time, e.g. for snoozing or filtering by date.
+//!
+//! The text that the user types is interpreted relative to the current time.
+//! Supported formats are:
+//!
+//!  - durations such as `90m`, `2d` or `1w 2d`, optionally preceded by `in`
+//!  - `today`, `tomorrow`
+//!  - weekdays such as `mon` or `friday`, optionally preceded by `next`,
+//!    which refer to the next such day after today
+//!  - dates such as `2024-03-01`, optionally followed by a time `14:30`
+//!
+//! Days without a time refer to their start (midnight) in local time.
+//!
+//! Points in time are Unix timestamps. vctuik does not know the local time
+//! zone, so applications pass its offset from UTC via `Clock`.
+
+use std::borrow::Cow;
+
+use ratatui::{layout::Rect, text::Span};
+use unicode_segmentation::UnicodeSegmentation;
+use vctools_utils::time::{civil_from_days, days_from_civil, unix_now};
+
+use crate::{
+    input::{Input, InputAction},
+    state::Builder,
+    theme::Themed,
+};
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// The current time and the offset of local time from UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    /// Unix timestamp in seconds.
+    pub now: i64,
+
+    /// Offset of local time from UTC in seconds.
+    pub utc_offset: i32,
+}
+impl Clock {
+    pub fn utc_now() -> Self {
+        Self {
+            now: unix_now(),
+            utc_offset: 0,
+        }
+    }
+
+    fn local_days(&self, timestamp: i64) -> i64 {
+        (timestamp + self.utc_offset as i64).div_euclid(DAY)
+    }
+
+    /// Timestamp of the start of the given local day.
+    fn start_of_day(&self, days: i64) -> i64 {
+        days * DAY - self.utc_offset as i64
+    }
+}
+
+/// Order of date components for display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// 2024-03-01
+    #[default]
+    Iso,
+
+    /// 01.03.2024 or 01/03/2024
+    DayMonthYear(char),
+
+    /// 03/01/2024
+    MonthDayYear,
+}
+impl DateFormat {
+    /// Choose a format based on the `LC_ALL`, `LC_TIME` and `LANG` environment
+    /// variables.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::for_locale(&locale)
+    }
+
+    /// Choose a format for a locale name such as `de_DE.UTF-8`.
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or("");
+        let (language, territory) = locale.split_once('_').unwrap_or((locale, ""));
+        match (language, territory) {
+            ("en", "US" | "PH") => DateFormat::MonthDayYear,
+            ("de" | "cs" | "da" | "fi" | "nb" | "no" | "pl" | "ru" | "sk" | "tr" | "uk", _) => {
+                DateFormat::DayMonthYear('.')
+            }
+            ("en" | "es" | "fr" | "it" | "pt" | "el" | "ga", _) => DateFormat::DayMonthYear('/'),
+            _ => DateFormat::Iso,
+        }
+    }
+}
+
+/// Day of the week, starting with Monday as 0.
+fn weekday(days: i64) -> usize {
+    (days + 3).rem_euclid(7) as usize
+}
+
+fn parse_duration(text: &str) -> Option<i64> {
+    let mut total = 0;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(rest.len());
+        let count: i64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let unit_len = rest.find(|ch: char| !ch.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+            "h" | "hour" | "hours" => HOUR,
+            "d" | "day" | "days" => DAY,
+            "w" | "week" | "weeks" => 7 * DAY,
+            _ => return None,
+        };
+        total += count.checked_mul(unit)?;
+        rest = rest[unit_len..].trim_start();
+    }
+    Some(total)
+}
+
+fn parse_weekday(text: &str) -> Option<usize> {
+    let names = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    if text.len() < 3 {
+        return None;
+    }
+    names.iter().position(|name| name.starts_with(text))
+}
+
+fn parse_date(text: &str, clock: Clock) -> Option<i64> {
+    let (date, time) = match text.split_once(' ') {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (text, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    if civil_from_days(days) != (year, month, day) {
+        return None; // e.g. February 30
+    }
+
+    let time = match time {
+        Some(time) => {
+            let (hours, minutes) = time.split_once(':')?;
+            let hours: i64 = hours.parse().ok()?;
+            let minutes: i64 = minutes.parse().ok()?;
+            if hours >= 24 || minutes >= 60 {
+                return None;
+            }
+            hours * HOUR + minutes * MINUTE
+        }
+        None => 0,
+    };
+
+    Some(clock.start_of_day(days) + time)
+}
+
+/// Interpret the given text as a point in time.
+pub fn parse(text: &str, clock: Clock) -> Result<i64, String> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return Err("Enter a date or duration".into());
+    }
+
+    let today = clock.local_days(clock.now);
+    if text == "today" {
+        return Ok(clock.start_of_day(today));
+    }
+    if text == "tomorrow" {
+        return Ok(clock.start_of_day(today + 1));
+    }
+
+    let weekday_text = text.strip_prefix("next ").unwrap_or(&text);
+    if let Some(day) = parse_weekday(weekday_text) {
+        let ahead = (day + 7 - weekday(today)) % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return Ok(clock.start_of_day(today + ahead as i64));
+    }
+
+    let duration_text = text.strip_prefix("in ").unwrap_or(&text);
+    if let Some(duration) = parse_duration(duration_text) {
+        return Ok(clock.now + duration);
+    }
+
+    parse_date(&text, clock).ok_or_else(|| format!("Not a date or duration: {text}"))
+}
+
+/// Format a point in time as local date and time, e.g. "Fri 2024-03-01 14:30".
+pub fn format_date(timestamp: i64, utc_offset: i32, format: DateFormat) -> String {
+    let local = timestamp + utc_offset as i64;
+    let days = local.div_euclid(DAY);
+    let seconds = local.rem_euclid(DAY);
+    let (year, month, day) = civil_from_days(days);
+    let date = match format {
+        DateFormat::Iso => format!("{year:04}-{month:02}-{day:02}"),
+        DateFormat::DayMonthYear(sep) => format!("{day:02}{sep}{month:02}{sep}{year:04}"),
+        DateFormat::MonthDayYear => format!("{month:02}/{day:02}/{year:04}"),
+    };
+    format!(
+        "{} {date} {:02}:{:02}",
+        WEEKDAYS[weekday(days)],
+        seconds / HOUR,
+        seconds % HOUR / MINUTE
+    )
+}
+
+/// A text field for entering a point in time, with a preview of how the
+/// text is interpreted.
+pub struct DatePicker<'picker> {
+    id: Cow<'picker, str>,
+    label: Option<Cow<'picker, str>>,
+    clock: Option<Clock>,
+    format: Option<DateFormat>,
+}
+impl<'picker> DatePicker<'picker> {
+    pub fn new(id: impl Into<Cow<'picker, str>>) -> Self {
+        Self {
+            id: id.into(),
+            label: None,
+            clock: None,
+            format: None,
+        }
+    }
+
+    pub fn label(self, label: impl Into<Cow<'picker, str>>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    /// Set the clock that relative input is interpreted against. Defaults to
+    /// the current time in UTC.
+    pub fn clock(self, clock: Clock) -> Self {
+        Self {
+            clock: Some(clock),
+            ..self
+        }
+    }
+
+    /// Set the display format. Defaults to `DateFormat::from_env()`.
+    pub fn format(self, format: DateFormat) -> Self {
+        Self {
+            format: Some(format),
+            ..self
+        }
+    }
+
+    /// Returns the picked point in time when Enter is pressed and the text
+    /// is valid.
+    pub fn build(self, builder: &mut Builder, text: &mut String) -> Option<i64> {
+        let clock = self.clock.unwrap_or_else(Clock::utc_now);
+        let format = self.format.unwrap_or_else(DateFormat::from_env);
+
+        let area = builder.take_lines_fixed(1);
+        let label_width = self
+            .label
+            .as_ref()
+            .map(|label| label.graphemes(true).count() as u16 + 1)
+            .unwrap_or(0);
+        let input_width = std::cmp::min(label_width + 16, area.width);
+        let input_area = Rect {
+            width: input_width,
+            ..area
+        };
+        let preview_area = Rect {
+            x: area.x + input_width,
+            width: area.width - input_width,
+            ..area
+        };
+
+        let mut input = Input::new(self.id).area(input_area);
+        if let Some(label) = self.label {
+            input = input.label(label);
+        }
+        let action = input.build(builder, text);
+
+        let parsed = parse(text, clock);
+        let span = match &parsed {
+            Ok(timestamp) => {
+                Span::from(format!("  {}", format_date(*timestamp, clock.utc_offset, format)))
+                    .theme_inactive(builder)
+            }
+            Err(err) if !text.is_empty() => Span::styled(
+                format!("  {err}"),
+                builder.theme().text(builder.theme_context()).error,
+            ),
+            Err(_) => Span::raw(""),
+        };
+        builder.frame().render_widget(span, preview_area);
+
+        match action {
+            Some(InputAction::Enter) => parsed.ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Friday, 2024-03-01 12:00 UTC
+    const CLOCK: Clock = Clock {
+        now: 1709294400,
+        utc_offset: 0,
+    };
+
+    #[test]
+    fn civil() {
+        for days in [-800000, -1, 0, 1, 19783, 800000] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(19783), (2024, 3, 1));
+        assert_eq!(weekday(19783), 4);
+    }
+
+    #[test]
+    fn relative() {
+        assert_eq!(parse("2d", CLOCK), Ok(CLOCK.now + 2 * DAY));
+        assert_eq!(parse("in 1w 2h", CLOCK), Ok(CLOCK.now + 7 * DAY + 2 * HOUR));
+        assert_eq!(parse("tomorrow", CLOCK), Ok(1709337600));
+        assert_eq!(parse("next Mon", CLOCK), Ok(1709510400));
+        assert_eq!(parse("fri", CLOCK), Ok(1709856000));
+        assert!(parse("2x", CLOCK).is_err());
+        assert!(parse("", CLOCK).is_err());
+    }
+
+    #[test]
+    fn absolute() {
+        assert_eq!(parse("2024-03-05", CLOCK), Ok(1709596800));
+        assert_eq!(parse("2024-03-05 14:30", CLOCK), Ok(1709596800 + 14 * HOUR + 30 * MINUTE));
+        assert!(parse("2023-02-29", CLOCK).is_err());
+
+        let clock = Clock {
+            utc_offset: 3600,
+            ..CLOCK
+        };
+        assert_eq!(parse("2024-03-05", clock), Ok(1709596800 - 3600));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(format_date(CLOCK.now, 0, DateFormat::Iso), "Fri 2024-03-01 12:00");
+        assert_eq!(
+            format_date(CLOCK.now, 0, DateFormat::for_locale("de_DE.UTF-8")),
+            "Fri 01.03.2024 12:00"
+        );
+        assert_eq!(
+            format_date(CLOCK.now, -5 * 3600, DateFormat::for_locale("en_US.UTF-8")),
+            "Fri 03/01/2024 07:00"
+        );
+    }
+}
@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+//! Input events, which are those of crossterm, and key sequences for matching
+//! key presses.
+
 use std::any::Any;
 
 pub use ratatui::crossterm::event::*;
@@ -40,6 +43,86 @@ impl KeySequence {
         self
     }
 }
+impl std::fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        // Shift is implied by upper-case characters and Shift+Tab.
+        let implied = matches!(self.code, KeyCode::Char(_) | KeyCode::BackTab);
+        if self.modifiers.contains(KeyModifiers::SHIFT) && !implied {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+impl std::str::FromStr for KeySequence {
+    type Err = String;
+
+    /// Parse the format produced by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::empty();
+        let mut rest = s;
+        loop {
+            let (prefix, modifier) = if rest.starts_with("Ctrl+") {
+                ("Ctrl+", KeyModifiers::CONTROL)
+            } else if rest.starts_with("Alt+") {
+                ("Alt+", KeyModifiers::ALT)
+            } else if rest.starts_with("Shift+") && rest != "Shift+Tab" {
+                ("Shift+", KeyModifiers::SHIFT)
+            } else {
+                break;
+            };
+            if rest.len() == prefix.len() {
+                break;
+            }
+            modifiers.insert(modifier);
+            rest = &rest[prefix.len()..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match rest {
+                "Space" => KeyCode::Char(' '),
+                "Shift+Tab" => KeyCode::BackTab,
+                "PgUp" => KeyCode::PageUp,
+                "PgDn" => KeyCode::PageDown,
+                "Backspace" => KeyCode::Backspace,
+                "Enter" => KeyCode::Enter,
+                "Left" => KeyCode::Left,
+                "Right" => KeyCode::Right,
+                "Up" => KeyCode::Up,
+                "Down" => KeyCode::Down,
+                "Home" => KeyCode::Home,
+                "End" => KeyCode::End,
+                "Tab" => KeyCode::Tab,
+                "Delete" => KeyCode::Delete,
+                "Insert" => KeyCode::Insert,
+                "Esc" => KeyCode::Esc,
+                _ => {
+                    let number = rest.strip_prefix("F(").and_then(|n| n.strip_suffix(')'));
+                    match number.and_then(|n| n.parse().ok()) {
+                        Some(n) => KeyCode::F(n),
+                        None => return Err(format!("unknown key: {s}")),
+                    }
+                }
+            },
+        };
+
+        Ok(KeySequence::new(code, modifiers))
+    }
+}
 impl From<KeyCode> for KeySequence {
     fn from(code: KeyCode) -> Self {
         KeySequence::new(code, KeyModifiers::empty())
@@ -58,6 +141,32 @@ impl WithModifiers for KeyCode {
     }
 }
 
+/// Whether consecutive copies of the event may be merged into one event with
+/// a repeat count. This is the case for presses of navigation keys, which are
+/// typically repeated by holding the key down.
+///
+/// Widgets that can handle many presses at once use
+/// `Builder::on_key_press_repeat`. Other widgets still see every press,
+/// because presses that weren't handled like that are delivered again.
+pub(crate) fn is_coalescible(event: &Event) -> bool {
+    match event {
+        Event::Key(ev) => {
+            ev.kind == KeyEventKind::Press
+                && matches!(
+                    ev.code,
+                    KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Whether the event only reports that the mouse pointer moved. Only the last
+/// of several queued motions is processed.
+pub(crate) fn is_pointer_motion(event: &Event) -> bool {
+    matches!(event, Event::Mouse(ev) if ev.kind == MouseEventKind::Moved)
+}
+
 pub(crate) enum EventExt {
     Event(Event),
     Custom(Box<dyn Any + Send + Sync>),
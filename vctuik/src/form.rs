@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Simple form layout built from labels, inputs, check boxes and radio groups.
+//!
+//! Fields are laid out one per line, with their labels aligned in a column
+//! to the left. Validation messages are shown below the field they belong
+//! to, and pressing Enter submits the form unless there are any.
+
+use std::borrow::Cow;
+
+use ratatui::{layout::Rect, text::Span};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    check_box::{build_check_box, CheckBoxStateRef},
+    event::KeyCode,
+    input::{Input, InputAction},
+    radio_group::build_radio_group,
+    state::Builder,
+    theme::Themed,
+};
+
+#[derive(Debug, Default)]
+struct State {
+    /// Width of the widest label in the last frame.
+    label_width: u16,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormResult {
+    /// Whether any field was changed in this frame.
+    pub changed: bool,
+
+    /// Whether no validation errors were reported.
+    pub valid: bool,
+
+    /// Whether the form was submitted with Enter. Never set when there are
+    /// validation errors.
+    pub submitted: bool,
+}
+
+#[derive(Debug)]
+pub struct Form<'form> {
+    id: Cow<'form, str>,
+}
+impl<'form> Form<'form> {
+    pub fn new(id: impl Into<Cow<'form, str>>) -> Self {
+        Self { id: id.into() }
+    }
+
+    pub fn build<F>(self, builder: &mut Builder, f: F) -> FormResult
+    where
+        F: FnOnce(&mut FormBuilder),
+    {
+        let state_id = builder.add_state_id(self.id);
+        let state: &mut State = builder.get_state(state_id);
+
+        builder.nest().id(state_id).build(|builder| {
+            let mut form = FormBuilder {
+                builder,
+                label_width: state.label_width,
+                max_label_width: 0,
+                result: FormResult {
+                    valid: true,
+                    ..Default::default()
+                },
+                enter: false,
+            };
+            f(&mut form);
+
+            if form.builder.has_group_focus()
+                && form.builder.on_key_binding(KeyCode::Enter, "Submit")
+            {
+                form.enter = true;
+            }
+            form.result.submitted = form.enter && form.result.valid;
+
+            // Labels are aligned based on the previous frame, so redraw if
+            // they changed.
+            if form.max_label_width != state.label_width {
+                state.label_width = form.max_label_width;
+                form.builder.need_refresh();
+            }
+
+            form.result
+        })
+    }
+}
+
+pub struct FormBuilder<'form, 'builder, 'store, 'frame> {
+    builder: &'form mut Builder<'builder, 'store, 'frame>,
+    label_width: u16,
+    max_label_width: u16,
+    result: FormResult,
+    enter: bool,
+}
+impl<'builder, 'store, 'frame> FormBuilder<'_, 'builder, 'store, 'frame> {
+    /// Access the underlying builder, e.g. to add custom content to the form.
+    pub fn builder(&mut self) -> &mut Builder<'builder, 'store, 'frame> {
+        self.builder
+    }
+
+    /// Take a line for a field, render its label and return the area of the
+    /// field.
+    fn row(&mut self, label: &str) -> Rect {
+        let width = label.graphemes(true).count() as u16;
+        self.max_label_width = std::cmp::max(self.max_label_width, width);
+
+        let area = self.builder.take_lines_fixed(1);
+        let label_width = std::cmp::min(self.label_width.saturating_add(1), area.width);
+        let label_area = Rect {
+            width: label_width,
+            ..area
+        };
+        let span = Span::from(label.to_string()).theme_text(self.builder);
+        self.builder.frame().render_widget(span, label_area);
+
+        Rect {
+            x: area.x + label_width,
+            width: area.width - label_width,
+            ..area
+        }
+    }
+
+    /// Add a line of text in the field column.
+    pub fn label<'text>(&mut self, text: impl Into<Cow<'text, str>>) {
+        let area = self.row("");
+        let span = Span::from(text).theme_text(self.builder);
+        self.builder.frame().render_widget(span, area);
+    }
+
+    /// Add a validation message for the preceding field.
+    ///
+    /// This marks the form as invalid, so that it can't be submitted.
+    pub fn error<'text>(&mut self, message: impl Into<Cow<'text, str>>) {
+        self.result.valid = false;
+
+        let area = self.row("");
+        let style = self.builder.theme().text(self.builder.theme_context()).error;
+        self.builder
+            .frame()
+            .render_widget(Span::styled(message, style), area);
+    }
+
+    /// Add a text input field.
+    ///
+    /// The label is also used as the ID of the field, so it must be unique
+    /// within the form.
+    pub fn input(&mut self, label: &str, text: &mut String) -> Option<InputAction> {
+        let area = self.row(label);
+        let action = Input::new(label).area(area).build(self.builder, text);
+        match action {
+            Some(InputAction::TextChanged) => self.result.changed = true,
+            Some(InputAction::Enter) => self.enter = true,
+            None => {}
+        }
+        action
+    }
+
+    /// Add a check box. Its title is shown in the field column.
+    pub fn check_box<'s>(&mut self, title: &str, state: impl CheckBoxStateRef<'s>) {
+        let area = self.row("");
+        if build_check_box(self.builder, area, title, state) {
+            self.result.changed = true;
+        }
+    }
+
+    /// Add a group of mutually exclusive options.
+    ///
+    /// Returns true if the selection was changed.
+    pub fn radio_group(&mut self, label: &str, options: &[&str], selected: &mut usize) -> bool {
+        let area = self.row(label);
+        let changed = build_radio_group(self.builder, area, label, options, selected);
+        self.result.changed |= changed;
+        changed
+    }
+}
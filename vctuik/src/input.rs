@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use ratatui::crossterm::event::KeyCode;
+use ratatui::crossterm::event::{Event, KeyCode};
 use ratatui::layout::Position;
 use ratatui::widgets::Block;
 use ratatui::{layout::Rect, text::Span};
@@ -88,13 +88,21 @@ impl<'input> Input<'input> {
         }
 
         let action = if has_focus {
-            if builder.on_key_press(KeyCode::Enter) {
+            if builder.on_key_binding(KeyCode::Enter, "Confirm input") {
                 Some(InputAction::Enter)
-            } else if builder.on_key_press(KeyCode::Esc) {
+            } else if builder.on_key_binding(KeyCode::Esc, "Leave input") {
                 builder.drop_focus(state_id);
                 None
             } else {
                 builder.with_event(|ev| {
+                    if let Event::Paste(pasted) = ev {
+                        for ch in pasted.chars().filter(|ch| !ch.is_control()) {
+                            input.handle(tui_input::InputRequest::InsertChar(ch));
+                        }
+                        *text = input.value().into();
+                        return Some(InputAction::TextChanged);
+                    }
+
                     // Let tui-input handle most key events (typing, cursor movement, etc.)
                     input.handle_event(ev).and_then(|change| {
                         if change.value {
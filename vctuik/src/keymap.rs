@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Key bindings that are active in the current frame.
+//!
+//! Widgets handle keys via `Builder::on_key_binding`, which also records a
+//! description of the binding. The terminal uses the recorded bindings to show
+//! an overview when `?` is pressed and not handled otherwise, so the overview
+//! always matches what the code actually does.
+
+use std::borrow::Cow;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    event::{KeyCode, KeyEvent, KeySequence},
+    theme::Theme,
+};
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub keys: Vec<KeySequence>,
+    pub description: Cow<'static, str>,
+}
+
+/// Bindings registered in one context, e.g. one section of the screen.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub context: String,
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    groups: Vec<Group>,
+}
+impl Keymap {
+    /// Record a binding. Keys with the same description in the same context
+    /// are shown together.
+    pub fn add(&mut self, context: &str, key: KeySequence, description: Cow<'static, str>) {
+        let group = match self
+            .groups
+            .iter()
+            .position(|group| group.context == context)
+        {
+            Some(idx) => &mut self.groups[idx],
+            None => {
+                self.groups.push(Group {
+                    context: context.to_string(),
+                    bindings: Vec::new(),
+                });
+                self.groups.last_mut().unwrap()
+            }
+        };
+        match group
+            .bindings
+            .iter_mut()
+            .find(|binding| binding.description == description)
+        {
+            Some(binding) => {
+                if !binding.keys.contains(&key) {
+                    binding.keys.push(key);
+                }
+            }
+            None => group.bindings.push(Binding {
+                keys: vec![key],
+                description,
+            }),
+        }
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// Overlay listing the bindings that were active when it was opened.
+#[derive(Debug)]
+pub(crate) struct HelpOverlay {
+    keymap: Keymap,
+    scroll: usize,
+}
+impl HelpOverlay {
+    pub(crate) fn new(keymap: Keymap) -> Self {
+        Self { keymap, scroll: 0 }
+    }
+
+    /// Handle a key press while the overlay is open. Returns false if the
+    /// overlay should be closed.
+    pub(crate) fn handle_key(&mut self, ev: &KeyEvent) -> bool {
+        match ev.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?') => return false,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll += 1,
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll += 10,
+            KeyCode::Home => self.scroll = 0,
+            _ => {}
+        }
+        true
+    }
+
+    pub(crate) fn render(&mut self, frame: &mut Frame, theme: &Theme) {
+        let text = &theme.modal_text;
+        let keys: Vec<Vec<String>> = self
+            .keymap
+            .groups()
+            .iter()
+            .map(|group| {
+                group
+                    .bindings
+                    .iter()
+                    .map(|binding| {
+                        binding
+                            .keys
+                            .iter()
+                            .map(|key| key.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" / ")
+                    })
+                    .collect()
+            })
+            .collect();
+        let width = keys
+            .iter()
+            .flatten()
+            .map(|keys| keys.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for (group, keys) in self.keymap.groups().iter().zip(keys) {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::styled(group.context.clone(), text.header1));
+            for (binding, keys) in group.bindings.iter().zip(keys) {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {keys:width$}  "), text.highlight),
+                    Span::styled(binding.description.clone(), text.normal),
+                ]));
+            }
+        }
+
+        let area = frame.area();
+        let area = Rect {
+            x: area.x + area.width / 10,
+            y: area.y + area.height / 10,
+            width: area.width - area.width / 5,
+            height: area.height - area.height / 5,
+        };
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .title(" Key bindings ")
+            .title_bottom(" Esc: close ")
+            .border_style(theme.modal_frame)
+            .style(theme.modal_background.patch(text.normal));
+        let inner = block.inner(area);
+        self.scroll = self
+            .scroll
+            .min(lines.len().saturating_sub(inner.height as usize));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(Paragraph::new(lines).scroll((self.scroll as u16, 0)), inner);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::{KeyModifiers, WithModifiers};
+
+    #[test]
+    fn merge_bindings() {
+        let mut keymap = Keymap::default();
+        keymap.add("Pager", KeyCode::Up.into(), "Move".into());
+        keymap.add("Pager", KeyCode::Down.into(), "Move".into());
+        keymap.add("Pager", KeyCode::Up.into(), "Move".into());
+        keymap.add("General", KeyCode::Char('q').into(), "Quit".into());
+        keymap.add("Pager", KeyCode::Char('N').into(), "Find previous".into());
+
+        let groups = keymap.groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].context, "Pager");
+        assert_eq!(groups[0].bindings.len(), 2);
+        assert_eq!(groups[0].bindings[0].keys.len(), 2);
+        assert_eq!(groups[1].bindings[0].description, "Quit");
+    }
+
+    #[test]
+    fn key_names() {
+        let key = |key: KeySequence| key.to_string();
+        assert_eq!(key(KeyCode::Char('N').into()), "N");
+        assert_eq!(key(KeyCode::Char(' ').into()), "Space");
+        assert_eq!(key(KeyCode::Char('s').with_modifiers(KeyModifiers::CONTROL)), "Ctrl+s");
+        assert_eq!(key(KeyCode::Up.with_modifiers(KeyModifiers::ALT)), "Alt+Up");
+        assert_eq!(key(KeyCode::Enter.into()), "Enter");
+    }
+}
@@ -1,5 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+//! Vertical layout of the items that widgets take from the builder, within
+//! the constraints on their sizes.
+
 use std::hash::Hash;
 use std::{collections::HashMap, ops::Range};
 
@@ -77,7 +80,7 @@ struct CacheItem {
 }
 
 #[derive(Debug)]
-pub struct LayoutCache<Id> {
+pub(crate) struct LayoutCache<Id> {
     items: HashMap<Id, CacheItem>,
 }
 impl<Id> Default for LayoutCache<Id> {
@@ -122,7 +125,7 @@ impl<Id> Item<Id> {
 }
 
 #[derive(Debug)]
-pub struct LayoutEngine<Id> {
+pub(crate) struct LayoutEngine<Id> {
     items: Vec<Item<Id>>,
     drags: Vec<(usize, i16)>,
     size: u16,
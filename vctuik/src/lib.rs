@@ -1,22 +1,108 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+//! An immediate mode TUI toolkit that layers composable input handling on top
+//! of [ratatui].
+//!
+//! The UI is built from scratch in every frame by a closure that is passed to
+//! [`Terminal::run`](terminal::Terminal::run). Widgets are functions or
+//! builders that take the [`Builder`](state::Builder) of the frame, draw
+//! themselves into the area that they take from it, and handle the event of
+//! the frame. State that outlives a frame, such as the scroll position of a
+//! pager, is kept by the builder under the widget's ID, or by the application.
+//!
+//! ```
+//! use vctuik::{
+//!     event::{Event, KeyCode, KeyEvent, KeyModifiers},
+//!     label::add_label,
+//!     section::with_section,
+//!     terminal::Terminal,
+//! };
+//!
+//! # fn main() -> vctuik::prelude::Result<()> {
+//! // A headless terminal draws into a buffer and takes its events from a
+//! // queue, which makes it suitable for tests.
+//! let mut terminal = Terminal::headless(30, 4)?;
+//! terminal.push_event(Event::Key(KeyEvent::new(KeyCode::Char('+'), KeyModifiers::NONE)));
+//!
+//! let mut count = 0;
+//! terminal.run(|builder| {
+//!     if builder.on_key_binding(KeyCode::Char('+'), "Count") {
+//!         count += 1;
+//!     }
+//!     with_section(builder, "Counter", |builder| {
+//!         add_label(builder, format!("Counted {count} time(s)"));
+//!     });
+//!     Ok(true)
+//! })?;
+//!
+//! let buffer = terminal.backend().buffer();
+//! let line: String = (0..buffer.area.width).map(|x| buffer[(x, 1)].symbol()).collect();
+//! assert_eq!(line.trim_end(), "Counted 1 time(s)");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Public API
+//!
+//! The following modules make up the public API, which follows semantic
+//! versioning:
+//!
+//! * Building the UI: [`state`] (the [`Builder`](state::Builder) and IDs),
+//!   [`layout`], [`terminal`], and [`signals`] for waking up the event loop
+//!   from other threads
+//! * Widgets: [`chart`], [`check_box`], [`command`], [`date_picker`], [`form`],
+//!   [`input`], [`label`], [`pager`], [`radio_group`], [`section`],
+//!   [`status_line`], and [`table`]
+//! * Styling: [`theme`]
+//! * Input: [`event`] and [`keymap`]
+//! * Utilities: [`prelude`] and [`stringtools`]
+//!
+//! Widgets that need an input line are behind the `input-widget` feature,
+//! which is enabled by default.
+//!
+//! Types of ratatui appear in the API, e.g. `Rect` and `Style`. The version of
+//! ratatui that vctuik is built with is re-exported as [`ratatui`], so that
+//! applications don't have to match it. Upgrading it is a breaking change.
+//!
+//! Items that are hidden from the documentation are implementation details,
+//! even if they are public, and may change in any release. So may the
+//! `vctuik-unsafe-internals` crate, which contains the code that needs
+//! `unsafe`. It is not part of the API.
+
+pub mod chart;
 pub mod check_box;
 pub mod command;
+#[cfg(feature = "input-widget")]
+pub mod date_picker;
 pub mod event;
 #[cfg(feature = "input-widget")]
+pub mod form;
+#[cfg(feature = "input-widget")]
 pub mod input;
+pub mod keymap;
 pub mod label;
 pub mod layout;
+mod macros;
 pub mod pager;
 pub mod prelude;
+pub mod radio_group;
+mod recorder;
+mod screen_reader;
 pub mod section;
 pub mod signals;
 pub mod state;
+pub mod status_line;
 pub mod stringtools;
 pub mod table;
 pub mod terminal;
 pub mod theme;
+mod tooltip;
+
+pub use ratatui;
 
+/// Switch the console to the alternate screen in raw mode, with mouse capture
+/// and bracketed paste, and return the terminal that draws on it. The console
+/// is restored when the terminal is dropped, or on panic.
 pub fn init() -> prelude::Result<terminal::Terminal> {
     terminal::Terminal::init()
 }
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Recording and replaying of key macros.
+//!
+//! `Q` followed by a register key (a letter or digit) starts recording all key
+//! presses into the register, and `Q` stops the recording. `@` followed by a
+//! register key replays the register, and `@@` replays the most recently
+//! replayed register. Like `?`, `Q` and `@` only take effect if no widget
+//! handles them.
+//!
+//! Registers are saved to a file if the application sets one, one register
+//! per line:
+//!
+//! ```text
+//! a Left Down Down n
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::PathBuf,
+};
+
+use log::warn;
+use ratatui::prelude::*;
+
+use crate::{
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeySequence},
+    state::BuildStore,
+    theme::Theme,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Idle,
+    AwaitRecordRegister,
+    Recording(char, Vec<KeySequence>),
+    AwaitReplayRegister,
+}
+
+#[derive(Debug)]
+pub(crate) struct Macros {
+    mode: Mode,
+    registers: BTreeMap<char, Vec<KeySequence>>,
+    last_replayed: Option<char>,
+    file: Option<PathBuf>,
+}
+impl Macros {
+    pub(crate) fn new() -> Self {
+        Self {
+            mode: Mode::Idle,
+            registers: BTreeMap::new(),
+            last_replayed: None,
+            file: None,
+        }
+    }
+
+    pub(crate) fn set_file(&mut self, file: PathBuf) {
+        self.registers.clear();
+        match std::fs::read_to_string(&file) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    match parse_register(line) {
+                        Ok((register, keys)) => {
+                            self.registers.insert(register, keys);
+                        }
+                        Err(err) => warn!("{}: {}", file.display(), err),
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => warn!("Error reading {}: {}", file.display(), err),
+        }
+        self.file = Some(file);
+    }
+
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let mut contents = String::new();
+        for (register, keys) in &self.registers {
+            let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+            contents.push_str(&format!("{} {}\n", register, keys.join(" ")));
+        }
+        let result = file
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(file, contents));
+        if let Err(err) = result {
+            warn!("Error writing {}: {}", file.display(), err);
+        }
+    }
+
+    /// Look at a key event before the UI processes it.
+    ///
+    /// Returns true if the event was consumed, i.e. it selected a register.
+    /// Key presses to replay are appended to `replay`.
+    pub(crate) fn pre_event(&mut self, event: &Event, replay: &mut VecDeque<Event>) -> bool {
+        let Event::Key(ev) = event else {
+            return false;
+        };
+        if ev.kind != KeyEventKind::Press {
+            return false;
+        }
+
+        match &mut self.mode {
+            Mode::Idle => false,
+            Mode::Recording(_, keys) => {
+                keys.push(KeySequence::new(ev.code, ev.modifiers));
+                false
+            }
+            Mode::AwaitRecordRegister => {
+                self.mode = match ev.code {
+                    KeyCode::Char(ch) if ch.is_ascii_alphanumeric() => {
+                        Mode::Recording(ch, Vec::new())
+                    }
+                    _ => Mode::Idle,
+                };
+                true
+            }
+            Mode::AwaitReplayRegister => {
+                self.mode = Mode::Idle;
+                let register = match ev.code {
+                    KeyCode::Char('@') => self.last_replayed,
+                    KeyCode::Char(ch) => Some(ch),
+                    _ => None,
+                };
+                if let Some(keys) = register.and_then(|register| self.registers.get(&register)) {
+                    self.last_replayed = register;
+                    replay.extend(keys.iter().map(|key| {
+                        Event::Key(KeyEvent::new(key.code, key.modifiers))
+                    }));
+                }
+                true
+            }
+        }
+    }
+
+    /// Handle the macro keys if the UI didn't handle them.
+    ///
+    /// Returns true if the mode changed.
+    pub(crate) fn post_event(&mut self, build_store: &mut BuildStore) -> bool {
+        if let Mode::Recording(_, keys) = &mut self.mode {
+            // pre_event only recorded one press of a coalesced event.
+            if let Some(&key) = keys.last() {
+                for _ in 1..build_store.handled_repeats() {
+                    keys.push(key);
+                }
+            }
+        }
+
+        let keymap = &mut build_store.keymap;
+        let record: KeySequence = KeyCode::Char('Q').into();
+        let replay: KeySequence = KeyCode::Char('@').into();
+        if let Mode::Recording(..) = self.mode {
+            keymap.add("General", record, "Stop recording the macro".into());
+        } else {
+            keymap.add("General", record, "Record a macro into a register".into());
+        }
+        keymap.add("General", replay, "Replay a macro from a register".into());
+
+        if build_store.is_unhandled_key_press(record) {
+            self.mode = match std::mem::replace(&mut self.mode, Mode::Idle) {
+                Mode::Recording(register, mut keys) => {
+                    // Drop the Q that stopped the recording.
+                    keys.pop();
+                    self.registers.insert(register, keys);
+                    self.save();
+                    Mode::Idle
+                }
+                _ => Mode::AwaitRecordRegister,
+            };
+            true
+        } else if build_store.is_unhandled_key_press(replay)
+            && !matches!(self.mode, Mode::Recording(..))
+        {
+            self.mode = Mode::AwaitReplayRegister;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Show the macro state in the top right corner of the screen.
+    pub(crate) fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let indicator = match &self.mode {
+            Mode::Idle => return,
+            Mode::AwaitRecordRegister => "record macro: register?".to_string(),
+            Mode::Recording(register, _) => format!("recording @{register}"),
+            Mode::AwaitReplayRegister => "replay macro: register?".to_string(),
+        };
+        let area = frame.area();
+        let width = std::cmp::min(indicator.len() as u16 + 2, area.width);
+        let area = Rect {
+            x: area.x + area.width - width,
+            width,
+            height: std::cmp::min(1, area.height),
+            ..area
+        };
+        let span = Span::styled(format!(" {indicator} "), theme.text.highlight);
+        frame.render_widget(span, area);
+    }
+}
+
+fn parse_register(line: &str) -> Result<(char, Vec<KeySequence>), String> {
+    let mut words = line.split_whitespace();
+    let register = words.next().ok_or("empty line")?;
+    let mut chars = register.chars();
+    let (Some(register), None) = (chars.next(), chars.next()) else {
+        return Err(format!("bad register: {register}"));
+    };
+    let keys = words.map(str::parse).collect::<Result<_, _>>()?;
+    Ok((register, keys))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let (register, keys) = parse_register("a Left Ctrl+s Space G Shift+Tab").unwrap();
+        assert_eq!(register, 'a');
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        assert_eq!(keys, ["Left", "Ctrl+s", "Space", "G", "Shift+Tab"]);
+
+        assert!(parse_register("ab Left").is_err());
+        assert!(parse_register("a Frobnicate").is_err());
+    }
+}
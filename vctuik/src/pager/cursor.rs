@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::any::Any;
+use std::{
+    any::Any,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use super::PagerSource;
 
@@ -56,15 +61,129 @@ impl std::cmp::Ord for Cursor {
     }
 }
 
+/// Number of `PersistentCursor`s that exist.
+static LIVE_CURSORS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of lines before and after the cursor whose contents are remembered.
+const CONTEXT_LINES: usize = 2;
+
+/// Maximum distance from the line found via the anchor in which the
+/// remembered contents are searched for.
+const SEARCH_RADIUS: usize = 200;
+
+fn hash_line(line: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line.trim_end().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes of the contents around a line.
+#[derive(Debug, Default)]
+struct Fingerprint {
+    /// Hashes of the lines from `line - before` to `line + CONTEXT_LINES`
+    /// (clamped to the source).
+    hashes: Vec<u64>,
+
+    /// Number of lines before the cursor line in `hashes`.
+    before: usize,
+}
+impl Fingerprint {
+    fn new<S>(source: &S, line: usize) -> Self
+    where
+        S: PagerSource + ?Sized,
+    {
+        let num_lines = source.num_lines();
+        if line >= num_lines {
+            return Self::default();
+        }
+
+        let first = line.saturating_sub(CONTEXT_LINES);
+        let end = std::cmp::min(num_lines, line + CONTEXT_LINES + 1);
+        Fingerprint {
+            hashes: (first..end)
+                .map(|line| hash_line(&source.get_raw_line(line, 0, usize::MAX)))
+                .collect(),
+            before: line - first,
+        }
+    }
+
+    fn center(&self) -> Option<u64> {
+        self.hashes.get(self.before).copied()
+    }
+
+    /// Find the line whose contents match the fingerprint best, preferring
+    /// lines close to `guess`.
+    ///
+    /// The cursor line itself must match, as well as at least half of the
+    /// context lines.
+    fn find<S>(&self, source: &S, guess: usize) -> Option<usize>
+    where
+        S: PagerSource + ?Sized,
+    {
+        let center = self.center()?;
+        let num_lines = source.num_lines();
+        let first = guess.saturating_sub(SEARCH_RADIUS + self.before);
+        let end = std::cmp::min(num_lines, guess + SEARCH_RADIUS + CONTEXT_LINES + 1);
+        let window: Vec<u64> = (first..end)
+            .map(|line| hash_line(&source.get_raw_line(line, 0, usize::MAX)))
+            .collect();
+
+        let score = |line: usize| -> Option<usize> {
+            if window.get(line - first) != Some(&center) {
+                return None;
+            }
+            let matches = self
+                .hashes
+                .iter()
+                .enumerate()
+                .filter(|&(idx, hash)| {
+                    idx != self.before
+                        && (line + idx)
+                            .checked_sub(self.before)
+                            .and_then(|other| other.checked_sub(first))
+                            .and_then(|other| window.get(other))
+                            == Some(hash)
+                })
+                .count();
+            (2 * matches >= self.hashes.len() - 1).then_some(matches)
+        };
+
+        let mut best: Option<(usize, usize)> = None;
+        for distance in 0..=SEARCH_RADIUS {
+            let candidates = [guess.checked_sub(distance), Some(guess + distance)];
+            for line in candidates.into_iter().flatten() {
+                if line < first || line >= end {
+                    continue;
+                }
+                if let Some(score) = score(line) {
+                    if best.is_none_or(|(_, best)| score > best) {
+                        best = Some((line, score));
+                    }
+                }
+            }
+            if best.is_some_and(|(_, score)| score == self.hashes.len() - 1) {
+                break;
+            }
+        }
+        best.map(|(line, _)| line)
+    }
+}
+
 /// A persistent cursor into a `PagerSource`.
 ///
 /// This is used to remember a position in the pager source across frames even for pager sources
 /// whose contents may change.
+///
+/// The position is primarily remembered via the anchor provided by the pager source. In addition,
+/// the contents around the cursor are remembered, so that the cursor can follow its line when the
+/// anchor is lost or points elsewhere after the contents changed, e.g. when lines were inserted
+/// or removed before it.
 #[derive(Debug)]
 pub struct PersistentCursor {
     anchor: Vec<Anchor>,
     line_offset: usize,
     col: usize,
+    fingerprint: Fingerprint,
 }
 impl PersistentCursor {
     pub fn persist<S>(source: &S, pos: Cursor) -> Self
@@ -72,10 +191,12 @@ impl PersistentCursor {
         S: PagerSource + ?Sized,
     {
         let (anchor, line_offset) = source.persist_line_number(pos.line);
+        LIVE_CURSORS.fetch_add(1, Ordering::Relaxed);
         PersistentCursor {
             anchor,
             line_offset,
             col: pos.col,
+            fingerprint: Fingerprint::new(source, pos.line),
         }
     }
 
@@ -90,7 +211,83 @@ impl PersistentCursor {
             line = max_line;
             success = false;
         }
-        
+
+        if let Some(center) = self.fingerprint.center() {
+            let unchanged = line < source.num_lines()
+                && hash_line(&source.get_raw_line(line, 0, usize::MAX)) == center;
+            if !unchanged {
+                if let Some(found) = self.fingerprint.find(source, line) {
+                    line = found;
+                    success = true;
+                }
+            }
+        }
+
         (Cursor::new(line, self.col), success)
     }
+
+    /// Number of persistent cursors that exist in the process, e.g. to check
+    /// that pagers don't accumulate them over a long session.
+    pub fn live_count() -> usize {
+        LIVE_CURSORS.load(Ordering::Relaxed)
+    }
+}
+impl Drop for PersistentCursor {
+    fn drop(&mut self) {
+        LIVE_CURSORS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pager::StringPagerSource;
+
+    fn text(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    #[test]
+    fn follows_content() {
+        let old = text(&["a", "b", "c", "d", "e", "f", "g"]);
+        let new = text(&["x", "y", "a", "b", "c", "d", "e", "f", "g"]);
+        let cursor = PersistentCursor::persist(&StringPagerSource::new(&old), Cursor::new(3, 1));
+        assert_eq!(
+            cursor.retrieve(&StringPagerSource::new(&new)),
+            (Cursor::new(5, 1), true)
+        );
+
+        let cursor = PersistentCursor::persist(&StringPagerSource::new(&new), Cursor::new(5, 0));
+        assert_eq!(
+            cursor.retrieve(&StringPagerSource::new(&old)),
+            (Cursor::new(3, 0), true)
+        );
+    }
+
+    #[test]
+    fn fuzzy_context() {
+        // The line itself is unchanged, but one of its neighbours was edited.
+        let old = text(&["1", "2", "fn main() {", "3", "4"]);
+        let new = text(&["0", "1", "2", "fn main() {", "changed", "4"]);
+        let cursor = PersistentCursor::persist(&StringPagerSource::new(&old), Cursor::new(2, 0));
+        assert_eq!(cursor.retrieve(&StringPagerSource::new(&new)).0.line, 3);
+    }
+
+    #[test]
+    fn prefers_nearest() {
+        let old = text(&["a", "}", "b", "}", "a", "}", "b"]);
+        let cursor = PersistentCursor::persist(&StringPagerSource::new(&old), Cursor::new(5, 0));
+        assert_eq!(cursor.retrieve(&StringPagerSource::new(&old)).0.line, 5);
+
+        let new = text(&["a", "}", "b", "}", "a", "}", "b", "x"]);
+        assert_eq!(cursor.retrieve(&StringPagerSource::new(&new)).0.line, 5);
+    }
+
+    #[test]
+    fn content_removed() {
+        let old = text(&["a", "b", "c", "d"]);
+        let new = text(&["w", "x", "y", "z"]);
+        let cursor = PersistentCursor::persist(&StringPagerSource::new(&old), Cursor::new(2, 0));
+        assert_eq!(cursor.retrieve(&StringPagerSource::new(&new)).0.line, 2);
+    }
 }
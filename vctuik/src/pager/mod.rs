@@ -8,7 +8,7 @@ mod widget;
 pub use rich_source::{RichPagerSource, RichPagerSourceBuilder};
 pub use cursor::{Anchor, Cursor, PersistentCursor};
 pub use string_source::StringPagerSource;
-pub use widget::{Pager, PagerState};
+pub use widget::{Pager, PagerResult, PagerState};
 
 use std::borrow::Cow;
 use std::ops::Range;
@@ -19,6 +19,25 @@ use regex::Regex;
 use crate::{command, event::KeyCode, prelude::*, theme};
 use crate::stringtools::StrScan;
 
+/// How a line is shown in the minimap of the `Pager` widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapMark {
+    Added,
+    Removed,
+
+    /// A change that is not interesting, e.g. because it only repeats a change
+    /// of the base.
+    Unimportant,
+}
+
+/// A hyperlink in a line of a `PagerSource`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Columns of the line that are covered by the link.
+    pub cols: Range<usize>,
+    pub url: String,
+}
+
 /// A source of data for the `Pager` widget.
 ///
 /// `StringPagerSource` is a simple implementation sufficient for showing plain text.
@@ -55,6 +74,17 @@ pub trait PagerSource {
         None
     }
 
+    /// Classify the given line for the minimap. Lines without a mark are shown
+    /// as blank.
+    fn minimap_mark(&self, _line: usize) -> Option<MinimapMark> {
+        None
+    }
+
+    /// Returns the hyperlinks in the given line.
+    fn get_links(&self, _line: usize) -> Vec<Link> {
+        Vec::new()
+    }
+
     /// Return (anchor, line_offset) for the given line.
     ///
     /// This is used to persist cursors across frames. The intention is that the
@@ -88,7 +118,7 @@ pub fn run(text: String) -> Result<()> {
         let was_search = command.as_ref().is_some_and(|cmd| cmd.starts_with('/'));
 
         let action = command::CommandLine::new("command", &mut command)
-            .help("/ to search, q to quit")
+            .help("/ to search, ? for help, q to quit")
             .build(builder, |builder, _| {
                 if let Some(error) = &error {
                     let area = builder.take_lines_fixed(1);
@@ -140,12 +170,12 @@ pub fn run(text: String) -> Result<()> {
         }
 
         // Global key bindings
-        if builder.on_key_press(KeyCode::Char('/')) {
+        if builder.on_key_binding(KeyCode::Char('/'), "Search") {
             command = Some("/".into());
             search = None;
             builder.need_refresh();
         }
-        if builder.on_key_press(KeyCode::Char('q')) {
+        if builder.on_key_binding(KeyCode::Char('q'), "Quit") {
             running = false;
         }
 
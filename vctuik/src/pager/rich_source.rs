@@ -90,6 +90,9 @@ pub struct RichPagerSourceBuilder<'text> {
     custom_style_map: HashMap<style::Style, CustomStyleId>,
 
     style: Vec<(Index, Style)>,
+
+    /// (begin, end, url) of hyperlinks, in order
+    links: Vec<(Index, Index, String)>,
 }
 impl<'text> Default for RichPagerSourceBuilder<'text> {
     fn default() -> Self {
@@ -111,6 +114,7 @@ impl<'text> Default for RichPagerSourceBuilder<'text> {
                 Index { element: 0, offset: 0 },
                 Style::default(),
             )],
+            links: Vec::new(),
         }
     }
 }
@@ -189,6 +193,38 @@ impl<'text> RichPagerSourceBuilder<'text> {
         self.set_style(Style::default())
     }
 
+    /// Write the text as a hyperlink to the URL. The text must not contain
+    /// newlines.
+    pub fn write_link(&mut self, text: &str, url: impl Into<String>) -> std::fmt::Result {
+        let (len, offset) = match self.content.last() {
+            Some(Element::String(s)) => (self.content.len(), s.len()),
+            _ => (0, 0),
+        };
+        self.write_str(text)?;
+
+        let element = self.content.len() - 1;
+        let Some(Element::String(s)) = self.content.last() else { unreachable!() };
+        let begin = if len == self.content.len() { offset } else { 0 };
+        self.links.push((
+            Index { element, offset: begin },
+            Index { element, offset: s.len() },
+            url.into(),
+        ));
+        Ok(())
+    }
+
+    /// Number of lines written so far, which is also the line number of the
+    /// next line that is written.
+    pub fn num_lines(&self) -> usize {
+        self.content
+            .iter()
+            .map(|element| match element.as_ref() {
+                ElementRef::Pager(pager) => pager.num_lines(),
+                ElementRef::Str(s) => s.split_inclusive('\n').count(),
+            })
+            .sum()
+    }
+
     pub fn set_indent(&mut self, indent: usize) {
         if let Some(last) = self.indent.last_mut() {
             if last.0 == self.content.len() {
@@ -322,6 +358,7 @@ impl<'text> RichPagerSourceBuilder<'text> {
             landmarks,
             custom_styles: self.custom_styles,
             style: self.style,
+            links: self.links,
             ..Default::default()
         }
     }
@@ -404,6 +441,8 @@ pub struct RichPagerSource<'text> {
     style: Vec<(Index, Style)>,
     style_lookup_cache: RefCell<usize>,
 
+    links: Vec<(Index, Index, String)>,
+
     /// Line number -> indent
     indent: Vec<(usize, usize)>,
     indent_lookup_cache: RefCell<usize>,
@@ -706,6 +745,48 @@ impl<'text> PagerSource for RichPagerSource<'text> {
             .or(outer_fr)
     }
 
+    fn minimap_mark(&self, line: usize) -> Option<MinimapMark> {
+        let idx = self.idx_from_pos(Cursor { line, col: 0 });
+        match self.content.get(idx.element)?.as_ref() {
+            ElementRef::Pager(pager) => pager.minimap_mark(idx.offset),
+            ElementRef::Str(_) => match self.style[self.style_idx_from_idx(idx)].1 {
+                Style::Themed(theme::TextStyle::Added) => Some(MinimapMark::Added),
+                Style::Themed(theme::TextStyle::Removed) => Some(MinimapMark::Removed),
+                _ => None,
+            },
+        }
+    }
+
+    fn get_links(&self, line: usize) -> Vec<Link> {
+        let indent = self.line_indent(line);
+        let idx = self.idx_from_pos(Cursor { line, col: 0 });
+        let mut links = match self.content.get(idx.element).map(Element::as_ref) {
+            Some(ElementRef::Pager(pager)) => pager.get_links(idx.offset),
+            Some(ElementRef::Str(s)) => {
+                let text = s[idx.offset..].get_first_line(usize::MAX);
+                let end = Index { offset: idx.offset + text.len(), ..idx };
+                let col = |index: Index| {
+                    let offset = index.offset.clamp(idx.offset, end.offset);
+                    text[..offset - idx.offset].chars().count()
+                };
+                let first = self.links.partition_point(|(_, link_end, _)| *link_end <= idx);
+                self.links[first..]
+                    .iter()
+                    .take_while(|(link_begin, _, _)| *link_begin < end)
+                    .map(|(link_begin, link_end, url)| Link {
+                        cols: col(*link_begin)..col(*link_end),
+                        url: url.clone(),
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        for link in &mut links {
+            link.cols = link.cols.start + indent..link.cols.end + indent;
+        }
+        links
+    }
+
     fn persist_line_number(&self, line: usize) -> (Vec<Anchor>, usize) {
         let idx = self.idx_from_pos(Cursor { line, col: 0 });
         match self.content.get(idx.element).map(Element::as_ref) {
@@ -27,6 +27,9 @@ pub struct PagerState {
     last_height: u16,
 
     collapse: Vec<PersistentCursor>,
+
+    /// Selected line as last announced in the screen reader mode.
+    announced: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -595,11 +598,37 @@ impl<'result> PagerResult<'result> {
         self.select
     }
 
+    /// Line number of the selected line.
+    pub fn selected_line(&self) -> usize {
+        self.select
+    }
+
+    /// Range of document lines from the first to the last line that is shown on
+    /// the screen. Collapsed lines in between are included.
+    pub fn visible_lines(&self) -> Range<usize> {
+        let height = (self.state.last_height as usize).max(1);
+        let last = self.screen_forward(self.scroll.line).take(height).last();
+        self.scroll.line..last.map_or(self.scroll.line, |line| line + 1)
+    }
+
     pub fn move_to(&mut self, line: usize) {
         let line = self.move_to_no_scroll(line);
         self.scroll_line_into_view(line);
     }
 
+    /// Collapse the folding range that starts at the given line, e.g. to hide
+    /// parts of the document by default. Does nothing if no folding range
+    /// starts there.
+    pub fn collapse(&mut self, line: usize) {
+        let starts_range = self
+            .source
+            .get_folding_range(line, false)
+            .is_some_and(|(range, _)| range.start == line);
+        if starts_range {
+            self.set_collapsed(line, true);
+        }
+    }
+
     fn search_impl(&self, pattern: &Regex, line: usize) -> bool {
         let line = self.source.get_raw_line(line, 0, usize::MAX);
         pattern.find(&line).is_some()
@@ -622,12 +651,14 @@ impl<'result> PagerResult<'result> {
 pub struct Pager<'build, 'result> {
     source: &'result dyn PagerSource,
     search_pattern: Option<Cow<'build, Regex>>,
+    minimap: bool,
 }
 impl<'build, 'result> Pager<'build, 'result> {
     pub fn new(source: &'result impl PagerSource) -> Self {
         Pager {
             source,
             search_pattern: None,
+            minimap: false,
         }
     }
 
@@ -638,6 +669,13 @@ impl<'build, 'result> Pager<'build, 'result> {
         }
     }
 
+    /// Show a gutter at the right edge with an overview of the whole document,
+    /// based on `PagerSource::minimap_mark`. The lines that are on screen are
+    /// marked, and clicking jumps to the corresponding part of the document.
+    pub fn minimap(self, minimap: bool) -> Self {
+        Self { minimap, ..self }
+    }
+
     pub fn build<'id>(self, builder: &mut Builder, id: impl Into<Cow<'id, str>>) {
         let state_id = builder.add_state_id(id);
         let state: &mut PagerState = builder.get_state(state_id);
@@ -670,6 +708,17 @@ impl<'build, 'result> Pager<'build, 'result> {
             builder.take_lines(LayoutItem1D::new(Constraint1D::new_min(5)).id(state_id, true));
         let has_focus = builder.check_focus(state_id);
 
+        let minimap_width = if self.minimap && area.width > 2 { 1 } else { 0 };
+        let minimap_area = Rect {
+            x: area.x + area.width - minimap_width,
+            width: minimap_width,
+            ..area
+        };
+        let area = Rect {
+            width: area.width - minimap_width,
+            ..area
+        };
+
         state.last_height = area.height;
 
         let mut result = PagerResult::new(self.source, state);
@@ -681,7 +730,10 @@ impl<'build, 'result> Pager<'build, 'result> {
         let mouse_page_size = std::cmp::min(5, vertical_page_size);
 
         if has_focus {
-            if builder.on_key_press(KeyCode::Left) {
+            const MOVE: &str = "Move the selection";
+            const SCROLL: &str = "Scroll without moving the selection";
+
+            if builder.on_key_binding(KeyCode::Left, "Scroll left / fold") {
                 if result.scroll.col > 0 {
                     result.scroll_by(0, -horizontal_page_size);
                 } else if let Some((range, _)) = self.source.get_folding_range(result.select, false)
@@ -699,50 +751,66 @@ impl<'build, 'result> Pager<'build, 'result> {
                     }
                 }
             }
-            if builder.on_key_press(KeyCode::Right) {
+            if builder.on_key_binding(KeyCode::Right, "Scroll right / unfold") {
                 if result.is_collapsed(result.select) {
                     result.set_collapsed(result.select, false);
                 } else {
                     result.scroll_by(0, horizontal_page_size);
                 }
             }
-            if builder.on_key_press(KeyCode::Up.with_modifiers(KeyModifiers::ALT)) {
-                result.scroll_by(-1, 0);
+            // Keys that are held down are handled all at once.
+            let alt = KeyModifiers::ALT;
+            let n = builder.on_key_binding_repeat(KeyCode::Up.with_modifiers(alt), SCROLL);
+            if n != 0 {
+                result.scroll_by(-(n as isize), 0);
             }
-            if builder.on_key_press(KeyCode::Down.with_modifiers(KeyModifiers::ALT)) {
-                result.scroll_by(1, 0);
+            let n = builder.on_key_binding_repeat(KeyCode::Down.with_modifiers(alt), SCROLL);
+            if n != 0 {
+                result.scroll_by(n as isize, 0);
             }
-            if builder.on_key_press(KeyCode::PageUp.with_modifiers(KeyModifiers::ALT)) {
-                result.scroll_by(-vertical_page_size, 0);
+            let n = builder.on_key_binding_repeat(KeyCode::PageUp.with_modifiers(alt), SCROLL);
+            if n != 0 {
+                result.scroll_by(-vertical_page_size * n as isize, 0);
             }
-            if builder.on_key_press(KeyCode::PageDown.with_modifiers(KeyModifiers::ALT)) {
-                result.scroll_by(vertical_page_size, 0);
+            let n = builder.on_key_binding_repeat(KeyCode::PageDown.with_modifiers(alt), SCROLL);
+            if n != 0 {
+                result.scroll_by(vertical_page_size * n as isize, 0);
             }
-            if builder.on_key_press(KeyCode::Up) {
-                result.move_by(-1);
+            let n = builder.on_key_binding_repeat(KeyCode::Up, MOVE);
+            if n != 0 {
+                result.move_by(-(n as isize));
             }
-            if builder.on_key_press(KeyCode::Down) {
-                result.move_by(1);
+            let n = builder.on_key_binding_repeat(KeyCode::Down, MOVE);
+            if n != 0 {
+                result.move_by(n as isize);
             }
-            if builder.on_key_press(KeyCode::PageUp) {
-                result.move_by(-vertical_page_size);
+            let n = builder.on_key_binding_repeat(KeyCode::PageUp, MOVE);
+            if n != 0 {
+                result.move_by(-vertical_page_size * n as isize);
             }
-            if builder.on_key_press(KeyCode::PageDown) {
-                result.move_by(vertical_page_size);
+            let n = builder.on_key_binding_repeat(KeyCode::PageDown, MOVE);
+            if n != 0 {
+                result.move_by(vertical_page_size * n as isize);
             }
-            if builder.on_key_press_any(&[KeyCode::Home.into(), KeyCode::Char('g').into()]) {
+            if builder.on_key_binding_any(
+                &[KeyCode::Home.into(), KeyCode::Char('g').into()],
+                "Go to top",
+            ) {
                 result.move_to(0);
             }
-            if builder.on_key_press_any(&[KeyCode::End.into(), KeyCode::Char('G').into()]) {
+            if builder.on_key_binding_any(
+                &[KeyCode::End.into(), KeyCode::Char('G').into()],
+                "Go to bottom",
+            ) {
                 let line = self.source.num_lines().saturating_sub(1);
                 result.move_to(line);
             }
-            if builder.on_key_press(KeyCode::Char('n')) {
+            if builder.on_key_binding(KeyCode::Char('n'), "Find next") {
                 if let Some(pattern) = &self.search_pattern {
                     result.search(pattern, true);
                 }
             }
-            if builder.on_key_press(KeyCode::Char('N')) {
+            if builder.on_key_binding(KeyCode::Char('N'), "Find previous") {
                 if let Some(pattern) = &self.search_pattern {
                     result.search(pattern, false);
                 }
@@ -785,6 +853,29 @@ impl<'build, 'result> Pager<'build, 'result> {
 
             builder.grab_focus(state_id);
         }
+        if let Some(pos) = builder.on_mouse_press(minimap_area, MouseButton::Left) {
+            let line = minimap_lines(
+                self.source.num_lines(),
+                minimap_area.height,
+                pos.y - minimap_area.y,
+            )
+            .start;
+            result.move_to(line);
+            builder.grab_focus(state_id);
+        }
+
+        if has_focus && builder.screen_reader() {
+            if result.state.announced != Some(result.select)
+                && result.select < self.source.num_lines()
+            {
+                let line = self.source.get_raw_line(result.select, 0, 4096);
+                let line = line.trim_end();
+                builder.announce(if line.is_empty() { "blank" } else { line });
+                result.state.announced = Some(result.select);
+            }
+        } else {
+            result.state.announced = None;
+        }
 
         // Render widget
         let block = Block::default().style(builder.theme().pane_background);
@@ -826,14 +917,13 @@ impl<'build, 'result> Pager<'build, 'result> {
                 result.scroll.col,
                 area.width as usize,
             );
-            builder.frame().render_widget(
-                line,
-                Rect {
-                    y,
-                    height: 1,
-                    ..area
-                },
-            );
+            let line_area = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+            builder.frame().render_widget(line, line_area);
+            self.add_links(builder, line_no, result.scroll.col, line_area);
         }
 
         let text_width = area.width.saturating_sub(1);
@@ -846,6 +936,12 @@ impl<'build, 'result> Pager<'build, 'result> {
         {
             let y = area.y + (ry as u16);
             assert!(line_no < num_lines);
+            let text_area = Rect {
+                y,
+                height: 1,
+                x: area.x + 1,
+                width: text_width,
+            };
 
             if selection.contains(&line_no) {
                 let block =
@@ -864,8 +960,8 @@ impl<'build, 'result> Pager<'build, 'result> {
                 if range.start == line_no {
                     // Render the folding range marker.
                     let marker = match result.is_collapsed(line_no) {
-                        true => "▶",
-                        false => "▼",
+                        true => builder.theme().symbols.collapsed,
+                        false => builder.theme().symbols.expanded,
                     };
                     let span = Span::from(marker).style(builder.theme().modal_text.normal);
                     builder.frame().render_widget(
@@ -1003,18 +1099,97 @@ impl<'build, 'result> Pager<'build, 'result> {
                     )
                     .style(builder.theme().text.normal);
 
-                builder.frame().render_widget(
-                    line,
-                    Rect {
-                        y,
-                        height: 1,
-                        x: area.x + 1,
-                        width: text_width,
-                    },
-                );
+                builder.frame().render_widget(line, text_area);
             }
+
+            self.add_links(builder, line_no, result.scroll.col, text_area);
+        }
+
+        if minimap_width != 0 {
+            self.render_minimap(builder, &result, minimap_area);
         }
 
         result
     }
+
+    /// Add the hyperlinks of the given line, which is shown in `area` starting
+    /// at column `scroll_col`.
+    fn add_links(&self, builder: &mut Builder, line_no: usize, scroll_col: usize, area: Rect) {
+        if !builder.hyperlinks() {
+            return;
+        }
+        for link in self.source.get_links(line_no) {
+            let begin = link.cols.start.max(scroll_col) - scroll_col;
+            let end = link.cols.end.saturating_sub(scroll_col).min(area.width as usize);
+            if begin < end {
+                let link_area = Rect {
+                    x: area.x + begin as u16,
+                    width: (end - begin) as u16,
+                    ..area
+                };
+                builder.add_hyperlink(link_area, link.url);
+            }
+        }
+    }
+
+    fn render_minimap(&self, builder: &mut Builder, result: &PagerResult, area: Rect) {
+        const BARS: [&str; 8] = ["▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
+
+        let num_lines = self.source.num_lines();
+        let first_shown = result.scroll.line;
+        let last_shown = result
+            .screen_forward(first_shown)
+            .take(area.height as usize)
+            .last()
+            .unwrap_or(first_shown);
+
+        let text = builder.theme().text(builder.theme_context()).clone();
+        let background = builder.theme().modal_background;
+
+        for ry in 0..area.height {
+            let lines = minimap_lines(num_lines, area.height, ry);
+            let (mut added, mut removed, mut unimportant) = (0, 0, 0);
+            for line in lines.clone() {
+                match self.source.minimap_mark(line) {
+                    Some(MinimapMark::Added) => added += 1,
+                    Some(MinimapMark::Removed) => removed += 1,
+                    Some(MinimapMark::Unimportant) => unimportant += 1,
+                    None => {}
+                }
+            }
+
+            let (count, style) = if added + removed != 0 {
+                (added + removed, if added >= removed { text.added } else { text.removed })
+            } else {
+                (unimportant, text.inactive)
+            };
+            let symbol = if count != 0 {
+                BARS[(count * BARS.len()).div_ceil(lines.len()) - 1]
+            } else {
+                " "
+            };
+
+            let on_screen = lines.start <= last_shown && first_shown < lines.end;
+            let base = if on_screen { text.selected } else { background };
+            let span = Span::styled(symbol, base.patch(style));
+            builder.frame().render_widget(
+                span,
+                Rect {
+                    y: area.y + ry,
+                    height: 1,
+                    ..area
+                },
+            );
+        }
+    }
+}
+
+/// Range of document lines that are summarized by the given row of a minimap
+/// with the given height. The range is never empty for rows inside of the
+/// document.
+fn minimap_lines(num_lines: usize, height: u16, row: u16) -> Range<usize> {
+    let height = std::cmp::max(height, 1) as usize;
+    let start = row as usize * num_lines / height;
+    let end = (row as usize + 1) * num_lines / height;
+    std::cmp::min(start, num_lines)..std::cmp::max(start + 1, end).min(num_lines)
 }
@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    event::{KeyCode, MouseButton},
+    state::Builder,
+    theme::Themed,
+};
+
+use ratatui::{prelude::*, text::Span};
+
+/// Add a group of mutually exclusive options in a single line.
+///
+/// `selected` is the index of the selected option. Returns true if the
+/// selection was changed.
+pub fn add_radio_group(
+    builder: &mut Builder,
+    title: &str,
+    options: &[&str],
+    selected: &mut usize,
+) -> bool {
+    let area = builder.take_lines_fixed(1);
+    build_radio_group(builder, area, title, options, selected)
+}
+
+/// Build a radio group in the given area instead of taking a line from the layout.
+pub(crate) fn build_radio_group(
+    builder: &mut Builder,
+    area: Rect,
+    title: &str,
+    options: &[&str],
+    selected: &mut usize,
+) -> bool {
+    let state_id = builder.add_state_id(title);
+    let has_focus = builder.check_focus(state_id);
+    let old_selected = *selected;
+
+    // Each option is rendered as "(*) option" followed by two spaces.
+    let mut x = area.x;
+    let extents: Vec<(u16, u16)> = options
+        .iter()
+        .map(|option| {
+            let width = 4 + option.graphemes(true).count() as u16;
+            let extent = (x, width);
+            x = x.saturating_add(width + 2);
+            extent
+        })
+        .collect();
+
+    for (idx, &(x, width)) in extents.iter().enumerate() {
+        let click_area = Rect { x, width, ..area }.intersection(area);
+        if builder.on_mouse_press(click_area, MouseButton::Left).is_some() {
+            *selected = idx;
+            builder.grab_focus(state_id);
+        }
+    }
+
+    if has_focus && !options.is_empty() {
+        if builder.on_key_binding(KeyCode::Left, "Previous option") {
+            *selected = selected.saturating_sub(1);
+        }
+        if builder.on_key_binding(KeyCode::Right, "Next option") {
+            *selected = std::cmp::min(*selected + 1, options.len() - 1);
+        }
+    }
+
+    for (idx, (option, &(x, width))) in options.iter().zip(&extents).enumerate() {
+        let option_area = Rect { x, width, ..area }.intersection(area);
+        let text = format!(
+            "({state_char}) {option}",
+            state_char = if idx == *selected { '*' } else { ' ' }
+        );
+
+        let mut span = Span::from(text);
+        if has_focus && idx == *selected {
+            span = span.theme_highlight(builder);
+            builder
+                .frame()
+                .set_cursor_position(Position::new(option_area.x + 1, area.y));
+        } else {
+            span = span.theme_text(builder);
+        }
+        builder.frame().render_widget(span, option_area);
+    }
+
+    *selected != old_selected
+}
@@ -0,0 +1,290 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Recording of sessions as asciinema v2 files.
+//!
+//! Every frame is written as an output event with the escape sequences that
+//! redraw the cells that changed since the previous frame, so that recordings
+//! can be played back with `asciinema play` or converted into animations for
+//! documentation.
+//!
+//! Input is written as `"i"` events, which players ignore. Their data is not
+//! the raw input but one of:
+//!
+//! ```text
+//! Ctrl+r                  a key press, in the format of key macros
+//! Paste <text>            pasted text
+//! Mouse Down(Left) 10 4   a mouse event with its column and row
+//! ```
+//!
+//! so that the interaction of a recording can be replayed, e.g. to reproduce a
+//! bug that a user ran into.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    buffer::Buffer,
+    layout::Rect,
+};
+
+use crate::{
+    event::{
+        Event, KeyEvent, KeyEventKind, KeyModifiers, KeySequence, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
+    prelude::*,
+};
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if ch < ' ' || ch == '\x7f' => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Parse a JSON string at the start of `s` and return it with the rest of `s`.
+fn parse_json_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut result = String::new();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((result, &s[idx + 2..])),
+            '\\' => {
+                let unescaped = match chars.next()?.1 {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, ch)| ch).collect();
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        // Surrogate pairs are replaced, we never write them.
+                        char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                    }
+                    ch => ch,
+                };
+                result.push(unescaped);
+            }
+            ch => result.push(ch),
+        }
+    }
+    None
+}
+
+/// Parse an event line of an asciinema v2 file into its type and data.
+fn parse_entry(line: &str) -> Option<(String, String)> {
+    let line = line.trim().strip_prefix('[')?;
+    let (_, rest) = line.split_once(',')?;
+    let (kind, rest) = parse_json_string(rest.trim_start())?;
+    let (data, _) = parse_json_string(rest.trim_start().strip_prefix(',')?.trim_start())?;
+    Some((kind, data))
+}
+
+fn encode_input(event: &Event) -> Option<String> {
+    match event {
+        Event::Key(ev) if ev.kind != KeyEventKind::Release => {
+            Some(KeySequence::new(ev.code, ev.modifiers).to_string())
+        }
+        Event::Paste(text) => Some(format!("Paste {text}")),
+        Event::Mouse(ev) => Some(format!("Mouse {:?} {} {}", ev.kind, ev.column, ev.row)),
+        _ => None,
+    }
+}
+
+fn parse_mouse_kind(s: &str) -> Option<MouseEventKind> {
+    let button = |s: &str| match s {
+        "Left)" => Some(MouseButton::Left),
+        "Right)" => Some(MouseButton::Right),
+        "Middle)" => Some(MouseButton::Middle),
+        _ => None,
+    };
+    Some(match s {
+        "Moved" => MouseEventKind::Moved,
+        "ScrollDown" => MouseEventKind::ScrollDown,
+        "ScrollUp" => MouseEventKind::ScrollUp,
+        "ScrollLeft" => MouseEventKind::ScrollLeft,
+        "ScrollRight" => MouseEventKind::ScrollRight,
+        _ => {
+            if let Some(b) = s.strip_prefix("Down(") {
+                MouseEventKind::Down(button(b)?)
+            } else if let Some(b) = s.strip_prefix("Up(") {
+                MouseEventKind::Up(button(b)?)
+            } else {
+                MouseEventKind::Drag(button(s.strip_prefix("Drag(")?)?)
+            }
+        }
+    })
+}
+
+fn decode_input(data: &str) -> std::result::Result<Event, String> {
+    if let Some(text) = data.strip_prefix("Paste ") {
+        return Ok(Event::Paste(text.to_string()));
+    }
+    if let Some(mouse) = data.strip_prefix("Mouse ") {
+        let parts: Vec<&str> = mouse.split(' ').collect();
+        let event = match parts[..] {
+            [kind, column, row] => {
+                parse_mouse_kind(kind).zip(column.parse().ok()).zip(row.parse().ok())
+            }
+            _ => None,
+        };
+        let Some(((kind, column), row)) = event else {
+            return Err(format!("bad mouse event: {mouse}"));
+        };
+        return Ok(Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }));
+    }
+    let key: KeySequence = data.parse()?;
+    Ok(Event::Key(KeyEvent::new(key.code, key.modifiers)))
+}
+
+/// Read the input events of a recording, for replaying them.
+pub(crate) fn read_input(path: &Path) -> Result<Vec<Event>> {
+    try_forward(
+        || -> Result<Vec<Event>> {
+            let mut events = Vec::new();
+            // The first line is the header.
+            for line in BufReader::new(File::open(path)?).lines().skip(1) {
+                let line = line?;
+                match parse_entry(&line) {
+                    Some((kind, data)) if kind == "i" => events.push(decode_input(&data)?),
+                    Some(_) => {}
+                    None if line.trim().is_empty() => {}
+                    None => Err(format!("bad line: {line}"))?,
+                }
+            }
+            Ok(events)
+        },
+        || format!("Error reading recording {}", path.display()),
+    )
+}
+
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+
+    /// The last frame that was written, which the next frame is diffed
+    /// against.
+    last: Option<Buffer>,
+}
+impl Recorder {
+    /// Create the file and write the header for a terminal of the given size.
+    pub(crate) fn create(path: &Path, size: Rect) -> Result<Self> {
+        try_forward(
+            || -> Result<Self> {
+                let mut out = BufWriter::new(File::create(path)?);
+                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let term = std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".into());
+                writeln!(
+                    out,
+                    "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \
+                     \"env\": {{\"TERM\": {}}}}}",
+                    size.width,
+                    size.height,
+                    timestamp,
+                    json_string(&term),
+                )?;
+                Ok(Self {
+                    out,
+                    start: Instant::now(),
+                    last: None,
+                })
+            },
+            || format!("Error creating recording {}", path.display()),
+        )
+    }
+
+    fn write(&mut self, time: Instant, kind: &str, data: &str) -> Result<()> {
+        let time = time.saturating_duration_since(self.start).as_secs_f64();
+        writeln!(self.out, "[{time:.6}, {}, {}]", json_string(kind), json_string(data))?;
+        Ok(())
+    }
+
+    pub(crate) fn input(&mut self, time: Instant, event: &Event) -> Result<()> {
+        match encode_input(event) {
+            Some(data) => self.write(time, "i", &data),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn frame(&mut self, buffer: &Buffer) -> Result<()> {
+        let mut output = Vec::new();
+        let empty;
+        let last = match &self.last {
+            Some(last) if last.area == buffer.area => last,
+            last => {
+                if last.is_some() {
+                    let size = format!("{}x{}", buffer.area.width, buffer.area.height);
+                    self.write(Instant::now(), "r", &size)?;
+                }
+                // Hide the cursor and start from a clear screen.
+                output.extend_from_slice(b"\x1b[?25l\x1b[H\x1b[2J");
+                empty = Buffer::empty(buffer.area);
+                &empty
+            }
+        };
+
+        let diff = last.diff(buffer);
+        if !diff.is_empty() {
+            CrosstermBackend::new(&mut output).draw(diff.into_iter())?;
+        }
+        if !output.is_empty() {
+            self.write(Instant::now(), "o", &String::from_utf8_lossy(&output))?;
+        }
+        self.out.flush()?;
+        self.last = Some(buffer.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn json_roundtrip() {
+        let s = "a \"b\" \\ \x1b[1m\n";
+        let json = json_string(s);
+        assert_eq!(json, "\"a \\\"b\\\" \\\\ \\u001b[1m\\n\"");
+        assert_eq!(parse_json_string(&json), Some((s.to_string(), "")));
+    }
+
+    #[test]
+    fn input_roundtrip() {
+        let inputs = [
+            "Ctrl+r",
+            "Down",
+            "Space",
+            "Paste two words",
+            "Mouse Down(Left) 3 4",
+            "Mouse ScrollUp 0 0",
+        ];
+        for data in inputs {
+            let event = decode_input(data).unwrap();
+            assert_eq!(encode_input(&event).as_deref(), Some(data));
+        }
+        let line = format!("[0.5, \"i\", {}]", json_string("Paste \"x\""));
+        assert_eq!(parse_entry(&line), Some(("i".into(), "Paste \"x\"".into())));
+    }
+}
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Linear output for screen readers and braille terminals.
+//!
+//! While the screen reader mode is enabled, widgets announce the item or line
+//! that is selected in the focused widget as plain text, and applications can
+//! announce events such as new notifications via `Builder::announce`.
+//!
+//! The most recent announcement is shown in the bottom line of the screen with
+//! the terminal cursor on it, where screen readers pick it up. Announcements
+//! can also be appended to a transcript file, which can be followed in a
+//! separate terminal (e.g. with `tail -f`) to get a linear log.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use log::warn;
+use ratatui::{
+    layout::{Position, Rect},
+    text::Span,
+    widgets::Clear,
+    Frame,
+};
+
+use crate::theme::Theme;
+
+#[derive(Debug, Default)]
+pub(crate) struct ScreenReader {
+    enabled: bool,
+    transcript_path: Option<PathBuf>,
+    transcript: Option<File>,
+    last: Option<String>,
+}
+impl ScreenReader {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        if enabled == self.enabled {
+            return;
+        }
+        if enabled {
+            self.enabled = true;
+            self.announce(["Screen reader mode on".to_string()]);
+        } else {
+            self.announce(["Screen reader mode off".to_string()]);
+            self.enabled = false;
+            self.last = None;
+        }
+    }
+
+    pub(crate) fn set_transcript(&mut self, path: PathBuf) {
+        self.transcript_path = Some(path);
+        self.transcript = None;
+    }
+
+    pub(crate) fn announce(&mut self, announcements: impl IntoIterator<Item = String>) {
+        if !self.enabled {
+            return;
+        }
+
+        for text in announcements {
+            if let Some(path) = &self.transcript_path {
+                if self.transcript.is_none() {
+                    match OpenOptions::new().create(true).append(true).open(path) {
+                        Ok(file) => self.transcript = Some(file),
+                        Err(err) => {
+                            warn!(
+                                "Error opening screen reader transcript {}: {err}",
+                                path.display()
+                            );
+                            self.transcript_path = None;
+                        }
+                    }
+                }
+                if let Some(file) = &mut self.transcript {
+                    if let Err(err) = writeln!(file, "{text}") {
+                        warn!("Error writing screen reader transcript: {err}");
+                    }
+                }
+            }
+            self.last = Some(text);
+        }
+    }
+
+    /// Show the most recent announcement in the bottom line and move the
+    /// cursor there.
+    pub(crate) fn render(&self, frame: &mut Frame, theme: &Theme) {
+        if !self.enabled {
+            return;
+        }
+
+        let area = frame.area();
+        if area.height == 0 {
+            return;
+        }
+        let area = Rect {
+            y: area.y + area.height - 1,
+            height: 1,
+            ..area
+        };
+        frame.render_widget(Clear, area);
+        let text = self.last.as_deref().unwrap_or("");
+        frame.render_widget(Span::styled(text, theme.text.normal), area);
+        frame.set_cursor_position(Position { x: area.x, y: area.y });
+    }
+}
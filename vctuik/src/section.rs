@@ -3,7 +3,7 @@
 use ratatui::{
     crossterm::event::KeyCode,
     layout::{Alignment, Rect},
-    widgets::{block::Title, Block, BorderType, Borders},
+    widgets::{block::Title, Block, Borders},
 };
 
 use crate::{
@@ -54,7 +54,7 @@ impl Section {
 
         let is_first = builder.is_at_top();
 
-        builder.nest().id(state_id).build(|builder| {
+        builder.nest().id(state_id).keymap_context(self.title.clone()).build(|builder| {
             let header_area = builder.take_lines_fixed(1);
             let has_focus = builder.check_group_focus(state_id);
 
@@ -97,8 +97,8 @@ impl Section {
 
             // Draw header
             let title = match (self.collapsible, state.collapsed) {
-                (true, true) => format!("▶ {}", self.title),
-                (true, false) => format!("▼ {}", self.title),
+                (true, true) => format!("{} {}", builder.theme().symbols.collapsed, self.title),
+                (true, false) => format!("{} {}", builder.theme().symbols.expanded, self.title),
                 (false, _) => self.title.clone(),
             };
 
@@ -108,15 +108,16 @@ impl Section {
                 .style(builder.theme().pane_background);
 
             if has_focus {
-                block = block.border_type(BorderType::Thick);
+                block = block.border_set(builder.theme().symbols.border_focus);
                 block = block.border_style(builder.theme().pane_frame_focus);
             } else {
-                block = block.border_type(BorderType::Plain);
+                block = block.border_set(builder.theme().symbols.border);
                 block = block.border_style(builder.theme().pane_frame_normal);
             }
 
             if !is_first {
-                block = block.title(Title::from("↕").alignment(Alignment::Right));
+                let resize = Title::from(builder.theme().symbols.resize);
+                block = block.title(resize.alignment(Alignment::Right));
             }
 
             builder.frame().render_widget(block, header_area);
@@ -126,11 +127,13 @@ impl Section {
             }
 
             if self.collapsible && has_focus {
-                if builder.on_key_press(KeyCode::Left) && !state.collapsed {
+                if builder.on_key_binding(KeyCode::Left, "Collapse / expand section")
+                    && !state.collapsed {
                     state.collapsed = true;
                     builder.need_refresh();
                 }
-                if builder.on_key_press(KeyCode::Right) && state.collapsed {
+                if builder.on_key_binding(KeyCode::Right, "Collapse / expand section")
+                    && state.collapsed {
                     state.collapsed = false;
                     builder.need_refresh();
                 }
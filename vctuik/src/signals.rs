@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::sync::{Arc, Condvar, Mutex};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Instant,
+};
 
 /// A `Dispatchable` is an object that handles an asynchronous trigger.
 ///
@@ -235,7 +238,13 @@ impl<'slf> Dispatch<'slf> {
     /// Polls every dispatchable at least once.
     ///
     /// If `wait` is true, we block once until at least one dispatchable was dispatched.
-    pub fn poll(mut self, wait: bool) {
+    pub fn poll(self, wait: bool) {
+        self.poll_deadline(wait, None)
+    }
+
+    /// Like `poll`, but stop waiting at the given deadline even if no dispatchable was
+    /// dispatched.
+    pub fn poll_deadline(mut self, wait: bool, deadline: Option<Instant>) {
         let wakeups = wait.then(|| {
             Arc::new(Wakeups {
                 condvar: Condvar::new(),
@@ -253,7 +262,13 @@ impl<'slf> Dispatch<'slf> {
                     let mut woken = wakeups.woken.lock().unwrap();
 
                     if woken.is_empty() {
-                        woken = wakeups.condvar.wait(woken).unwrap();
+                        woken = match deadline {
+                            Some(deadline) => {
+                                let timeout = deadline.saturating_duration_since(Instant::now());
+                                wakeups.condvar.wait_timeout(woken, timeout).unwrap().0
+                            }
+                            None => wakeups.condvar.wait(woken).unwrap(),
+                        };
                     }
 
                     std::mem::swap(&mut poll, &mut woken);
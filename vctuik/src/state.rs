@@ -1,6 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{any::Any, borrow::Cow, collections::HashMap, ops::Range, time::Instant};
+//! The builder through which the UI is built in every frame.
+//!
+//! Widgets take their area from the `Builder`, handle the event of the frame
+//! with methods such as `on_key_binding`, and keep state across frames under
+//! a `StateId`. IDs are derived from names that are unique within the
+//! enclosing nest, so that the state of a widget is found again in the next
+//! frame even if the UI around it changed.
+
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::HashMap,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
 use ratatui::{
     layout::{Position, Rect},
@@ -13,7 +27,9 @@ use vctuik_unsafe_internals::state;
 
 use crate::{
     event::{Event, EventExt, KeyCode, KeyEventKind, KeySequence, MouseButton, MouseEventKind},
+    keymap::Keymap,
     layout::{Constraint1D, LayoutCache, LayoutEngine, LayoutItem1D},
+    pager::PersistentCursor,
     theme::{Context, Theme},
 };
 
@@ -172,9 +188,25 @@ pub(crate) struct BuildStore<'store, 'frame> {
     event_handled: bool,
     pub(crate) injected: Vec<Box<dyn Any + Send + Sync>>,
     pub(crate) need_refresh: bool,
+
+    /// Earliest point in time at which the UI should be rebuilt even without events.
+    pub(crate) refresh_at: Option<Instant>,
     focus_action: FocusAction,
     start_frame: Instant,
     pub(crate) trace_frame: bool,
+    pub(crate) keymap: Keymap,
+    pub(crate) screen_reader: bool,
+    pub(crate) announcements: Vec<String>,
+    pub(crate) hyperlinks: bool,
+    pub(crate) links: Vec<(Rect, String)>,
+
+    /// Position of the mouse pointer, if known.
+    pub(crate) pointer: Option<Position>,
+    pub(crate) tooltip: Option<String>,
+
+    /// How often the key of the event was pressed in a row.
+    pub(crate) repeat: usize,
+    repeat_consumed: bool,
 }
 impl<'store, 'frame> BuildStore<'store, 'frame> {
     pub(crate) fn new(
@@ -198,9 +230,49 @@ impl<'store, 'frame> BuildStore<'store, 'frame> {
             event_handled: false,
             injected: Vec::new(),
             need_refresh: false,
+            refresh_at: None,
             focus_action: FocusAction::None,
             start_frame,
             trace_frame: false,
+            keymap: Keymap::default(),
+            screen_reader: false,
+            announcements: Vec::new(),
+            hyperlinks: false,
+            links: Vec::new(),
+            pointer: None,
+            tooltip: None,
+            repeat: 1,
+            repeat_consumed: false,
+        }
+    }
+
+    /// Whether the event is a key press matching the sequence that hasn't
+    /// been handled by any widget.
+    pub(crate) fn is_unhandled_key_press(&self, key_seq: KeySequence) -> bool {
+        !self.event_handled
+            && matches!(
+                self.event,
+                Some(EventExt::Event(Event::Key(ev)))
+                    if ev.kind == KeyEventKind::Press && key_seq.matches(&ev))
+    }
+
+    /// Return the key presses of a coalesced event that have not been handled,
+    /// as the event and the number of presses.
+    pub(crate) fn unhandled_repeats(&self) -> Option<(Event, usize)> {
+        match &self.event {
+            Some(EventExt::Event(event)) if self.repeat > 1 && !self.repeat_consumed => {
+                Some((event.clone(), self.repeat - 1))
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of key presses of the event that were handled.
+    pub(crate) fn handled_repeats(&self) -> usize {
+        if self.repeat_consumed {
+            self.repeat
+        } else {
+            1
         }
     }
 
@@ -493,6 +565,7 @@ pub struct Builder<'builder, 'store, 'frame> {
     store: &'builder mut BuildStore<'store, 'frame>,
     name_prefix: String,
     theme_context: Context,
+    keymap_context: String,
     viewport: Rect,
     layout: &'builder mut LayoutEngine<StateId>,
 }
@@ -506,6 +579,7 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
             store,
             name_prefix: String::new(),
             theme_context: Context::None,
+            keymap_context: "General".into(),
             viewport,
             layout,
         }
@@ -519,6 +593,16 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
         self.store.start_frame
     }
 
+    /// Sizes of what is kept across frames, for detecting leaks over long
+    /// sessions. At the end of the frame, this is what the next frame gets.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            ids: self.store.ids.current.ids.len(),
+            states: self.store.state_builder.len(),
+            persistent_cursors: PersistentCursor::live_count(),
+        }
+    }
+
     pub fn theme_context(&self) -> Context {
         self.theme_context
     }
@@ -643,6 +727,15 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
         self.store.need_refresh = true;
     }
 
+    /// Request that the UI is rebuilt after the given delay even if there are
+    /// no events, e.g. because it shows relative times that go stale.
+    pub fn refresh_after(&mut self, delay: Duration) {
+        let Some(at) = Instant::now().checked_add(delay) else {
+            return;
+        };
+        self.store.refresh_at = Some(self.store.refresh_at.map_or(at, |prev| prev.min(at)));
+    }
+
     pub fn inject_custom<T: Sync + Send + 'static>(&mut self, event: T) {
         self.store.injected.push(Box::new(event));
     }
@@ -689,6 +782,64 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
             && self.store.event_handled()
     }
 
+    /// Like `on_key_press`, but also record the binding with a description
+    /// for the key binding overview.
+    pub fn on_key_binding(
+        &mut self,
+        key_seq: impl Into<KeySequence>,
+        description: impl Into<Cow<'static, str>>,
+    ) -> bool {
+        let key_seq = key_seq.into();
+        self.describe_key(key_seq, description);
+        self.on_key_press(key_seq)
+    }
+
+    pub fn on_key_binding_any(
+        &mut self,
+        key_seqs: &[KeySequence],
+        description: impl Into<Cow<'static, str>>,
+    ) -> bool {
+        let description = description.into();
+        for key_seq in key_seqs {
+            self.describe_key(*key_seq, description.clone());
+        }
+        self.on_key_press_any(key_seqs)
+    }
+
+    /// Record a key binding for the overview without handling it, for keys
+    /// that are handled elsewhere.
+    pub fn describe_key(
+        &mut self,
+        key_seq: impl Into<KeySequence>,
+        description: impl Into<Cow<'static, str>>,
+    ) {
+        self.store.keymap.add(&self.keymap_context, key_seq.into(), description.into());
+    }
+
+    /// Like `on_key_press`, but handle all presses of a key that is repeated,
+    /// e.g. because it is held down, and return their number. Returns 0 if the
+    /// key wasn't pressed.
+    pub fn on_key_press_repeat(&mut self, key_seq: impl Into<KeySequence>) -> usize {
+        if self.on_key_press(key_seq) {
+            self.store.repeat_consumed = true;
+            self.store.repeat
+        } else {
+            0
+        }
+    }
+
+    /// Like `on_key_press_repeat`, but also record the binding with a
+    /// description for the key binding overview.
+    pub fn on_key_binding_repeat(
+        &mut self,
+        key_seq: impl Into<KeySequence>,
+        description: impl Into<Cow<'static, str>>,
+    ) -> usize {
+        let key_seq = key_seq.into();
+        self.describe_key(key_seq, description);
+        self.on_key_press_repeat(key_seq)
+    }
+
     pub fn on_key_press_any(&mut self, key_seqs: &[KeySequence]) -> bool {
         match self.store.event {
             Some(EventExt::Event(Event::Key(ev))) if ev.kind == KeyEventKind::Press => {
@@ -758,7 +909,7 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
         }
     }
 
-    pub fn add_state_id_impl(&mut self, mut name: Cow<'_, str>) -> StateId {
+    fn add_state_id_impl(&mut self, mut name: Cow<'_, str>) -> StateId {
         assert!(!name.is_empty());
         assert!(name.find("##").is_none(), "id cannot contain '##'");
 
@@ -789,6 +940,7 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
                 store: self.store,
                 name_prefix: self.name_prefix.clone(),
                 theme_context: self.theme_context,
+                keymap_context: self.keymap_context.clone(),
                 viewport: self.viewport,
                 layout: self.layout,
             },
@@ -804,6 +956,51 @@ impl<'builder, 'store, 'frame> Builder<'builder, 'store, 'frame> {
     pub fn trace_frame(&self) -> bool {
         self.store.trace_frame
     }
+
+    /// Whether the screen reader mode is enabled.
+    pub fn screen_reader(&self) -> bool {
+        self.store.screen_reader
+    }
+
+    /// Announce the text in the screen reader mode.
+    ///
+    /// Does nothing when the screen reader mode is disabled.
+    pub fn announce(&mut self, text: impl Into<String>) {
+        if self.store.screen_reader {
+            self.store.announcements.push(text.into());
+        }
+    }
+
+    /// Whether the mouse pointer is in the area.
+    pub fn is_hovered(&self, area: Rect) -> bool {
+        self.store.pointer.is_some_and(|pointer| area.contains(pointer))
+    }
+
+    /// Show a tooltip with the text next to the mouse pointer while it is in
+    /// the area.
+    ///
+    /// If areas overlap, the tooltip that is added last is shown.
+    pub fn tooltip(&mut self, area: Rect, text: impl Into<String>) {
+        if self.is_hovered(area) {
+            self.store.tooltip = Some(text.into());
+        }
+    }
+
+    /// Whether links are shown as hyperlinks, see
+    /// [`crate::terminal::Terminal::set_hyperlinks`].
+    pub fn hyperlinks(&self) -> bool {
+        self.store.hyperlinks
+    }
+
+    /// Make the text in the given area a hyperlink to the URL.
+    ///
+    /// Does nothing when hyperlinks are disabled.
+    pub fn add_hyperlink(&mut self, area: Rect, url: impl Into<String>) {
+        let area = area.intersection(self.store.frame.area());
+        if self.store.hyperlinks && !area.is_empty() {
+            self.store.links.push((area, url.into()));
+        }
+    }
 }
 
 pub struct Nest<'nest, 'store, 'frame> {
@@ -908,9 +1105,35 @@ impl<'nest, 'store, 'frame> Nest<'nest, 'store, 'frame> {
             ..self
         }
     }
+
+    /// Group the key bindings of nested widgets under the given name in the
+    /// key binding overview.
+    pub fn keymap_context(self, keymap_context: impl Into<String>) -> Self {
+        Nest {
+            builder: Builder {
+                keymap_context: keymap_context.into(),
+                ..self.builder
+            },
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct NestResult {
     pub has_focus: bool,
 }
+
+/// See `Builder::stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// IDs that were added in this frame so far.
+    pub ids: usize,
+
+    /// States of widgets that were kept or created in this frame so far.
+    pub states: usize,
+
+    /// Persistent cursors in the whole process, e.g. scroll positions of
+    /// pagers.
+    pub persistent_cursors: usize,
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A single-line bar showing contextual information.
+//!
+//! Applications describe the status line as a list of segments each frame:
+//!
+//! ```ignore
+//! StatusLine::new()
+//!     .segment(Segment::new("github.com").label("host"))
+//!     .segment(Segment::new(format!("#{pr}")).style(TextStyle::Highlight))
+//!     .segment(Segment::new("3 pending").right().priority(1))
+//!     .build(builder);
+//! ```
+//!
+//! If the line is too narrow, segments with the lowest priority are dropped
+//! first.
+
+use std::borrow::Cow;
+
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Clear},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{state::Builder, theme::TextStyle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+pub struct Segment<'a> {
+    text: Cow<'a, str>,
+    label: Option<Cow<'a, str>>,
+    style: TextStyle,
+    align: Align,
+    priority: u8,
+}
+impl<'a> Segment<'a> {
+    pub fn new(text: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            text: text.into(),
+            label: None,
+            style: TextStyle::Normal,
+            align: Align::Left,
+            priority: 0,
+        }
+    }
+
+    /// Short label shown before the text in a less prominent style.
+    pub fn label(self, label: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    pub fn style(self, style: TextStyle) -> Self {
+        Self { style, ..self }
+    }
+
+    /// Place the segment on the right-hand side of the line.
+    pub fn right(self) -> Self {
+        Self {
+            align: Align::Right,
+            ..self
+        }
+    }
+
+    /// Segments with a higher priority are kept when space runs out.
+    pub fn priority(self, priority: u8) -> Self {
+        Self { priority, ..self }
+    }
+
+    fn width(&self) -> usize {
+        let label = self.label.as_ref().map_or(0, |label| label.graphemes(true).count() + 1);
+        label + self.text.graphemes(true).count()
+    }
+
+    fn spans(&self, builder: &Builder) -> Vec<Span<'a>> {
+        let text = builder.theme().text(builder.theme_context());
+        let mut spans = Vec::new();
+        if let Some(label) = &self.label {
+            spans.push(Span::styled(format!("{label} "), text.inactive));
+        }
+        spans.push(Span::styled(self.text.clone(), text[self.style]));
+        spans
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StatusLine<'a> {
+    segments: Vec<Segment<'a>>,
+}
+impl<'a> StatusLine<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn segment(mut self, segment: Segment<'a>) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Add a segment only if it is `Some`, which is convenient for
+    /// information that may not be available (yet).
+    pub fn maybe_segment(self, segment: Option<Segment<'a>>) -> Self {
+        match segment {
+            Some(segment) => self.segment(segment),
+            None => self,
+        }
+    }
+
+    pub fn build(self, builder: &mut Builder) {
+        let area = builder.take_lines_fixed(1);
+        let width = area.width as usize;
+
+        // Drop the lowest-priority segments (the later ones first) until
+        // everything fits.
+        let mut segments = self.segments;
+        let separator = format!(" {} ", builder.theme().symbols.vertical);
+        let total_width = |segments: &[Segment]| -> usize {
+            let content: usize = segments.iter().map(Segment::width).sum();
+            content + separator.chars().count() * segments.len().saturating_sub(1)
+        };
+        while total_width(&segments) > width && !segments.is_empty() {
+            let (idx, _) = segments
+                .iter()
+                .enumerate()
+                .rev()
+                .min_by_key(|(_, segment)| segment.priority)
+                .unwrap();
+            segments.remove(idx);
+        }
+
+        let separator_style = builder.theme().text(builder.theme_context()).inactive;
+        let line = |align: Align| -> Line<'a> {
+            let mut spans = Vec::new();
+            for segment in segments.iter().filter(|segment| segment.align == align) {
+                if !spans.is_empty() {
+                    spans.push(Span::styled(separator.clone(), separator_style));
+                }
+                spans.extend(segment.spans(builder));
+            }
+            Line::from(spans)
+        };
+        let left = line(Align::Left);
+        let right = line(Align::Right).right_aligned();
+
+        builder.frame().render_widget(Clear, area);
+        let block = Block::new().style(builder.theme().pane_background);
+        builder.frame().render_widget(block, area);
+        builder.frame().render_widget(left, area);
+        builder.frame().render_widget(right, area);
+    }
+}
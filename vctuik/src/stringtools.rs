@@ -136,6 +136,25 @@ impl StrScan for str {
     }
 }
 
+/// Match `pattern` against `text` as a case-insensitive subsequence.
+///
+/// Returns the indices of the matched characters of `text` (in chars, not
+/// bytes), or None if `text` doesn't contain all characters of the pattern in
+/// order. Matches are greedy, i.e. each pattern character matches its
+/// earliest possible occurrence.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    let mut pattern = pattern.chars().flat_map(char::to_lowercase).peekable();
+    let mut indices = Vec::new();
+    for (idx, ch) in text.chars().enumerate() {
+        let Some(&next) = pattern.peek() else { break };
+        if ch.to_lowercase().eq(std::iter::once(next)) {
+            indices.push(idx);
+            pattern.next();
+        }
+    }
+    pattern.peek().is_none().then_some(indices)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -148,4 +167,14 @@ mod test {
         assert_eq!("", "".get_first_line(100));
         Ok(())
     }
+
+    #[test]
+    fn fuzzy_match() {
+        assert_eq!(Some(vec![]), super::fuzzy_match("", "anything"));
+        assert_eq!(Some(vec![0, 3, 5]), super::fuzzy_match("fbr", "foobar"));
+        assert_eq!(Some(vec![3, 4]), super::fuzzy_match("BA", "foobar"));
+        assert_eq!(Some(vec![0]), super::fuzzy_match("ä", "Ää"));
+        assert_eq!(None, super::fuzzy_match("rab", "foobar"));
+        assert_eq!(None, super::fuzzy_match("x", ""));
+    }
 }
@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Table source adapter for items whose children are loaded asynchronously.
+//!
+//! Wrap the table source of a frame in an `AsyncTableSource` and mark the
+//! items whose children haven't arrived yet as loading (or as failed). Those
+//! items get a placeholder child, so they can be expanded like any other
+//! item. When a loading item is expanded on screen, the callback registered
+//! with `on_request` is called with its ID, which is where the application
+//! starts loading the children.
+//!
+//! Once the data arrives and the application rebuilds the table source, the
+//! placeholder is replaced by the real children while the item stays
+//! expanded.
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+
+use super::TableSource;
+
+/// Bit that distinguishes the IDs of placeholder children from the IDs of the
+/// wrapped source, which must not use it.
+const PLACEHOLDER_BIT: u64 = 1 << 63;
+
+#[derive(Debug)]
+enum Pending<'source> {
+    Loading,
+    Failed(Cow<'source, str>),
+}
+
+type OnRequest<'source> = RefCell<Box<dyn FnMut(u64) + 'source>>;
+
+pub struct AsyncTableSource<'source, S> {
+    inner: S,
+    pending: HashMap<u64, Pending<'source>>,
+    on_request: Option<OnRequest<'source>>,
+}
+impl<S> std::fmt::Debug for AsyncTableSource<'_, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AsyncTableSource")
+            .field("pending", &self.pending)
+            .finish_non_exhaustive()
+    }
+}
+impl<'source, S: TableSource> AsyncTableSource<'source, S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            pending: HashMap::new(),
+            on_request: None,
+        }
+    }
+
+    /// Set the callback that is called with the ID of loading items when they
+    /// are expanded on screen.
+    ///
+    /// The callback may be called again in later frames for as long as the
+    /// item is marked as loading, so it should not start duplicate requests.
+    pub fn on_request(self, f: impl FnMut(u64) + 'source) -> Self {
+        Self {
+            on_request: Some(RefCell::new(Box::new(f))),
+            ..self
+        }
+    }
+
+    /// Mark the children of the given item as not loaded yet.
+    pub fn loading(&mut self, item_id: u64) {
+        debug_assert!(item_id & PLACEHOLDER_BIT == 0);
+        self.pending.insert(item_id, Pending::Loading);
+    }
+
+    /// Mark the children of the given item as failed to load, and show the
+    /// given message in their place.
+    pub fn failed(&mut self, item_id: u64, message: impl Into<Cow<'source, str>>) {
+        debug_assert!(item_id & PLACEHOLDER_BIT == 0);
+        self.pending.insert(item_id, Pending::Failed(message.into()));
+    }
+
+    /// If the given ID is a placeholder, return the ID of its parent.
+    fn placeholder_parent(&self, item_id: u64) -> Option<u64> {
+        (item_id & PLACEHOLDER_BIT != 0).then_some(item_id & !PLACEHOLDER_BIT)
+    }
+}
+impl<S: TableSource> TableSource for AsyncTableSource<'_, S> {
+    fn exists(&self, item_id: u64) -> bool {
+        match self.placeholder_parent(item_id) {
+            Some(parent_id) => self.pending.contains_key(&parent_id),
+            None => self.inner.exists(item_id),
+        }
+    }
+
+    fn num_children(&self, item_id: u64) -> usize {
+        if self.placeholder_parent(item_id).is_some() {
+            0
+        } else if self.pending.contains_key(&item_id) {
+            1
+        } else {
+            self.inner.num_children(item_id)
+        }
+    }
+
+    fn child_id(&self, item_id: u64, child_idx: usize) -> u64 {
+        if self.pending.contains_key(&item_id) {
+            assert!(child_idx == 0);
+            item_id | PLACEHOLDER_BIT
+        } else {
+            self.inner.child_id(item_id, child_idx)
+        }
+    }
+
+    fn parent_id(&self, item_id: u64) -> u64 {
+        self.placeholder_parent(item_id)
+            .unwrap_or_else(|| self.inner.parent_id(item_id))
+    }
+
+    fn child_idx(&self, item_id: u64) -> usize {
+        match self.placeholder_parent(item_id) {
+            Some(_) => 0,
+            None => self.inner.child_idx(item_id),
+        }
+    }
+
+    fn get_data(&self, item_id: u64, column_idx: usize) -> Vec<Span<'_>> {
+        let Some(parent_id) = self.placeholder_parent(item_id) else {
+            return self.inner.get_data(item_id, column_idx);
+        };
+        if column_idx != 0 {
+            return Vec::new();
+        }
+        let style = Style::default().add_modifier(Modifier::ITALIC);
+        match self.pending.get(&parent_id) {
+            Some(Pending::Loading) => vec![Span::styled("Loading...", style)],
+            Some(Pending::Failed(message)) => vec![Span::styled(message.as_ref(), style)],
+            None => Vec::new(),
+        }
+    }
+
+    fn get_link(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        match self.placeholder_parent(item_id) {
+            Some(_) => None,
+            None => self.inner.get_link(item_id, column_idx),
+        }
+    }
+
+    fn get_tooltip(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        match self.placeholder_parent(item_id) {
+            Some(_) => None,
+            None => self.inner.get_tooltip(item_id, column_idx),
+        }
+    }
+
+    fn is_group(&self, item_id: u64) -> bool {
+        self.placeholder_parent(item_id).is_none() && self.inner.is_group(item_id)
+    }
+
+    fn children_pending(&self, item_id: u64) -> bool {
+        matches!(self.pending.get(&item_id), Some(Pending::Loading))
+    }
+
+    fn request_children(&self, item_id: u64) {
+        if let Some(on_request) = &self.on_request {
+            (on_request.borrow_mut())(item_id);
+        }
+    }
+}
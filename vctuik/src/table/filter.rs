@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Filtered view of a table source for the quick filter.
+
+use std::collections::HashMap;
+
+use ratatui::{style::Style, text::Span};
+
+use crate::{stringtools::fuzzy_match, table::TableSource};
+
+/// Text of an item in a column, as it is matched against the filter.
+fn item_text(source: &dyn TableSource, item_id: u64, column: usize) -> String {
+    source
+        .get_data(item_id, column)
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect()
+}
+
+/// View of a table source that only contains the items whose text in a
+/// column fuzzily matches a filter, and their ancestors.
+pub(super) struct FilteredSource<'a> {
+    source: &'a dyn TableSource,
+
+    /// Visible children of every item that has any.
+    children: HashMap<u64, Vec<u64>>,
+
+    /// Index of every visible item among its parent's visible children.
+    child_idx: HashMap<u64, usize>,
+}
+impl<'a> FilteredSource<'a> {
+    pub(super) fn new(source: &'a dyn TableSource, column: usize, filter: &str) -> Self {
+        let mut filtered = Self {
+            source,
+            children: HashMap::new(),
+            child_idx: HashMap::new(),
+        };
+        filtered.add_children(0, column, filter);
+        filtered
+    }
+
+    /// Add the visible children of the given item. Returns true if any of its
+    /// descendants is visible.
+    fn add_children(&mut self, item_id: u64, column: usize, filter: &str) -> bool {
+        let mut children = Vec::new();
+        for child_idx in 0..self.source.num_children(item_id) {
+            let child_id = self.source.child_id(item_id, child_idx);
+            let matches = fuzzy_match(filter, &item_text(self.source, child_id, column)).is_some();
+            if self.add_children(child_id, column, filter) || matches {
+                self.child_idx.insert(child_id, children.len());
+                children.push(child_id);
+            }
+        }
+        if children.is_empty() {
+            return false;
+        }
+        self.children.insert(item_id, children);
+        true
+    }
+}
+impl TableSource for FilteredSource<'_> {
+    fn exists(&self, item_id: u64) -> bool {
+        self.child_idx.contains_key(&item_id)
+    }
+
+    fn num_children(&self, item_id: u64) -> usize {
+        self.children.get(&item_id).map_or(0, Vec::len)
+    }
+
+    fn child_id(&self, item_id: u64, child_idx: usize) -> u64 {
+        self.children[&item_id][child_idx]
+    }
+
+    fn parent_id(&self, item_id: u64) -> u64 {
+        self.source.parent_id(item_id)
+    }
+
+    fn child_idx(&self, item_id: u64) -> usize {
+        self.child_idx[&item_id]
+    }
+
+    fn get_data(&self, item_id: u64, column_idx: usize) -> Vec<Span<'_>> {
+        self.source.get_data(item_id, column_idx)
+    }
+
+    fn get_link(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        self.source.get_link(item_id, column_idx)
+    }
+
+    fn get_tooltip(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        self.source.get_tooltip(item_id, column_idx)
+    }
+
+    fn is_group(&self, item_id: u64) -> bool {
+        self.source.is_group(item_id)
+    }
+
+    fn children_pending(&self, item_id: u64) -> bool {
+        self.source.children_pending(item_id)
+    }
+
+    fn request_children(&self, item_id: u64) {
+        self.source.request_children(item_id)
+    }
+}
+
+/// Patch the characters of `spans` that match the filter with `style`.
+pub(super) fn highlight<'a>(spans: Vec<Span<'a>>, filter: &str, style: Style) -> Vec<Span<'a>> {
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    let Some(indices) = fuzzy_match(filter, &text) else {
+        return spans;
+    };
+    if indices.is_empty() {
+        return spans;
+    }
+
+    let mut result = Vec::new();
+    let mut indices = indices.into_iter().peekable();
+    let mut pos = 0;
+    for span in spans {
+        // Split the span into runs of matched and unmatched characters.
+        let mut run = String::new();
+        let mut run_matched = false;
+        for ch in span.content.chars() {
+            let matched = indices.next_if_eq(&pos).is_some();
+            pos += 1;
+            if matched != run_matched && !run.is_empty() {
+                let style = if run_matched { span.style.patch(style) } else { span.style };
+                result.push(Span::styled(std::mem::take(&mut run), style));
+            }
+            run_matched = matched;
+            run.push(ch);
+        }
+        if !run.is_empty() {
+            let style = if run_matched { span.style.patch(style) } else { span.style };
+            result.push(Span::styled(run, style));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::style::Stylize;
+
+    use super::*;
+    use crate::table::simple_table::SourceState;
+
+    #[test]
+    fn filtered_source() {
+        let mut state = SourceState::<&str>::default();
+        let mut builder = state.build();
+        let fruit = builder.add(0, "fruit").raw(0, "Fruit").id();
+        let apple = builder.add(fruit, "apple").raw(0, "Apple").id();
+        builder.add(fruit, "cherry").raw(0, "Cherry");
+        let banana = builder.add(fruit, "banana").raw(0, "Banana").id();
+        builder.add(0, "vegetables").raw(0, "Vegetables");
+        let source = builder.finish();
+
+        let filtered = FilteredSource::new(&source, 0, "an");
+        assert_eq!(1, filtered.num_children(0));
+        assert_eq!(fruit, filtered.child_id(0, 0));
+        assert_eq!(1, filtered.num_children(fruit));
+        assert_eq!(banana, filtered.child_id(fruit, 0));
+        assert!(!filtered.exists(apple));
+
+        // Ancestors stay visible even if they don't match themselves.
+        let filtered = FilteredSource::new(&source, 0, "ple");
+        assert_eq!(1, filtered.num_children(0));
+        assert_eq!(1, filtered.num_children(fruit));
+        assert_eq!(0, filtered.child_idx(apple));
+    }
+
+    #[test]
+    fn highlight_spans() {
+        let style = Style::new().bold();
+        let spans = vec![Span::raw("foo"), Span::raw("bar")];
+        let result = highlight(spans, "ob", style);
+        let expected = vec![
+            Span::raw("f"),
+            Span::styled("o", style),
+            Span::raw("o"),
+            Span::styled("b", style),
+            Span::raw("ar"),
+        ];
+        assert_eq!(expected, result);
+    }
+}
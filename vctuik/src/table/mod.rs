@@ -2,6 +2,8 @@
 
 //! Tree/table widget and associated infrastructure.
 
+pub mod async_source;
+mod filter;
 pub mod simple_table;
 mod widget;
 
@@ -14,6 +16,8 @@ use ratatui::text::Span;
 ///  - items with globally unique IDs
 ///   - ID 0 is reserved for the virtual root -- top-level items have parent 0
 ///  - each item's data is produced as ratatui `Span`s for each column
+///  - items can be marked as group rows, which stick to the top of the table
+///    while their descendants are scrolled
 pub trait TableSource {
     /// Whether an item with the given ID exists.
     fn exists(&self, item_id: u64) -> bool;
@@ -32,6 +36,35 @@ pub trait TableSource {
 
     /// Return the data for the given item in the column with the given index.
     fn get_data(&self, item_id: u64, column_idx: usize) -> Vec<Span<'_>>;
+
+    /// Return the URL that the data in the column with the given index links
+    /// to, if any.
+    fn get_link(&self, _item_id: u64, _column_idx: usize) -> Option<&str> {
+        None
+    }
+
+    /// Return the tooltip for the data in the column with the given index, if
+    /// any. Data that doesn't fit its column is shown in full as its tooltip
+    /// by default.
+    fn get_tooltip(&self, _item_id: u64, _column_idx: usize) -> Option<&str> {
+        None
+    }
+
+    /// Whether the item with the given ID is a group row (e.g., a repository
+    /// header) that stays visible at the top while its children scroll.
+    fn is_group(&self, _item_id: u64) -> bool {
+        false
+    }
+
+    /// Whether the children of the item with the given ID are loaded
+    /// asynchronously and haven't arrived yet.
+    fn children_pending(&self, _item_id: u64) -> bool {
+        false
+    }
+
+    /// Called in every frame in which an item with pending children is
+    /// expanded on screen.
+    fn request_children(&self, _item_id: u64) {}
 }
 
-pub use widget::{Column, Table};
+pub use widget::{Column, Table, TableState};
@@ -49,6 +49,9 @@ impl<K> SourceState<K> {
                 child_idx: 0,
                 children: 0..0,
                 data: vec![],
+                links: vec![],
+                tooltips: vec![],
+                group: false,
             },
         );
         let builder = GenericSourceBuilder {
@@ -127,6 +130,9 @@ impl<'source> GenericSourceBuilder<'source> {
             child_idx: 0,
             children: 0..0,
             data: vec![],
+            links: vec![],
+            tooltips: vec![],
+            group: false,
         });
 
         self.child_links.push((parent, id));
@@ -228,6 +234,13 @@ struct Item<'widget> {
     child_idx: usize,
     children: Range<usize>,
     data: Vec<(StyleId, Cow<'widget, str>)>,
+
+    /// (column index, URL) of hyperlinks
+    links: Vec<(usize, Cow<'widget, str>)>,
+
+    /// (column index, text) of tooltips
+    tooltips: Vec<(usize, Cow<'widget, str>)>,
+    group: bool,
 }
 
 #[derive(Debug)]
@@ -269,6 +282,28 @@ impl<'builder, 'source> ItemBuilder<'builder, 'source> {
         self.styled_impl(column_idx, text.into(), style)
     }
 
+    /// Make the data in the given column a hyperlink to the URL.
+    pub fn link(self, column_idx: usize, url: impl Into<Cow<'source, str>>) -> Self {
+        self.item.links.retain(|(idx, _)| *idx != column_idx);
+        self.item.links.push((column_idx, url.into()));
+        self
+    }
+
+    /// Show a tooltip when the mouse pointer is on the data in the given
+    /// column.
+    pub fn tooltip(self, column_idx: usize, text: impl Into<Cow<'source, str>>) -> Self {
+        self.item.tooltips.retain(|(idx, _)| *idx != column_idx);
+        self.item.tooltips.push((column_idx, text.into()));
+        self
+    }
+
+    /// Make the item a group row that sticks to the top of the table while
+    /// its children are scrolled.
+    pub fn group(self) -> Self {
+        self.item.group = true;
+        self
+    }
+
     pub fn id(self) -> u64 {
         self.id
     }
@@ -312,4 +347,18 @@ impl TableSource for Source<'_> {
             .unwrap_or((0, ""));
         vec![Span::styled(text, self.styles[style_id as usize])]
     }
+
+    fn get_link(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        let item = self.items.get(&item_id).unwrap();
+        item.links.iter().find(|(idx, _)| *idx == column_idx).map(|(_, url)| url.as_ref())
+    }
+
+    fn get_tooltip(&self, item_id: u64, column_idx: usize) -> Option<&str> {
+        let item = self.items.get(&item_id).unwrap();
+        item.tooltips.iter().find(|(idx, _)| *idx == column_idx).map(|(_, text)| text.as_ref())
+    }
+
+    fn is_group(&self, item_id: u64) -> bool {
+        self.items.get(&item_id).unwrap().group
+    }
 }
@@ -5,7 +5,7 @@ use std::{borrow::Cow, cmp::Ordering, collections::HashMap};
 use itertools::{FoldWhile, Itertools};
 
 use ratatui::{
-    layout::Rect,
+    layout::{Position, Rect},
     text::{Line, Span},
     widgets::Block,
 };
@@ -14,7 +14,7 @@ use crate::{
     event::*,
     layout::{Constraint1D, LayoutCache, LayoutEngine, LayoutItem1D},
     state::Builder,
-    table::TableSource,
+    table::{filter, TableSource},
 };
 
 trait SourceExtras {
@@ -146,6 +146,25 @@ pub struct TableState {
     default_collapsed: bool,
 
     column_cache: LayoutCache<usize>,
+
+    /// Selected item and its collapsed state, as last announced in the
+    /// screen reader mode.
+    announced: Option<(u64, bool)>,
+
+    /// Quick filter on the designated filter column of the table.
+    filter: String,
+
+    /// Whether typed characters currently go to the quick filter.
+    filtering: bool,
+}
+impl TableState {
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: impl Into<String>) {
+        self.filter = filter.into();
+    }
 }
 
 struct LiveState<'a> {
@@ -155,8 +174,12 @@ struct LiveState<'a> {
     /// Height of the widget area. The actual screen in state.screen may be shorter.
     height: usize,
 
-    /// Computed (x, width) for each column.
+    /// Computed (x, width) for each column, in the order of the columns.
     column_extents: Vec<(u16, u16)>,
+
+    /// Whether the source is narrowed down by the quick filter. All items are
+    /// shown expanded while it is.
+    filtered: bool,
 }
 impl<'a> LiveState<'a> {
     fn new<'b>(
@@ -164,6 +187,7 @@ impl<'a> LiveState<'a> {
         width: u16,
         height: usize,
         source: &'a dyn TableSource,
+        filtered: bool,
         columns: &[Column<'b>],
     ) -> Self {
         // Update columns.
@@ -203,10 +227,14 @@ impl<'a> LiveState<'a> {
             state,
             height,
             column_extents,
+            filtered,
         };
 
-        // Preserve collapsed state and selection.
-        live.state.collapsed.retain(|id, _| source.exists(*id));
+        // Preserve collapsed state and selection. The filtered source hides
+        // items that still exist, so keep their collapsed state for later.
+        if !filtered {
+            live.state.collapsed.retain(|id, _| source.exists(*id));
+        }
 
         let initial_selection_y =
             live.state.selection.and_then(|selection_id| {
@@ -286,7 +314,7 @@ impl<'a> LiveState<'a> {
             live.state.top_row_path.clear();
             live.state.screen.clear();
             debug_assert!(live.state.selection.is_none());
-            debug_assert!(live.state.collapsed.is_empty());
+            debug_assert!(filtered || live.state.collapsed.is_empty());
             return live;
         };
 
@@ -367,6 +395,9 @@ impl<'a> LiveState<'a> {
     }
 
     fn is_collapsed(&self, item_id: u64) -> bool {
+        if self.filtered {
+            return false;
+        }
         self.state
             .collapsed
             .get(&item_id)
@@ -475,6 +506,53 @@ impl<'a> LiveState<'a> {
         }
     }
 
+    /// Computes the sticky group rows as (depth, item_id) pairs.
+    ///
+    /// They cover the first lines of the screen.
+    fn sticky_groups(&self) -> Vec<(usize, u64)> {
+        let Some(&(top_depth, top_row_id)) = self.state.screen.first() else {
+            return Vec::new();
+        };
+
+        // Add groups that contain the top row.
+        let mut sticky_groups: Vec<(usize, u64)> = self
+            .source
+            .strict_ancestor_ids(top_row_id)
+            .zip((0..top_depth).rev())
+            .filter(|&(id, _)| self.source.is_group(id))
+            .map(|(id, depth)| (depth, id))
+            .collect();
+        sticky_groups.reverse();
+
+        // Iterate over rows on the screen that are covered by sticky groups
+        // and adjust the groups accordingly.
+        for (y, &(depth, item_id)) in self.state.screen.iter().enumerate() {
+            if y >= sticky_groups.len() {
+                break;
+            }
+
+            // Groups at the same or a greater depth end before this row.
+            while let Some(&(d, _)) = sticky_groups.last() {
+                if d >= depth {
+                    sticky_groups.pop();
+                } else {
+                    break;
+                }
+            }
+            if y >= sticky_groups.len() {
+                break;
+            }
+            if self.source.is_group(item_id) {
+                sticky_groups.push((depth, item_id));
+            }
+        }
+
+        let max_groups = self.height / 2;
+        sticky_groups.drain(0..sticky_groups.len().saturating_sub(max_groups));
+
+        sticky_groups
+    }
+
     fn scroll_into_view(&mut self, item_id: u64) {
         // Can only use this function if the item can actually be shown on the screen.
         assert!(self.nearest_visible_ancestor(item_id) == item_id);
@@ -483,8 +561,9 @@ impl<'a> LiveState<'a> {
 
         let ordering = 'ordering: {
             if let Some(y) = self.screen_pos(item_id) {
-                // Item is already on screen, just check whether it's in the margins.
-                if y < margin {
+                // Item is already on screen, just check whether it's in the
+                // margins or hidden by sticky groups.
+                if y < self.sticky_groups().len() + margin {
                     Ordering::Less
                 } else if y > self.height - margin - 1 {
                     Ordering::Greater
@@ -546,7 +625,20 @@ impl<'a> LiveState<'a> {
 
         match ordering {
             Ordering::Less => {
-                self.update_screen(item_id, margin);
+                // Move the item further down until it is no longer hidden by
+                // the sticky groups of the new screen.
+                let mut y = margin;
+                loop {
+                    self.update_screen(item_id, y);
+                    let min_y = std::cmp::min(
+                        self.sticky_groups().len() + margin,
+                        self.height.saturating_sub(1),
+                    );
+                    if y >= min_y {
+                        break;
+                    }
+                    y = min_y;
+                }
             }
             Ordering::Greater => {
                 self.update_screen(item_id, self.height - margin - 1);
@@ -664,6 +756,7 @@ pub struct Table<'table> {
     columns: Vec<Column<'table>>,
     default_collapsed: bool,
     show_headers: bool,
+    filter_column: Option<usize>,
 }
 impl std::fmt::Debug for Table<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -671,6 +764,7 @@ impl std::fmt::Debug for Table<'_> {
             .field("id", &self.id)
             .field("default_collapsed", &self.default_collapsed)
             .field("show_headers", &self.show_headers)
+            .field("filter_column", &self.filter_column)
             .finish_non_exhaustive()
     }
 }
@@ -683,6 +777,7 @@ impl<'table> Table<'table> {
             columns: Vec::new(),
             default_collapsed: false,
             show_headers: false,
+            filter_column: None,
         }
     }
 
@@ -718,6 +813,16 @@ impl<'table> Table<'table> {
         }
     }
 
+    /// Enable the quick filter, which narrows the table down to the items
+    /// whose text in the column with the given source ID fuzzily matches what
+    /// the user types.
+    pub fn filter_column(self, source_id: usize) -> Self {
+        Self {
+            filter_column: Some(source_id),
+            ..self
+        }
+    }
+
     pub fn build(mut self, builder: &mut Builder) -> TableResult {
         let state_id = builder.add_state_id(self.id.unwrap_or("table".into()));
         let state = self.state.unwrap_or_else(|| builder.get_state(state_id));
@@ -731,6 +836,29 @@ impl<'table> Table<'table> {
                 .push(Column::new(0, "", Constraint1D::new_min(5)));
         }
 
+        if self.filter_column.is_some() {
+            if has_focus {
+                Self::handle_filter_keys(builder, state);
+            } else {
+                state.filtering = false;
+            }
+        } else {
+            state.filter.clear();
+            state.filtering = false;
+        }
+        let show_filter = state.filtering || !state.filter.is_empty();
+
+        let filter_height = std::cmp::min(if show_filter { 1 } else { 0 }, area.height);
+        let area = Rect {
+            height: area.height - filter_height,
+            ..area
+        };
+        let filter_area = Rect {
+            y: area.y + area.height,
+            height: filter_height,
+            ..area
+        };
+
         let header_height = std::cmp::min(if self.show_headers { 1 } else { 0 }, area.height);
         let body_height = area.height.saturating_sub(header_height);
 
@@ -746,13 +874,25 @@ impl<'table> Table<'table> {
 
         state.default_collapsed = self.default_collapsed;
 
+        let filter = std::mem::take(&mut state.filter);
+        let filtered_source = self
+            .filter_column
+            .filter(|_| !filter.is_empty())
+            .map(|column| filter::FilteredSource::new(self.source, column, &filter));
+        let source = match &filtered_source {
+            Some(filtered_source) => filtered_source as &dyn TableSource,
+            None => self.source,
+        };
+
         let mut live = LiveState::new(
             state,
             header_area.width,
             body_height as usize,
-            self.source,
+            source,
+            filtered_source.is_some(),
             &self.columns,
         );
+        live.state.filter = filter;
 
         let page_size = std::cmp::max(
             (body_area.height / 2) as isize + 1,
@@ -761,7 +901,9 @@ impl<'table> Table<'table> {
         let mouse_page_size = std::cmp::min(5, page_size);
 
         if has_focus {
-            if builder.on_key_press(KeyCode::Left) {
+            const MOVE: &str = "Move the selection";
+
+            if builder.on_key_binding(KeyCode::Left, "Fold / go to parent") {
                 if let Some(selection) = live.state.selection {
                     if !live.is_collapsed(selection) && live.source.num_children(selection) != 0 {
                         live.set_collapsed(selection, true);
@@ -773,41 +915,49 @@ impl<'table> Table<'table> {
                     }
                 }
             }
-            if builder.on_key_press(KeyCode::Right) {
+            if builder.on_key_binding(KeyCode::Right, "Unfold") {
                 if let Some(selection) = live.state.selection {
                     if live.is_collapsed(selection) && live.source.num_children(selection) != 0 {
                         live.set_collapsed(selection, false);
                     }
                 }
             }
-            if builder.on_key_press(KeyCode::Down) {
-                live.move_by(1);
+            // Keys that are held down are handled all at once.
+            let n = builder.on_key_binding_repeat(KeyCode::Down, MOVE);
+            if n != 0 {
+                live.move_by(n as isize);
             }
-            if builder.on_key_press(KeyCode::Up) {
-                live.move_by(-1);
+            let n = builder.on_key_binding_repeat(KeyCode::Up, MOVE);
+            if n != 0 {
+                live.move_by(-(n as isize));
             }
-            if builder.on_key_press(KeyCode::Home) {
+            if builder.on_key_binding(KeyCode::Home, "Go to top") {
                 if !live.state.screen.is_empty() {
                     live.move_to(live.source.child_id(0, 0));
                 }
             }
-            if builder.on_key_press(KeyCode::End) {
+            if builder.on_key_binding(KeyCode::End, "Go to bottom") {
                 if !live.state.screen.is_empty() {
                     live.move_to(live.last_descendant(0).0);
                 }
             }
-            if builder.on_key_press(KeyCode::PageDown) {
-                live.move_by(page_size);
+            let n = builder.on_key_binding_repeat(KeyCode::PageDown, MOVE);
+            if n != 0 {
+                live.move_by(page_size * n as isize);
             }
-            if builder.on_key_press(KeyCode::PageUp) {
-                live.move_by(-page_size);
+            let n = builder.on_key_binding_repeat(KeyCode::PageUp, MOVE);
+            if n != 0 {
+                live.move_by(-page_size * n as isize);
             }
         }
 
         if let Some(point) = builder.on_mouse_press(body_area, MouseButton::Left) {
             let rx = point.x.saturating_sub(body_area.x) as usize;
             let ry = point.y.saturating_sub(body_area.y) as usize;
-            if ry < live.state.screen.len() {
+            let sticky_groups = live.sticky_groups();
+            if ry < sticky_groups.len() {
+                live.move_to(sticky_groups[ry].1);
+            } else if ry < live.state.screen.len() {
                 let (depth, item_id) = live.state.screen[ry];
                 live.move_to_no_scroll(item_id);
 
@@ -825,6 +975,13 @@ impl<'table> Table<'table> {
             live.scroll_by(mouse_page_size);
         }
 
+        // Request the children of expanded items that are still loading.
+        for &(_, item_id) in &live.state.screen {
+            if !live.is_collapsed(item_id) && live.source.children_pending(item_id) {
+                live.source.request_children(item_id);
+            }
+        }
+
         // Render the widget.
         if header_height != 0 {
             let block = Block::new().style(builder.theme().modal_background);
@@ -833,7 +990,7 @@ impl<'table> Table<'table> {
             let header_style = builder.theme().text(builder.theme_context()).header0;
 
             for (idx, column) in self.columns.iter_mut().enumerate() {
-                let (x, width) = live.column_extents[column.source_id];
+                let (x, width) = live.column_extents[idx];
                 let column_area = Rect {
                     x: header_area.x + x,
                     width,
@@ -851,9 +1008,8 @@ impl<'table> Table<'table> {
                         width: 1,
                         ..column_area
                     };
-                    builder
-                        .frame()
-                        .render_widget(Span::from("│").style(header_style), bar_area);
+                    let bar = Span::from(builder.theme().symbols.vertical).style(header_style);
+                    builder.frame().render_widget(bar, bar_area);
                 }
             }
         }
@@ -866,7 +1022,14 @@ impl<'table> Table<'table> {
         );
         builder.frame().render_widget(block, body_area);
 
-        for (ry, (depth, item_id)) in live.state.screen.iter().copied().enumerate() {
+        let search_style = builder.theme().text(builder.theme_context()).search;
+        let sticky_groups = live.sticky_groups();
+        let rows = sticky_groups
+            .iter()
+            .copied()
+            .chain(live.state.screen.iter().copied().skip(sticky_groups.len()));
+
+        for (ry, (depth, item_id)) in rows.enumerate() {
             let indent = (depth * 2) as u16;
             let line_area = Rect {
                 y: body_area.y + ry as u16,
@@ -874,6 +1037,11 @@ impl<'table> Table<'table> {
                 ..body_area
             };
 
+            if ry < sticky_groups.len() {
+                let block = Block::default().style(builder.theme().modal_background);
+                builder.frame().render_widget(block, line_area);
+            }
+
             let selected = live.state.selection == Some(item_id);
             if selected {
                 let block =
@@ -889,10 +1057,8 @@ impl<'table> Table<'table> {
 
             for (idx, column) in self.columns.iter().enumerate() {
                 let column_area = Rect {
-                    x: body_area
-                        .x
-                        .saturating_add(live.column_extents[column.source_id].0),
-                    width: live.column_extents[column.source_id].1,
+                    x: body_area.x.saturating_add(live.column_extents[idx].0),
+                    width: live.column_extents[idx].1,
                     ..line_area
                 };
 
@@ -905,11 +1071,11 @@ impl<'table> Table<'table> {
                         ..column_area
                     };
 
-                    if self.source.num_children(item_id) != 0 {
+                    if source.num_children(item_id) != 0 {
                         // Render the folding range marker.
                         let marker = match live.is_collapsed(item_id) {
-                            true => "▶",
-                            false => "▼",
+                            true => builder.theme().symbols.collapsed,
+                            false => builder.theme().symbols.expanded,
                         };
                         let span = Span::from(marker).style(base_style);
                         builder.frame().render_widget(span, column_area);
@@ -922,15 +1088,125 @@ impl<'table> Table<'table> {
                     }
                 };
 
-                let spans = self.source.get_data(item_id, column.source_id);
+                let mut spans = source.get_data(item_id, column.source_id);
+                if filtered_source.is_some() && self.filter_column == Some(column.source_id) {
+                    spans = filter::highlight(spans, &live.state.filter, search_style);
+                }
                 let line = Line::from(spans).style(base_style);
+                if builder.is_hovered(item_area) {
+                    match source.get_tooltip(item_id, column.source_id) {
+                        Some(text) => builder.tooltip(item_area, text),
+                        None if line.width() > item_area.width as usize => {
+                            let text: String =
+                                line.spans.iter().map(|span| span.content.as_ref()).collect();
+                            builder.tooltip(item_area, text);
+                        }
+                        None => {}
+                    }
+                }
+                if builder.hyperlinks() {
+                    if let Some(url) = source.get_link(item_id, column.source_id) {
+                        let width = (line.width() as u16).min(item_area.width);
+                        builder.add_hyperlink(Rect { width, ..item_area }, url);
+                    }
+                }
                 builder.frame().render_widget(line, item_area);
             }
         }
 
+        if filter_height != 0 {
+            let text = builder.theme().text(builder.theme_context());
+            let label = Span::styled("Filter: ", text.header2);
+            let label_width = label.width() as u16;
+            let filter = Span::styled(live.state.filter.clone(), text.normal);
+            let line = Line::from(vec![label, filter]);
+            let block = Block::new().style(builder.theme().modal_background);
+            builder.frame().render_widget(block, filter_area);
+            builder.frame().render_widget(line, filter_area);
+
+            if has_focus && live.state.filtering {
+                let cursor_x = filter_area.x.saturating_add(label_width).saturating_add(
+                    live.state.filter.chars().count() as u16,
+                );
+                let cursor_x = std::cmp::min(
+                    cursor_x,
+                    (filter_area.x + filter_area.width).saturating_sub(1),
+                );
+                builder.frame().set_cursor_position(Position::new(cursor_x, filter_area.y));
+            }
+        }
+
+        if has_focus && builder.screen_reader() {
+            let current = live.state.selection.map(|id| (id, live.is_collapsed(id)));
+            if current != live.state.announced {
+                if let Some((item_id, collapsed)) = current {
+                    let mut text = String::new();
+                    for column in &self.columns {
+                        let spans = source.get_data(item_id, column.source_id);
+                        let data: String = spans.iter().map(|span| span.content.as_ref()).collect();
+                        let data = data.trim();
+                        if !data.is_empty() {
+                            if !text.is_empty() {
+                                text.push_str(", ");
+                            }
+                            text.push_str(data);
+                        }
+                    }
+                    if source.num_children(item_id) != 0 {
+                        text.push_str(if collapsed { " (collapsed)" } else { " (expanded)" });
+                    }
+                    builder.announce(text);
+                }
+                live.state.announced = current;
+            }
+        } else {
+            live.state.announced = None;
+        }
+
         TableResult {
             has_focus,
             selection: live.state.selection,
         }
     }
+
+    /// Handle the keys of the quick filter. While the filter is being typed,
+    /// all printable characters go to it.
+    fn handle_filter_keys(builder: &mut Builder, state: &mut TableState) {
+        if state.filtering {
+            if builder.on_key_binding(KeyCode::Enter, "Keep the filter") {
+                state.filtering = false;
+            } else if builder.on_key_binding(KeyCode::Esc, "Clear the filter") {
+                state.filter.clear();
+                state.filtering = false;
+            } else {
+                builder.with_event(|ev| {
+                    let Event::Key(ev) = ev else { return None };
+                    if ev.kind != KeyEventKind::Press
+                        || ev.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                    {
+                        return None;
+                    }
+                    match ev.code {
+                        KeyCode::Char(ch) => state.filter.push(ch),
+                        KeyCode::Backspace => {
+                            state.filter.pop();
+                        }
+                        _ => return None,
+                    }
+                    Some(())
+                });
+            }
+            builder.describe_key(KeyCode::Backspace, "Edit the filter");
+            return;
+        }
+
+        let start = KeyCode::Char('f').with_modifiers(KeyModifiers::CONTROL);
+        if builder.on_key_binding(start, "Filter") {
+            state.filtering = true;
+        } else if !state.filter.is_empty()
+            && builder.on_key_binding(KeyCode::Esc, "Clear the filter")
+        {
+            state.filter.clear();
+        }
+    }
 }
@@ -1,31 +1,66 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::{any::Any, time::Instant};
+use std::{
+    any::Any,
+    collections::VecDeque,
+    io::{BufWriter, Stdout, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use log::debug;
 use ratatui::{
+    backend::{Backend, CrosstermBackend, TestBackend},
+    buffer::{Buffer, Cell},
     crossterm::{
-        event::{DisableMouseCapture, EnableMouseCapture},
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        },
         execute,
+        terminal::{enable_raw_mode, EnterAlternateScreen},
     },
-    layout::Position,
+    layout::{Position, Rect},
+    text::Span,
     widgets::Clear,
-    DefaultTerminal,
 };
 
 use crate::{
-    event::{self, Event, EventExt},
+    event::{self, Event, EventExt, KeyCode, KeyEventKind},
+    keymap::HelpOverlay,
+    macros::Macros,
     layout::{self, Constraint1D},
     prelude::*,
+    recorder::{self, Recorder},
+    screen_reader::ScreenReader,
     signals::{self, Dispatch, MergeWakeupWait, Receiver},
     state::{BuildStore, Builder, Store},
-    theme::Theme,
+    theme::{Symbols, Theme},
+    tooltip,
 };
 
 struct Events {
     recv: Receiver<Result<Event>>,
     injected: Vec<Box<dyn Any + Send + Sync>>,
+    replay: VecDeque<Event>,
     wakeup_waits: Vec<MergeWakeupWait>,
+
+    /// Events that were read ahead or put back, with their repeat counts.
+    pending: VecDeque<(Event, usize)>,
+
+    /// Generator of input that takes the place of the console, if any.
+    synthetic: Option<Box<dyn FnMut() -> Option<Event>>>,
+
+    /// When to stop waiting for events and rebuild the UI anyway.
+    refresh_at: Option<Instant>,
+
+    /// Whether there is no console to read events from, so that only the
+    /// events in `replay` are processed.
+    headless: bool,
+
+    /// Events that were read from the console or `replay` and not yet
+    /// written to the recording, if the session is recorded. Events that are
+    /// put back or replayed by macros are not included.
+    recorded: Option<Vec<(Instant, Event)>>,
 }
 impl Events {
     fn new() -> Self {
@@ -35,7 +70,18 @@ impl Events {
             if let Err(err) = try_forward(
                 || -> Result<()> {
                     loop {
-                        signal.signal(Ok(event::read()?));
+                        let event = event::read()?;
+
+                        // The Windows console also reports key releases, which
+                        // no widget handles. Drop them early so that they don't
+                        // cause redundant frames.
+                        if let Event::Key(ev) = &event {
+                            if ev.kind == KeyEventKind::Release {
+                                continue;
+                            }
+                        }
+
+                        signal.signal(Ok(event));
                     }
                 },
                 || "",
@@ -47,23 +93,102 @@ impl Events {
         Self {
             recv,
             injected: Vec::new(),
+            replay: VecDeque::new(),
             wakeup_waits: Vec::new(),
+            pending: VecDeque::new(),
+            synthetic: None,
+            refresh_at: None,
+            headless: false,
+            recorded: None,
         }
     }
 
-    fn get(&mut self, wait: bool) -> Result<Option<EventExt>> {
+    fn new_headless() -> Self {
+        let (_, recv) = signals::make_channel();
+
+        Self {
+            recv,
+            injected: Vec::new(),
+            replay: VecDeque::new(),
+            wakeup_waits: Vec::new(),
+            pending: VecDeque::new(),
+            synthetic: None,
+            refresh_at: None,
+            headless: true,
+            recorded: None,
+        }
+    }
+
+    /// Get the next event and how often it is repeated.
+    ///
+    /// Copies of coalescible events that are already queued are merged.
+    fn get(&mut self, wait: bool) -> Result<Option<(EventExt, usize)>> {
         if !self.injected.is_empty() {
-            return Ok(Some(EventExt::Custom(
-                self.injected.drain(0..1).next().unwrap(),
+            return Ok(Some((
+                EventExt::Custom(self.injected.drain(0..1).next().unwrap()),
+                1,
             )));
         }
 
+        let Some((mut event, mut repeat)) = self.next(wait)? else {
+            return Ok(None);
+        };
+        if event::is_pointer_motion(&event) {
+            while let Some((next, count)) = self.next(false)? {
+                if !event::is_pointer_motion(&next) {
+                    self.pending.push_front((next, count));
+                    break;
+                }
+                event = next;
+            }
+        } else if event::is_coalescible(&event) {
+            while let Some((next, count)) = self.next(false)? {
+                if next != event {
+                    self.pending.push_front((next, count));
+                    break;
+                }
+                repeat += count;
+            }
+        }
+        Ok(Some((EventExt::Event(event), repeat)))
+    }
+
+    /// Deliver the presses of a coalesced event again that weren't handled.
+    fn put_back(&mut self, event: Event, repeat: usize) {
+        self.pending.push_front((event, repeat));
+    }
+
+    /// Deliver the events before all others, e.g. the keys of a macro.
+    fn push_front(&mut self, events: VecDeque<Event>) {
+        for event in events.into_iter().rev() {
+            self.pending.push_front((event, 1));
+        }
+    }
+
+    fn next(&mut self, wait: bool) -> Result<Option<(Event, usize)>> {
+        if let Some(pending) = self.pending.pop_front() {
+            return Ok(Some(pending));
+        }
+        if let Some(event) = self.replay.pop_front() {
+            self.record(&event);
+            return Ok(Some((event, 1)));
+        }
+        if let Some(synthetic) = &mut self.synthetic {
+            match synthetic() {
+                Some(event) => {
+                    self.record(&event);
+                    return Ok(Some((event, 1)));
+                }
+                None => self.synthetic = None,
+            }
+        }
+
         let mut the_event = None;
         let mut the_err = None;
         let mut dispatch = Dispatch::new();
         dispatch.add(self.recv.dispatch_one(|event| match event {
             Ok(event) => {
-                the_event = Some(EventExt::Event(event));
+                the_event = Some((event, 1));
             }
             Err(err) => {
                 the_err = Some(err);
@@ -72,51 +197,221 @@ impl Events {
         for wait in &mut self.wakeup_waits {
             dispatch.add(wait.dispatch());
         }
-        dispatch.poll(wait);
+        dispatch.poll_deadline(wait, self.refresh_at);
 
         if let Some(err) = the_err.take() {
             Err(err)?
         }
 
+        if let Some((event, _)) = &the_event {
+            self.record(event);
+        }
         Ok(the_event)
     }
+
+    fn record(&mut self, event: &Event) {
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push((Instant::now(), event.clone()));
+        }
+    }
+}
+
+/// Size of the output buffer. Frames are written to the console in a single
+/// write unless they are larger than this, which keeps redraws quick over
+/// high-latency connections.
+const OUTPUT_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Minimum time between frames when redraws are coalesced (about 30 fps).
+const COALESCED_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// Whether the terminal is known to support OSC 8 hyperlinks, based on the
+/// environment.
+///
+/// Most terminals ignore the escape sequence if they don't support it, but
+/// some show it as garbage, so only terminals that are known to support it
+/// are detected. Terminal multiplexers don't pass it through by default.
+pub fn supports_hyperlinks() -> bool {
+    detect_hyperlinks(|name| std::env::var(name).ok())
+}
+
+fn detect_hyperlinks(var: impl Fn(&str) -> Option<String>) -> bool {
+    let term = var("TERM").unwrap_or_default();
+    if term == "dumb" || term == "linux" || term.starts_with("screen") || var("TMUX").is_some() {
+        return false;
+    }
+    if ["xterm-kitty", "xterm-ghostty", "alacritty", "foot", "wezterm"]
+        .iter()
+        .any(|prefix| term.starts_with(prefix))
+    {
+        return true;
+    }
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if ["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper"].contains(&program.as_str()) {
+        return true;
+    }
+    // VTE-based terminals, e.g. GNOME Terminal, since version 0.50.
+    if var("VTE_VERSION").and_then(|version| version.parse::<u32>().ok()) >= Some(5000) {
+        return true;
+    }
+    var("WT_SESSION").is_some() || var("KONSOLE_VERSION").is_some()
 }
 
-pub struct Terminal {
-    terminal: DefaultTerminal,
+/// Backends that can show hyperlinks.
+pub trait HyperlinkBackend: Backend {
+    /// Draw the given cells again as a hyperlink to the URL.
+    fn draw_hyperlink<'a, I>(&mut self, url: &str, content: I) -> std::io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>;
+}
+impl<W: Write> HyperlinkBackend for CrosstermBackend<W> {
+    fn draw_hyperlink<'a, I>(&mut self, url: &str, content: I) -> std::io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        // The URL must not end the escape sequence early.
+        let mut escaped = String::with_capacity(url.len());
+        for byte in url.bytes() {
+            if (0x21..0x7f).contains(&byte) {
+                escaped.push(byte as char);
+            } else {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        write!(self, "\x1b]8;;{escaped}\x1b\\")?;
+        self.draw(content)?;
+        write!(self, "\x1b]8;;\x1b\\")
+    }
+}
+impl HyperlinkBackend for TestBackend {
+    fn draw_hyperlink<'a, I>(&mut self, _url: &str, _content: I) -> std::io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        Ok(())
+    }
+}
+
+/// The cells of one row of a frame that are a hyperlink.
+#[derive(Debug)]
+struct Hyperlink {
+    url: String,
+    y: u16,
+
+    /// (x, cell) pairs. Cells that are covered by wide characters are skipped.
+    cells: Vec<(u16, Cell)>,
+}
+
+fn capture_hyperlinks(buffer: &Buffer, links: Vec<(Rect, String)>) -> Vec<Hyperlink> {
+    let mut hyperlinks = Vec::new();
+    for (area, url) in links {
+        for y in area.top()..area.bottom() {
+            let mut cells = Vec::new();
+            let mut skip = 0;
+            for x in area.left()..area.right() {
+                let cell = &buffer[(x, y)];
+                if skip == 0 {
+                    cells.push((x, cell.clone()));
+                    skip = Span::raw(cell.symbol()).width().max(1);
+                }
+                skip -= 1;
+            }
+            hyperlinks.push(Hyperlink { url: url.clone(), y, cells });
+        }
+    }
+    hyperlinks
+}
+
+fn restore() {
+    let mut stdout = std::io::stdout();
+    if let Err(err) = execute!(stdout, DisableBracketedPaste, DisableMouseCapture) {
+        eprintln!("Failed to disable bracketed paste and mouse capture: {err}");
+    }
+    ratatui::restore();
+}
+
+pub struct Terminal<B: Backend = CrosstermBackend<BufWriter<Stdout>>> {
+    terminal: ratatui::Terminal<B>,
     store: Store,
     events: Events,
     theme: Theme,
     need_refresh: bool,
+    help: Option<HelpOverlay>,
+    macros: Macros,
+    screen_reader: ScreenReader,
+    frame_interval: Option<Duration>,
+    hyperlinks: bool,
+
+    /// Hyperlinks of the last frame, which are drawn after it.
+    links: Vec<Hyperlink>,
+
+    /// Last reported position of the mouse pointer, which is forgotten when a
+    /// key is pressed so that tooltips don't get in the way.
+    pointer: Option<Position>,
+
+    recorder: Option<Recorder>,
 }
 impl Terminal {
     pub(crate) fn init() -> Result<Terminal> {
-        let mut terminal = ratatui::try_init()?;
-        terminal.clear()?;
-
         let mut stdout = std::io::stdout();
-        execute!(stdout, EnableMouseCapture)?;
+        enable_raw_mode()?;
+        // Bracketed paste delivers pasted text as a single event instead of a
+        // key press per character.
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+
+        // ratatui only writes the cells that changed since the last frame, and
+        // the buffer collects them into a single write per frame.
+        let backend = CrosstermBackend::new(BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, stdout));
+        let mut terminal = ratatui::Terminal::new(backend)?;
+        terminal.clear()?;
 
         let old_hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(move |info| {
-            Terminal::restore();
+            restore();
             old_hook(info);
         }));
 
-        Ok(Terminal {
+        let mut terminal = Terminal::new(terminal, Events::new());
+        terminal.hyperlinks = supports_hyperlinks();
+        Ok(terminal)
+    }
+}
+impl Terminal<TestBackend> {
+    /// Create a terminal that renders into a buffer instead of the console,
+    /// e.g. for tests that run in CI.
+    ///
+    /// Events are only taken from `push_event`, and `run` returns once they
+    /// have all been processed.
+    pub fn headless(width: u16, height: u16) -> Result<Self> {
+        let terminal = ratatui::Terminal::new(TestBackend::new(width, height))?;
+        Ok(Terminal::new(terminal, Events::new_headless()))
+    }
+}
+impl<B: HyperlinkBackend> Terminal<B> {
+    fn new(terminal: ratatui::Terminal<B>, events: Events) -> Self {
+        Terminal {
             terminal,
             store: Store::default(),
-            events: Events::new(),
-            theme: Theme::default(),
+            events,
+            theme: Theme::from_env(),
             need_refresh: true,
-        })
+            help: None,
+            macros: Macros::new(),
+            screen_reader: ScreenReader::default(),
+            frame_interval: None,
+            hyperlinks: false,
+            links: Vec::new(),
+            pointer: None,
+            recorder: None,
+        }
     }
 
-    fn restore() {
-        let mut stdout = std::io::stdout();
-        if let Err(err) = execute!(stdout, DisableMouseCapture) {
-            eprintln!("Failed to disable mouse capture: {err}");
-        }
+    pub fn backend(&self) -> &B {
+        self.terminal.backend()
+    }
+
+    /// Queue an event as if it was read from the console.
+    pub fn push_event(&mut self, event: Event) {
+        self.events.replay.push_back(event);
     }
 
     /// Add a waiter part of a merge wakeup pair.
@@ -126,6 +421,85 @@ impl Terminal {
         self.events.wakeup_waits.push(wakeup_wait);
     }
 
+    /// Load key macros from the given file and save them there when they
+    /// are recorded.
+    pub fn set_macro_file(&mut self, path: PathBuf) {
+        self.macros.set_file(path);
+    }
+
+    /// The theme of the UI, e.g. to adjust it to the preferences of the user.
+    pub fn theme_mut(&mut self) -> &mut Theme {
+        &mut self.theme
+    }
+
+    /// Use only ASCII characters for markers and lines, which works better on
+    /// limited terminals and over slow links.
+    ///
+    /// vctuik has no animations, so this is all that is needed to reduce the
+    /// motion on screen.
+    pub fn set_reduced_motion(&mut self, reduced: bool) {
+        self.theme.symbols = if reduced { Symbols::ASCII } else { Symbols::UNICODE };
+    }
+
+    /// Limit redraws to about 30 per second. Events that arrive in the meantime
+    /// are all processed before the next redraw, e.g. when a key is held down.
+    ///
+    /// This reduces the output over slow connections at the cost of a little
+    /// latency.
+    pub fn set_coalesce_redraws(&mut self, coalesce: bool) {
+        self.frame_interval = coalesce.then_some(COALESCED_FRAME_INTERVAL);
+    }
+
+    /// Enable or disable the screen reader mode.
+    ///
+    /// The mode can also be toggled at runtime with F2.
+    pub fn set_screen_reader(&mut self, enabled: bool) {
+        self.screen_reader.set_enabled(enabled);
+    }
+
+    /// Show links, e.g. to pull requests or commits, as OSC 8 hyperlinks that
+    /// can be clicked.
+    ///
+    /// Enabled by default if the terminal is known to support them, see
+    /// [`supports_hyperlinks`].
+    pub fn set_hyperlinks(&mut self, enabled: bool) {
+        self.hyperlinks = enabled;
+    }
+
+    /// Append screen reader announcements to the given file.
+    pub fn set_screen_reader_transcript(&mut self, path: PathBuf) {
+        self.screen_reader.set_transcript(path);
+    }
+
+    /// Record the frames and input of the session to an asciinema v2 file.
+    ///
+    /// The input is written in a form that `replay_recording` can read back.
+    pub fn set_recording(&mut self, path: &Path) -> Result<()> {
+        let size = self.terminal.size()?;
+        self.recorder = Some(Recorder::create(path, Rect::new(0, 0, size.width, size.height))?);
+        self.events.recorded = Some(Vec::new());
+        Ok(())
+    }
+
+    /// Queue the input of a recording as if it was read from the console,
+    /// e.g. to reproduce a bug.
+    pub fn replay_recording(&mut self, path: &Path) -> Result<()> {
+        for event in recorder::read_input(path)? {
+            self.push_event(event);
+        }
+        Ok(())
+    }
+
+    /// Take input from the given generator instead of the console until it
+    /// returns None, e.g. random key presses for a soak test. The generator
+    /// may block to pace the input.
+    ///
+    /// Queued events, e.g. of a replayed recording, come first. A headless
+    /// terminal runs until the generator is done.
+    pub fn set_synthetic_input(&mut self, input: impl FnMut() -> Option<Event> + 'static) {
+        self.events.synthetic = Some(Box::new(input));
+    }
+
     /// Run a default event loop until f returns false.
     pub fn run<F>(&mut self, mut f: F) -> Result<()>
     where
@@ -133,13 +507,43 @@ impl Terminal {
     {
         let mut the_result = Ok(());
         let mut the_event: Option<EventExt> = None;
+        let mut the_repeat = 1;
         let mut start_frame = Instant::now();
         let mut running = true;
 
         loop {
-            self.terminal.draw(|frame| {
+            let start_draw = Instant::now();
+            let completed = self.terminal.draw(|frame| {
                 the_result = || -> Result<()> {
                     loop {
+                        // The key binding overview takes all key presses while
+                        // it is open.
+                        if let Some(help) = &mut self.help {
+                            if let Some(EventExt::Event(Event::Key(ev))) = &the_event {
+                                if ev.kind == KeyEventKind::Press
+                                    && !(0..the_repeat).all(|_| help.handle_key(ev))
+                                {
+                                    self.help = None;
+                                }
+                                the_event = None;
+                            }
+                        }
+                        if let Some(EventExt::Event(event)) = &the_event {
+                            let mut replay = VecDeque::new();
+                            if self.macros.pre_event(event, &mut replay) {
+                                the_event = None;
+                            }
+                            self.events.push_front(replay);
+                        }
+
+                        match &the_event {
+                            Some(EventExt::Event(Event::Mouse(ev))) => {
+                                self.pointer = Some(Position::new(ev.column, ev.row));
+                            }
+                            Some(EventExt::Event(Event::Key(_))) => self.pointer = None,
+                            _ => {}
+                        }
+
                         // Process the UI once.
                         let area = frame.area();
                         let mut build_store = BuildStore::new(
@@ -149,10 +553,21 @@ impl Terminal {
                             the_event.take(),
                             start_frame,
                         );
+                        build_store.repeat = std::mem::replace(&mut the_repeat, 1);
+                        build_store.screen_reader = self.screen_reader.is_enabled();
+                        build_store.hyperlinks = self.hyperlinks;
+                        build_store.pointer = self.pointer;
+
+                        // The screen reader mode reserves the bottom line for
+                        // announcements.
+                        let mut ui_area = area;
+                        if build_store.screen_reader {
+                            ui_area.height = ui_area.height.saturating_sub(1);
+                        }
 
                         {
                             let mut layout = layout::LayoutEngine::new();
-                            let mut builder = Builder::new(&mut build_store, &mut layout, area);
+                            let mut builder = Builder::new(&mut build_store, &mut layout, ui_area);
                             if !f(&mut builder)? {
                                 running = false;
                                 return Ok(());
@@ -160,7 +575,7 @@ impl Terminal {
 
                             if layout
                                 .finish(
-                                    Constraint1D::new_fixed(area.height),
+                                    Constraint1D::new_fixed(ui_area.height),
                                     &mut build_store.current_layout_mut(),
                                 )
                                 .0
@@ -169,9 +584,52 @@ impl Terminal {
                             }
                         }
 
+                        // Focus changes are handled in end_frame.
+                        build_store.keymap.add(
+                            "General",
+                            KeyCode::Tab.into(),
+                            "Move focus to the next / previous pane".into(),
+                        );
+                        build_store.keymap.add(
+                            "General",
+                            KeyCode::BackTab.into(),
+                            "Move focus to the next / previous pane".into(),
+                        );
+                        build_store.keymap.add(
+                            "General",
+                            KeyCode::Char('?').into(),
+                            "Show this overview of key bindings".into(),
+                        );
+                        build_store.keymap.add(
+                            "General",
+                            KeyCode::F(2).into(),
+                            "Toggle screen reader mode".into(),
+                        );
+                        if self.macros.post_event(&mut build_store) {
+                            build_store.need_refresh = true;
+                        }
+                        if self.help.is_none()
+                            && build_store.is_unhandled_key_press(KeyCode::Char('?').into())
+                        {
+                            self.help =
+                                Some(HelpOverlay::new(std::mem::take(&mut build_store.keymap)));
+                        }
+
+                        self.screen_reader.announce(build_store.announcements.drain(..));
+                        if build_store.is_unhandled_key_press(KeyCode::F(2).into()) {
+                            self.screen_reader.set_enabled(!self.screen_reader.is_enabled());
+                            build_store.need_refresh = true;
+                        }
+
                         build_store.end_frame();
+                        if let Some((event, repeat)) = build_store.unhandled_repeats() {
+                            self.events.put_back(event, repeat);
+                        }
                         self.events.injected.append(&mut build_store.injected);
+                        let mut links = std::mem::take(&mut build_store.links);
+                        let tooltip = build_store.tooltip.take();
                         self.need_refresh = build_store.need_refresh;
+                        self.events.refresh_at = build_store.refresh_at;
 
                         if build_store.trace_frame {
                             let buffer = frame.buffer_mut();
@@ -187,6 +645,22 @@ impl Terminal {
                             }
                         }
 
+                        if let Some((text, pointer)) = tooltip.zip(self.pointer) {
+                            let area = tooltip::render(frame, &self.theme, pointer, &text);
+                            links.retain(|(link_area, _)| !link_area.intersects(area));
+                        }
+                        self.macros.render(frame, &self.theme);
+                        self.screen_reader.render(frame, &self.theme);
+                        if let Some(help) = &mut self.help {
+                            help.render(frame, &self.theme);
+                        }
+
+                        // The overview of key bindings covers the links.
+                        self.links = match self.help {
+                            None => capture_hyperlinks(frame.buffer_mut(), links),
+                            Some(_) => Vec::new(),
+                        };
+
                         // If the UI hasn't settled, just re-process it immediately
                         // without an event (since the settling could affect how
                         // events are routed).
@@ -198,10 +672,11 @@ impl Terminal {
                         // to process, break out of the loop and actually send out
                         // the rendered frame.
                         if !self.need_refresh {
-                            the_event = self.events.get(false)?;
-                            if the_event.is_none() {
+                            let Some((event, repeat)) = self.events.get(false)? else {
                                 return Ok(());
-                            }
+                            };
+                            the_event = Some(event);
+                            the_repeat = repeat;
                         }
 
                         // TODO: reset the cursor position properly instead of this
@@ -215,20 +690,421 @@ impl Terminal {
                 }();
             })?;
 
+            if let Some(recorder) = &mut self.recorder {
+                for (time, event) in self.events.recorded.iter_mut().flat_map(|r| r.drain(..)) {
+                    recorder.input(time, &event)?;
+                }
+                recorder.frame(completed.buffer)?;
+            }
+
             if !running || the_result.is_err() {
                 break;
             }
 
-            the_event = self.events.get(true)?;
+            if !self.links.is_empty() {
+                let size = self.terminal.size()?;
+                let backend = self.terminal.backend_mut();
+                for link in &self.links {
+                    let content = link.cells.iter().map(|(x, cell)| (*x, link.y, cell));
+                    backend.draw_hyperlink(&link.url, content)?;
+                }
+                // Restore the cursor position that was set for the frame.
+                backend.set_cursor_position(Position {
+                    x: size.width,
+                    y: size.height,
+                })?;
+                Backend::flush(backend)?;
+            }
+
+            if let Some(interval) = self.frame_interval {
+                // Let events queue up, so that they are handled together before
+                // the next redraw.
+                if let Some(remaining) = interval.checked_sub(start_draw.elapsed()) {
+                    std::thread::sleep(remaining);
+                }
+            }
+
+            let next = self.events.get(!self.events.headless)?;
+            if next.is_none() && self.events.headless {
+                break;
+            }
+            (the_event, the_repeat) = match next {
+                Some((event, repeat)) => (Some(event), repeat),
+                None => (None, 1),
+            };
             start_frame = Instant::now();
         }
 
         the_result
     }
 }
-impl Drop for Terminal {
+impl<B: Backend> Drop for Terminal<B> {
     fn drop(&mut self) {
-        Terminal::restore();
-        ratatui::restore();
+        if !self.events.headless {
+            restore();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fmt::Write as _;
+
+    use crate::{
+        event::{KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
+        form::Form,
+        pager::{Pager, PagerState, RichPagerSourceBuilder, StringPagerSource},
+        section::with_section,
+        table::{async_source::AsyncTableSource, simple_table::SourceState, Table, TableState},
+    };
+
+    use super::*;
+
+    const TEXT: &str = "one\ntwo\nthree\n";
+
+    fn screen(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn press(terminal: &mut Terminal<TestBackend>, code: KeyCode) {
+        terminal.push_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn headless_render() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let source = StringPagerSource::new(TEXT);
+        terminal
+            .run(|builder| {
+                with_section(builder, "Text", |builder| {
+                    Pager::new(&source).build(builder, "pager");
+                });
+                Ok(true)
+            })
+            .unwrap();
+
+        let screen = screen(&terminal);
+        assert!(screen[0].contains("Text"), "{screen:?}");
+        assert!(screen[1].contains("one"), "{screen:?}");
+        assert!(screen[3].contains("three"), "{screen:?}");
+    }
+
+    #[test]
+    fn headless_keys() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let source = StringPagerSource::new(TEXT);
+        let mut state = PagerState::default();
+        let mut selected = Vec::new();
+        press(&mut terminal, KeyCode::Down);
+        press(&mut terminal, KeyCode::Down);
+        terminal
+            .run(|builder| {
+                let result = Pager::new(&source).build_with_state(builder, "pager", &mut state);
+                selected.push(result.selected_line());
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(selected.first(), Some(&0));
+        assert_eq!(selected.last(), Some(&2));
+    }
+
+    #[test]
+    fn coalesce_key_repeat() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let source = StringPagerSource::new("0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+        let mut state = PagerState::default();
+        let mut key_frames = 0;
+        let mut selected = 0;
+        for _ in 0..5 {
+            press(&mut terminal, KeyCode::Down);
+        }
+        terminal
+            .run(|builder| {
+                if builder.peek_event().is_some() {
+                    key_frames += 1;
+                }
+                let result = Pager::new(&source).build_with_state(builder, "pager", &mut state);
+                selected = result.selected_line();
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(selected, 5);
+        assert_eq!(key_frames, 1);
+    }
+
+    #[test]
+    fn coalesce_key_repeat_unhandled() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let mut presses = 0;
+        for _ in 0..3 {
+            press(&mut terminal, KeyCode::Down);
+        }
+        press(&mut terminal, KeyCode::Up);
+        terminal
+            .run(|builder| {
+                if builder.on_key_press(KeyCode::Down) {
+                    presses += 1;
+                }
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(presses, 3);
+    }
+
+    #[test]
+    fn synthetic_input() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let source = StringPagerSource::new(TEXT);
+        let mut keys = vec![KeyCode::Down, KeyCode::Down].into_iter();
+        terminal.set_synthetic_input(move || {
+            keys.next().map(|code| Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+        });
+        let mut state = PagerState::default();
+        let mut selected = 0;
+        let mut stats = Vec::new();
+        terminal
+            .run(|builder| {
+                let result = Pager::new(&source).build_with_state(builder, "pager", &mut state);
+                selected = result.selected_line();
+                // The pager persists its cursors when the result is dropped.
+                drop(result);
+                stats.push(builder.stats());
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(selected, 2);
+        // Other tests may create cursors concurrently, so only the lower bound
+        // of the scroll and selection cursors is known.
+        assert!(stats.iter().all(|frame| frame.ids == 1), "{stats:?}");
+        assert!(stats.iter().all(|frame| frame.persistent_cursors >= 2), "{stats:?}");
+    }
+
+    #[test]
+    fn table_sticky_groups() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let mut source_state = SourceState::new();
+        let mut state = TableState::default();
+        for _ in 0..10 {
+            press(&mut terminal, KeyCode::Down);
+        }
+        terminal
+            .run(|builder| {
+                let mut source = source_state.build();
+                let group = source.add(0, 0).raw(0, "group").group().id();
+                for i in 1..20 {
+                    source.add(group, i).raw(0, format!("item {i}"));
+                }
+                Table::new(&source.finish()).state(&mut state).build(builder);
+                Ok(true)
+            })
+            .unwrap();
+
+        let screen = screen(&terminal);
+        assert!(screen[0].contains("group"), "{screen:?}");
+        assert!(screen.iter().any(|line| line.contains("item 10")), "{screen:?}");
+        assert!(!screen.iter().any(|line| line.ends_with("item 1")), "{screen:?}");
+    }
+
+    #[test]
+    fn table_async_children() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let mut source_state = SourceState::new();
+        let mut repo = 0;
+        let mut requested = Vec::new();
+        terminal
+            .run(|builder| {
+                let mut source = source_state.build();
+                repo = source.add(0, 0).raw(0, "repo").id();
+                let mut source = AsyncTableSource::new(source.finish())
+                    .on_request(|item_id| requested.push(item_id));
+                source.loading(repo);
+                Table::new(&source).build(builder);
+                Ok(true)
+            })
+            .unwrap();
+
+        assert!(!requested.is_empty());
+        assert!(requested.iter().all(|&item_id| item_id == repo));
+        let screen = screen(&terminal);
+        assert!(screen[1].contains("Loading..."), "{screen:?}");
+    }
+
+    #[test]
+    fn form_submit() {
+        for (name, expected) in [("", false), ("world", true)] {
+            let mut terminal = Terminal::headless(30, 8).unwrap();
+            let mut name = name.to_string();
+            let mut submitted = false;
+            press(&mut terminal, KeyCode::Tab);
+            press(&mut terminal, KeyCode::Enter);
+            terminal
+                .run(|builder| {
+                    let result = Form::new("form").build(builder, |form| {
+                        form.input("Name:", &mut name);
+                        if name.is_empty() {
+                            form.error("Please enter a name");
+                        }
+                    });
+                    submitted |= result.submitted;
+                    Ok(true)
+                })
+                .unwrap();
+
+            assert_eq!(submitted, expected);
+        }
+    }
+
+    #[test]
+    fn detect_hyperlinks() {
+        let detect = |vars: &[(&str, &str)]| {
+            super::detect_hyperlinks(|name| {
+                vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+            })
+        };
+        assert!(!detect(&[("TERM", "xterm-256color")]));
+        assert!(detect(&[("TERM", "xterm-kitty")]));
+        assert!(detect(&[("TERM", "xterm-256color"), ("VTE_VERSION", "7600")]));
+        assert!(!detect(&[("TERM", "xterm-256color"), ("VTE_VERSION", "4800")]));
+        assert!(!detect(&[("TERM", "screen-256color"), ("TERM_PROGRAM", "WezTerm")]));
+        assert!(!detect(&[("TERM", "dumb"), ("WT_SESSION", "1")]));
+    }
+
+    #[test]
+    fn headless_hyperlinks() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        terminal.set_hyperlinks(true);
+        let mut source = RichPagerSourceBuilder::new();
+        source.set_indent(2);
+        source.write_str("see ").unwrap();
+        source.write_link("#12", "https://example.com/pull/12").unwrap();
+        source.write_str(" and ").unwrap();
+        source.write_link("#13", "https://example.com/pull/13").unwrap();
+        source.write_str("\n").unwrap();
+        let source = source.build();
+        terminal
+            .run(|builder| {
+                Pager::new(&source).build(builder, "pager");
+                Ok(true)
+            })
+            .unwrap();
+
+        let links: Vec<_> = terminal
+            .links
+            .iter()
+            .map(|link| {
+                let text: String = link.cells.iter().map(|(_, cell)| cell.symbol()).collect();
+                (link.url.as_str(), link.cells[0].0, link.y, text)
+            })
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("https://example.com/pull/12", 7, 0, "#12".to_string()),
+                ("https://example.com/pull/13", 15, 0, "#13".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn headless_tooltip() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let mut source_state = SourceState::new();
+        terminal.push_event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        }));
+        terminal
+            .run(|builder| {
+                let mut source = source_state.build();
+                source.add(0, 0).raw(0, "a commit subject that is too long to fit");
+                source.add(0, 1).raw(0, "short").tooltip(0, "a tooltip");
+                Table::new(&source.finish()).build(builder);
+                Ok(true)
+            })
+            .unwrap();
+
+        let lines = screen(&terminal);
+        assert!(lines.iter().any(|line| line.contains("long to fit")), "{lines:?}");
+
+        // Pressing a key hides the tooltip.
+        press(&mut terminal, KeyCode::Down);
+        terminal
+            .run(|builder| {
+                let mut source = source_state.build();
+                source.add(0, 0).raw(0, "a commit subject that is too long to fit");
+                Table::new(&source.finish()).build(builder);
+                Ok(true)
+            })
+            .unwrap();
+        let lines = screen(&terminal);
+        assert!(!lines.iter().any(|line| line.contains("long to fit")), "{lines:?}");
+    }
+
+    #[test]
+    fn headless_recording() {
+        let path = std::env::temp_dir().join(format!("vctuik-test-{}.cast", std::process::id()));
+        let source = StringPagerSource::new(TEXT);
+        let run = |terminal: &mut Terminal<TestBackend>| {
+            let mut state = PagerState::default();
+            let mut selected = 0;
+            terminal
+                .run(|builder| {
+                    let result = Pager::new(&source).build_with_state(builder, "pager", &mut state);
+                    selected = result.selected_line();
+                    Ok(true)
+                })
+                .unwrap();
+            selected
+        };
+
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        terminal.set_recording(&path).unwrap();
+        press(&mut terminal, KeyCode::Down);
+        press(&mut terminal, KeyCode::Down);
+        let recorded = run(&mut terminal);
+        drop(terminal);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 30, \"height\": 8"));
+        assert!(lines.iter().any(|line| line.contains("\"o\"") && line.contains("three")));
+        assert_eq!(lines.iter().filter(|line| line.contains("\"i\", \"Down\"")).count(), 2);
+
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        terminal.replay_recording(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let replayed = run(&mut terminal);
+        assert_eq!((recorded, replayed), (2, 2));
+    }
+
+    #[test]
+    fn headless_screen_reader() {
+        let mut terminal = Terminal::headless(30, 8).unwrap();
+        let source = StringPagerSource::new(TEXT);
+        press(&mut terminal, KeyCode::F(2));
+        press(&mut terminal, KeyCode::Down);
+        terminal
+            .run(|builder| {
+                Pager::new(&source).build(builder, "pager");
+                Ok(true)
+            })
+            .unwrap();
+
+        assert_eq!(screen(&terminal)[7], "two");
     }
 }
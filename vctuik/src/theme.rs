@@ -1,10 +1,17 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+//! Colors and styles of the UI.
+//!
+//! Widgets look up the styles of the `Theme` for the `Context` that they are
+//! built in, e.g. a pane or a modal popup, so that applications can restyle
+//! the whole UI in one place.
+
 use std::sync::LazyLock;
 
 use ratatui::{
     prelude::*,
     style::{Style, Styled},
+    symbols::border,
 };
 
 use crate::state::Builder;
@@ -82,6 +89,65 @@ pub enum Context {
     Modal,
 }
 
+/// Characters used for markers and lines.
+#[derive(Debug, Clone)]
+pub struct Symbols {
+    /// Marker of a collapsed folding range or tree item.
+    pub collapsed: &'static str,
+
+    /// Marker of an expanded folding range or tree item.
+    pub expanded: &'static str,
+
+    /// Marker of a marked item, e.g. for batch actions.
+    pub marked: &'static str,
+
+    /// Marker of a section that can be resized by dragging.
+    pub resize: &'static str,
+
+    /// Line between columns.
+    pub vertical: &'static str,
+
+    pub border: border::Set,
+    pub border_focus: border::Set,
+}
+impl Symbols {
+    pub const UNICODE: Symbols = Symbols {
+        collapsed: "▶",
+        expanded: "▼",
+        marked: "✔",
+        resize: "↕",
+        vertical: "│",
+        border: border::PLAIN,
+        border_focus: border::THICK,
+    };
+
+    /// Only ASCII characters, for limited terminals and fonts.
+    pub const ASCII: Symbols = Symbols {
+        collapsed: ">",
+        expanded: "v",
+        marked: "*",
+        resize: "|",
+        vertical: "|",
+        border: ASCII_BORDER,
+        border_focus: border::Set {
+            horizontal_top: "=",
+            horizontal_bottom: "=",
+            ..ASCII_BORDER
+        },
+    };
+}
+
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
 #[derive(Debug, Clone)]
 pub struct Theme {
     pub text: Text,
@@ -92,8 +158,19 @@ pub struct Theme {
     pub modal_background: Style,
     pub modal_frame: Style,
     pub modal_text: Text,
+    pub symbols: Symbols,
 }
 impl Theme {
+    /// The default theme, or the theme without colors if the `NO_COLOR`
+    /// environment variable is set (see <https://no-color.org>).
+    pub fn from_env() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            NO_COLOR.clone()
+        } else {
+            Self::default()
+        }
+    }
+
     pub fn text(&self, context: Context) -> &Text {
         match context {
             Context::None => &self.text,
@@ -101,6 +178,16 @@ impl Theme {
             Context::Modal => &self.modal_text,
         }
     }
+
+    /// Change the styles of added and removed text in all contexts. `f` is
+    /// called with `TextStyle::Added` or `TextStyle::Removed` and the current
+    /// style.
+    pub fn map_diff_styles(&mut self, mut f: impl FnMut(TextStyle, Style) -> Style) {
+        for text in [&mut self.text, &mut self.pane_text, &mut self.modal_text] {
+            text.added = f(TextStyle::Added, text.added);
+            text.removed = f(TextStyle::Removed, text.removed);
+        }
+    }
 }
 impl Default for Theme {
     fn default() -> Self {
@@ -199,12 +286,44 @@ fn make_solarized(dark: bool) -> Theme {
             removed: Style::default().fg(red),
             error: Style::default().fg(red),
         },
+        symbols: Symbols::UNICODE,
+    }
+}
+
+fn make_no_color() -> Theme {
+    let text = Text {
+        normal: Style::default(),
+        highlight: Style::default().bold(),
+        inactive: Style::default().dim(),
+        selected: Style::default().reversed(),
+        search: Style::default().underlined(),
+        header0: Style::default().bold().underlined(),
+        header1: Style::default().bold(),
+        header2: Style::default().italic(),
+        added: Style::default(),
+        removed: Style::default(),
+        error: Style::default().bold(),
+    };
+
+    Theme {
+        text: text.clone(),
+        pane_background: Style::default(),
+        pane_frame_normal: Style::default(),
+        pane_frame_focus: Style::default().bold(),
+        pane_text: text.clone(),
+        modal_background: Style::default(),
+        modal_frame: Style::default().bold(),
+        modal_text: text,
+        symbols: Symbols::UNICODE,
     }
 }
 
 pub static SOLARIZED_LIGHT: LazyLock<Theme> = LazyLock::new(|| make_solarized(false));
 pub static SOLARIZED_DARK: LazyLock<Theme> = LazyLock::new(|| make_solarized(true));
 
+/// Text attributes such as bold and reversed only, without colors.
+pub static NO_COLOR: LazyLock<Theme> = LazyLock::new(make_no_color);
+
 pub trait Themed {
     type Item;
 
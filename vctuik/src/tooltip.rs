@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tooltips that follow the mouse pointer.
+//!
+//! Widgets offer tooltips for areas of the screen via `Builder::tooltip`, and
+//! the tooltip of the area under the pointer is shown on top of the UI. This
+//! requires a terminal that reports pointer motion, which most do while mouse
+//! capture is enabled.
+
+use ratatui::{
+    layout::{Position, Rect},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::theme::Theme;
+
+/// Maximum width of the text of a tooltip. Longer lines are wrapped.
+const MAX_WIDTH: usize = 60;
+
+/// Wrap the text at word boundaries into lines of at most `width` characters.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for text_line in text.lines() {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in text_line.split(' ') {
+            let word: Vec<char> = word.chars().collect();
+            if line_width != 0 && line_width + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            } else if line_width != 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            // Words that are too long by themselves are split.
+            let mut chunks = word.chunks(width).peekable();
+            while let Some(chunk) = chunks.next() {
+                line.extend(chunk);
+                line_width += chunk.len();
+                if chunks.peek().is_some() {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Render the tooltip next to the pointer and return the area that it covers.
+pub(crate) fn render(frame: &mut Frame, theme: &Theme, pointer: Position, text: &str) -> Rect {
+    let screen = frame.area();
+    let lines = wrap(text, MAX_WIDTH.min(screen.width.saturating_sub(2) as usize));
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 2;
+    let height = lines.len() as u16 + 2;
+    let width = width.min(screen.width);
+    let height = height.min(screen.height);
+
+    // Below and to the right of the pointer if possible, so that it doesn't
+    // hide what the pointer is on.
+    let x = if pointer.x + 1 + width <= screen.right() {
+        pointer.x + 1
+    } else {
+        pointer.x.saturating_sub(width).max(screen.x)
+    };
+    let y = if pointer.y + 1 + height <= screen.bottom() {
+        pointer.y + 1
+    } else {
+        pointer.y.saturating_sub(height).max(screen.y)
+    };
+    let area = Rect { x, y, width, height };
+
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(theme.modal_frame)
+        .style(theme.modal_background.patch(theme.modal_text.normal));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+    area
+}